@@ -34,6 +34,7 @@ fn main() -> anyhow::Result<()> {
             test_directory_module(out, "tests/misc_testsuite/tail-call", strategy)?;
             test_directory_module(out, "tests/misc_testsuite/threads", strategy)?;
             test_directory_module(out, "tests/misc_testsuite/memory64", strategy)?;
+            test_directory_module(out, "tests/misc_testsuite/exception-handling", strategy)?;
             test_directory_module(out, "tests/misc_testsuite/component-model", strategy)?;
             test_directory_module(out, "tests/misc_testsuite/function-references", strategy)?;
             test_directory_module(out, "tests/misc_testsuite/typed-continuations", strategy)?;
@@ -239,9 +240,6 @@ fn ignore(testsuite: &str, testname: &str, strategy: &str) -> bool {
         "s390x" => {
             // FIXME: These tests fail under qemu due to a qemu bug.
             testname == "simd_f32x4_pmin_pmax" || testname == "simd_f64x2_pmin_pmax"
-                // TODO(#6530): These tests require tail calls, but s390x
-                // doesn't support them yet.
-                || testsuite == "function_references" || testsuite == "tail_call"
         }
 
         "riscv64" => {
@@ -249,19 +247,8 @@ fn ignore(testsuite: &str, testname: &str, strategy: &str) -> bool {
                 return true;
             }
 
-            let known_failure = [
-                "canonicalize_nan",
-                "cvt_from_uint",
-                "issue_3327_bnot_lowering",
-                "simd_conversions",
-                "simd_f32x4_rounding",
-                "simd_f64x2_rounding",
-                "simd_i32x4_trunc_sat_f32x4",
-                "simd_i32x4_trunc_sat_f64x2",
-                "simd_load",
-                "simd_splat",
-            ]
-            .contains(&testname);
+            let known_failure =
+                ["issue_3327_bnot_lowering", "simd_load", "simd_splat"].contains(&testname);
 
             known_failure
         }
@@ -341,6 +341,7 @@ fn run_compilation(compilation: &IsleCompilation) -> Result<(), Errors> {
         // include!()s it. (See
         // https://github.com/rust-lang/rust/issues/47995.)
         options.exclude_global_allow_pragmas = true;
+        options.emit_rule_trace = cfg!(feature = "isle-coverage");
 
         isle::compile::from_files(file_paths, &options)?
     };
@@ -1300,6 +1300,46 @@ pub(crate) fn define(
         .operands_out(vec![Operand::new("addr", iAddr)]),
     );
 
+    ig.push(
+        Inst::new(
+            "stack_switch",
+            r#"
+        Suspends execution on the current stack and resumes execution on
+        another one.
+
+        ``store_context_ptr`` and ``load_context_ptr`` point to contexts of
+        three pointer-sized words each: the stack pointer, the frame pointer,
+        and the program counter at which to resume. The state of the current
+        stack is saved to the context at ``store_context_ptr``, and execution
+        then continues using the stack pointer, frame pointer and program
+        counter loaded from the context at ``load_context_ptr``.
+
+        Control comes back to the instruction following ``stack_switch`` when
+        another ``stack_switch`` loads the context that this one stored. That
+        ``stack_switch``'s ``in_payload0`` is then returned as
+        ``out_payload0``. Likewise, ``in_payload0`` is passed to whichever
+        stack is resumed, either as the result of its ``stack_switch`` or, for
+        a stack which is started for the first time, in a fixed register:
+        ``rdi`` on x86-64 and ``x0`` on aarch64.
+
+        All registers, including callee-saved ones, are treated as clobbered,
+        so values which are live across the switch are saved and restored by
+        the function containing this instruction. The stacks must belong to the
+        same thread.
+        "#,
+            &formats.ternary,
+        )
+        .operands_in(vec![
+            Operand::new("store_context_ptr", iAddr),
+            Operand::new("load_context_ptr", iAddr),
+            Operand::new("in_payload0", iAddr),
+        ])
+        .operands_out(vec![Operand::new("out_payload0", iAddr)])
+        .other_side_effects()
+        .can_load()
+        .can_store(),
+    );
+
     let TableOffset = &TypeVar::new(
         "TableOffset",
         "An unsigned table offset",
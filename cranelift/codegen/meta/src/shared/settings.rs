@@ -53,6 +53,68 @@ pub(crate) fn define() -> SettingGroup {
         true,
     );
 
+    settings.add_bool(
+        "enable_inlining",
+        "Inline calls to small functions.",
+        r#"
+            This allows `Context::inline` to replace direct calls with copies of
+            the callee's body, as provided by an embedder-supplied `Inline`
+            implementation. Callees are only inlined while they fit within the
+            budget described by `inlining_callee_size_limit` and
+            `inlining_caller_size_log2`.
+        "#,
+        false,
+    );
+
+    settings.add_num(
+        "inlining_callee_size_limit",
+        "The maximum number of instructions in a function that may be inlined.",
+        r#"
+            Callees with more instructions than this are never inlined.
+
+            The default is 32.
+        "#,
+        32,
+    );
+
+    settings.add_num(
+        "inlining_caller_size_log2",
+        "The log2 of the number of instructions after which a caller stops inlining.",
+        r#"
+            Once a function has grown to this many instructions, no more calls
+            within it are inlined. This bounds code growth, including the
+            growth caused by recursively inlining calls within inlined bodies.
+
+            The default is 12, which translates to 4096 instructions.
+        "#,
+        12,
+    );
+
+    settings.add_bool(
+        "enable_loop_opts",
+        "Enable loop optimizations in the mid-end.",
+        r#"
+            This runs a pass before the egraph optimizations which gives every
+            loop a preheader, hoists conditional traps with a loop-invariant
+            condition (such as explicit bounds checks) out of loops, and
+            strength-reduces multiplications of induction variables. It also
+            unrolls small loops when `loop_unroll_factor` is greater than one.
+        "#,
+        false,
+    );
+
+    settings.add_num(
+        "loop_unroll_factor",
+        "The number of copies of the body of a small loop to make when unrolling it.",
+        r#"
+            Only loops made of a single block are unrolled, and only when
+            `enable_loop_opts` is set. A factor of 0 or 1 disables unrolling.
+
+            The default is 1.
+        "#,
+        1,
+    );
+
     settings.add_bool(
         "enable_verifier",
         "Run the Cranelift IR verifier at strategic times during compilation.",
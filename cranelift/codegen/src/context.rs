@@ -14,10 +14,12 @@ use crate::dce::do_dce;
 use crate::dominator_tree::DominatorTree;
 use crate::egraph::EgraphPass;
 use crate::flowgraph::ControlFlowGraph;
+use crate::inline::{do_inlining, Inline};
 use crate::ir::Function;
 use crate::isa::TargetIsa;
 use crate::legalizer::simple_legalize;
 use crate::loop_analysis::LoopAnalysis;
+use crate::loop_opts::do_loop_opts;
 use crate::machinst::{CompiledCode, CompiledCodeStencil};
 use crate::nan_canonicalization::do_nan_canonicalization;
 use crate::remove_constant_phis::do_remove_constant_phis;
@@ -184,6 +186,10 @@ impl Context {
 
         self.remove_constant_phis(isa)?;
 
+        if opt_level != OptLevel::None && isa.flags().enable_loop_opts() {
+            self.loop_opts(isa)?;
+        }
+
         if opt_level != OptLevel::None {
             self.egraph_pass(isa)?;
         }
@@ -274,6 +280,41 @@ impl Context {
         Ok(())
     }
 
+    /// Perform loop optimizations on the function: preheader insertion, hoisting of loop-invariant
+    /// conditional traps, induction-variable strength reduction and, if configured, unrolling.
+    pub fn loop_opts<'a, FOI: Into<FlagsOrIsa<'a>>>(&mut self, fisa: FOI) -> CodegenResult<()> {
+        let fisa = fisa.into();
+        do_loop_opts(
+            &mut self.func,
+            &mut self.cfg,
+            &mut self.domtree,
+            &mut self.loop_analysis,
+            fisa.flags,
+        );
+        self.verify_if(fisa)
+    }
+
+    /// Inline calls within the function, using `inliner` to provide the bodies of callees.
+    ///
+    /// This does nothing unless the `enable_inlining` setting is true. Returns whether any call
+    /// was inlined.
+    pub fn inline<'a, FOI: Into<FlagsOrIsa<'a>>>(
+        &mut self,
+        fisa: FOI,
+        inliner: impl Inline,
+    ) -> CodegenResult<bool> {
+        let fisa = fisa.into();
+        let inlined = do_inlining(&mut self.func, fisa.flags, inliner)?;
+        if inlined {
+            // Inlining changes the CFG, so any previously computed analyses are stale.
+            self.cfg.clear();
+            self.domtree.clear();
+            self.loop_analysis.clear();
+            self.verify_if(fisa)?;
+        }
+        Ok(inlined)
+    }
+
     /// Perform NaN canonicalizing rewrites on the function.
     pub fn canonicalize_nans(&mut self, isa: &dyn TargetIsa) -> CodegenResult<()> {
         do_nan_canonicalization(&mut self.func);
@@ -0,0 +1,549 @@
+//! Function inlining.
+//!
+//! Cranelift compiles one function at a time and has no knowledge of the bodies of the functions
+//! that a function calls. Inlining is therefore driven by the embedder: for every direct `call`
+//! instruction, an [`Inline`] implementation is asked whether it can provide the callee's body. If
+//! it does, and the callee fits within the size budget configured by the `enable_inlining`,
+//! `inlining_callee_size_limit` and `inlining_caller_size_log2` settings, the call is replaced by a
+//! copy of the callee's body.
+//!
+//! The call's block is split in two at the call instruction. The call itself becomes a `jump` to
+//! the copy of the callee's entry block, and every `return` in the copied body becomes a `jump` to
+//! the second half of the split block, whose parameters take the place of the call's results.
+//!
+//! Calls within inlined bodies are themselves considered for inlining, so the caller's size limit
+//! is what ultimately bounds recursive inlining.
+
+use crate::dominator_tree::DominatorTree;
+use crate::flowgraph::ControlFlowGraph;
+use crate::ir::{
+    self, ArgumentPurpose, Block, BlockCall, ExternalName, Function, GlobalValueData, Inst,
+    InstBuilder, InstructionData, InstructionMapper, Opcode, Value, ValueList,
+};
+use crate::result::{CodegenError, CodegenResult};
+use crate::settings::Flags;
+use crate::timing;
+use crate::trace;
+use alloc::borrow::Cow;
+use alloc::format;
+use alloc::vec::Vec;
+use cranelift_entity::packed_option::PackedOption;
+use cranelift_entity::SecondaryMap;
+use smallvec::SmallVec;
+
+/// A callback that provides the bodies of functions to inline.
+pub trait Inline {
+    /// Decide whether to inline the direct call `call_inst` to `callee` within `caller`.
+    ///
+    /// `call_args` are the arguments of the call. Returning [`InlineCommand::Inline`] with the
+    /// callee's body allows the call to be inlined, subject to the size budget in the caller's
+    /// settings; returning [`InlineCommand::KeepCall`] leaves the call as is.
+    fn inline(
+        &mut self,
+        caller: &Function,
+        call_inst: Inst,
+        callee: ir::FuncRef,
+        call_args: &[Value],
+    ) -> InlineCommand<'_>;
+}
+
+impl<'a, T> Inline for &'a mut T
+where
+    T: Inline,
+{
+    fn inline(
+        &mut self,
+        caller: &Function,
+        call_inst: Inst,
+        callee: ir::FuncRef,
+        call_args: &[Value],
+    ) -> InlineCommand<'_> {
+        (**self).inline(caller, call_inst, callee, call_args)
+    }
+}
+
+/// The result of an [`Inline::inline`] query.
+pub enum InlineCommand<'a> {
+    /// Do not inline this call.
+    KeepCall,
+
+    /// Inline this call, using the given body for the callee.
+    Inline(Cow<'a, Function>),
+}
+
+/// Inline calls within `func` according to `inliner` and the budget in `flags`.
+///
+/// Returns whether any call was inlined.
+pub fn do_inlining(
+    func: &mut Function,
+    flags: &Flags,
+    mut inliner: impl Inline,
+) -> CodegenResult<bool> {
+    let _tt = timing::inline();
+
+    if !flags.enable_inlining() {
+        return Ok(false);
+    }
+
+    let callee_size_limit = usize::from(flags.inlining_callee_size_limit());
+    let caller_size_limit = 1usize
+        .checked_shl(u32::from(flags.inlining_caller_size_log2()))
+        .unwrap_or(usize::MAX);
+    let mut caller_size = num_insts(func);
+    let mut inlined_any = false;
+
+    let mut next_block = func.layout.entry_block();
+    'blocks: while let Some(block) = next_block {
+        let mut next_inst = func.layout.first_inst(block);
+        while let Some(inst) = next_inst {
+            next_inst = func.layout.next_inst(inst);
+
+            if caller_size >= caller_size_limit {
+                break 'blocks;
+            }
+
+            let (callee_ref, args) = match func.dfg.insts[inst] {
+                InstructionData::Call {
+                    opcode: Opcode::Call,
+                    func_ref,
+                    args,
+                } => (func_ref, args),
+                _ => continue,
+            };
+            let call_args: SmallVec<[Value; 8]> =
+                SmallVec::from_slice(args.as_slice(&func.dfg.value_lists));
+
+            let callee = match inliner.inline(func, inst, callee_ref, &call_args) {
+                InlineCommand::KeepCall => continue,
+                InlineCommand::Inline(callee) => callee,
+            };
+
+            let callee_size = num_insts(&callee);
+            if callee_size > callee_size_limit {
+                trace!(
+                    "not inlining {} into {}: callee has {} instructions",
+                    callee.name,
+                    func.name,
+                    callee_size
+                );
+                continue;
+            }
+            if !can_inline(func, &callee, callee_ref, &call_args)? {
+                continue;
+            }
+
+            trace!("inlining {} into {} at {}", callee.name, func.name, inst);
+            inline_one(func, inst, &callee, &call_args);
+            caller_size += callee_size;
+            inlined_any = true;
+
+            // The rest of this block was moved to a new block after the inlined body; continue
+            // with the inlined body itself so that its calls are considered for inlining too.
+            break;
+        }
+        next_block = func.layout.next_block(block);
+    }
+
+    Ok(inlined_any)
+}
+
+/// Count the instructions in `func`'s layout.
+fn num_insts(func: &Function) -> usize {
+    func.layout
+        .blocks()
+        .map(|block| func.layout.block_insts(block).count())
+        .sum()
+}
+
+/// Check whether `callee` can be inlined at a call to `callee_ref` with the given arguments.
+///
+/// Returns an error if the callee's signature doesn't match the call, since that indicates a bug
+/// in the embedder's `Inline` implementation.
+fn can_inline(
+    caller: &Function,
+    callee: &Function,
+    callee_ref: ir::FuncRef,
+    call_args: &[Value],
+) -> CodegenResult<bool> {
+    let sig = &caller.dfg.signatures[caller.dfg.ext_funcs[callee_ref].signature];
+    let params_match = sig.params.len() == callee.signature.params.len()
+        && sig
+            .params
+            .iter()
+            .zip(&callee.signature.params)
+            .all(|(a, b)| a.value_type == b.value_type);
+    let returns_match = sig.returns.len() == callee.signature.returns.len()
+        && sig
+            .returns
+            .iter()
+            .zip(&callee.signature.returns)
+            .all(|(a, b)| a.value_type == b.value_type);
+    if !params_match || !returns_match {
+        return Err(CodegenError::Unsupported(format!(
+            "cannot inline {} into {}: callee signature {} does not match call signature {}",
+            callee.name, caller.name, callee.signature, sig
+        )));
+    }
+
+    if callee.layout.entry_block().is_none() {
+        return Ok(false);
+    }
+
+    // Struct arguments and return areas are memory the ABI allocates for the callee; passing the
+    // caller's pointer straight through would drop the copy the callee is entitled to.
+    let passes_memory = callee
+        .signature
+        .params
+        .iter()
+        .chain(&callee.signature.returns)
+        .any(|param| {
+            matches!(
+                param.purpose,
+                ArgumentPurpose::StructReturn | ArgumentPurpose::StructArgument(_)
+            )
+        });
+    if passes_memory {
+        return Ok(false);
+    }
+
+    // Dynamic types are defined in terms of the target's vector size and are rare enough that
+    // they aren't worth supporting here.
+    if !callee.dynamic_stack_slots.is_empty() || !callee.dfg.dynamic_types.is_empty() {
+        return Ok(false);
+    }
+
+    // A tail call in the callee would have to become a regular call followed by a jump to the
+    // continuation, which changes its stack usage; leave such callees alone.
+    for block in callee.layout.blocks() {
+        for inst in callee.layout.block_insts(block) {
+            match callee.dfg.insts[inst].opcode() {
+                Opcode::ReturnCall | Opcode::ReturnCallIndirect => return Ok(false),
+                _ => {}
+            }
+        }
+    }
+
+    // The callee's `vmctx` global value refers to its own `vmctx` parameter. That can only be
+    // expressed in the caller if the argument passed for it is the caller's own `vmctx`.
+    let callee_uses_vmctx = callee
+        .global_values
+        .values()
+        .any(|gv| matches!(gv, GlobalValueData::VMContext));
+    if callee_uses_vmctx {
+        let callee_vmctx = callee
+            .signature
+            .special_param_index(ArgumentPurpose::VMContext);
+        let caller_vmctx = caller.special_param(ArgumentPurpose::VMContext);
+        match (callee_vmctx, caller_vmctx) {
+            (Some(index), Some(caller_vmctx))
+                if caller.dfg.resolve_aliases(call_args[index]) == caller_vmctx => {}
+            _ => return Ok(false),
+        }
+    }
+
+    Ok(true)
+}
+
+/// Replace the call `call_inst` in `caller` with a copy of `callee`'s body.
+fn inline_one(caller: &mut Function, call_inst: Inst, callee: &Function, call_args: &[Value]) {
+    let call_srcloc = caller.srcloc(call_inst);
+
+    // Split the call's block after the call; the new block receives the callee's return values in
+    // place of the call's results.
+    let return_block = caller.dfg.make_block();
+    let after_call = caller
+        .layout
+        .next_inst(call_inst)
+        .expect("calls are not terminators");
+    caller.layout.split_block(return_block, after_call);
+
+    let results = caller.dfg.detach_results(call_inst);
+    let results: SmallVec<[Value; 4]> =
+        SmallVec::from_slice(results.as_slice(&caller.dfg.value_lists));
+    for result in results {
+        let ty = caller.dfg.value_type(result);
+        let param = caller.dfg.append_block_param(return_block, ty);
+        caller.dfg.change_to_alias(result, param);
+    }
+
+    // Only reachable blocks are copied. Visiting them in reverse postorder ensures that every value
+    // is copied before it is used, except in block parameters which are created up front.
+    let cfg = ControlFlowGraph::with_function(callee);
+    let domtree = DominatorTree::with_function(callee, &cfg);
+
+    let mut mapper = Mapper::new(caller, callee);
+    for block in callee.layout.blocks() {
+        if !domtree.is_reachable(block) {
+            continue;
+        }
+        let new_block = mapper.caller.dfg.make_block();
+        mapper.caller.layout.insert_block(new_block, return_block);
+        mapper.blocks[block] = new_block.into();
+        for &param in callee.dfg.block_params(block) {
+            let ty = callee.dfg.value_type(param);
+            let new_param = mapper.caller.dfg.append_block_param(new_block, ty);
+            mapper.values[param] = new_param.into();
+        }
+    }
+
+    for &block in domtree.cfg_postorder().iter().rev() {
+        let new_block = mapper.blocks[block].unwrap();
+        for inst in callee.layout.block_insts(block) {
+            let data = callee.dfg.insts[inst].map(&mut mapper);
+            let data = match data {
+                InstructionData::MultiAry {
+                    opcode: Opcode::Return,
+                    args,
+                } => {
+                    let pool = &mut mapper.caller.dfg.value_lists;
+                    let args: SmallVec<[Value; 4]> = SmallVec::from_slice(args.as_slice(pool));
+                    InstructionData::Jump {
+                        opcode: Opcode::Jump,
+                        destination: BlockCall::new(return_block, &args, pool),
+                    }
+                }
+                data => data,
+            };
+
+            let new_inst = mapper.caller.dfg.make_inst(data);
+            let ctrl_typevar = callee.dfg.ctrl_typevar(inst);
+            mapper.caller.dfg.make_inst_results(new_inst, ctrl_typevar);
+            for (&result, &new_result) in callee
+                .dfg
+                .inst_results(inst)
+                .iter()
+                .zip(mapper.caller.dfg.inst_results(new_inst))
+            {
+                mapper.values[result] = new_result.into();
+            }
+
+            mapper.caller.layout.append_inst(new_inst, new_block);
+            // Instructions without a location of their own are attributed to the call.
+            let srcloc = match callee.srcloc(inst) {
+                loc if loc.is_default() => call_srcloc,
+                loc => loc,
+            };
+            if !srcloc.is_default() {
+                mapper.caller.set_srcloc(new_inst, srcloc);
+            }
+        }
+    }
+
+    // Finally, turn the call into a jump to the inlined entry block.
+    let entry = mapper.blocks[callee.layout.entry_block().unwrap()].unwrap();
+    caller.dfg.replace(call_inst).jump(entry, call_args);
+}
+
+/// Maps entities in the callee to their copies in the caller, copying them on first use.
+struct Mapper<'a> {
+    caller: &'a mut Function,
+    callee: &'a Function,
+    blocks: SecondaryMap<Block, PackedOption<Block>>,
+    values: SecondaryMap<Value, PackedOption<Value>>,
+    global_values: SecondaryMap<ir::GlobalValue, PackedOption<ir::GlobalValue>>,
+    func_refs: SecondaryMap<ir::FuncRef, PackedOption<ir::FuncRef>>,
+    sig_refs: SecondaryMap<ir::SigRef, PackedOption<ir::SigRef>>,
+    stack_slots: SecondaryMap<ir::StackSlot, PackedOption<ir::StackSlot>>,
+    tables: SecondaryMap<ir::Table, PackedOption<ir::Table>>,
+}
+
+impl<'a> Mapper<'a> {
+    fn new(caller: &'a mut Function, callee: &'a Function) -> Self {
+        Self {
+            caller,
+            callee,
+            blocks: SecondaryMap::new(),
+            values: SecondaryMap::new(),
+            global_values: SecondaryMap::new(),
+            func_refs: SecondaryMap::new(),
+            sig_refs: SecondaryMap::new(),
+            stack_slots: SecondaryMap::new(),
+            tables: SecondaryMap::new(),
+        }
+    }
+
+    fn map_name(&mut self, name: &ExternalName) -> ExternalName {
+        match name {
+            ExternalName::User(name_ref) => {
+                let name = self.callee.params.user_named_funcs()[*name_ref].clone();
+                ExternalName::User(self.caller.declare_imported_user_function(name))
+            }
+            name => name.clone(),
+        }
+    }
+
+    fn caller_vmctx(&mut self) -> ir::GlobalValue {
+        match self
+            .caller
+            .global_values
+            .iter()
+            .find(|(_, data)| matches!(data, GlobalValueData::VMContext))
+        {
+            Some((gv, _)) => gv,
+            None => self.caller.create_global_value(GlobalValueData::VMContext),
+        }
+    }
+}
+
+impl InstructionMapper for Mapper<'_> {
+    fn map_value(&mut self, value: Value) -> Value {
+        let value = self.callee.dfg.resolve_aliases(value);
+        self.values[value]
+            .expand()
+            .expect("values are copied before their uses")
+    }
+
+    fn map_value_list(&mut self, value_list: ValueList) -> ValueList {
+        let callee = self.callee;
+        let values: SmallVec<[Value; 8]> = value_list
+            .as_slice(&callee.dfg.value_lists)
+            .iter()
+            .map(|&value| self.map_value(value))
+            .collect();
+        ValueList::from_slice(&values, &mut self.caller.dfg.value_lists)
+    }
+
+    fn map_global_value(&mut self, global_value: ir::GlobalValue) -> ir::GlobalValue {
+        if let Some(gv) = self.global_values[global_value].expand() {
+            return gv;
+        }
+        let callee = self.callee;
+        let data = match &callee.global_values[global_value] {
+            GlobalValueData::VMContext => {
+                let gv = self.caller_vmctx();
+                self.global_values[global_value] = gv.into();
+                return gv;
+            }
+            GlobalValueData::Load {
+                base,
+                offset,
+                global_type,
+                readonly,
+            } => GlobalValueData::Load {
+                base: self.map_global_value(*base),
+                offset: *offset,
+                global_type: *global_type,
+                readonly: *readonly,
+            },
+            GlobalValueData::IAddImm {
+                base,
+                offset,
+                global_type,
+            } => GlobalValueData::IAddImm {
+                base: self.map_global_value(*base),
+                offset: *offset,
+                global_type: *global_type,
+            },
+            GlobalValueData::Symbol {
+                name,
+                offset,
+                colocated,
+                tls,
+            } => GlobalValueData::Symbol {
+                name: self.map_name(name),
+                offset: *offset,
+                colocated: *colocated,
+                tls: *tls,
+            },
+            data @ GlobalValueData::DynScaleTargetConst { .. } => data.clone(),
+        };
+        let gv = self.caller.create_global_value(data);
+        self.global_values[global_value] = gv.into();
+        gv
+    }
+
+    fn map_jump_table(&mut self, jump_table: ir::JumpTable) -> ir::JumpTable {
+        let callee = self.callee;
+        let data = &callee.dfg.jump_tables[jump_table];
+        let default = self.map_block_call(data.default_block());
+        let table: Vec<BlockCall> = data
+            .as_slice()
+            .iter()
+            .map(|&block_call| self.map_block_call(block_call))
+            .collect();
+        self.caller
+            .create_jump_table(ir::JumpTableData::new(default, &table))
+    }
+
+    fn map_block_call(&mut self, block_call: BlockCall) -> BlockCall {
+        let callee = self.callee;
+        let pool = &callee.dfg.value_lists;
+        let block = self.blocks[block_call.block(pool)]
+            .expand()
+            .expect("reachable blocks only branch to reachable blocks");
+        let args: SmallVec<[Value; 8]> = block_call
+            .args_slice(pool)
+            .iter()
+            .map(|&arg| self.map_value(arg))
+            .collect();
+        BlockCall::new(block, &args, &mut self.caller.dfg.value_lists)
+    }
+
+    fn map_func_ref(&mut self, func_ref: ir::FuncRef) -> ir::FuncRef {
+        if let Some(func_ref) = self.func_refs[func_ref].expand() {
+            return func_ref;
+        }
+        let callee = self.callee;
+        let data = &callee.dfg.ext_funcs[func_ref];
+        let new_data = ir::ExtFuncData {
+            name: self.map_name(&data.name),
+            signature: self.map_sig_ref(data.signature),
+            colocated: data.colocated,
+        };
+        let new_func_ref = self.caller.import_function(new_data);
+        self.func_refs[func_ref] = new_func_ref.into();
+        new_func_ref
+    }
+
+    fn map_sig_ref(&mut self, sig_ref: ir::SigRef) -> ir::SigRef {
+        if let Some(sig_ref) = self.sig_refs[sig_ref].expand() {
+            return sig_ref;
+        }
+        let sig = self.callee.dfg.signatures[sig_ref].clone();
+        let new_sig_ref = self.caller.import_signature(sig);
+        self.sig_refs[sig_ref] = new_sig_ref.into();
+        new_sig_ref
+    }
+
+    fn map_stack_slot(&mut self, stack_slot: ir::StackSlot) -> ir::StackSlot {
+        if let Some(stack_slot) = self.stack_slots[stack_slot].expand() {
+            return stack_slot;
+        }
+        let data = self.callee.sized_stack_slots[stack_slot].clone();
+        let new_stack_slot = self.caller.create_sized_stack_slot(data);
+        self.stack_slots[stack_slot] = new_stack_slot.into();
+        new_stack_slot
+    }
+
+    fn map_dynamic_stack_slot(
+        &mut self,
+        _dynamic_stack_slot: ir::DynamicStackSlot,
+    ) -> ir::DynamicStackSlot {
+        unreachable!("callees with dynamic stack slots are not inlined")
+    }
+
+    fn map_table(&mut self, table: ir::Table) -> ir::Table {
+        if let Some(table) = self.tables[table].expand() {
+            return table;
+        }
+        let data = self.callee.tables[table].clone();
+        let new_data = ir::TableData {
+            base_gv: self.map_global_value(data.base_gv),
+            bound_gv: self.map_global_value(data.bound_gv),
+            ..data
+        };
+        let new_table = self.caller.create_table(new_data);
+        self.tables[table] = new_table.into();
+        new_table
+    }
+
+    fn map_constant(&mut self, constant: ir::Constant) -> ir::Constant {
+        let data = self.callee.dfg.constants.get(constant).clone();
+        self.caller.dfg.constants.insert(data)
+    }
+
+    fn map_immediate(&mut self, immediate: ir::Immediate) -> ir::Immediate {
+        let data = self.callee.dfg.immediates[immediate].clone();
+        self.caller.dfg.immediates.push(data)
+    }
+}
@@ -5,6 +5,7 @@ use crate::ir::types::*;
 use crate::ir::GlobalValue;
 use crate::ir::PrimaryMap;
 use crate::ir::Type;
+use core::fmt;
 
 #[cfg(feature = "enable-serde")]
 use serde::{Deserialize, Serialize};
@@ -35,6 +36,12 @@ impl DynamicTypeData {
     }
 }
 
+impl fmt::Display for DynamicTypeData {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}*{}", self.base_vector_ty, self.dynamic_scale)
+    }
+}
+
 /// All allocated dynamic types.
 pub type DynamicTypes = PrimaryMap<DynamicType, DynamicTypeData>;
 
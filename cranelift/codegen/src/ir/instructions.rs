@@ -484,6 +484,107 @@ pub enum CallInfo<'a> {
     Indirect(SigRef, &'a [Value]),
 }
 
+/// A mapping from the entities referenced by an instruction to new entities.
+///
+/// This is used by `InstructionData::map` to rewrite an instruction, for example when copying it
+/// from one function into another as part of inlining.
+pub trait InstructionMapper {
+    /// Map a value operand.
+    fn map_value(&mut self, value: Value) -> Value;
+
+    /// Map a list of value operands.
+    fn map_value_list(&mut self, value_list: ValueList) -> ValueList;
+
+    /// Map a global value reference.
+    fn map_global_value(&mut self, global_value: ir::GlobalValue) -> ir::GlobalValue;
+
+    /// Map a jump table reference.
+    fn map_jump_table(&mut self, jump_table: ir::JumpTable) -> ir::JumpTable;
+
+    /// Map a block call, including its arguments.
+    fn map_block_call(&mut self, block_call: BlockCall) -> BlockCall;
+
+    /// Map a function reference.
+    fn map_func_ref(&mut self, func_ref: FuncRef) -> FuncRef;
+
+    /// Map a signature reference.
+    fn map_sig_ref(&mut self, sig_ref: SigRef) -> SigRef;
+
+    /// Map a stack slot reference.
+    fn map_stack_slot(&mut self, stack_slot: StackSlot) -> StackSlot;
+
+    /// Map a dynamic stack slot reference.
+    fn map_dynamic_stack_slot(
+        &mut self,
+        dynamic_stack_slot: ir::DynamicStackSlot,
+    ) -> ir::DynamicStackSlot;
+
+    /// Map a table reference.
+    fn map_table(&mut self, table: ir::Table) -> ir::Table;
+
+    /// Map a constant pool reference.
+    fn map_constant(&mut self, constant: ir::Constant) -> ir::Constant;
+
+    /// Map an immediate reference.
+    fn map_immediate(&mut self, immediate: ir::Immediate) -> ir::Immediate;
+}
+
+impl<'a, T> InstructionMapper for &'a mut T
+where
+    T: InstructionMapper,
+{
+    fn map_value(&mut self, value: Value) -> Value {
+        (**self).map_value(value)
+    }
+
+    fn map_value_list(&mut self, value_list: ValueList) -> ValueList {
+        (**self).map_value_list(value_list)
+    }
+
+    fn map_global_value(&mut self, global_value: ir::GlobalValue) -> ir::GlobalValue {
+        (**self).map_global_value(global_value)
+    }
+
+    fn map_jump_table(&mut self, jump_table: ir::JumpTable) -> ir::JumpTable {
+        (**self).map_jump_table(jump_table)
+    }
+
+    fn map_block_call(&mut self, block_call: BlockCall) -> BlockCall {
+        (**self).map_block_call(block_call)
+    }
+
+    fn map_func_ref(&mut self, func_ref: FuncRef) -> FuncRef {
+        (**self).map_func_ref(func_ref)
+    }
+
+    fn map_sig_ref(&mut self, sig_ref: SigRef) -> SigRef {
+        (**self).map_sig_ref(sig_ref)
+    }
+
+    fn map_stack_slot(&mut self, stack_slot: StackSlot) -> StackSlot {
+        (**self).map_stack_slot(stack_slot)
+    }
+
+    fn map_dynamic_stack_slot(
+        &mut self,
+        dynamic_stack_slot: ir::DynamicStackSlot,
+    ) -> ir::DynamicStackSlot {
+        (**self).map_dynamic_stack_slot(dynamic_stack_slot)
+    }
+
+    fn map_table(&mut self, table: ir::Table) -> ir::Table {
+        (**self).map_table(table)
+    }
+
+    fn map_constant(&mut self, constant: ir::Constant) -> ir::Constant {
+        (**self).map_constant(constant)
+    }
+
+    fn map_immediate(&mut self, immediate: ir::Immediate) -> ir::Immediate {
+        (**self).map_immediate(immediate)
+    }
+}
+
 /// Value type constraints for a given opcode.
 ///
 /// The `InstructionFormat` determines the constraints on most operands, but `Value` operands and
@@ -435,7 +435,7 @@ impl Display for Type {
         } else if self.is_vector() {
             write!(f, "{}x{}", self.lane_type(), self.lane_count())
         } else if self.is_dynamic_vector() {
-            write!(f, "{:?}x{}xN", self.lane_type(), self.min_lane_count())
+            write!(f, "{}x{}xN", self.lane_type(), self.min_lane_count())
         } else if self.is_ref() {
             write!(f, "r{}", self.lane_bits())
         } else {
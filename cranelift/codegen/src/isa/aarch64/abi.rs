@@ -1506,6 +1506,10 @@ const fn default_aapcs_clobbers() -> PRegSet {
 
 const DEFAULT_AAPCS_CLOBBERS: PRegSet = default_aapcs_clobbers();
 
+/// Every allocatable register, all of which are clobbered by
+/// `stack_switch`.
+pub(crate) const ALL_CLOBBERS: PRegSet = TAIL_CLOBBERS;
+
 // NB: The `tail` calling convention clobbers all allocatable registers.
 const TAIL_CLOBBERS: PRegSet = PRegSet::empty()
     .with(xreg_preg(0))
@@ -3179,16 +3179,17 @@ impl MachInstEmit for Inst {
                 }
                 sink.add_reloc(Reloc::Arm64Call, &info.dest, 0);
                 sink.put4(enc_jump26(0b100101, 0));
-                if info.opcode.is_call() {
-                    sink.add_call_site(info.opcode);
-                }
-
                 let callee_pop_size = i64::from(info.callee_pop_size);
                 state.virtual_sp_offset -= callee_pop_size;
                 trace!(
                     "call adjusts virtual sp offset by {callee_pop_size} -> {}",
                     state.virtual_sp_offset
                 );
+
+                if info.opcode.is_call() {
+                    let sp_to_fp = state.nominal_sp_to_fp + state.virtual_sp_offset;
+                    sink.add_call_site(info.opcode, u32::try_from(sp_to_fp).unwrap());
+                }
             }
             &Inst::CallInd { ref info } => {
                 if let Some(s) = state.take_stack_map() {
@@ -3196,16 +3197,133 @@ impl MachInstEmit for Inst {
                 }
                 let rn = allocs.next(info.rn);
                 sink.put4(0b1101011_0001_11111_000000_00000_00000 | (machreg_to_gpr(rn) << 5));
-                if info.opcode.is_call() {
-                    sink.add_call_site(info.opcode);
-                }
-
                 let callee_pop_size = i64::from(info.callee_pop_size);
                 state.virtual_sp_offset -= callee_pop_size;
                 trace!(
                     "call adjusts virtual sp offset by {callee_pop_size} -> {}",
                     state.virtual_sp_offset
                 );
+
+                if info.opcode.is_call() {
+                    let sp_to_fp = state.nominal_sp_to_fp + state.virtual_sp_offset;
+                    sink.add_call_site(info.opcode, u32::try_from(sp_to_fp).unwrap());
+                }
+            }
+            &Inst::StackSwitch {
+                store_context_ptr,
+                load_context_ptr,
+                in_payload0,
+                out_payload0,
+            } => {
+                let store_context_ptr = allocs.next(store_context_ptr);
+                let load_context_ptr = allocs.next(load_context_ptr);
+                let in_payload0 = allocs.next(in_payload0);
+                let out_payload0 = allocs.next_writable(out_payload0);
+                debug_assert_eq!(in_payload0, xreg(0));
+                debug_assert_eq!(out_payload0.to_reg(), xreg(0));
+
+                // Nothing needs to be done here to preserve registers or to
+                // pass the payload: the register allocator saves everything
+                // that is live across this instruction since it clobbers all
+                // allocatable registers, and the payload is passed in `x0` on
+                // both sides. LR isn't allocatable though, and this function
+                // may not have saved it in a frame record, so it is saved on
+                // the stack across the switch. We generate:
+                //
+                //   str lr, [sp, #-16]!
+                //   mov x16, sp
+                //   stp x16, fp, [store_context_ptr]
+                //   adr x17, resume
+                //   str x17, [store_context_ptr, #16]
+                //   ldp x16, fp, [load_context_ptr]
+                //   mov sp, x16
+                //   ldr x16, [load_context_ptr, #16]
+                //   br x16
+                // resume:
+                //   bti j
+                //   ldr lr, [sp], #16
+                let flags = MemFlags::trusted();
+                let pc_slot = |rn| AMode::UnsignedOffset {
+                    rn,
+                    uimm12: UImm12Scaled::maybe_from_i64(16, I64).unwrap(),
+                };
+                let sp_fp_slots = |reg| PairAMode::SignedOffset {
+                    reg,
+                    simm7: SImm7Scaled::maybe_from_i64(0, I64).unwrap(),
+                };
+                let mov_sp = |rd, rn| Inst::AluRRImm12 {
+                    alu_op: ALUOp::Add,
+                    size: OperandSize::Size64,
+                    rd,
+                    rn,
+                    imm12: Imm12 {
+                        bits: 0,
+                        shift12: false,
+                    },
+                };
+
+                let insts = [
+                    Inst::Store64 {
+                        rd: link_reg(),
+                        mem: AMode::SPPreIndexed {
+                            simm9: SImm9::maybe_from_i64(-16).unwrap(),
+                        },
+                        flags,
+                    },
+                    mov_sp(writable_spilltmp_reg(), stack_reg()),
+                    Inst::StoreP64 {
+                        rt: spilltmp_reg(),
+                        rt2: fp_reg(),
+                        mem: sp_fp_slots(store_context_ptr),
+                        flags,
+                    },
+                    // The offset from here to `resume`: six instructions.
+                    Inst::Adr {
+                        rd: writable_tmp2_reg(),
+                        off: 24,
+                    },
+                    Inst::Store64 {
+                        rd: tmp2_reg(),
+                        mem: pc_slot(store_context_ptr),
+                        flags,
+                    },
+                    Inst::LoadP64 {
+                        rt: writable_spilltmp_reg(),
+                        rt2: writable_fp_reg(),
+                        mem: sp_fp_slots(load_context_ptr),
+                        flags,
+                    },
+                    mov_sp(writable_stack_reg(), spilltmp_reg()),
+                    Inst::ULoad64 {
+                        rd: writable_spilltmp_reg(),
+                        mem: pc_slot(load_context_ptr),
+                        flags,
+                    },
+                    Inst::IndirectBr {
+                        rn: spilltmp_reg(),
+                        targets: vec![],
+                    },
+                ];
+                let adr_offset = sink.cur_offset() + 12;
+                for inst in insts {
+                    inst.emit(&[], sink, emit_info, state);
+                }
+                debug_assert_eq!(sink.cur_offset() - adr_offset, 24);
+
+                // `bti` is a no-op on processors without BTI, so it is emitted
+                // unconditionally.
+                Inst::Bti {
+                    targets: BranchTargetType::J,
+                }
+                .emit(&[], sink, emit_info, state);
+                Inst::ULoad64 {
+                    rd: writable_link_reg(),
+                    mem: AMode::SPPostIndexed {
+                        simm9: SImm9::maybe_from_i64(16).unwrap(),
+                    },
+                    flags,
+                }
+                .emit(&[], sink, emit_info, state);
             }
             &Inst::ReturnCall {
                 ref callee,
@@ -3218,7 +3336,7 @@ impl MachInstEmit for Inst {
                 // for the target, but rather a function relocation.
                 sink.add_reloc(Reloc::Arm64Call, callee, 0);
                 sink.put4(enc_jump26(0b000101, 0));
-                sink.add_call_site(ir::Opcode::ReturnCall);
+                sink.add_call_site(ir::Opcode::ReturnCall, 0);
 
                 // `emit_return_call_common_sequence` emits an island if
                 // necessary, so we can safely disable the worst-case-size check
@@ -3235,7 +3353,7 @@ impl MachInstEmit for Inst {
                     targets: vec![],
                 }
                 .emit(&[], sink, emit_info, state);
-                sink.add_call_site(ir::Opcode::ReturnCallIndirect);
+                sink.add_call_site(ir::Opcode::ReturnCallIndirect, 0);
 
                 // `emit_return_call_common_sequence` emits an island if
                 // necessary, so we can safely disable the worst-case-size check
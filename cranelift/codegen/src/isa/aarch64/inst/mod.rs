@@ -875,6 +875,22 @@ fn aarch64_get_operands<F: Fn(VReg) -> VReg>(inst: &Inst, collector: &mut Operan
             }
             collector.reg_clobbers(info.clobbers);
         }
+        &Inst::StackSwitch {
+            store_context_ptr,
+            load_context_ptr,
+            in_payload0,
+            out_payload0,
+        } => {
+            collector.reg_use(store_context_ptr);
+            collector.reg_use(load_context_ptr);
+            collector.reg_fixed_use(in_payload0, xreg(0));
+            collector.reg_fixed_def(out_payload0, xreg(0));
+            // Every other register is clobbered, since the stack that is
+            // switched to may use any of them before switching back.
+            let mut clobbers = crate::isa::aarch64::abi::ALL_CLOBBERS;
+            clobbers.remove(regs::xreg_preg(0));
+            collector.reg_clobbers(clobbers);
+        }
         &Inst::CallInd { ref info, .. } => {
             if info.callee_callconv == CallConv::Tail {
                 // TODO(https://github.com/bytecodealliance/regalloc2/issues/145):
@@ -2555,6 +2571,20 @@ impl Inst {
                 let rn = pretty_print_reg(info.rn, allocs);
                 format!("blr {}", rn)
             }
+            &Inst::StackSwitch {
+                store_context_ptr,
+                load_context_ptr,
+                in_payload0,
+                out_payload0,
+            } => {
+                let store_context_ptr = pretty_print_reg(store_context_ptr, allocs);
+                let load_context_ptr = pretty_print_reg(load_context_ptr, allocs);
+                let in_payload0 = pretty_print_reg(in_payload0, allocs);
+                let out_payload0 = pretty_print_reg(out_payload0.to_reg(), allocs);
+                format!(
+                    "stack_switch {out_payload0}, {store_context_ptr}, {load_context_ptr}, {in_payload0}"
+                )
+            }
             &Inst::ReturnCall {
                 ref callee,
                 ref info,
@@ -812,9 +812,6 @@ impl MachInstEmit for Inst {
                 // call
                 match info.dest {
                     ExternalName::User { .. } => {
-                        if info.opcode.is_call() {
-                            sink.add_call_site(info.opcode);
-                        }
                         sink.add_reloc(Reloc::RiscvCall, &info.dest, 0);
                         if let Some(s) = state.take_stack_map() {
                             sink.add_stack_map(StackMapExtent::UpcomingBytes(8), s);
@@ -842,9 +839,6 @@ impl MachInstEmit for Inst {
                         if let Some(s) = state.take_stack_map() {
                             sink.add_stack_map(StackMapExtent::UpcomingBytes(4), s);
                         }
-                        if info.opcode.is_call() {
-                            sink.add_call_site(info.opcode);
-                        }
                         // call
                         Inst::Jalr {
                             rd: writable_link_reg(),
@@ -861,6 +855,11 @@ impl MachInstEmit for Inst {
                     "call adjusts virtual sp offset by {callee_pop_size} -> {}",
                     state.virtual_sp_offset
                 );
+
+                if info.opcode.is_call() {
+                    let sp_to_fp = state.nominal_sp_to_fp + state.virtual_sp_offset;
+                    sink.add_call_site(info.opcode, u32::try_from(sp_to_fp).unwrap());
+                }
             }
             &Inst::CallInd { ref info } => {
                 let rn = allocs.next(info.rn);
@@ -868,9 +867,6 @@ impl MachInstEmit for Inst {
                     sink.add_stack_map(StackMapExtent::UpcomingBytes(4), s);
                 }
 
-                if info.opcode.is_call() {
-                    sink.add_call_site(info.opcode);
-                }
                 Inst::Jalr {
                     rd: writable_link_reg(),
                     base: rn,
@@ -884,6 +880,11 @@ impl MachInstEmit for Inst {
                     "call adjusts virtual sp offset by {callee_pop_size} -> {}",
                     state.virtual_sp_offset
                 );
+
+                if info.opcode.is_call() {
+                    let sp_to_fp = state.nominal_sp_to_fp + state.virtual_sp_offset;
+                    sink.add_call_site(info.opcode, u32::try_from(sp_to_fp).unwrap());
+                }
             }
 
             &Inst::ReturnCall {
@@ -900,7 +901,7 @@ impl MachInstEmit for Inst {
                     &info.uses,
                 );
 
-                sink.add_call_site(ir::Opcode::ReturnCall);
+                sink.add_call_site(ir::Opcode::ReturnCall, 0);
                 sink.add_reloc(Reloc::RiscvCall, &callee, 0);
                 Inst::construct_auipc_and_jalr(None, writable_spilltmp_reg(), 0)
                     .into_iter()
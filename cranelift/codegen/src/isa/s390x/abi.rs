@@ -29,6 +29,26 @@
 //!   the lowest word on the stack (part of the register save area)
 //!   holds a copy of the stack pointer at function entry.
 //!
+//! - The "tail" calling convention uses the same argument registers
+//!   and stack argument layout as the standard ABI, with these differences
+//!   (mostly to support tail calls):
+//!
+//!   - There are no callee-saved registers (except for the stack pointer
+//!     itself).  All GPRs and vector registers are clobbered by a call.
+//!
+//!   - Vector lanes use little-endian lane order, as in the Wasmtime ABI.
+//!
+//!   - The callee pops its stack arguments (but not the register save
+//!     area) on return.  Since the caller allocates its outgoing argument
+//!     area only once in the prologue, it re-allocates that space directly
+//!     after each call returns (and re-writes the backchain if needed).
+//!
+//!   A `return_call` builds the callee's stack arguments in the outgoing
+//!   argument area as for a regular call, then copies them to their final
+//!   location relative to the caller's incoming SP (overwriting the
+//!   caller's own incoming stack arguments), restores the link register,
+//!   and branches to the callee.
+//!
 //! Overall, the stack frame layout on s390x is as follows:
 //!
 //! ```plain
@@ -189,6 +209,11 @@ static STACK_ARG_RET_SIZE_LIMIT: u32 = 128 * 1024 * 1024;
 /// The size of the register save area
 pub static REG_SAVE_AREA_SIZE: u32 = 160;
 
+/// This is the limit for the size of the stack argument area when using the
+/// "tail" calling convention.  A `return_call` copies stack arguments using
+/// 20-bit displacements, which requires this area to be addressable that way.
+static TAIL_STACK_ARG_SIZE_LIMIT: u32 = 256 * 1024;
+
 impl Into<MemArg> for StackAMode {
     fn into(self) -> MemArg {
         match self {
@@ -232,12 +257,6 @@ impl ABIMachineSpec for S390xMachineDeps {
     where
         I: IntoIterator<Item = &'a ir::AbiParam>,
     {
-        assert_ne!(
-            call_conv,
-            isa::CallConv::Tail,
-            "s390x does not support the 'tail' calling convention yet"
-        );
-
         let mut next_gpr = 0;
         let mut next_fpr = 0;
         let mut next_vr = 0;
@@ -256,7 +275,11 @@ impl ABIMachineSpec for S390xMachineDeps {
         for mut param in params.into_iter().copied() {
             let intreg = in_int_reg(param.value_type);
             let fltreg = in_flt_reg(param.value_type);
-            let vecreg = in_vec_reg(param.value_type);
+            // An implicitly referenced buffer would not survive a tail call,
+            // so the tail calling convention passes `i128` values in vector
+            // registers, and on the stack by value once those run out.
+            let vecreg = in_vec_reg(param.value_type)
+                || (call_conv == isa::CallConv::Tail && param.value_type == types::I128);
             debug_assert!(intreg as i32 + fltreg as i32 + vecreg as i32 <= 1);
 
             let (next_reg, candidate, implicit_ref) = if intreg {
@@ -399,6 +422,12 @@ impl ABIMachineSpec for S390xMachineDeps {
         if next_stack > STACK_ARG_RET_SIZE_LIMIT {
             return Err(CodegenError::ImplLimitExceeded);
         }
+        if call_conv == isa::CallConv::Tail
+            && args_or_rets == ArgsOrRets::Args
+            && next_stack > TAIL_STACK_ARG_SIZE_LIMIT
+        {
+            return Err(CodegenError::ImplLimitExceeded);
+        }
 
         Ok((next_stack, extra_arg))
     }
@@ -443,10 +472,17 @@ impl ABIMachineSpec for S390xMachineDeps {
     fn gen_ret(
         _setup_frame: bool,
         _isa_flags: &s390x_settings::Flags,
-        _call_conv: isa::CallConv,
+        call_conv: isa::CallConv,
         rets: Vec<RetPair>,
         stack_bytes_to_pop: u32,
     ) -> Inst {
+        // The stack argument space includes the register save area, which
+        // is always owned by the caller.
+        let stack_bytes_to_pop = if call_conv == isa::CallConv::Tail {
+            stack_bytes_to_pop - REG_SAVE_AREA_SIZE
+        } else {
+            stack_bytes_to_pop
+        };
         Inst::Ret {
             link: gpr(14),
             rets,
@@ -782,8 +818,12 @@ impl ABIMachineSpec for S390xMachineDeps {
         s.initial_sp_offset
     }
 
-    fn get_regs_clobbered_by_call(_call_conv_of_callee: isa::CallConv) -> PRegSet {
-        CLOBBERS
+    fn get_regs_clobbered_by_call(call_conv_of_callee: isa::CallConv) -> PRegSet {
+        if call_conv_of_callee == isa::CallConv::Tail {
+            TAIL_CLOBBERS
+        } else {
+            CLOBBERS
+        }
     }
 
     fn get_ext_mode(
@@ -827,7 +867,13 @@ impl ABIMachineSpec for S390xMachineDeps {
     }
 }
 
-fn is_reg_saved_in_prologue(_call_conv: isa::CallConv, r: RealReg) -> bool {
+fn is_reg_saved_in_prologue(call_conv: isa::CallConv, r: RealReg) -> bool {
+    // The tail calling convention has no callee-saved registers.  Note that
+    // the link register and stack pointer are still saved in the prologue of
+    // non-leaf functions, see `get_clobbered_gpr_fpr`.
+    if call_conv == isa::CallConv::Tail {
+        return false;
+    }
     match r.class() {
         RegClass::Int => {
             // r6 - r15 inclusive are callee-saves.
@@ -947,3 +993,57 @@ const fn clobbers() -> PRegSet {
 }
 
 const CLOBBERS: PRegSet = clobbers();
+
+const fn tail_clobbers() -> PRegSet {
+    // The tail calling convention clobbers all registers except for the
+    // stack pointer.  (The link register is clobbered by the call itself.)
+    PRegSet::empty()
+        .with(gpr_preg(0))
+        .with(gpr_preg(1))
+        .with(gpr_preg(2))
+        .with(gpr_preg(3))
+        .with(gpr_preg(4))
+        .with(gpr_preg(5))
+        .with(gpr_preg(6))
+        .with(gpr_preg(7))
+        .with(gpr_preg(8))
+        .with(gpr_preg(9))
+        .with(gpr_preg(10))
+        .with(gpr_preg(11))
+        .with(gpr_preg(12))
+        .with(gpr_preg(13))
+        .with(vr_preg(0))
+        .with(vr_preg(1))
+        .with(vr_preg(2))
+        .with(vr_preg(3))
+        .with(vr_preg(4))
+        .with(vr_preg(5))
+        .with(vr_preg(6))
+        .with(vr_preg(7))
+        .with(vr_preg(8))
+        .with(vr_preg(9))
+        .with(vr_preg(10))
+        .with(vr_preg(11))
+        .with(vr_preg(12))
+        .with(vr_preg(13))
+        .with(vr_preg(14))
+        .with(vr_preg(15))
+        .with(vr_preg(16))
+        .with(vr_preg(17))
+        .with(vr_preg(18))
+        .with(vr_preg(19))
+        .with(vr_preg(20))
+        .with(vr_preg(21))
+        .with(vr_preg(22))
+        .with(vr_preg(23))
+        .with(vr_preg(24))
+        .with(vr_preg(25))
+        .with(vr_preg(26))
+        .with(vr_preg(27))
+        .with(vr_preg(28))
+        .with(vr_preg(29))
+        .with(vr_preg(30))
+        .with(vr_preg(31))
+}
+
+const TAIL_CLOBBERS: PRegSet = tail_clobbers();
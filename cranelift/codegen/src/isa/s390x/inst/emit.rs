@@ -2,10 +2,12 @@
 
 use crate::binemit::{Reloc, StackMap};
 use crate::ir::{MemFlags, RelSourceLoc, TrapCode};
-use crate::isa::s390x::abi::S390xMachineDeps;
+use crate::isa::s390x::abi::{S390xMachineDeps, REG_SAVE_AREA_SIZE};
 use crate::isa::s390x::inst::*;
 use crate::isa::s390x::settings as s390x_settings;
+use crate::isa::CallConv;
 use crate::machinst::{ABIMachineSpec, Reg, RegClass};
+use crate::settings;
 use crate::trace;
 use core::convert::TryFrom;
 use cranelift_control::ControlPlane;
@@ -1397,12 +1399,13 @@ impl EmitState {
 
 /// Constant state used during function compilation.
 pub struct EmitInfo {
+    flags: settings::Flags,
     isa_flags: s390x_settings::Flags,
 }
 
 impl EmitInfo {
-    pub(crate) fn new(isa_flags: s390x_settings::Flags) -> Self {
-        Self { isa_flags }
+    pub(crate) fn new(flags: settings::Flags, isa_flags: s390x_settings::Flags) -> Self {
+        Self { flags, isa_flags }
     }
 }
 
@@ -3528,8 +3531,13 @@ impl Inst {
                     0,
                 );
                 if info.opcode.is_call() {
-                    sink.add_call_site(info.opcode);
+                    // The callee has popped its stack arguments, which are
+                    // only re-allocated by the fixup below.
+                    let sp_to_fp = state.initial_sp_offset + state.virtual_sp_offset
+                        - i64::from(info.callee_pop_size);
+                    sink.add_call_site(info.opcode, u32::try_from(sp_to_fp).unwrap());
                 }
+                emit_callee_pop_fixup(sink, emit_info, state, info.callee_pop_size);
             }
             &Inst::CallInd { link, ref info } => {
                 debug_assert_eq!(link.to_reg(), gpr(14));
@@ -3541,8 +3549,39 @@ impl Inst {
                 }
                 put(sink, &enc_rr(opcode, link.to_reg(), rn));
                 if info.opcode.is_call() {
-                    sink.add_call_site(info.opcode);
+                    // The callee has popped its stack arguments, which are
+                    // only re-allocated by the fixup below.
+                    let sp_to_fp = state.initial_sp_offset + state.virtual_sp_offset
+                        - i64::from(info.callee_pop_size);
+                    sink.add_call_site(info.opcode, u32::try_from(sp_to_fp).unwrap());
                 }
+                emit_callee_pop_fixup(sink, emit_info, state, info.callee_pop_size);
+            }
+            &Inst::ReturnCall {
+                ref callee,
+                ref info,
+            } => {
+                emit_return_call_common_sequence(sink, emit_info, state, info, None);
+
+                let opcode = 0xc04; // BCRL
+                let reloc = Reloc::S390xPLTRel32Dbl;
+                put_with_reloc(sink, &enc_ril_c(opcode, 15, 0), 2, reloc, callee, 0);
+
+                // The sequence above has a variable size, and we already
+                // checked whether an island is needed, so we can safely
+                // disable the worst-case-size check here.
+                start_off = sink.cur_offset();
+            }
+            &Inst::ReturnCallInd { callee, ref info } => {
+                let callee = allocs.next(callee);
+
+                emit_return_call_common_sequence(sink, emit_info, state, info, Some(callee));
+
+                let opcode = 0x07; // BCR
+                put(sink, &enc_rr(opcode, gpr(15), spilltmp_reg()));
+
+                // See above.
+                start_off = sink.cur_offset();
             }
             &Inst::Args { .. } => {}
             &Inst::Ret {
@@ -3705,3 +3744,192 @@ impl Inst {
         state.clear_post_insn();
     }
 }
+
+/// Re-allocate the stack argument space popped by a callee using the tail
+/// calling convention, and re-write the backchain if requested.  This must
+/// only clobber registers that are clobbered by the call anyway.
+fn emit_callee_pop_fixup(
+    sink: &mut MachBuffer<Inst>,
+    emit_info: &EmitInfo,
+    state: &mut EmitState,
+    callee_pop_size: u32,
+) {
+    if callee_pop_size == 0 {
+        return;
+    }
+
+    for inst in S390xMachineDeps::gen_sp_reg_adjust(-i32::try_from(callee_pop_size).unwrap()) {
+        inst.emit(&[], sink, emit_info, state);
+    }
+
+    if emit_info.flags.preserve_frame_pointers() {
+        let tmp = writable_spilltmp_reg();
+        let inst = Inst::LoadAddr {
+            rd: tmp,
+            mem: MemArg::InitialSPOffset { off: 0 },
+        };
+        inst.emit(&[], sink, emit_info, state);
+        let inst = Inst::Store64 {
+            rd: tmp.to_reg(),
+            mem: MemArg::reg_plus_off(stack_reg(), 0, MemFlags::trusted()),
+        };
+        inst.emit(&[], sink, emit_info, state);
+    }
+}
+
+/// Emit the part of a `return_call` sequence common to direct and indirect
+/// tail calls, i.e. everything except for the final branch.  If `target` is
+/// given, it is moved to the spill temp register (%r1), which the caller
+/// must then branch to.
+fn emit_return_call_common_sequence(
+    sink: &mut MachBuffer<Inst>,
+    emit_info: &EmitInfo,
+    state: &mut EmitState,
+    info: &ReturnCallInfo,
+    target: Option<Reg>,
+) {
+    assert_eq!(
+        info.new_stack_arg_size % 8,
+        0,
+        "size of new stack arguments must be 8-byte aligned"
+    );
+    let new_stack_words = info.new_stack_arg_size / 8;
+
+    // We are emitting a dynamic number of instructions and might need an
+    // island.  Up to 64 bytes are needed for the fixed part of the sequence
+    // (including the final branch), plus 12 bytes per stack argument word.
+    let space_needed = 64 + 12 * new_stack_words;
+    if sink.island_needed(space_needed) {
+        let jump_around_label = sink.get_label();
+        Inst::Jump {
+            dest: jump_around_label,
+        }
+        .emit(&[], sink, emit_info, state);
+        sink.emit_island(&mut state.ctrl_plane);
+        sink.bind_label(jump_around_label, &mut state.ctrl_plane);
+    }
+
+    // The current stack layout is the following:
+    //
+    //            | ...                       |
+    //            | old stack arguments       |
+    //            +---------------------------+
+    //    current | reg save area             |
+    //    frame   | (incl. saved %r14)        | <-- SP at function entry
+    //            | ...                       |
+    //            | new stack arguments       |
+    //            | outgoing reg save area    | <-- SP
+    //            +---------------------------+
+    //
+    // We need to restore the link register, copy the new stack arguments
+    // over the old stack arguments (which may overlap the current register
+    // save area), set SP to the value the callee expects on entry, and then
+    // branch to the callee.  The callee sees its incoming stack arguments
+    // directly above its (caller-allocated) register save area, just as if
+    // it had been called by our caller.
+    //
+    // There are no callee-saved registers to restore in the tail calling
+    // convention.  We may use %r0, %r1 and %r14 as temporaries; %r14 is
+    // loaded with the return address last.  The target of an indirect tail
+    // call is kept in %r1.  Since the argument copies may overwrite the
+    // saved return address and backchain, we first stash those in our own
+    // outgoing register save area, which is free at this point.
+    let tmp = writable_gpr(0);
+    let entry_sp = writable_gpr(14);
+    const RETURN_ADDRESS_STASH: i64 = 152;
+    const BACKCHAIN_STASH: i64 = 144;
+
+    if let Some(target) = target {
+        Inst::mov64(writable_spilltmp_reg(), target).emit(&[], sink, emit_info, state);
+    }
+
+    // Compute SP at function entry.  Note that `gen_add_imm` does not
+    // require any temporary register.
+    let frame_size = state.initial_sp_offset + state.virtual_sp_offset;
+    for inst in S390xMachineDeps::gen_add_imm(
+        CallConv::Tail,
+        entry_sp,
+        stack_reg(),
+        u32::try_from(frame_size).unwrap(),
+    ) {
+        inst.emit(&[], sink, emit_info, state);
+    }
+
+    // Stash the return address and (if needed) the backchain.
+    Inst::Load64 {
+        rd: tmp,
+        mem: MemArg::reg_plus_off(entry_sp.to_reg(), 112, MemFlags::trusted()),
+    }
+    .emit(&[], sink, emit_info, state);
+    Inst::Store64 {
+        rd: tmp.to_reg(),
+        mem: MemArg::reg_plus_off(stack_reg(), RETURN_ADDRESS_STASH, MemFlags::trusted()),
+    }
+    .emit(&[], sink, emit_info, state);
+    if emit_info.flags.preserve_frame_pointers() {
+        Inst::Load64 {
+            rd: tmp,
+            mem: MemArg::reg_plus_off(entry_sp.to_reg(), 0, MemFlags::trusted()),
+        }
+        .emit(&[], sink, emit_info, state);
+        Inst::Store64 {
+            rd: tmp.to_reg(),
+            mem: MemArg::reg_plus_off(stack_reg(), BACKCHAIN_STASH, MemFlags::trusted()),
+        }
+        .emit(&[], sink, emit_info, state);
+    }
+
+    // Compute the callee's SP at entry.
+    let new_entry_sp = entry_sp;
+    let delta = i64::from(info.old_stack_arg_size) - i64::from(info.new_stack_arg_size);
+    if delta != 0 {
+        Inst::AluRSImm32 {
+            alu_op: ALUOp::Add64,
+            rd: new_entry_sp,
+            ri: entry_sp.to_reg(),
+            imm: i32::try_from(delta).unwrap(),
+        }
+        .emit(&[], sink, emit_info, state);
+    }
+
+    // Copy the new stack arguments into place.  The target area is always
+    // above the source area, so we copy from the top down to correctly
+    // handle overlapping areas.  The size of the stack argument area is
+    // limited so that all offsets here fit into a 20-bit displacement.
+    for i in (0..new_stack_words).rev() {
+        let off = i64::from(REG_SAVE_AREA_SIZE) + i64::from(i) * 8;
+        Inst::Load64 {
+            rd: tmp,
+            mem: MemArg::reg_plus_off(stack_reg(), off, MemFlags::trusted()),
+        }
+        .emit(&[], sink, emit_info, state);
+        Inst::Store64 {
+            rd: tmp.to_reg(),
+            mem: MemArg::reg_plus_off(new_entry_sp.to_reg(), off, MemFlags::trusted()),
+        }
+        .emit(&[], sink, emit_info, state);
+    }
+
+    // Write the callee's backchain, if needed.
+    if emit_info.flags.preserve_frame_pointers() {
+        Inst::Load64 {
+            rd: tmp,
+            mem: MemArg::reg_plus_off(stack_reg(), BACKCHAIN_STASH, MemFlags::trusted()),
+        }
+        .emit(&[], sink, emit_info, state);
+        Inst::Store64 {
+            rd: tmp.to_reg(),
+            mem: MemArg::reg_plus_off(new_entry_sp.to_reg(), 0, MemFlags::trusted()),
+        }
+        .emit(&[], sink, emit_info, state);
+    }
+
+    // Restore the return address and set up the callee's SP.
+    Inst::Load64 {
+        rd: tmp,
+        mem: MemArg::reg_plus_off(stack_reg(), RETURN_ADDRESS_STASH, MemFlags::trusted()),
+    }
+    .emit(&[], sink, emit_info, state);
+    Inst::mov64(writable_stack_reg(), new_entry_sp.to_reg()).emit(&[], sink, emit_info, state);
+    Inst::mov64(writable_gpr(14), tmp.to_reg()).emit(&[], sink, emit_info, state);
+}
@@ -6994,6 +6994,7 @@ fn test_s390x_binemit() {
                 opcode: Opcode::Call,
                 caller_callconv: CallConv::SystemV,
                 callee_callconv: CallConv::SystemV,
+                callee_pop_size: 0,
                 tls_symbol: None,
             }),
         },
@@ -7012,11 +7013,36 @@ fn test_s390x_binemit() {
                 opcode: Opcode::CallIndirect,
                 caller_callconv: CallConv::SystemV,
                 callee_callconv: CallConv::SystemV,
+                callee_pop_size: 0,
             }),
         },
         "0DE1",
         "basr %r14, %r1",
     ));
+    insns.push((
+        Inst::ReturnCall {
+            callee: Box::new(ExternalName::testcase("test0")),
+            info: Box::new(ReturnCallInfo {
+                uses: smallvec![],
+                old_stack_arg_size: 0,
+                new_stack_arg_size: 0,
+            }),
+        },
+        "41E0F000E300E0700004E300F0980024E300F0980004B90400FEB90400E0C0F400000000",
+        "return_call %test0 ; old_stack_arg_size 0 ; new_stack_arg_size 0",
+    ));
+    insns.push((
+        Inst::ReturnCallInd {
+            callee: gpr(3),
+            info: Box::new(ReturnCallInfo {
+                uses: smallvec![],
+                old_stack_arg_size: 0,
+                new_stack_arg_size: 0,
+            }),
+        },
+        "B904001341E0F000E300E0700004E300F0980024E300F0980004B90400FEB90400E007F1",
+        "return_call_ind %r3 ; old_stack_arg_size 0 ; new_stack_arg_size 0",
+    ));
 
     insns.push((
         Inst::Ret {
@@ -13377,7 +13403,7 @@ fn test_s390x_binemit() {
     let ctrl_plane = &mut Default::default();
     let constants = Default::default();
 
-    let emit_info = EmitInfo::new(isa_flags);
+    let emit_info = EmitInfo::new(flags, isa_flags);
     for (insn, expected_encoding, expected_printing) in insns {
         println!(
             "S390x: {:?}, {}, {}",
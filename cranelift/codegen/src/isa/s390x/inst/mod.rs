@@ -45,6 +45,7 @@ pub struct CallInfo {
     pub opcode: Opcode,
     pub caller_callconv: CallConv,
     pub callee_callconv: CallConv,
+    pub callee_pop_size: u32,
     pub tls_symbol: Option<SymbolReloc>,
 }
 
@@ -59,6 +60,16 @@ pub struct CallIndInfo {
     pub opcode: Opcode,
     pub caller_callconv: CallConv,
     pub callee_callconv: CallConv,
+    pub callee_pop_size: u32,
+}
+
+/// Additional information for `return_call[_ind]` instructions, left out of
+/// line to lower the size of the Inst enum.
+#[derive(Clone, Debug)]
+pub struct ReturnCallInfo {
+    pub uses: CallArgList,
+    pub old_stack_arg_size: u32,
+    pub new_stack_arg_size: u32,
 }
 
 #[test]
@@ -239,6 +250,8 @@ impl Inst {
             | Inst::VecReplicateLane { .. }
             | Inst::Call { .. }
             | Inst::CallInd { .. }
+            | Inst::ReturnCall { .. }
+            | Inst::ReturnCallInd { .. }
             | Inst::Args { .. }
             | Inst::Ret { .. }
             | Inst::Jump { .. }
@@ -948,6 +961,17 @@ fn s390x_get_operands<F: Fn(VReg) -> VReg>(inst: &Inst, collector: &mut OperandC
             clobbers.add(link.to_reg().to_real_reg().unwrap().into());
             collector.reg_clobbers(clobbers);
         }
+        &Inst::ReturnCall { ref info, .. } => {
+            for u in &info.uses {
+                collector.reg_fixed_use(u.vreg, u.preg);
+            }
+        }
+        &Inst::ReturnCallInd { callee, ref info } => {
+            collector.reg_use(callee);
+            for u in &info.uses {
+                collector.reg_fixed_use(u.vreg, u.preg);
+            }
+        }
         &Inst::Args { ref args } => {
             for arg in args {
                 collector.reg_fixed_def(arg.vreg, arg.preg);
@@ -1057,6 +1081,7 @@ impl MachInst for Inst {
     fn is_term(&self) -> MachTerminator {
         match self {
             &Inst::Ret { .. } => MachTerminator::Ret,
+            &Inst::ReturnCall { .. } | &Inst::ReturnCallInd { .. } => MachTerminator::RetCall,
             &Inst::Jump { .. } => MachTerminator::Uncond,
             &Inst::CondBr { .. } => MachTerminator::Cond,
             &Inst::OneWayCondBr { .. } => {
@@ -3144,19 +3169,60 @@ impl Inst {
                     }
                     _ => unreachable!(),
                 };
+                let callee_pop_size = if info.callee_pop_size > 0 {
+                    format!(" ; callee_pop_size {}", info.callee_pop_size)
+                } else {
+                    "".to_string()
+                };
                 debug_assert_eq!(link, gpr(14));
                 format!(
-                    "brasl {}, {}{}",
+                    "brasl {}, {}{}{}",
                     show_reg(link),
                     info.dest.display(None),
-                    tls_symbol
+                    tls_symbol,
+                    callee_pop_size
                 )
             }
             &Inst::CallInd { link, ref info, .. } => {
                 let link = link.to_reg();
                 let rn = pretty_print_reg(info.rn, allocs);
+                let callee_pop_size = if info.callee_pop_size > 0 {
+                    format!(" ; callee_pop_size {}", info.callee_pop_size)
+                } else {
+                    "".to_string()
+                };
                 debug_assert_eq!(link, gpr(14));
-                format!("basr {}, {}", show_reg(link), rn)
+                format!("basr {}, {}{}", show_reg(link), rn, callee_pop_size)
+            }
+            &Inst::ReturnCall {
+                ref callee,
+                ref info,
+            } => {
+                let mut s = format!(
+                    "return_call {} ; old_stack_arg_size {} ; new_stack_arg_size {}",
+                    callee.display(None),
+                    info.old_stack_arg_size,
+                    info.new_stack_arg_size
+                );
+                for u in &info.uses {
+                    let preg = pretty_print_reg(u.preg, &mut empty_allocs);
+                    let vreg = pretty_print_reg(u.vreg, allocs);
+                    write!(&mut s, " {}={}", vreg, preg).unwrap();
+                }
+                s
+            }
+            &Inst::ReturnCallInd { callee, ref info } => {
+                let callee = pretty_print_reg(callee, allocs);
+                let mut s = format!(
+                    "return_call_ind {} ; old_stack_arg_size {} ; new_stack_arg_size {}",
+                    callee, info.old_stack_arg_size, info.new_stack_arg_size
+                );
+                for u in &info.uses {
+                    let preg = pretty_print_reg(u.preg, &mut empty_allocs);
+                    let vreg = pretty_print_reg(u.vreg, allocs);
+                    write!(&mut s, " {}={}", vreg, preg).unwrap();
+                }
+                s
             }
             &Inst::Args { ref args } => {
                 let mut s = "args".to_string();
@@ -8,7 +8,7 @@ use crate::ir::ExternalName;
 use crate::isa::s390x::abi::{S390xMachineDeps, REG_SAVE_AREA_SIZE};
 use crate::isa::s390x::inst::{
     gpr, stack_reg, writable_gpr, zero_reg, CallIndInfo, CallInfo, Cond, Inst as MInst, LaneOrder,
-    MemArg, MemArgPair, RegPair, SymbolReloc, UImm12, UImm16Shifted, UImm32Shifted,
+    MemArg, MemArgPair, RegPair, ReturnCallInfo, SymbolReloc, UImm12, UImm16Shifted, UImm32Shifted,
     WritableRegPair,
 };
 use crate::isa::s390x::S390xBackend;
@@ -24,7 +24,7 @@ use crate::{
     machinst::abi::ABIMachineSpec,
     machinst::{
         ArgPair, CallArgList, CallArgPair, CallRetList, CallRetPair, InstOutput, Lower, MachInst,
-        VCodeConstant, VCodeConstantData,
+        SigSet, VCodeConstant, VCodeConstantData,
     },
 };
 use crate::{isle_common_prelude_methods, isle_lower_prelude_methods};
@@ -45,6 +45,7 @@ pub struct LibCallInfo {
 
 type BoxCallInfo = Box<CallInfo>;
 type BoxCallIndInfo = Box<CallIndInfo>;
+type BoxReturnCallInfo = Box<ReturnCallInfo>;
 type VecMachLabel = Vec<MachLabel>;
 type BoxExternalName = Box<ExternalName>;
 type BoxSymbolReloc = Box<SymbolReloc>;
@@ -83,23 +84,21 @@ impl generated_code::Context for IsleContext<'_, '_, MInst, S390xBackend> {
 
     fn gen_return_call(
         &mut self,
-        callee_sig: SigRef,
-        callee: ExternalName,
-        distance: RelocDistance,
-        args: ValueSlice,
+        _callee_sig: SigRef,
+        _callee: ExternalName,
+        _distance: RelocDistance,
+        _args: ValueSlice,
     ) -> InstOutput {
-        let _ = (callee_sig, callee, distance, args);
-        todo!()
+        unreachable!("s390x lowers `return_call` via the rules in lower.isle")
     }
 
     fn gen_return_call_indirect(
         &mut self,
-        callee_sig: SigRef,
-        callee: Value,
-        args: ValueSlice,
+        _callee_sig: SigRef,
+        _callee: Value,
+        _args: ValueSlice,
     ) -> InstOutput {
-        let _ = (callee_sig, callee, args);
-        todo!()
+        unreachable!("s390x lowers `return_call_indirect` via the rules in lower.isle")
     }
 
     #[inline]
@@ -205,6 +204,7 @@ impl generated_code::Context for IsleContext<'_, '_, MInst, S390xBackend> {
             opcode: *opcode,
             caller_callconv: self.lower_ctx.abi().call_conv(self.lower_ctx.sigs()),
             callee_callconv: self.lower_ctx.sigs()[abi].call_conv(),
+            callee_pop_size: callee_pop_size(self.lower_ctx.sigs(), abi),
             tls_symbol: None,
         })
     }
@@ -226,6 +226,36 @@ impl generated_code::Context for IsleContext<'_, '_, MInst, S390xBackend> {
             opcode: *opcode,
             caller_callconv: self.lower_ctx.abi().call_conv(self.lower_ctx.sigs()),
             callee_callconv: self.lower_ctx.sigs()[abi].call_conv(),
+            callee_pop_size: callee_pop_size(self.lower_ctx.sigs(), abi),
+        })
+    }
+
+    fn abi_ret_area_ptr(&mut self) -> Reg {
+        self.lower_ctx
+            .abi()
+            .ret_area_ptr()
+            .expect("if the tail callee has a return pointer, then the tail caller must as well")
+            .to_reg()
+    }
+
+    fn abi_return_call_info(&mut self, abi: Sig, uses: &CallArgList) -> BoxReturnCallInfo {
+        let sigs = self.lower_ctx.sigs();
+        debug_assert_eq!(
+            self.lower_ctx.abi().call_conv(sigs),
+            CallConv::Tail,
+            "Can only do `return_call`s from within a `tail` calling convention function"
+        );
+        debug_assert_eq!(sigs[abi].call_conv(), CallConv::Tail);
+
+        // Both stack argument sizes include the register save area, which
+        // is not moved by the tail call.
+        let old_stack_arg_size = self.lower_ctx.abi().stack_args_size(sigs) - REG_SAVE_AREA_SIZE;
+        let new_stack_arg_size =
+            u32::try_from(sigs[abi].sized_stack_arg_space()).unwrap() - REG_SAVE_AREA_SIZE;
+        Box::new(ReturnCallInfo {
+            uses: uses.clone(),
+            old_stack_arg_size,
+            new_stack_arg_size,
         })
     }
 
@@ -303,6 +333,7 @@ impl generated_code::Context for IsleContext<'_, '_, MInst, S390xBackend> {
             opcode: Opcode::Call,
             caller_callconv,
             callee_callconv,
+            callee_pop_size: 0,
             tls_symbol: info.tls_symbol.clone(),
         })
     }
@@ -986,13 +1017,24 @@ impl generated_code::Context for IsleContext<'_, '_, MInst, S390xBackend> {
     }
 }
 
+/// Number of stack argument bytes popped by the callee of a call using
+/// signature `abi`.
+fn callee_pop_size(sigs: &SigSet, abi: Sig) -> u32 {
+    // The tail calling convention has callees pop stack arguments,
+    // except for the register save area.
+    if sigs[abi].call_conv() == CallConv::Tail {
+        u32::try_from(sigs[abi].sized_stack_arg_space()).unwrap() - REG_SAVE_AREA_SIZE
+    } else {
+        0
+    }
+}
+
 /// Lane order to be used for a given calling convention.
 #[inline]
 fn lane_order_for_call_conv(call_conv: CallConv) -> LaneOrder {
-    if call_conv == CallConv::WasmtimeSystemV {
-        LaneOrder::LittleEndian
-    } else {
-        LaneOrder::BigEndian
+    match call_conv {
+        CallConv::WasmtimeSystemV | CallConv::Tail => LaneOrder::LittleEndian,
+        _ => LaneOrder::BigEndian,
     }
 }
 
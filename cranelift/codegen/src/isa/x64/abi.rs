@@ -1119,6 +1119,10 @@ const WINDOWS_CLOBBERS: PRegSet = windows_clobbers();
 const SYSV_CLOBBERS: PRegSet = sysv_clobbers();
 const TAIL_CLOBBERS: PRegSet = tail_clobbers();
 
+/// Every allocatable register, all of which are clobbered by
+/// `stack_switch`.
+pub(crate) const ALL_CLOBBERS: PRegSet = tail_clobbers();
+
 const fn windows_clobbers() -> PRegSet {
     PRegSet::empty()
         .with(regs::gpr_preg(regs::ENC_RAX))
@@ -1577,12 +1577,13 @@ pub(crate) fn emit(
             // beginning of the immediate field.
             emit_reloc(sink, Reloc::X86CallPCRel4, &dest, -4);
             sink.put4(0);
-            if call_info.opcode.is_call() {
-                sink.add_call_site(call_info.opcode);
-            }
-
             let callee_pop_size = i64::from(call_info.callee_pop_size);
             state.adjust_virtual_sp_offset(-callee_pop_size);
+
+            if call_info.opcode.is_call() {
+                let sp_to_fp = state.nominal_sp_to_fp() + state.virtual_sp_offset();
+                sink.add_call_site(call_info.opcode, u32::try_from(sp_to_fp).unwrap());
+            }
         }
 
         Inst::ReturnCallKnown {
@@ -1612,7 +1613,7 @@ pub(crate) fn emit(
             // beginning of the immediate field.
             emit_reloc(sink, Reloc::X86CallPCRel4, &callee, -4);
             sink.put4(0);
-            sink.add_call_site(ir::Opcode::ReturnCall);
+            sink.add_call_site(ir::Opcode::ReturnCall, 0);
         }
 
         Inst::ReturnCallUnknown {
@@ -1635,7 +1636,7 @@ pub(crate) fn emit(
             );
 
             Inst::JmpUnknown { target: callee }.emit(&[], sink, info, state);
-            sink.add_call_site(ir::Opcode::ReturnCallIndirect);
+            sink.add_call_site(ir::Opcode::ReturnCallIndirect, 0);
         }
 
         Inst::CallUnknown {
@@ -1677,12 +1678,13 @@ pub(crate) fn emit(
             if let Some(s) = state.take_stack_map() {
                 sink.add_stack_map(StackMapExtent::StartedAtOffset(start_offset), s);
             }
-            if call_info.opcode.is_call() {
-                sink.add_call_site(call_info.opcode);
-            }
-
             let callee_pop_size = i64::from(call_info.callee_pop_size);
             state.adjust_virtual_sp_offset(-callee_pop_size);
+
+            if call_info.opcode.is_call() {
+                let sp_to_fp = state.nominal_sp_to_fp() + state.virtual_sp_offset();
+                sink.add_call_site(call_info.opcode, u32::try_from(sp_to_fp).unwrap());
+            }
         }
 
         Inst::Args { .. } => {}
@@ -1790,6 +1792,80 @@ pub(crate) fn emit(
             }
         }
 
+        Inst::StackSwitchBasic {
+            store_context_ptr,
+            load_context_ptr,
+            in_payload0,
+            out_payload0,
+        } => {
+            let store_context_ptr = allocs.next(**store_context_ptr);
+            let load_context_ptr = allocs.next(**load_context_ptr);
+            let in_payload0 = allocs.next(**in_payload0);
+            let out_payload0 = allocs.next(*out_payload0.to_reg());
+            debug_assert_eq!(in_payload0, regs::rdi());
+            debug_assert_eq!(out_payload0, regs::rdi());
+
+            // Nothing needs to be done here to preserve registers or to pass
+            // the payload: the register allocator saves everything that is
+            // live across this instruction since it clobbers all registers,
+            // and the payload is passed in `rdi` on both sides.
+            //
+            // Since everything is clobbered, any register which isn't an
+            // operand can be used as a temporary. We generate:
+            //
+            // lea resume(%rip), %tmp
+            // mov %tmp, 16(%store_context_ptr)
+            // mov %rsp, 0(%store_context_ptr)
+            // mov %rbp, 8(%store_context_ptr)
+            // mov 0(%load_context_ptr), %rsp
+            // mov 8(%load_context_ptr), %rbp
+            // jmp *16(%load_context_ptr)
+            // resume:
+            let tmp = [regs::rax(), regs::rcx(), regs::rdx()]
+                .into_iter()
+                .find(|r| *r != store_context_ptr && *r != load_context_ptr)
+                .unwrap();
+            let tmp = Writable::from_reg(tmp);
+            let resume = sink.get_label();
+
+            Inst::lea(Amode::rip_relative(resume), tmp).emit(&[], sink, info, state);
+            let slot = |base, i: i32| Amode::imm_reg(i * 8, base);
+            Inst::mov_r_m(
+                OperandSize::Size64,
+                tmp.to_reg(),
+                slot(store_context_ptr, 2),
+            )
+            .emit(&[], sink, info, state);
+            Inst::mov_r_m(OperandSize::Size64, regs::rsp(), slot(store_context_ptr, 0)).emit(
+                &[],
+                sink,
+                info,
+                state,
+            );
+            Inst::mov_r_m(OperandSize::Size64, regs::rbp(), slot(store_context_ptr, 1)).emit(
+                &[],
+                sink,
+                info,
+                state,
+            );
+
+            Inst::mov64_m_r(slot(load_context_ptr, 0), Writable::from_reg(regs::rsp())).emit(
+                &[],
+                sink,
+                info,
+                state,
+            );
+            Inst::mov64_m_r(slot(load_context_ptr, 1), Writable::from_reg(regs::rbp())).emit(
+                &[],
+                sink,
+                info,
+                state,
+            );
+            Inst::jmp_unknown(RegMem::mem(slot(load_context_ptr, 2))).emit(&[], sink, info, state);
+
+            sink.bind_label(resume, state.ctrl_plane_mut());
+        }
+
         Inst::JmpTableSeq {
             idx,
             tmp1,
@@ -103,6 +103,7 @@ impl Inst {
             | Inst::CallUnknown { .. }
             | Inst::ReturnCallKnown { .. }
             | Inst::ReturnCallUnknown { .. }
+            | Inst::StackSwitchBasic { .. }
             | Inst::CheckedSRemSeq { .. }
             | Inst::CheckedSRemSeq8 { .. }
             | Inst::Cmove { .. }
@@ -1707,6 +1708,22 @@ impl PrettyPrint for Inst {
                 format!("{op} {taken}; j {not_taken}")
             }
 
+            Inst::StackSwitchBasic {
+                store_context_ptr,
+                load_context_ptr,
+                in_payload0,
+                out_payload0,
+            } => {
+                let store_context_ptr = pretty_print_reg(**store_context_ptr, 8, allocs);
+                let load_context_ptr = pretty_print_reg(**load_context_ptr, 8, allocs);
+                let in_payload0 = pretty_print_reg(**in_payload0, 8, allocs);
+                let out_payload0 = pretty_print_reg(*out_payload0.to_reg(), 8, allocs);
+                let op = ljustify("stack_switch_basic".to_string());
+                format!(
+                    "{op} {store_context_ptr}, {load_context_ptr}, {in_payload0}, {out_payload0}"
+                )
+            }
+
             Inst::JmpTableSeq {
                 idx, tmp1, tmp2, ..
             } => {
@@ -2333,6 +2350,23 @@ fn x64_get_operands<F: Fn(VReg) -> VReg>(inst: &Inst, collector: &mut OperandCol
             collector.reg_early_def(tmp.to_writable_reg());
         }
 
+        Inst::StackSwitchBasic {
+            store_context_ptr,
+            load_context_ptr,
+            in_payload0,
+            out_payload0,
+        } => {
+            collector.reg_use(**store_context_ptr);
+            collector.reg_use(**load_context_ptr);
+            collector.reg_fixed_use(**in_payload0, regs::rdi());
+            collector.reg_fixed_def(out_payload0.to_writable_reg(), regs::rdi());
+            // Every other register is clobbered, since the stack that is
+            // switched to may use any of them before switching back.
+            let mut clobbers = crate::isa::x64::abi::ALL_CLOBBERS;
+            clobbers.remove(regs::gpr_preg(regs::ENC_RDI));
+            collector.reg_clobbers(clobbers);
+        }
+
         Inst::JmpTableSeq {
             ref idx,
             ref tmp1,
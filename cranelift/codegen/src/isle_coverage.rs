@@ -0,0 +1,160 @@
+//! ISLE rule coverage.
+//!
+//! With the `isle-coverage` feature enabled, the ISLE-generated code for the mid-end and for each
+//! backend reports every rule that fires. This module counts those firings per rule on the
+//! current thread, so that rule tuning can be guided by which rules a workload actually
+//! exercises, and can also record the order in which rules fire while compiling a single
+//! function.
+//!
+//! Rules are named by their term and the ISLE source position where they are defined, e.g.
+//! `lower at src/isa/x64/lower.isle line 1234`.
+
+use core::fmt;
+use std::cell::RefCell;
+use std::mem;
+use std::vec::Vec;
+
+/// Accumulated rule firing counts.
+#[derive(Default)]
+pub struct RuleCoverage {
+    /// Firing counts for each ISLE-generated rule table that has been used, indexed like the
+    /// table.
+    tables: Vec<(&'static [&'static str], Vec<u64>)>,
+}
+
+impl RuleCoverage {
+    fn counts_mut(&mut self, rules: &'static [&'static str]) -> &mut Vec<u64> {
+        let idx = match self
+            .tables
+            .iter()
+            .position(|(t, _)| core::ptr::eq(*t, rules))
+        {
+            Some(idx) => idx,
+            None => {
+                self.tables.push((rules, vec![0; rules.len()]));
+                self.tables.len() - 1
+            }
+        };
+        &mut self.tables[idx].1
+    }
+
+    /// Add the counts of `other` to this `RuleCoverage`.
+    pub fn add(&mut self, other: &Self) {
+        for (rules, counts) in &other.tables {
+            for (a, b) in self.counts_mut(rules).iter_mut().zip(counts) {
+                *a += b;
+            }
+        }
+    }
+
+    /// The rules that fired at least once, with the number of times each fired, most frequent
+    /// first.
+    pub fn fired(&self) -> Vec<(&'static str, u64)> {
+        let mut fired: Vec<_> = self
+            .tables
+            .iter()
+            .flat_map(|(rules, counts)| rules.iter().copied().zip(counts.iter().copied()))
+            .filter(|&(_, count)| count > 0)
+            .collect();
+        fired.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        fired
+    }
+
+    /// The rules that never fired, among the rule tables of which at least one rule did.
+    pub fn unfired(&self) -> Vec<&'static str> {
+        self.tables
+            .iter()
+            .flat_map(|(rules, counts)| rules.iter().copied().zip(counts.iter().copied()))
+            .filter(|&(_, count)| count == 0)
+            .map(|(rule, _)| rule)
+            .collect()
+    }
+
+    /// The total number of rules in the rule tables of which at least one rule fired.
+    pub fn total(&self) -> usize {
+        self.tables.iter().map(|(rules, _)| rules.len()).sum()
+    }
+}
+
+impl fmt::Display for RuleCoverage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let fired = self.fired();
+        writeln!(f, "============  ==================================")?;
+        writeln!(f, "       Count  Rule")?;
+        writeln!(f, "------------  ----------------------------------")?;
+        for (rule, count) in &fired {
+            writeln!(f, "{:12}  {}", count, rule)?;
+        }
+        writeln!(f, "============  ==================================")?;
+        writeln!(f, "{} of {} rules fired", fired.len(), self.total())
+    }
+}
+
+thread_local! {
+    static COVERAGE: RefCell<RuleCoverage> = RefCell::new(Default::default());
+    static TRACE: RefCell<Option<Vec<&'static str>>> = RefCell::new(None);
+}
+
+/// Record that the rule `rules[rule]` fired.
+///
+/// This is called from the ISLE-generated code through `Context::rule_fired`.
+pub(crate) fn rule_fired(rules: &'static [&'static str], rule: usize) {
+    COVERAGE.with(|rc| rc.borrow_mut().counts_mut(rules)[rule] += 1);
+    TRACE.with(|rc| {
+        if let Some(trace) = &mut *rc.borrow_mut() {
+            trace.push(rules[rule]);
+        }
+    });
+}
+
+/// Take the current accumulated rule counts and reset the counts for the current thread.
+pub fn take_current() -> RuleCoverage {
+    COVERAGE.with(|rc| mem::take(&mut *rc.borrow_mut()))
+}
+
+/// Start recording the order in which rules fire on the current thread.
+///
+/// This is typically done right before compiling the single function of interest, and the
+/// recorded trace is retrieved with [`take_trace`] after it has been compiled.
+pub fn start_trace() {
+    TRACE.with(|rc| *rc.borrow_mut() = Some(Vec::new()));
+}
+
+/// Stop recording rule firings on the current thread, and return the rules that fired since
+/// [`start_trace`] was called, in order.
+pub fn take_trace() -> Vec<&'static str> {
+    TRACE.with(|rc| rc.borrow_mut().take().unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    static RULES: &[&str] = &[
+        "a at x.isle line 1",
+        "b at x.isle line 2",
+        "c at x.isle line 3",
+    ];
+
+    #[test]
+    fn counts_and_trace() {
+        take_current();
+        rule_fired(RULES, 1);
+        start_trace();
+        rule_fired(RULES, 2);
+        rule_fired(RULES, 1);
+        assert_eq!(take_trace(), ["c at x.isle line 3", "b at x.isle line 2"]);
+        rule_fired(RULES, 2);
+        assert!(take_trace().is_empty());
+
+        let coverage = take_current();
+        assert_eq!(
+            coverage.fired(),
+            [("b at x.isle line 2", 2), ("c at x.isle line 3", 2)]
+        );
+        assert_eq!(coverage.unfired(), ["a at x.isle line 1"]);
+        assert!(coverage.to_string().ends_with("2 of 3 rules fired\n"));
+        assert!(take_current().fired().is_empty());
+    }
+}
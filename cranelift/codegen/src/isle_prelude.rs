@@ -7,6 +7,11 @@
 #[doc(hidden)]
 macro_rules! isle_common_prelude_methods {
     () => {
+        #[cfg(feature = "isle-coverage")]
+        fn rule_fired(&mut self, rules: &'static [&'static str], rule: usize) {
+            $crate::isle_coverage::rule_fired(rules, rule);
+        }
+
         /// We don't have a way of making a `()` value in isle directly.
         #[inline]
         fn unit(&mut self) -> Unit {
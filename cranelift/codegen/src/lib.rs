@@ -105,10 +105,12 @@ mod ctxhash;
 mod dce;
 mod egraph;
 mod fx;
+mod inline;
 mod inst_predicates;
 mod isle_prelude;
 mod iterators;
 mod legalizer;
+mod loop_opts;
 mod nan_canonicalization;
 mod opts;
 mod remove_constant_phis;
@@ -121,11 +123,15 @@ mod value_label;
 #[cfg(feature = "souper-harvest")]
 mod souper_harvest;
 
+pub use crate::inline::{Inline, InlineCommand};
 pub use crate::result::{CodegenError, CodegenResult, CompileError};
 
 #[cfg(feature = "incremental-cache")]
 pub mod incremental_cache;
 
+#[cfg(feature = "isle-coverage")]
+pub mod isle_coverage;
+
 /// Even when trace logging is disabled, the trace macro has a significant performance cost so we
 /// disable it by default.
 #[macro_export]
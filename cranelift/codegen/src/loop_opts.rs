@@ -0,0 +1,607 @@
+//! Loop optimizations.
+//!
+//! The egraph pass already hoists loop-invariant pure computations out of loops, but it cannot
+//! change the shape of the control flow graph or the set of values that flow around a loop. This
+//! pass performs the loop transformations that need to, on the natural loops found by
+//! [`LoopAnalysis`]:
+//!
+//! - Every loop is given a preheader: a block outside of the loop whose only successor is the loop
+//!   header, and which is the only predecessor of the header from outside of the loop.
+//!
+//! - Conditional traps at the start of a loop header whose condition is loop-invariant, such as
+//!   explicit bounds checks of an invariant address, are hoisted into the preheader along with the
+//!   pure instructions computing their condition. Such a trap either fires on the first iteration,
+//!   before the loop has any side effect, or never fires at all.
+//!
+//! - Induction variables are strength-reduced: when a header parameter `i` is incremented by a
+//!   constant `step` on every back edge, products `i * c` and shifts `i << c` inside the loop are
+//!   replaced by a new header parameter that starts at `init * c` and is incremented by
+//!   `step * c`.
+//!
+//! - Small loops made of a single block are unrolled by the `loop_unroll_factor` setting. Every
+//!   copy of the body keeps its exit test, so unrolling is valid whatever the loop's trip count.
+//!
+//! The pass runs after legalization, so only constants materialized by `iconst` are recognized.
+
+use crate::cursor::{Cursor, FuncCursor};
+use crate::dominator_tree::DominatorTree;
+use crate::entity::{EntitySet, SecondaryMap};
+use crate::flowgraph::ControlFlowGraph;
+use crate::fx::FxHashSet;
+use crate::inst_predicates::is_pure_for_egraph;
+use crate::ir::immediates::Imm64;
+use crate::ir::{
+    Block, Function, Inst, InstBuilder, InstructionData, Opcode, Type, Value, ValueDef,
+};
+use crate::loop_analysis::{Loop, LoopAnalysis};
+use crate::packed_option::PackedOption;
+use crate::settings::Flags;
+use crate::timing;
+use crate::trace;
+use alloc::vec::Vec;
+use smallvec::SmallVec;
+
+/// The maximum number of instructions in a loop body that may be unrolled.
+const UNROLL_MAX_INSTS: usize = 32;
+
+/// Perform loop optimizations on `func`, according to `flags`.
+///
+/// The control flow graph, dominator tree and loop analysis are recomputed on entry, and are valid
+/// for the transformed function on exit. Returns whether the function was changed.
+pub fn do_loop_opts(
+    func: &mut Function,
+    cfg: &mut ControlFlowGraph,
+    domtree: &mut DominatorTree,
+    loop_analysis: &mut LoopAnalysis,
+    flags: &Flags,
+) -> bool {
+    let _tt = timing::loop_opts();
+
+    cfg.compute(func);
+    domtree.compute(func, cfg);
+    loop_analysis.compute(func, cfg, domtree);
+
+    let loops: Vec<Loop> = loop_analysis.loops().collect();
+    if loops.is_empty() {
+        return false;
+    }
+
+    // Inserting preheaders changes the CFG, so do it for all loops up front.
+    let mut changed = false;
+    for &lp in &loops {
+        changed |= insert_preheader(func, cfg, domtree, loop_analysis.loop_header(lp));
+    }
+    if changed {
+        cfg.compute(func);
+        domtree.compute(func, cfg);
+        loop_analysis.compute(func, cfg, domtree);
+    }
+
+    // Neither of these change the CFG: they move instructions into preheaders and add parameters
+    // to loop headers.
+    for &lp in &loops {
+        let header = loop_analysis.loop_header(lp);
+        let preheader = match preheader(func, cfg, domtree, header) {
+            Some(preheader) => preheader,
+            None => continue,
+        };
+        changed |= hoist_invariant_guards(func, loop_analysis, lp, preheader);
+        changed |= reduce_strength(func, cfg, domtree, loop_analysis, lp, preheader);
+    }
+
+    let unroll_factor = flags.loop_unroll_factor();
+    if unroll_factor > 1 {
+        let used_outside = blocks_with_values_used_outside(func);
+        for &lp in &loops {
+            changed |= unroll(
+                func,
+                cfg,
+                domtree,
+                loop_analysis,
+                &used_outside,
+                lp,
+                unroll_factor,
+            );
+        }
+    }
+
+    if changed {
+        cfg.compute(func);
+        domtree.compute(func, cfg);
+        loop_analysis.compute(func, cfg, domtree);
+    }
+    changed
+}
+
+/// Is the branch `inst` to `header` a back edge?
+fn is_back_edge(func: &Function, domtree: &DominatorTree, header: Block, inst: Inst) -> bool {
+    domtree.dominates(header, inst, &func.layout)
+}
+
+/// Find the preheader of the loop with the given `header`, if it has one.
+fn preheader(
+    func: &Function,
+    cfg: &ControlFlowGraph,
+    domtree: &DominatorTree,
+    header: Block,
+) -> Option<Block> {
+    let mut entries = cfg
+        .pred_iter(header)
+        .filter(|pred| !is_back_edge(func, domtree, header, pred.inst));
+    let entry = entries.next()?;
+    if entries.next().is_some() {
+        return None;
+    }
+    match func.dfg.insts[entry.inst] {
+        InstructionData::Jump { .. } => Some(entry.block),
+        _ => None,
+    }
+}
+
+/// Give the loop with the given `header` a preheader, unless it already has one. Returns whether
+/// a block was inserted.
+fn insert_preheader(
+    func: &mut Function,
+    cfg: &ControlFlowGraph,
+    domtree: &DominatorTree,
+    header: Block,
+) -> bool {
+    if func.layout.entry_block() == Some(header) || preheader(func, cfg, domtree, header).is_some()
+    {
+        return false;
+    }
+
+    let entries: SmallVec<[Inst; 4]> = cfg
+        .pred_iter(header)
+        .filter(|pred| !is_back_edge(func, domtree, header, pred.inst))
+        .map(|pred| pred.inst)
+        .collect();
+    if entries.is_empty() {
+        return false;
+    }
+
+    let preheader = func.dfg.make_block();
+    func.layout.insert_block(preheader, header);
+    let params: SmallVec<[Value; 4]> = func
+        .dfg
+        .block_params(header)
+        .iter()
+        .map(|&param| func.dfg.value_type(param))
+        .collect::<SmallVec<[Type; 4]>>()
+        .into_iter()
+        .map(|ty| func.dfg.append_block_param(preheader, ty))
+        .collect();
+    FuncCursor::new(func)
+        .at_bottom(preheader)
+        .ins()
+        .jump(header, &params);
+
+    for inst in entries {
+        func.rewrite_branch_destination(inst, header, preheader);
+    }
+
+    trace!("loop_opts: inserted preheader {} for {}", preheader, header);
+    true
+}
+
+/// Move conditional traps with a loop-invariant condition from the start of the header of `lp` to
+/// its `preheader`, along with the pure instructions that compute the condition.
+fn hoist_invariant_guards(
+    func: &mut Function,
+    loop_analysis: &LoopAnalysis,
+    lp: Loop,
+    preheader: Block,
+) -> bool {
+    let header = loop_analysis.loop_header(lp);
+    let preheader_jump = func.layout.last_inst(preheader).unwrap();
+
+    // Pure instructions of the header whose operands are all loop-invariant.
+    let mut invariant_insts = FxHashSet::default();
+    let is_invariant = |func: &Function, invariant_insts: &FxHashSet<Inst>, value: Value| match func
+        .dfg
+        .value_def(func.dfg.resolve_aliases(value))
+    {
+        ValueDef::Result(inst, _) => {
+            invariant_insts.contains(&inst)
+                || !loop_analysis.is_in_loop(func.layout.inst_block(inst).unwrap(), lp)
+        }
+        ValueDef::Param(block, _) => !loop_analysis.is_in_loop(block, lp),
+        ValueDef::Union(..) => false,
+    };
+
+    let mut changed = false;
+    let mut next_inst = func.layout.first_inst(header);
+    while let Some(inst) = next_inst {
+        next_inst = func.layout.next_inst(inst);
+
+        if is_pure_for_egraph(func, inst) {
+            if func
+                .dfg
+                .inst_args(inst)
+                .iter()
+                .all(|&arg| is_invariant(func, &invariant_insts, arg))
+            {
+                invariant_insts.insert(inst);
+            }
+            continue;
+        }
+
+        let arg = match func.dfg.insts[inst] {
+            InstructionData::CondTrap {
+                opcode: Opcode::Trapz | Opcode::Trapnz,
+                arg,
+                ..
+            } if is_invariant(func, &invariant_insts, arg) => arg,
+            // Anything else may have a side effect, which the traps after it must not be moved
+            // ahead of.
+            _ => break,
+        };
+
+        // Collect the instructions computing the condition which are still in the header.
+        let mut to_hoist = FxHashSet::default();
+        let mut stack: SmallVec<[Value; 8]> = SmallVec::new();
+        stack.push(arg);
+        while let Some(value) = stack.pop() {
+            if let ValueDef::Result(def, _) = func.dfg.value_def(func.dfg.resolve_aliases(value)) {
+                if invariant_insts.contains(&def) && to_hoist.insert(def) {
+                    stack.extend(func.dfg.inst_args(def).iter().copied());
+                }
+            }
+        }
+
+        // Move them in their original order, so that definitions still precede uses.
+        let mut cursor = func.layout.first_inst(header);
+        while let Some(hoisted) = cursor {
+            cursor = func.layout.next_inst(hoisted);
+            if hoisted == inst || to_hoist.contains(&hoisted) {
+                func.layout.remove_inst(hoisted);
+                func.layout.insert_inst(hoisted, preheader_jump);
+                invariant_insts.remove(&hoisted);
+            }
+            if hoisted == inst {
+                break;
+            }
+        }
+
+        trace!("loop_opts: hoisted {} out of {}", inst, header);
+        changed = true;
+    }
+    changed
+}
+
+/// Get the value of `value` if it is an integer constant.
+fn iconst_value(func: &Function, value: Value) -> Option<u64> {
+    let inst = func.dfg.value_def(func.dfg.resolve_aliases(value)).inst()?;
+    match func.dfg.insts[inst] {
+        InstructionData::UnaryImm {
+            opcode: Opcode::Iconst,
+            imm,
+        } => Some(imm.bits() as u64),
+        _ => None,
+    }
+}
+
+/// Make an `iconst` immediate of type `ty` holding `value`, truncated to the width of `ty`.
+fn iconst_imm(ty: Type, value: u64) -> Imm64 {
+    let mask = u64::MAX >> (64 - ty.bits());
+    Imm64::new((value & mask) as i64)
+}
+
+/// If the header parameter at `index` is a basic induction variable, incremented by a constant on
+/// every back edge, return the incremented value and the constant.
+fn basic_induction_step(
+    func: &Function,
+    header: Block,
+    back_edges: &[Inst],
+    index: usize,
+) -> Option<(Value, u64)> {
+    let param = func.dfg.block_params(header)[index];
+    let pool = &func.dfg.value_lists;
+
+    // Every back edge must pass the same incremented value.
+    let mut next = None;
+    for &inst in back_edges {
+        for dest in func.dfg.insts[inst].branch_destination(&func.dfg.jump_tables) {
+            if dest.block(pool) != header {
+                continue;
+            }
+            let arg = func.dfg.resolve_aliases(dest.args_slice(pool)[index]);
+            match next {
+                None => next = Some(arg),
+                Some(next) if next == arg => {}
+                Some(_) => return None,
+            }
+        }
+    }
+    let next = next?;
+
+    let inst = func.dfg.value_def(next).inst()?;
+    let step = match func.dfg.insts[inst] {
+        InstructionData::Binary {
+            opcode: Opcode::Iadd,
+            args: [x, y],
+        } => {
+            if func.dfg.resolve_aliases(x) == param {
+                iconst_value(func, y)?
+            } else if func.dfg.resolve_aliases(y) == param {
+                iconst_value(func, x)?
+            } else {
+                return None;
+            }
+        }
+        InstructionData::Binary {
+            opcode: Opcode::Isub,
+            args: [x, y],
+        } if func.dfg.resolve_aliases(x) == param => iconst_value(func, y)?.wrapping_neg(),
+        _ => return None,
+    };
+    Some((next, step))
+}
+
+/// If `inst` multiplies `iv` by a constant, return that constant.
+fn induction_scale(func: &Function, inst: Inst, iv: Value) -> Option<u64> {
+    match func.dfg.insts[inst] {
+        InstructionData::Binary {
+            opcode: Opcode::Imul,
+            args: [x, y],
+        } => {
+            if func.dfg.resolve_aliases(x) == iv {
+                iconst_value(func, y)
+            } else if func.dfg.resolve_aliases(y) == iv {
+                iconst_value(func, x)
+            } else {
+                None
+            }
+        }
+        InstructionData::Binary {
+            opcode: Opcode::Ishl,
+            args: [x, y],
+        } if func.dfg.resolve_aliases(x) == iv => {
+            let bits = func.dfg.value_type(iv).bits();
+            let amount = iconst_value(func, y)? as u32 & (bits - 1);
+            Some(1 << amount)
+        }
+        _ => None,
+    }
+}
+
+/// Replace multiplications of the basic induction variables of `lp` by constants with new
+/// induction variables.
+fn reduce_strength(
+    func: &mut Function,
+    cfg: &ControlFlowGraph,
+    domtree: &DominatorTree,
+    loop_analysis: &LoopAnalysis,
+    lp: Loop,
+    preheader: Block,
+) -> bool {
+    let header = loop_analysis.loop_header(lp);
+    let preheader_jump = func.layout.last_inst(preheader).unwrap();
+    let back_edges: SmallVec<[Inst; 4]> = cfg
+        .pred_iter(header)
+        .filter(|pred| is_back_edge(func, domtree, header, pred.inst))
+        .map(|pred| pred.inst)
+        .collect();
+
+    let mut changed = false;
+    for index in 0..func.dfg.num_block_params(header) {
+        let iv = func.dfg.block_params(header)[index];
+        let ty = func.dfg.value_type(iv);
+        if !ty.is_int() || ty.bits() > 64 {
+            continue;
+        }
+        let (next, step) = match basic_induction_step(func, header, &back_edges, index) {
+            Some((_, 0)) | None => continue,
+            Some(iv_step) => iv_step,
+        };
+
+        let mut derived: Vec<(Inst, u64)> = Vec::new();
+        for block in func.layout.blocks() {
+            if !loop_analysis.is_in_loop(block, lp) {
+                continue;
+            }
+            for inst in func.layout.block_insts(block) {
+                match induction_scale(func, inst, iv) {
+                    // Leave trivial products to the egraph.
+                    Some(0) | Some(1) | None => {}
+                    Some(scale) => derived.push((inst, scale)),
+                }
+            }
+        }
+
+        // One new induction variable for each distinct scale.
+        let mut reduced: SmallVec<[(u64, Value); 4]> = SmallVec::new();
+        for (inst, scale) in derived {
+            let scaled = match reduced.iter().find(|(s, _)| *s == scale) {
+                Some(&(_, scaled)) => scaled,
+                None => {
+                    let scaled = add_scaled_induction_variable(
+                        func,
+                        header,
+                        preheader_jump,
+                        &back_edges,
+                        index,
+                        next,
+                        step,
+                        scale,
+                    );
+                    reduced.push((scale, scaled));
+                    scaled
+                }
+            };
+
+            let result = func.dfg.first_result(inst);
+            func.dfg.clear_results(inst);
+            func.dfg.change_to_alias(result, scaled);
+            func.layout.remove_inst(inst);
+            trace!(
+                "loop_opts: replaced {} with induction variable {}",
+                result,
+                scaled
+            );
+            changed = true;
+        }
+    }
+    changed
+}
+
+/// Add a parameter to `header` which is always the value of its parameter at `index` times
+/// `scale`, and return it.
+fn add_scaled_induction_variable(
+    func: &mut Function,
+    header: Block,
+    preheader_jump: Inst,
+    back_edges: &[Inst],
+    index: usize,
+    next: Value,
+    step: u64,
+    scale: u64,
+) -> Value {
+    let iv = func.dfg.block_params(header)[index];
+    let ty = func.dfg.value_type(iv);
+    let scaled = func.dfg.append_block_param(header, ty);
+
+    // The initial value is computed in the preheader.
+    let init = func.dfg.insts[preheader_jump].branch_destination(&func.dfg.jump_tables)[0]
+        .args_slice(&func.dfg.value_lists)[index];
+    let mut pos = FuncCursor::new(func).at_inst(preheader_jump);
+    let scale_value = pos.ins().iconst(ty, iconst_imm(ty, scale));
+    let scaled_init = pos.ins().imul(init, scale_value);
+
+    // The increment is computed next to the increment of the original induction variable, which
+    // dominates all back edges.
+    let next_inst = pos.func.dfg.value_def(next).unwrap_inst();
+    let mut pos = pos.after_inst(next_inst);
+    let scaled_step = pos
+        .ins()
+        .iconst(ty, iconst_imm(ty, step.wrapping_mul(scale)));
+    let scaled_next = pos.ins().iadd(scaled, scaled_step);
+
+    let dfg = &mut pos.func.dfg;
+    for &inst in [preheader_jump].iter().chain(back_edges) {
+        let arg = if inst == preheader_jump {
+            scaled_init
+        } else {
+            scaled_next
+        };
+        for dest in dfg.insts[inst].branch_destination_mut(&mut dfg.jump_tables) {
+            if dest.block(&dfg.value_lists) == header {
+                dest.append_argument(arg, &mut dfg.value_lists);
+            }
+        }
+    }
+    scaled
+}
+
+/// Find the blocks defining values which are used in other blocks.
+fn blocks_with_values_used_outside(func: &Function) -> EntitySet<Block> {
+    let mut blocks = EntitySet::with_capacity(func.dfg.num_blocks());
+    for block in func.layout.blocks() {
+        for inst in func.layout.block_insts(block) {
+            for value in func.dfg.inst_values(inst) {
+                let def_block = match func.dfg.value_def(func.dfg.resolve_aliases(value)) {
+                    ValueDef::Result(def, _) => func.layout.inst_block(def),
+                    ValueDef::Param(def, _) => Some(def),
+                    ValueDef::Union(..) => None,
+                };
+                match def_block {
+                    Some(def_block) if def_block != block => {
+                        blocks.insert(def_block);
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+    blocks
+}
+
+/// Unroll `lp` by `factor`, if it is a small loop consisting of a single block.
+///
+/// The copies of the loop body are chained through their back edges, each keeping its own exit
+/// edge.
+fn unroll(
+    func: &mut Function,
+    cfg: &ControlFlowGraph,
+    domtree: &DominatorTree,
+    loop_analysis: &LoopAnalysis,
+    used_outside: &EntitySet<Block>,
+    lp: Loop,
+    factor: u8,
+) -> bool {
+    let header = loop_analysis.loop_header(lp);
+
+    // Values of the body used after the loop would need to be merged from all the copies.
+    if used_outside.contains(header) {
+        return false;
+    }
+
+    // The header must be the only block of the loop, and end in a conditional branch to itself.
+    let mut back_edges = cfg
+        .pred_iter(header)
+        .filter(|pred| is_back_edge(func, domtree, header, pred.inst));
+    match (back_edges.next(), back_edges.next()) {
+        (Some(pred), None) if pred.block == header => {}
+        _ => return false,
+    }
+    let terminator = func.layout.last_inst(header).unwrap();
+    match func.dfg.insts[terminator] {
+        InstructionData::Brif { blocks, .. }
+            if blocks
+                .iter()
+                .filter(|dest| dest.block(&func.dfg.value_lists) == header)
+                .count()
+                == 1 => {}
+        _ => return false,
+    }
+    let body: SmallVec<[Inst; 16]> = func.layout.block_insts(header).collect();
+    if body.len() > UNROLL_MAX_INSTS {
+        return false;
+    }
+
+    let mut copies: SmallVec<[Block; 8]> = SmallVec::new();
+    let mut last = header;
+    for _ in 1..factor {
+        let copy = func.dfg.make_block();
+        func.layout.insert_block_after(copy, last);
+        last = copy;
+
+        let mut values: SecondaryMap<Value, PackedOption<Value>> = SecondaryMap::new();
+        for i in 0..func.dfg.num_block_params(header) {
+            let param = func.dfg.block_params(header)[i];
+            let ty = func.dfg.value_type(param);
+            values[param] = func.dfg.append_block_param(copy, ty).into();
+        }
+
+        for &inst in &body {
+            let new_inst = func.dfg.clone_inst(inst);
+            func.dfg.map_inst_values(new_inst, |dfg, value| {
+                let value = dfg.resolve_aliases(value);
+                values[value].expand().unwrap_or(value)
+            });
+            for i in 0..func.dfg.inst_results(inst).len() {
+                values[func.dfg.inst_results(inst)[i]] = func.dfg.inst_results(new_inst)[i].into();
+            }
+            func.layout.append_inst(new_inst, copy);
+            let srcloc = func.srclocs[inst];
+            if !srcloc.is_default() {
+                func.srclocs[new_inst] = srcloc;
+            }
+        }
+        copies.push(copy);
+    }
+
+    // Chain the back edges: the header branches to the first copy, each copy to the next one, and
+    // the last copy back to the header.
+    let chain: SmallVec<[Block; 8]> = core::iter::once(header)
+        .chain(copies.iter().copied())
+        .collect();
+    for (i, &block) in chain.iter().enumerate() {
+        let target = chain.get(i + 1).copied().unwrap_or(header);
+        let terminator = func.layout.last_inst(block).unwrap();
+        func.rewrite_branch_destination(terminator, header, target);
+    }
+
+    trace!("loop_opts: unrolled {} by {}", header, factor);
+    true
+}
@@ -2000,6 +2000,11 @@ impl<M: ABIMachineSpec> Callee<M> {
             .expect("frame size not computed before prologue generation")
     }
 
+    /// Returns the register holding the return-area pointer, if any.
+    pub fn ret_area_ptr(&self) -> Option<Writable<Reg>> {
+        self.ret_area_ptr
+    }
+
     /// Returns the size of arguments expected on the stack.
     pub fn stack_args_size(&self, sigs: &SigSet) -> u32 {
         sigs[self.sig].sized_stack_arg_space
@@ -1558,7 +1558,10 @@ impl<I: VCodeInst> MachBuffer<I> {
     }
 
     /// Add a call-site record at the current offset.
-    pub fn add_call_site(&mut self, opcode: Opcode) {
+    ///
+    /// `sp_to_fp` is the distance from the stack pointer to the frame pointer
+    /// once the call has returned; it is zero for return calls.
+    pub fn add_call_site(&mut self, opcode: Opcode, sp_to_fp: u32) {
         debug_assert!(
             opcode.is_call(),
             "adding call site info for a non-call instruction."
@@ -1566,6 +1569,7 @@ impl<I: VCodeInst> MachBuffer<I> {
         self.call_sites.push(MachCallSite {
             ret_addr: self.data.len() as CodeOffset,
             opcode,
+            sp_to_fp,
         });
     }
 
@@ -1758,6 +1762,10 @@ pub struct MachCallSite {
     pub ret_addr: CodeOffset,
     /// The call's opcode.
     pub opcode: Opcode,
+    /// The distance from the stack pointer to the frame pointer (on s390x, to
+    /// the caller's stack pointer) at the return address, after any stack
+    /// arguments popped by the callee have been accounted for.
+    pub sp_to_fp: u32,
 }
 
 /// A source-location mapping resulting from a compilation.
@@ -2319,7 +2327,7 @@ mod test {
         buf.put1(2);
         buf.add_trap(TrapCode::IntegerOverflow);
         buf.add_trap(TrapCode::IntegerDivisionByZero);
-        buf.add_call_site(Opcode::Call);
+        buf.add_call_site(Opcode::Call, 16);
         buf.add_reloc(
             Reloc::Abs4,
             &ExternalName::User(UserExternalNameRef::new(0)),
@@ -2350,9 +2358,9 @@ mod test {
         assert_eq!(
             buf.call_sites()
                 .iter()
-                .map(|call_site| (call_site.ret_addr, call_site.opcode))
+                .map(|call_site| (call_site.ret_addr, call_site.opcode, call_site.sp_to_fp))
                 .collect::<Vec<_>>(),
-            vec![(2, Opcode::Call)]
+            vec![(2, Opcode::Call, 16)]
         );
         assert_eq!(
             buf.relocs()
@@ -521,6 +521,9 @@ mod tests {
         let actual = f.to_string();
         let expected = r#"[shared]
 opt_level = "none"
+inlining_callee_size_limit = 32
+inlining_caller_size_log2 = 12
+loop_unroll_factor = 1
 tls_model = "none"
 libcall_call_conv = "isa_default"
 probestack_size_log2 = 12
@@ -529,6 +532,8 @@ bb_padding_log2_minus_one = 0
 regalloc_checker = false
 regalloc_verbose_logs = false
 enable_alias_analysis = true
+enable_inlining = false
+enable_loop_opts = false
 enable_verifier = true
 is_pic = false
 use_colocated_libcalls = false
@@ -53,7 +53,12 @@ pub trait FuncWriter {
 
         for (gv, gv_data) in &func.global_values {
             any = true;
-            self.write_entity_definition(w, func, gv.into(), gv_data)?;
+            self.write_entity_definition(w, func, gv.into(), &gv_data.display(Some(&func.params)))?;
+        }
+
+        for (dt, dt_data) in &func.dfg.dynamic_types {
+            any = true;
+            self.write_entity_definition(w, func, dt.into(), dt_data)?;
         }
 
         for (table, table_data) in &func.tables {
@@ -199,7 +204,24 @@ fn write_spec(w: &mut dyn Write, func: &Function) -> fmt::Result {
 // Basic blocks
 
 fn write_arg(w: &mut dyn Write, func: &Function, arg: Value) -> fmt::Result {
-    write!(w, "{}: {}", arg, func.dfg.value_type(arg))
+    write!(w, "{}: ", arg)?;
+    write_type(w, func, func.dfg.value_type(arg))
+}
+
+/// Write out a value type. Dynamic vector types are written as the dynamic type entity that
+/// declares them, since that is how the text format refers to them.
+fn write_type(w: &mut dyn Write, func: &Function, ty: Type) -> fmt::Result {
+    if ty.is_dynamic_vector() {
+        let dt = func
+            .dfg
+            .dynamic_types
+            .iter()
+            .find(|(_, data)| data.concrete() == Some(ty));
+        if let Some((dt, _)) = dt {
+            return write!(w, "{}", dt);
+        }
+    }
+    write!(w, "{}", ty)
 }
 
 /// Write out the basic block header, outdented:
@@ -354,9 +376,10 @@ fn write_instruction(
     // Then the opcode, possibly with a '.type' suffix.
     let opcode = func.dfg.insts[inst].opcode();
 
-    match type_suffix(func, inst) {
-        Some(suf) => write!(w, "{}.{}", opcode, suf)?,
-        None => write!(w, "{}", opcode)?,
+    write!(w, "{}", opcode)?;
+    if let Some(suf) = type_suffix(func, inst) {
+        write!(w, ".")?;
+        write_type(w, func, suf)?;
     }
 
     write_operands(w, &func.dfg, inst)?;
@@ -375,8 +398,12 @@ pub fn write_operands(w: &mut dyn Write, dfg: &DataFlowGraph, inst: Inst) -> fmt
     let jump_tables = &dfg.jump_tables;
     use crate::ir::instructions::InstructionData::*;
     match dfg.insts[inst] {
-        AtomicRmw { op, args, .. } => write!(w, " {} {}, {}", op, args[0], args[1]),
-        AtomicCas { args, .. } => write!(w, " {}, {}, {}", args[0], args[1], args[2]),
+        AtomicRmw {
+            flags, op, args, ..
+        } => write!(w, "{} {} {}, {}", flags, op, args[0], args[1]),
+        AtomicCas { flags, args, .. } => {
+            write!(w, "{} {}, {}, {}", flags, args[0], args[1], args[2])
+        }
         LoadNoOffset { flags, arg, .. } => write!(w, "{} {}", flags, arg),
         StoreNoOffset { flags, args, .. } => write!(w, "{} {}, {}", flags, args[0], args[1]),
         Unary { arg, .. } => write!(w, " {}", arg),
@@ -39,8 +39,10 @@ mod test_cat;
 mod test_compile;
 mod test_dce;
 mod test_domtree;
+mod test_inline;
 mod test_interpret;
 mod test_legalizer;
+mod test_loop_opts;
 mod test_optimize;
 mod test_print_cfg;
 mod test_run;
@@ -109,8 +111,10 @@ fn new_subtest(parsed: &TestCommand) -> anyhow::Result<Box<dyn subtest::SubTest>
         "compile" => test_compile::subtest(parsed),
         "dce" => test_dce::subtest(parsed),
         "domtree" => test_domtree::subtest(parsed),
+        "inline" => test_inline::subtest(parsed),
         "interpret" => test_interpret::subtest(parsed),
         "legalizer" => test_legalizer::subtest(parsed),
+        "loop-opts" => test_loop_opts::subtest(parsed),
         "optimize" => test_optimize::subtest(parsed),
         "print-cfg" => test_print_cfg::subtest(parsed),
         "run" => test_run::subtest(parsed),
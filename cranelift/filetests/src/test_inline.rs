@@ -0,0 +1,106 @@
+//! Test command for testing the inlining pass.
+//!
+//! The `inline` test command runs each function through the inlining pass, using the other
+//! functions in the same file as the bodies of callees. Calls are resolved by name: a call to
+//! `%foo` is inlined with the body of `function %foo`, subject to the inlining settings.
+//!
+//! The resulting function is sent to `filecheck`.
+
+use crate::runone::FileUpdate;
+use crate::subtest::{run_filecheck, Context, SubTest};
+use anyhow::Context as _;
+use cranelift_codegen::ir::{self, ExternalName, Function, UserFuncName};
+use cranelift_codegen::isa::TargetIsa;
+use cranelift_codegen::settings::Flags;
+use cranelift_codegen::{self, Inline, InlineCommand};
+use cranelift_reader::{TestCommand, TestFile};
+use log::info;
+use std::borrow::Cow;
+
+struct TestInline;
+
+pub fn subtest(parsed: &TestCommand) -> anyhow::Result<Box<dyn SubTest>> {
+    assert_eq!(parsed.command, "inline");
+    if !parsed.options.is_empty() {
+        anyhow::bail!("No options allowed on {}", parsed);
+    }
+    Ok(Box::new(TestInline))
+}
+
+impl SubTest for TestInline {
+    fn name(&self) -> &'static str {
+        "inline"
+    }
+
+    fn is_mutating(&self) -> bool {
+        true
+    }
+
+    fn run_target<'a>(
+        &self,
+        testfile: &TestFile,
+        file_update: &mut FileUpdate,
+        file_path: &'a str,
+        flags: &'a Flags,
+        isa: Option<&'a dyn TargetIsa>,
+    ) -> anyhow::Result<()> {
+        let mut inliner = FunctionsInFile {
+            functions: testfile.functions.iter().map(|(func, _)| func).collect(),
+        };
+
+        for (func, details) in &testfile.functions {
+            info!("Test: {}({})", self.name(), func.name);
+
+            let context = Context {
+                preamble_comments: &testfile.preamble_comments,
+                details,
+                flags,
+                isa,
+                file_path,
+                file_update,
+            };
+
+            let mut comp_ctx = cranelift_codegen::Context::for_function(func.clone());
+            comp_ctx
+                .inline(context.flags_or_isa(), &mut inliner)
+                .map_err(|e| crate::pretty_anyhow_error(&comp_ctx.func, e))
+                .context(self.name())?;
+
+            let text = comp_ctx.func.display().to_string();
+            run_filecheck(&text, &context).context(self.name())?;
+        }
+
+        Ok(())
+    }
+
+    fn run(&self, _func: Cow<Function>, _context: &Context) -> anyhow::Result<()> {
+        unreachable!()
+    }
+}
+
+/// Provides callee bodies from the functions defined in the test file.
+struct FunctionsInFile<'a> {
+    functions: Vec<&'a Function>,
+}
+
+impl Inline for FunctionsInFile<'_> {
+    fn inline(
+        &mut self,
+        caller: &Function,
+        _call_inst: ir::Inst,
+        callee: ir::FuncRef,
+        _call_args: &[ir::Value],
+    ) -> InlineCommand<'_> {
+        let name = match &caller.dfg.ext_funcs[callee].name {
+            ExternalName::TestCase(name) => UserFuncName::Testcase(name.clone()),
+            ExternalName::User(name_ref) => {
+                UserFuncName::User(caller.params.user_named_funcs()[*name_ref].clone())
+            }
+            _ => return InlineCommand::KeepCall,
+        };
+        match self.functions.iter().find(|func| func.name == name) {
+            Some(func) => InlineCommand::Inline(Cow::Borrowed(*func)),
+            None => InlineCommand::KeepCall,
+        }
+    }
+}
@@ -48,7 +48,7 @@ impl SubTest for TestInterpret {
         _: &mut FileUpdate,
         _: &'a str,
         _: &'a Flags,
-        _: Option<&'a dyn TargetIsa>,
+        isa: Option<&'a dyn TargetIsa>,
     ) -> anyhow::Result<()> {
         // We can build the FunctionStore once and reuse it
         let mut func_store = FunctionStore::default();
@@ -56,10 +56,13 @@ impl SubTest for TestInterpret {
             func_store.add(func.name.to_string(), &func);
         }
 
+        // Interpret stack addresses with the target's pointer size, if there is a target.
+        let pointer_type = isa.map_or(ir::types::I64, |isa| isa.pointer_type());
+
         for (func, details) in &testfile.functions {
             info!("Test: {}({}) interpreter", self.name(), func.name);
 
-            run_test(&func_store, func, details).context(self.name())?;
+            run_test(&func_store, func, details, pointer_type).context(self.name())?;
         }
 
         Ok(())
@@ -74,7 +77,12 @@ impl SubTest for TestInterpret {
     }
 }
 
-fn run_test(func_store: &FunctionStore, func: &Function, details: &Details) -> anyhow::Result<()> {
+fn run_test(
+    func_store: &FunctionStore,
+    func: &Function,
+    details: &Details,
+    pointer_type: ir::Type,
+) -> anyhow::Result<()> {
     for comment in details.comments.iter() {
         if let Some(command) = parse_run_command(comment.text, &func.signature)? {
             trace!("Parsed run command: {}", command);
@@ -83,7 +91,9 @@ fn run_test(func_store: &FunctionStore, func: &Function, details: &Details) -> a
                 .run(|func_name, run_args| {
                     // Rebuild the interpreter state on every run to ensure that we don't accidentally depend on
                     // some leftover state
-                    let state = InterpreterState::default().with_function_store(func_store.clone());
+                    let state = InterpreterState::default()
+                        .with_function_store(func_store.clone())
+                        .with_pointer_type(pointer_type);
 
                     let mut args = Vec::with_capacity(run_args.len());
                     args.extend_from_slice(run_args);
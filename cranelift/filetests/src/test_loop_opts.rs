@@ -0,0 +1,44 @@
+//! Test command for testing the loop optimization pass.
+//!
+//! The `loop-opts` test command runs each function through the loop optimization pass, which
+//! inserts loop preheaders, hoists loop-invariant guards, reduces the strength of derived
+//! induction variables and, when `loop_unroll_factor` is set, unrolls small loops.
+//!
+//! The resulting function is sent to `filecheck`.
+
+use crate::subtest::{run_filecheck, Context, SubTest};
+use cranelift_codegen;
+use cranelift_codegen::ir::Function;
+use cranelift_reader::TestCommand;
+use std::borrow::Cow;
+
+struct TestLoopOpts;
+
+pub fn subtest(parsed: &TestCommand) -> anyhow::Result<Box<dyn SubTest>> {
+    assert_eq!(parsed.command, "loop-opts");
+    if !parsed.options.is_empty() {
+        anyhow::bail!("No options allowed on {}", parsed);
+    }
+    Ok(Box::new(TestLoopOpts))
+}
+
+impl SubTest for TestLoopOpts {
+    fn name(&self) -> &'static str {
+        "loop-opts"
+    }
+
+    fn is_mutating(&self) -> bool {
+        true
+    }
+
+    fn run(&self, func: Cow<Function>, context: &Context) -> anyhow::Result<()> {
+        let mut comp_ctx = cranelift_codegen::Context::for_function(func.into_owned());
+
+        comp_ctx
+            .loop_opts(context.flags_or_isa())
+            .map_err(|e| crate::pretty_anyhow_error(&comp_ctx.func, Into::into(e)))?;
+
+        let text = comp_ctx.func.display().to_string();
+        run_filecheck(&text, context)
+    }
+}
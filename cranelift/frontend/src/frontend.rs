@@ -26,6 +26,7 @@ pub struct FunctionBuilderContext {
     ssa: SSABuilder,
     status: SecondaryMap<Block, BlockStatus>,
     types: SecondaryMap<Variable, Type>,
+    var_labels: SecondaryMap<Variable, PackedOption<ValueLabel>>,
 }
 
 /// Temporary object used to build a single Cranelift IR `Function`.
@@ -63,10 +64,14 @@ impl FunctionBuilderContext {
         self.ssa.clear();
         self.status.clear();
         self.types.clear();
+        self.var_labels.clear();
     }
 
     fn is_empty(&self) -> bool {
-        self.ssa.is_empty() && self.status.is_empty() && self.types.is_empty()
+        self.ssa.is_empty()
+            && self.status.is_empty()
+            && self.types.is_empty()
+            && self.var_labels.is_empty()
     }
 }
 
@@ -311,6 +316,11 @@ impl<'a> FunctionBuilder<'a> {
         self.srcloc = srcloc;
     }
 
+    /// Get the source location that is assigned to all new instructions.
+    pub fn srcloc(&self) -> ir::SourceLoc {
+        self.srcloc
+    }
+
     /// Creates a new `Block` and returns its reference.
     pub fn create_block(&mut self) -> Block {
         let block = self.func.dfg.make_block();
@@ -422,6 +432,18 @@ impl<'a> FunctionBuilder<'a> {
                 .use_var(self.func, var, ty, self.position.unwrap())
         };
         self.handle_ssa_side_effects(side_effects);
+
+        // The value may be a block parameter that SSA construction just introduced for this
+        // variable, so make sure it carries the variable's label too.
+        if let Some(label) = self.func_ctx.var_labels[var].expand() {
+            let unlabeled = matches!(
+                &self.func.dfg.values_labels,
+                Some(values_labels) if !values_labels.contains_key(&val)
+            );
+            if unlabeled {
+                self.set_val_label(val, label);
+            }
+        }
         Ok(val)
     }
 
@@ -450,6 +472,9 @@ impl<'a> FunctionBuilder<'a> {
         }
 
         self.func_ctx.ssa.def_var(var, val, self.position.unwrap());
+        if let Some(label) = self.func_ctx.var_labels[var].expand() {
+            self.set_val_label(val, label);
+        }
         Ok(())
     }
 
@@ -473,6 +498,16 @@ impl<'a> FunctionBuilder<'a> {
             })
     }
 
+    /// Set the label of a variable, so that every value defined for it gets that label, as if
+    /// [`FunctionBuilder::set_val_label`] was called on it. This includes the block parameters
+    /// introduced for the variable while building SSA form.
+    ///
+    /// This lets debug info describe where a source-level variable lives, and will not do
+    /// anything unless `func.dfg.collect_debug_info` is called first.
+    pub fn set_var_label(&mut self, var: Variable, label: ValueLabel) {
+        self.func_ctx.var_labels[var] = label.into();
+    }
+
     /// Set label for Value
     ///
     /// This will not do anything unless `func.dfg.collect_debug_info` is called first.
@@ -1118,7 +1153,10 @@ mod tests {
     use cranelift_codegen::entity::EntityRef;
     use cranelift_codegen::ir::condcodes::IntCC;
     use cranelift_codegen::ir::{types::*, UserFuncName};
-    use cranelift_codegen::ir::{AbiParam, Function, InstBuilder, MemFlags, Signature, Value};
+    use cranelift_codegen::ir::{
+        AbiParam, Function, InstBuilder, MemFlags, Signature, Value, ValueLabel,
+        ValueLabelAssignments,
+    };
     use cranelift_codegen::isa::{CallConv, TargetFrontendConfig, TargetIsa};
     use cranelift_codegen::settings;
     use cranelift_codegen::verifier::verify_function;
@@ -1233,6 +1271,64 @@ mod tests {
         sample_function(true)
     }
 
+    #[test]
+    fn var_labels() {
+        let mut sig = Signature::new(CallConv::SystemV);
+        sig.params.push(AbiParam::new(I32));
+
+        let mut fn_ctx = FunctionBuilderContext::new();
+        let mut func = Function::with_name_signature(UserFuncName::testcase("sample"), sig);
+        func.collect_debug_info();
+        let (param, header_param, sum) = {
+            let mut builder = FunctionBuilder::new(&mut func, &mut fn_ctx);
+
+            let block0 = builder.create_block();
+            let block1 = builder.create_block();
+            let block2 = builder.create_block();
+            let x = Variable::new(0);
+            let label = ValueLabel::new(7);
+            builder.declare_var(x, I32);
+            builder.set_var_label(x, label);
+            builder.append_block_params_for_function_params(block0);
+
+            builder.switch_to_block(block0);
+            builder.seal_block(block0);
+            let param = builder.block_params(block0)[0];
+            builder.def_var(x, param);
+            builder.ins().jump(block1, &[]);
+
+            builder.switch_to_block(block1);
+            let header_param = builder.use_var(x);
+            let one = builder.ins().iconst(I32, 1);
+            let sum = builder.ins().iadd(header_param, one);
+            builder.def_var(x, sum);
+            builder.ins().brif(sum, block1, &[], block2, &[]);
+            builder.seal_block(block1);
+
+            builder.switch_to_block(block2);
+            builder.seal_block(block2);
+            builder.ins().return_(&[]);
+            builder.finalize();
+            (param, header_param, sum)
+        };
+
+        assert_eq!(
+            func.dfg.block_params(func.layout.blocks().nth(1).unwrap()),
+            &[header_param]
+        );
+        let values_labels = func.dfg.values_labels.as_ref().unwrap();
+        for value in [param, header_param, sum] {
+            match &values_labels[&value] {
+                ValueLabelAssignments::Starts(starts) => {
+                    assert_eq!(starts.len(), 1);
+                    assert_eq!(starts[0].label, ValueLabel::new(7));
+                }
+                _ => panic!("unexpected label assignment for {}", value),
+            }
+        }
+        assert_eq!(values_labels.len(), 3);
+    }
+
     #[track_caller]
     fn check(func: &Function, expected_ir: &str) {
         let actual_ir = func.display().to_string();
@@ -887,6 +887,7 @@ static OPCODE_SIGNATURES: Lazy<Vec<OpcodeSignature>> = Lazy::new(|| {
                 (Opcode::GetFramePointer),
                 (Opcode::GetStackPointer),
                 (Opcode::GetReturnAddress),
+                (Opcode::StackSwitch),
                 (Opcode::TableAddr),
                 (Opcode::Null),
                 (Opcode::X86Blendv),
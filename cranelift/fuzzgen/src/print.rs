@@ -2,6 +2,7 @@ use cranelift::codegen::data_value::DataValue;
 use cranelift::codegen::ir::Function;
 use cranelift::prelude::settings::{self, SettingKind};
 use cranelift::prelude::*;
+use cranelift_reader::ParseOptions;
 use std::fmt;
 
 use crate::TestCaseInput;
@@ -118,8 +119,11 @@ impl<'a> fmt::Debug for PrintableTestCase<'a> {
 }
 
 /// Print only non default flags.
+///
+/// The defaults are the ones the reader starts out with, which is not quite the same as
+/// `settings::builder()`, so that the printed test case parses back into the same flags.
 fn write_non_default_flags(f: &mut fmt::Formatter<'_>, flags: &settings::Flags) -> fmt::Result {
-    let default_flags = settings::Flags::new(settings::builder());
+    let default_flags = settings::Flags::new(ParseOptions::default().flag_builder());
     for (default, flag) in default_flags.iter().zip(flags.iter()) {
         assert_eq!(default.name, flag.name);
 
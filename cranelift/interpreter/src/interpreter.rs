@@ -11,8 +11,8 @@ use crate::step::{step, ControlFlow, StepError};
 use crate::value::{DataValueExt, ValueError};
 use cranelift_codegen::data_value::DataValue;
 use cranelift_codegen::ir::{
-    ArgumentPurpose, Block, Endianness, ExternalName, FuncRef, Function, GlobalValue,
-    GlobalValueData, LibCall, MemFlags, StackSlot, TrapCode, Type,
+    types, ArgumentPurpose, Block, DynamicStackSlot, Endianness, ExternalName, FuncRef, Function,
+    GlobalValue, GlobalValueData, KnownSymbol, LibCall, MemFlags, StackSlot, TrapCode, Type,
 };
 use log::trace;
 use smallvec::SmallVec;
@@ -194,11 +194,15 @@ pub enum InterpreterError {
 
 pub type LibCallValues = SmallVec<[DataValue; 1]>;
 pub type LibCallHandler = fn(LibCall, LibCallValues) -> Result<LibCallValues, TrapCode>;
+pub type KnownSymbolHandler = fn(KnownSymbol, LibCallValues) -> Result<LibCallValues, TrapCode>;
 
 /// Maintains the [Interpreter]'s state, implementing the [State] trait.
 pub struct InterpreterState<'a> {
     pub functions: FunctionStore<'a>,
     pub libcall_handler: LibCallHandler,
+    pub known_symbol_handler: KnownSymbolHandler,
+    /// The type of pointers on the interpreted target, used for the addresses of stack slots
+    pub pointer_type: Type,
     pub frame_stack: Vec<Frame<'a>>,
     /// Number of bytes from the bottom of the stack where the current frame's stack space is
     pub frame_offset: usize,
@@ -217,6 +221,8 @@ impl Default for InterpreterState<'_> {
         Self {
             functions: FunctionStore::default(),
             libcall_handler: |_, _| Err(TrapCode::UnreachableCodeReached),
+            known_symbol_handler: |_, _| Err(TrapCode::UnreachableCodeReached),
+            pointer_type: types::I64,
             frame_stack: vec![],
             frame_offset: 0,
             stack: Vec::with_capacity(1024),
@@ -226,6 +232,28 @@ impl Default for InterpreterState<'_> {
     }
 }
 
+/// The number of bytes a dynamic stack slot occupies. Dynamic vectors are interpreted with a
+/// scale of one, so this is the size of the slot's base vector type.
+fn dynamic_stack_slot_size(function: &Function, slot: DynamicStackSlot) -> u64 {
+    let dyn_ty = function.dynamic_stack_slots[slot].dyn_ty;
+    let ty = function
+        .get_concrete_dynamic_ty(dyn_ty)
+        .and_then(|ty| ty.dynamic_to_vector())
+        .expect("a dynamic vector type");
+    ty.bytes() as u64
+}
+
+/// The number of bytes of stack space needed by a frame of `function`: all of its sized stack
+/// slots, followed by all of its dynamic stack slots.
+fn frame_size(function: &Function) -> usize {
+    let dynamic_size: u64 = function
+        .dynamic_stack_slots
+        .keys()
+        .map(|k| dynamic_stack_slot_size(function, k))
+        .sum();
+    function.fixed_stack_size() as usize + dynamic_size as usize
+}
+
 impl<'a> InterpreterState<'a> {
     pub fn with_function_store(self, functions: FunctionStore<'a>) -> Self {
         Self { functions, ..self }
@@ -236,6 +264,20 @@ impl<'a> InterpreterState<'a> {
         self.libcall_handler = handler;
         self
     }
+
+    /// Registers a handler for calls to known symbols
+    pub fn with_known_symbol_handler(mut self, handler: KnownSymbolHandler) -> Self {
+        self.known_symbol_handler = handler;
+        self
+    }
+
+    /// Sets the type of pointers on the interpreted target, which defaults to `i64`
+    pub fn with_pointer_type(self, pointer_type: Type) -> Self {
+        Self {
+            pointer_type,
+            ..self
+        }
+    }
 }
 
 impl<'a> State<'a> for InterpreterState<'a> {
@@ -251,14 +293,22 @@ impl<'a> State<'a> for InterpreterState<'a> {
         self.libcall_handler
     }
 
+    fn get_known_symbol_handler(&self) -> KnownSymbolHandler {
+        self.known_symbol_handler
+    }
+
+    fn pointer_type(&self) -> Type {
+        self.pointer_type
+    }
+
     fn push_frame(&mut self, function: &'a Function) {
         if let Some(frame) = self.frame_stack.iter().last() {
-            self.frame_offset += frame.function().fixed_stack_size() as usize;
+            self.frame_offset += frame_size(frame.function());
         }
 
         // Grow the stack by the space necessary for this frame
         self.stack
-            .extend(iter::repeat(0).take(function.fixed_stack_size() as usize));
+            .extend(iter::repeat(0).take(frame_size(function)));
 
         self.frame_stack.push(Frame::new(function));
     }
@@ -266,11 +316,11 @@ impl<'a> State<'a> for InterpreterState<'a> {
         if let Some(frame) = self.frame_stack.pop() {
             // Shorten the stack after exiting the frame
             self.stack
-                .truncate(self.stack.len() - frame.function().fixed_stack_size() as usize);
+                .truncate(self.stack.len() - frame_size(frame.function()));
 
             // Reset frame_offset to the start of this function
             if let Some(frame) = self.frame_stack.iter().last() {
-                self.frame_offset -= frame.function().fixed_stack_size() as usize;
+                self.frame_offset -= frame_size(frame.function());
             }
         }
     }
@@ -319,6 +369,26 @@ impl<'a> State<'a> for InterpreterState<'a> {
         Address::from_parts(size, AddressRegion::Stack, 0, final_offset)
     }
 
+    fn dynamic_stack_address(
+        &self,
+        size: AddressSize,
+        slot: DynamicStackSlot,
+    ) -> Result<Address, MemoryError> {
+        let function = self.get_current_function();
+
+        // Dynamic stack slots are laid out after all of the sized stack slots
+        let slot_offset: u64 = function
+            .dynamic_stack_slots
+            .keys()
+            .filter(|k| k < &slot)
+            .map(|k| dynamic_stack_slot_size(function, k))
+            .sum();
+
+        let final_offset =
+            self.frame_offset as u64 + function.fixed_stack_size() as u64 + slot_offset;
+        Address::from_parts(size, AddressRegion::Stack, 0, final_offset)
+    }
+
     fn checked_load(
         &self,
         addr: Address,
@@ -518,7 +588,10 @@ impl<'a> State<'a> for InterpreterState<'a> {
                         action_stack.push(ResolveAction::Resolve(base));
                     }
                     GlobalValueData::Symbol { .. } => unimplemented!(),
-                    GlobalValueData::DynScaleTargetConst { .. } => unimplemented!(),
+                    // Dynamic vectors are interpreted as holding exactly one fixed-width vector.
+                    GlobalValueData::DynScaleTargetConst { .. } => {
+                        current_val = DataValue::I64(1);
+                    }
                 },
                 Some(ResolveAction::Add(dv)) => {
                     current_val = current_val
@@ -556,7 +629,7 @@ mod tests {
     use super::*;
     use crate::step::CraneliftTrap;
     use cranelift_codegen::ir::immediates::Ieee32;
-    use cranelift_codegen::ir::TrapCode;
+    use cranelift_codegen::ir::{Opcode, TrapCode};
     use cranelift_reader::parse_functions;
     use smallvec::smallvec;
 
@@ -967,6 +1040,58 @@ mod tests {
         )
     }
 
+    #[test]
+    fn known_symbol() {
+        let code = "function %test() -> i64 {
+            fn0 = %ElfGlobalOffsetTable () -> i64
+        block0:
+            v0 = call fn0()
+            return v0
+        }";
+
+        let func = parse_functions(code).unwrap().into_iter().next().unwrap();
+        let mut env = FunctionStore::default();
+        env.add(func.name.to_string(), &func);
+        let state = InterpreterState::default()
+            .with_function_store(env)
+            .with_known_symbol_handler(|symbol, args| {
+                Ok(smallvec![match (symbol, &args[..]) {
+                    (KnownSymbol::ElfGlobalOffsetTable, []) => DataValue::I64(0x1000),
+                    _ => panic!("Unexpected args"),
+                }])
+            });
+
+        let result = Interpreter::new(state).call_by_name("%test", &[]).unwrap();
+
+        assert_eq!(
+            result,
+            ControlFlow::Return(smallvec![DataValue::I64(0x1000)])
+        );
+    }
+
+    #[test]
+    fn stack_switch_errors() {
+        let code = "function %test(i64) -> i64 {
+        block0(v0: i64):
+            v1 = stack_switch v0, v0, v0
+            return v1
+        }";
+
+        let func = parse_functions(code).unwrap().into_iter().next().unwrap();
+        let mut env = FunctionStore::default();
+        env.add(func.name.to_string(), &func);
+        let state = InterpreterState::default().with_function_store(env);
+
+        let result = Interpreter::new(state).call_by_name("%test", &[DataValue::I64(0)]);
+
+        assert!(matches!(
+            result,
+            Err(InterpreterError::StepError(StepError::UnsupportedOpcode(
+                Opcode::StackSwitch
+            )))
+        ));
+    }
+
     #[test]
     fn misaligned_store_traps() {
         let code = "
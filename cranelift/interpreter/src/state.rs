@@ -2,11 +2,11 @@
 //! ways this can happen.
 use crate::address::{Address, AddressSize};
 use crate::frame::Frame;
-use crate::interpreter::LibCallHandler;
+use crate::interpreter::{KnownSymbolHandler, LibCallHandler};
 use cranelift_codegen::data_value::DataValue;
 use cranelift_codegen::ir::{
-    types, ExternalName, FuncRef, Function, GlobalValue, LibCall, MemFlags, Signature, StackSlot,
-    Type, Value,
+    types, DynamicStackSlot, ExternalName, FuncRef, Function, GlobalValue, KnownSymbol, LibCall,
+    MemFlags, Signature, StackSlot, Type, Value,
 };
 use cranelift_codegen::isa::CallConv;
 use smallvec::SmallVec;
@@ -27,6 +27,10 @@ pub trait State<'a> {
     fn get_current_function(&self) -> &'a Function;
     /// Retrieve the handler callback for a [LibCall](cranelift_codegen::ir::LibCall)
     fn get_libcall_handler(&self) -> LibCallHandler;
+    /// Retrieve the handler callback for a [KnownSymbol](cranelift_codegen::ir::KnownSymbol)
+    fn get_known_symbol_handler(&self) -> KnownSymbolHandler;
+    /// The type of pointers on the interpreted target.
+    fn pointer_type(&self) -> Type;
 
     /// Record that an interpreter has called into a new [Function].
     fn push_frame(&mut self, function: &'a Function);
@@ -49,6 +53,12 @@ pub trait State<'a> {
         slot: StackSlot,
         offset: u64,
     ) -> Result<Address, MemoryError>;
+    /// Computes the stack address for this dynamic stack slot.
+    fn dynamic_stack_address(
+        &self,
+        size: AddressSize,
+        slot: DynamicStackSlot,
+    ) -> Result<Address, MemoryError>;
     /// Retrieve a value `V` from memory at the given `address`, checking if it belongs either to the
     /// stack or to one of the heaps; the number of bytes loaded corresponds to the specified [Type].
     fn checked_load(
@@ -89,6 +99,8 @@ pub trait State<'a> {
 pub enum InterpreterFunctionRef<'a> {
     Function(&'a Function),
     LibCall(LibCall),
+    /// A known symbol, called with the signature it was declared with.
+    KnownSymbol(KnownSymbol, &'a Signature),
 }
 
 impl<'a> InterpreterFunctionRef<'a> {
@@ -98,6 +110,7 @@ impl<'a> InterpreterFunctionRef<'a> {
             // CallConv here is sort of irrelevant, since we don't use it for anything
             // FIXME handle non-64bit systems
             InterpreterFunctionRef::LibCall(lc) => lc.signature(CallConv::SystemV, types::I64),
+            InterpreterFunctionRef::KnownSymbol(_, signature) => (*signature).clone(),
         }
     }
 }
@@ -30,6 +30,8 @@ fn validate_signature_params(sig: &[AbiParam], args: &[DataValue]) -> bool {
             //
             // Ideally the user has run the verifier and caught this properly...
             (a, b) if a.is_vector() && b.is_vector() => true,
+            // References are represented as plain integers of the same width.
+            (a, b) if b.is_ref() => a.is_int() && a.bits() == b.bits(),
             (a, b) => a == b,
         })
 }
@@ -63,6 +65,14 @@ where
         }
     );
 
+    // Dynamic vectors are interpreted with a scale of one (see `DynScaleTargetConst`), which makes
+    // them behave exactly like their fixed-width counterparts.
+    let ctrl_ty = if ctrl_ty.is_dynamic_vector() {
+        ctrl_ty.dynamic_to_vector().unwrap()
+    } else {
+        ctrl_ty
+    };
+
     // The following closures make the `step` implementation much easier to express. Note that they
     // frequently close over the `state` or `inst_context` for brevity.
 
@@ -195,7 +205,6 @@ where
     // instruction's results.
     let unary =
         |op: fn(DataValue) -> ValueResult<DataValue>, arg: DataValue| -> ValueResult<ControlFlow> {
-            let ctrl_ty = inst_context.controlling_type().unwrap();
             let res = unary_arith(arg, ctrl_ty, op)?;
             Ok(assign(res))
         };
@@ -206,7 +215,6 @@ where
                   left: DataValue,
                   right: DataValue|
      -> ValueResult<ControlFlow> {
-        let ctrl_ty = inst_context.controlling_type().unwrap();
         let res = binary_arith(left, right, ctrl_ty, op)?;
         Ok(assign(res))
     };
@@ -216,7 +224,6 @@ where
                            left: DataValue,
                            right: DataValue|
      -> ValueResult<ControlFlow> {
-        let ctrl_ty = inst_context.controlling_type().unwrap();
         let res = binary_arith(left, right, ctrl_ty, op);
         assign_or_trap(res)
     };
@@ -275,8 +282,8 @@ where
                 )));
             }
 
-            Ok(match func_ref {
-                InterpreterFunctionRef::Function(func) => make_ctrl_flow(func, args),
+            let res = match func_ref {
+                InterpreterFunctionRef::Function(func) => return Ok(make_ctrl_flow(func, args)),
                 InterpreterFunctionRef::LibCall(libcall) => {
                     debug_assert!(
                         !matches!(
@@ -288,20 +295,35 @@ where
                     let libcall_handler = state.get_libcall_handler();
 
                     // We don't transfer control to a libcall, we just execute it and return the results
-                    let res = libcall_handler(libcall, args);
-                    let res = match res {
-                        Err(trap) => return Ok(ControlFlow::Trap(CraneliftTrap::User(trap))),
-                        Ok(rets) => rets,
-                    };
+                    libcall_handler(libcall, args)
+                }
+                InterpreterFunctionRef::KnownSymbol(symbol, _) => {
+                    debug_assert!(
+                        !matches!(
+                            inst.opcode(),
+                            Opcode::ReturnCall | Opcode::ReturnCallIndirect,
+                        ),
+                        "Cannot tail call to known symbols"
+                    );
+                    let known_symbol_handler = state.get_known_symbol_handler();
 
-                    // Check that what the handler returned is what we expect.
-                    if validate_signature_params(&signature.returns[..], &res[..]) {
-                        ControlFlow::Assign(res)
-                    } else {
-                        ControlFlow::Trap(CraneliftTrap::User(TrapCode::BadSignature))
-                    }
+                    // Like libcalls, known symbols are executed by the handler in place
+                    known_symbol_handler(symbol, args)
                 }
-            })
+            };
+            let res = match res {
+                Err(trap) => return Ok(ControlFlow::Trap(CraneliftTrap::User(trap))),
+                Ok(rets) => rets,
+            };
+
+            // Check that what the handler returned is what we expect.
+            Ok(
+                if validate_signature_params(&signature.returns[..], &res[..]) {
+                    ControlFlow::Assign(res)
+                } else {
+                    ControlFlow::Trap(CraneliftTrap::User(TrapCode::BadSignature))
+                },
+            )
         };
 
     // Interpret a Cranelift instruction.
@@ -380,7 +402,10 @@ where
                     InterpreterFunctionRef::Function(function)
                 }
                 ExternalName::LibCall(libcall) => InterpreterFunctionRef::LibCall(libcall),
-                ExternalName::KnownSymbol(_) => unimplemented!(),
+                ExternalName::KnownSymbol(symbol) => InterpreterFunctionRef::KnownSymbol(
+                    symbol,
+                    &curr_func.dfg.signatures[ext_data.signature],
+                ),
             };
 
             let make_control_flow = match inst.opcode() {
@@ -446,7 +471,6 @@ where
         | Opcode::Sload16x4
         | Opcode::Uload32x2
         | Opcode::Sload32x2 => {
-            let ctrl_ty = inst_context.controlling_type().unwrap();
             let (load_ty, kind) = match inst.opcode() {
                 Opcode::Load => (ctrl_ty, None),
                 Opcode::Uload8 => (types::I8, Some(ValueConversionKind::ZeroExtend(ctrl_ty))),
@@ -455,12 +479,30 @@ where
                 Opcode::Sload16 => (types::I16, Some(ValueConversionKind::SignExtend(ctrl_ty))),
                 Opcode::Uload32 => (types::I32, Some(ValueConversionKind::ZeroExtend(ctrl_ty))),
                 Opcode::Sload32 => (types::I32, Some(ValueConversionKind::SignExtend(ctrl_ty))),
-                Opcode::Uload8x8
-                | Opcode::Sload8x8
-                | Opcode::Uload16x4
-                | Opcode::Sload16x4
-                | Opcode::Uload32x2
-                | Opcode::Sload32x2 => unimplemented!(),
+                Opcode::Uload8x8 => (
+                    types::I8X8,
+                    Some(ValueConversionKind::ZeroExtend(types::I16)),
+                ),
+                Opcode::Sload8x8 => (
+                    types::I8X8,
+                    Some(ValueConversionKind::SignExtend(types::I16)),
+                ),
+                Opcode::Uload16x4 => (
+                    types::I16X4,
+                    Some(ValueConversionKind::ZeroExtend(types::I32)),
+                ),
+                Opcode::Sload16x4 => (
+                    types::I16X4,
+                    Some(ValueConversionKind::SignExtend(types::I32)),
+                ),
+                Opcode::Uload32x2 => (
+                    types::I32X2,
+                    Some(ValueConversionKind::ZeroExtend(types::I64)),
+                ),
+                Opcode::Sload32x2 => (
+                    types::I32X2,
+                    Some(ValueConversionKind::SignExtend(types::I64)),
+                ),
                 _ => unreachable!(),
             };
 
@@ -472,6 +514,18 @@ where
             );
 
             match (loaded, kind) {
+                // Extending vector loads widen each of the loaded lanes individually.
+                (ControlFlow::Assign(ret), Some(c)) if load_ty.is_vector() => ControlFlow::Assign(
+                    ret.into_iter()
+                        .map(|loaded| {
+                            let lanes = extractlanes(&loaded, load_ty)?
+                                .into_iter()
+                                .map(|lane| lane.convert(c.clone()))
+                                .collect::<ValueResult<SimdVec<_>>>()?;
+                            vectorizelanes(&lanes, load_ty.double_width().unwrap())
+                        })
+                        .collect::<ValueResult<SmallVec<[DataValue; 1]>>>()?,
+                ),
                 (ControlFlow::Assign(ret), Some(c)) => ControlFlow::Assign(
                     ret.into_iter()
                         .map(|loaded| loaded.convert(c.clone()))
@@ -507,8 +561,8 @@ where
             let offset = sum_unsigned(imm(), args())? as u64;
             let mem_flags = MemFlags::new();
             assign_or_memtrap({
-                state
-                    .stack_address(AddressSize::_64, slot, offset)
+                AddressSize::try_from(state.pointer_type())
+                    .and_then(|addr_size| state.stack_address(addr_size, slot, offset))
                     .and_then(|addr| state.checked_load(addr, load_ty, mem_flags))
             })
         }
@@ -518,8 +572,8 @@ where
             let offset = sum_unsigned(imm(), args_range(1..)?)? as u64;
             let mem_flags = MemFlags::new();
             continue_or_memtrap({
-                state
-                    .stack_address(AddressSize::_64, slot, offset)
+                AddressSize::try_from(state.pointer_type())
+                    .and_then(|addr_size| state.stack_address(addr_size, slot, offset))
                     .and_then(|addr| state.checked_store(addr, arg, mem_flags))
             })
         }
@@ -535,9 +589,56 @@ where
                 })
             })
         }
-        Opcode::DynamicStackAddr => unimplemented!("DynamicStackSlot"),
-        Opcode::DynamicStackLoad => unimplemented!("DynamicStackLoad"),
-        Opcode::DynamicStackStore => unimplemented!("DynamicStackStore"),
+        Opcode::DynamicStackAddr => {
+            let slot = if let InstructionData::DynamicStackLoad {
+                dynamic_stack_slot, ..
+            } = inst
+            {
+                dynamic_stack_slot
+            } else {
+                unreachable!()
+            };
+            assign_or_memtrap({
+                AddressSize::try_from(ctrl_ty).and_then(|addr_size| {
+                    let addr = state.dynamic_stack_address(addr_size, slot)?;
+                    let dv = DataValue::try_from(addr)?;
+                    Ok(dv.into())
+                })
+            })
+        }
+        Opcode::DynamicStackLoad => {
+            let slot = if let InstructionData::DynamicStackLoad {
+                dynamic_stack_slot, ..
+            } = inst
+            {
+                dynamic_stack_slot
+            } else {
+                unreachable!()
+            };
+            let mem_flags = MemFlags::new();
+            assign_or_memtrap({
+                AddressSize::try_from(state.pointer_type())
+                    .and_then(|addr_size| state.dynamic_stack_address(addr_size, slot))
+                    .and_then(|addr| state.checked_load(addr, ctrl_ty, mem_flags))
+            })
+        }
+        Opcode::DynamicStackStore => {
+            let arg = arg(0);
+            let slot = if let InstructionData::DynamicStackStore {
+                dynamic_stack_slot, ..
+            } = inst
+            {
+                dynamic_stack_slot
+            } else {
+                unreachable!()
+            };
+            let mem_flags = MemFlags::new();
+            continue_or_memtrap({
+                AddressSize::try_from(state.pointer_type())
+                    .and_then(|addr_size| state.dynamic_stack_address(addr_size, slot))
+                    .and_then(|addr| state.checked_store(addr, arg, mem_flags))
+            })
+        }
         Opcode::GlobalValue | Opcode::SymbolValue | Opcode::TlsValue => {
             if let InstructionData::UnaryGlobalValue { global_value, .. } = inst {
                 assign_or_memtrap(state.resolve_global_value(global_value))
@@ -577,7 +678,11 @@ where
         Opcode::F32const => assign(imm()),
         Opcode::F64const => assign(imm()),
         Opcode::Vconst => assign(imm()),
-        Opcode::Null => unimplemented!("Null"),
+        // References are represented as integers of the same width, with null being zero.
+        Opcode::Null => assign(DataValueExt::int(
+            0,
+            Type::int(ctrl_ty.bits() as u16).unwrap(),
+        )?),
         Opcode::Nop => ControlFlow::Continue,
         Opcode::Select | Opcode::SelectSpectreGuard => choose(arg(0).into_bool()?, arg(1), arg(2)),
         Opcode::Bitselect => assign(bitselect(arg(0), arg(1), arg(2))?),
@@ -900,8 +1005,12 @@ where
         Opcode::Floor => unary(DataValueExt::floor, arg(0))?,
         Opcode::Trunc => unary(DataValueExt::trunc, arg(0))?,
         Opcode::Nearest => unary(DataValueExt::nearest, arg(0))?,
-        Opcode::IsNull => unimplemented!("IsNull"),
-        Opcode::IsInvalid => unimplemented!("IsInvalid"),
+        Opcode::IsNull => assign(DataValueExt::bool(arg(0).is_zero()?, false, types::I8)?),
+        Opcode::IsInvalid => {
+            let arg = arg(0);
+            let invalid = DataValueExt::int(-1, arg.ty())?;
+            assign(DataValueExt::bool(arg == invalid, false, types::I8)?)
+        }
         Opcode::Bitcast | Opcode::ScalarToVector => {
             let input_ty = inst_context.type_of(inst_context.args()[0]).unwrap();
             let lanes = &if input_ty.is_vector() {
@@ -1303,11 +1412,18 @@ where
             assign(binary_pairwise(arg(0), arg(1), ctrl_ty, DataValueExt::add)?)
         }
         Opcode::ExtractVector => {
-            unimplemented!("ExtractVector not supported");
+            // With a dynamic scale of one, the only valid subvector is the whole vector.
+            if imm().into_int_unsigned()? != 0 {
+                return Err(ValueError::InvalidValue(ctrl_ty).into());
+            }
+            assign(arg(0))
         }
         Opcode::GetFramePointer => unimplemented!("GetFramePointer"),
         Opcode::GetStackPointer => unimplemented!("GetStackPointer"),
         Opcode::GetReturnAddress => unimplemented!("GetReturnAddress"),
+        // Switching stacks needs real machine stacks, which the interpreter
+        // doesn't have.
+        Opcode::StackSwitch => return Err(StepError::UnsupportedOpcode(Opcode::StackSwitch)),
         Opcode::X86Pshufb => unimplemented!("X86Pshufb"),
         Opcode::X86Blendv => unimplemented!("X86Blendv"),
         Opcode::X86Pmulhrsw => unimplemented!("X86Pmulhrsw"),
@@ -1326,6 +1442,8 @@ pub enum StepError {
     ValueError(#[from] ValueError),
     #[error("failed to access memory")]
     MemoryError(#[from] MemoryError),
+    #[error("the interpreter cannot execute this instruction: {0}")]
+    UnsupportedOpcode(Opcode),
 }
 
 /// Enumerate the ways in which the control flow can change based on a single step in a Cranelift
@@ -398,6 +398,11 @@ impl DataValueExt for DataValue {
                 (val, ty) if val.ty().is_int() && ty.is_int() => {
                     DataValue::from_integer(val.into_int_signed()?, ty)?
                 }
+                // References are represented as integers of the same width.
+                (val, ty) if val.ty().is_int() && ty.is_ref() => DataValue::from_integer(
+                    val.into_int_signed()?,
+                    Type::int(ty.bits() as u16).unwrap(),
+                )?,
                 (DataValue::I32(n), types::F32) => DataValue::F32(f32::from_bits(n as u32).into()),
                 (DataValue::I64(n), types::F64) => DataValue::F64(f64::from_bits(n as u64).into()),
                 (DataValue::F32(n), types::I32) => DataValue::I32(n.bits() as i32),
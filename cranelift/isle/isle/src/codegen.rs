@@ -1,9 +1,11 @@
 //! Generate Rust code from a series of Sequences.
 
+use crate::lexer::Pos;
 use crate::sema::{ExternalSig, ReturnKind, Sym, Term, TermEnv, TermId, Type, TypeEnv, TypeId};
 use crate::serialize::{Block, ControlFlow, EvalStep, MatchArm};
 use crate::trie_again::{Binding, BindingId, Constraint, RuleSet};
 use crate::StableSet;
+use std::collections::HashMap;
 use std::fmt::Write;
 
 /// Options for code generation.
@@ -12,6 +14,13 @@ pub struct CodegenOptions {
     /// Do not include the `#![allow(...)]` pragmas in the generated
     /// source. Useful if it must be include!()'d elsewhere.
     pub exclude_global_allow_pragmas: bool,
+
+    /// Instrument the generated code to report every rule that fires.
+    ///
+    /// This emits a `RULES` table naming each rule by its term and source
+    /// position, and calls `Context::rule_fired` with the rule's index in that
+    /// table whenever a rule's right-hand side is evaluated.
+    pub emit_rule_trace: bool,
 }
 
 /// Emit Rust source code for the given type and term environments.
@@ -34,16 +43,23 @@ struct Codegen<'a> {
 struct BodyContext<'a, W> {
     out: &'a mut W,
     ruleset: &'a RuleSet,
+    /// Index of each rule in the generated `RULES` table, if tracing rules.
+    rule_ids: Option<&'a HashMap<Pos, usize>>,
     indent: String,
     is_ref: StableSet<BindingId>,
     is_bound: StableSet<BindingId>,
 }
 
 impl<'a, W: Write> BodyContext<'a, W> {
-    fn new(out: &'a mut W, ruleset: &'a RuleSet) -> Self {
+    fn new(
+        out: &'a mut W,
+        ruleset: &'a RuleSet,
+        rule_ids: Option<&'a HashMap<Pos, usize>>,
+    ) -> Self {
         Self {
             out,
             ruleset,
+            rule_ids,
             indent: Default::default(),
             is_ref: Default::default(),
             is_bound: Default::default(),
@@ -97,9 +113,15 @@ impl<'a> Codegen<'a> {
         let mut code = String::new();
 
         self.generate_header(&mut code, options);
-        self.generate_ctx_trait(&mut code);
+        self.generate_ctx_trait(&mut code, options);
         self.generate_internal_types(&mut code);
-        self.generate_internal_term_constructors(&mut code).unwrap();
+        let rule_ids = if options.emit_rule_trace {
+            Some(self.generate_rule_table(&mut code))
+        } else {
+            None
+        };
+        self.generate_internal_term_constructors(&mut code, rule_ids.as_ref())
+            .unwrap();
 
         code
     }
@@ -185,7 +207,7 @@ impl<'a> Codegen<'a> {
         .unwrap();
     }
 
-    fn generate_ctx_trait(&self, code: &mut String) {
+    fn generate_ctx_trait(&self, code: &mut String, options: &CodegenOptions) {
         writeln!(code).unwrap();
         writeln!(
             code,
@@ -213,6 +235,19 @@ impl<'a> Codegen<'a> {
                 self.generate_trait_sig(code, "    ", &ext_sig);
             }
         }
+        if options.emit_rule_trace {
+            writeln!(
+                code,
+                "    /// Called whenever the rule `rules[rule]` fires, just before its right-hand side"
+            )
+            .unwrap();
+            writeln!(code, "    /// is evaluated.").unwrap();
+            writeln!(
+                code,
+                "    fn rule_fired(&mut self, rules: &'static [&'static str], rule: usize) {{}}"
+            )
+            .unwrap();
+        }
         writeln!(code, "}}").unwrap();
         writeln!(
             code,
@@ -300,6 +335,33 @@ impl<'a> Codegen<'a> {
         }
     }
 
+    /// Emit the `RULES` table naming every rule of the generated terms, and return the index of
+    /// each rule in it, keyed by the rule's position.
+    fn generate_rule_table(&self, code: &mut String) -> HashMap<Pos, usize> {
+        let mut rule_ids = HashMap::new();
+        writeln!(
+            code,
+            "\n/// Every rule, named by its term and the position where it is defined."
+        )
+        .unwrap();
+        writeln!(code, "pub static RULES: &[&str] = &[").unwrap();
+        for &(termid, ref ruleset) in self.terms.iter() {
+            let termdata = &self.termenv.terms[termid.index()];
+            let term_name = &self.typeenv.syms[termdata.name.index()];
+            for rule in ruleset.rules.iter() {
+                rule_ids.insert(rule.pos, rule_ids.len());
+                let name = format!(
+                    "{} at {}",
+                    term_name,
+                    rule.pos.pretty_print_line(&self.typeenv.filenames)
+                );
+                writeln!(code, "    {:?},", name).unwrap();
+            }
+        }
+        writeln!(code, "];").unwrap();
+        rule_ids
+    }
+
     fn type_name(&self, typeid: TypeId, by_ref: bool) -> String {
         match self.typeenv.types[typeid.index()] {
             Type::Primitive(_, sym, _) => self.typeenv.syms[sym.index()].clone(),
@@ -310,10 +372,14 @@ impl<'a> Codegen<'a> {
         }
     }
 
-    fn generate_internal_term_constructors(&self, code: &mut String) -> std::fmt::Result {
+    fn generate_internal_term_constructors(
+        &self,
+        code: &mut String,
+        rule_ids: Option<&HashMap<Pos, usize>>,
+    ) -> std::fmt::Result {
         for &(termid, ref ruleset) in self.terms.iter() {
             let root = crate::serialize::serialize(ruleset);
-            let mut ctx = BodyContext::new(code, ruleset);
+            let mut ctx = BodyContext::new(code, ruleset, rule_ids);
 
             let termdata = &self.termenv.terms[termid.index()];
             let term_name = &self.typeenv.syms[termdata.name.index()];
@@ -526,6 +592,13 @@ impl<'a> Codegen<'a> {
                         &ctx.indent,
                         pos.pretty_print_line(&self.typeenv.filenames)
                     )?;
+                    if let Some(rule_ids) = ctx.rule_ids {
+                        writeln!(
+                            ctx.out,
+                            "{}ctx.rule_fired(RULES, {});",
+                            &ctx.indent, rule_ids[&pos]
+                        )?;
+                    }
                     write!(ctx.out, "{}", &ctx.indent)?;
                     match ret_kind {
                         ReturnKind::Plain => write!(ctx.out, "return ")?,
@@ -74,5 +74,22 @@ pub fn run_run(isle_filename: &str) {
         .success());
 }
 
+#[test]
+fn rule_trace() {
+    let options = cranelift_isle::codegen::CodegenOptions {
+        emit_rule_trace: true,
+        ..Default::default()
+    };
+    let code = compile::from_files(&["isle_examples/run/iconst.isle"], &options).unwrap();
+    assert!(
+        code.contains("fn rule_fired(&mut self, rules: &'static [&'static str], rule: usize) {}")
+    );
+    assert!(code.contains(
+        "pub static RULES: &[&str] = &[\n    \"X at isle_examples/run/iconst.isle line 4\",\n"
+    ));
+    assert!(code.contains("ctx.rule_fired(RULES, 0);"));
+    assert!(code.contains("ctx.rule_fired(RULES, 12);"));
+}
+
 // Generated by build.rs.
 include!(concat!(env!("OUT_DIR"), "/isle_tests.rs"));
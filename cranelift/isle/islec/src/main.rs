@@ -1,8 +1,8 @@
 use clap::Parser;
+use cranelift_isle::codegen::CodegenOptions;
 use cranelift_isle::compile;
 use cranelift_isle::error::Errors;
 use std::{
-    default::Default,
     fs,
     io::{self, Write},
     path::PathBuf,
@@ -15,6 +15,11 @@ struct Opts {
     #[clap(short, long)]
     output: Option<PathBuf>,
 
+    /// Instrument the generated code to report each rule that fires through
+    /// `Context::rule_fired`.
+    #[clap(long)]
+    rule_trace: bool,
+
     /// The input ISLE DSL source files.
     #[clap(required = true)]
     inputs: Vec<PathBuf>,
@@ -24,7 +29,11 @@ fn main() -> Result<(), Errors> {
     let _ = env_logger::try_init();
 
     let opts = Opts::parse();
-    let code = compile::from_files(opts.inputs, &Default::default())?;
+    let options = CodegenOptions {
+        emit_rule_trace: opts.rule_trace,
+        ..CodegenOptions::default()
+    };
+    let code = compile::from_files(opts.inputs, &options)?;
 
     let stdout = io::stdout();
     let (mut output, output_name): (Box<dyn Write>, _) = match &opts.output {
@@ -1,5 +1,6 @@
 //! Defines `ObjectModule`.
 
+use crate::debug::{DebugInfo, DwarfSectionRelocTarget};
 use anyhow::anyhow;
 use cranelift_codegen::binemit::{Addend, CodeOffset, Reloc};
 use cranelift_codegen::entity::SecondaryMap;
@@ -12,7 +13,8 @@ use cranelift_module::{
 };
 use log::info;
 use object::write::{
-    Object, Relocation, SectionId, StandardSection, Symbol, SymbolId, SymbolSection,
+    Object, Relocation, SectionId, StandardSection, StandardSegment, Symbol, SymbolId,
+    SymbolSection,
 };
 use object::{
     RelocationEncoding, RelocationKind, SectionKind, SymbolFlags, SymbolKind, SymbolScope,
@@ -480,6 +482,82 @@ impl Module for ObjectModule {
 }
 
 impl ObjectModule {
+    /// Write the DWARF debug info sections describing the functions recorded in `debug`.
+    ///
+    /// All of these functions must have been defined in this module.
+    pub fn emit_debug_info(&mut self, debug: &DebugInfo) -> ModuleResult<()> {
+        let sections = debug.write_sections(&*self.isa)?;
+        let format = self.object.format();
+
+        let section_ids: HashMap<_, _> = sections
+            .iter()
+            .map(|section| {
+                let name = if format == object::BinaryFormat::MachO {
+                    format!("__{}", &section.id.name()[1..])
+                } else {
+                    section.id.name().to_string()
+                };
+                let section_id = self.object.add_section(
+                    self.object.segment_name(StandardSegment::Debug).to_vec(),
+                    name.into_bytes(),
+                    SectionKind::Debug,
+                );
+                self.object
+                    .append_section_data(section_id, &section.body, 1);
+                (section.id, section_id)
+            })
+            .collect();
+
+        for section in &sections {
+            for reloc in &section.relocs {
+                let (symbol, kind) = match reloc.target {
+                    DwarfSectionRelocTarget::Func(index) => {
+                        let func_id = debug.function(index);
+                        match self.functions[func_id] {
+                            Some((symbol, true)) => (symbol, RelocationKind::Absolute),
+                            _ => {
+                                return Err(ModuleError::Backend(anyhow!(
+                                    "debug info refers to undefined function {}",
+                                    func_id
+                                )))
+                            }
+                        }
+                    }
+                    // Mach-O debug info isn't linked, so offsets into other DWARF sections
+                    // are left as they are.
+                    DwarfSectionRelocTarget::Section(_)
+                        if format == object::BinaryFormat::MachO =>
+                    {
+                        continue
+                    }
+                    DwarfSectionRelocTarget::Section(id) => {
+                        let kind = if format == object::BinaryFormat::Coff {
+                            RelocationKind::SectionOffset
+                        } else {
+                            RelocationKind::Absolute
+                        };
+                        (self.object.section_symbol(section_ids[&id]), kind)
+                    }
+                };
+                self.object
+                    .add_relocation(
+                        section_ids[&section.id],
+                        Relocation {
+                            offset: u64::from(reloc.offset),
+                            size: reloc.size * 8,
+                            kind,
+                            encoding: RelocationEncoding::Generic,
+                            symbol,
+                            addend: reloc.addend,
+                        },
+                    )
+                    .map_err(|e| ModuleError::Backend(e.into()))?;
+            }
+        }
+
+        Ok(())
+    }
+
     /// Finalize all relocations and output an object.
     pub fn finish(mut self) -> ObjectProduct {
         let symbol_relocs = mem::take(&mut self.relocs);
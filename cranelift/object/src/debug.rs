@@ -0,0 +1,492 @@
+//! DWARF debug info for functions compiled by frontends other than Wasmtime's.
+//!
+//! A frontend registers its source files and base types with a [`DebugInfo`], and asks it for
+//! the [`SourceLoc`] to attach to instructions through `FunctionBuilder::set_srcloc` for each
+//! source position. Variables are described by their `ValueLabel`, which the frontend attaches
+//! to values through `FunctionBuilder::set_var_label` or `FunctionBuilder::set_val_label`, after
+//! calling `collect_debug_info` on the function.
+//!
+//! Once a function has been defined in the module, [`DebugInfo::add_function`] records its line
+//! table and the value label ranges computed by the register allocator. Finally,
+//! `ObjectModule::emit_debug_info` writes the `.debug_info`, `.debug_line` and related sections.
+
+use anyhow::anyhow;
+use cranelift_codegen::binemit::CodeOffset;
+use cranelift_codegen::gimli::write::{
+    Address, AttributeValue, DwarfUnit, EndianVec, Expression, FileId, LineProgram, LineString,
+    Location, LocationList, Range, RangeList, Sections, Writer,
+};
+use cranelift_codegen::gimli::{self, Encoding, Format, LineEncoding, Register, RunTimeEndian};
+use cranelift_codegen::ir::{SourceLoc, ValueLabel};
+use cranelift_codegen::isa::TargetIsa;
+use cranelift_codegen::{Context, LabelValueLoc, ValueLabelsRanges};
+use cranelift_module::{FuncId, ModuleError, ModuleResult};
+use std::collections::HashMap;
+
+/// A source file registered with [`DebugInfo::add_file`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DebugFileId(usize);
+
+/// A type registered with [`DebugInfo::add_base_type`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DebugTypeId(usize);
+
+/// How the value of a base type is encoded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BaseTypeEncoding {
+    /// A two's complement signed integer.
+    Signed,
+    /// An unsigned integer.
+    Unsigned,
+    /// An IEEE floating point number.
+    Float,
+    /// A boolean, where zero is false.
+    Boolean,
+    /// A machine address.
+    Address,
+}
+
+/// Debug info about a function, provided by the frontend.
+#[derive(Clone, Debug)]
+pub struct FunctionDebugInfo {
+    /// The source-level name of the function.
+    pub name: String,
+    /// The file in which the function is declared.
+    pub file: DebugFileId,
+    /// The line on which the function is declared.
+    pub line: u32,
+    /// The variables of the function.
+    pub variables: Vec<VariableDebugInfo>,
+}
+
+/// Debug info about a variable of a function, provided by the frontend.
+#[derive(Clone, Debug)]
+pub struct VariableDebugInfo {
+    /// The source-level name of the variable.
+    pub name: String,
+    /// The label of the values holding this variable.
+    pub label: ValueLabel,
+    /// The type of the variable.
+    pub ty: DebugTypeId,
+    /// The line on which the variable is declared, in the function's file.
+    pub line: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct SourceLocation {
+    file: DebugFileId,
+    line: u32,
+    column: u32,
+}
+
+struct BaseType {
+    name: String,
+    byte_size: u8,
+    encoding: BaseTypeEncoding,
+}
+
+struct DefinedFunction {
+    func_id: FuncId,
+    info: FunctionDebugInfo,
+    size: CodeOffset,
+    /// The start offset of each run of code with the same source location.
+    srclocs: Vec<(CodeOffset, SourceLoc)>,
+    value_labels_ranges: ValueLabelsRanges,
+}
+
+/// The debug info of all the functions of a module, forming a single DWARF compilation unit.
+pub struct DebugInfo {
+    producer: String,
+    comp_dir: String,
+    name: String,
+    files: Vec<String>,
+    locations: Vec<SourceLocation>,
+    location_ids: HashMap<SourceLocation, u32>,
+    types: Vec<BaseType>,
+    functions: Vec<DefinedFunction>,
+}
+
+impl DebugInfo {
+    /// Create an empty `DebugInfo` for the compilation unit `name` compiled in `comp_dir`, by the
+    /// compiler described by `producer`.
+    pub fn new<P, D, N>(producer: P, comp_dir: D, name: N) -> Self
+    where
+        P: Into<String>,
+        D: Into<String>,
+        N: Into<String>,
+    {
+        Self {
+            producer: producer.into(),
+            comp_dir: comp_dir.into(),
+            name: name.into(),
+            files: Vec::new(),
+            locations: Vec::new(),
+            location_ids: HashMap::new(),
+            types: Vec::new(),
+            functions: Vec::new(),
+        }
+    }
+
+    /// Register a source file, whose path is either absolute or relative to the compilation
+    /// directory.
+    pub fn add_file<P: Into<String>>(&mut self, path: P) -> DebugFileId {
+        self.files.push(path.into());
+        DebugFileId(self.files.len() - 1)
+    }
+
+    /// Register a base type, such as an integer or floating point type.
+    pub fn add_base_type<N: Into<String>>(
+        &mut self,
+        name: N,
+        byte_size: u8,
+        encoding: BaseTypeEncoding,
+    ) -> DebugTypeId {
+        self.types.push(BaseType {
+            name: name.into(),
+            byte_size,
+            encoding,
+        });
+        DebugTypeId(self.types.len() - 1)
+    }
+
+    /// Get the source location to attach to instructions for the given position in `file`.
+    ///
+    /// Lines and columns start at 1; a column of 0 means that the column is unknown.
+    pub fn source_loc(&mut self, file: DebugFileId, line: u32, column: u32) -> SourceLoc {
+        let location = SourceLocation { file, line, column };
+        let locations = &mut self.locations;
+        let id = *self.location_ids.entry(location).or_insert_with(|| {
+            locations.push(location);
+            u32::try_from(locations.len() - 1).unwrap()
+        });
+        SourceLoc::new(id)
+    }
+
+    /// Record the debug info of the function `func_id`, which must just have been defined in
+    /// the module by compiling `ctx`.
+    pub fn add_function(
+        &mut self,
+        func_id: FuncId,
+        ctx: &Context,
+        info: FunctionDebugInfo,
+    ) -> ModuleResult<()> {
+        let compiled = ctx.compiled_code().ok_or_else(|| {
+            ModuleError::Backend(anyhow!("function {} has not been compiled", func_id))
+        })?;
+
+        let mut srclocs: Vec<(CodeOffset, SourceLoc)> = vec![];
+        for srcloc in compiled.buffer.get_srclocs_sorted() {
+            if srcloc.loc.is_default() || srcloc.loc.bits() as usize >= self.locations.len() {
+                continue;
+            }
+            if srclocs.last().map(|&(_, loc)| loc) != Some(srcloc.loc) {
+                srclocs.push((srcloc.start, srcloc.loc));
+            }
+        }
+
+        self.functions.push(DefinedFunction {
+            func_id,
+            info,
+            size: compiled.code_info().total_size,
+            srclocs,
+            value_labels_ranges: compiled.value_labels_ranges.clone(),
+        });
+        Ok(())
+    }
+
+    /// Write the DWARF sections describing the functions added so far.
+    ///
+    /// Addresses in these sections refer to [`DwarfSectionRelocTarget::Func`] symbols, which
+    /// index the functions in the order they were added.
+    pub(crate) fn write_sections(&self, isa: &dyn TargetIsa) -> ModuleResult<Vec<DwarfSection>> {
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 4,
+            address_size: isa.pointer_bytes(),
+        };
+        let mut dwarf = DwarfUnit::new(encoding);
+
+        let mut line_program = LineProgram::new(
+            encoding,
+            LineEncoding::default(),
+            LineString::String(self.comp_dir.clone().into_bytes()),
+            LineString::String(self.name.clone().into_bytes()),
+            None,
+        );
+        let dir = line_program.default_directory();
+        let files: Vec<FileId> = self
+            .files
+            .iter()
+            .map(|path| {
+                line_program.add_file(LineString::String(path.clone().into_bytes()), dir, None)
+            })
+            .collect();
+
+        let root = dwarf.unit.root();
+        let producer = dwarf.strings.add(self.producer.as_str());
+        let name = dwarf.strings.add(self.name.as_str());
+        let comp_dir = dwarf.strings.add(self.comp_dir.as_str());
+        let entry = dwarf.unit.get_mut(root);
+        entry.set(gimli::DW_AT_producer, AttributeValue::StringRef(producer));
+        entry.set(gimli::DW_AT_name, AttributeValue::StringRef(name));
+        entry.set(gimli::DW_AT_comp_dir, AttributeValue::StringRef(comp_dir));
+        entry.set(
+            gimli::DW_AT_low_pc,
+            AttributeValue::Address(Address::Constant(0)),
+        );
+
+        let types: Vec<_> = self
+            .types
+            .iter()
+            .map(|ty| {
+                let id = dwarf.unit.add(root, gimli::DW_TAG_base_type);
+                let name = dwarf.strings.add(ty.name.as_str());
+                let encoding = match ty.encoding {
+                    BaseTypeEncoding::Signed => gimli::DW_ATE_signed,
+                    BaseTypeEncoding::Unsigned => gimli::DW_ATE_unsigned,
+                    BaseTypeEncoding::Float => gimli::DW_ATE_float,
+                    BaseTypeEncoding::Boolean => gimli::DW_ATE_boolean,
+                    BaseTypeEncoding::Address => gimli::DW_ATE_address,
+                };
+                let entry = dwarf.unit.get_mut(id);
+                entry.set(gimli::DW_AT_name, AttributeValue::StringRef(name));
+                entry.set(gimli::DW_AT_encoding, AttributeValue::Encoding(encoding));
+                entry.set(
+                    gimli::DW_AT_byte_size,
+                    AttributeValue::Udata(ty.byte_size.into()),
+                );
+                id
+            })
+            .collect();
+
+        let mut ranges = vec![];
+        for (index, func) in self.functions.iter().enumerate() {
+            let address = |addend: CodeOffset| Address::Symbol {
+                symbol: index,
+                addend: addend.into(),
+            };
+            ranges.push(Range::StartLength {
+                begin: address(0),
+                length: func.size.into(),
+            });
+
+            line_program.begin_sequence(Some(address(0)));
+            for &(offset, loc) in &func.srclocs {
+                let location = self.locations[loc.bits() as usize];
+                let row = line_program.row();
+                row.address_offset = offset.into();
+                row.file = files[location.file.0];
+                row.line = location.line.into();
+                row.column = location.column.into();
+                line_program.generate_row();
+            }
+            line_program.end_sequence(func.size.into());
+
+            let subprogram = dwarf.unit.add(root, gimli::DW_TAG_subprogram);
+            let name = dwarf.strings.add(func.info.name.as_str());
+            let file = files[func.info.file.0];
+            let mut frame_base = Expression::new();
+            frame_base.op(gimli::DW_OP_call_frame_cfa);
+            let entry = dwarf.unit.get_mut(subprogram);
+            entry.set(gimli::DW_AT_name, AttributeValue::StringRef(name));
+            entry.set(
+                gimli::DW_AT_decl_file,
+                AttributeValue::FileIndex(Some(file)),
+            );
+            entry.set(
+                gimli::DW_AT_decl_line,
+                AttributeValue::Udata(func.info.line.into()),
+            );
+            entry.set(gimli::DW_AT_low_pc, AttributeValue::Address(address(0)));
+            entry.set(
+                gimli::DW_AT_high_pc,
+                AttributeValue::Udata(func.size.into()),
+            );
+            entry.set(gimli::DW_AT_frame_base, AttributeValue::Exprloc(frame_base));
+
+            for var in &func.info.variables {
+                let variable = dwarf.unit.add(subprogram, gimli::DW_TAG_variable);
+                let name = dwarf.strings.add(var.name.as_str());
+                let locations: Vec<_> = func
+                    .value_labels_ranges
+                    .get(&var.label)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|range| {
+                        let mut data = Expression::new();
+                        match range.loc {
+                            LabelValueLoc::Reg(reg) => {
+                                let reg = isa.map_regalloc_reg_to_dwarf(reg).ok()?;
+                                data.op_reg(Register(reg));
+                            }
+                            // Spilled values are not tracked by the register allocator's debug
+                            // locations, so these are never produced.
+                            LabelValueLoc::SPOffset(_) => return None,
+                        }
+                        Some(Location::StartLength {
+                            begin: address(range.start),
+                            length: (range.end - range.start).into(),
+                            data,
+                        })
+                    })
+                    .collect();
+                let locations = if locations.is_empty() {
+                    None
+                } else {
+                    Some(dwarf.unit.locations.add(LocationList(locations)))
+                };
+                let entry = dwarf.unit.get_mut(variable);
+                entry.set(gimli::DW_AT_name, AttributeValue::StringRef(name));
+                entry.set(
+                    gimli::DW_AT_decl_file,
+                    AttributeValue::FileIndex(Some(file)),
+                );
+                entry.set(
+                    gimli::DW_AT_decl_line,
+                    AttributeValue::Udata(var.line.into()),
+                );
+                entry.set(gimli::DW_AT_type, AttributeValue::UnitRef(types[var.ty.0]));
+                if let Some(list) = locations {
+                    entry.set(gimli::DW_AT_location, AttributeValue::LocationListRef(list));
+                }
+            }
+        }
+        let ranges = dwarf.unit.ranges.add(RangeList(ranges));
+        dwarf
+            .unit
+            .get_mut(root)
+            .set(gimli::DW_AT_ranges, AttributeValue::RangeListRef(ranges));
+        dwarf.unit.line_program = line_program;
+
+        let endian = match isa.endianness() {
+            cranelift_codegen::ir::Endianness::Little => RunTimeEndian::Little,
+            cranelift_codegen::ir::Endianness::Big => RunTimeEndian::Big,
+        };
+        let mut sections = Sections::new(WriterRelocate {
+            relocs: Vec::new(),
+            writer: EndianVec::new(endian),
+        });
+        dwarf
+            .write(&mut sections)
+            .map_err(|e| ModuleError::Backend(anyhow!("failed to write DWARF: {}", e)))?;
+
+        let mut result = vec![];
+        sections
+            .for_each_mut(|id, section| -> gimli::write::Result<()> {
+                let body = section.writer.take();
+                if !body.is_empty() {
+                    result.push(DwarfSection {
+                        id,
+                        body,
+                        relocs: std::mem::take(&mut section.relocs),
+                    });
+                }
+                Ok(())
+            })
+            .unwrap();
+        Ok(result)
+    }
+
+    /// The function whose code the `DwarfSectionRelocTarget::Func(index)` relocations refer to.
+    pub(crate) fn function(&self, index: usize) -> FuncId {
+        self.functions[index].func_id
+    }
+}
+
+/// The contents of a DWARF section, along with the relocations to apply to it.
+pub(crate) struct DwarfSection {
+    pub id: gimli::SectionId,
+    pub body: Vec<u8>,
+    pub relocs: Vec<DwarfSectionReloc>,
+}
+
+/// A relocation in a DWARF section.
+#[derive(Clone)]
+pub(crate) struct DwarfSectionReloc {
+    pub target: DwarfSectionRelocTarget,
+    pub offset: u32,
+    pub addend: i64,
+    pub size: u8,
+}
+
+/// The symbol that a DWARF section relocation refers to.
+#[derive(Clone, Copy)]
+pub(crate) enum DwarfSectionRelocTarget {
+    /// The function at the given index in `DebugInfo`.
+    Func(usize),
+    /// The start of another DWARF section.
+    Section(gimli::SectionId),
+}
+
+/// A DWARF writer that records the relocations needed for addresses and section offsets.
+#[derive(Clone)]
+struct WriterRelocate {
+    relocs: Vec<DwarfSectionReloc>,
+    writer: EndianVec<RunTimeEndian>,
+}
+
+impl Writer for WriterRelocate {
+    type Endian = RunTimeEndian;
+
+    fn endian(&self) -> Self::Endian {
+        self.writer.endian()
+    }
+
+    fn len(&self) -> usize {
+        self.writer.len()
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> gimli::write::Result<()> {
+        self.writer.write(bytes)
+    }
+
+    fn write_at(&mut self, offset: usize, bytes: &[u8]) -> gimli::write::Result<()> {
+        self.writer.write_at(offset, bytes)
+    }
+
+    fn write_address(&mut self, address: Address, size: u8) -> gimli::write::Result<()> {
+        match address {
+            Address::Constant(val) => self.write_udata(val, size),
+            Address::Symbol { symbol, addend } => {
+                self.relocs.push(DwarfSectionReloc {
+                    target: DwarfSectionRelocTarget::Func(symbol),
+                    offset: self.len() as u32,
+                    addend,
+                    size,
+                });
+                self.write_udata(addend as u64, size)
+            }
+        }
+    }
+
+    fn write_offset(
+        &mut self,
+        val: usize,
+        section: gimli::SectionId,
+        size: u8,
+    ) -> gimli::write::Result<()> {
+        self.relocs.push(DwarfSectionReloc {
+            target: DwarfSectionRelocTarget::Section(section),
+            offset: self.len() as u32,
+            addend: val as i64,
+            size,
+        });
+        self.write_udata(val as u64, size)
+    }
+
+    fn write_offset_at(
+        &mut self,
+        offset: usize,
+        val: usize,
+        section: gimli::SectionId,
+        size: u8,
+    ) -> gimli::write::Result<()> {
+        self.relocs.push(DwarfSectionReloc {
+            target: DwarfSectionRelocTarget::Section(section),
+            offset: offset as u32,
+            addend: val as i64,
+            size,
+        });
+        self.write_udata_at(offset, val as u64, size)
+    }
+}
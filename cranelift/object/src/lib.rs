@@ -25,8 +25,12 @@
 )]
 
 mod backend;
+mod debug;
 
 pub use crate::backend::{ObjectBuilder, ObjectModule, ObjectProduct};
+pub use crate::debug::{
+    BaseTypeEncoding, DebugFileId, DebugInfo, DebugTypeId, FunctionDebugInfo, VariableDebugInfo,
+};
 
 /// Version number of this crate.
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -239,3 +239,120 @@ fn reject_nul_byte_symbol_for_data() {
         )
         .unwrap();
 }
+
+#[test]
+fn debug_info() {
+    use cranelift_codegen::gimli::{self, EndianSlice, LittleEndian};
+    use cranelift_object::object::{Object as _, ObjectSection as _};
+
+    let flag_builder = settings::builder();
+    let isa_builder = cranelift_codegen::isa::lookup_by_name("x86_64-unknown-linux-gnu").unwrap();
+    let isa = isa_builder
+        .finish(settings::Flags::new(flag_builder))
+        .unwrap();
+    let mut module =
+        ObjectModule::new(ObjectBuilder::new(isa, "foo", default_libcall_names()).unwrap());
+
+    let mut debug = DebugInfo::new("test", "/src", "square.lang");
+    let file = debug.add_file("square.lang");
+    let int = debug.add_base_type("int", 4, BaseTypeEncoding::Signed);
+
+    let sig = Signature {
+        params: vec![AbiParam::new(types::I32)],
+        returns: vec![AbiParam::new(types::I32)],
+        call_conv: CallConv::SystemV,
+    };
+    let func_id = module
+        .declare_function("square", Linkage::Export, &sig)
+        .unwrap();
+
+    let mut ctx = Context::new();
+    ctx.func = Function::with_name_signature(UserFuncName::user(0, func_id.as_u32()), sig);
+    ctx.func.collect_debug_info();
+    let mut func_ctx = FunctionBuilderContext::new();
+    {
+        let mut bcx: FunctionBuilder = FunctionBuilder::new(&mut ctx.func, &mut func_ctx);
+        let x = Variable::new(0);
+        bcx.declare_var(x, types::I32);
+        bcx.set_var_label(x, ValueLabel::new(0));
+
+        let block = bcx.create_block();
+        bcx.append_block_params_for_function_params(block);
+        bcx.switch_to_block(block);
+        bcx.seal_block(block);
+        bcx.set_srcloc(debug.source_loc(file, 1, 1));
+        let param = bcx.block_params(block)[0];
+        bcx.def_var(x, param);
+
+        bcx.set_srcloc(debug.source_loc(file, 2, 5));
+        let x_val = bcx.use_var(x);
+        let square = bcx.ins().imul(x_val, x_val);
+        bcx.set_srcloc(debug.source_loc(file, 3, 5));
+        let result = bcx.ins().iadd(square, x_val);
+        bcx.ins().return_(&[result]);
+        bcx.finalize();
+    }
+
+    module.define_function(func_id, &mut ctx).unwrap();
+    debug
+        .add_function(
+            func_id,
+            &ctx,
+            FunctionDebugInfo {
+                name: "square".to_string(),
+                file,
+                line: 1,
+                variables: vec![VariableDebugInfo {
+                    name: "x".to_string(),
+                    label: ValueLabel::new(0),
+                    ty: int,
+                    line: 1,
+                }],
+            },
+        )
+        .unwrap();
+    module.emit_debug_info(&debug).unwrap();
+    let bytes = module.finish().emit().unwrap();
+
+    let file = object::File::parse(&*bytes).unwrap();
+    let dwarf = gimli::Dwarf::load(|id| -> Result<_, gimli::Error> {
+        let data = file
+            .section_by_name(id.name())
+            .map(|section| section.data().unwrap())
+            .unwrap_or(&[]);
+        Ok(EndianSlice::new(data, LittleEndian))
+    })
+    .unwrap();
+
+    let header = dwarf.units().next().unwrap().unwrap();
+    let unit = dwarf.unit(header).unwrap();
+    let mut names = vec![];
+    let mut entries = unit.entries();
+    while let Some((_, entry)) = entries.next_dfs().unwrap() {
+        if let Some(name) = entry.attr_value(gimli::DW_AT_name).unwrap() {
+            let name = dwarf.attr_string(&unit, name).unwrap().to_string_lossy();
+            let has_location = entry.attr(gimli::DW_AT_location).unwrap().is_some();
+            names.push((entry.tag(), name.into_owned(), has_location));
+        }
+    }
+    assert_eq!(
+        names,
+        [
+            (gimli::DW_TAG_compile_unit, "square.lang".to_string(), false),
+            (gimli::DW_TAG_base_type, "int".to_string(), false),
+            (gimli::DW_TAG_subprogram, "square".to_string(), false),
+            (gimli::DW_TAG_variable, "x".to_string(), true),
+        ]
+    );
+
+    let program = unit.line_program.clone().unwrap();
+    let mut rows = program.rows();
+    let mut lines = vec![];
+    while let Some((_, row)) = rows.next_row().unwrap() {
+        if !row.end_sequence() {
+            lines.push(row.line().unwrap().get());
+        }
+    }
+    assert!(lines.contains(&2), "{:?}", lines);
+    assert!(lines.contains(&3), "{:?}", lines);
+}
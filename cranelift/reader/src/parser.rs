@@ -117,6 +117,36 @@ impl Default for ParseOptions<'_> {
     }
 }
 
+impl ParseOptions<'_> {
+    /// The settings that a test file starts out with, before any of its `set` commands are
+    /// applied.
+    ///
+    /// These differ from `settings::builder()` for the settings that can be configured through
+    /// `ParseOptions`, so a test file only round-trips if it sets every flag whose value differs
+    /// from these.
+    pub fn flag_builder(&self) -> settings::Builder {
+        let bool_to_str = |val: bool| {
+            if val {
+                "true"
+            } else {
+                "false"
+            }
+        };
+
+        let mut flag_builder = settings::builder();
+        flag_builder
+            .set(
+                "machine_code_cfg_info",
+                bool_to_str(self.machine_code_cfg_info),
+            )
+            .expect("machine_code_cfg_info option should be present");
+        flag_builder
+            .set("unwind_info", bool_to_str(self.unwind_info))
+            .expect("unwind_info option should be present");
+        flag_builder
+    }
+}
+
 /// Parse the entire `text` as a test case file.
 ///
 /// The returned `TestFile` contains direct references to substrings of `text`.
@@ -1041,27 +1071,7 @@ impl<'a> Parser<'a> {
         let mut last_set_loc = None;
 
         let mut targets = Vec::new();
-        let mut flag_builder = settings::builder();
-
-        let bool_to_str = |val: bool| {
-            if val {
-                "true"
-            } else {
-                "false"
-            }
-        };
-
-        // default to enabling cfg info
-        flag_builder
-            .set(
-                "machine_code_cfg_info",
-                bool_to_str(options.machine_code_cfg_info),
-            )
-            .expect("machine_code_cfg_info option should be present");
-
-        flag_builder
-            .set("unwind_info", bool_to_str(options.unwind_info))
-            .expect("unwind_info option should be present");
+        let mut flag_builder = options.flag_builder();
 
         while let Some(Token::Identifier(command)) = self.token() {
             match command {
@@ -1350,14 +1360,20 @@ impl<'a> Parser<'a> {
         }
 
         // The calling convention is optional.
-        if let Some(Token::Identifier(text)) = self.token() {
-            match text.parse() {
+        match self.token() {
+            Some(Token::Identifier(text)) => match text.parse() {
                 Ok(cc) => {
                     self.consume();
                     sig.call_conv = cc;
                 }
                 _ => return err!(self.loc, "unknown calling convention: {}", text),
+            },
+            // `cold` is lexed as a keyword because it is also a block flag.
+            Some(Token::Cold) => {
+                self.consume();
+                sig.call_conv = CallConv::Cold;
             }
+            _ => {}
         }
 
         Ok(sig)
@@ -2011,7 +2027,15 @@ impl<'a> Parser<'a> {
             ctx.function.dfg.make_invalid_value_for_parser();
         }
 
-        let t = self.match_type("expected block argument type")?;
+        let t = if let Some(Token::DynamicType(_)) = self.token() {
+            let dt = self.match_dt("expected block argument type")?;
+            match self.concrete_from_dt(dt, ctx) {
+                Some(t) => t,
+                None => return err!(self.loc, "invalid dynamic type {}", dt),
+            }
+        } else {
+            self.match_type("expected block argument type")?
+        };
         // Allocate the block argument.
         ctx.function.dfg.append_block_param_for_parser(block, t, v);
         ctx.map.def_value(v, v_location)?;
@@ -3529,4 +3553,70 @@ mod tests {
         assert!(func.layout.is_cold(Block::from_u32(1)));
         assert!(!func.layout.is_cold(Block::from_u32(2)));
     }
+
+    #[test]
+    fn roundtrip() {
+        let code = "function u1:0(i8, i64) -> i8 tail {
+    gv0 = dyn_scale_target_const.i8x16
+    gv1 = symbol colocated u2:6
+    gv2 = symbol %ElfGlobalOffsetTable
+    gv3 = symbol tls u3:1+8
+    dt0 = i8x16*gv0
+    sig0 = (i32) -> i32 cold
+    fn0 = u3:4 sig0
+
+block0(v0: i8, v1: i64):
+    v2 = splat.dt0 v0
+    v3 = atomic_rmw.i64 little add v1, v1
+    v4 = atomic_cas big v1, v3, v3
+    jump block1(v2)
+
+block1(v5: dt0):
+    v6 = extract_vector v5, 0
+    v7 = extractlane v6, 0
+    return v7
+}
+";
+        let func = Parser::new(code).parse_function().unwrap().0;
+        assert_eq!(func.signature.call_conv, CallConv::Tail);
+        assert_eq!(
+            func.dfg.signatures[SigRef::from_u32(0)].call_conv,
+            CallConv::Cold
+        );
+        let name = |gv| match &func.global_values[GlobalValue::from_u32(gv)] {
+            GlobalValueData::Symbol { name, .. } => name.display(Some(&func.params)).to_string(),
+            _ => panic!("expected a symbol"),
+        };
+        assert_eq!(name(1), "u2:6");
+        assert_eq!(name(2), "%ElfGlobalOffsetTable");
+        assert_eq!(name(3), "u3:1");
+
+        let text = func.display().to_string();
+        assert_eq!(text, code);
+        let reparsed = Parser::new(&text).parse_function().unwrap().0;
+        assert_eq!(reparsed.display().to_string(), code);
+    }
+
+    #[test]
+    fn parse_options_flags() {
+        let flags = settings::Flags::new(ParseOptions::default().flag_builder());
+        assert!(!flags.unwind_info());
+        assert!(!flags.machine_code_cfg_info());
+
+        let options = ParseOptions {
+            unwind_info: true,
+            ..ParseOptions::default()
+        };
+        match parse_test(
+            "target x86_64
+                          function %foo() system_v {}",
+            options,
+        )
+        .unwrap()
+        .isa_spec
+        {
+            IsaSpec::Some(isas) => assert!(isas[0].flags().unwind_info()),
+            IsaSpec::None(_) => panic!("expected a target"),
+        }
+    }
 }
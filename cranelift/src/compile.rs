@@ -28,6 +28,16 @@ pub struct Options {
     #[clap(short = 'D', long)]
     disasm: bool,
 
+    /// Print a report of how many times each ISLE rule fired
+    #[cfg(feature = "isle-coverage")]
+    #[clap(long)]
+    isle_coverage: bool,
+
+    /// Print the ISLE rules that fire while compiling the named function, in order
+    #[cfg(feature = "isle-coverage")]
+    #[clap(long, value_name = "FUNCTION")]
+    isle_trace: Option<String>,
+
     /// Configure Cranelift settings
     #[clap(long = "set")]
     settings: Vec<String>,
@@ -72,6 +82,11 @@ pub fn run(options: &Options) -> Result<()> {
         std::fs::write(output, bytes)?;
     }
 
+    #[cfg(feature = "isle-coverage")]
+    if options.isle_coverage {
+        print!("{}", cranelift_codegen::isle_coverage::take_current());
+    }
+
     Ok(())
 }
 
@@ -100,10 +115,27 @@ fn handle_module(
         context.func = func;
         let mut mem = vec![];
 
+        #[cfg(feature = "isle-coverage")]
+        let trace = options.isle_trace.as_ref().map_or(false, |f| {
+            f.trim_start_matches('%') == context.func.name.to_string().trim_start_matches('%')
+        });
+        #[cfg(feature = "isle-coverage")]
+        if trace {
+            cranelift_codegen::isle_coverage::start_trace();
+        }
+
         // Compile and encode the result to machine code.
         let compiled_code = context
             .compile_and_emit(isa, &mut mem, &mut Default::default())
             .map_err(|err| anyhow::anyhow!("{}", pretty_error(&err.func, err.inner)))?;
+
+        #[cfg(feature = "isle-coverage")]
+        if trace {
+            println!("ISLE rules fired while compiling {}:", context.func.name);
+            for rule in cranelift_codegen::isle_coverage::take_trace() {
+                println!("  {}", rule);
+            }
+        }
         let code_info = compiled_code.code_info();
 
         if let Some(&mut ref mut module) = module {
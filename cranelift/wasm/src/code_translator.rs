@@ -75,7 +75,7 @@ mod bounds_checks;
 
 use super::{hash_map, HashMap};
 use crate::environ::{FuncEnvironment, GlobalVariable};
-use crate::state::{ControlStackFrame, ElseData, FuncTranslationState};
+use crate::state::{ControlStackFrame, ElseData, FuncTranslationState, TryHandler};
 use crate::translation_utils::{
     block_with_params, blocktype_params_results, f32_translation, f64_translation,
 };
@@ -429,7 +429,7 @@ pub fn translate_operator<FE: FuncEnvironment + ?Sized>(
                 _ => unreachable!(),
             }
         }
-        Operator::End => {
+        Operator::End | Operator::Delegate { .. } => {
             let frame = state.control_stack.pop().unwrap();
             let next_block = frame.following_code();
             let return_count = frame.num_return_values();
@@ -443,6 +443,10 @@ pub fn translate_operator<FE: FuncEnvironment + ?Sized>(
             // since we truncate the stack back to the original height
             // below.
 
+            if let ControlStackFrame::Try { handler, .. } = frame {
+                forward_uncaught_exceptions(op, handler, builder, state, environ)?;
+            }
+
             builder.switch_to_block(next_block);
             builder.seal_block(next_block);
 
@@ -602,12 +606,46 @@ pub fn translate_operator<FE: FuncEnvironment + ?Sized>(
         | Operator::Throw { .. }
         | Operator::Rethrow { .. }
         | Operator::Delegate { .. }
-        | Operator::CatchAll => {
+        | Operator::CatchAll
+            if !environ.exceptions_enabled() =>
+        {
             return Err(wasm_unsupported!(
                 "proposed exception handling operator {:?}",
                 op
             ));
         }
+        Operator::Try { blockty } => {
+            let (params, results) = blocktype_params_results(validator, *blockty)?;
+            let next = block_with_params(builder, results.clone(), environ)?;
+            state.push_try(next, params.len(), results.len());
+        }
+        Operator::Catch { tag_index } => {
+            translate_catch(Some(*tag_index), builder, state, environ)?;
+        }
+        Operator::CatchAll => {
+            translate_catch(None, builder, state, environ)?;
+        }
+        Operator::Throw { tag_index } => {
+            let num_args = environ.tag_params(*tag_index).len();
+            environ.translate_throw(builder, *tag_index, state.peekn(num_args))?;
+            state.popn(num_args);
+            let handler = state.exception_handler(builder, state.control_stack.len());
+            builder.ins().jump(handler, &[]);
+            state.reachable = false;
+        }
+        Operator::Rethrow { relative_depth } => {
+            let i = state.control_stack.len() - 1 - *relative_depth as usize;
+            let exn = match state.control_stack[i] {
+                ControlStackFrame::Try { handler, .. } => handler
+                    .exception
+                    .expect("a reachable `catch` clause has caught an exception"),
+                _ => unreachable!(),
+            };
+            environ.translate_rethrow(builder, exn)?;
+            let handler = state.exception_handler(builder, state.control_stack.len());
+            builder.ins().jump(handler, &[]);
+            state.reachable = false;
+        }
         /************************************ Calls ****************************************
          * The call instructions pop off their arguments from the stack and append their
          * return values to it. `call_indirect` needs environment support because there is an
@@ -641,6 +679,7 @@ pub fn translate_operator<FE: FuncEnvironment + ?Sized>(
             );
             state.popn(num_args);
             state.pushn(inst_results);
+            translate_exception_landing(call, builder, state, environ)?;
         }
         Operator::CallIndirect {
             type_index,
@@ -675,6 +714,7 @@ pub fn translate_operator<FE: FuncEnvironment + ?Sized>(
             );
             state.popn(num_args);
             state.pushn(inst_results);
+            translate_exception_landing(call, builder, state, environ)?;
         }
         /******************************* Tail Calls ******************************************
          * The tail call instructions pop their arguments from the stack and
@@ -2469,6 +2509,7 @@ pub fn translate_operator<FE: FuncEnvironment + ?Sized>(
             );
             state.popn(num_args);
             state.pushn(inst_results);
+            translate_exception_landing(call, builder, state, environ)?;
         }
         Operator::RefAsNonNull => {
             let r = state.pop1();
@@ -2686,6 +2727,7 @@ pub fn translate_operator<FE: FuncEnvironment + ?Sized>(
                 environ.typed_continuations_drop_cont_obj(builder, original_contobj);
 
                 state.pushn(&values);
+                translate_exception_check(builder, state, environ)?;
             }
         }
         Operator::Suspend { tag_index } => {
@@ -2769,6 +2811,15 @@ fn translate_unreachable_operator<FE: FuncEnvironment + ?Sized>(
         Operator::Loop { blockty: _ } | Operator::Block { blockty: _ } => {
             state.push_block(ir::Block::reserved_value(), 0, 0);
         }
+        Operator::Try { blockty: _ } => {
+            state.push_try(ir::Block::reserved_value(), 0, 0);
+        }
+        Operator::Catch { tag_index } => {
+            translate_catch(Some(tag_index), builder, state, environ)?;
+        }
+        Operator::CatchAll => {
+            translate_catch(None, builder, state, environ)?;
+        }
         Operator::Else => {
             let i = state.control_stack.len() - 1;
             match state.control_stack[i] {
@@ -2824,10 +2875,12 @@ fn translate_unreachable_operator<FE: FuncEnvironment + ?Sized>(
                 _ => unreachable!(),
             }
         }
-        Operator::End => {
+        Operator::End | Operator::Delegate { .. } => {
+            let frame = state.control_stack.pop().unwrap();
+            if let ControlStackFrame::Try { handler, .. } = frame {
+                forward_uncaught_exceptions(op, handler, builder, state, environ)?;
+            }
             let stack = &mut state.stack;
-            let control_stack = &mut state.control_stack;
-            let frame = control_stack.pop().unwrap();
 
             // Pop unused parameters from stack.
             frame.truncate_value_stack_to_original_size(stack);
@@ -2879,6 +2932,143 @@ fn translate_unreachable_operator<FE: FuncEnvironment + ?Sized>(
     Ok(())
 }
 
+/// Make `call` a landing site for the exceptions thrown by its callee if it is
+/// within the body of a `try` block, and emit the check for them after it.
+/// Other calls need no check, since their frames are unwound by the runtime.
+fn translate_exception_landing<FE: FuncEnvironment + ?Sized>(
+    call: ir::Inst,
+    builder: &mut FunctionBuilder,
+    state: &mut FuncTranslationState,
+    environ: &mut FE,
+) -> WasmResult<()> {
+    if !environ.exceptions_enabled() || !state.in_try_body() {
+        return Ok(());
+    }
+    environ.translate_exception_landing(builder, call)?;
+    translate_exception_check(builder, state, environ)
+}
+
+/// Emit a check for a pending exception, after a landing site or a `resume`,
+/// which branches to the handler of the exception if there is one.
+fn translate_exception_check<FE: FuncEnvironment + ?Sized>(
+    builder: &mut FunctionBuilder,
+    state: &mut FuncTranslationState,
+    environ: &mut FE,
+) -> WasmResult<()> {
+    if !environ.exceptions_enabled() {
+        return Ok(());
+    }
+    let pending = environ.translate_exception_pending(builder)?;
+    let handler = state.exception_handler(builder, state.control_stack.len());
+    let next = builder.create_block();
+    builder.ins().brif(pending, handler, &[], next, &[]);
+    builder.seal_block(next); // Only predecessor is the current block.
+    builder.switch_to_block(next);
+    Ok(())
+}
+
+/// Translate the start of a `catch` clause of the innermost `try` block, which
+/// catches the exceptions of the tag `tag`, or of a `catch_all` clause if
+/// `tag` is `None`.
+fn translate_catch<FE: FuncEnvironment + ?Sized>(
+    tag: Option<u32>,
+    builder: &mut FunctionBuilder,
+    state: &mut FuncTranslationState,
+    environ: &mut FE,
+) -> WasmResult<()> {
+    let i = state.control_stack.len() - 1;
+
+    // Finish the body or the previous clause, which exits the `try` block.
+    let frame = &mut state.control_stack[i];
+    if state.reachable {
+        frame.set_branched_to_exit();
+        let return_args = state.stack.len() - frame.num_return_values();
+        canonicalise_then_jump(builder, frame.following_code(), &state.stack[return_args..]);
+    }
+    frame.truncate_value_stack_to_original_size(&mut state.stack);
+
+    let mut handler = match *frame {
+        ControlStackFrame::Try { handler, .. } => handler,
+        _ => unreachable!(),
+    };
+    if !handler.in_clauses {
+        // This is the first clause, so the exceptions raised within the body
+        // are dispatched from the landing pad, if anything in the body may
+        // throw.
+        handler.in_clauses = true;
+        if let Some(landing_pad) = handler.landing_pad {
+            builder.seal_block(landing_pad);
+            builder.switch_to_block(landing_pad);
+            handler.exception = Some(environ.translate_catch_exception(builder)?);
+            handler.next_clause = Some(landing_pad);
+        }
+    } else if let Some(next_clause) = handler.next_clause {
+        builder.switch_to_block(next_clause);
+    }
+
+    state.reachable = handler.next_clause.is_some();
+    if state.reachable {
+        handler.next_clause = match tag {
+            Some(tag) => {
+                let exn = handler.exception.unwrap();
+                let has_tag = environ.translate_exception_has_tag(builder, exn, tag)?;
+                let clause = builder.create_block();
+                let next_clause = builder.create_block();
+                builder.ins().brif(has_tag, clause, &[], next_clause, &[]);
+                builder.seal_block(clause);
+                builder.seal_block(next_clause);
+                builder.switch_to_block(clause);
+                let payload = environ.translate_exception_payload(builder, exn, tag)?;
+                state.pushn(&payload);
+                Some(next_clause)
+            }
+            // A `catch_all` leaves no exception for the following clauses.
+            None => None,
+        };
+    }
+
+    match &mut state.control_stack[i] {
+        ControlStackFrame::Try { handler: h, .. } => *h = handler,
+        _ => unreachable!(),
+    }
+    Ok(())
+}
+
+/// Emit the code forwarding the exceptions which the `try` block whose
+/// `handler` is given does not catch to the enclosing handler, when the `try`
+/// block ends with `op`, which is either `end` or `delegate`. The `try` block
+/// must already have been popped from the control stack.
+fn forward_uncaught_exceptions<FE: FuncEnvironment + ?Sized>(
+    op: &Operator,
+    handler: TryHandler,
+    builder: &mut FunctionBuilder,
+    state: &mut FuncTranslationState,
+    environ: &mut FE,
+) -> WasmResult<()> {
+    // The label of a `delegate` is relative to the frames enclosing the `try`.
+    let frames = match *op {
+        Operator::Delegate { relative_depth } => {
+            state.control_stack.len() - relative_depth as usize
+        }
+        _ => state.control_stack.len(),
+    };
+    if !handler.in_clauses {
+        // No clause takes the exception, which is still pending.
+        if let Some(landing_pad) = handler.landing_pad {
+            builder.seal_block(landing_pad);
+            builder.switch_to_block(landing_pad);
+            let outer = state.exception_handler(builder, frames);
+            builder.ins().jump(outer, &[]);
+        }
+    } else if let Some(next_clause) = handler.next_clause {
+        builder.switch_to_block(next_clause);
+        environ.translate_rethrow(builder, handler.exception.unwrap())?;
+        let outer = state.exception_handler(builder, frames);
+        builder.ins().jump(outer, &[]);
+    }
+    Ok(())
+}
+
 /// This function is a generalized helper for validating that a wasm-supplied
 /// heap address is in-bounds.
 ///
@@ -3133,7 +3323,13 @@ fn translate_load<FE: FuncEnvironment + ?Sized>(
             Reachability::Reachable((f, i, b)) => (f, i, b),
         };
 
-    environ.before_load(builder, mem_op_size, wasm_index, memarg.offset);
+    environ.before_load(
+        builder,
+        mem_op_size,
+        wasm_index,
+        memarg.offset,
+        MemoryIndex::from_u32(memarg.memory),
+    );
 
     let (load, dfg) = builder
         .ins()
@@ -3159,7 +3355,13 @@ fn translate_store<FE: FuncEnvironment + ?Sized>(
         prepare_addr(memarg, mem_op_size, builder, state, environ)?
     );
 
-    environ.before_store(builder, mem_op_size, wasm_index, memarg.offset);
+    environ.before_store(
+        builder,
+        mem_op_size,
+        wasm_index,
+        memarg.offset,
+        MemoryIndex::from_u32(memarg.memory),
+    );
 
     builder
         .ins()
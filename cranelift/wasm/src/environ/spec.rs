@@ -624,6 +624,108 @@ pub trait FuncEnvironment: TargetEnvironment {
         false
     }
 
+    /// Whether the exception handling instructions should be translated. If
+    /// so, the `translate_*` exception methods below must be implemented.
+    ///
+    /// Exceptions are dispatched by the runtime, which unwinds the frames
+    /// between the thrower and the innermost call within the body of a `try`
+    /// block, and resumes execution just after that call with the exception
+    /// pending. Only those calls are followed by a check for an exception.
+    fn exceptions_enabled(&self) -> bool {
+        false
+    }
+
+    /// Translate a `throw` instruction, making an exception of the tag
+    /// `tag_index` with the payload `args` pending. The translator then
+    /// branches to the handler of the exception.
+    fn translate_throw(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        tag_index: u32,
+        args: &[ir::Value],
+    ) -> WasmResult<()> {
+        let _ = (builder, tag_index, args);
+        Err(WasmError::Unsupported("wasm exceptions".to_string()))
+    }
+
+    /// Translate a `rethrow` instruction, making the caught exception `exn`
+    /// pending again.
+    fn translate_rethrow(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        exn: ir::Value,
+    ) -> WasmResult<()> {
+        let _ = (builder, exn);
+        Err(WasmError::Unsupported("wasm exceptions".to_string()))
+    }
+
+    /// Record that `call`, which is within the body of a `try` block, is where
+    /// the exceptions thrown by its callee land: the runtime resumes execution
+    /// just after it with the exception pending, rather than unwinding the
+    /// function's frame. The translator then checks for the exception.
+    fn translate_exception_landing(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        call: ir::Inst,
+    ) -> WasmResult<()> {
+        let _ = (builder, call);
+        Err(WasmError::Unsupported("wasm exceptions".to_string()))
+    }
+
+    /// Translate the unwinding of the function's frame by the pending
+    /// exception, raised outside of any `try` block, which continues to be
+    /// dispatched from the function's caller. The code following is
+    /// unreachable.
+    fn translate_unwind(&mut self, builder: &mut FunctionBuilder) -> WasmResult<()> {
+        let _ = builder;
+        Err(WasmError::Unsupported("wasm exceptions".to_string()))
+    }
+
+    /// Returns an integer value which is nonzero if an exception is pending,
+    /// after a landing site or a `resume`.
+    fn translate_exception_pending(
+        &mut self,
+        builder: &mut FunctionBuilder,
+    ) -> WasmResult<ir::Value> {
+        let _ = builder;
+        Err(WasmError::Unsupported("wasm exceptions".to_string()))
+    }
+
+    /// Take the pending exception to be handled by the clauses of a `try`
+    /// block, returning a reference to it of the type
+    /// `self.reference_type(WasmHeapType::Extern)`.
+    fn translate_catch_exception(
+        &mut self,
+        builder: &mut FunctionBuilder,
+    ) -> WasmResult<ir::Value> {
+        let _ = builder;
+        Err(WasmError::Unsupported("wasm exceptions".to_string()))
+    }
+
+    /// Returns an `i32` value which is nonzero if the caught exception `exn`
+    /// has the tag `tag_index`.
+    fn translate_exception_has_tag(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        exn: ir::Value,
+        tag_index: u32,
+    ) -> WasmResult<ir::Value> {
+        let _ = (builder, exn, tag_index);
+        Err(WasmError::Unsupported("wasm exceptions".to_string()))
+    }
+
+    /// Load the payload of the caught exception `exn`, which has the tag
+    /// `tag_index`.
+    fn translate_exception_payload(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        exn: ir::Value,
+        tag_index: u32,
+    ) -> WasmResult<Vec<ir::Value>> {
+        let _ = (builder, exn, tag_index);
+        Err(WasmError::Unsupported("wasm exceptions".to_string()))
+    }
+
     /// TODO(dhil): write documentation.
     fn translate_cont_new(
         &mut self,
@@ -786,6 +888,7 @@ pub trait FuncEnvironment: TargetEnvironment {
         _val_size: u8,
         _addr: ir::Value,
         _offset: u64,
+        _mem_index: MemoryIndex,
     ) {
     }
 
@@ -796,6 +899,7 @@ pub trait FuncEnvironment: TargetEnvironment {
         _val_size: u8,
         _addr: ir::Value,
         _offset: u64,
+        _mem_index: MemoryIndex,
     ) {
     }
 
@@ -285,6 +285,14 @@ fn parse_function_body<FE: FuncEnvironment + ?Sized>(
     // or the end of the function is unreachable.
     state.stack.clear();
 
+    // An exception raised outside of any `try` block unwinds the frame.
+    if let Some(unwind_block) = state.unwind_block.take() {
+        builder.switch_to_block(unwind_block);
+        builder.seal_block(unwind_block);
+        environ.translate_unwind(builder)?;
+        builder.ins().trap(ir::TrapCode::UnreachableCodeReached);
+    }
+
     Ok(())
 }
 
@@ -7,6 +7,7 @@ use crate::environ::{FuncEnvironment, GlobalVariable};
 use crate::{FuncIndex, GlobalIndex, Heap, MemoryIndex, TableIndex, TypeIndex, WasmResult};
 use crate::{HashMap, Occupied, Vacant};
 use cranelift_codegen::ir::{self, Block, Inst, Value};
+use cranelift_frontend::FunctionBuilder;
 use std::vec::Vec;
 
 /// Information about the presence of an associated `else` for an `if`, or the
@@ -39,15 +40,34 @@ pub enum ElseData {
     },
 }
 
-/// A control stack frame can be an `if`, a `block` or a `loop`, each one having the following
-/// fields:
+/// The state of the exception handler of a `try` block.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TryHandler {
+    /// Where exceptions raised within the body of the `try` are dispatched
+    /// from. This is created the first time something within the body may
+    /// throw.
+    pub landing_pad: Option<Block>,
+    /// Have we finished translating the body and started translating the
+    /// `catch` and `catch_all` clauses?
+    pub in_clauses: bool,
+    /// The exception being handled, within the clauses.
+    pub exception: Option<Value>,
+    /// Where an exception that no clause so far has caught continues to be
+    /// dispatched. This is `None` if no exception can reach the remaining
+    /// clauses, which includes after a `catch_all`.
+    pub next_clause: Option<Block>,
+}
+
+/// A control stack frame can be an `if`, a `block`, a `loop` or a `try`, each one having the
+/// following fields:
 ///
 /// - `destination`: reference to the `Block` that will hold the code after the control block;
 /// - `num_return_values`: number of values returned by the control block;
 /// - `original_stack_size`: size of the value stack at the beginning of the control block.
 ///
 /// The `loop` frame has a `header` field that references the `Block` that contains the beginning
-/// of the body of the loop.
+/// of the body of the loop, and the `try` frame has a `handler` field that tracks the translation
+/// of its exception handler.
 #[derive(Debug)]
 pub enum ControlStackFrame {
     If {
@@ -84,6 +104,14 @@ pub enum ControlStackFrame {
         num_return_values: usize,
         original_stack_size: usize,
     },
+    Try {
+        destination: Block,
+        num_param_values: usize,
+        num_return_values: usize,
+        original_stack_size: usize,
+        exit_is_branched_to: bool,
+        handler: TryHandler,
+    },
 }
 
 /// Helper methods for the control stack objects.
@@ -98,6 +126,9 @@ impl ControlStackFrame {
             }
             | Self::Loop {
                 num_return_values, ..
+            }
+            | Self::Try {
+                num_return_values, ..
             } => num_return_values,
         }
     }
@@ -111,6 +142,9 @@ impl ControlStackFrame {
             }
             | Self::Loop {
                 num_param_values, ..
+            }
+            | Self::Try {
+                num_param_values, ..
             } => num_param_values,
         }
     }
@@ -118,12 +152,15 @@ impl ControlStackFrame {
         match *self {
             Self::If { destination, .. }
             | Self::Block { destination, .. }
-            | Self::Loop { destination, .. } => destination,
+            | Self::Loop { destination, .. }
+            | Self::Try { destination, .. } => destination,
         }
     }
     pub fn br_destination(&self) -> Block {
         match *self {
-            Self::If { destination, .. } | Self::Block { destination, .. } => destination,
+            Self::If { destination, .. }
+            | Self::Block { destination, .. }
+            | Self::Try { destination, .. } => destination,
             Self::Loop { header, .. } => header,
         }
     }
@@ -142,12 +179,16 @@ impl ControlStackFrame {
             | Self::Loop {
                 original_stack_size,
                 ..
+            }
+            | Self::Try {
+                original_stack_size,
+                ..
             } => original_stack_size,
         }
     }
     pub fn is_loop(&self) -> bool {
         match *self {
-            Self::If { .. } | Self::Block { .. } => false,
+            Self::If { .. } | Self::Block { .. } | Self::Try { .. } => false,
             Self::Loop { .. } => true,
         }
     }
@@ -161,6 +202,10 @@ impl ControlStackFrame {
             | Self::Block {
                 exit_is_branched_to,
                 ..
+            }
+            | Self::Try {
+                exit_is_branched_to,
+                ..
             } => exit_is_branched_to,
             Self::Loop { .. } => false,
         }
@@ -175,6 +220,10 @@ impl ControlStackFrame {
             | Self::Block {
                 ref mut exit_is_branched_to,
                 ..
+            }
+            | Self::Try {
+                ref mut exit_is_branched_to,
+                ..
             } => *exit_is_branched_to = true,
             Self::Loop { .. } => {}
         }
@@ -241,6 +290,10 @@ pub struct FuncTranslationState {
     // `FuncEnvironment::make_direct_func()`.
     // Stores both the function reference and the number of WebAssembly arguments
     functions: HashMap<FuncIndex, (ir::FuncRef, usize)>,
+
+    /// The block which unwinds the frame with an exception pending, created
+    /// the first time something outside of any `try` block may throw.
+    pub(crate) unwind_block: Option<Block>,
 }
 
 // Public methods that are exposed to non-`cranelift_wasm` API consumers.
@@ -250,6 +303,12 @@ impl FuncTranslationState {
     pub fn reachable(&self) -> bool {
         self.reachable
     }
+
+    /// The values currently on the operand stack, from the bottom to the top.
+    #[inline]
+    pub fn value_stack(&self) -> &[Value] {
+        &self.stack
+    }
 }
 
 impl FuncTranslationState {
@@ -264,6 +323,7 @@ impl FuncTranslationState {
             tables: HashMap::new(),
             signatures: HashMap::new(),
             functions: HashMap::new(),
+            unwind_block: None,
         }
     }
 
@@ -276,6 +336,7 @@ impl FuncTranslationState {
         self.tables.clear();
         self.signatures.clear();
         self.functions.clear();
+        self.unwind_block = None;
     }
 
     /// Initialize the state for compiling a function with the given signature.
@@ -437,6 +498,57 @@ impl FuncTranslationState {
             blocktype,
         });
     }
+
+    /// Push a try on the control stack.
+    pub(crate) fn push_try(
+        &mut self,
+        following_code: Block,
+        num_param_types: usize,
+        num_result_types: usize,
+    ) {
+        debug_assert!(num_param_types <= self.stack.len());
+        self.control_stack.push(ControlStackFrame::Try {
+            destination: following_code,
+            original_stack_size: self.stack.len() - num_param_types,
+            num_param_values: num_param_types,
+            num_return_values: num_result_types,
+            exit_is_branched_to: false,
+            handler: TryHandler::default(),
+        });
+    }
+
+    /// Are we translating the body of a `try` block, whose handler catches the
+    /// exceptions raised here?
+    pub(crate) fn in_try_body(&self) -> bool {
+        self.control_stack.iter().any(|frame| match frame {
+            ControlStackFrame::Try { handler, .. } => !handler.in_clauses,
+            _ => false,
+        })
+    }
+
+    /// Get the block to branch to when an exception is raised within the
+    /// outermost `frames` frames of the control stack: the landing pad of the
+    /// innermost `try` block whose body is being translated, or the block
+    /// unwinding the frame if there is none. The block is created if
+    /// necessary.
+    pub(crate) fn exception_handler(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        frames: usize,
+    ) -> Block {
+        for frame in self.control_stack[..frames].iter_mut().rev() {
+            if let ControlStackFrame::Try { handler, .. } = frame {
+                if !handler.in_clauses {
+                    return *handler
+                        .landing_pad
+                        .get_or_insert_with(|| builder.create_block());
+                }
+            }
+        }
+        *self
+            .unwind_block
+            .get_or_insert_with(|| builder.create_block())
+    }
 }
 
 /// Methods for handling entity references.
@@ -1,6 +1,6 @@
 //! Module for configuring the cache system.
 
-use super::Worker;
+use super::{CacheBackend, Worker};
 use anyhow::{anyhow, bail, Context, Result};
 use directories_next::ProjectDirs;
 use log::{trace, warn};
@@ -87,10 +87,14 @@ pub struct CacheConfig {
         deserialize_with = "deserialize_percent"
     )]
     files_total_size_limit_percent_if_deleting: Option<u8>,
+    #[serde(default, rename = "incremental-compilation")]
+    incremental_compilation: bool,
 
     #[serde(skip)]
     worker: Option<Worker>,
     #[serde(skip)]
+    backend: Option<Arc<dyn CacheBackend>>,
+    #[serde(skip)]
     state: Arc<CacheState>,
 }
 
@@ -306,9 +310,16 @@ impl CacheConfig {
         self.enabled
     }
 
+    /// Returns true if the compiled code of individual functions should be
+    /// cached in the cache directory, with an
+    /// [`IncrementalCacheStore`](crate::IncrementalCacheStore).
+    pub fn incremental_compilation(&self) -> bool {
+        self.enabled && self.incremental_compilation
+    }
+
     /// Returns path to the cache directory.
     ///
-    /// Panics if the cache is disabled.
+    /// Panics if the cache is disabled, or uses a custom [`CacheBackend`].
     pub fn directory(&self) -> &PathBuf {
         self.directory
             .as_ref()
@@ -331,11 +342,24 @@ impl CacheConfig {
             files_total_size_soft_limit: None,
             file_count_limit_percent_if_deleting: None,
             files_total_size_limit_percent_if_deleting: None,
+            incremental_compilation: false,
             worker: None,
+            backend: None,
             state: Arc::new(CacheState::default()),
         }
     }
 
+    /// Creates a new set of configuration which represents an enabled cache
+    /// storing its data in `backend` rather than in a cache directory.
+    ///
+    /// No worker is spawned for such a cache, so `backend` is responsible for
+    /// evicting data as it sees fit.
+    pub fn new_with_backend(backend: Arc<dyn CacheBackend>) -> Self {
+        let mut conf = Self::new_cache_enabled_template();
+        conf.backend = Some(backend);
+        conf
+    }
+
     fn new_cache_enabled_template() -> Self {
         let mut conf = Self::new_cache_disabled();
         conf.enabled = true;
@@ -385,14 +409,30 @@ impl CacheConfig {
         self.state.misses.load(SeqCst)
     }
 
-    pub(crate) fn on_cache_get_async(&self, path: impl AsRef<Path>) {
+    /// Returns the custom backend of the cache, if it doesn't use the cache
+    /// directory.
+    pub fn backend(&self) -> Option<&Arc<dyn CacheBackend>> {
+        self.backend.as_ref()
+    }
+
+    /// Returns the path of the file storing the data for `key` in the cache
+    /// directory.
+    pub(crate) fn module_path(&self, key: &str) -> PathBuf {
+        self.directory().join("modules").join(key)
+    }
+
+    pub(crate) fn on_cache_get_async(&self, key: &str) {
         self.state.hits.fetch_add(1, SeqCst);
-        self.worker().on_cache_get_async(path)
+        if self.backend.is_none() {
+            self.worker().on_cache_get_async(self.module_path(key))
+        }
     }
 
-    pub(crate) fn on_cache_update_async(&self, path: impl AsRef<Path>) {
+    pub(crate) fn on_cache_update_async(&self, key: &str) {
         self.state.misses.fetch_add(1, SeqCst);
-        self.worker().on_cache_update_async(path)
+        if self.backend.is_none() {
+            self.worker().on_cache_update_async(self.module_path(key))
+        }
     }
 
     fn load_and_parse_file(config_file: Option<&Path>) -> Result<Self> {
@@ -523,3 +523,45 @@ fn test_percent_settings() {
         cd
     );
 }
+
+#[test]
+fn test_incremental_compilation_settings() {
+    let (_td, cd, cp) = test_prolog();
+    let conf = load_config!(
+        cp,
+        "[cache]\n\
+         enabled = true\n\
+         directory = {cache_dir}",
+        cd
+    );
+    assert!(!conf.incremental_compilation());
+
+    let conf = load_config!(
+        cp,
+        "[cache]\n\
+         enabled = true\n\
+         directory = {cache_dir}\n\
+         incremental-compilation = true",
+        cd
+    );
+    assert!(conf.incremental_compilation());
+
+    let conf = load_config!(
+        cp,
+        "[cache]\n\
+         enabled = false\n\
+         directory = {cache_dir}\n\
+         incremental-compilation = true",
+        cd
+    );
+    assert!(!conf.incremental_compilation());
+
+    bad_config!(
+        cp,
+        "[cache]\n\
+         enabled = true\n\
+         directory = {cache_dir}\n\
+         incremental-compilation = 'yes'",
+        cd
+    );
+}
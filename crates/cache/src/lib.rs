@@ -1,26 +1,30 @@
 use base64::Engine;
-use log::{debug, trace, warn};
+use log::warn;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::fs;
 use std::hash::Hash;
 use std::hash::Hasher;
 use std::io::Write;
-use std::path::{Path, PathBuf};
+use std::path::Path;
 
 #[macro_use] // for tests
 mod config;
+mod store;
 mod worker;
 
 pub use config::{create_new_config, CacheConfig};
+use store::FileSystemCacheBackend;
+pub use store::{CacheBackend, InMemoryCacheBackend, IncrementalCacheStore};
 use worker::Worker;
 
 /// Module level cache entry.
 pub struct ModuleCacheEntry<'config>(Option<ModuleCacheEntryInner<'config>>);
 
 struct ModuleCacheEntryInner<'config> {
-    root_path: PathBuf,
+    compiler_dir: String,
     cache_config: &'config CacheConfig,
+    fs_backend: FileSystemCacheBackend<'config>,
 }
 
 struct Sha256Hasher(Sha256);
@@ -89,18 +93,17 @@ impl<'config> ModuleCacheEntry<'config> {
         // standard encoding uses '/' which can't be used for filename
         let hash = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(&hash);
 
-        if let Some(cached_val) = inner.get_data(&hash) {
+        let key = inner.key(&hash);
+        if let Some(cached_val) = inner.backend().get(&key) {
             if let Some(val) = deserialize(state, cached_val) {
-                let mod_cache_path = inner.root_path.join(&hash);
-                inner.cache_config.on_cache_get_async(&mod_cache_path); // call on success
+                inner.cache_config.on_cache_get_async(&key); // call on success
                 return Ok(val);
             }
         }
         let val_to_cache = compute(state)?;
         if let Some(bytes) = serialize(state, &val_to_cache) {
-            if inner.update_data(&hash, &bytes).is_some() {
-                let mod_cache_path = inner.root_path.join(&hash);
-                inner.cache_config.on_cache_update_async(&mod_cache_path); // call on success
+            if inner.backend().insert(&key, &bytes) {
+                inner.cache_config.on_cache_update_async(&key); // call on success
             }
         }
         Ok(val_to_cache)
@@ -109,95 +112,60 @@ impl<'config> ModuleCacheEntry<'config> {
 
 impl<'config> ModuleCacheEntryInner<'config> {
     fn new<'data>(compiler_name: &str, cache_config: &'config CacheConfig) -> Self {
-        // If debug assertions are enabled then assume that we're some sort of
-        // local build. We don't want local builds to stomp over caches between
-        // builds, so just use a separate cache directory based on the mtime of
-        // our executable, which should roughly correlate with "you changed the
-        // source code so you get a different directory".
-        //
-        // Otherwise if this is a release build we use the `GIT_REV` env var
-        // which is either the git rev if installed from git or the crate
-        // version if installed from crates.io.
-        let compiler_dir = if cfg!(debug_assertions) {
-            fn self_mtime() -> Option<String> {
-                let path = std::env::current_exe().ok()?;
-                let metadata = path.metadata().ok()?;
-                let mtime = metadata.modified().ok()?;
-                Some(match mtime.duration_since(std::time::UNIX_EPOCH) {
-                    Ok(dur) => format!("{}", dur.as_millis()),
-                    Err(err) => format!("m{}", err.duration().as_millis()),
-                })
-            }
-            let self_mtime = self_mtime().unwrap_or("no-mtime".to_string());
-            format!(
-                "{comp_name}-{comp_ver}-{comp_mtime}",
-                comp_name = compiler_name,
-                comp_ver = env!("GIT_REV"),
-                comp_mtime = self_mtime,
-            )
-        } else {
-            format!(
-                "{comp_name}-{comp_ver}",
-                comp_name = compiler_name,
-                comp_ver = env!("GIT_REV"),
-            )
-        };
-        let root_path = cache_config.directory().join("modules").join(compiler_dir);
-
         Self {
-            root_path,
+            compiler_dir: compiler_dir(compiler_name),
             cache_config,
+            fs_backend: FileSystemCacheBackend::new(cache_config),
         }
     }
 
-    fn get_data(&self, hash: &str) -> Option<Vec<u8>> {
-        let mod_cache_path = self.root_path.join(hash);
-        trace!("get_data() for path: {}", mod_cache_path.display());
-        let compressed_cache_bytes = fs::read(&mod_cache_path).ok()?;
-        let cache_bytes = zstd::decode_all(&compressed_cache_bytes[..])
-            .map_err(|err| warn!("Failed to decompress cached code: {}", err))
-            .ok()?;
-        Some(cache_bytes)
+    /// Returns the key of the data for `hash` in the backend.
+    fn key(&self, hash: &str) -> String {
+        format!("{}/{}", self.compiler_dir, hash)
     }
 
-    fn update_data(&self, hash: &str, serialized_data: &[u8]) -> Option<()> {
-        let mod_cache_path = self.root_path.join(hash);
-        trace!("update_data() for path: {}", mod_cache_path.display());
-        let compressed_data = zstd::encode_all(
-            &serialized_data[..],
-            self.cache_config.baseline_compression_level(),
-        )
-        .map_err(|err| warn!("Failed to compress cached code: {}", err))
-        .ok()?;
-
-        // Optimize syscalls: first, try writing to disk. It should succeed in most cases.
-        // Otherwise, try creating the cache directory and retry writing to the file.
-        if fs_write_atomic(&mod_cache_path, "mod", &compressed_data) {
-            return Some(());
+    fn backend(&self) -> &dyn CacheBackend {
+        match self.cache_config.backend() {
+            Some(backend) => &**backend,
+            None => &self.fs_backend,
         }
+    }
+}
 
-        debug!(
-            "Attempting to create the cache directory, because \
-             failed to write cached code to disk, path: {}",
-            mod_cache_path.display(),
-        );
-
-        let cache_dir = mod_cache_path.parent().unwrap();
-        fs::create_dir_all(cache_dir)
-            .map_err(|err| {
-                warn!(
-                    "Failed to create cache directory, path: {}, message: {}",
-                    cache_dir.display(),
-                    err
-                )
+/// Returns the name of the directory holding the data of `compiler_name`.
+fn compiler_dir(compiler_name: &str) -> String {
+    // If debug assertions are enabled then assume that we're some sort of
+    // local build. We don't want local builds to stomp over caches between
+    // builds, so just use a separate cache directory based on the mtime of
+    // our executable, which should roughly correlate with "you changed the
+    // source code so you get a different directory".
+    //
+    // Otherwise if this is a release build we use the `GIT_REV` env var
+    // which is either the git rev if installed from git or the crate
+    // version if installed from crates.io.
+    if cfg!(debug_assertions) {
+        fn self_mtime() -> Option<String> {
+            let path = std::env::current_exe().ok()?;
+            let metadata = path.metadata().ok()?;
+            let mtime = metadata.modified().ok()?;
+            Some(match mtime.duration_since(std::time::UNIX_EPOCH) {
+                Ok(dur) => format!("{}", dur.as_millis()),
+                Err(err) => format!("m{}", err.duration().as_millis()),
             })
-            .ok()?;
-
-        if fs_write_atomic(&mod_cache_path, "mod", &compressed_data) {
-            Some(())
-        } else {
-            None
         }
+        let self_mtime = self_mtime().unwrap_or("no-mtime".to_string());
+        format!(
+            "{comp_name}-{comp_ver}-{comp_mtime}",
+            comp_name = compiler_name,
+            comp_ver = env!("GIT_REV"),
+            comp_mtime = self_mtime,
+        )
+    } else {
+        format!(
+            "{comp_name}-{comp_ver}",
+            comp_name = compiler_name,
+            comp_ver = env!("GIT_REV"),
+        )
     }
 }
 
@@ -0,0 +1,209 @@
+//! Storage backends for the cache.
+
+use crate::{compiler_dir, fs_write_atomic, CacheConfig};
+use base64::Engine;
+use log::{debug, trace, warn};
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// A backend storing the serialized artifacts of the cache.
+///
+/// The default backend stores artifacts in the cache directory, compressed,
+/// and relies on a worker thread to clean it up. Embedders can supply their
+/// own backend, such as a shared content-addressed store or an in-memory
+/// LRU, with [`CacheConfig::new_with_backend`].
+///
+/// Keys are made of URL-safe characters, and of `/` separators. They
+/// identify the compiler, its version and the input that was compiled, so
+/// the data stored for a given key never changes.
+pub trait CacheBackend: Send + Sync + Debug {
+    /// Returns the data stored for `key`, if any.
+    fn get(&self, key: &str) -> Option<Vec<u8>>;
+
+    /// Stores `value` for `key`.
+    ///
+    /// Returns false when the value couldn't be stored.
+    fn insert(&self, key: &str, value: &[u8]) -> bool;
+}
+
+/// The default backend, which stores compressed artifacts in the `modules`
+/// directory of the cache directory.
+#[derive(Debug)]
+pub(crate) struct FileSystemCacheBackend<'config> {
+    cache_config: &'config CacheConfig,
+}
+
+impl<'config> FileSystemCacheBackend<'config> {
+    pub(crate) fn new(cache_config: &'config CacheConfig) -> Self {
+        Self { cache_config }
+    }
+}
+
+impl CacheBackend for FileSystemCacheBackend<'_> {
+    fn get(&self, key: &str) -> Option<Vec<u8>> {
+        let mod_cache_path = self.cache_config.module_path(key);
+        trace!("get_data() for path: {}", mod_cache_path.display());
+        read_compressed(&mod_cache_path)
+    }
+
+    fn insert(&self, key: &str, serialized_data: &[u8]) -> bool {
+        let mod_cache_path = self.cache_config.module_path(key);
+        trace!("update_data() for path: {}", mod_cache_path.display());
+        write_compressed(
+            &mod_cache_path,
+            serialized_data,
+            self.cache_config.baseline_compression_level(),
+        )
+    }
+}
+
+/// A backend keeping artifacts in memory, uncompressed, for as long as it is
+/// alive.
+///
+/// Nothing is ever evicted, so this backend is meant for tests and for
+/// short-lived processes compiling a bounded set of modules, such as sharing
+/// compiled artifacts between several engines.
+#[derive(Debug, Default)]
+pub struct InMemoryCacheBackend(pub(crate) Mutex<HashMap<String, Vec<u8>>>);
+
+impl InMemoryCacheBackend {
+    /// Creates an empty backend.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of artifacts stored.
+    pub fn len(&self) -> usize {
+        self.0.lock().unwrap().len()
+    }
+
+    /// Returns whether no artifacts are stored.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl CacheBackend for InMemoryCacheBackend {
+    fn get(&self, key: &str) -> Option<Vec<u8>> {
+        self.0.lock().unwrap().get(key).cloned()
+    }
+
+    fn insert(&self, key: &str, value: &[u8]) -> bool {
+        self.0
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), value.to_vec());
+        true
+    }
+}
+
+/// A store for Cranelift's incremental compilation cache, which keeps the
+/// compiled code of individual functions in the cache directory.
+///
+/// Entries are stored compressed in the `incremental` directory of the cache
+/// directory, next to the compiled modules. The cache worker treats them like
+/// any other cache file: they count towards the limits of the cache, and are
+/// evicted and recompressed in the same way.
+///
+/// Wasmtime uses this store when the `incremental-compilation` setting of the
+/// cache configuration is enabled.
+#[derive(Debug)]
+pub struct IncrementalCacheStore {
+    cache_config: CacheConfig,
+    compiler_dir: String,
+}
+
+impl IncrementalCacheStore {
+    /// Creates a store which keeps its entries in the cache directory of
+    /// `cache_config`.
+    ///
+    /// Panics if the cache is disabled, or uses a custom [`CacheBackend`].
+    pub fn new(cache_config: &CacheConfig) -> Self {
+        assert!(cache_config.enabled() && cache_config.backend().is_none());
+        Self {
+            cache_config: cache_config.clone(),
+            compiler_dir: compiler_dir("cranelift"),
+        }
+    }
+
+    fn path(&self, key: &[u8]) -> PathBuf {
+        let hash: [u8; 32] = Sha256::digest(key).into();
+        // standard encoding uses '/' which can't be used for filename
+        let hash = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(hash);
+        self.cache_config
+            .directory()
+            .join("incremental")
+            .join(&self.compiler_dir)
+            .join(hash)
+    }
+}
+
+impl wasmtime_environ::CacheStore for IncrementalCacheStore {
+    fn get(&self, key: &[u8]) -> Option<Cow<'_, [u8]>> {
+        let path = self.path(key);
+        trace!("get() for incremental cache path: {}", path.display());
+        let data = read_compressed(&path)?;
+        self.cache_config.worker().on_cache_get_async(path);
+        Some(Cow::Owned(data))
+    }
+
+    fn insert(&self, key: &[u8], value: Vec<u8>) -> bool {
+        let path = self.path(key);
+        trace!("insert() for incremental cache path: {}", path.display());
+        if !write_compressed(
+            &path,
+            &value,
+            self.cache_config.baseline_compression_level(),
+        ) {
+            return false;
+        }
+        self.cache_config.worker().on_cache_update_async(path);
+        true
+    }
+}
+
+fn read_compressed(path: &Path) -> Option<Vec<u8>> {
+    let compressed_cache_bytes = fs::read(path).ok()?;
+    let cache_bytes = zstd::decode_all(&compressed_cache_bytes[..])
+        .map_err(|err| warn!("Failed to decompress cached code: {}", err))
+        .ok()?;
+    Some(cache_bytes)
+}
+
+fn write_compressed(path: &Path, serialized_data: &[u8], compression_level: i32) -> bool {
+    let compressed_data = match zstd::encode_all(serialized_data, compression_level) {
+        Ok(data) => data,
+        Err(err) => {
+            warn!("Failed to compress cached code: {}", err);
+            return false;
+        }
+    };
+
+    // Optimize syscalls: first, try writing to disk. It should succeed in most cases.
+    // Otherwise, try creating the cache directory and retry writing to the file.
+    fs_write_atomic(path, "mod", &compressed_data) || {
+        debug!(
+            "Attempting to create the cache directory, because \
+             failed to write cached code to disk, path: {}",
+            path.display(),
+        );
+
+        let cache_dir = path.parent().unwrap();
+        match fs::create_dir_all(cache_dir) {
+            Ok(()) => fs_write_atomic(path, "mod", &compressed_data),
+            Err(err) => {
+                warn!(
+                    "Failed to create cache directory, path: {}, message: {}",
+                    cache_dir.display(),
+                    err
+                );
+                false
+            }
+        }
+    }
+}
@@ -1,6 +1,11 @@
 use super::config::tests::test_prolog;
 use super::*;
+use filetime::{set_file_mtime, FileTime};
 use std::fs;
+use std::process;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use wasmtime_environ::CacheStore as _;
 
 // Since cache system is a global thing, each test needs to be run in seperate process.
 // So, init() tests are run as integration tests.
@@ -90,3 +95,131 @@ fn test_write_read_cache() {
     entry1.get_data::<_, i32, i32>(4, |_| panic!()).unwrap();
     entry2.get_data::<_, i32, i32>(1, |_| panic!()).unwrap();
 }
+
+#[test]
+fn test_write_read_custom_backend() {
+    let backend = Arc::new(InMemoryCacheBackend::new());
+    let cache_config = CacheConfig::new_with_backend(backend.clone());
+    assert!(cache_config.enabled());
+
+    let entry1 = ModuleCacheEntry::new("test-1", &cache_config);
+    let entry2 = ModuleCacheEntry::new("test-2", &cache_config);
+
+    entry1.get_data::<_, i32, i32>(1, |_| Ok(100)).unwrap();
+    assert_eq!(
+        entry1.get_data::<_, i32, i32>(1, |_| panic!()).unwrap(),
+        100
+    );
+    entry1.get_data::<_, i32, i32>(2, |_| Ok(200)).unwrap();
+    assert_eq!(
+        entry1.get_data::<_, i32, i32>(2, |_| panic!()).unwrap(),
+        200
+    );
+    entry2.get_data::<_, i32, i32>(1, |_| Ok(300)).unwrap();
+    assert_eq!(
+        entry2.get_data::<_, i32, i32>(1, |_| panic!()).unwrap(),
+        300
+    );
+    assert_eq!(
+        entry1.get_data::<_, i32, i32>(1, |_| panic!()).unwrap(),
+        100
+    );
+
+    assert_eq!(backend.len(), 3);
+    assert_eq!(cache_config.cache_misses(), 3);
+    assert_eq!(cache_config.cache_hits(), 4);
+
+    // Data which can't be deserialized is recomputed and replaced.
+    for value in backend.0.lock().unwrap().values_mut() {
+        value.clear();
+    }
+    assert_eq!(entry1.get_data::<_, i32, i32>(1, |_| Ok(100)).unwrap(), 100);
+    assert_eq!(
+        entry1.get_data::<_, i32, i32>(1, |_| panic!()).unwrap(),
+        100
+    );
+}
+
+#[test]
+fn test_write_read_incremental_store() {
+    let (_tempdir, cache_dir, config_path) = test_prolog();
+    let cache_config = load_config!(
+        config_path,
+        "[cache]\n\
+         enabled = true\n\
+         directory = {cache_dir}\n\
+         incremental-compilation = true\n",
+        cache_dir
+    );
+    assert!(cache_config.incremental_compilation());
+
+    let store = IncrementalCacheStore::new(&cache_config);
+    assert!(store.get(b"key-1").is_none());
+    assert!(store.insert(b"key-1", vec![1; 100]));
+    assert!(store.insert(b"key-2", vec![2; 100]));
+    assert_eq!(store.get(b"key-1").as_deref(), Some(&[1; 100][..]));
+    assert_eq!(store.get(b"key-2").as_deref(), Some(&[2; 100][..]));
+
+    // Entries outlive the store, so the next run of Wasmtime can reuse them.
+    drop(store);
+    let store = IncrementalCacheStore::new(&cache_config);
+    assert_eq!(store.get(b"key-1").as_deref(), Some(&[1; 100][..]));
+    assert!(store.get(b"key-3").is_none());
+
+    // The cache worker keeps statistics for the entries like for modules.
+    let worker = cache_config.worker();
+    worker.wait_for_all_events_handled();
+    assert_eq!(worker.events_dropped(), 0);
+    let incremental_dir = cache_dir.join("incremental");
+    let mut entries = Vec::new();
+    for dir in fs::read_dir(&incremental_dir).unwrap() {
+        for file in fs::read_dir(dir.unwrap().path()).unwrap() {
+            entries.push(file.unwrap().path());
+        }
+    }
+    entries.sort();
+    assert_eq!(entries.len(), 4);
+    for pair in entries.chunks(2) {
+        assert_eq!(pair[1], pair[0].with_extension("stats"));
+    }
+}
+
+#[test]
+fn test_incremental_store_cleanup() {
+    let (_tempdir, cache_dir, config_path) = test_prolog();
+    let cache_config = load_config!(
+        config_path,
+        "[cache]\n\
+         enabled = true\n\
+         directory = {cache_dir}\n\
+         incremental-compilation = true\n\
+         file-count-soft-limit = '1'\n\
+         file-count-limit-percent-if-deleting = '100%'\n",
+        cache_dir
+    );
+    let worker = cache_config.worker();
+
+    let store = IncrementalCacheStore::new(&cache_config);
+    assert!(store.insert(b"key", vec![1; 100]));
+    worker.wait_for_all_events_handled();
+
+    // Make the entry older than the module cached below, and let the cache
+    // be cleaned up again.
+    let an_hour_ago = FileTime::from_system_time(SystemTime::now() - Duration::from_secs(60 * 60));
+    for dir in fs::read_dir(cache_dir.join("incremental")).unwrap() {
+        for file in fs::read_dir(dir.unwrap().path()).unwrap() {
+            set_file_mtime(file.unwrap().path(), an_hour_ago).unwrap();
+        }
+    }
+    fs::remove_file(cache_dir.join(format!(".cleanup.wip-{}", process::id()))).unwrap();
+
+    let entry = ModuleCacheEntry::new("test", &cache_config);
+    entry.get_data::<_, i32, i32>(1, |_| Ok(100)).unwrap();
+    worker.wait_for_all_events_handled();
+    assert_eq!(worker.events_dropped(), 0);
+
+    // Both stores share the limits of the cache, and the least recently used
+    // file is evicted.
+    assert!(store.get(b"key").is_none());
+    assert_eq!(entry.get_data::<_, i32, i32>(1, |_| panic!()).unwrap(), 100);
+}
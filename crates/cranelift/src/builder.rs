@@ -20,7 +20,6 @@ struct Builder {
     linkopts: LinkOptions,
     cache_store: Option<Arc<dyn CacheStore>>,
     clif_dir: Option<path::PathBuf>,
-    wmemcheck: bool,
 }
 
 #[derive(Clone, Default)]
@@ -43,7 +42,6 @@ pub fn builder() -> Box<dyn CompilerBuilder> {
         linkopts: LinkOptions::default(),
         cache_store: None,
         clif_dir: None,
-        wmemcheck: false,
     })
 }
 
@@ -93,7 +91,6 @@ impl CompilerBuilder for Builder {
             self.cache_store.clone(),
             self.linkopts.clone(),
             self.clif_dir.clone(),
-            self.wmemcheck,
         )))
     }
 
@@ -108,10 +105,6 @@ impl CompilerBuilder for Builder {
         self.cache_store = Some(cache_store);
         Ok(())
     }
-
-    fn wmemcheck(&mut self, enable: bool) {
-        self.wmemcheck = enable;
-    }
 }
 
 impl fmt::Debug for Builder {
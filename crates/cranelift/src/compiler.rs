@@ -8,8 +8,8 @@ use cranelift_codegen::ir::{
 };
 use cranelift_codegen::isa::{OwnedTargetIsa, TargetIsa};
 use cranelift_codegen::print_errors::pretty_error;
-use cranelift_codegen::Context;
-use cranelift_codegen::{CompiledCode, MachStackMap};
+use cranelift_codegen::{CompiledCode, Final, MachBufferFinalized, MachStackMap};
+use cranelift_codegen::{Context, Inline, InlineCommand};
 use cranelift_entity::{EntityRef, PrimaryMap};
 use cranelift_frontend::FunctionBuilder;
 use cranelift_wasm::{
@@ -19,19 +19,22 @@ use cranelift_wasm::{
 use object::write::{Object, StandardSegment, SymbolId};
 use object::{RelocationEncoding, RelocationKind, SectionKind};
 use std::any::Any;
+use std::borrow::Cow;
 use std::cmp;
 use std::collections::BTreeMap;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::convert::TryFrom;
 use std::mem;
+use std::ops::Range;
 use std::path;
 use std::sync::{Arc, Mutex};
-use wasmparser::{FuncValidatorAllocations, FunctionBody};
+use std::time::{Duration, Instant};
+use wasmparser::FuncValidatorAllocations;
 use wasmtime_cranelift_shared::{CompiledFunction, ModuleTextBuilder};
 use wasmtime_environ::{
-    AddressMapSection, CacheStore, CompileError, FlagValue, FunctionBodyData, FunctionLoc,
-    ModuleTranslation, ModuleTypes, PtrSize, StackMapInformation, TrapEncodingBuilder, Tunables,
-    VMOffsets, WasmFunctionInfo,
+    AddressMapSection, CacheStore, CompileError, ExceptionLanding, FlagValue, FunctionBodyData,
+    FunctionLoc, FunctionStats, InliningCompiler, Module, ModuleTranslation, ModuleTypes, PtrSize,
+    StackMapInformation, TrapEncodingBuilder, Tunables, VMOffsets, WasmFunctionInfo,
 };
 
 #[cfg(feature = "component-model")]
@@ -71,7 +74,6 @@ pub(crate) struct Compiler {
     linkopts: LinkOptions,
     cache_store: Option<Arc<dyn CacheStore>>,
     clif_dir: Option<path::PathBuf>,
-    wmemcheck: bool,
 }
 
 impl Drop for Compiler {
@@ -109,7 +111,6 @@ impl Compiler {
         cache_store: Option<Arc<dyn CacheStore>>,
         linkopts: LinkOptions,
         clif_dir: Option<path::PathBuf>,
-        wmemcheck: bool,
     ) -> Compiler {
         Compiler {
             contexts: Default::default(),
@@ -118,7 +119,6 @@ impl Compiler {
             linkopts,
             cache_store,
             clif_dir,
-            wmemcheck,
         }
     }
 }
@@ -131,102 +131,12 @@ impl wasmtime_environ::Compiler for Compiler {
         input: FunctionBodyData<'_>,
         types: &ModuleTypes,
     ) -> Result<(WasmFunctionInfo, Box<dyn Any + Send>), CompileError> {
-        let isa = &*self.isa;
-        let module = &translation.module;
-        let func_index = module.func_index(func_index);
-        let sig = translation.module.functions[func_index].signature;
-        let wasm_func_ty = &types[sig];
-
+        let start = Instant::now();
         let mut compiler = self.function_compiler();
+        let body = compiler.translate(translation, func_index, input, types)?;
 
-        let context = &mut compiler.cx.codegen_context;
-        context.func.signature = wasm_call_signature(isa, wasm_func_ty, &self.tunables);
-        context.func.name = UserFuncName::User(UserExternalName {
-            namespace: 0,
-            index: func_index.as_u32(),
-        });
-
-        if self.tunables.generate_native_debuginfo {
-            context.func.collect_debug_info();
-        }
-
-        let mut func_env =
-            FuncEnvironment::new(isa, translation, types, &self.tunables, self.wmemcheck);
-
-        // The `stack_limit` global value below is the implementation of stack
-        // overflow checks in Wasmtime.
-        //
-        // The Wasm spec defines that stack overflows will raise a trap, and
-        // there's also an added constraint where as an embedder you frequently
-        // are running host-provided code called from wasm. WebAssembly and
-        // native code currently share the same call stack, so Wasmtime needs to
-        // make sure that host-provided code will have enough call-stack
-        // available to it.
-        //
-        // The way that stack overflow is handled here is by adding a prologue
-        // check to all functions for how much native stack is remaining. The
-        // `VMContext` pointer is the first argument to all functions, and the
-        // first field of this structure is `*const VMRuntimeLimits` and the
-        // first field of that is the stack limit. Note that the stack limit in
-        // this case means "if the stack pointer goes below this, trap". Each
-        // function which consumes stack space or isn't a leaf function starts
-        // off by loading the stack limit, checking it against the stack
-        // pointer, and optionally traps.
-        //
-        // This manual check allows the embedder to give wasm a relatively
-        // precise amount of stack allocation. Using this scheme we reserve a
-        // chunk of stack for wasm code relative from where wasm code was
-        // called. This ensures that native code called by wasm should have
-        // native stack space to run, and the numbers of stack spaces here
-        // should all be configurable for various embeddings.
-        //
-        // Note that this check is independent of each thread's stack guard page
-        // here. If the stack guard page is reached that's still considered an
-        // abort for the whole program since the runtime limits configured by
-        // the embedder should cause wasm to trap before it reaches that
-        // (ensuring the host has enough space as well for its functionality).
-        let vmctx = context
-            .func
-            .create_global_value(ir::GlobalValueData::VMContext);
-        let interrupts_ptr = context.func.create_global_value(ir::GlobalValueData::Load {
-            base: vmctx,
-            offset: i32::try_from(func_env.offsets.vmctx_runtime_limits())
-                .unwrap()
-                .into(),
-            global_type: isa.pointer_type(),
-            readonly: true,
-        });
-        let stack_limit = context.func.create_global_value(ir::GlobalValueData::Load {
-            base: interrupts_ptr,
-            offset: i32::try_from(func_env.offsets.ptr.vmruntime_limits_stack_limit())
-                .unwrap()
-                .into(),
-            global_type: isa.pointer_type(),
-            readonly: false,
-        });
-        context.func.stack_limit = Some(stack_limit);
-        let FunctionBodyData { validator, body } = input;
-        let mut validator =
-            validator.into_validator(mem::take(&mut compiler.cx.validator_allocations));
-        compiler.cx.func_translator.translate_body(
-            &mut validator,
-            body.clone(),
-            &mut context.func,
-            &mut func_env,
-        )?;
-
-        if let Some(path) = &self.clif_dir {
-            use std::io::Write;
-
-            let mut path = path.to_path_buf();
-            path.push(format!("wasm_func_{}", func_index.as_u32()));
-            path.set_extension("clif");
-
-            let mut output = std::fs::File::create(path).unwrap();
-            write!(output, "{}", context.func.display()).unwrap();
-        }
-
-        let (info, func) = compiler.finish_with_info(Some((&body, &self.tunables)))?;
+        let (mut info, func) = compiler.finish_with_info(Some((body, &self.tunables)))?;
+        info.stats.compile_time = Some(start.elapsed());
 
         let timing = cranelift_codegen::timing::take_current();
         log::debug!("{:?} translated in {:?}", func_index, timing.total());
@@ -531,6 +441,14 @@ impl wasmtime_environ::Compiler for Compiler {
         self.isa.is_branch_protection_enabled()
     }
 
+    fn inlining_compiler(&self) -> Option<&dyn InliningCompiler> {
+        if self.isa.flags().enable_inlining() {
+            Some(self)
+        } else {
+            None
+        }
+    }
+
     #[cfg(feature = "component-model")]
     fn component_compiler(&self) -> &dyn wasmtime_environ::component::ComponentCompiler {
         self
@@ -630,6 +548,130 @@ impl wasmtime_environ::Compiler for Compiler {
     }
 }
 
+impl InliningCompiler for Compiler {
+    fn translate_function(
+        &self,
+        translation: &ModuleTranslation<'_>,
+        func_index: DefinedFuncIndex,
+        input: FunctionBodyData<'_>,
+        types: &ModuleTypes,
+    ) -> Result<Box<dyn Any + Send + Sync>, CompileError> {
+        let start = Instant::now();
+        let mut compiler = self.function_compiler();
+        let body = compiler.translate(translation, func_index, input, types)?;
+        let func = mem::replace(&mut compiler.cx.codegen_context.func, ir::Function::new());
+        self.contexts.lock().unwrap().push(compiler.cx);
+
+        Ok(Box::new(TranslatedFunction {
+            func,
+            body,
+            exception_landings: compiler.exception_landings,
+            translate_time: start.elapsed(),
+        }))
+    }
+
+    fn compile_translated_function(
+        &self,
+        translation: &ModuleTranslation<'_>,
+        func_index: DefinedFuncIndex,
+        translated: &PrimaryMap<DefinedFuncIndex, Box<dyn Any + Send + Sync>>,
+        _types: &ModuleTypes,
+    ) -> Result<(WasmFunctionInfo, Box<dyn Any + Send>), CompileError> {
+        let start = Instant::now();
+        let translated_func = TranslatedFunction::get(translated, func_index);
+
+        let mut compiler = self.function_compiler();
+        compiler.exception_landings = translated_func.exception_landings.clone();
+        let context = &mut compiler.cx.codegen_context;
+        context.func = translated_func.func.clone();
+        let inliner = ModuleInliner {
+            module: &translation.module,
+            translated,
+            exception_landings: &mut compiler.exception_landings,
+        };
+        context
+            .inline(&*self.isa, inliner)
+            .map_err(|error| CompileError::Codegen(pretty_error(&context.func, error)))?;
+
+        let (mut info, func) =
+            compiler.finish_with_info(Some((translated_func.body.clone(), &self.tunables)))?;
+        info.stats.compile_time = Some(translated_func.translate_time + start.elapsed());
+
+        let timing = cranelift_codegen::timing::take_current();
+        log::debug!("{:?} translated in {:?}", func_index, timing.total());
+        log::trace!("{:?} timing info\n{}", func_index, timing);
+
+        Ok((info, Box::new(func)))
+    }
+}
+
+/// A wasm function translated to CLIF by
+/// `InliningCompiler::translate_function`, waiting to be compiled.
+struct TranslatedFunction {
+    func: ir::Function,
+    /// The range of the function's body within the wasm module.
+    body: Range<usize>,
+    /// The source locations of the function's exception landing sites.
+    exception_landings: Vec<ir::SourceLoc>,
+    translate_time: Duration,
+}
+
+impl TranslatedFunction {
+    fn get(
+        translated: &PrimaryMap<DefinedFuncIndex, Box<dyn Any + Send + Sync>>,
+        index: DefinedFuncIndex,
+    ) -> &TranslatedFunction {
+        translated[index].downcast_ref().unwrap()
+    }
+}
+
+/// Provides the translated bodies of the functions defined in a module so that
+/// direct calls between them can be inlined.
+struct ModuleInliner<'a> {
+    module: &'a Module,
+    translated: &'a PrimaryMap<DefinedFuncIndex, Box<dyn Any + Send + Sync>>,
+    /// The exception landing sites of the function being compiled, which grow
+    /// with those of every inlined callee.
+    exception_landings: &'a mut Vec<ir::SourceLoc>,
+}
+
+impl Inline for ModuleInliner<'_> {
+    fn inline(
+        &mut self,
+        caller: &ir::Function,
+        call_inst: ir::Inst,
+        callee: ir::FuncRef,
+        _call_args: &[Value],
+    ) -> InlineCommand<'_> {
+        // Functions of the module are named by their `FuncIndex` in namespace
+        // 0, see `FuncEnvironment::make_direct_func`.
+        let name = match caller.dfg.ext_funcs[callee].name {
+            ir::ExternalName::User(name) => &caller.params.user_named_funcs()[name],
+            _ => return InlineCommand::KeepCall,
+        };
+        if name.namespace != 0 {
+            return InlineCommand::KeepCall;
+        }
+        // Exceptions resume at the return address of a landing site, so it
+        // must remain a call.
+        if self.exception_landings.contains(&caller.srcloc(call_inst)) {
+            return InlineCommand::KeepCall;
+        }
+        match self
+            .module
+            .defined_func_index(FuncIndex::from_u32(name.index))
+        {
+            Some(index) => {
+                let callee = TranslatedFunction::get(self.translated, index);
+                self.exception_landings
+                    .extend_from_slice(&callee.exception_landings);
+                InlineCommand::Inline(Cow::Borrowed(&callee.func))
+            }
+            None => InlineCommand::KeepCall,
+        }
+    }
+}
+
 #[cfg(feature = "incremental-cache")]
 mod incremental_cache {
     use super::*;
@@ -955,6 +997,7 @@ impl Compiler {
         let saved_context = self.contexts.lock().unwrap().pop();
         FunctionCompiler {
             compiler: self,
+            exception_landings: Vec::new(),
             cx: saved_context
                 .map(|mut ctx| {
                     ctx.codegen_context.clear();
@@ -992,6 +1035,9 @@ impl wasmtime_cranelift_shared::CompiledFuncEnv for CompiledFuncEnv {
 struct FunctionCompiler<'a> {
     compiler: &'a Compiler,
     cx: CompilerContext,
+    /// The source locations of the calls of the function being compiled which
+    /// are exception landing sites.
+    exception_landings: Vec<ir::SourceLoc>,
 }
 
 impl FunctionCompiler<'_> {
@@ -1009,6 +1055,112 @@ impl FunctionCompiler<'_> {
         (builder, block0)
     }
 
+    /// Translates the function `func_index` within `translation` into this
+    /// compiler's context, returning the range of its body within the module.
+    fn translate(
+        &mut self,
+        translation: &ModuleTranslation<'_>,
+        func_index: DefinedFuncIndex,
+        input: FunctionBodyData<'_>,
+        types: &ModuleTypes,
+    ) -> Result<Range<usize>, CompileError> {
+        let isa = &*self.compiler.isa;
+        let module = &translation.module;
+        let func_index = module.func_index(func_index);
+        let sig = translation.module.functions[func_index].signature;
+        let wasm_func_ty = &types[sig];
+
+        let context = &mut self.cx.codegen_context;
+        context.func.signature = wasm_call_signature(isa, wasm_func_ty, &self.compiler.tunables);
+        context.func.name = UserFuncName::User(UserExternalName {
+            namespace: 0,
+            index: func_index.as_u32(),
+        });
+
+        if self.compiler.tunables.generate_native_debuginfo {
+            context.func.collect_debug_info();
+        }
+
+        let mut func_env = FuncEnvironment::new(isa, translation, types, &self.compiler.tunables);
+
+        // The `stack_limit` global value below is the implementation of stack
+        // overflow checks in Wasmtime.
+        //
+        // The Wasm spec defines that stack overflows will raise a trap, and
+        // there's also an added constraint where as an embedder you frequently
+        // are running host-provided code called from wasm. WebAssembly and
+        // native code currently share the same call stack, so Wasmtime needs to
+        // make sure that host-provided code will have enough call-stack
+        // available to it.
+        //
+        // The way that stack overflow is handled here is by adding a prologue
+        // check to all functions for how much native stack is remaining. The
+        // `VMContext` pointer is the first argument to all functions, and the
+        // first field of this structure is `*const VMRuntimeLimits` and the
+        // first field of that is the stack limit. Note that the stack limit in
+        // this case means "if the stack pointer goes below this, trap". Each
+        // function which consumes stack space or isn't a leaf function starts
+        // off by loading the stack limit, checking it against the stack
+        // pointer, and optionally traps.
+        //
+        // This manual check allows the embedder to give wasm a relatively
+        // precise amount of stack allocation. Using this scheme we reserve a
+        // chunk of stack for wasm code relative from where wasm code was
+        // called. This ensures that native code called by wasm should have
+        // native stack space to run, and the numbers of stack spaces here
+        // should all be configurable for various embeddings.
+        //
+        // Note that this check is independent of each thread's stack guard page
+        // here. If the stack guard page is reached that's still considered an
+        // abort for the whole program since the runtime limits configured by
+        // the embedder should cause wasm to trap before it reaches that
+        // (ensuring the host has enough space as well for its functionality).
+        let vmctx = context
+            .func
+            .create_global_value(ir::GlobalValueData::VMContext);
+        let interrupts_ptr = context.func.create_global_value(ir::GlobalValueData::Load {
+            base: vmctx,
+            offset: i32::try_from(func_env.offsets.vmctx_runtime_limits())
+                .unwrap()
+                .into(),
+            global_type: isa.pointer_type(),
+            readonly: true,
+        });
+        let stack_limit = context.func.create_global_value(ir::GlobalValueData::Load {
+            base: interrupts_ptr,
+            offset: i32::try_from(func_env.offsets.ptr.vmruntime_limits_stack_limit())
+                .unwrap()
+                .into(),
+            global_type: isa.pointer_type(),
+            readonly: false,
+        });
+        context.func.stack_limit = Some(stack_limit);
+        let FunctionBodyData { validator, body } = input;
+        let mut validator = validator.into_validator(mem::take(&mut self.cx.validator_allocations));
+        self.cx.func_translator.translate_body(
+            &mut validator,
+            body.clone(),
+            &mut context.func,
+            &mut func_env,
+        )?;
+        self.exception_landings = mem::take(&mut func_env.exception_landings);
+
+        if let Some(path) = &self.compiler.clif_dir {
+            use std::io::Write;
+
+            let mut path = path.to_path_buf();
+            path.push(format!("wasm_func_{}", func_index.as_u32()));
+            path.set_extension("clif");
+
+            let mut output = std::fs::File::create(path).unwrap();
+            write!(output, "{}", context.func.display()).unwrap();
+        }
+
+        let data = body.get_binary_reader();
+        let offset = data.original_position();
+        Ok(offset..offset + data.bytes_remaining())
+    }
+
     fn finish(self) -> Result<CompiledFunction<CompiledFuncEnv>, CompileError> {
         let (info, func) = self.finish_with_info(None)?;
         assert!(info.stack_maps.is_empty());
@@ -1017,13 +1169,20 @@ impl FunctionCompiler<'_> {
 
     fn finish_with_info(
         mut self,
-        body_and_tunables: Option<(&FunctionBody<'_>, &Tunables)>,
+        body_and_tunables: Option<(Range<usize>, &Tunables)>,
     ) -> Result<(WasmFunctionInfo, CompiledFunction<CompiledFuncEnv>), CompileError> {
         let context = &mut self.cx.codegen_context;
         let isa = &*self.compiler.isa;
+        let insts_before_opt = count_insts(&context.func);
         let (_, _code_buf) =
             compile_maybe_cached(context, isa, self.cx.incremental_cache_ctx.as_mut())?;
         let compiled_code = context.compiled_code().unwrap();
+        let mut stats = FunctionStats {
+            insts_before_opt,
+            insts_after_opt: count_insts(&context.func),
+            trap_sites: compiled_code.buffer.traps().len() as u32,
+            ..FunctionStats::default()
+        };
 
         // Give wasm functions, user defined code, a "preferred" alignment
         // instead of the minimum alignment as this can help perf in niche
@@ -1041,12 +1200,11 @@ impl FunctionCompiler<'_> {
         let mut compiled_function =
             CompiledFunction::new(compiled_code.buffer.clone(), env, alignment);
 
-        if let Some((body, tunables)) = body_and_tunables {
-            let data = body.get_binary_reader();
-            let offset = data.original_position();
-            let len = data.bytes_remaining();
+        if let Some((body, tunables)) = &body_and_tunables {
+            let len = body.len();
+            stats.body_size = len as u32;
             compiled_function.set_address_map(
-                offset as u32,
+                body.start as u32,
                 len as u32,
                 tunables.generate_address_map,
             );
@@ -1070,6 +1228,8 @@ impl FunctionCompiler<'_> {
         }
 
         let stack_maps = mach_stack_maps_to_stack_maps(compiled_code.buffer.stack_maps());
+        let exception_landings =
+            mach_call_sites_to_exception_landings(&compiled_code.buffer, &self.exception_landings);
         compiled_function
             .set_sized_stack_slots(std::mem::take(&mut context.func.sized_stack_slots));
         self.compiler.contexts.lock().unwrap().push(self.cx);
@@ -1078,12 +1238,53 @@ impl FunctionCompiler<'_> {
             WasmFunctionInfo {
                 start_srcloc: compiled_function.metadata().address_map.start_srcloc,
                 stack_maps: stack_maps.into(),
+                exception_landings: exception_landings.into(),
+                stats,
             },
             compiled_function,
         ))
     }
 }
 
+/// Returns the exception landings of the calls in `buffer` whose source
+/// location is one of `landings`, sorted by code offset.
+fn mach_call_sites_to_exception_landings(
+    buffer: &MachBufferFinalized<Final>,
+    landings: &[ir::SourceLoc],
+) -> Vec<ExceptionLanding> {
+    if landings.is_empty() {
+        return Vec::new();
+    }
+    let landings: HashSet<ir::SourceLoc> = landings.iter().copied().collect();
+    let srclocs = buffer.get_srclocs_sorted();
+    let mut exception_landings = Vec::new();
+    for site in buffer.call_sites() {
+        // The last byte of the call instruction is just before its return
+        // address.
+        let i = srclocs.partition_point(|l| l.end < site.ret_addr);
+        let loc = match srclocs.get(i) {
+            Some(l) if l.start < site.ret_addr => l.loc,
+            _ => continue,
+        };
+        if landings.contains(&loc) {
+            exception_landings.push(ExceptionLanding {
+                code_offset: site.ret_addr,
+                sp_to_fp: site.sp_to_fp,
+            });
+        }
+    }
+    exception_landings.sort_unstable_by_key(|l| l.code_offset);
+    exception_landings
+}
+
+/// Returns the number of instructions in the layout of `func`.
+fn count_insts(func: &ir::Function) -> u32 {
+    func.layout
+        .blocks()
+        .map(|block| func.layout.block_insts(block).count() as u32)
+        .sum()
+}
+
 fn mach_stack_maps_to_stack_maps(mach_stack_maps: &[MachStackMap]) -> Vec<StackMapInformation> {
     // This is converting from Cranelift's representation of a stack map to
     // Wasmtime's representation. They happen to align today but that may
@@ -1,4 +1,3 @@
-use cfg_if::cfg_if;
 use cranelift_codegen::cursor::FuncCursor;
 use cranelift_codegen::ir;
 use cranelift_codegen::ir::condcodes::*;
@@ -22,7 +21,8 @@ use std::mem;
 use wasmparser::Operator;
 use wasmtime_environ::{
     BuiltinFunctionIndex, MemoryPlan, MemoryStyle, Module, ModuleTranslation, ModuleTypes, PtrSize,
-    TableStyle, Tunables, TypeConvert, VMOffsets, WASM_PAGE_SIZE,
+    TableStyle, Tunables, TypeConvert, VMDebugValueType, VMOffsets,
+    VMDEBUG_FRAME_VALUES_UNAVAILABLE, WASM_PAGE_SIZE,
 };
 use wasmtime_environ::{FUNCREF_INIT_BIT, FUNCREF_MASK};
 
@@ -157,8 +157,32 @@ pub struct FuncEnvironment<'module_environment> {
 
     fuel_consumed: i64,
 
-    #[cfg(feature = "wmemcheck")]
-    wmemcheck: bool,
+    /// The number of locals, including parameters, of the function being
+    /// translated.
+    num_locals: u32,
+
+    /// The `VMDebugFrame` of the function being translated, when compiling
+    /// with debug instrumentation.
+    debug_frame: Option<DebugFrame>,
+
+    /// The source locations of the calls within the bodies of `try` blocks,
+    /// which are where the exceptions thrown by their callees land.
+    pub(crate) exception_landings: Vec<ir::SourceLoc>,
+}
+
+/// The stack slots backing the `VMDebugFrame` of a function compiled with
+/// debug instrumentation, and the values used to maintain it.
+struct DebugFrame {
+    /// The address of the `VMDebugFrame` itself.
+    addr: ir::Value,
+    /// The frame of the caller, loaded on entry.
+    prev: ir::Value,
+    /// The slot the values of locals and the operand stack are written to.
+    values_slot: ir::StackSlot,
+    /// The slot the type tags of those values are written to.
+    types_slot: ir::StackSlot,
+    /// The largest number of values written to the slots.
+    max_values: u32,
 }
 
 impl<'module_environment> FuncEnvironment<'module_environment> {
@@ -167,7 +191,6 @@ impl<'module_environment> FuncEnvironment<'module_environment> {
         translation: &'module_environment ModuleTranslation<'module_environment>,
         types: &'module_environment ModuleTypes,
         tunables: &'module_environment Tunables,
-        wmemcheck: bool,
     ) -> Self {
         let builtin_function_signatures = BuiltinFunctionSignatures::new(
             isa.pointer_type(),
@@ -179,10 +202,6 @@ impl<'module_environment> FuncEnvironment<'module_environment> {
             CallConv::triple_default(isa.triple()),
         );
 
-        // Avoid unused warning in default build.
-        #[cfg(not(feature = "wmemcheck"))]
-        let _ = wmemcheck;
-
         Self {
             isa,
             module: &translation.module,
@@ -201,8 +220,9 @@ impl<'module_environment> FuncEnvironment<'module_environment> {
             // Start with at least one fuel being consumed because even empty
             // functions should consume at least some fuel.
             fuel_consumed: 1,
-            #[cfg(feature = "wmemcheck")]
-            wmemcheck,
+            num_locals: 0,
+            debug_frame: None,
+            exception_landings: Vec::new(),
         }
     }
 
@@ -210,6 +230,12 @@ impl<'module_environment> FuncEnvironment<'module_environment> {
         self.isa.pointer_type()
     }
 
+    /// The size of a context saved by `stack_switch`: the stack pointer, the
+    /// frame pointer and the program counter.
+    fn stack_switch_context_size(&self) -> i64 {
+        i64::from(3 * self.pointer_type().bytes())
+    }
+
     fn vmctx(&mut self, func: &mut Function) -> ir::GlobalValue {
         self.vmctx.unwrap_or_else(|| {
             let vmctx = func.create_global_value(ir::GlobalValueData::VMContext);
@@ -406,23 +432,10 @@ impl<'module_environment> FuncEnvironment<'module_environment> {
             return;
         }
 
-        self.fuel_consumed += match op {
-            // Nop and drop generate no code, so don't consume fuel for them.
-            Operator::Nop | Operator::Drop => 0,
-
-            // Control flow may create branches, but is generally cheap and
-            // free, so don't consume fuel. Note the lack of `if` since some
-            // cost is incurred with the conditional check.
-            Operator::Block { .. }
-            | Operator::Loop { .. }
-            | Operator::Unreachable
-            | Operator::Return
-            | Operator::Else
-            | Operator::End => 0,
-
-            // everything else, just call it one operation.
-            _ => 1,
-        };
+        // The cost of each operator comes from the configured cost model. By
+        // default `nop`, `drop` and structured control flow are free, and
+        // everything else costs one unit of fuel.
+        self.fuel_consumed += i64::from(self.tunables.fuel_costs.cost(op));
 
         match op {
             // Exiting a function (via a return or unreachable) or otherwise
@@ -437,9 +450,15 @@ impl<'module_environment> FuncEnvironment<'module_environment> {
             Operator::Unreachable
             | Operator::Return
             | Operator::CallIndirect { .. }
+            | Operator::CallRef { .. }
             | Operator::Call { .. }
             | Operator::ReturnCall { .. }
-            | Operator::ReturnCallIndirect { .. } => {
+            | Operator::ReturnCallIndirect { .. }
+            | Operator::ReturnCallRef { .. }
+            | Operator::Resume { .. }
+            | Operator::Suspend { .. }
+            | Operator::Throw { .. }
+            | Operator::Rethrow { .. } => {
                 self.fuel_increment_var(builder);
                 self.fuel_save_from_var(builder);
             }
@@ -470,7 +489,13 @@ impl<'module_environment> FuncEnvironment<'module_environment> {
             // for an `if` block. The same reasoning applies though in that we
             // are terminating a basic block and need to update the fuel
             // variable.
-            | Operator::Else => self.fuel_increment_var(builder),
+            | Operator::Else
+
+            // The same goes for the terminators of the body and the clauses of
+            // a `try` block.
+            | Operator::Catch { .. }
+            | Operator::CatchAll
+            | Operator::Delegate { .. } => self.fuel_increment_var(builder),
 
             // This is a normal instruction where the fuel is buffered to later
             // get added to `self.fuel_var`.
@@ -494,10 +519,14 @@ impl<'module_environment> FuncEnvironment<'module_environment> {
     }
 
     fn fuel_after_op(&mut self, op: &Operator<'_>, builder: &mut FunctionBuilder<'_>) {
-        // After a function call we need to reload our fuel value since the
-        // function may have changed it.
+        // After a function call, or after switching back from another
+        // continuation, we need to reload our fuel value since the code which
+        // ran in the meantime may have changed it.
         match op {
-            Operator::Call { .. } | Operator::CallIndirect { .. } => {
+            Operator::Call { .. }
+            | Operator::CallIndirect { .. }
+            | Operator::CallRef { .. }
+            | Operator::Suspend { .. } => {
                 self.fuel_load_into_var(builder);
             }
             _ => {}
@@ -622,55 +651,456 @@ impl<'module_environment> FuncEnvironment<'module_environment> {
         self.epoch_check(builder);
     }
 
-    #[cfg(feature = "wmemcheck")]
-    fn hook_malloc_exit(&mut self, builder: &mut FunctionBuilder, retvals: &[Value]) {
-        let check_malloc_sig = self.builtin_function_signatures.check_malloc(builder.func);
-        let (vmctx, check_malloc) = self.translate_load_builtin_function_address(
+    /// Allocates this function's `VMDebugFrame` and pushes it on the list of
+    /// frames in `VMRuntimeLimits`.
+    fn debug_function_entry(&mut self, builder: &mut FunctionBuilder<'_>) {
+        let pointer_type = self.pointer_type();
+        let ptr = self.offsets.ptr;
+        let frame_slot = builder.create_sized_stack_slot(ir::StackSlotData::new(
+            ir::StackSlotKind::ExplicitSlot,
+            u32::from(ptr.size_of_vmdebug_frame()),
+        ));
+        // The sizes of these two are only known once the whole function is
+        // translated, see `debug_function_exit`.
+        let values_slot = builder
+            .create_sized_stack_slot(ir::StackSlotData::new(ir::StackSlotKind::ExplicitSlot, 0));
+        let types_slot = builder
+            .create_sized_stack_slot(ir::StackSlotData::new(ir::StackSlotKind::ExplicitSlot, 0));
+        let addr = builder.ins().stack_addr(pointer_type, frame_slot, 0);
+        let values = builder.ins().stack_addr(pointer_type, values_slot, 0);
+        let types = builder.ins().stack_addr(pointer_type, types_slot, 0);
+
+        let limits = builder.use_var(self.vmruntime_limits_ptr);
+        let flags = ir::MemFlags::trusted();
+        let prev = builder.ins().load(
+            pointer_type,
+            flags,
+            limits,
+            i32::from(ptr.vmruntime_limits_debug_frame()),
+        );
+        let vmctx = self.vmctx(builder.func);
+        let vmctx = builder.ins().global_value(pointer_type, vmctx);
+        let func_index = match &builder.func.name {
+            UserFuncName::User(user) => user.index,
+            _ => panic!("function name not a UserFuncName::User as expected"),
+        };
+        let func_index = builder.ins().iconst(I32, i64::from(func_index));
+        let num_locals = builder.ins().iconst(I32, i64::from(self.num_locals));
+        let zero = builder.ins().iconst(I32, 0);
+        let unavailable = builder
+            .ins()
+            .iconst(I32, i64::from(VMDEBUG_FRAME_VALUES_UNAVAILABLE));
+        for (value, offset) in [
+            (prev, ptr.vmdebug_frame_prev()),
+            (vmctx, ptr.vmdebug_frame_vmctx()),
+            (values, ptr.vmdebug_frame_values()),
+            (types, ptr.vmdebug_frame_types()),
+            (func_index, ptr.vmdebug_frame_func_index()),
+            (zero, ptr.vmdebug_frame_offset()),
+            (num_locals, ptr.vmdebug_frame_num_locals()),
+            (unavailable, ptr.vmdebug_frame_num_values()),
+        ] {
+            builder.ins().store(flags, value, addr, i32::from(offset));
+        }
+        builder.ins().store(
+            flags,
+            addr,
+            limits,
+            i32::from(ptr.vmruntime_limits_debug_frame()),
+        );
+
+        self.debug_frame = Some(DebugFrame {
+            addr,
+            prev,
+            values_slot,
+            types_slot,
+            max_values: 0,
+        });
+    }
+
+    /// Makes this function's frame the innermost one, records the offset of
+    /// the instruction about to be executed, and calls the `debug_break`
+    /// libcall if the store asked to pause.
+    fn debug_before_op(
+        &mut self,
+        op: &Operator,
+        builder: &mut FunctionBuilder,
+        state: &FuncTranslationState,
+    ) {
+        let ptr = self.offsets.ptr;
+        let flags = ir::MemFlags::trusted();
+        let frame = self.debug_frame.as_ref().unwrap();
+        let (addr, prev) = (frame.addr, frame.prev);
+        let limits = builder.use_var(self.vmruntime_limits_ptr);
+
+        // The frame may no longer be the innermost one after a call returned
+        // or an exception was caught, so it is set again before every
+        // instruction.
+        builder.ins().store(
+            flags,
+            addr,
+            limits,
+            i32::from(ptr.vmruntime_limits_debug_frame()),
+        );
+        let offset = i64::from(builder.srcloc().bits());
+        let offset = builder.ins().iconst(I32, offset);
+        builder
+            .ins()
+            .store(flags, offset, addr, i32::from(ptr.vmdebug_frame_offset()));
+
+        let break_block = builder.create_block();
+        let continuation_block = builder.create_block();
+        builder.set_cold_block(break_block);
+        let armed = builder.ins().load(
+            I32,
+            flags,
+            limits,
+            i32::from(ptr.vmruntime_limits_debug_break()),
+        );
+        builder
+            .ins()
+            .brif(armed, break_block, &[], continuation_block, &[]);
+        builder.seal_block(break_block);
+
+        builder.switch_to_block(break_block);
+        self.debug_spill_values(builder, state);
+        let debug_break_sig = self.builtin_function_signatures.debug_break(builder.func);
+        let (vmctx, debug_break) = self.translate_load_builtin_function_address(
             &mut builder.cursor(),
-            BuiltinFunctionIndex::check_malloc(),
+            BuiltinFunctionIndex::debug_break(),
         );
-        let func_args = builder
-            .func
-            .dfg
-            .block_params(builder.func.layout.entry_block().unwrap());
-        let len = if func_args.len() < 3 {
-            return;
+        builder
+            .ins()
+            .call_indirect(debug_break_sig, debug_break, &[vmctx]);
+        builder.ins().jump(continuation_block, &[]);
+        builder.seal_block(continuation_block);
+        builder.switch_to_block(continuation_block);
+
+        match op {
+            // The host may inspect this frame while the callee runs.
+            Operator::Call { .. }
+            | Operator::CallIndirect { .. }
+            | Operator::CallRef { .. }
+            | Operator::Resume { .. }
+            | Operator::ResumeThrow { .. } => self.debug_spill_values(builder, state),
+            // A core dump taken when this traps records this frame.
+            op if may_trap(op) => self.debug_spill_values(builder, state),
+            // This frame is replaced by the callee's.
+            Operator::ReturnCall { .. }
+            | Operator::ReturnCallIndirect { .. }
+            | Operator::ReturnCallRef { .. } => {
+                builder.ins().store(
+                    flags,
+                    prev,
+                    limits,
+                    i32::from(ptr.vmruntime_limits_debug_frame()),
+                );
+            }
+            // Values written for an earlier instruction may be stale by now,
+            // so they're marked as unavailable rather than left to be read.
+            _ => {
+                let unavailable = builder
+                    .ins()
+                    .iconst(I32, i64::from(VMDEBUG_FRAME_VALUES_UNAVAILABLE));
+                builder.ins().store(
+                    flags,
+                    unavailable,
+                    addr,
+                    i32::from(ptr.vmdebug_frame_num_values()),
+                );
+            }
+        }
+    }
+
+    /// Writes the current values of all locals and of the operand stack, and
+    /// their type tags, to this function's `VMDebugFrame`.
+    fn debug_spill_values(&mut self, builder: &mut FunctionBuilder, state: &FuncTranslationState) {
+        let mut values = (0..self.num_locals)
+            .map(|i| builder.use_var(Variable::new(i as usize)))
+            .collect::<Vec<_>>();
+        values.extend_from_slice(state.value_stack());
+
+        let ptr = self.offsets.ptr;
+        let pointer_type = self.pointer_type();
+        let frame = self.debug_frame.as_mut().unwrap();
+        let values_addr = builder.ins().stack_addr(pointer_type, frame.values_slot, 0);
+        let types_addr = builder.ins().stack_addr(pointer_type, frame.types_slot, 0);
+        // Values may be vectors, which aren't necessarily aligned here.
+        let flags = ir::MemFlags::new().with_notrap();
+        for (i, value) in values.iter().enumerate() {
+            let ty = builder.func.dfg.value_type(*value);
+            let tag = match ty {
+                I32 => VMDebugValueType::I32,
+                I64 => VMDebugValueType::I64,
+                F32 => VMDebugValueType::F32,
+                F64 => VMDebugValueType::F64,
+                R32 | R64 => VMDebugValueType::ExternRef,
+                ty if ty.is_vector() => VMDebugValueType::V128,
+                ty => panic!("unexpected type of wasm value: {ty}"),
+            };
+            let i = i32::try_from(i).unwrap();
+            builder.ins().store(flags, *value, values_addr, i * 16);
+            let tag = builder.ins().iconst(I8, i64::from(tag as u8));
+            builder.ins().store(flags, tag, types_addr, i);
+        }
+        let num_values = u32::try_from(values.len()).unwrap();
+        frame.max_values = frame.max_values.max(num_values);
+        let num_values = builder.ins().iconst(I32, i64::from(num_values));
+        builder.ins().store(
+            ir::MemFlags::trusted(),
+            num_values,
+            frame.addr,
+            i32::from(ptr.vmdebug_frame_num_values()),
+        );
+    }
+
+    /// Sizes the stack slots of this function's `VMDebugFrame` now that the
+    /// largest number of values written to them is known.
+    fn debug_function_exit(&mut self, builder: &mut FunctionBuilder) {
+        let frame = self.debug_frame.take().unwrap();
+        builder.func.sized_stack_slots[frame.values_slot].size = frame.max_values * 16;
+        builder.func.sized_stack_slots[frame.types_slot].size = frame.max_values;
+    }
+
+    /// Calls one of the `instrument_*` builtins with `args` after the
+    /// `vmctx`.
+    ///
+    /// The store's instrumentation handler may call back into WebAssembly,
+    /// so fuel is saved and reloaded around the call.
+    fn instrumentation_call(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        sig: ir::SigRef,
+        index: BuiltinFunctionIndex,
+        args: &[ir::Value],
+    ) {
+        if self.tunables.consume_fuel {
+            self.fuel_save_from_var(builder);
+        }
+        let (vmctx, func_addr) =
+            self.translate_load_builtin_function_address(&mut builder.cursor(), index);
+        let mut call_args = vec![vmctx];
+        call_args.extend_from_slice(args);
+        builder.ins().call_indirect(sig, func_addr, &call_args);
+        if self.tunables.consume_fuel {
+            self.fuel_load_into_var(builder);
+        }
+    }
+
+    /// Zero-extends a 32-bit address or size to the 64 bits the
+    /// `instrument_*` builtins take.
+    fn instrumentation_u64(builder: &mut FunctionBuilder, value: ir::Value) -> ir::Value {
+        if builder.func.dfg.value_type(value) == I32 {
+            builder.ins().uextend(I64, value)
         } else {
-            // If a function named `malloc` has at least one argument, we assume the
-            // first argument is the requested allocation size.
-            func_args[2]
+            value
+        }
+    }
+
+    fn instrumentation_func_index(&self, builder: &mut FunctionBuilder) -> ir::Value {
+        let func_index = match &builder.func.name {
+            UserFuncName::User(user) => user.index,
+            _ => panic!("function name not a UserFuncName::User as expected"),
         };
-        let retval = if retvals.len() < 1 {
+        builder.ins().iconst(I32, i64::from(func_index))
+    }
+
+    fn instrumentation_function_entry(&mut self, builder: &mut FunctionBuilder) {
+        let sig = self
+            .builtin_function_signatures
+            .instrument_call(builder.func);
+        let func_index = self.instrumentation_func_index(builder);
+        self.instrumentation_call(
+            builder,
+            sig,
+            BuiltinFunctionIndex::instrument_call(),
+            &[func_index],
+        );
+    }
+
+    fn instrumentation_function_exit(&mut self, builder: &mut FunctionBuilder) {
+        let sig = self
+            .builtin_function_signatures
+            .instrument_return(builder.func);
+        let func_index = self.instrumentation_func_index(builder);
+        self.instrumentation_call(
+            builder,
+            sig,
+            BuiltinFunctionIndex::instrument_return(),
+            &[func_index],
+        );
+    }
+
+    fn instrumentation_memory_grow(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        delta: ir::Value,
+        mem_index: MemoryIndex,
+    ) {
+        let sig = self
+            .builtin_function_signatures
+            .instrument_memory_grow(builder.func);
+        let memory = builder.ins().iconst(I32, i64::from(mem_index.as_u32()));
+        let delta = Self::instrumentation_u64(builder, delta);
+        self.instrumentation_call(
+            builder,
+            sig,
+            BuiltinFunctionIndex::instrument_memory_grow(),
+            &[memory, delta],
+        );
+    }
+
+    /// Reports a load or store of `val_size` bytes at `addr + offset` if it
+    /// overlaps any of the instrumented address ranges.
+    ///
+    /// The address has already been bounds-checked, so computing the end of
+    /// the access can't overflow.
+    fn instrumentation_memory_access(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        is_store: bool,
+        val_size: u8,
+        addr: ir::Value,
+        offset: u64,
+        mem_index: MemoryIndex,
+    ) {
+        let ranges = self
+            .tunables
+            .instrumentation
+            .memory_ranges()
+            .iter()
+            .filter(|range| !range.is_empty())
+            .cloned()
+            .collect::<Vec<_>>();
+        if ranges.is_empty() {
             return;
+        }
+
+        let addr = Self::instrumentation_u64(builder, addr);
+        let start = builder.ins().iadd_imm(addr, offset as i64);
+        let end = builder.ins().iadd_imm(start, i64::from(val_size));
+        let mut in_range = None;
+        for range in ranges {
+            let before_end =
+                builder
+                    .ins()
+                    .icmp_imm(IntCC::UnsignedLessThan, start, range.end as i64);
+            let after_start =
+                builder
+                    .ins()
+                    .icmp_imm(IntCC::UnsignedGreaterThan, end, range.start as i64);
+            let overlaps = builder.ins().band(before_end, after_start);
+            in_range = Some(match in_range {
+                Some(prev) => builder.ins().bor(prev, overlaps),
+                None => overlaps,
+            });
+        }
+
+        let report_block = builder.create_block();
+        let continuation_block = builder.create_block();
+        builder.ins().brif(
+            in_range.unwrap(),
+            report_block,
+            &[],
+            continuation_block,
+            &[],
+        );
+        builder.seal_block(report_block);
+        builder.set_cold_block(report_block);
+
+        builder.switch_to_block(report_block);
+        let (sig, index) = if is_store {
+            (
+                self.builtin_function_signatures
+                    .instrument_store(builder.func),
+                BuiltinFunctionIndex::instrument_store(),
+            )
         } else {
-            retvals[0]
+            (
+                self.builtin_function_signatures
+                    .instrument_load(builder.func),
+                BuiltinFunctionIndex::instrument_load(),
+            )
         };
-        builder
-            .ins()
-            .call_indirect(check_malloc_sig, check_malloc, &[vmctx, retval, len]);
+        let memory = builder.ins().iconst(I32, i64::from(mem_index.as_u32()));
+        let size = builder.ins().iconst(I32, i64::from(val_size));
+        self.instrumentation_call(builder, sig, index, &[memory, start, size]);
+        builder.ins().jump(continuation_block, &[]);
+        builder.seal_block(continuation_block);
+
+        builder.switch_to_block(continuation_block);
     }
 
-    #[cfg(feature = "wmemcheck")]
-    fn hook_free_exit(&mut self, builder: &mut FunctionBuilder) {
-        let check_free_sig = self.builtin_function_signatures.check_free(builder.func);
-        let (vmctx, check_free) = self.translate_load_builtin_function_address(
-            &mut builder.cursor(),
-            BuiltinFunctionIndex::check_free(),
+    /// Returns the first parameter of the function being translated, which
+    /// is the size for `malloc` and the address for `free`.
+    fn instrumentation_first_param(builder: &FunctionBuilder) -> Option<ir::Value> {
+        let entry = builder.func.layout.entry_block().unwrap();
+        // Skip the callee and caller `vmctx` parameters.
+        builder.func.dfg.block_params(entry).get(2).copied()
+    }
+
+    fn instrumentation_allocator_entry(&mut self, builder: &mut FunctionBuilder) {
+        let sig = self
+            .builtin_function_signatures
+            .instrument_allocator_entry(builder.func);
+        self.instrumentation_call(
+            builder,
+            sig,
+            BuiltinFunctionIndex::instrument_allocator_entry(),
+            &[],
         );
-        let func_args = builder
-            .func
-            .dfg
-            .block_params(builder.func.layout.entry_block().unwrap());
-        let ptr = if func_args.len() < 3 {
-            return;
-        } else {
-            // If a function named `free` has at least one argument, we assume the
-            // first argument is a pointer to memory.
-            func_args[2]
+    }
+
+    fn instrumentation_malloc_exit(&mut self, builder: &mut FunctionBuilder, retvals: &[Value]) {
+        let (size, addr) = match (Self::instrumentation_first_param(builder), retvals.first()) {
+            (Some(size), Some(addr)) => (size, *addr),
+            _ => return,
         };
-        builder
-            .ins()
-            .call_indirect(check_free_sig, check_free, &[vmctx, ptr]);
+        let sig = self
+            .builtin_function_signatures
+            .instrument_malloc(builder.func);
+        let addr = Self::instrumentation_u64(builder, addr);
+        let size = Self::instrumentation_u64(builder, size);
+        self.instrumentation_call(
+            builder,
+            sig,
+            BuiltinFunctionIndex::instrument_malloc(),
+            &[addr, size],
+        );
+    }
+
+    fn instrumentation_free_exit(&mut self, builder: &mut FunctionBuilder) {
+        let addr = match Self::instrumentation_first_param(builder) {
+            Some(addr) => addr,
+            None => return,
+        };
+        let sig = self
+            .builtin_function_signatures
+            .instrument_free(builder.func);
+        let addr = Self::instrumentation_u64(builder, addr);
+        self.instrumentation_call(
+            builder,
+            sig,
+            BuiltinFunctionIndex::instrument_free(),
+            &[addr],
+        );
+    }
+
+    fn instrumentation_stack_pointer(&mut self, builder: &mut FunctionBuilder, value: ir::Value) {
+        let ty = builder.func.dfg.value_type(value);
+        if ty != I32 && ty != I64 {
+            return;
+        }
+        let sig = self
+            .builtin_function_signatures
+            .instrument_stack_pointer(builder.func);
+        let value = Self::instrumentation_u64(builder, value);
+        self.instrumentation_call(
+            builder,
+            sig,
+            BuiltinFunctionIndex::instrument_stack_pointer(),
+            &[value],
+        );
     }
 
     fn epoch_ptr(&mut self, builder: &mut FunctionBuilder<'_>) -> ir::Value {
@@ -882,28 +1312,6 @@ impl<'module_environment> FuncEnvironment<'module_environment> {
         result_param
     }
 
-    fn check_malloc_start(&mut self, builder: &mut FunctionBuilder) {
-        let malloc_start_sig = self.builtin_function_signatures.malloc_start(builder.func);
-        let (vmctx, malloc_start) = self.translate_load_builtin_function_address(
-            &mut builder.cursor(),
-            BuiltinFunctionIndex::malloc_start(),
-        );
-        builder
-            .ins()
-            .call_indirect(malloc_start_sig, malloc_start, &[vmctx]);
-    }
-
-    fn check_free_start(&mut self, builder: &mut FunctionBuilder) {
-        let free_start_sig = self.builtin_function_signatures.free_start(builder.func);
-        let (vmctx, free_start) = self.translate_load_builtin_function_address(
-            &mut builder.cursor(),
-            BuiltinFunctionIndex::free_start(),
-        );
-        builder
-            .ins()
-            .call_indirect(free_start_sig, free_start, &[vmctx]);
-    }
-
     fn current_func_name(&self, builder: &mut FunctionBuilder) -> Option<&str> {
         let func_index = match &builder.func.name {
             UserFuncName::User(user) => FuncIndex::from_u32(user.index),
@@ -952,6 +1360,172 @@ impl<'module_environment> FuncEnvironment<'module_environment> {
     }
 }
 
+/// Returns whether `op` may trap, or call into the host, which can take a
+/// core dump or inspect the frames of WebAssembly functions.
+fn may_trap(op: &Operator<'_>) -> bool {
+    match op {
+        Operator::Unreachable => true,
+
+        // Out-of-bounds and misaligned accesses.
+        Operator::F32Load { .. }
+        | Operator::F32Store { .. }
+        | Operator::F64Load { .. }
+        | Operator::F64Store { .. }
+        | Operator::I32AtomicLoad { .. }
+        | Operator::I32AtomicLoad16U { .. }
+        | Operator::I32AtomicLoad8U { .. }
+        | Operator::I32AtomicRmw16AddU { .. }
+        | Operator::I32AtomicRmw16AndU { .. }
+        | Operator::I32AtomicRmw16CmpxchgU { .. }
+        | Operator::I32AtomicRmw16OrU { .. }
+        | Operator::I32AtomicRmw16SubU { .. }
+        | Operator::I32AtomicRmw16XchgU { .. }
+        | Operator::I32AtomicRmw16XorU { .. }
+        | Operator::I32AtomicRmw8AddU { .. }
+        | Operator::I32AtomicRmw8AndU { .. }
+        | Operator::I32AtomicRmw8CmpxchgU { .. }
+        | Operator::I32AtomicRmw8OrU { .. }
+        | Operator::I32AtomicRmw8SubU { .. }
+        | Operator::I32AtomicRmw8XchgU { .. }
+        | Operator::I32AtomicRmw8XorU { .. }
+        | Operator::I32AtomicRmwAdd { .. }
+        | Operator::I32AtomicRmwAnd { .. }
+        | Operator::I32AtomicRmwCmpxchg { .. }
+        | Operator::I32AtomicRmwOr { .. }
+        | Operator::I32AtomicRmwSub { .. }
+        | Operator::I32AtomicRmwXchg { .. }
+        | Operator::I32AtomicRmwXor { .. }
+        | Operator::I32AtomicStore { .. }
+        | Operator::I32AtomicStore16 { .. }
+        | Operator::I32AtomicStore8 { .. }
+        | Operator::I32Load { .. }
+        | Operator::I32Load16S { .. }
+        | Operator::I32Load16U { .. }
+        | Operator::I32Load8S { .. }
+        | Operator::I32Load8U { .. }
+        | Operator::I32Store { .. }
+        | Operator::I32Store16 { .. }
+        | Operator::I32Store8 { .. }
+        | Operator::I64AtomicLoad { .. }
+        | Operator::I64AtomicLoad16U { .. }
+        | Operator::I64AtomicLoad32U { .. }
+        | Operator::I64AtomicLoad8U { .. }
+        | Operator::I64AtomicRmw16AddU { .. }
+        | Operator::I64AtomicRmw16AndU { .. }
+        | Operator::I64AtomicRmw16CmpxchgU { .. }
+        | Operator::I64AtomicRmw16OrU { .. }
+        | Operator::I64AtomicRmw16SubU { .. }
+        | Operator::I64AtomicRmw16XchgU { .. }
+        | Operator::I64AtomicRmw16XorU { .. }
+        | Operator::I64AtomicRmw32AddU { .. }
+        | Operator::I64AtomicRmw32AndU { .. }
+        | Operator::I64AtomicRmw32CmpxchgU { .. }
+        | Operator::I64AtomicRmw32OrU { .. }
+        | Operator::I64AtomicRmw32SubU { .. }
+        | Operator::I64AtomicRmw32XchgU { .. }
+        | Operator::I64AtomicRmw32XorU { .. }
+        | Operator::I64AtomicRmw8AddU { .. }
+        | Operator::I64AtomicRmw8AndU { .. }
+        | Operator::I64AtomicRmw8CmpxchgU { .. }
+        | Operator::I64AtomicRmw8OrU { .. }
+        | Operator::I64AtomicRmw8SubU { .. }
+        | Operator::I64AtomicRmw8XchgU { .. }
+        | Operator::I64AtomicRmw8XorU { .. }
+        | Operator::I64AtomicRmwAdd { .. }
+        | Operator::I64AtomicRmwAnd { .. }
+        | Operator::I64AtomicRmwCmpxchg { .. }
+        | Operator::I64AtomicRmwOr { .. }
+        | Operator::I64AtomicRmwSub { .. }
+        | Operator::I64AtomicRmwXchg { .. }
+        | Operator::I64AtomicRmwXor { .. }
+        | Operator::I64AtomicStore { .. }
+        | Operator::I64AtomicStore16 { .. }
+        | Operator::I64AtomicStore32 { .. }
+        | Operator::I64AtomicStore8 { .. }
+        | Operator::I64Load { .. }
+        | Operator::I64Load16S { .. }
+        | Operator::I64Load16U { .. }
+        | Operator::I64Load32S { .. }
+        | Operator::I64Load32U { .. }
+        | Operator::I64Load8S { .. }
+        | Operator::I64Load8U { .. }
+        | Operator::I64Store { .. }
+        | Operator::I64Store16 { .. }
+        | Operator::I64Store32 { .. }
+        | Operator::I64Store8 { .. }
+        | Operator::MemoryAtomicNotify { .. }
+        | Operator::MemoryAtomicWait32 { .. }
+        | Operator::MemoryAtomicWait64 { .. }
+        | Operator::V128Load { .. }
+        | Operator::V128Load16Lane { .. }
+        | Operator::V128Load16Splat { .. }
+        | Operator::V128Load16x4S { .. }
+        | Operator::V128Load16x4U { .. }
+        | Operator::V128Load32Lane { .. }
+        | Operator::V128Load32Splat { .. }
+        | Operator::V128Load32Zero { .. }
+        | Operator::V128Load32x2S { .. }
+        | Operator::V128Load32x2U { .. }
+        | Operator::V128Load64Lane { .. }
+        | Operator::V128Load64Splat { .. }
+        | Operator::V128Load64Zero { .. }
+        | Operator::V128Load8Lane { .. }
+        | Operator::V128Load8Splat { .. }
+        | Operator::V128Load8x8S { .. }
+        | Operator::V128Load8x8U { .. }
+        | Operator::V128Store { .. }
+        | Operator::V128Store16Lane { .. }
+        | Operator::V128Store32Lane { .. }
+        | Operator::V128Store64Lane { .. }
+        | Operator::V128Store8Lane { .. } => true,
+
+        // Bulk memory and table instructions, and calls to the resource
+        // limiter.
+        Operator::MemoryCopy { .. }
+        | Operator::MemoryFill { .. }
+        | Operator::MemoryInit { .. }
+        | Operator::MemoryGrow { .. }
+        | Operator::TableGet { .. }
+        | Operator::TableSet { .. }
+        | Operator::TableCopy { .. }
+        | Operator::TableFill { .. }
+        | Operator::TableInit { .. }
+        | Operator::TableGrow { .. } => true,
+
+        // Division by zero, integer overflow and invalid conversions.
+        Operator::I32DivS
+        | Operator::I32DivU
+        | Operator::I32RemS
+        | Operator::I32RemU
+        | Operator::I64DivS
+        | Operator::I64DivU
+        | Operator::I64RemS
+        | Operator::I64RemU
+        | Operator::I32TruncF32S
+        | Operator::I32TruncF32U
+        | Operator::I32TruncF64S
+        | Operator::I32TruncF64U
+        | Operator::I64TruncF32S
+        | Operator::I64TruncF32U
+        | Operator::I64TruncF64S
+        | Operator::I64TruncF64U => true,
+
+        Operator::RefAsNonNull => true,
+
+        // Fuel and epoch checks happen at loop headers.
+        Operator::Loop { .. } => true,
+
+        // Exceptions and continuations are handled by libcalls.
+        Operator::Throw { .. }
+        | Operator::Rethrow { .. }
+        | Operator::ContNew { .. }
+        | Operator::ContBind { .. }
+        | Operator::Suspend { .. } => true,
+
+        _ => false,
+    }
+}
+
 macro_rules! generate_builtin_call {
     ($self : ident, $builder: ident, $builtin_name: ident, $args: expr) => {{
         let index = BuiltinFunctionIndex::$builtin_name();
@@ -1266,6 +1840,7 @@ impl<'module_environment> cranelift_wasm::FuncEnvironment for FuncEnvironment<'m
     }
 
     fn after_locals(&mut self, num_locals: usize) {
+        self.num_locals = u32::try_from(num_locals).unwrap();
         self.vmruntime_limits_ptr = Variable::new(num_locals);
         self.fuel_var = Variable::new(num_locals + 1);
         self.epoch_deadline_var = Variable::new(num_locals + 2);
@@ -2449,6 +3024,19 @@ impl<'module_environment> cranelift_wasm::FuncEnvironment for FuncEnvironment<'m
         if self.tunables.consume_fuel {
             self.fuel_before_op(op, builder, state.reachable());
         }
+        if self.tunables.debug_instrumentation && state.reachable() {
+            self.debug_before_op(op, builder, state);
+        }
+        // Tail calls leave the function without going through
+        // `handle_before_return`.
+        if self.tunables.instrumentation.calls_enabled() && state.reachable() {
+            if let Operator::ReturnCall { .. }
+            | Operator::ReturnCallIndirect { .. }
+            | Operator::ReturnCallRef { .. } = op
+            {
+                self.instrumentation_function_exit(builder);
+            }
+        }
         Ok(())
     }
 
@@ -2482,7 +3070,11 @@ impl<'module_environment> cranelift_wasm::FuncEnvironment for FuncEnvironment<'m
     ) -> WasmResult<()> {
         // If the `vmruntime_limits_ptr` variable will get used then we initialize
         // it here.
-        if self.tunables.consume_fuel || self.tunables.epoch_interruption {
+        if self.tunables.consume_fuel
+            || self.tunables.epoch_interruption
+            || self.tunables.exceptions
+            || self.tunables.debug_instrumentation
+        {
             self.declare_vmruntime_limits_ptr(builder);
         }
         // Additionally we initialize `fuel_var` if it will get used.
@@ -2493,12 +3085,18 @@ impl<'module_environment> cranelift_wasm::FuncEnvironment for FuncEnvironment<'m
         if self.tunables.epoch_interruption {
             self.epoch_function_entry(builder);
         }
+        if self.tunables.debug_instrumentation {
+            self.debug_function_entry(builder);
+        }
+        if self.tunables.instrumentation.calls_enabled() {
+            self.instrumentation_function_entry(builder);
+        }
 
-        let func_name = self.current_func_name(builder);
-        if func_name == Some("malloc") {
-            self.check_malloc_start(builder);
-        } else if func_name == Some("free") {
-            self.check_free_start(builder);
+        if self.tunables.instrumentation.allocations_enabled() {
+            let func_name = self.current_func_name(builder);
+            if func_name == Some("malloc") || func_name == Some("free") {
+                self.instrumentation_allocator_entry(builder);
+            }
         }
 
         Ok(())
@@ -2512,6 +3110,9 @@ impl<'module_environment> cranelift_wasm::FuncEnvironment for FuncEnvironment<'m
         if self.tunables.consume_fuel && state.reachable() {
             self.fuel_function_exit(builder);
         }
+        if self.tunables.debug_instrumentation {
+            self.debug_function_exit(builder);
+        }
         Ok(())
     }
 
@@ -2527,6 +3128,103 @@ impl<'module_environment> cranelift_wasm::FuncEnvironment for FuncEnvironment<'m
         self.isa.triple().architecture == target_lexicon::Architecture::X86_64
     }
 
+    fn exceptions_enabled(&self) -> bool {
+        self.tunables.exceptions
+    }
+
+    fn translate_throw(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        tag_index: u32,
+        args: &[ir::Value],
+    ) -> WasmResult<()> {
+        // The payload is passed to the runtime through the same buffer as the
+        // payload of a `suspend`.
+        let param_types = self.tag_params(tag_index).to_vec();
+        self.typed_continuations_store_payloads(builder, &param_types, args);
+        let tag = builder.ins().iconst(I32, i64::from(tag_index));
+        generate_builtin_call_no_return_val!(self, builder, throw, [tag]);
+        Ok(())
+    }
+
+    fn translate_rethrow(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        exn: ir::Value,
+    ) -> WasmResult<()> {
+        generate_builtin_call_no_return_val!(self, builder, rethrow, [exn]);
+        Ok(())
+    }
+
+    fn translate_exception_landing(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        call: ir::Inst,
+    ) -> WasmResult<()> {
+        // Other calls made for the same instruction share the location, but no
+        // exception is ever dispatched through them.
+        self.exception_landings.push(builder.func.srcloc(call));
+        Ok(())
+    }
+
+    fn translate_unwind(&mut self, builder: &mut FunctionBuilder) -> WasmResult<()> {
+        // The frame is discarded, so save the fuel this function consumed.
+        if self.tunables.consume_fuel {
+            self.fuel_save_from_var(builder);
+        }
+        generate_builtin_call_no_return_val!(self, builder, unwind, []);
+        Ok(())
+    }
+
+    fn translate_exception_pending(
+        &mut self,
+        builder: &mut FunctionBuilder,
+    ) -> WasmResult<ir::Value> {
+        // The handler may be reached without going through `fuel_after_op`, so
+        // reload the fuel which the callee may have consumed here.
+        if self.tunables.consume_fuel {
+            self.fuel_load_into_var(builder);
+        }
+        let limits = builder.use_var(self.vmruntime_limits_ptr);
+        let offset = i32::from(self.offsets.ptr.vmruntime_limits_pending_exception());
+        Ok(builder
+            .ins()
+            .load(self.pointer_type(), ir::MemFlags::trusted(), limits, offset))
+    }
+
+    fn translate_catch_exception(
+        &mut self,
+        builder: &mut FunctionBuilder,
+    ) -> WasmResult<ir::Value> {
+        let (_vmctx, exn) = generate_builtin_call!(self, builder, catch_exception, []);
+        Ok(exn)
+    }
+
+    fn translate_exception_has_tag(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        exn: ir::Value,
+        tag_index: u32,
+    ) -> WasmResult<ir::Value> {
+        let tag = builder.ins().iconst(I32, i64::from(tag_index));
+        let (_vmctx, has_tag) =
+            generate_builtin_call!(self, builder, exception_has_tag, [exn, tag]);
+        Ok(has_tag)
+    }
+
+    fn translate_exception_payload(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        exn: ir::Value,
+        tag_index: u32,
+    ) -> WasmResult<Vec<ir::Value>> {
+        let param_types = self.tag_params(tag_index).to_vec();
+        if !param_types.is_empty() {
+            generate_builtin_call_no_return_val!(self, builder, exception_payload, [exn]);
+        }
+        Ok(self.typed_continuations_load_payloads(builder, &param_types))
+    }
+
     fn translate_cont_new(
         &mut self,
         builder: &mut FunctionBuilder,
@@ -2556,13 +3254,30 @@ impl<'module_environment> cranelift_wasm::FuncEnvironment for FuncEnvironment<'m
     ) -> WasmResult<(ir::Value, ir::Value, ir::Value)> {
         // Strategy:
         //
+        // First, call the `resume_start` builtin, which does the bookkeeping
+        // for entering the continuation and returns the contexts to switch
+        // between.
         //
-        // First, store the remainder of `call_args` in the
-        // designated typed continuation store in the VM context.
+        // Second, switch to the continuation's stack, saving our own context
+        // for it to switch back to when it returns or suspends.
         //
-        // Second: Call the `resume` builtin
+        // Third, pass the payload it switched back with to the
+        // `resume_finish` builtin, which tells us how it came back.
 
-        let (vmctx, result) = generate_builtin_call!(self, builder, resume, [contobj]);
+        let (_vmctx, contexts) = generate_builtin_call!(self, builder, resume_start, [contobj]);
+        let cont_context = builder
+            .ins()
+            .iadd_imm(contexts, self.stack_switch_context_size());
+        let payload = builder.ins().iconst(self.pointer_type(), 0);
+        let payload = builder.ins().stack_switch(contexts, cont_context, payload);
+        let (vmctx, result) =
+            generate_builtin_call!(self, builder, resume_finish, [contobj, payload]);
+
+        // The code after `resume` branches on how the continuation returned,
+        // so reload the fuel it consumed here rather than in `fuel_after_op`.
+        if self.tunables.consume_fuel {
+            self.fuel_load_into_var(builder);
+        }
 
         // The result encodes whether the return happens via ordinary
         // means or via a suspend. If the high bit is set, then it is
@@ -2596,8 +3311,19 @@ impl<'module_environment> cranelift_wasm::FuncEnvironment for FuncEnvironment<'m
         _state: &FuncTranslationState,
         tag_index: ir::Value,
     ) -> ir::Value {
+        // Switch back to the stack which resumed us, saving our own context
+        // for the next `resume`. The payload tells `resume_finish` which tag
+        // we suspended with; see `translate_resume`.
+        let (vmctx, contexts) = generate_builtin_call!(self, builder, suspend_start, [tag_index]);
+        let cont_context = builder
+            .ins()
+            .iadd_imm(contexts, self.stack_switch_context_size());
+        let signal = builder.ins().bor_imm(tag_index, 0xf000_0000);
+        let payload = builder.ins().uextend(self.pointer_type(), signal);
+        builder.ins().stack_switch(cont_context, contexts, payload);
+
         // Returns the vmctx
-        return generate_builtin_call_no_return_val!(self, builder, suspend, [tag_index]);
+        vmctx
     }
 
     fn continuation_arguments(&self, index: u32) -> &[WasmType] {
@@ -2890,102 +3616,63 @@ impl<'module_environment> cranelift_wasm::FuncEnvironment for FuncEnvironment<'m
         self.isa.has_x86_pmaddubsw_lowering()
     }
 
-    cfg_if! {
-        if #[cfg(feature = "wmemcheck")] {
-            fn handle_before_return(
-                &mut self,
-                retvals: &[Value],
-                builder: &mut FunctionBuilder,
-            ) {
-                if self.wmemcheck {
-                    let func_name = self.current_func_name(builder);
-                    if func_name == Some("malloc") {
-                        self.hook_malloc_exit(builder, retvals);
-                    } else if func_name == Some("free") {
-                        self.hook_free_exit(builder);
-                    }
-                }
-            }
-
-            fn before_load(&mut self, builder: &mut FunctionBuilder, val_size: u8, addr: ir::Value, offset: u64) {
-                if self.wmemcheck {
-                    let check_load_sig = self.builtin_function_signatures.check_load(builder.func);
-                    let (vmctx, check_load) = self.translate_load_builtin_function_address(
-                        &mut builder.cursor(),
-                        BuiltinFunctionIndex::check_load(),
-                    );
-                    let num_bytes = builder.ins().iconst(I32, val_size as i64);
-                    let offset_val = builder.ins().iconst(I64, offset as i64);
-                    builder
-                        .ins()
-                        .call_indirect(check_load_sig, check_load, &[vmctx, num_bytes, addr, offset_val]);
-                }
-            }
-
-            fn before_store(&mut self, builder: &mut FunctionBuilder, val_size: u8, addr: ir::Value, offset: u64) {
-                if self.wmemcheck {
-                    let check_store_sig = self.builtin_function_signatures.check_store(builder.func);
-                    let (vmctx, check_store) = self.translate_load_builtin_function_address(
-                        &mut builder.cursor(),
-                        BuiltinFunctionIndex::check_store(),
-                    );
-                    let num_bytes = builder.ins().iconst(I32, val_size as i64);
-                    let offset_val = builder.ins().iconst(I64, offset as i64);
-                    builder
-                        .ins()
-                        .call_indirect(check_store_sig, check_store, &[vmctx, num_bytes, addr, offset_val]);
-                }
-            }
-
-            fn update_global(&mut self, builder: &mut FunctionBuilder, global_index: u32, value: ir::Value) {
-                if self.wmemcheck {
-                    if global_index == 0 {
-                        // We are making the assumption that global 0 is the auxiliary stack pointer.
-                        let update_stack_pointer_sig = self.builtin_function_signatures.update_stack_pointer(builder.func);
-                        let (vmctx, update_stack_pointer) = self.translate_load_builtin_function_address(
-                            &mut builder.cursor(),
-                            BuiltinFunctionIndex::update_stack_pointer(),
-                        );
-                        builder
-                            .ins()
-                            .call_indirect(update_stack_pointer_sig, update_stack_pointer, &[vmctx, value]);
-                    }
-                }
-            }
-
-            fn before_memory_grow(&mut self, builder: &mut FunctionBuilder, num_pages: ir::Value, mem_index: MemoryIndex) {
-                if self.wmemcheck && mem_index.as_u32() == 0 {
-                    let update_mem_size_sig = self.builtin_function_signatures.update_mem_size(builder.func);
-                    let (vmctx, update_mem_size) = self.translate_load_builtin_function_address(
-                        &mut builder.cursor(),
-                        BuiltinFunctionIndex::update_mem_size(),
-                    );
-                    builder
-                        .ins()
-                        .call_indirect(update_mem_size_sig, update_mem_size, &[vmctx, num_pages]);
-                }
-            }
-        } else {
-            fn handle_before_return(&mut self, _retvals: &[Value], builder: &mut FunctionBuilder) {
-                let _ = self.builtin_function_signatures.check_malloc(builder.func);
-                let _ = self.builtin_function_signatures.check_free(builder.func);
+    fn handle_before_return(&mut self, retvals: &[Value], builder: &mut FunctionBuilder) {
+        if self.tunables.instrumentation.calls_enabled() {
+            self.instrumentation_function_exit(builder);
+        }
+        if self.tunables.instrumentation.allocations_enabled() {
+            let func_name = self.current_func_name(builder);
+            if func_name == Some("malloc") {
+                self.instrumentation_malloc_exit(builder, retvals);
+            } else if func_name == Some("free") {
+                self.instrumentation_free_exit(builder);
             }
+        }
+    }
 
-            fn before_load(&mut self, builder: &mut FunctionBuilder, _val_size: u8, _addr: ir::Value, _offset: u64) {
-                let _ = self.builtin_function_signatures.check_load(builder.func);
-            }
+    fn before_load(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        val_size: u8,
+        addr: ir::Value,
+        offset: u64,
+        mem_index: MemoryIndex,
+    ) {
+        self.instrumentation_memory_access(builder, false, val_size, addr, offset, mem_index);
+    }
 
-            fn before_store(&mut self, builder: &mut FunctionBuilder, _val_size: u8, _addr: ir::Value, _offset: u64) {
-                let _ = self.builtin_function_signatures.check_store(builder.func);
-            }
+    fn before_store(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        val_size: u8,
+        addr: ir::Value,
+        offset: u64,
+        mem_index: MemoryIndex,
+    ) {
+        self.instrumentation_memory_access(builder, true, val_size, addr, offset, mem_index);
+    }
 
-            fn update_global(&mut self, builder: &mut FunctionBuilder, _global_index: u32, _value: ir::Value) {
-                let _ = self.builtin_function_signatures.update_stack_pointer(builder.func);
-            }
+    fn update_global(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        global_index: u32,
+        value: ir::Value,
+    ) {
+        // We are making the assumption that global 0 is the auxiliary stack
+        // pointer.
+        if global_index == 0 && self.tunables.instrumentation.stack_pointer_enabled() {
+            self.instrumentation_stack_pointer(builder, value);
+        }
+    }
 
-            fn before_memory_grow(&mut self, builder: &mut FunctionBuilder, _num_pages: Value, _mem_index: MemoryIndex) {
-                let _ = self.builtin_function_signatures.update_mem_size(builder.func);
-            }
+    fn before_memory_grow(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        num_pages: ir::Value,
+        mem_index: MemoryIndex,
+    ) {
+        if self.tunables.instrumentation.memory_grow_enabled() {
+            self.instrumentation_memory_grow(builder, num_pages, mem_index);
         }
     }
 }
@@ -144,14 +144,12 @@ fn wasm_call_signature(
         // If the tail calls proposal is enabled, we must use the tail calling
         // convention. We don't use it by default yet because of
         // https://github.com/bytecodealliance/wasmtime/issues/6759
-        arch if tunables.tail_callable => {
-            assert_ne!(
-                arch,
-                Architecture::S390x,
-                "https://github.com/bytecodealliance/wasmtime/issues/6530"
-            );
-            CallConv::Tail
-        }
+        //
+        // Exceptions need it too: the runtime resumes a function after a call
+        // without restoring any registers, which is only sound when the call
+        // clobbers all of them, and the tail calling convention has no
+        // callee-saved registers.
+        _ if tunables.tail_callable || tunables.exceptions => CallConv::Tail,
 
         // On s390x the "wasmtime" calling convention is used to give vectors
         // little-endian lane order at the ABI layer which should reduce the
@@ -51,13 +51,41 @@ macro_rules! foreach_builtin_function {
             out_of_gas(vmctx: vmctx);
             /// Invoked when we reach a new epoch.
             new_epoch(vmctx: vmctx) -> i64;
+            /// Invoked by code compiled with debug instrumentation when the
+            /// store wants to pause before an instruction.
+            debug_break(vmctx: vmctx);
+            /// Invoked by instrumented code on entry to a function.
+            instrument_call(vmctx: vmctx, func: i32);
+            /// Invoked by instrumented code before a function returns.
+            instrument_return(vmctx: vmctx, func: i32);
+            /// Invoked by instrumented code before `memory.grow`.
+            instrument_memory_grow(vmctx: vmctx, memory: i32, delta: i64);
+            /// Invoked by instrumented code before a load in an instrumented
+            /// address range.
+            instrument_load(vmctx: vmctx, memory: i32, addr: i64, size: i32);
+            /// Invoked by instrumented code before a store in an instrumented
+            /// address range.
+            instrument_store(vmctx: vmctx, memory: i32, addr: i64, size: i32);
+            /// Invoked by instrumented code on entry to `malloc` or `free`.
+            instrument_allocator_entry(vmctx: vmctx);
+            /// Invoked by instrumented code before `malloc` returns.
+            instrument_malloc(vmctx: vmctx, addr: i64, size: i64);
+            /// Invoked by instrumented code before `free` returns.
+            instrument_free(vmctx: vmctx, addr: i64);
+            /// Invoked by instrumented code before the stack pointer is set.
+            instrument_stack_pointer(vmctx: vmctx, value: i64);
 
             /// Creates a new continuation from a funcref.
             cont_new(vmctx: vmctx, r: pointer, param_count: i64, result_count: i64) -> pointer;
-            /// Resumes a continuation.
-            resume(vmctx: vmctx, contobj: pointer) -> i32;
-            /// Suspends a continuation.
-            suspend(vmctx: vmctx, tag: i32);
+            /// Prepares resuming a continuation and returns the contexts to
+            /// switch between with `stack_switch`.
+            resume_start(vmctx: vmctx, contobj: pointer) -> pointer;
+            /// Finishes resuming a continuation once it switched back with
+            /// the given payload, and returns how it did so.
+            resume_finish(vmctx: vmctx, contobj: pointer, payload: i64) -> i32;
+            /// Prepares suspending the running continuation and returns the
+            /// contexts to switch between with `stack_switch`.
+            suspend_start(vmctx: vmctx, tag: i32) -> pointer;
             /// Projects the buffer storing the results after a continuation
             /// function has returned normally.
             /// Must only be called after the continuation has returned and
@@ -115,22 +143,23 @@ macro_rules! foreach_builtin_function {
             /// TODO
             drop_cont_obj(vmctx: vmctx, contobj: pointer);
 
-            /// Invoked before malloc returns.
-            check_malloc(vmctx: vmctx, addr: i32, len: i32) -> i32;
-            /// Invoked before the free returns.
-            check_free(vmctx: vmctx, addr: i32) -> i32;
-            /// Invoked before a load is executed.
-            check_load(vmctx: vmctx, num_bytes: i32, addr: i32, offset: i32) -> i32;
-            /// Invoked before a store is executed.
-            check_store(vmctx: vmctx, num_bytes: i32, addr: i32, offset: i32) -> i32;
-            /// Invoked after malloc is called.
-            malloc_start(vmctx: vmctx);
-            /// Invoked after free is called.
-            free_start(vmctx: vmctx);
-            /// Invoked when wasm stack pointer is updated.
-            update_stack_pointer(vmctx: vmctx, value: i32);
-            /// Invoked before memory.grow is called.
-            update_mem_size(vmctx: vmctx, num_bytes: i32);
+            /// Throws an exception of the given tag, whose payload has been
+            /// stored in the payload buffer, by making it the pending exception.
+            throw(vmctx: vmctx, tag: i32);
+            /// Rethrows a caught exception by making it the pending exception
+            /// again.
+            rethrow(vmctx: vmctx, exn: reference);
+            /// Unwinds the frames of the caller and of its callers, up to the
+            /// handler of the pending exception. Never returns.
+            unwind(vmctx: vmctx);
+            /// Takes the pending exception to be handled by a `try` block.
+            catch_exception(vmctx: vmctx) -> reference;
+            /// Returns a boolean indicating whether the exception has the given
+            /// tag of the calling instance.
+            exception_has_tag(vmctx: vmctx, exn: reference, tag: i32) -> i32;
+            /// Allocates a payload buffer and stores the exception's payload in
+            /// it, to be read with `get_payload_buffer`.
+            exception_payload(vmctx: vmctx, exn: reference);
         }
     };
 }
@@ -16,6 +16,7 @@ use std::collections::BTreeMap;
 use std::fmt;
 use std::path;
 use std::sync::Arc;
+use std::time::Duration;
 use thiserror::Error;
 
 /// Information about a function, such as trap information, address map,
@@ -25,6 +26,29 @@ use thiserror::Error;
 pub struct WasmFunctionInfo {
     pub start_srcloc: FilePos,
     pub stack_maps: Box<[StackMapInformation]>,
+    pub exception_landings: Box<[ExceptionLanding]>,
+    pub stats: FunctionStats,
+}
+
+/// Statistics about the compilation of a function, reported by
+/// `Module::function_reports`.
+#[derive(Serialize, Deserialize, Default, Debug, Copy, Clone)]
+pub struct FunctionStats {
+    /// The size in bytes of the function's body in the original wasm binary.
+    pub body_size: u32,
+    /// The number of instructions in the compiled code which may trap.
+    pub trap_sites: u32,
+    /// The number of IR instructions of the function after translation from
+    /// wasm, before optimizations.
+    pub insts_before_opt: u32,
+    /// The number of IR instructions of the function after optimizations.
+    pub insts_after_opt: u32,
+    /// The time it took to compile the function.
+    ///
+    /// This isn't part of serialized modules, to keep them deterministic, so
+    /// it's only known for modules compiled by this process.
+    #[serde(skip)]
+    pub compile_time: Option<Duration>,
 }
 
 /// Description of where a function is located in the text section of a
@@ -50,6 +74,20 @@ pub struct StackMapInformation {
     pub stack_map: StackMap,
 }
 
+/// The offset within a function of the return address of a call within the
+/// body of a `try` block, where the exceptions thrown by the callee land.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct ExceptionLanding {
+    /// The offset of the return address within the function's native code. It
+    /// is relative to the beginning of the function.
+    pub code_offset: u32,
+
+    /// The distance from the stack pointer to the frame pointer at the return
+    /// address, from which the stack pointer is restored when an exception
+    /// lands there.
+    pub sp_to_fp: u32,
+}
+
 /// An error while compiling WebAssembly to machine code.
 #[derive(Error, Debug)]
 pub enum CompileError {
@@ -127,9 +165,6 @@ pub trait CompilerBuilder: Send + Sync + fmt::Debug {
 
     /// Builds a new [`Compiler`] object from this configuration.
     fn build(&self) -> Result<Box<dyn Compiler>>;
-
-    /// Enables or disables wmemcheck during runtime according to the wmemcheck CLI flag.
-    fn wmemcheck(&mut self, _enable: bool) {}
 }
 
 /// Description of compiler settings returned by [`CompilerBuilder::settings`].
@@ -167,6 +202,41 @@ pub enum ObjectKind {
     Component,
 }
 
+/// A compiler which compiles the functions of a module in two steps so that
+/// calls between them can be inlined.
+///
+/// Every function of a module is first translated with
+/// [`InliningCompiler::translate_function`], and only then is each function
+/// compiled with [`InliningCompiler::compile_translated_function`], which has
+/// access to the translations of all the functions it may call.
+pub trait InliningCompiler: Send + Sync {
+    /// Translates the function `index` within `translation` to the compiler's
+    /// intermediate representation without compiling it.
+    ///
+    /// The arguments are the same as those of [`Compiler::compile_function`].
+    fn translate_function(
+        &self,
+        translation: &ModuleTranslation<'_>,
+        index: DefinedFuncIndex,
+        data: FunctionBodyData<'_>,
+        types: &ModuleTypes,
+    ) -> Result<Box<dyn Any + Send + Sync>, CompileError>;
+
+    /// Compiles the function `index` within `translation`.
+    ///
+    /// `translated` holds what [`InliningCompiler::translate_function`]
+    /// returned for every defined function of the module, including `index`
+    /// itself. The bodies of the other functions may be inlined at calls to
+    /// them.
+    fn compile_translated_function(
+        &self,
+        translation: &ModuleTranslation<'_>,
+        index: DefinedFuncIndex,
+        translated: &PrimaryMap<DefinedFuncIndex, Box<dyn Any + Send + Sync>>,
+        types: &ModuleTypes,
+    ) -> Result<(WasmFunctionInfo, Box<dyn Any + Send>), CompileError>;
+}
+
 /// An implementation of a compiler which can compile WebAssembly functions to
 /// machine code and perform other miscellaneous tasks needed by the JIT runtime.
 pub trait Compiler: Send + Sync {
@@ -356,6 +426,15 @@ pub trait Compiler: Send + Sync {
     /// Get a flag indicating whether branch protection is enabled.
     fn is_branch_protection_enabled(&self) -> bool;
 
+    /// Returns a compiler which can inline calls between the functions of a
+    /// module, or `None` if inlining isn't supported or is disabled.
+    ///
+    /// When this returns `Some`, the functions of a module are compiled with
+    /// [`InliningCompiler`] rather than with [`Compiler::compile_function`].
+    fn inlining_compiler(&self) -> Option<&dyn InliningCompiler> {
+        None
+    }
+
     /// Returns a suitable compiler usable for component-related compliations.
     ///
     /// Note that the `ComponentCompiler` trait can also be implemented for
@@ -0,0 +1,211 @@
+use serde::{Deserialize, Serialize};
+use wasmparser::Operator;
+
+/// The amount of fuel consumed by each kind of WebAssembly operator when fuel
+/// consumption is enabled.
+///
+/// Operators are grouped into a few categories, each with its own cost. The
+/// default costs charge one unit of fuel for every operator, except for
+/// `nop`, `drop` and structured control flow which are free.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FuelCosts {
+    default: u32,
+    nop: u32,
+    control: u32,
+    memory_load: u32,
+    memory_store: u32,
+    memory_grow: u32,
+    bulk_memory: u32,
+    call: u32,
+    call_indirect: u32,
+    continuation_switch: u32,
+}
+
+impl Default for FuelCosts {
+    fn default() -> Self {
+        Self {
+            default: 1,
+            nop: 0,
+            control: 0,
+            memory_load: 1,
+            memory_store: 1,
+            memory_grow: 1,
+            bulk_memory: 1,
+            call: 1,
+            call_indirect: 1,
+            continuation_switch: 1,
+        }
+    }
+}
+
+impl FuelCosts {
+    /// Creates the default cost model, see [`FuelCosts`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the cost of operators which don't belong to any other category,
+    /// such as arithmetic, locals and globals.
+    pub fn default_cost(&mut self, cost: u32) -> &mut Self {
+        self.default = cost;
+        self
+    }
+
+    /// Sets the cost of `nop` and `drop`, which generate no code.
+    pub fn nop(&mut self, cost: u32) -> &mut Self {
+        self.nop = cost;
+        self
+    }
+
+    /// Sets the cost of `block`, `loop`, `try`, `else`, `end`, `return` and
+    /// `unreachable`.
+    ///
+    /// Branches are not part of this category.
+    pub fn control(&mut self, cost: u32) -> &mut Self {
+        self.control = cost;
+        self
+    }
+
+    /// Sets the cost of loads from linear memory, including atomic and SIMD
+    /// loads.
+    pub fn memory_load(&mut self, cost: u32) -> &mut Self {
+        self.memory_load = cost;
+        self
+    }
+
+    /// Sets the cost of stores to linear memory, including atomic and SIMD
+    /// stores.
+    pub fn memory_store(&mut self, cost: u32) -> &mut Self {
+        self.memory_store = cost;
+        self
+    }
+
+    /// Sets the cost of `memory.grow`.
+    pub fn memory_grow(&mut self, cost: u32) -> &mut Self {
+        self.memory_grow = cost;
+        self
+    }
+
+    /// Sets the cost of `memory.copy`, `memory.fill` and `memory.init`.
+    ///
+    /// This is a fixed cost, independent of the number of bytes involved.
+    pub fn bulk_memory(&mut self, cost: u32) -> &mut Self {
+        self.bulk_memory = cost;
+        self
+    }
+
+    /// Sets the cost of direct calls, `call` and `return_call`.
+    pub fn call(&mut self, cost: u32) -> &mut Self {
+        self.call = cost;
+        self
+    }
+
+    /// Sets the cost of indirect calls, `call_indirect`, `call_ref` and their
+    /// `return_*` counterparts.
+    pub fn call_indirect(&mut self, cost: u32) -> &mut Self {
+        self.call_indirect = cost;
+        self
+    }
+
+    /// Sets the cost of switching between continuations with `resume`,
+    /// `resume_throw` and `suspend`.
+    pub fn continuation_switch(&mut self, cost: u32) -> &mut Self {
+        self.continuation_switch = cost;
+        self
+    }
+
+    /// Returns the fuel consumed by executing `op`.
+    pub fn cost(&self, op: &Operator<'_>) -> u32 {
+        match op {
+            Operator::Nop | Operator::Drop => self.nop,
+
+            Operator::Block { .. }
+            | Operator::Loop { .. }
+            | Operator::Try { .. }
+            | Operator::Unreachable
+            | Operator::Return
+            | Operator::Else
+            | Operator::End => self.control,
+
+            Operator::I32Load { .. }
+            | Operator::I64Load { .. }
+            | Operator::F32Load { .. }
+            | Operator::F64Load { .. }
+            | Operator::I32Load8S { .. }
+            | Operator::I32Load8U { .. }
+            | Operator::I32Load16S { .. }
+            | Operator::I32Load16U { .. }
+            | Operator::I64Load8S { .. }
+            | Operator::I64Load8U { .. }
+            | Operator::I64Load16S { .. }
+            | Operator::I64Load16U { .. }
+            | Operator::I64Load32S { .. }
+            | Operator::I64Load32U { .. }
+            | Operator::I32AtomicLoad { .. }
+            | Operator::I64AtomicLoad { .. }
+            | Operator::I32AtomicLoad8U { .. }
+            | Operator::I32AtomicLoad16U { .. }
+            | Operator::I64AtomicLoad8U { .. }
+            | Operator::I64AtomicLoad16U { .. }
+            | Operator::I64AtomicLoad32U { .. }
+            | Operator::V128Load { .. }
+            | Operator::V128Load8x8S { .. }
+            | Operator::V128Load8x8U { .. }
+            | Operator::V128Load16x4S { .. }
+            | Operator::V128Load16x4U { .. }
+            | Operator::V128Load32x2S { .. }
+            | Operator::V128Load32x2U { .. }
+            | Operator::V128Load8Splat { .. }
+            | Operator::V128Load16Splat { .. }
+            | Operator::V128Load32Splat { .. }
+            | Operator::V128Load64Splat { .. }
+            | Operator::V128Load32Zero { .. }
+            | Operator::V128Load64Zero { .. }
+            | Operator::V128Load8Lane { .. }
+            | Operator::V128Load16Lane { .. }
+            | Operator::V128Load32Lane { .. }
+            | Operator::V128Load64Lane { .. } => self.memory_load,
+
+            Operator::I32Store { .. }
+            | Operator::I64Store { .. }
+            | Operator::F32Store { .. }
+            | Operator::F64Store { .. }
+            | Operator::I32Store8 { .. }
+            | Operator::I32Store16 { .. }
+            | Operator::I64Store8 { .. }
+            | Operator::I64Store16 { .. }
+            | Operator::I64Store32 { .. }
+            | Operator::I32AtomicStore { .. }
+            | Operator::I64AtomicStore { .. }
+            | Operator::I32AtomicStore8 { .. }
+            | Operator::I32AtomicStore16 { .. }
+            | Operator::I64AtomicStore8 { .. }
+            | Operator::I64AtomicStore16 { .. }
+            | Operator::I64AtomicStore32 { .. }
+            | Operator::V128Store { .. }
+            | Operator::V128Store8Lane { .. }
+            | Operator::V128Store16Lane { .. }
+            | Operator::V128Store32Lane { .. }
+            | Operator::V128Store64Lane { .. } => self.memory_store,
+
+            Operator::MemoryGrow { .. } => self.memory_grow,
+
+            Operator::MemoryCopy { .. }
+            | Operator::MemoryFill { .. }
+            | Operator::MemoryInit { .. } => self.bulk_memory,
+
+            Operator::Call { .. } | Operator::ReturnCall { .. } => self.call,
+
+            Operator::CallIndirect { .. }
+            | Operator::ReturnCallIndirect { .. }
+            | Operator::CallRef { .. }
+            | Operator::ReturnCallRef { .. } => self.call_indirect,
+
+            Operator::Resume { .. } | Operator::ResumeThrow { .. } | Operator::Suspend { .. } => {
+                self.continuation_switch
+            }
+
+            _ => self.default,
+        }
+    }
+}
@@ -0,0 +1,199 @@
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// The events that compiled code reports to the host when instrumentation is
+/// enabled.
+///
+/// Each kind of event is opt-in, and code for the events which aren't
+/// enabled isn't generated at all. By default nothing is instrumented.
+#[derive(Clone, Debug, Default, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Instrumentation {
+    calls: bool,
+    memory_grow: bool,
+    memory_ranges: Vec<Range<u64>>,
+    allocations: bool,
+    stack_pointer: bool,
+}
+
+impl Instrumentation {
+    /// Creates a configuration with no instrumentation enabled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Configures whether an event is reported on entry to, and exit from,
+    /// every WebAssembly function.
+    ///
+    /// Exit events are reported for returns and tail calls, but not when a
+    /// function is unwound by a trap or an exception.
+    pub fn calls(mut self, enable: bool) -> Self {
+        self.calls = enable;
+        self
+    }
+
+    /// Configures whether an event is reported before every `memory.grow`.
+    pub fn memory_grow(mut self, enable: bool) -> Self {
+        self.memory_grow = enable;
+        self
+    }
+
+    /// Adds a range of addresses whose loads and stores are reported.
+    ///
+    /// An event is reported for every scalar load or store which accesses at
+    /// least one byte within `range`, in any linear memory. The range is
+    /// checked inline by compiled code, so accesses outside of it only cost a
+    /// few instructions. SIMD loads and stores are not reported.
+    pub fn memory_range(mut self, range: Range<u64>) -> Self {
+        self.memory_ranges.push(range);
+        self
+    }
+
+    /// Configures whether calls to the functions named `malloc` and `free`
+    /// in the module's name section are reported.
+    ///
+    /// An event is reported on entry to either function, and another with
+    /// the allocated or freed address before it returns. The first parameter
+    /// of `malloc` is taken to be the requested size, its first result the
+    /// allocated address, and the first parameter of `free` the address to
+    /// free.
+    pub fn allocations(mut self, enable: bool) -> Self {
+        self.allocations = enable;
+        self
+    }
+
+    /// Configures whether every `global.set` of global 0, which toolchains
+    /// conventionally use as the shadow stack pointer, is reported.
+    pub fn stack_pointer(mut self, enable: bool) -> Self {
+        self.stack_pointer = enable;
+        self
+    }
+
+    /// Returns the instrumentation needed by the wmemcheck memory checker:
+    /// `memory.grow`, every load and store, allocations and the stack
+    /// pointer.
+    pub fn wmemcheck() -> Self {
+        Self::new()
+            .memory_grow(true)
+            .memory_range(0..u64::MAX)
+            .allocations(true)
+            .stack_pointer(true)
+    }
+
+    /// Returns instrumentation reporting every event reported by either
+    /// `self` or `other`.
+    pub fn union(&self, other: &Self) -> Self {
+        let mut memory_ranges = self.memory_ranges.clone();
+        memory_ranges.extend(other.memory_ranges.iter().cloned());
+        Self {
+            calls: self.calls || other.calls,
+            memory_grow: self.memory_grow || other.memory_grow,
+            memory_ranges,
+            allocations: self.allocations || other.allocations,
+            stack_pointer: self.stack_pointer || other.stack_pointer,
+        }
+    }
+
+    /// Returns whether `event` is one that this configuration asks for.
+    pub fn reports(&self, event: &InstrumentationEvent) -> bool {
+        let overlaps = |address: u64, size: u8| {
+            let end = address.saturating_add(u64::from(size));
+            self.memory_ranges
+                .iter()
+                .any(|range| !range.is_empty() && address < range.end && end > range.start)
+        };
+        match *event {
+            InstrumentationEvent::Call { .. } | InstrumentationEvent::Return { .. } => self.calls,
+            InstrumentationEvent::MemoryGrow { .. } => self.memory_grow,
+            InstrumentationEvent::Load { address, size, .. }
+            | InstrumentationEvent::Store { address, size, .. } => overlaps(address, size),
+            InstrumentationEvent::AllocatorEntry
+            | InstrumentationEvent::Malloc { .. }
+            | InstrumentationEvent::Free { .. } => self.allocations,
+            InstrumentationEvent::StackPointer { .. } => self.stack_pointer,
+        }
+    }
+
+    /// Returns whether function entry and exit are reported.
+    pub fn calls_enabled(&self) -> bool {
+        self.calls
+    }
+
+    /// Returns whether `memory.grow` is reported.
+    pub fn memory_grow_enabled(&self) -> bool {
+        self.memory_grow
+    }
+
+    /// Returns the address ranges whose loads and stores are reported.
+    pub fn memory_ranges(&self) -> &[Range<u64>] {
+        &self.memory_ranges
+    }
+
+    /// Returns whether calls to `malloc` and `free` are reported.
+    pub fn allocations_enabled(&self) -> bool {
+        self.allocations
+    }
+
+    /// Returns whether sets of the stack pointer are reported.
+    pub fn stack_pointer_enabled(&self) -> bool {
+        self.stack_pointer
+    }
+}
+
+/// An event reported by code compiled with [`Instrumentation`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum InstrumentationEvent {
+    /// A function was entered.
+    Call {
+        /// The index of the function within its module.
+        func_index: u32,
+    },
+    /// A function is about to return, or to tail call another function.
+    Return {
+        /// The index of the function within its module.
+        func_index: u32,
+    },
+    /// A `memory.grow` is about to be executed.
+    MemoryGrow {
+        /// The index of the memory within its module.
+        memory: u32,
+        /// The number of WebAssembly pages the memory is asked to grow by.
+        delta: u64,
+    },
+    /// A load within one of the configured ranges is about to be executed.
+    Load {
+        /// The index of the memory within its module.
+        memory: u32,
+        /// The effective address of the load, including its static offset.
+        address: u64,
+        /// The number of bytes loaded.
+        size: u8,
+    },
+    /// A store within one of the configured ranges is about to be executed.
+    Store {
+        /// The index of the memory within its module.
+        memory: u32,
+        /// The effective address of the store, including its static offset.
+        address: u64,
+        /// The number of bytes stored.
+        size: u8,
+    },
+    /// A function named `malloc` or `free` was entered.
+    AllocatorEntry,
+    /// A function named `malloc` is about to return.
+    Malloc {
+        /// The address returned, in the module's first memory.
+        address: u64,
+        /// The number of bytes requested.
+        size: u64,
+    },
+    /// A function named `free` is about to return.
+    Free {
+        /// The address freed, in the module's first memory.
+        address: u64,
+    },
+    /// Global 0, the stack pointer, is about to be set.
+    StackPointer {
+        /// The new value of the stack pointer.
+        value: u64,
+    },
+}
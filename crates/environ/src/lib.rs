@@ -26,6 +26,8 @@
 mod address_map;
 mod builtin;
 mod compilation;
+mod fuel;
+mod instrumentation;
 mod module;
 mod module_environ;
 mod module_types;
@@ -40,6 +42,8 @@ mod vmoffsets;
 pub use crate::address_map::*;
 pub use crate::builtin::*;
 pub use crate::compilation::*;
+pub use crate::fuel::FuelCosts;
+pub use crate::instrumentation::{Instrumentation, InstrumentationEvent};
 pub use crate::module::*;
 pub use crate::module_environ::*;
 pub use crate::module_types::*;
@@ -286,8 +286,15 @@ impl<'a, 'data> ModuleEnvironment<'a, 'data> {
                             EntityType::Table(self.convert_table_type(&ty))
                         }
 
-                        // doesn't get past validation
-                        TypeRef::Tag(_) => unreachable!(),
+                        // Tags are identified by their index within the module
+                        // throwing, catching, suspending to or resuming with
+                        // them, so they can't be shared between instances.
+                        TypeRef::Tag(_) => {
+                            return Err(WasmError::Unsupported(format!(
+                                "tag imports are not supported: {}::{}",
+                                import.module, import.name
+                            )));
+                        }
                     };
                     self.declare_import(import.module, import.name, ty);
                 }
@@ -374,10 +381,6 @@ impl<'a, 'data> ModuleEnvironment<'a, 'data> {
                     let sig_index = self.result.module.types[ty].unwrap_function();
                     self.result.module.push_tag(sig_index);
                 }
-
-                // This feature isn't enabled at this time, so we should
-                // never get here.
-                //unreachable!();
             }
 
             Payload::GlobalSection(globals) => {
@@ -437,8 +440,13 @@ impl<'a, 'data> ModuleEnvironment<'a, 'data> {
                         ExternalKind::Memory => EntityIndex::Memory(MemoryIndex::from_u32(index)),
                         ExternalKind::Global => EntityIndex::Global(GlobalIndex::from_u32(index)),
 
-                        // this never gets past validation
-                        ExternalKind::Tag => unreachable!(),
+                        // See the comment on tag imports above.
+                        ExternalKind::Tag => {
+                            return Err(WasmError::Unsupported(format!(
+                                "tag exports are not supported: {}",
+                                name
+                            )));
+                        }
                     };
                     self.result
                         .module
@@ -800,7 +808,7 @@ and for re-adding support for interface types you can see this issue:
                 EntityIndex::Memory(self.result.module.memory_plans.push(plan))
             }
             EntityType::Global(ty) => EntityIndex::Global(self.result.module.globals.push(ty)),
-            EntityType::Tag(_) => unimplemented!(),
+            EntityType::Tag(_) => unreachable!("tag imports are rejected"),
         }
     }
 
@@ -1,3 +1,4 @@
+use crate::{FuelCosts, Instrumentation};
 use serde::{Deserialize, Serialize};
 
 /// Tunable parameters for WebAssembly compilation.
@@ -28,6 +29,10 @@ pub struct Tunables {
     /// will be consumed every time a wasm instruction is executed.
     pub consume_fuel: bool,
 
+    /// The amount of fuel consumed by each operator when `consume_fuel` is
+    /// enabled.
+    pub fuel_costs: FuelCosts,
+
     /// Whether or not we use epoch-based interruption.
     pub epoch_interruption: bool,
 
@@ -52,6 +57,20 @@ pub struct Tunables {
 
     /// Whether or not Wasm functions can be tail-called or not.
     pub tail_callable: bool,
+
+    /// Whether or not Wasm exceptions are enabled, in which case Wasm functions
+    /// use the tail calling convention, which has no callee-saved registers
+    /// for the runtime to restore when it unwinds frames.
+    pub exceptions: bool,
+
+    /// Whether or not compiled code is instrumented to support the
+    /// interactive debugging API (breakpoints, single-stepping and frame
+    /// inspection).
+    pub debug_instrumentation: bool,
+
+    /// The events that compiled code reports to the store's instrumentation
+    /// handler.
+    pub instrumentation: Instrumentation,
 }
 
 impl Default for Tunables {
@@ -103,6 +122,7 @@ impl Default for Tunables {
             generate_native_debuginfo: false,
             parse_wasm_debuginfo: true,
             consume_fuel: false,
+            fuel_costs: FuelCosts::default(),
             epoch_interruption: false,
             static_memory_bound_is_maximum: false,
             guard_before_linear_memory: true,
@@ -110,6 +130,9 @@ impl Default for Tunables {
             debug_adapter_modules: false,
             relaxed_simd_deterministic: false,
             tail_callable: false,
+            exceptions: false,
+            debug_instrumentation: false,
+            instrumentation: Instrumentation::default(),
         }
     }
 }
@@ -200,6 +200,21 @@ pub trait PtrSize {
         self.vmruntime_limits_last_wasm_exit_pc() + self.size()
     }
 
+    /// Return the offset of the `pending_exception` field of `VMRuntimeLimits`.
+    fn vmruntime_limits_pending_exception(&self) -> u8 {
+        self.vmruntime_limits_last_wasm_entry_sp() + self.size()
+    }
+
+    /// Return the offset of the `debug_frame` field of `VMRuntimeLimits`.
+    fn vmruntime_limits_debug_frame(&self) -> u8 {
+        self.vmruntime_limits_pending_exception() + self.size()
+    }
+
+    /// Return the offset of the `debug_break` field of `VMRuntimeLimits`.
+    fn vmruntime_limits_debug_break(&self) -> u8 {
+        self.vmruntime_limits_debug_frame() + self.size()
+    }
+
     // Offsets within `VMMemoryDefinition`
 
     /// The offset of the `base` field.
@@ -228,6 +243,62 @@ pub trait PtrSize {
         self.size()
     }
 
+    // Offsets within `VMDebugFrame`
+
+    /// Return the offset of the `prev` field of `VMDebugFrame`.
+    #[inline]
+    fn vmdebug_frame_prev(&self) -> u8 {
+        0
+    }
+
+    /// Return the offset of the `vmctx` field of `VMDebugFrame`.
+    #[inline]
+    fn vmdebug_frame_vmctx(&self) -> u8 {
+        self.vmdebug_frame_prev() + self.size()
+    }
+
+    /// Return the offset of the `values` field of `VMDebugFrame`.
+    #[inline]
+    fn vmdebug_frame_values(&self) -> u8 {
+        self.vmdebug_frame_vmctx() + self.size()
+    }
+
+    /// Return the offset of the `types` field of `VMDebugFrame`.
+    #[inline]
+    fn vmdebug_frame_types(&self) -> u8 {
+        self.vmdebug_frame_values() + self.size()
+    }
+
+    /// Return the offset of the `func_index` field of `VMDebugFrame`.
+    #[inline]
+    fn vmdebug_frame_func_index(&self) -> u8 {
+        self.vmdebug_frame_types() + self.size()
+    }
+
+    /// Return the offset of the `offset` field of `VMDebugFrame`.
+    #[inline]
+    fn vmdebug_frame_offset(&self) -> u8 {
+        self.vmdebug_frame_func_index() + 4
+    }
+
+    /// Return the offset of the `num_locals` field of `VMDebugFrame`.
+    #[inline]
+    fn vmdebug_frame_num_locals(&self) -> u8 {
+        self.vmdebug_frame_offset() + 4
+    }
+
+    /// Return the offset of the `num_values` field of `VMDebugFrame`.
+    #[inline]
+    fn vmdebug_frame_num_values(&self) -> u8 {
+        self.vmdebug_frame_num_locals() + 4
+    }
+
+    /// Return the size of `VMDebugFrame`.
+    #[inline]
+    fn size_of_vmdebug_frame(&self) -> u8 {
+        self.vmdebug_frame_num_values() + 4
+    }
+
     // Offsets within `VMArrayCallHostFuncContext`.
 
     // Offsets within `VMNativeCallHostFuncContext`.
@@ -956,6 +1027,46 @@ pub const VM_ARRAY_CALL_HOST_FUNC_MAGIC: u32 = u32::from_le_bytes(*b"ACHF");
 /// and double-checked on `VMNativeCallHostFuncContext::from_opaque`.
 pub const VM_NATIVE_CALL_HOST_FUNC_MAGIC: u32 = u32::from_le_bytes(*b"NCHF");
 
+/// The `num_values` of a `VMDebugFrame` whose values weren't written for the
+/// instruction the function is executing, and so aren't known.
+pub const VMDEBUG_FRAME_VALUES_UNAVAILABLE: u32 = u32::MAX;
+
+/// The type tag of a value in a `VMDebugFrame`.
+///
+/// Values are tagged with their type in compiled code, where references other
+/// than `externref` are indistinguishable from integers of the pointer width.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(u8)]
+pub enum VMDebugValueType {
+    /// An `i32`, or a non-`externref` reference on 32-bit hosts.
+    I32 = 0,
+    /// An `i64`, or a non-`externref` reference on 64-bit hosts.
+    I64 = 1,
+    /// An `f32`.
+    F32 = 2,
+    /// An `f64`.
+    F64 = 3,
+    /// A `v128`.
+    V128 = 4,
+    /// An `externref`.
+    ExternRef = 5,
+}
+
+impl VMDebugValueType {
+    /// Returns the type with the tag `tag`, if any.
+    pub fn from_u8(tag: u8) -> Option<VMDebugValueType> {
+        Some(match tag {
+            0 => VMDebugValueType::I32,
+            1 => VMDebugValueType::I64,
+            2 => VMDebugValueType::F32,
+            3 => VMDebugValueType::F64,
+            4 => VMDebugValueType::V128,
+            5 => VMDebugValueType::ExternRef,
+            _ => return None,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::vmoffsets::align;
@@ -17,6 +17,8 @@ cfg_if::cfg_if! {
     }
 }
 
+pub use imp::{switch_to, SwitchContext};
+
 /// Represents an execution stack to use for a fiber.
 #[derive(Debug)]
 pub struct FiberStack(imp::FiberStack);
@@ -64,6 +66,36 @@ impl FiberStack {
         self.0.write_parent(tsp);
     }
 
+    /// Gets the pointer stored with `write_switch_data`.
+    pub unsafe fn switch_data(&self) -> *mut u8 {
+        self.0.switch_data()
+    }
+
+    /// Stores a pointer at the top of a stack that is switched to with
+    /// `stack_switch`, for code running on the stack to find.
+    pub unsafe fn write_switch_data(&self, data: *mut u8) {
+        self.0.write_switch_data(data);
+    }
+
+    /// Initializes `context` such that switching to it with Cranelift's
+    /// `stack_switch` instruction calls `entry(entry_arg0, payload)` on this
+    /// stack, where `payload` is the payload of that switch.
+    ///
+    /// `entry` must not return. It should instead use `switch_to` to leave
+    /// this stack once it is done.
+    ///
+    /// # Safety
+    ///
+    /// This overwrites the top of the stack, so the stack must not be in use.
+    pub unsafe fn init_switch_context(
+        &self,
+        context: &mut SwitchContext,
+        entry: extern "C" fn(*mut u8, u64) -> !,
+        entry_arg0: *mut u8,
+    ) {
+        self.0.init_switch_context(context, entry, entry_arg0);
+    }
+
     /// Returns the range of where this stack resides in memory if the platform
     /// supports it.
     pub fn range(&self) -> Option<Range<usize>> {
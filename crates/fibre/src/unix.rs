@@ -28,6 +28,19 @@
 //! Additionally `resumes` stores state at 0xAff0 to restart execution, and
 //! `suspend`, which has 0xB000 so it can find this, will read that and write
 //! its own resumption information into this slot as well.
+//!
+//! Stacks which are instead switched to with Cranelift's `stack_switch`
+//! instruction (see `FiberStack::init_switch_context`) keep their resumption
+//! state in a `SwitchContext` outside of the stack. They use `0xAff8` to hold
+//! an arbitrary pointer for the embedder, and the initial frame looks like so:
+//!
+//! ```text
+//! 0xAff0 +-----------------------+
+//!        | entry_arg0            |
+//! 0xAfe8 +-----------------------+
+//!        | entry                 |   <- initial sp, 16-byte aligned
+//! 0xAfe0 +-----------------------+
+//! ```
 
 #![allow(unused_macros)]
 
@@ -86,12 +99,17 @@ impl FiberStack {
 
     pub fn malloc(size: usize) -> io::Result<Self> {
         unsafe {
-            let layout = Layout::array::<u8>(size).unwrap();
-            let base = alloc(layout);
+            let base = alloc(Self::malloc_layout(size));
             FiberStack::from_raw_parts(base, size)
         }
     }
 
+    // The top of the stack must be 16-byte aligned for the initial frames set
+    // up by `wasmtime_fibre_init` and `init_switch_context`.
+    fn malloc_layout(size: usize) -> Layout {
+        Layout::from_size_align(size, 16).unwrap()
+    }
+
     pub unsafe fn from_raw_parts(base: *mut u8, len: usize) -> io::Result<Self> {
         Ok(Self {
             top: base.add(len),
@@ -116,6 +134,48 @@ impl FiberStack {
         let base = unsafe { self.top.sub(self.len) as usize };
         Some(base..base + self.len)
     }
+
+    pub unsafe fn switch_data(&self) -> *mut u8 {
+        self.top.cast::<*mut u8>().offset(-1).read()
+    }
+
+    pub unsafe fn write_switch_data(&self, data: *mut u8) {
+        self.top.cast::<*mut u8>().offset(-1).write(data);
+    }
+
+    pub unsafe fn init_switch_context(
+        &self,
+        context: &mut SwitchContext,
+        entry: extern "C" fn(*mut u8, u64) -> !,
+        entry_arg0: *mut u8,
+    ) {
+        // In the diagram above this is writing to 0xAfe0 and 0xAfe8.
+        let sp = self.top.cast::<usize>().offset(-4);
+        sp.write(entry as usize);
+        sp.add(1).write(entry_arg0 as usize);
+
+        context.sp = sp as usize;
+        // There is no frame to return to on a fresh stack, so terminate the
+        // frame pointer chain here.
+        context.fp = 0;
+        context.pc = wasmtime_fibre_switch_start as unsafe extern "C" fn() as usize;
+    }
+}
+
+/// The state of a stack which was switched away from with Cranelift's
+/// `stack_switch` instruction, in the layout that instruction stores and loads.
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct SwitchContext {
+    pub sp: usize,
+    pub fp: usize,
+    pub pc: usize,
+}
+
+/// Switches to `context` the same way `stack_switch` does, making `payload`
+/// the result of the `stack_switch` that saved `context`.
+pub unsafe fn switch_to(context: &SwitchContext, payload: u64) -> ! {
+    wasmtime_fibre_switch_to(context, payload)
 }
 
 impl Drop for FiberStack {
@@ -125,8 +185,7 @@ impl Drop for FiberStack {
                 let ret = rustix::mm::munmap(self.top.sub(self.len) as _, self.len);
                 debug_assert!(ret.is_ok());
             } else {
-                let layout = Layout::array::<u8>(self.len).unwrap();
-                dealloc(self.top.sub(self.len), layout);
+                dealloc(self.top.sub(self.len), Self::malloc_layout(self.len));
             }
         }
     }
@@ -145,6 +204,8 @@ extern "C" {
     fn wasmtime_fibre_switch(top_of_stack: *mut u8);
     #[allow(dead_code)] // only used in inline assembly for some platforms
     fn wasmtime_fibre_start();
+    fn wasmtime_fibre_switch_to(context: *const SwitchContext, payload: u64) -> !;
+    fn wasmtime_fibre_switch_start();
 }
 
 extern "C" fn fiber_start<F, A, B, C>(arg0: *mut u8, top_of_stack: *mut u8)
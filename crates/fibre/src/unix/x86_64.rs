@@ -158,3 +158,46 @@ asm_func!(
         .cfi_endproc
     ",
 );
+
+// fn(context(rdi): *const SwitchContext, payload(rsi): u64) -> !
+//
+// This is the same switch as the `stack_switch` instruction, minus saving the
+// state of the stack we switch away from, since that stack is never resumed.
+asm_func!(
+    "wasmtime_fibre_switch_to",
+    "
+        mov rsp, 0x00[rdi]
+        mov rbp, 0x08[rdi]
+        mov rax, 0x10[rdi]
+        mov rdi, rsi
+        jmp rax
+    ",
+);
+
+// The first code to run on a stack set up by `init_switch_context`, reached by
+// the first `stack_switch` to it. The payload of that switch is in rdi, and
+// the stack pointer points at the entry point and its first argument (see the
+// diagram in unix.rs).
+//
+// Unlike `wasmtime_fibre_start` this doesn't describe how to unwind into the
+// stack which switched to it, as the stack that eventually resumes a stack
+// switched away from with `stack_switch` may not be the one that started it.
+// We instead mark the return address as undefined to end unwinding here.
+asm_func!(
+    "wasmtime_fibre_switch_start",
+    "
+        .cfi_startproc simple
+        .cfi_def_cfa rsp, 0
+        .cfi_undefined rip
+
+        // Call `entry(entry_arg0, payload)`. The stack pointer is still
+        // 16-byte aligned here, as required for the `call`. The entry point
+        // never returns, so the trailing `ud2` is just for safety.
+        mov rsi, rdi
+        mov rdi, 0x08[rsp]
+        mov rax, 0x00[rsp]
+        call rax
+        ud2
+        .cfi_endproc
+    ",
+);
@@ -6,10 +6,7 @@ use crate::{Instance, TrapReason};
 use std::cmp;
 use std::mem;
 use std::ptr;
-use wasmtime_fibre::{Fiber, FiberStack, Suspend};
-
-type ContinuationFiber = Fiber<'static, (), u32, ()>;
-type Yield = Suspend<(), u32, ()>;
+use wasmtime_fibre::{FiberStack, SwitchContext};
 
 #[allow(dead_code)]
 const ENABLE_DEBUG_PRINTING: bool = false;
@@ -72,10 +69,46 @@ enum State {
     Returned,
 }
 
+/// The contexts which `resume` and `suspend` switch between with Cranelift's
+/// `stack_switch` instruction.
+#[repr(C)]
+#[derive(Default)]
+struct StackSwitchContexts {
+    /// The stack which last resumed the continuation.
+    parent: SwitchContext,
+    /// The continuation's own stack.
+    cont: SwitchContext,
+}
+
+/// The payload with which a continuation switches back to its parent when
+/// its function returns. Suspending passes the tag index with the high bits of
+/// the payload set instead, so the two can't be confused.
+const RETURN_PAYLOAD: u64 = 0;
+
+/// The bits of the payload which signal that a continuation suspended.
+const SUSPEND_SIGNAL_MASK: u32 = 0xf000_0000;
+
 /// TODO
 #[repr(C)]
 pub struct ContinuationObject {
-    fiber: *mut ContinuationFiber,
+    stack: FiberStack,
+
+    contexts: StackSwitchContexts,
+
+    /// The function passed to `cont.new`, and the context of the instance
+    /// which created the continuation.
+    func: *mut VMFuncRef,
+    caller_ctx: *mut VMOpaqueContext,
+
+    /// The debug frame of the stack which resumed the continuation, put back
+    /// when the continuation switches back to it.
+    parent_debug_frame: usize,
+
+    /// The stack pointer at which Wasm was entered on the continuation's stack,
+    /// while it is suspended, and that of the stack which resumed it, while it
+    /// runs. Exceptions are unwound up to the entry of the running stack.
+    entry_sp: usize,
+    parent_entry_sp: usize,
 
     /// Used to store
     /// 1. The arguments to the function passed to cont.new
@@ -227,7 +260,6 @@ pub fn new_cont_ref(contobj: *mut ContinuationObject) -> *mut ContinuationRefere
 #[inline(always)]
 pub fn drop_cont_obj(contobj: *mut ContinuationObject) {
     let contobj: Box<ContinuationObject> = unsafe { Box::from_raw(contobj) };
-    let _: Box<ContinuationFiber> = unsafe { Box::from_raw(contobj.fiber) };
     unsafe {
         let _: Vec<u128> = Vec::from_raw_parts(
             contobj.args.data,
@@ -300,6 +332,28 @@ pub fn get_payload_buffer(instance: &mut Instance, element_count: usize) -> *mut
     return vec_data;
 }
 
+/// The function running at the bottom of every continuation's stack, reached by
+/// the first `resume` of the continuation.
+extern "C" fn cont_entry(contobj: *mut u8, _payload: u64) -> ! {
+    let contobj = contobj.cast::<ContinuationObject>();
+    unsafe {
+        let func = (*contobj).func;
+        let f = mem::transmute::<
+            VMArrayCallFunction,
+            unsafe extern "C" fn(*mut VMOpaqueContext, *mut VMOpaqueContext, *mut ValRaw, usize),
+        >((*func).array_call);
+        // The results of the function are written to the argument buffer by
+        // virtue of using the array calling trampoline to execute it.
+        f(
+            (*func).vmctx,
+            (*contobj).caller_ctx,
+            (*contobj).args.data as *mut ValRaw,
+            (*contobj).args.capacity,
+        );
+        wasmtime_fibre::switch_to(&(*contobj).contexts.parent, RETURN_PAYLOAD)
+    }
+}
+
 /// TODO
 #[inline(always)]
 pub fn cont_new(
@@ -308,33 +362,17 @@ pub fn cont_new(
     param_count: usize,
     result_count: usize,
 ) -> *mut ContinuationObject {
-    let func = func as *mut VMFuncRef;
-    let callee_ctx = unsafe { (*func).vmctx };
-    let caller_ctx = VMOpaqueContext::from_vmcontext(instance.vmctx());
-    let f = unsafe {
-        mem::transmute::<
-            VMArrayCallFunction,
-            unsafe extern "C" fn(*mut VMOpaqueContext, *mut VMOpaqueContext, *mut ValRaw, usize),
-        >((*func).array_call)
-    };
     let capacity = cmp::max(param_count, result_count);
 
-    let payload = Payloads::new(capacity);
-
-    let args_ptr = payload.data;
-    let fiber = Box::new(
-        Fiber::new(
-            FiberStack::malloc(4096).unwrap(),
-            move |_first_val: (), _suspend: &Yield| unsafe {
-                f(callee_ctx, caller_ctx, args_ptr as *mut ValRaw, capacity)
-            },
-        )
-        .unwrap(),
-    );
-
     let contobj = Box::new(ContinuationObject {
-        fiber: Box::into_raw(fiber),
-        args: payload,
+        stack: FiberStack::malloc(4096).unwrap(),
+        contexts: StackSwitchContexts::default(),
+        func: func as *mut VMFuncRef,
+        caller_ctx: VMOpaqueContext::from_vmcontext(instance.vmctx()),
+        parent_debug_frame: 0,
+        entry_sp: 0,
+        parent_entry_sp: 0,
+        args: Payloads::new(capacity),
         tag_return_values: None,
         state: State::Allocated,
     });
@@ -342,19 +380,28 @@ pub fn cont_new(
     // TODO(dhil): we need memory clean up of
     // continuation reference objects.
     let pointer = Box::into_raw(contobj);
+    unsafe {
+        let stack = &(*pointer).stack;
+        stack.init_switch_context(&mut (*pointer).contexts.cont, cont_entry, pointer.cast());
+        // This is how `suspend_start` finds the continuation running on this
+        // stack.
+        stack.write_switch_data(pointer.cast());
+    }
     debug_println!("Created contobj @ {:p}", pointer);
     return pointer;
 }
 
-/// TODO
+/// Prepares switching to the stack of `contobj`, and returns the pointer to
+/// the contexts to switch between: code resuming the continuation saves its
+/// own context at the returned address and switches to the context 24 bytes
+/// past it, then passes the payload it receives to `resume_finish`.
 #[inline(always)]
-pub fn resume(
+pub fn resume_start(
     instance: &mut Instance,
     contobj: *mut ContinuationObject,
-) -> Result<u32, TrapReason> {
+) -> Result<*mut u8, TrapReason> {
     assert!(unsafe { (*contobj).state == State::Allocated || (*contobj).state == State::Invoked });
-    let fiber = unsafe { (*contobj).fiber };
-    let fiber_stack = unsafe { &fiber.as_ref().unwrap().stack() };
+    let fiber_stack = unsafe { &(*contobj).stack };
     let tsp = TopOfStackPointer::as_raw(instance.tsp());
     unsafe { fiber_stack.write_parent(tsp) };
     instance.set_tsp(TopOfStackPointer::from_raw(fiber_stack.top().unwrap()));
@@ -377,47 +424,73 @@ pub fn resume(
             instance.get_typed_continuations_store_mut() as *mut *mut ContinuationObject;
         cont_store_ptr.write(contobj)
     };
-    match unsafe { fiber.as_mut().unwrap().resume(()) } {
-        Ok(()) => {
-            // The result of the continuation was written to the first
-            // entry of the payload store by virtue of using the array
-            // calling trampoline to execute it.
-
-            // Restore tsp pointer in instance
-            let _tsp = TopOfStackPointer::as_raw(instance.tsp());
-            let parent = unsafe { (*(*contobj).fiber).stack().parent() };
-            instance.set_tsp(TopOfStackPointer::from_raw(parent));
-
-            debug_println!(
-                "Continuation @ {:p} returned normally, setting tsp from {:p} to {:p}",
-                contobj,
-                _tsp,
-                parent
-            );
-
-            unsafe { (*contobj).state = State::Returned };
-            Ok(0) // zero value = return normally.
-        }
-        Err(tag) => {
-            debug_println!("Continuation {:p} suspended", contobj);
+    // The functions running on the continuation have their own chain of debug
+    // frames, since the frame resuming it may be gone by the next resume.
+    let limits = unsafe { (*instance.store()).vmruntime_limits() };
+    unsafe { (*contobj).parent_debug_frame = (*limits).debug_frame.get().replace(0) };
+    unsafe {
+        (*contobj).parent_entry_sp = (*limits)
+            .last_wasm_entry_sp
+            .get()
+            .replace((*contobj).entry_sp)
+    };
+    Ok(unsafe { ptr::addr_of_mut!((*contobj).contexts).cast() })
+}
 
-            // We set the high bit to signal a return via suspend. We
-            // encode the tag into the remainder of the integer.
-            let signal_mask = 0xf000_0000;
-            debug_assert_eq!(tag & signal_mask, 0);
-            unsafe {
-                let cont_store_ptr =
-                    instance.get_typed_continuations_store_mut() as *mut *mut ContinuationObject;
-                cont_store_ptr.write(contobj)
-            };
-            Ok(tag | signal_mask)
-        }
+/// Finishes a `resume` of `contobj` once its stack switched back with
+/// `payload`. Returns 0 if the continuation returned, or the index of the tag
+/// it suspended with and the high bits set.
+#[inline(always)]
+pub fn resume_finish(
+    instance: &mut Instance,
+    contobj: *mut ContinuationObject,
+    payload: u64,
+) -> Result<u32, TrapReason> {
+    let limits = unsafe { (*instance.store()).vmruntime_limits() };
+    unsafe { *(*limits).debug_frame.get() = (*contobj).parent_debug_frame };
+    unsafe {
+        (*contobj).entry_sp = (*limits)
+            .last_wasm_entry_sp
+            .get()
+            .replace((*contobj).parent_entry_sp)
+    };
+    if payload == RETURN_PAYLOAD {
+        // Restore tsp pointer in instance
+        let _tsp = TopOfStackPointer::as_raw(instance.tsp());
+        let parent = unsafe { (*contobj).stack.parent() };
+        instance.set_tsp(TopOfStackPointer::from_raw(parent));
+
+        debug_println!(
+            "Continuation @ {:p} returned normally, setting tsp from {:p} to {:p}",
+            contobj,
+            _tsp,
+            parent
+        );
+
+        unsafe { (*contobj).state = State::Returned };
+        Ok(0) // zero value = return normally.
+    } else {
+        debug_println!("Continuation {:p} suspended", contobj);
+
+        let signal = payload as u32;
+        debug_assert_eq!(signal & SUSPEND_SIGNAL_MASK, SUSPEND_SIGNAL_MASK);
+        unsafe {
+            let cont_store_ptr =
+                instance.get_typed_continuations_store_mut() as *mut *mut ContinuationObject;
+            cont_store_ptr.write(contobj)
+        };
+        Ok(signal)
     }
 }
 
-/// TODO
+/// Prepares switching from the running continuation back to its parent, and
+/// returns the pointer to the contexts to switch between: code suspending
+/// saves its own context 24 bytes past the returned address and switches to
+/// the context at it, passing the tag index with the high bits set as the
+/// payload.
 #[inline(always)]
-pub fn suspend(instance: &mut Instance, tag_index: u32) {
+pub fn suspend_start(instance: &mut Instance, tag_index: u32) -> *mut u8 {
+    debug_assert_eq!(tag_index & SUSPEND_SIGNAL_MASK, 0);
     let stack_ptr = TopOfStackPointer::as_raw(instance.tsp());
     let parent = unsafe { stack_ptr.cast::<*mut u8>().offset(-2).read() };
     debug_println!(
@@ -426,6 +499,36 @@ pub fn suspend(instance: &mut Instance, tag_index: u32) {
         parent
     );
     instance.set_tsp(TopOfStackPointer::from_raw(parent));
-    let suspend = wasmtime_fibre::unix::Suspend::from_top_ptr(stack_ptr);
-    suspend.switch::<(), u32, ()>(wasmtime_fibre::RunResult::Yield(tag_index))
+    let contobj = unsafe {
+        stack_ptr
+            .cast::<*mut ContinuationObject>()
+            .offset(-1)
+            .read()
+    };
+    unsafe { ptr::addr_of_mut!((*contobj).contexts).cast() }
+}
+
+/// Switches from the running continuation of `instance` back to its parent,
+/// as if the continuation returned, if the Wasm frames entered at `entry_sp`
+/// are the bottommost ones on the continuation's stack. Their exception is
+/// left pending for the parent to handle.
+///
+/// # Safety
+///
+/// An exception must be pending, and no Rust destructors can be on the stack.
+/// They will be skipped and not executed.
+pub(crate) unsafe fn unwind_to_parent(instance: &mut Instance, entry_sp: usize) {
+    let tsp = TopOfStackPointer::as_raw(instance.tsp());
+    if tsp.is_null() {
+        return;
+    }
+    let contobj = tsp.cast::<*mut ContinuationObject>().offset(-1).read();
+    let range = (*contobj).stack.range().unwrap();
+    // A call from the host into Wasm on the continuation's stack is an
+    // activation of its own, which the host returns from.
+    if !range.contains(&entry_sp) || crate::traphandlers::caught_within(&range) {
+        return;
+    }
+    debug_println!("Unwinding contobj @ {:p} to its parent", contobj);
+    wasmtime_fibre::switch_to(&(*contobj).contexts.parent, RETURN_PAYLOAD)
 }
@@ -240,6 +240,55 @@ impl MemoryImage {
     }
 }
 
+/// A snapshot of the contents of a linear memory, created with
+/// `Memory::snapshot`.
+///
+/// The contents are always copied in full. When the platform supports it the
+/// copy is made into a `MemoryImage`, which restoring the snapshot maps
+/// copy-on-write into linear memory rather than copying it back in, so that
+/// restoring the same snapshot many times shares the snapshot's pages.
+/// Otherwise the copy is a plain buffer of the bytes.
+#[derive(Debug)]
+pub struct MemorySnapshot {
+    /// The byte size of the linear memory when the snapshot was taken.
+    size: usize,
+    contents: SnapshotContents,
+}
+
+#[derive(Debug)]
+enum SnapshotContents {
+    Image(Arc<MemoryImage>),
+    Bytes(Vec<u8>),
+}
+
+impl MemorySnapshot {
+    /// Captures the contents of the linear memory `data`.
+    ///
+    /// This copies all of `data`, whether or not it has been written to since
+    /// the memory was created, into either a memfd or a plain buffer.
+    pub(crate) fn new(data: &[u8]) -> Result<MemorySnapshot> {
+        let page_size = crate::page_size();
+        let image = if data.len() % page_size == 0 {
+            MemoryImage::new(page_size as u32, 0, data, None)?
+        } else {
+            None
+        };
+        let contents = match image {
+            Some(image) => SnapshotContents::Image(Arc::new(image)),
+            None => SnapshotContents::Bytes(data.to_vec()),
+        };
+        Ok(MemorySnapshot {
+            size: data.len(),
+            contents,
+        })
+    }
+
+    /// Returns the byte size of the linear memory when the snapshot was taken.
+    pub fn byte_size(&self) -> usize {
+        self.size
+    }
+}
+
 #[cfg(all(target_os = "linux", not(miri)))]
 fn create_memfd() -> Result<Option<memfd::Memfd>> {
     use std::io::ErrorKind;
@@ -562,6 +611,55 @@ impl MemoryImageSlot {
         Ok(())
     }
 
+    /// Replaces the contents of this slot with those of `snapshot`, resizing
+    /// the accessible heap to the size of the snapshot.
+    ///
+    /// Bytes beyond the size of the snapshot are zeroed and made
+    /// inaccessible, so that later growth starts from zeroed memory as
+    /// WebAssembly requires.
+    pub(crate) fn restore(&mut self, snapshot: &MemorySnapshot) -> Result<()> {
+        assert!(snapshot.size <= self.static_size);
+
+        match &snapshot.contents {
+            SnapshotContents::Image(image) => {
+                // Throw away the current contents of the whole slot, including
+                // any previous image, and then map the snapshot's image over
+                // fresh zeroed memory.
+                self.reset_with_anon_memory()?;
+                self.set_protection(0..snapshot.size, true)?;
+                self.accessible = snapshot.size;
+                if image.len > 0 {
+                    unsafe {
+                        image.map_at(self.base.as_ptr())?;
+                    }
+                }
+                self.image = Some(image.clone());
+            }
+            SnapshotContents::Bytes(bytes) => {
+                self.remove_image()?;
+                if snapshot.size < self.accessible {
+                    unsafe {
+                        std::ptr::write_bytes(
+                            self.base.as_ptr().add(snapshot.size),
+                            0u8,
+                            self.accessible - snapshot.size,
+                        );
+                    }
+                    self.set_protection(snapshot.size..self.accessible, false)?;
+                    self.accessible = snapshot.size;
+                } else {
+                    self.set_heap_limit(snapshot.size)?;
+                }
+                unsafe {
+                    std::ptr::copy_nonoverlapping(bytes.as_ptr(), self.base.as_ptr(), bytes.len());
+                }
+            }
+        }
+
+        self.dirty = true;
+        Ok(())
+    }
+
     pub(crate) fn remove_image(&mut self) -> Result<()> {
         if let Some(image) = &self.image {
             unsafe {
@@ -0,0 +1,272 @@
+//! Runtime support for the Wasm exception handling proposal.
+//!
+//! A thrown exception is stored in the `pending_exception` member of the
+//! store's `VMRuntimeLimits`, and the frames between the thrower and the
+//! handler are unwound by walking them with the same frame-pointer walk as
+//! backtraces. Calls within the body of a `try` block are the function's
+//! exception landing sites: [`unwind`] looks up the youngest frame returning to
+//! one, pops the frames younger than it, and resumes at the return address of
+//! the call, where compiled code finds the pending exception and branches to
+//! the handler of the `try` block. Calls outside of `try` blocks need no code
+//! at all. Once an exception reaches the host, it is taken out of the store and
+//! turned into an error.
+//!
+//! Compiled code which can be unwound uses the tail calling convention, which
+//! has no callee-saved registers: the code at a landing site expects every
+//! register but the stack and frame pointers to be clobbered by the call, so
+//! there is nothing for the unwinder to restore.
+//!
+//! Exceptions are reference counted through `VMExternRef`, which lets caught
+//! exceptions be held in Wasm frames and traced by the GC like any other
+//! `externref`. They are matched against `catch` clauses by the canonical
+//! identity of their tag, a [`VMTagId`].
+
+use crate::traphandlers::Backtrace;
+use crate::vmcontext::{VMContext, VMDebugFrame, VMRuntimeLimits, ValRaw};
+use crate::{Instance, ModuleRuntimeInfo, VMExternRef};
+use cfg_if::cfg_if;
+use std::ffi::c_void;
+use std::ops::ControlFlow;
+use std::sync::Arc;
+use wasmtime_environ::{TagIndex, WasmHeapType, WasmType};
+
+cfg_if! {
+    if #[cfg(target_arch = "x86_64")] {
+        mod x86_64;
+        use x86_64 as arch;
+    } else if #[cfg(target_arch = "aarch64")] {
+        mod aarch64;
+        use aarch64 as arch;
+    } else if #[cfg(target_arch = "s390x")] {
+        mod s390x;
+        use s390x as arch;
+    } else if #[cfg(target_arch = "riscv64")] {
+        mod riscv64;
+        use riscv64 as arch;
+    } else {
+        compile_error!("unsupported architecture");
+    }
+}
+
+/// The canonical identity of a tag, which exceptions are matched against by
+/// `catch` clauses.
+///
+/// A tag is identified by the instance defining it, rather than by the
+/// instance referring to it, so that identities stay canonical once tags can
+/// be imported. Instances are identified by a serial number rather than by
+/// the address of their `VMContext`, which is reused once they are
+/// deallocated, so that an exception outliving the instance defining its tag
+/// never matches a tag of another instance.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct VMTagId {
+    pub(crate) instance: u64,
+    pub(crate) index: TagIndex,
+}
+
+/// A Wasm exception: an instance of a tag, along with its payload.
+pub struct VMException {
+    /// The identity of the exception's tag.
+    tag_id: VMTagId,
+    module: Arc<dyn ModuleRuntimeInfo>,
+    tag: TagIndex,
+    payload: Vec<ValRaw>,
+    /// The non-null `externref`s of the payload, which the exception keeps
+    /// alive.
+    externrefs: Vec<VMExternRef>,
+}
+
+impl VMException {
+    /// Create an exception of the tag `tag` defined by `instance`, wrapped in
+    /// an `externref`.
+    ///
+    /// # Safety
+    ///
+    /// The values of `payload` must have the types of the tag's parameters, and
+    /// any `externref`s among them must be alive.
+    pub unsafe fn new(instance: &Instance, tag: TagIndex, payload: Vec<ValRaw>) -> VMExternRef {
+        let module = instance.runtime_info().clone();
+        let params = module.tag_params(tag);
+        assert_eq!(params.len(), payload.len());
+        let externrefs = params
+            .iter()
+            .zip(&payload)
+            .filter(|(ty, _)| is_externref(ty))
+            .map(|(_, val)| val.get_externref())
+            .filter(|raw| !raw.is_null())
+            .map(|raw| VMExternRef::clone_from_raw(raw.cast()))
+            .collect();
+        VMExternRef::new(VMException {
+            tag_id: instance.tag_id(tag),
+            module,
+            tag,
+            payload,
+            externrefs,
+        })
+    }
+
+    /// The index of the exception's tag within the module defining it.
+    pub fn tag(&self) -> TagIndex {
+        self.tag
+    }
+
+    /// Is the exception's tag the tag identified by `tag_id`?
+    pub fn has_tag(&self, tag_id: VMTagId) -> bool {
+        self.tag_id == tag_id
+    }
+
+    /// The types of the values in the exception's payload.
+    pub fn param_types(&self) -> &[WasmType] {
+        self.module.tag_params(self.tag)
+    }
+
+    /// The values of the exception's payload, which are only valid for as
+    /// long as the exception is.
+    pub fn payload(&self) -> &[ValRaw] {
+        &self.payload
+    }
+
+    /// The `externref`s of the payload.
+    pub(crate) fn externrefs(&self) -> &[VMExternRef] {
+        &self.externrefs
+    }
+}
+
+fn is_externref(ty: &WasmType) -> bool {
+    matches!(ty, WasmType::Ref(r) if r.heap_type == WasmHeapType::Extern)
+}
+
+/// Get the `VMException` wrapped by `exception`.
+///
+/// # Panics
+///
+/// Panics if `exception` is not a `VMException`, which compiled code never
+/// passes to the exception libcalls.
+pub fn downcast(exception: &VMExternRef) -> &VMException {
+    exception
+        .downcast_ref::<VMException>()
+        .expect("externref is not an exception")
+}
+
+/// Make `exception` the pending exception of the store owning `limits`.
+///
+/// # Safety
+///
+/// `limits` must be the valid `VMRuntimeLimits` of a store, and `exception`
+/// must wrap a `VMException`.
+pub unsafe fn set_pending(limits: *const VMRuntimeLimits, exception: VMExternRef) {
+    debug_assert!(exception.is::<VMException>());
+    let previous = (*limits)
+        .pending_exception
+        .get()
+        .replace(exception.into_raw() as usize);
+    if previous != 0 {
+        drop(VMExternRef::from_raw(previous as *mut u8));
+    }
+}
+
+/// Take the pending exception of the store owning `limits`, if there is one.
+///
+/// # Safety
+///
+/// `limits` must be the valid `VMRuntimeLimits` of a store.
+pub unsafe fn take_pending(limits: *const VMRuntimeLimits) -> Option<VMExternRef> {
+    match (*limits).pending_exception.get().replace(0) {
+        0 => None,
+        raw => Some(VMExternRef::from_raw(raw as *mut u8)),
+    }
+}
+
+/// Unwind the Wasm frames which most recently exited to the host, through the
+/// instance of `vmctx`, to the handler of the pending exception.
+///
+/// Execution resumes at the exception landing site of the youngest frame with
+/// one. Without such a frame, the exception is passed on to the stack which
+/// resumed the running continuation if the frames run on it, or to the host
+/// which called into them otherwise.
+///
+/// # Safety
+///
+/// `vmctx` must be the `VMContext` of the Wasm function which most recently
+/// exited to the host, and an exception must be pending. Additionally no Rust
+/// destructors can be on the stack. They will be skipped and not executed.
+pub unsafe fn unwind(vmctx: *mut VMContext) -> ! {
+    Instance::from_vmctx(vmctx, |instance| unwind_to_handler(instance));
+    crate::traphandlers::raise_exception()
+}
+
+/// Same as [`unwind`], for the instance of the Wasm function which called a
+/// libcall.
+pub(crate) unsafe fn unwind_instance(instance: &mut Instance) -> ! {
+    unwind_to_handler(instance);
+    crate::traphandlers::raise_exception()
+}
+
+/// Resume at the handler of the pending exception, unless it is in the host.
+unsafe fn unwind_to_handler(instance: &mut Instance) {
+    let limits = *instance.runtime_limits();
+    debug_assert_ne!(*(*limits).pending_exception.get(), 0);
+    // A host function called directly by the host has no Wasm caller.
+    if !crate::traphandlers::entered_wasm(limits) {
+        return;
+    }
+    let (_, lookup) = (*instance.store()).externref_activations_table();
+    let mut landing = None;
+    let _ = Backtrace::trace_innermost_activation(limits, |frame| {
+        let pc = frame.pc();
+        match lookup
+            .lookup(pc)
+            .and_then(|info| info.lookup_exception_landing(pc))
+        {
+            Some(l) => {
+                landing = Some((pc, frame.fp() - l.sp_to_fp as usize, frame.fp()));
+                ControlFlow::Break(())
+            }
+            None => ControlFlow::Continue(()),
+        }
+    });
+    if let Some((pc, sp, fp)) = landing {
+        // The debug frames of the unwound functions are in their frames.
+        let debug_frame = (*limits).debug_frame.get();
+        while *debug_frame != 0 && *debug_frame < sp {
+            *debug_frame = (*(*debug_frame as *const VMDebugFrame)).prev as usize;
+        }
+        arch::resume(pc, sp, fp);
+    }
+    crate::continuation::unwind_to_parent(instance, *(*limits).last_wasm_entry_sp.get());
+}
+
+/// Read a value of type `ty` from a slot of a payload buffer, as written by
+/// compiled code.
+pub(crate) unsafe fn read_payload_slot(slot: *const u128, ty: &WasmType) -> ValRaw {
+    match ty {
+        WasmType::I32 => ValRaw::i32(*slot.cast::<i32>()),
+        WasmType::I64 => ValRaw::i64(*slot.cast::<i64>()),
+        WasmType::F32 => ValRaw::f32(*slot.cast::<u32>()),
+        WasmType::F64 => ValRaw::f64(*slot.cast::<u64>()),
+        WasmType::V128 => ValRaw::v128(*slot),
+        WasmType::Ref(r) => match r.heap_type {
+            WasmHeapType::Extern => ValRaw::externref(*slot.cast::<*mut c_void>()),
+            WasmHeapType::Func | WasmHeapType::TypedFunc(_) => {
+                ValRaw::funcref(*slot.cast::<*mut c_void>())
+            }
+        },
+    }
+}
+
+/// Write a value of type `ty` to a slot of a payload buffer, to be read by
+/// compiled code.
+pub(crate) unsafe fn write_payload_slot(slot: *mut u128, ty: &WasmType, val: ValRaw) {
+    match ty {
+        WasmType::I32 => *slot.cast::<i32>() = val.get_i32(),
+        WasmType::I64 => *slot.cast::<i64>() = val.get_i64(),
+        WasmType::F32 => *slot.cast::<u32>() = val.get_f32(),
+        WasmType::F64 => *slot.cast::<u64>() = val.get_f64(),
+        WasmType::V128 => *slot = val.get_v128(),
+        WasmType::Ref(r) => match r.heap_type {
+            WasmHeapType::Extern => *slot.cast::<*mut c_void>() = val.get_externref(),
+            WasmHeapType::Func | WasmHeapType::TypedFunc(_) => {
+                *slot.cast::<*mut c_void>() = val.get_funcref()
+            }
+        },
+    }
+}
@@ -0,0 +1,15 @@
+/// Resume execution at `pc` with the stack pointer `sp` and the frame pointer
+/// `fp`, discarding every frame younger than the one they belong to.
+pub unsafe fn resume(pc: usize, sp: usize, fp: usize) -> ! {
+    // `pc` is a return address, so it is branched to with `ret`: branch target
+    // identification doesn't require a landing pad there, unlike for `br`.
+    std::arch::asm!(
+        "mov sp, {sp}",
+        "mov x29, {fp}",
+        "ret {pc}",
+        pc = in(reg) pc,
+        sp = in(reg) sp,
+        fp = in(reg) fp,
+        options(noreturn),
+    )
+}
@@ -0,0 +1,13 @@
+/// Resume execution at `pc` with the stack pointer `sp` and the frame pointer
+/// `fp`, discarding every frame younger than the one they belong to.
+pub unsafe fn resume(pc: usize, sp: usize, fp: usize) -> ! {
+    std::arch::asm!(
+        "mv sp, {sp}",
+        "mv s0, {fp}",
+        "jr {pc}",
+        pc = in(reg) pc,
+        sp = in(reg) sp,
+        fp = in(reg) fp,
+        options(noreturn),
+    )
+}
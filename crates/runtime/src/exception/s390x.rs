@@ -0,0 +1,13 @@
+// Currently `asm!` isn't stable on s390x, so the routine is defined in
+// `trampolines/s390x.S`.
+extern "C" {
+    #[wasmtime_versioned_export_macros::versioned_link]
+    fn wasmtime_resume_wasm(pc: usize, sp: usize) -> !;
+}
+
+/// Resume execution at `pc` with the stack pointer `sp`, discarding every frame
+/// younger than the one it belongs to. The "frame pointer" `fp` is the
+/// backchain of that frame, which is still stored at `sp`.
+pub unsafe fn resume(pc: usize, sp: usize, _fp: usize) -> ! {
+    wasmtime_resume_wasm(pc, sp)
+}
@@ -110,7 +110,7 @@ use std::mem;
 use std::ops::Deref;
 use std::ptr::{self, NonNull};
 use std::sync::atomic::{self, AtomicUsize, Ordering};
-use wasmtime_environ::StackMap;
+use wasmtime_environ::{ExceptionLanding, StackMap};
 
 /// An external reference to some opaque data.
 ///
@@ -803,6 +803,10 @@ pub trait ModuleInfoLookup {
 pub trait ModuleInfo {
     /// Lookup the stack map at a program counter value.
     fn lookup_stack_map(&self, pc: usize) -> Option<&StackMap>;
+
+    /// Lookup the exception landing whose return address is a program counter
+    /// value.
+    fn lookup_exception_landing(&self, pc: usize) -> Option<&ExceptionLanding>;
 }
 
 #[derive(Debug, Default)]
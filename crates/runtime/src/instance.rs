@@ -2,6 +2,7 @@
 //! wasm module (except its callstack and register state). An
 //! `InstanceHandle` is a reference-counting handle for an `Instance`.
 
+use crate::exception::VMTagId;
 use crate::export::Export;
 use crate::externref::VMExternRefActivationsTable;
 use crate::memory::{Memory, RuntimeMemoryCreator};
@@ -12,8 +13,8 @@ use crate::vmcontext::{
     VMTableDefinition, VMTableImport,
 };
 use crate::{
-    ExportFunction, ExportGlobal, ExportMemory, ExportTable, Imports, ModuleRuntimeInfo,
-    SendSyncPtr, Store, VMFunctionBody, VMSharedSignatureIndex, WasmFault,
+    CompiledModuleId, ExportFunction, ExportGlobal, ExportMemory, ExportTable, Imports,
+    ModuleRuntimeInfo, SendSyncPtr, Store, VMFunctionBody, VMSharedSignatureIndex, WasmFault,
 };
 use anyhow::Error;
 use anyhow::Result;
@@ -23,21 +24,24 @@ use std::any::Any;
 use std::convert::TryFrom;
 use std::ops::Range;
 use std::ptr::NonNull;
-use std::sync::atomic::AtomicU64;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::{mem, ptr};
 use wasmtime_environ::{
     packed_option::ReservedValue, DataIndex, DefinedGlobalIndex, DefinedMemoryIndex,
     DefinedTableIndex, ElemIndex, EntityIndex, EntityRef, EntitySet, FuncIndex, GlobalIndex,
     GlobalInit, HostPtr, MemoryIndex, MemoryPlan, Module, PrimaryMap, SignatureIndex, TableIndex,
-    TableInitialValue, Trap, VMOffsets, WasmHeapType, WasmRefType, WasmType, VMCONTEXT_MAGIC,
+    TableInitialValue, TagIndex, Trap, VMOffsets, WasmHeapType, WasmRefType, WasmType,
+    VMCONTEXT_MAGIC,
 };
 #[cfg(feature = "wmemcheck")]
 use wasmtime_wmemcheck::Wmemcheck;
 
 mod allocator;
+mod snapshot;
 
 pub use allocator::*;
+pub use snapshot::InstanceSnapshot;
 
 /// A type that roughly corresponds to a WebAssembly instance, but is also used
 /// for host-defined objects.
@@ -96,6 +100,13 @@ pub struct Instance {
     /// index of the slot in the pooling allocator.
     index: usize,
 
+    /// A number identifying this instance among all the instances ever
+    /// created by this process. Unlike the address of the `vmctx`, which the
+    /// allocator reuses once the instance is deallocated, it is never reused,
+    /// so it identifies the tags defined by this instance even to exceptions
+    /// which outlive it.
+    serial: u64,
+
     /// A pointer to the `vmctx` field at the end of the `Instance`.
     ///
     /// If you're looking at this a reasonable question would be "why do we need
@@ -155,6 +166,9 @@ pub struct Instance {
     vmctx: VMContext,
 }
 
+/// The `serial` of the next instance to be created.
+static NEXT_SERIAL: AtomicU64 = AtomicU64::new(0);
+
 #[allow(clippy::cast_ptr_alignment)]
 impl Instance {
     pub(crate) fn tsp(&self) -> TopOfStackPointer {
@@ -195,6 +209,7 @@ impl Instance {
             Instance {
                 runtime_info: req.runtime_info.clone(),
                 index,
+                serial: NEXT_SERIAL.fetch_add(1, Ordering::Relaxed),
                 memories,
                 tables,
                 dropped_elements,
@@ -279,6 +294,22 @@ impl Instance {
         self.runtime_info.module()
     }
 
+    pub(crate) fn runtime_info(&self) -> &Arc<dyn ModuleRuntimeInfo> {
+        &self.runtime_info
+    }
+
+    /// Get the canonical identity of the tag `index` of this instance.
+    ///
+    /// Modules importing or exporting tags are rejected at compile time, so
+    /// every tag of an instance is defined by that instance.
+    pub(crate) fn tag_id(&self, index: TagIndex) -> VMTagId {
+        debug_assert!(self.module().tags.is_valid(index));
+        VMTagId {
+            instance: self.serial,
+            index,
+        }
+    }
+
     #[inline]
     fn offsets(&self) -> &VMOffsets<HostPtr> {
         self.runtime_info.offsets()
@@ -536,6 +567,12 @@ impl Instance {
         &*self.host_state
     }
 
+    /// Return the unique ID of the compiled module this instance was created
+    /// from, if any.
+    pub fn module_id(&self) -> Option<CompiledModuleId> {
+        self.runtime_info.unique_id()
+    }
+
     /// Return the table index for the given `VMTableDefinition`.
     pub unsafe fn table_index(&mut self, table: &VMTableDefinition) -> DefinedTableIndex {
         let index = DefinedTableIndex::new(
@@ -1322,6 +1359,16 @@ impl InstanceHandle {
         self.instance().module()
     }
 
+    /// Return the canonical identity of the tag `index`.
+    pub fn tag_id(&self, index: TagIndex) -> VMTagId {
+        self.instance().tag_id(index)
+    }
+
+    /// Return the types of the parameters of the tag `index`.
+    pub fn tag_params(&self, index: TagIndex) -> &[WasmType] {
+        self.instance().runtime_info.tag_params(index)
+    }
+
     /// Lookup a function by index.
     pub fn get_exported_func(&mut self, export: FuncIndex) -> ExportFunction {
         self.instance_mut().get_exported_func(export)
@@ -1366,6 +1413,12 @@ impl InstanceHandle {
         self.instance().host_state()
     }
 
+    /// Return the unique ID of the compiled module this instance was created
+    /// from, if any.
+    pub fn module_id(&self) -> Option<CompiledModuleId> {
+        self.instance().module_id()
+    }
+
     /// Get a table defined locally within this module.
     pub fn get_defined_table(&mut self, index: DefinedTableIndex) -> *mut Table {
         self.instance_mut().get_defined_table(index)
@@ -1429,6 +1482,22 @@ impl InstanceHandle {
         allocator::initialize_instance(self.instance_mut(), module, is_bulk_memory)
     }
 
+    /// Captures the contents of the memories, tables and globals defined by
+    /// this instance.
+    ///
+    /// Returns an error if the instance defines a memory which can't be
+    /// snapshotted, such as a shared memory.
+    pub fn snapshot(&mut self) -> Result<InstanceSnapshot> {
+        self.instance_mut().snapshot()
+    }
+
+    /// Rolls the memories, tables and globals defined by this instance back to
+    /// the state captured in `snapshot`, which must have been taken from this
+    /// instance.
+    pub fn restore(&mut self, snapshot: &InstanceSnapshot) -> Result<()> {
+        self.instance_mut().restore(snapshot)
+    }
+
     /// Attempts to convert from the host `addr` specified to a WebAssembly
     /// based address recorded in `WasmFault`.
     ///
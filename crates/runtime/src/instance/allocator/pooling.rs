@@ -956,7 +956,7 @@ mod test {
         CompiledModuleId, Imports, MemoryImage, ModuleRuntimeInfo, StorePtr, VMSharedSignatureIndex,
     };
     use std::{ptr::NonNull, sync::Arc};
-    use wasmtime_environ::{DefinedFuncIndex, DefinedMemoryIndex};
+    use wasmtime_environ::{DefinedFuncIndex, DefinedMemoryIndex, TagIndex, WasmType};
 
     pub(crate) fn empty_runtime_info(
         module: Arc<wasmtime_environ::Module>,
@@ -1007,6 +1007,9 @@ mod test {
             fn offsets(&self) -> &VMOffsets<HostPtr> {
                 &self.1
             }
+            fn tag_params(&self, _: TagIndex) -> &[WasmType] {
+                unimplemented!()
+            }
         }
 
         let offsets = VMOffsets::new(HostPtr, &module);
@@ -0,0 +1,106 @@
+//! Snapshots of the mutable state of an instance, used to checkpoint and roll
+//! back a whole store.
+
+use super::Instance;
+use crate::{MemorySnapshot, TableElement, VMExternRef};
+use anyhow::{bail, Result};
+use wasmtime_environ::{
+    DefinedGlobalIndex, DefinedMemoryIndex, DefinedTableIndex, PrimaryMap, WasmHeapType,
+    WasmRefType, WasmType,
+};
+
+/// The mutable state defined by an instance, captured by
+/// `InstanceHandle::snapshot`: the contents of its defined memories, tables and
+/// globals.
+///
+/// Imported items are not part of the snapshot; they are captured by the
+/// snapshot of the instance which defines them.
+pub struct InstanceSnapshot {
+    memories: PrimaryMap<DefinedMemoryIndex, MemorySnapshot>,
+    tables: PrimaryMap<DefinedTableIndex, Vec<TableElement>>,
+    globals: PrimaryMap<DefinedGlobalIndex, GlobalSnapshot>,
+}
+
+enum GlobalSnapshot {
+    /// The raw bits of a global whose value needs no ownership tracking.
+    Bits(u128),
+    /// An `externref` global, which holds a reference to its value.
+    ExternRef(Option<VMExternRef>),
+}
+
+impl Instance {
+    pub(crate) fn snapshot(&mut self) -> Result<InstanceSnapshot> {
+        let mut memories = PrimaryMap::with_capacity(self.memories.len());
+        for memory in self.memories.values_mut() {
+            memories.push(memory.snapshot()?);
+        }
+
+        let tables = self
+            .tables
+            .values()
+            .map(|table| (0..table.size()).map(|i| table.get(i).unwrap()).collect())
+            .collect();
+
+        let module = self.module().clone();
+        let mut globals = PrimaryMap::with_capacity(module.globals.len());
+        for (index, global) in module.globals.iter().skip(module.num_imported_globals) {
+            let def = module.defined_global_index(index).unwrap();
+            let ptr = self.global_ptr(def);
+            let snapshot = unsafe {
+                match global.wasm_ty {
+                    WasmType::Ref(WasmRefType {
+                        heap_type: WasmHeapType::Extern,
+                        ..
+                    }) => GlobalSnapshot::ExternRef((*ptr).as_externref().clone()),
+                    _ => GlobalSnapshot::Bits(*(*ptr).as_u128()),
+                }
+            };
+            globals.push(snapshot);
+        }
+
+        Ok(InstanceSnapshot {
+            memories,
+            tables,
+            globals,
+        })
+    }
+
+    pub(crate) fn restore(&mut self, snapshot: &InstanceSnapshot) -> Result<()> {
+        if snapshot.memories.len() != self.memories.len()
+            || snapshot.tables.len() != self.tables.len()
+            || snapshot.globals.len()
+                != self.module().globals.len() - self.module().num_imported_globals
+        {
+            bail!("snapshot was not taken from this instance");
+        }
+
+        for (index, memory_snapshot) in snapshot.memories.iter() {
+            let memory = &mut self.memories[index];
+            let result = unsafe { memory.restore(memory_snapshot) };
+            // Keep the `VMContext` up to date even if restoring failed part
+            // way through, since the memory may have moved or been resized.
+            let vmmemory = memory.vmmemory();
+            self.set_memory(index, vmmemory);
+            result?;
+        }
+
+        for (index, elements) in snapshot.tables.iter() {
+            let result = unsafe { self.tables[index].restore(elements) };
+            let vmtable = self.tables[index].vmtable();
+            self.set_table(index, vmtable);
+            result?;
+        }
+
+        for (index, global) in snapshot.globals.iter() {
+            let ptr = self.global_ptr(index);
+            unsafe {
+                match global {
+                    GlobalSnapshot::Bits(bits) => *(*ptr).as_u128_mut() = *bits,
+                    GlobalSnapshot::ExternRef(r) => *(*ptr).as_externref_mut() = r.clone(),
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -21,11 +21,15 @@
 )]
 
 use anyhow::Error;
+use std::any::Any;
 use std::fmt;
 use std::ptr::NonNull;
 use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
-use wasmtime_environ::{DefinedFuncIndex, DefinedMemoryIndex, HostPtr, VMOffsets};
+use wasmtime_environ::{
+    DefinedFuncIndex, DefinedMemoryIndex, HostPtr, InstrumentationEvent, TagIndex, VMOffsets,
+    WasmType,
+};
 
 #[macro_use]
 mod trampolines;
@@ -48,6 +52,7 @@ mod vmcontext;
 
 pub mod continuation;
 pub mod debug_builtins;
+pub mod exception;
 pub mod libcalls;
 
 pub use wasmtime_jit_debug::gdb_jit_int::GdbJitImageRegistration;
@@ -56,7 +61,7 @@ pub use crate::export::*;
 pub use crate::externref::*;
 pub use crate::imports::Imports;
 pub use crate::instance::{
-    Instance, InstanceAllocationRequest, InstanceAllocator, InstanceHandle,
+    Instance, InstanceAllocationRequest, InstanceAllocator, InstanceHandle, InstanceSnapshot,
     OnDemandInstanceAllocator, StorePtr,
 };
 #[cfg(feature = "pooling-allocator")]
@@ -72,11 +77,11 @@ pub use crate::store_box::*;
 pub use crate::table::{Table, TableElement};
 pub use crate::traphandlers::*;
 pub use crate::vmcontext::{
-    VMArrayCallFunction, VMArrayCallHostFuncContext, VMContext, VMFuncRef, VMFunctionBody,
-    VMFunctionImport, VMGlobalDefinition, VMGlobalImport, VMInvokeArgument, VMMemoryDefinition,
-    VMMemoryImport, VMNativeCallFunction, VMNativeCallHostFuncContext, VMOpaqueContext,
-    VMRuntimeLimits, VMSharedSignatureIndex, VMTableDefinition, VMTableImport, VMWasmCallFunction,
-    ValRaw,
+    VMArrayCallFunction, VMArrayCallHostFuncContext, VMContext, VMDebugFrame, VMFuncRef,
+    VMFunctionBody, VMFunctionImport, VMGlobalDefinition, VMGlobalImport, VMInvokeArgument,
+    VMMemoryDefinition, VMMemoryImport, VMNativeCallFunction, VMNativeCallHostFuncContext,
+    VMOpaqueContext, VMRuntimeLimits, VMSharedSignatureIndex, VMTableDefinition, VMTableImport,
+    VMWasmCallFunction, ValRaw,
 };
 pub use send_sync_ptr::SendSyncPtr;
 
@@ -84,7 +89,7 @@ mod module_id;
 pub use module_id::{CompiledModuleId, CompiledModuleIdAllocator};
 
 mod cow;
-pub use crate::cow::{MemoryImage, MemoryImageSlot, ModuleMemoryImages};
+pub use crate::cow::{MemoryImage, MemoryImageSlot, MemorySnapshot, ModuleMemoryImages};
 
 /// Version number of this crate.
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -134,6 +139,9 @@ pub unsafe trait Store {
     /// Callback invoked to notify the store's resource limiter that a memory
     /// grow operation has failed.
     fn memory_grow_failed(&mut self, error: &Error);
+    /// Callback invoked after a memory grew from `old_size` to `new_size`
+    /// bytes.
+    fn memory_grown(&mut self, old_size: usize, new_size: usize);
     /// Callback invoked to allow the store's resource limiter to reject a
     /// table grow operation.
     fn table_growing(
@@ -145,6 +153,9 @@ pub unsafe trait Store {
     /// Callback invoked to notify the store's resource limiter that a table
     /// grow operation has failed.
     fn table_grow_failed(&mut self, error: &Error);
+    /// Callback invoked after a table grew from `old_size` to `new_size`
+    /// elements.
+    fn table_grown(&mut self, old_size: u32, new_size: u32);
     /// Callback invoked whenever fuel runs out by a wasm instance. If an error
     /// is returned that's raised as a trap. Otherwise wasm execution will
     /// continue as normal.
@@ -153,6 +164,24 @@ pub unsafe trait Store {
     /// number. Cannot fail; cooperative epoch-based yielding is
     /// completely semantically transparent. Returns the new deadline.
     fn new_epoch(&mut self) -> Result<u64, Error>;
+    /// Callback invoked by code compiled with debug instrumentation when the
+    /// store has asked to be notified before the next instruction, either
+    /// because of a breakpoint or because it is single-stepping. If an error
+    /// is returned that's raised as a trap.
+    fn debug_break(&mut self) -> Result<(), Error>;
+    /// Callback invoked by instrumented code to report `event` to the store,
+    /// where `instance` is the host state of the instance which reported it.
+    /// If an error is returned that's raised as a trap.
+    fn instrumentation_event(
+        &mut self,
+        instance: &dyn Any,
+        event: InstrumentationEvent,
+    ) -> Result<(), Error>;
+    /// Callback invoked whenever a continuation is created.
+    fn continuation_created(&mut self);
+    /// Callback invoked whenever execution switches between continuations,
+    /// either by resuming one or by suspending the current one.
+    fn continuation_switched(&mut self);
 
     /// Metadata required for resources for the component model.
     #[cfg(feature = "component-model")]
@@ -222,6 +251,10 @@ pub trait ModuleRuntimeInfo: Send + Sync + 'static {
 
     /// Offset information for the current host.
     fn offsets(&self) -> &VMOffsets<HostPtr>;
+
+    /// The types of the parameters of the tag `index`, which are the types of
+    /// the payload of exceptions with that tag.
+    fn tag_params(&self, index: TagIndex) -> &[WasmType];
 }
 
 /// Returns the host OS page size, in bytes.
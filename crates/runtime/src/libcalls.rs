@@ -54,6 +54,7 @@
 //! }
 //! ```
 
+use crate::exception::VMException;
 use crate::externref::VMExternRef;
 use crate::table::{Table, TableElementType};
 use crate::vmcontext::VMFuncRef;
@@ -61,17 +62,19 @@ use crate::{Instance, TrapReason};
 #[cfg(feature = "wmemcheck")]
 use anyhow::bail;
 use anyhow::Result;
-use cfg_if::cfg_if;
 use std::mem;
 use std::ptr::{self, NonNull};
 use std::time::{Duration, Instant};
 use wasmtime_environ::{
-    DataIndex, ElemIndex, FuncIndex, GlobalIndex, MemoryIndex, TableIndex, Trap,
+    DataIndex, ElemIndex, FuncIndex, GlobalIndex, InstrumentationEvent, MemoryIndex, TableIndex,
+    TagIndex, Trap,
 };
 #[cfg(feature = "wmemcheck")]
 use wasmtime_wmemcheck::AccessError::{
     DoubleMalloc, InvalidFree, InvalidRead, InvalidWrite, OutOfBounds,
 };
+#[cfg(feature = "wmemcheck")]
+use wasmtime_wmemcheck::Wmemcheck;
 
 /// Actually public trampolines which are used by the runtime as the entrypoint
 /// for libcalls.
@@ -495,145 +498,155 @@ unsafe fn new_epoch(instance: &mut Instance) -> Result<u64> {
     (*instance.store()).new_epoch()
 }
 
-cfg_if! {
-    if #[cfg(feature = "wmemcheck")] {
-        // Hook for validating malloc using wmemcheck_state.
-        unsafe fn check_malloc(instance: &mut Instance, addr: u32, len: u32) -> Result<u32> {
-            if let Some(wmemcheck_state) = &mut instance.wmemcheck_state {
-                let result = wmemcheck_state.malloc(addr as usize, len as usize);
-                wmemcheck_state.memcheck_on();
-                match result {
-                    Ok(()) => {
-                        return Ok(0);
-                    }
-                    Err(DoubleMalloc { addr, len }) => {
-                        bail!("Double malloc at addr {:#x} of size {}", addr, len)
-                    }
-                    Err(OutOfBounds { addr, len }) => {
-                        bail!("Malloc out of bounds at addr {:#x} of size {}", addr, len);
-                    }
-                    _ => {
-                        panic!("unreachable")
-                    }
-                }
-            }
-            Ok(0)
-        }
-
-        // Hook for validating free using wmemcheck_state.
-        unsafe fn check_free(instance: &mut Instance, addr: u32) -> Result<u32> {
-            if let Some(wmemcheck_state) = &mut instance.wmemcheck_state {
-                let result = wmemcheck_state.free(addr as usize);
-                wmemcheck_state.memcheck_on();
-                match result {
-                    Ok(()) => {
-                        return Ok(0);
-                    }
-                    Err(InvalidFree { addr }) => {
-                        bail!("Invalid free at addr {:#x}", addr)
-                    }
-                    _ => {
-                        panic!("unreachable")
-                    }
-                }
-            }
-            Ok(0)
-        }
-
-        // Hook for validating load using wmemcheck_state.
-        fn check_load(instance: &mut Instance, num_bytes: u32, addr: u32, offset: u32) -> Result<u32> {
-            if let Some(wmemcheck_state) = &mut instance.wmemcheck_state {
-                let result = wmemcheck_state.read(addr as usize + offset as usize, num_bytes as usize);
-                match result {
-                    Ok(()) => {
-                        return Ok(0);
-                    }
-                    Err(InvalidRead { addr, len }) => {
-                        bail!("Invalid load at addr {:#x} of size {}", addr, len);
-                    }
-                    Err(OutOfBounds { addr, len }) => {
-                        bail!("Load out of bounds at addr {:#x} of size {}", addr, len);
-                    }
-                    _ => {
-                        panic!("unreachable")
-                    }
-                }
-            }
-            Ok(0)
-        }
-
-        // Hook for validating store using wmemcheck_state.
-        fn check_store(instance: &mut Instance, num_bytes: u32, addr: u32, offset: u32) -> Result<u32> {
-            if let Some(wmemcheck_state) = &mut instance.wmemcheck_state {
-                let result = wmemcheck_state.write(addr as usize + offset as usize, num_bytes as usize);
-                match result {
-                    Ok(()) => {
-                        return Ok(0);
-                    }
-                    Err(InvalidWrite { addr, len }) => {
-                        bail!("Invalid store at addr {:#x} of size {}", addr, len)
-                    }
-                    Err(OutOfBounds { addr, len }) => {
-                        bail!("Store out of bounds at addr {:#x} of size {}", addr, len)
-                    }
-                    _ => {
-                        panic!("unreachable")
-                    }
-                }
-            }
-            Ok(0)
-        }
+// Hook for when debug-instrumented code is about to execute an instruction
+// the store wants to pause at.
+unsafe fn debug_break(instance: &mut Instance) -> Result<()> {
+    (*instance.store()).debug_break()
+}
 
-        // Hook for turning wmemcheck load/store validation off when entering a malloc function.
-        fn malloc_start(instance: &mut Instance) {
-            if let Some(wmemcheck_state) = &mut instance.wmemcheck_state {
-                wmemcheck_state.memcheck_off();
-            }
-        }
+// Hooks for instrumented code reporting an event to the store.
+unsafe fn instrument_call(instance: &mut Instance, func: u32) -> Result<()> {
+    instrumentation_event(instance, InstrumentationEvent::Call { func_index: func })
+}
 
-        // Hook for turning wmemcheck load/store validation off when entering a free function.
-        fn free_start(instance: &mut Instance) {
-            if let Some(wmemcheck_state) = &mut instance.wmemcheck_state {
-                wmemcheck_state.memcheck_off();
-            }
-        }
+unsafe fn instrument_return(instance: &mut Instance, func: u32) -> Result<()> {
+    instrumentation_event(instance, InstrumentationEvent::Return { func_index: func })
+}
 
-        // Hook for tracking wasm stack updates using wmemcheck_state.
-        fn update_stack_pointer(_instance: &mut Instance, _value: u32) {
-            // TODO: stack-tracing has yet to be finalized. All memory below
-            // the address of the top of the stack is marked as valid for
-            // loads and stores.
-            // if let Some(wmemcheck_state) = &mut instance.wmemcheck_state {
-            //     instance.wmemcheck_state.update_stack_pointer(value as usize);
-            // }
-        }
+unsafe fn instrument_memory_grow(instance: &mut Instance, memory: u32, delta: u64) -> Result<()> {
+    instrumentation_event(instance, InstrumentationEvent::MemoryGrow { memory, delta })
+}
 
-        // Hook updating wmemcheck_state memory state vector every time memory.grow is called.
-        fn update_mem_size(instance: &mut Instance, num_pages: u32) {
-            if let Some(wmemcheck_state) = &mut instance.wmemcheck_state {
-                const KIB: usize = 1024;
-                let num_bytes = num_pages as usize * 64 * KIB;
-                wmemcheck_state.update_mem_size(num_bytes);
-            }
-        }
-    } else {
-        // No-op for all wmemcheck hooks.
-        unsafe fn check_malloc(_instance: &mut Instance, _addr: u32, _len: u32) -> Result<u32> { Ok(0) }
+unsafe fn instrument_load(
+    instance: &mut Instance,
+    memory: u32,
+    addr: u64,
+    size: u32,
+) -> Result<()> {
+    let event = InstrumentationEvent::Load {
+        memory,
+        address: addr,
+        size: size as u8,
+    };
+    instrumentation_event(instance, event)
+}
 
-        unsafe fn check_free(_instance: &mut Instance, _addr: u32) -> Result<u32> { Ok(0) }
+unsafe fn instrument_store(
+    instance: &mut Instance,
+    memory: u32,
+    addr: u64,
+    size: u32,
+) -> Result<()> {
+    let event = InstrumentationEvent::Store {
+        memory,
+        address: addr,
+        size: size as u8,
+    };
+    instrumentation_event(instance, event)
+}
 
-        fn check_load(_instance: &mut Instance, _num_bytes: u32, _addr: u32, _offset: u32) -> Result<u32> { Ok(0) }
+unsafe fn instrument_allocator_entry(instance: &mut Instance) -> Result<()> {
+    instrumentation_event(instance, InstrumentationEvent::AllocatorEntry)
+}
 
-        fn check_store(_instance: &mut Instance, _num_bytes: u32, _addr: u32, _offset: u32) -> Result<u32> { Ok(0) }
+unsafe fn instrument_malloc(instance: &mut Instance, addr: u64, size: u64) -> Result<()> {
+    let event = InstrumentationEvent::Malloc {
+        address: addr,
+        size,
+    };
+    instrumentation_event(instance, event)
+}
 
-        fn malloc_start(_instance: &mut Instance) {}
+unsafe fn instrument_free(instance: &mut Instance, addr: u64) -> Result<()> {
+    instrumentation_event(instance, InstrumentationEvent::Free { address: addr })
+}
 
-        fn free_start(_instance: &mut Instance) {}
+unsafe fn instrument_stack_pointer(instance: &mut Instance, value: u64) -> Result<()> {
+    instrumentation_event(instance, InstrumentationEvent::StackPointer { value })
+}
 
-        fn update_stack_pointer(_instance: &mut Instance, _value: u32) {}
+// Validates an event with the instance's memory checker, if it has one, and
+// then reports it to the store.
+unsafe fn instrumentation_event(
+    instance: &mut Instance,
+    event: InstrumentationEvent,
+) -> Result<()> {
+    #[cfg(feature = "wmemcheck")]
+    if let Some(wmemcheck_state) = &mut instance.wmemcheck_state {
+        wmemcheck(wmemcheck_state, event)?;
+    }
+    (*instance.store()).instrumentation_event(instance.host_state(), event)
+}
 
-        fn update_mem_size(_instance: &mut Instance, _num_pages: u32) {}
+// Updates wmemcheck_state with an event, failing on an invalid access. The
+// memory checker only tracks the first memory.
+#[cfg(feature = "wmemcheck")]
+fn wmemcheck(wmemcheck_state: &mut Wmemcheck, event: InstrumentationEvent) -> Result<()> {
+    match event {
+        // Loads and stores made by the allocator itself aren't checked.
+        InstrumentationEvent::AllocatorEntry => wmemcheck_state.memcheck_off(),
+        InstrumentationEvent::Malloc { address, size } => {
+            let result = wmemcheck_state.malloc(address as usize, size as usize);
+            wmemcheck_state.memcheck_on();
+            match result {
+                Ok(()) => {}
+                Err(DoubleMalloc { addr, len }) => {
+                    bail!("Double malloc at addr {:#x} of size {}", addr, len)
+                }
+                Err(OutOfBounds { addr, len }) => {
+                    bail!("Malloc out of bounds at addr {:#x} of size {}", addr, len)
+                }
+                Err(_) => unreachable!(),
+            }
+        }
+        InstrumentationEvent::Free { address } => {
+            let result = wmemcheck_state.free(address as usize);
+            wmemcheck_state.memcheck_on();
+            match result {
+                Ok(()) => {}
+                Err(InvalidFree { addr }) => bail!("Invalid free at addr {:#x}", addr),
+                Err(_) => unreachable!(),
+            }
+        }
+        InstrumentationEvent::Load {
+            memory: 0,
+            address,
+            size,
+        } => match wmemcheck_state.read(address as usize, usize::from(size)) {
+            Ok(()) => {}
+            Err(InvalidRead { addr, len }) => {
+                bail!("Invalid load at addr {:#x} of size {}", addr, len)
+            }
+            Err(OutOfBounds { addr, len }) => {
+                bail!("Load out of bounds at addr {:#x} of size {}", addr, len)
+            }
+            Err(_) => unreachable!(),
+        },
+        InstrumentationEvent::Store {
+            memory: 0,
+            address,
+            size,
+        } => match wmemcheck_state.write(address as usize, usize::from(size)) {
+            Ok(()) => {}
+            Err(InvalidWrite { addr, len }) => {
+                bail!("Invalid store at addr {:#x} of size {}", addr, len)
+            }
+            Err(OutOfBounds { addr, len }) => {
+                bail!("Store out of bounds at addr {:#x} of size {}", addr, len)
+            }
+            Err(_) => unreachable!(),
+        },
+        InstrumentationEvent::MemoryGrow { memory: 0, delta } => {
+            let num_bytes = delta as usize * wasmtime_environ::WASM_PAGE_SIZE as usize;
+            wmemcheck_state.update_mem_size(num_bytes);
+        }
+        // TODO: stack-tracing has yet to be finalized. All memory below the
+        // address of the top of the stack is marked as valid for loads and
+        // stores.
+        InstrumentationEvent::StackPointer { .. } => {}
+        _ => {}
     }
+    Ok(())
 }
 
 /// This module contains functions which are used for resolving relocations at
@@ -782,19 +795,34 @@ fn cont_new(
     param_count: u64,
     result_count: u64,
 ) -> *mut u8 {
+    unsafe { (*instance.store()).continuation_created() };
     crate::continuation::cont_new(instance, func, param_count as usize, result_count as usize)
         as *mut u8
 }
 
-fn resume(instance: &mut Instance, contobj: *mut u8) -> Result<u32, TrapReason> {
-    crate::continuation::resume(
+fn resume_start(instance: &mut Instance, contobj: *mut u8) -> Result<*mut u8, TrapReason> {
+    unsafe { (*instance.store()).continuation_switched() };
+    crate::continuation::resume_start(
+        instance,
+        contobj as *mut crate::continuation::ContinuationObject,
+    )
+}
+
+fn resume_finish(
+    instance: &mut Instance,
+    contobj: *mut u8,
+    payload: u64,
+) -> Result<u32, TrapReason> {
+    crate::continuation::resume_finish(
         instance,
         contobj as *mut crate::continuation::ContinuationObject,
+        payload,
     )
 }
 
-fn suspend(instance: &mut Instance, tag_index: u32) {
-    crate::continuation::suspend(instance, tag_index)
+fn suspend_start(instance: &mut Instance, tag_index: u32) -> *mut u8 {
+    unsafe { (*instance.store()).continuation_switched() };
+    crate::continuation::suspend_start(instance, tag_index)
 }
 
 fn cont_obj_get_results(_instance: &mut Instance, contobj: *mut u8) -> *mut u8 {
@@ -893,3 +921,84 @@ fn get_payload_buffer(instance: &mut Instance, expected_element_capacity: u32) -
 fn drop_cont_obj(_instance: &mut Instance, contobj: *mut u8) {
     crate::continuation::drop_cont_obj(contobj as *mut crate::continuation::ContinuationObject)
 }
+
+// Throw an exception of the tag `tag`, whose payload compiled code has stored
+// in the payload buffer.
+unsafe fn throw(instance: &mut Instance, tag: u32) {
+    let tag = TagIndex::from_u32(tag);
+    let module = instance.runtime_info().clone();
+    let params = module.tag_params(tag);
+    let payload = if params.is_empty() {
+        vec![]
+    } else {
+        let buffer = crate::continuation::get_payload_buffer(instance, params.len());
+        let payload = params
+            .iter()
+            .enumerate()
+            .map(|(i, ty)| crate::exception::read_payload_slot(buffer.add(i), ty))
+            .collect();
+        crate::continuation::deallocate_payload_buffer(instance, params.len());
+        payload
+    };
+    let exception = VMException::new(instance, tag, payload);
+    crate::exception::set_pending(*instance.runtime_limits(), exception);
+}
+
+// Rethrow an exception previously taken by `catch_exception`.
+unsafe fn rethrow(instance: &mut Instance, exn: *mut u8) {
+    let exn = VMExternRef::clone_from_raw(exn);
+    crate::exception::set_pending(*instance.runtime_limits(), exn);
+}
+
+// Unwind the caller's frame, and those of its callers, up to the handler of the
+// pending exception.
+unsafe fn unwind(instance: &mut Instance) {
+    crate::exception::unwind_instance(instance)
+}
+
+// Take the pending exception, to be held by the handler of a `try` block.
+unsafe fn catch_exception(instance: &mut Instance) -> *mut u8 {
+    let limits = *instance.runtime_limits();
+    let exn = crate::exception::take_pending(limits).expect("no exception is pending");
+    let raw = exn.as_raw();
+    // The exception is about to be on the Wasm stack, so it must be in the
+    // activations table.
+    let (activations_table, module_info_lookup) = (*instance.store()).externref_activations_table();
+    activations_table.insert_with_gc(limits, exn, module_info_lookup);
+    raw
+}
+
+// Test whether an exception has the tag `tag` of the calling instance.
+unsafe fn exception_has_tag(
+    instance: &mut Instance,
+    exn: *mut u8,
+    tag: u32,
+) -> Result<u32, TrapReason> {
+    let exn = VMExternRef::clone_from_raw(exn);
+    let exception = crate::exception::downcast(&exn);
+    let tag_id = instance.tag_id(TagIndex::from_u32(tag));
+    Ok(exception.has_tag(tag_id) as u32)
+}
+
+// Store the payload of an exception in a new payload buffer.
+unsafe fn exception_payload(instance: &mut Instance, exn: *mut u8) {
+    let exn = VMExternRef::clone_from_raw(exn);
+    let exception = crate::exception::downcast(&exn);
+
+    // The `externref`s of the payload are about to be on the Wasm stack, so
+    // they must be in the activations table too.
+    let limits = *instance.runtime_limits();
+    let (activations_table, module_info_lookup) = (*instance.store()).externref_activations_table();
+    for externref in exception.externrefs() {
+        activations_table.insert_with_gc(limits, externref.clone(), module_info_lookup);
+    }
+
+    let params = exception.param_types();
+    if params.is_empty() {
+        return;
+    }
+    let buffer = crate::continuation::allocate_payload_buffer(instance, params.len());
+    for (i, (ty, val)) in params.iter().zip(exception.payload()).enumerate() {
+        crate::exception::write_payload_slot(buffer.add(i), ty, *val);
+    }
+}
@@ -5,7 +5,7 @@
 use crate::mmap::Mmap;
 use crate::parking_spot::ParkingSpot;
 use crate::vmcontext::VMMemoryDefinition;
-use crate::{MemoryImage, MemoryImageSlot, SendSyncPtr, Store, WaitResult};
+use crate::{MemoryImage, MemoryImageSlot, MemorySnapshot, SendSyncPtr, Store, WaitResult};
 use anyhow::Error;
 use anyhow::{bail, format_err, Result};
 use std::convert::TryFrom;
@@ -123,7 +123,12 @@ pub trait RuntimeLinearMemory: Send + Sync {
         }
 
         match self.grow_to(new_byte_size) {
-            Ok(_) => Ok(Some((old_byte_size, new_byte_size))),
+            Ok(_) => {
+                if let Some(store) = store {
+                    store.memory_grown(old_byte_size, new_byte_size);
+                }
+                Ok(Some((old_byte_size, new_byte_size)))
+            }
             Err(e) => {
                 // FIXME: shared memories may not have an associated store to
                 // report the growth failure to but the error should not be
@@ -160,6 +165,16 @@ pub trait RuntimeLinearMemory: Send + Sync {
     /// This starts at the base of linear memory and ends at the end of the
     /// guard pages, if any.
     fn wasm_accessible(&self) -> Range<usize>;
+
+    /// Replaces the size and contents of this memory with those captured in
+    /// `snapshot`.
+    ///
+    /// Memories which don't support snapshots return an error, which is the
+    /// default.
+    fn restore(&mut self, snapshot: &MemorySnapshot) -> Result<()> {
+        let _ = snapshot;
+        bail!("this linear memory does not support restoring snapshots")
+    }
 }
 
 /// A linear memory instance.
@@ -359,6 +374,38 @@ impl RuntimeLinearMemory for MmapMemory {
         let end = base + (self.mmap.len() - self.pre_guard_size);
         base..end
     }
+
+    fn restore(&mut self, snapshot: &MemorySnapshot) -> Result<()> {
+        let size = snapshot.byte_size();
+        if let Some(max) = self.maximum {
+            if size > max {
+                bail!(
+                    "memory snapshot of {} bytes exceeds the memory's maximum size of {} bytes",
+                    size,
+                    max
+                );
+            }
+        }
+        if size > self.accessible {
+            self.grow_to(size)?;
+        }
+
+        // Restoring goes through a `MemoryImageSlot` so that snapshot images
+        // can be mapped in, so create one over our mmap if this memory didn't
+        // start out with an image or lost it when growth moved the memory.
+        if self.memory_image.is_none() {
+            let base = unsafe { self.mmap.as_mut_ptr().add(self.pre_guard_size) };
+            let static_size = self.mmap.len() - self.pre_guard_size - self.offset_guard_size;
+            let mut slot = MemoryImageSlot::create(base.cast(), self.accessible, static_size);
+            // As in `MmapMemory::new`, dropping our mmap already releases this
+            // range.
+            slot.no_clear_on_drop();
+            self.memory_image = Some(slot);
+        }
+        self.memory_image.as_mut().unwrap().restore(snapshot)?;
+        self.accessible = size;
+        Ok(())
+    }
 }
 
 /// A "static" memory where the lifetime of the backing memory is managed
@@ -458,6 +505,20 @@ impl RuntimeLinearMemory for StaticMemory {
         let end = base + self.memory_and_guard_size;
         base..end
     }
+
+    fn restore(&mut self, snapshot: &MemorySnapshot) -> Result<()> {
+        let size = snapshot.byte_size();
+        if size > self.capacity {
+            bail!(
+                "memory snapshot of {} bytes exceeds the memory's maximum size of {} bytes",
+                size,
+                self.capacity
+            );
+        }
+        self.memory_image.restore(snapshot)?;
+        self.size = size;
+        Ok(())
+    }
 }
 
 /// For shared memory (and only for shared memory), this lock-version restricts
@@ -863,6 +924,32 @@ impl Memory {
         self.0.vmmemory()
     }
 
+    /// Captures the current size and contents of this memory.
+    ///
+    /// Returns an error for shared memories, whose contents may be changed
+    /// concurrently by other threads.
+    pub fn snapshot(&mut self) -> Result<MemorySnapshot> {
+        if self.as_shared_memory().is_some() {
+            bail!("cannot snapshot a shared memory");
+        }
+        let vmmemory = self.vmmemory();
+        let len = vmmemory.current_length();
+        let data = unsafe { std::slice::from_raw_parts(vmmemory.base, len) };
+        MemorySnapshot::new(data)
+    }
+
+    /// Replaces the size and contents of this memory with those captured in
+    /// `snapshot`.
+    ///
+    /// # Safety
+    ///
+    /// Like `grow`, restoring may move the memory buffer of dynamic memories
+    /// and changes the memory's size, so the `VMContext` of the instance
+    /// owning this memory must be updated with `vmmemory` afterwards.
+    pub unsafe fn restore(&mut self, snapshot: &MemorySnapshot) -> Result<()> {
+        self.0.restore(snapshot)
+    }
+
     /// Consume the memory, returning its [`MemoryImageSlot`] if any is present.
     /// The image should only be present for a subset of memories created with
     /// [`Memory::new_static()`].
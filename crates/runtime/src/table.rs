@@ -392,10 +392,62 @@ impl Table {
 
         self.fill(old_size, init_value, delta)
             .expect("table should not be out of bounds");
+        store.table_grown(old_size, new_size);
 
         Ok(Some(old_size))
     }
 
+    /// Replaces the size and contents of this table with `elements`, as
+    /// previously read out of it with `get`.
+    ///
+    /// This is used to restore a snapshot of the table, so unlike `grow` it may
+    /// also shrink the table and it doesn't consult the store's limiter.
+    ///
+    /// # Unsafety
+    ///
+    /// The same as for `grow`: this can reallocate the table's elements buffer
+    /// and the `VMContext` of this table's instance must be fixed up before
+    /// calling into Wasm again.
+    pub unsafe fn restore(&mut self, elements: &[TableElement]) -> Result<(), Error> {
+        let new_size = u32::try_from(elements.len()).unwrap();
+        if let Some(max) = self.maximum() {
+            if new_size > max {
+                bail!(
+                    "table snapshot of {} elements exceeds the table's maximum size of {}",
+                    new_size,
+                    max
+                );
+            }
+        }
+
+        // Release the elements which no longer fit in the table, since static
+        // storage beyond the table's size is expected to be empty.
+        let ty = self.element_type();
+        let null = match ty {
+            TableElementType::Func => TableElement::UninitFunc,
+            TableElementType::Extern => TableElement::ExternRef(None),
+        };
+        if let Some(rest) = self.elements_mut().get_mut(new_size as usize..) {
+            for e in rest {
+                Self::set_raw(ty, e, null.clone());
+            }
+        }
+
+        match self {
+            Table::Static { size, .. } => *size = new_size,
+            Table::Dynamic { elements, .. } => elements.resize(new_size as usize, None),
+        }
+
+        debug_assert!(elements
+            .iter()
+            .all(|e| e.is_uninit() || self.type_matches(e)));
+        for (dst, elem) in self.elements_mut().iter_mut().zip(elements) {
+            Self::set_raw(ty, dst, elem.clone());
+        }
+
+        Ok(())
+    }
+
     /// Get reference to the specified element.
     ///
     /// Returns `None` if the index is out of bounds.
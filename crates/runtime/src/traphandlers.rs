@@ -9,6 +9,7 @@ use anyhow::Error;
 use std::any::Any;
 use std::cell::{Cell, UnsafeCell};
 use std::mem::MaybeUninit;
+use std::ops::Range;
 use std::ptr;
 use std::sync::Once;
 
@@ -204,6 +205,41 @@ pub unsafe fn resume_panic(payload: Box<dyn Any + Send>) -> ! {
     tls::with(|info| info.unwrap().unwind_with(UnwindReason::Panic(payload)))
 }
 
+/// Returns to the host which called into the innermost sequence of Wasm frames
+/// with an exception pending, which no frame of the sequence handles.
+///
+/// `catch_traps` below returns successfully, leaving the exception for its
+/// caller to take.
+///
+/// # Safety
+///
+/// Only safe to call when wasm code is on the stack, aka `catch_traps` must
+/// have been previously called. Additionally no Rust destructors can be on the
+/// stack. They will be skipped and not executed.
+pub(crate) unsafe fn raise_exception() -> ! {
+    tls::with(|info| info.unwrap().unwind_with(UnwindReason::Exception))
+}
+
+/// Was Wasm entered since the innermost call to `catch_traps` below, rather
+/// than only host functions being called by it?
+pub(crate) unsafe fn entered_wasm(limits: *const VMRuntimeLimits) -> bool {
+    tls::with(|info| {
+        info.map_or(false, |state| {
+            state.old_last_wasm_entry_sp() != *(*limits).last_wasm_entry_sp.get()
+        })
+    })
+}
+
+/// Did the innermost call to `catch_traps` below run on the stack spanning
+/// `range`?
+pub(crate) fn caught_within(range: &Range<usize>) -> bool {
+    tls::with(|info| {
+        info.map_or(false, |state| {
+            range.contains(&(state as *const CallThreadState as usize))
+        })
+    })
+}
+
 /// Stores trace message with backtrace.
 #[derive(Debug)]
 pub struct Trap {
@@ -319,6 +355,7 @@ where
             coredumpstack,
         })),
         Err((UnwindReason::Panic(panic), _, _)) => std::panic::resume_unwind(panic),
+        Err((UnwindReason::Exception, _, _)) => Ok(()),
     };
 
     extern "C" fn call_closure<F>(payload: *mut u8, caller: *mut VMContext)
@@ -430,6 +467,7 @@ pub use call_thread_state::*;
 enum UnwindReason {
     Panic(Box<dyn Any + Send>),
     Trap(TrapReason),
+    Exception,
 }
 
 impl CallThreadState {
@@ -463,7 +501,9 @@ impl CallThreadState {
             | UnwindReason::Trap(TrapReason::User {
                 needs_backtrace: false,
                 ..
-            }) => (None, None),
+            })
+            // Nor do exceptions, which aren't errors until the host takes them.
+            | UnwindReason::Exception => (None, None),
             UnwindReason::Trap(_) => (self.capture_backtrace(self.limits, None), self.capture_coredump(self.limits, None)),
         };
         unsafe {
@@ -181,6 +181,26 @@ impl Backtrace {
         log::trace!("====== Done Capturing Backtrace (reached end of activations) ======");
     }
 
+    /// Walk the contiguous sequence of Wasm frames which most recently exited
+    /// to the host, from the youngest to the oldest, without going through the
+    /// older activations which called into the host.
+    ///
+    /// # Safety
+    ///
+    /// `limits` must be the valid `VMRuntimeLimits` of a store, whose Wasm code
+    /// called into the host and is still on the stack.
+    pub(crate) unsafe fn trace_innermost_activation(
+        limits: *const VMRuntimeLimits,
+        f: impl FnMut(Frame) -> ControlFlow<()>,
+    ) -> ControlFlow<()> {
+        Self::trace_through_wasm(
+            *(*limits).last_wasm_exit_pc.get(),
+            *(*limits).last_wasm_exit_fp.get(),
+            *(*limits).last_wasm_entry_sp.get(),
+            f,
+        )
+    }
+
     /// Walk through a contiguous sequence of Wasm frames starting with the
     /// frame at the given PC and FP and ending at `trampoline_sp`.
     unsafe fn trace_through_wasm(
@@ -1,6 +1,7 @@
 use wasm_encoder::CoreDumpValue;
+use wasmtime_environ::{VMDebugValueType, VMDEBUG_FRAME_VALUES_UNAVAILABLE};
 
-use crate::{Backtrace, VMRuntimeLimits};
+use crate::{Backtrace, VMDebugFrame, VMRuntimeLimits};
 
 use super::CallThreadState;
 
@@ -10,12 +11,20 @@ pub struct CoreDumpStack {
     /// The backtrace containing the stack frames for the CoreDump
     pub bt: Backtrace,
 
-    /// Unimplemented
-    /// The indices of the locals and operand_stack all map to each other (ie.
-    /// index 0 is the locals for the first frame in the backtrace, etc)
+    /// The function indices of the frames of code compiled with debug
+    /// instrumentation, innermost first. The indices of `func_indices`,
+    /// `locals` and `operand_stack` all map to each other (ie. index 0 is
+    /// the innermost such frame).
+    ///
+    /// Code compiled without debug instrumentation doesn't record its
+    /// locals and operand stack, so none of these have entries for it.
+    /// Frames whose values weren't recorded at the instruction they were
+    /// executing have missing locals and an empty operand stack.
+    pub func_indices: Vec<u32>,
+
+    /// The locals for each stack frame
     pub locals: Vec<Vec<CoreDumpValue>>,
 
-    /// Unimplemented
     /// The operands for each stack frame
     pub operand_stack: Vec<Vec<CoreDumpValue>>,
 }
@@ -29,10 +38,49 @@ impl CoreDumpStack {
     ) -> Self {
         let bt = unsafe { Backtrace::new_with_trap_state(limits, cts, trap_pc_and_fp) };
 
+        let mut func_indices = Vec::new();
+        let mut locals = Vec::new();
+        let mut operand_stack = Vec::new();
+        unsafe {
+            let mut ptr = *(*limits).debug_frame.get() as *const VMDebugFrame;
+            while let Some(frame) = ptr.as_ref() {
+                func_indices.push(frame.func_index);
+                if frame.num_values == VMDEBUG_FRAME_VALUES_UNAVAILABLE {
+                    locals.push(vec![CoreDumpValue::Missing; frame.num_locals as usize]);
+                    operand_stack.push(Vec::new());
+                } else {
+                    let mut values = (0..frame.num_values as usize).map(|i| read_value(frame, i));
+                    locals.push(values.by_ref().take(frame.num_locals as usize).collect());
+                    operand_stack.push(values.collect());
+                }
+                ptr = frame.prev;
+            }
+        }
+
         Self {
             bt,
-            locals: vec![],
-            operand_stack: vec![],
+            func_indices,
+            locals,
+            operand_stack,
+        }
+    }
+}
+
+/// Reads the `i`th value written to a live `frame`. Values the core dump
+/// format can't represent are recorded as missing.
+unsafe fn read_value(frame: &VMDebugFrame, i: usize) -> CoreDumpValue {
+    let value = frame.values.add(i).cast::<u8>();
+    match VMDebugValueType::from_u8(*frame.types.add(i)) {
+        Some(VMDebugValueType::I32) => CoreDumpValue::I32(value.cast::<i32>().read_unaligned()),
+        Some(VMDebugValueType::I64) => CoreDumpValue::I64(value.cast::<i64>().read_unaligned()),
+        Some(VMDebugValueType::F32) => {
+            CoreDumpValue::F32(f32::from_bits(value.cast::<u32>().read_unaligned()))
+        }
+        Some(VMDebugValueType::F64) => {
+            CoreDumpValue::F64(f64::from_bits(value.cast::<u64>().read_unaligned()))
+        }
+        Some(VMDebugValueType::V128) | Some(VMDebugValueType::ExternRef) | None => {
+            CoreDumpValue::Missing
         }
     }
 }
@@ -848,12 +848,36 @@ pub struct VMRuntimeLimits {
     /// Used to find the end of a contiguous sequence of Wasm frames when
     /// walking the stack.
     pub last_wasm_entry_sp: UnsafeCell<usize>,
+
+    /// The exception currently propagating through the stack, as a raw
+    /// `VMExternRef` owning a `VMException`, or `0` if there is none.
+    ///
+    /// Set by the `throw` and `rethrow` libcalls, and by host functions which
+    /// throw. When Wasm exceptions are enabled, compiled code checks this
+    /// member at the exception landing sites which the runtime unwinds to, and
+    /// branches to the handler of the enclosing `try` block. When the
+    /// exception reaches the host it is taken out of this member and turned
+    /// into an error.
+    pub pending_exception: UnsafeCell<usize>,
+
+    /// The innermost `VMDebugFrame` of the Wasm frames currently on the
+    /// stack, or `0` if there is none.
+    ///
+    /// Only maintained by code compiled with debug instrumentation, which
+    /// points this member at the frame of the running function before every
+    /// instruction.
+    pub debug_frame: UnsafeCell<usize>,
+
+    /// Whether code compiled with debug instrumentation must call the
+    /// `debug_break` libcall before its next instruction, which is the case
+    /// when the store has breakpoints or is single-stepping.
+    pub debug_break: UnsafeCell<u32>,
 }
 
-// The `VMRuntimeLimits` type is a pod-type with no destructor, and we don't
-// access any fields from other threads, so add in these trait impls which are
-// otherwise not available due to the `fuel_consumed` and `epoch_deadline`
-// variables in `VMRuntimeLimits`.
+// The only field of `VMRuntimeLimits` owning anything is `pending_exception`,
+// whose `VMExternRef` is itself `Send` and `Sync`, and we don't access any
+// fields from other threads, so add in these trait impls which are otherwise
+// not available due to the `UnsafeCell` fields of `VMRuntimeLimits`.
 unsafe impl Send for VMRuntimeLimits {}
 unsafe impl Sync for VMRuntimeLimits {}
 
@@ -866,6 +890,19 @@ impl Default for VMRuntimeLimits {
             last_wasm_exit_fp: UnsafeCell::new(0),
             last_wasm_exit_pc: UnsafeCell::new(0),
             last_wasm_entry_sp: UnsafeCell::new(0),
+            pending_exception: UnsafeCell::new(0),
+            debug_frame: UnsafeCell::new(0),
+            debug_break: UnsafeCell::new(0),
+        }
+    }
+}
+
+impl Drop for VMRuntimeLimits {
+    fn drop(&mut self) {
+        // Calls into Wasm take the exception left pending when they return,
+        // but don't leak one that is pending when the store is dropped.
+        unsafe {
+            drop(crate::exception::take_pending(self));
         }
     }
 }
@@ -915,6 +952,101 @@ mod test_vmruntime_limits {
             offset_of!(VMRuntimeLimits, last_wasm_entry_sp),
             usize::from(offsets.ptr.vmruntime_limits_last_wasm_entry_sp())
         );
+        assert_eq!(
+            offset_of!(VMRuntimeLimits, pending_exception),
+            usize::from(offsets.ptr.vmruntime_limits_pending_exception())
+        );
+        assert_eq!(
+            offset_of!(VMRuntimeLimits, debug_frame),
+            usize::from(offsets.ptr.vmruntime_limits_debug_frame())
+        );
+        assert_eq!(
+            offset_of!(VMRuntimeLimits, debug_break),
+            usize::from(offsets.ptr.vmruntime_limits_debug_break())
+        );
+    }
+}
+
+/// The state of a Wasm function compiled with debug instrumentation, which
+/// lives in that function's native stack frame.
+///
+/// Frames are linked together through `prev`, with the innermost one stored
+/// in `VMRuntimeLimits::debug_frame`. The values of the function's locals,
+/// followed by those of its operand stack, are written to `values` (16 bytes
+/// each, like `ValRaw`) with one type tag per value in `types`, but only when
+/// the function is paused, calls another function, or executes an instruction
+/// which may trap. `num_values` is the number of values written for the
+/// instruction being executed, or `VMDEBUG_FRAME_VALUES_UNAVAILABLE` if they
+/// weren't written for it.
+#[derive(Debug)]
+#[repr(C)]
+pub struct VMDebugFrame {
+    /// The frame of the calling Wasm function, if any.
+    pub prev: *const VMDebugFrame,
+    /// The `VMContext` of the instance the function belongs to.
+    pub vmctx: *mut VMContext,
+    /// The values of the locals and operand stack.
+    pub values: *const ValRaw,
+    /// The `VMDebugValueType` tag of each value.
+    pub types: *const u8,
+    /// The index of the function within its module.
+    pub func_index: u32,
+    /// The module offset of the instruction the function is executing.
+    pub offset: u32,
+    /// The number of locals, including parameters, of the function.
+    pub num_locals: u32,
+    /// The number of values in `values` and `types`, or
+    /// `VMDEBUG_FRAME_VALUES_UNAVAILABLE`.
+    pub num_values: u32,
+}
+
+#[cfg(test)]
+mod test_vmdebug_frame {
+    use super::VMDebugFrame;
+    use memoffset::offset_of;
+    use std::mem::size_of;
+    use wasmtime_environ::{Module, PtrSize, VMOffsets};
+
+    #[test]
+    fn field_offsets() {
+        let module = Module::new();
+        let offsets = VMOffsets::new(size_of::<*mut u8>() as u8, &module);
+        assert_eq!(
+            offset_of!(VMDebugFrame, prev),
+            usize::from(offsets.ptr.vmdebug_frame_prev())
+        );
+        assert_eq!(
+            offset_of!(VMDebugFrame, vmctx),
+            usize::from(offsets.ptr.vmdebug_frame_vmctx())
+        );
+        assert_eq!(
+            offset_of!(VMDebugFrame, values),
+            usize::from(offsets.ptr.vmdebug_frame_values())
+        );
+        assert_eq!(
+            offset_of!(VMDebugFrame, types),
+            usize::from(offsets.ptr.vmdebug_frame_types())
+        );
+        assert_eq!(
+            offset_of!(VMDebugFrame, func_index),
+            usize::from(offsets.ptr.vmdebug_frame_func_index())
+        );
+        assert_eq!(
+            offset_of!(VMDebugFrame, offset),
+            usize::from(offsets.ptr.vmdebug_frame_offset())
+        );
+        assert_eq!(
+            offset_of!(VMDebugFrame, num_locals),
+            usize::from(offsets.ptr.vmdebug_frame_num_locals())
+        );
+        assert_eq!(
+            offset_of!(VMDebugFrame, num_values),
+            usize::from(offsets.ptr.vmdebug_frame_num_values())
+        );
+        assert_eq!(
+            size_of::<VMDebugFrame>(),
+            usize::from(offsets.ptr.size_of_vmdebug_frame())
+        );
     }
 }
 
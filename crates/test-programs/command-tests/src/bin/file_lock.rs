@@ -0,0 +1,56 @@
+use command_tests::wasi::filesystem::preopens;
+use command_tests::wasi::filesystem::types::{
+    self as filesystem, DescriptorFlags, ErrorCode, Modes, OpenFlags, PathFlags,
+};
+
+fn main() {
+    let (dir, _) = preopens::get_directories()
+        .into_iter()
+        .next()
+        .expect("a preopened directory");
+
+    let open = || {
+        filesystem::open_at(
+            dir,
+            PathFlags::empty(),
+            "bar.txt",
+            OpenFlags::empty(),
+            DescriptorFlags::READ | DescriptorFlags::WRITE,
+            Modes::READABLE | Modes::WRITABLE,
+        )
+        .expect("open bar.txt")
+    };
+    let a = open();
+    let b = open();
+
+    // An exclusive lock excludes every other lock.
+    filesystem::lock_exclusive(a).unwrap();
+    assert_eq!(filesystem::try_lock_shared(b), Err(ErrorCode::WouldBlock));
+    assert_eq!(
+        filesystem::try_lock_exclusive(b),
+        Err(ErrorCode::WouldBlock)
+    );
+
+    // Shared locks may be held at the same time.
+    filesystem::lock_shared(a).unwrap();
+    filesystem::try_lock_shared(b).unwrap();
+    assert_eq!(
+        filesystem::try_lock_exclusive(a),
+        Err(ErrorCode::WouldBlock)
+    );
+
+    // A failed conversion keeps the lock which was held before.
+    filesystem::unlock(b).unwrap();
+    assert_eq!(
+        filesystem::try_lock_exclusive(b),
+        Err(ErrorCode::WouldBlock)
+    );
+
+    // Once released, the lock can be taken by someone else.
+    filesystem::unlock(a).unwrap();
+    filesystem::try_lock_exclusive(b).unwrap();
+    filesystem::unlock(b).unwrap();
+
+    filesystem::drop_descriptor(a);
+    filesystem::drop_descriptor(b);
+}
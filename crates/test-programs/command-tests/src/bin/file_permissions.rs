@@ -0,0 +1,88 @@
+use command_tests::wasi::filesystem::preopens;
+use command_tests::wasi::filesystem::types::{
+    self as filesystem, AccessType, ErrorCode, Modes, PathFlags,
+};
+
+fn main() {
+    let (dir, _) = preopens::get_directories()
+        .into_iter()
+        .next()
+        .expect("a preopened directory");
+
+    filesystem::access_at(
+        dir,
+        PathFlags::SYMLINK_FOLLOW,
+        "bar.txt",
+        AccessType::Exists,
+    )
+    .unwrap();
+    assert_eq!(
+        filesystem::access_at(
+            dir,
+            PathFlags::SYMLINK_FOLLOW,
+            "baz.txt",
+            AccessType::Exists
+        ),
+        Err(ErrorCode::NoEntry)
+    );
+    filesystem::access_at(
+        dir,
+        PathFlags::SYMLINK_FOLLOW,
+        "bar.txt",
+        AccessType::Access(Modes::READABLE),
+    )
+    .unwrap();
+
+    // Make `bar.txt` read-only. The host checks the result, as access checks
+    // always succeed for a superuser.
+    filesystem::change_file_permissions_at(
+        dir,
+        PathFlags::SYMLINK_FOLLOW,
+        "bar.txt",
+        Modes::READABLE,
+    )
+    .unwrap();
+    filesystem::access_at(
+        dir,
+        PathFlags::SYMLINK_FOLLOW,
+        "bar.txt",
+        AccessType::Access(Modes::READABLE),
+    )
+    .unwrap();
+
+    // Files and directories can't be mixed up.
+    assert_eq!(
+        filesystem::change_file_permissions_at(
+            dir,
+            PathFlags::SYMLINK_FOLLOW,
+            "sub",
+            Modes::READABLE
+        ),
+        Err(ErrorCode::IsDirectory)
+    );
+    assert_eq!(
+        filesystem::change_directory_permissions_at(
+            dir,
+            PathFlags::SYMLINK_FOLLOW,
+            "bar.txt",
+            Modes::READABLE
+        ),
+        Err(ErrorCode::NotDirectory)
+    );
+    assert_eq!(
+        filesystem::change_directory_permissions_at(
+            dir,
+            PathFlags::SYMLINK_FOLLOW,
+            "sub",
+            Modes::READABLE | Modes::EXECUTABLE
+        ),
+        Err(ErrorCode::Invalid)
+    );
+    filesystem::change_directory_permissions_at(
+        dir,
+        PathFlags::SYMLINK_FOLLOW,
+        "sub",
+        Modes::READABLE | Modes::WRITABLE,
+    )
+    .unwrap();
+}
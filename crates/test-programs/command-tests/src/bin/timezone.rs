@@ -0,0 +1,20 @@
+use command_tests::wasi::clocks::timezone;
+use command_tests::wasi::clocks::wall_clock::Datetime;
+
+fn main() {
+    // The host pins the timezone to a fixed offset of -04:30. The timezone
+    // interface can't create timezones, so any handle names that zone.
+    let when = Datetime {
+        seconds: 1_700_000_000,
+        nanoseconds: 0,
+    };
+
+    let display = timezone::display(0, when);
+    assert_eq!(display.utc_offset, -4 * 3600 - 1800);
+    assert_eq!(display.name, "-04:30");
+    assert!(!display.in_daylight_saving_time);
+
+    assert_eq!(timezone::utc_offset(0, when), -4 * 3600 - 1800);
+
+    timezone::drop_timezone(0);
+}
@@ -8,8 +8,8 @@ use wasmtime::{
 use wasmtime_wasi::preview2::{
     command::{add_to_linker, Command},
     pipe::MemoryInputPipe,
-    DirPerms, FilePerms, HostMonotonicClock, HostWallClock, IsATTY, Table, WasiCtx, WasiCtxBuilder,
-    WasiView,
+    DirPerms, FilePerms, HostMonotonicClock, HostWallClock, IsATTY, SystemTimezone, Table, WasiCtx,
+    WasiCtxBuilder, WasiView,
 };
 
 lazy_static::lazy_static! {
@@ -173,6 +173,23 @@ async fn time() -> Result<()> {
         .map_err(|()| anyhow::anyhow!("command returned with failing exit status"))
 }
 
+#[test_log::test(tokio::test(flavor = "multi_thread"))]
+async fn timezone() -> Result<()> {
+    let mut table = Table::new();
+    let wasi = WasiCtxBuilder::new()
+        .timezone(SystemTimezone::fixed(-4 * 3600 - 1800)?)
+        .build(&mut table)?;
+
+    let (mut store, command) =
+        instantiate(get_component("timezone"), CommandCtx { table, wasi }).await?;
+
+    command
+        .wasi_cli_run()
+        .call_run(&mut store)
+        .await?
+        .map_err(|()| anyhow::anyhow!("command returned with failing exit status"))
+}
+
 #[test_log::test(tokio::test(flavor = "multi_thread"))]
 async fn stdin() -> Result<()> {
     let mut table = Table::new();
@@ -311,6 +328,60 @@ async fn file_dir_sync() -> Result<()> {
         .map_err(|()| anyhow::anyhow!("command returned with failing exit status"))
 }
 
+#[test_log::test(tokio::test(flavor = "multi_thread"))]
+async fn file_lock() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+
+    std::fs::File::create(dir.path().join("bar.txt"))?.write_all(b"locked")?;
+
+    let open_dir = Dir::open_ambient_dir(dir.path(), ambient_authority())?;
+
+    let mut table = Table::new();
+    let wasi = WasiCtxBuilder::new()
+        .preopened_dir(open_dir, DirPerms::all(), FilePerms::all(), "/")
+        .build(&mut table)?;
+
+    let (mut store, command) =
+        instantiate(get_component("file_lock"), CommandCtx { table, wasi }).await?;
+
+    command
+        .wasi_cli_run()
+        .call_run(&mut store)
+        .await?
+        .map_err(|()| anyhow::anyhow!("command returned with failing exit status"))
+}
+
+#[test_log::test(tokio::test(flavor = "multi_thread"))]
+async fn file_permissions() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+
+    std::fs::File::create(dir.path().join("bar.txt"))?;
+    std::fs::create_dir(dir.path().join("sub"))?;
+
+    let open_dir = Dir::open_ambient_dir(dir.path(), ambient_authority())?;
+
+    let mut table = Table::new();
+    let wasi = WasiCtxBuilder::new()
+        .preopened_dir(open_dir, DirPerms::all(), FilePerms::all(), "/")
+        .build(&mut table)?;
+
+    let (mut store, command) = instantiate(
+        get_component("file_permissions"),
+        CommandCtx { table, wasi },
+    )
+    .await?;
+
+    command
+        .wasi_cli_run()
+        .call_run(&mut store)
+        .await?
+        .map_err(|()| anyhow::anyhow!("command returned with failing exit status"))?;
+
+    let perms = std::fs::metadata(dir.path().join("bar.txt"))?.permissions();
+    assert!(perms.readonly());
+    Ok(())
+}
+
 #[test_log::test(tokio::test(flavor = "multi_thread"))]
 async fn exit_success() -> Result<()> {
     let mut table = Table::new();
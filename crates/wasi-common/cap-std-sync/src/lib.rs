@@ -45,6 +45,7 @@ pub use cap_std::fs::Dir;
 pub use cap_std::net::TcpListener;
 pub use clocks::clocks_ctx;
 pub use sched::sched_ctx;
+pub use wasi_common::replay::Replay;
 
 use crate::net::Socket;
 use cap_rand::{Rng, RngCore, SeedableRng};
@@ -147,6 +148,16 @@ impl WasiCtxBuilder {
             .insert_file(fd, file, FileAccessMode::READ | FileAccessMode::WRITE);
         Ok(self)
     }
+    /// Records the nondeterministic results observed by the guest into
+    /// `replay`, or replays them from it.
+    ///
+    /// Only the arguments, environment, stdio and preopens configured before
+    /// this call are recorded or replayed, so it should be the last
+    /// configuration step before [`build`](WasiCtxBuilder::build).
+    pub fn replay(&mut self, replay: Replay) -> Result<&mut Self, Error> {
+        self.ctx.set_replay(replay)?;
+        Ok(self)
+    }
     pub fn build(&mut self) -> WasiCtx {
         assert!(!self.built);
         let WasiCtxBuilder { ctx, .. } = mem::replace(self, Self::new());
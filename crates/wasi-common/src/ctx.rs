@@ -1,6 +1,9 @@
-use crate::clocks::WasiClocks;
+use crate::clocks::{WasiClocks, WasiMonotonicOffsetClock};
 use crate::dir::{DirEntry, WasiDir};
 use crate::file::{FileAccessMode, FileEntry, WasiFile};
+use crate::replay::{
+    Replay, ReplayDir, ReplayFile, ReplayMonotonicClock, ReplayRng, ReplaySystemClock,
+};
 use crate::sched::WasiSched;
 use crate::string_array::StringArray;
 use crate::table::Table;
@@ -28,6 +31,7 @@ pub struct WasiCtxInner {
     pub clocks: WasiClocks,
     pub sched: Box<dyn WasiSched>,
     pub table: Table,
+    pub replay: Option<Replay>,
 }
 
 impl WasiCtx {
@@ -44,6 +48,7 @@ impl WasiCtx {
             clocks,
             sched,
             table,
+            replay: None,
         }));
         s.set_stdin(Box::new(crate::pipe::ReadPipe::new(std::io::empty())));
         s.set_stdout(Box::new(crate::pipe::WritePipe::new(std::io::sink())));
@@ -118,6 +123,72 @@ impl WasiCtx {
             .push(Arc::new(DirEntry::new(Some(path.as_ref().to_owned()), dir)))?;
         Ok(())
     }
+
+    /// Records the nondeterministic results observed by the guest into
+    /// `replay`, or replays them from it.
+    ///
+    /// This wraps the clocks, randomness, files and directories currently in
+    /// the context, and records or replaces its arguments and environment, so
+    /// it should be the last step in initializing the context. See the
+    /// [`replay`](crate::replay) module for what is covered.
+    pub fn set_replay(&mut self, replay: Replay) -> Result<(), Error> {
+        let s = Arc::get_mut(&mut self.0).expect(
+            "`set_replay` should only be used during initialization before the context is cloned",
+        );
+
+        let args = std::mem::replace(&mut s.args, StringArray::new()).into_vec();
+        for arg in replay.args(args)? {
+            s.args.push(arg).map_err(|e| Error::trap(e.into()))?;
+        }
+        let env = std::mem::replace(&mut s.env, StringArray::new()).into_vec();
+        for var in replay.env(env)? {
+            s.env.push(var).map_err(|e| Error::trap(e.into()))?;
+        }
+
+        let random = s.random.get_mut().unwrap();
+        let inner = std::mem::replace(random, Box::new(crate::random::Deterministic::new(vec![0])));
+        *random = Box::new(ReplayRng::new(inner, replay.clone()));
+
+        if let Some(clock) = s.clocks.system.take() {
+            s.clocks.system = Some(Box::new(ReplaySystemClock::new(clock, replay.clone())));
+        }
+        if let Some(clock) = s.clocks.monotonic.take() {
+            s.clocks.monotonic = Some(WasiMonotonicOffsetClock::new(ReplayMonotonicClock::new(
+                clock.abs_clock,
+                replay.clone(),
+            )));
+        }
+
+        let shared = |fd| Error::trap(anyhow::anyhow!("cannot replay shared descriptor {fd}"));
+        for fd in s.table.keys() {
+            if s.table.is::<FileEntry>(fd) {
+                let entry = s.table.delete::<FileEntry>(fd).unwrap();
+                let entry = Arc::try_unwrap(entry).map_err(|_| shared(fd))?;
+                let file = Box::new(ReplayFile::new(entry.file, replay.clone()));
+                s.table
+                    .insert_at(fd, Arc::new(FileEntry::new(file, entry.access_mode)));
+            } else if s.table.is::<DirEntry>(fd) {
+                let entry = s.table.delete::<DirEntry>(fd).unwrap();
+                let entry = Arc::try_unwrap(entry).map_err(|_| shared(fd))?;
+                let preopen_path = entry.preopen_path().clone();
+                let dir = Box::new(ReplayDir::new(entry.dir, replay.clone()));
+                s.table
+                    .insert_at(fd, Arc::new(DirEntry::new(preopen_path, dir)));
+            }
+        }
+        s.replay = Some(replay);
+        Ok(())
+    }
+
+    /// Fails with the error which a recorded or replayed clock or random
+    /// number generator ran into, if any, so that the WASI call which used
+    /// them traps.
+    pub(crate) fn check_replay(&self) -> Result<(), Error> {
+        match &self.replay {
+            Some(replay) => replay.check(),
+            None => Ok(()),
+        }
+    }
 }
 
 impl Deref for WasiCtx {
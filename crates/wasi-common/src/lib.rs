@@ -57,6 +57,7 @@ mod error;
 pub mod file;
 pub mod pipe;
 pub mod random;
+pub mod replay;
 pub mod sched;
 pub mod snapshots;
 mod string_array;
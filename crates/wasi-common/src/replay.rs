@@ -0,0 +1,934 @@
+//! Recording and replaying the nondeterministic results observed by a guest.
+//!
+//! A [`Replay`] log is installed into a [`WasiCtx`](crate::WasiCtx) with
+//! [`WasiCtx::set_replay`](crate::WasiCtx::set_replay), which wraps the
+//! context's clocks, randomness, files and directories. When recording, every
+//! nondeterministic result those resources hand to the guest is appended to
+//! the log. When replaying, the same results are read back from the log
+//! instead of from the host, so that an execution can be re-run exactly.
+//!
+//! The log covers the arguments and environment, the system and monotonic
+//! clocks, random bytes, the data read from files, which includes stdin and
+//! anything opened beneath a preopened directory, and the data received from
+//! sockets. Metadata, directory listings and the timing of `poll_oneoff` are
+//! not part of the log, so a replay still needs the same preopened
+//! directories to be present.
+//!
+//! Other WASI implementations, such as the one for components in
+//! `wasmtime-wasi`, can share the log format through the public methods of
+//! [`Replay`].
+//!
+//! If the guest asks for a different kind of result than the log holds next,
+//! or the log is truncated or corrupt, the execution has diverged from the
+//! recording, and the guest traps. Clocks and random number generators can't
+//! report errors themselves, so when they run into one it is held in the log
+//! until the WASI call that used them completes, which then traps; see
+//! [`Replay::check`].
+//!
+//! The log is a text file with one result per line, so that it can be
+//! inspected by hand:
+//!
+//! ```text
+//! args 2 6d61696e2e7761736d 2d76
+//! env 0
+//! monotonic-time 0 0
+//! system-time 1700000000 123456789
+//! random 8f3a0c
+//! read 68656c6c6f0a
+//! read-error 8
+//! recv 0 6f6b
+//! stream-end 6869
+//! ```
+
+use crate::clocks::{WasiMonotonicClock, WasiSystemClock};
+use crate::dir::{OpenResult, ReaddirCursor, ReaddirEntity, WasiDir};
+use crate::file::{
+    Advice, FdFlags, FileType, Filestat, OFlags, RiFlags, RoFlags, SdFlags, SiFlags, WasiFile,
+};
+use crate::{Errno, Error, SystemTimeSpec};
+use cap_rand::RngCore;
+use cap_std::time::{Duration, Instant, SystemTime};
+use std::any::Any;
+use std::io::{BufRead, BufReader, IoSlice, IoSliceMut, Read, SeekFrom, Write};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// A log of the nondeterministic results observed by a guest, which is
+/// either being recorded or replayed.
+///
+/// Clones refer to the same log.
+#[derive(Clone)]
+pub struct Replay(Arc<Mutex<State>>);
+
+struct State {
+    log: Log,
+    /// Whether the log has failed, after which it can't be used any more.
+    failed: bool,
+    /// An error which couldn't be reported when it happened, and is reported
+    /// by the next [`Replay::check`].
+    failure: Option<Error>,
+}
+
+enum Log {
+    Record(Box<dyn Write + Send>),
+    Replay(Box<dyn BufRead + Send>),
+}
+
+/// A single nondeterministic result.
+#[derive(Debug, PartialEq)]
+enum Event {
+    Args(Vec<String>),
+    Env(Vec<String>),
+    SystemTime(Duration),
+    MonotonicTime(Duration),
+    Random(Vec<u8>),
+    Read(Result<Vec<u8>, u16>),
+    Recv(Result<(Vec<u8>, u32), u16>),
+    StreamRead(Result<(Vec<u8>, bool), ()>),
+}
+
+impl Replay {
+    fn new(log: Log) -> Self {
+        Replay(Arc::new(Mutex::new(State {
+            log,
+            failed: false,
+            failure: None,
+        })))
+    }
+
+    /// Creates a log which records results into `log` as they are observed.
+    pub fn record(log: impl Write + Send + 'static) -> Self {
+        Replay::new(Log::Record(Box::new(log)))
+    }
+
+    /// Creates a log which replays the results previously recorded into
+    /// `log`.
+    pub fn replay(log: impl Read + Send + 'static) -> Self {
+        Replay::new(Log::Replay(Box::new(BufReader::new(log))))
+    }
+
+    /// Whether results come from the log rather than from the host.
+    pub fn is_replaying(&self) -> bool {
+        matches!(self.0.lock().unwrap().log, Log::Replay(_))
+    }
+
+    /// Returns the error which a clock or random number generator ran into
+    /// since the last check, if any.
+    ///
+    /// Those can't report errors when they happen, so WASI calls which use
+    /// them should check the log before returning to the guest.
+    pub fn check(&self) -> Result<(), Error> {
+        match self.0.lock().unwrap().failure.take() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    /// Holds `e` until the next [`Replay::check`].
+    fn fail(&self, e: Error) {
+        let mut state = self.0.lock().unwrap();
+        state.failed = true;
+        if state.failure.is_none() {
+            state.failure = Some(e);
+        }
+    }
+
+    /// When recording, appends `event` to the log.
+    fn log(&self, event: &Event) -> Result<(), Error> {
+        let mut state = self.0.lock().unwrap();
+        if state.failed {
+            return Err(failed());
+        }
+        let result = match &mut state.log {
+            Log::Record(out) => writeln!(out, "{}", event.encode())
+                .and_then(|()| out.flush())
+                .map_err(|e| Error::trap(anyhow::Error::new(e).context("writing replay log"))),
+            Log::Replay(_) => Ok(()),
+        };
+        state.failed = result.is_err();
+        result
+    }
+
+    /// When replaying, takes the next event from the log, which must be of
+    /// the kind named `what` and understood by `extract`. Returns `None` when
+    /// recording.
+    fn replayed<T>(
+        &self,
+        what: &str,
+        extract: impl FnOnce(Event) -> Option<T>,
+    ) -> Result<Option<T>, Error> {
+        let mut state = self.0.lock().unwrap();
+        if state.failed {
+            return Err(failed());
+        }
+        let input = match &mut state.log {
+            Log::Record(_) => return Ok(None),
+            Log::Replay(input) => input,
+        };
+        let result = next_event(input.as_mut(), what, extract).map(Some);
+        state.failed = result.is_err();
+        result
+    }
+
+    /// Returns the arguments to give the guest, which are `host` when
+    /// recording.
+    pub fn args(&self, host: Vec<String>) -> Result<Vec<String>, Error> {
+        self.string_list(host, "args", Event::Args, |e| match e {
+            Event::Args(args) => Some(args),
+            _ => None,
+        })
+    }
+
+    /// Returns the environment variables, as `KEY=VALUE`, to give the guest,
+    /// which are `host` when recording.
+    pub fn env(&self, host: Vec<String>) -> Result<Vec<String>, Error> {
+        self.string_list(host, "env", Event::Env, |e| match e {
+            Event::Env(env) => Some(env),
+            _ => None,
+        })
+    }
+
+    fn string_list(
+        &self,
+        host: Vec<String>,
+        what: &str,
+        event: fn(Vec<String>) -> Event,
+        extract: fn(Event) -> Option<Vec<String>>,
+    ) -> Result<Vec<String>, Error> {
+        if let Some(replayed) = self.replayed(what, extract)? {
+            return Ok(replayed);
+        }
+        let event = event(host);
+        self.log(&event)?;
+        Ok(extract(event).unwrap())
+    }
+
+    /// Returns a reading of the system clock as a duration since the Unix
+    /// epoch, which is taken from `host` when recording.
+    ///
+    /// If the log fails, this returns the host's reading and holds the error
+    /// until the next [`Replay::check`].
+    pub fn system_time(&self, host: impl FnOnce() -> Duration) -> Duration {
+        self.time("system-time", Event::SystemTime, host, |e| match e {
+            Event::SystemTime(d) => Some(d),
+            _ => None,
+        })
+    }
+
+    /// Returns a reading of a monotonic clock as a duration since some fixed
+    /// point of the host's choosing, which is taken from `host` when
+    /// recording.
+    ///
+    /// If the log fails, this returns the host's reading and holds the error
+    /// until the next [`Replay::check`].
+    pub fn monotonic_time(&self, host: impl FnOnce() -> Duration) -> Duration {
+        self.time("monotonic-time", Event::MonotonicTime, host, |e| match e {
+            Event::MonotonicTime(d) => Some(d),
+            _ => None,
+        })
+    }
+
+    fn time(
+        &self,
+        what: &str,
+        event: fn(Duration) -> Event,
+        host: impl FnOnce() -> Duration,
+        extract: fn(Event) -> Option<Duration>,
+    ) -> Duration {
+        match self.replayed(what, extract) {
+            Ok(Some(d)) => d,
+            Ok(None) => {
+                let d = host();
+                if let Err(e) = self.log(&event(d)) {
+                    self.fail(e);
+                }
+                d
+            }
+            Err(e) => {
+                self.fail(e);
+                host()
+            }
+        }
+    }
+
+    /// Fills `buf` with random bytes, which come from `host` when recording.
+    ///
+    /// If the log fails, this uses the host's bytes and holds the error until
+    /// the next [`Replay::check`].
+    pub fn fill_bytes(&self, buf: &mut [u8], host: impl FnOnce(&mut [u8])) {
+        let len = buf.len();
+        let replayed = self.replayed("random", |e| match e {
+            Event::Random(bytes) if bytes.len() == len => Some(bytes),
+            _ => None,
+        });
+        match replayed {
+            Ok(Some(bytes)) => buf.copy_from_slice(&bytes),
+            Ok(None) => {
+                host(buf);
+                if let Err(e) = self.log(&Event::Random(buf.to_vec())) {
+                    self.fail(e);
+                }
+            }
+            Err(e) => {
+                self.fail(e);
+                host(buf);
+            }
+        }
+    }
+
+    /// When replaying, returns the result of a read of at most `size` bytes
+    /// from a stream: either the bytes read and whether the end of the stream
+    /// was reached, or `Err(())` if the read failed. Returns `None` when
+    /// recording.
+    pub fn replayed_stream_read(
+        &self,
+        size: usize,
+    ) -> Result<Option<Result<(Vec<u8>, bool), ()>>, Error> {
+        self.replayed("stream-read", |e| match e {
+            Event::StreamRead(Ok((bytes, end))) if bytes.len() <= size => Some(Ok((bytes, end))),
+            Event::StreamRead(Err(())) => Some(Err(())),
+            _ => None,
+        })
+    }
+
+    /// When recording, logs the result of a read from a stream, in the form
+    /// returned by [`Replay::replayed_stream_read`].
+    pub fn record_stream_read(&self, result: Result<(&[u8], bool), ()>) -> Result<(), Error> {
+        self.log(&Event::StreamRead(
+            result.map(|(bytes, end)| (bytes.to_vec(), end)),
+        ))
+    }
+}
+
+fn next_event<T>(
+    input: &mut dyn BufRead,
+    what: &str,
+    extract: impl FnOnce(Event) -> Option<T>,
+) -> Result<T, Error> {
+    let mut line = String::new();
+    let n = input
+        .read_line(&mut line)
+        .map_err(|e| Error::trap(anyhow::Error::new(e).context("reading replay log")))?;
+    if n == 0 {
+        return Err(diverged(what, "the end of the log"));
+    }
+    let line = line.trim_end_matches(&['\r', '\n'][..]);
+    let event = Event::decode(line)
+        .ok_or_else(|| Error::trap(anyhow::anyhow!("malformed replay log entry {line:?}")))?;
+    extract(event).ok_or_else(|| diverged(what, line))
+}
+
+fn diverged(expected: &str, found: &str) -> Error {
+    Error::trap(anyhow::anyhow!(
+        "replay diverged from the log: the guest asked for `{expected}`, but the log has {found:?}"
+    ))
+}
+
+fn failed() -> Error {
+    Error::trap(anyhow::anyhow!(
+        "the replay log can't be used after an earlier failure"
+    ))
+}
+
+impl Event {
+    fn encode(&self) -> String {
+        fn strings(kind: &str, list: &[String]) -> String {
+            let mut s = format!("{kind} {}", list.len());
+            for item in list {
+                s.push(' ');
+                s.push_str(&hex(item.as_bytes()));
+            }
+            s
+        }
+        match self {
+            Event::Args(args) => strings("args", args),
+            Event::Env(env) => strings("env", env),
+            Event::SystemTime(d) => format!("system-time {} {}", d.as_secs(), d.subsec_nanos()),
+            Event::MonotonicTime(d) => {
+                format!("monotonic-time {} {}", d.as_secs(), d.subsec_nanos())
+            }
+            Event::Random(bytes) => format!("random {}", hex(bytes)),
+            Event::Read(Ok(bytes)) => format!("read {}", hex(bytes)),
+            Event::Read(Err(errno)) => format!("read-error {errno}"),
+            Event::Recv(Ok((bytes, flags))) => format!("recv {flags} {}", hex(bytes)),
+            Event::Recv(Err(errno)) => format!("recv-error {errno}"),
+            Event::StreamRead(Ok((bytes, false))) => format!("stream-read {}", hex(bytes)),
+            Event::StreamRead(Ok((bytes, true))) => format!("stream-end {}", hex(bytes)),
+            Event::StreamRead(Err(())) => "stream-error".to_string(),
+        }
+    }
+
+    fn decode(line: &str) -> Option<Event> {
+        fn strings(payload: &str) -> Option<Vec<String>> {
+            let mut parts = payload.split(' ');
+            let len: usize = parts.next()?.parse().ok()?;
+            let list = parts
+                .map(|s| String::from_utf8(unhex(s)?).ok())
+                .collect::<Option<Vec<_>>>()?;
+            if list.len() == len {
+                Some(list)
+            } else {
+                None
+            }
+        }
+        fn duration(payload: &str) -> Option<Duration> {
+            let (secs, nanos) = payload.split_once(' ')?;
+            let nanos: u32 = nanos.parse().ok()?;
+            if nanos >= 1_000_000_000 {
+                return None;
+            }
+            Some(Duration::new(secs.parse().ok()?, nanos))
+        }
+        let (kind, payload) = line.split_once(' ').unwrap_or((line, ""));
+        Some(match kind {
+            "args" => Event::Args(strings(payload)?),
+            "env" => Event::Env(strings(payload)?),
+            "system-time" => Event::SystemTime(duration(payload)?),
+            "monotonic-time" => Event::MonotonicTime(duration(payload)?),
+            "random" => Event::Random(unhex(payload)?),
+            "read" => Event::Read(Ok(unhex(payload)?)),
+            "read-error" => Event::Read(Err(payload.parse().ok()?)),
+            "recv" => {
+                let (flags, bytes) = payload.split_once(' ')?;
+                Event::Recv(Ok((unhex(bytes)?, flags.parse().ok()?)))
+            }
+            "recv-error" => Event::Recv(Err(payload.parse().ok()?)),
+            "stream-read" => Event::StreamRead(Ok((unhex(payload)?, false))),
+            "stream-end" => Event::StreamRead(Ok((unhex(payload)?, true))),
+            "stream-error" if payload.is_empty() => Event::StreamRead(Err(())),
+            _ => return None,
+        })
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn unhex(s: &str) -> Option<Vec<u8>> {
+    if s.len() % 2 != 0 {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// A [`WasiSystemClock`] whose readings are recorded or replayed.
+pub(crate) struct ReplaySystemClock {
+    inner: Box<dyn WasiSystemClock>,
+    replay: Replay,
+}
+
+impl ReplaySystemClock {
+    pub(crate) fn new(inner: Box<dyn WasiSystemClock>, replay: Replay) -> Self {
+        ReplaySystemClock { inner, replay }
+    }
+}
+
+impl WasiSystemClock for ReplaySystemClock {
+    fn resolution(&self) -> Duration {
+        self.inner.resolution()
+    }
+
+    fn now(&self, precision: Duration) -> SystemTime {
+        let epoch = SystemTime::from_std(std::time::SystemTime::UNIX_EPOCH);
+        epoch
+            + self.replay.system_time(|| {
+                // Times before the epoch aren't representable in the log, and
+                // aren't representable as a WASI timestamp either.
+                self.inner
+                    .now(precision)
+                    .duration_since(epoch)
+                    .unwrap_or_default()
+            })
+    }
+}
+
+/// A [`WasiMonotonicClock`] whose readings are recorded or replayed.
+///
+/// Readings are logged relative to the first reading of the underlying
+/// clock, as an `Instant` has no meaning outside of the process it was taken
+/// in.
+pub(crate) struct ReplayMonotonicClock {
+    inner: Box<dyn WasiMonotonicClock>,
+    base: Instant,
+    replay: Replay,
+}
+
+impl ReplayMonotonicClock {
+    pub(crate) fn new(inner: Box<dyn WasiMonotonicClock>, replay: Replay) -> Self {
+        let base = inner.now(inner.resolution());
+        ReplayMonotonicClock {
+            inner,
+            base,
+            replay,
+        }
+    }
+}
+
+impl WasiMonotonicClock for ReplayMonotonicClock {
+    fn resolution(&self) -> Duration {
+        self.inner.resolution()
+    }
+
+    fn now(&self, precision: Duration) -> Instant {
+        self.base
+            + self
+                .replay
+                .monotonic_time(|| self.inner.now(precision).duration_since(self.base))
+    }
+}
+
+/// A random number generator whose output is recorded or replayed.
+pub(crate) struct ReplayRng {
+    inner: Box<dyn RngCore + Send + Sync>,
+    replay: Replay,
+}
+
+impl ReplayRng {
+    pub(crate) fn new(inner: Box<dyn RngCore + Send + Sync>, replay: Replay) -> Self {
+        ReplayRng { inner, replay }
+    }
+}
+
+impl RngCore for ReplayRng {
+    fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0; 4];
+        self.fill_bytes(&mut bytes);
+        u32::from_le_bytes(bytes)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0; 8];
+        self.fill_bytes(&mut bytes);
+        u64::from_le_bytes(bytes)
+    }
+
+    fn fill_bytes(&mut self, buf: &mut [u8]) {
+        let inner = &mut self.inner;
+        self.replay.fill_bytes(buf, |buf| inner.fill_bytes(buf));
+    }
+
+    fn try_fill_bytes(&mut self, buf: &mut [u8]) -> Result<(), cap_rand::Error> {
+        self.fill_bytes(buf);
+        Ok(())
+    }
+}
+
+/// A [`WasiFile`] whose reads are recorded or replayed.
+pub(crate) struct ReplayFile {
+    inner: Box<dyn WasiFile>,
+    replay: Replay,
+}
+
+impl ReplayFile {
+    pub(crate) fn new(inner: Box<dyn WasiFile>, replay: Replay) -> Self {
+        ReplayFile { inner, replay }
+    }
+
+    /// Returns the replayed result of a read into `bufs`, if replaying.
+    fn replayed_read(&self, bufs: &mut [IoSliceMut<'_>]) -> Result<Option<u64>, Error> {
+        let capacity: usize = bufs.iter().map(|b| b.len()).sum();
+        let replayed = self.replay.replayed("read", |e| match e {
+            Event::Read(Ok(bytes)) if bytes.len() <= capacity => Some(Ok(bytes)),
+            Event::Read(Err(errno)) => Some(Err(errno)),
+            _ => None,
+        })?;
+        match replayed {
+            None => Ok(None),
+            Some(Err(errno)) => {
+                let errno = Errno::try_from(errno).map_err(|e| Error::trap(e.into()))?;
+                Err(errno.into())
+            }
+            Some(Ok(bytes)) => {
+                scatter(bufs, &bytes);
+                Ok(Some(bytes.len() as u64))
+            }
+        }
+    }
+
+    /// Logs the result of a read into `bufs`.
+    fn record_read(
+        &self,
+        bufs: &[IoSliceMut<'_>],
+        result: Result<u64, Error>,
+    ) -> Result<u64, Error> {
+        let event = match &result {
+            Ok(n) => Event::Read(Ok(gather(bufs, *n))),
+            Err(e) => match e.downcast_ref() {
+                Some(errno) => Event::Read(Err((*errno).into())),
+                // Traps end the execution, so there's nothing to replay.
+                None => return result,
+            },
+        };
+        self.replay.log(&event)?;
+        result
+    }
+
+    /// Returns the replayed result of receiving into `bufs`, if replaying.
+    fn replayed_recv(&self, bufs: &mut [IoSliceMut<'_>]) -> Result<Option<(u64, RoFlags)>, Error> {
+        let capacity: usize = bufs.iter().map(|b| b.len()).sum();
+        let replayed = self.replay.replayed("recv", |e| match e {
+            Event::Recv(Ok((bytes, flags))) if bytes.len() <= capacity => Some(Ok((bytes, flags))),
+            Event::Recv(Err(errno)) => Some(Err(errno)),
+            _ => None,
+        })?;
+        match replayed {
+            None => Ok(None),
+            Some(Err(errno)) => {
+                let errno = Errno::try_from(errno).map_err(|e| Error::trap(e.into()))?;
+                Err(errno.into())
+            }
+            Some(Ok((bytes, flags))) => {
+                scatter(bufs, &bytes);
+                Ok(Some((
+                    bytes.len() as u64,
+                    RoFlags::from_bits_truncate(flags),
+                )))
+            }
+        }
+    }
+
+    /// Logs the result of receiving into `bufs`.
+    fn record_recv(
+        &self,
+        bufs: &[IoSliceMut<'_>],
+        result: Result<(u64, RoFlags), Error>,
+    ) -> Result<(u64, RoFlags), Error> {
+        let event = match &result {
+            Ok((n, flags)) => Event::Recv(Ok((gather(bufs, *n), flags.bits()))),
+            Err(e) => match e.downcast_ref() {
+                Some(errno) => Event::Recv(Err((*errno).into())),
+                None => return result,
+            },
+        };
+        self.replay.log(&event)?;
+        result
+    }
+}
+
+/// Copies `bytes` into the start of `bufs`, which must be large enough.
+fn scatter(bufs: &mut [IoSliceMut<'_>], mut bytes: &[u8]) {
+    for buf in bufs.iter_mut() {
+        let n = buf.len().min(bytes.len());
+        buf[..n].copy_from_slice(&bytes[..n]);
+        bytes = &bytes[n..];
+    }
+}
+
+/// Copies the first `n` bytes out of `bufs`.
+fn gather(bufs: &[IoSliceMut<'_>], n: u64) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(n as usize);
+    let mut rest = n as usize;
+    for buf in bufs {
+        let n = buf.len().min(rest);
+        bytes.extend_from_slice(&buf[..n]);
+        rest -= n;
+    }
+    bytes
+}
+
+#[wiggle::async_trait]
+impl WasiFile for ReplayFile {
+    fn as_any(&self) -> &dyn Any {
+        // Schedulers downcast files to find the host resource to wait on.
+        self.inner.as_any()
+    }
+    async fn get_filetype(&self) -> Result<FileType, Error> {
+        self.inner.get_filetype().await
+    }
+    #[cfg(unix)]
+    fn pollable(&self) -> Option<rustix::fd::BorrowedFd> {
+        self.inner.pollable()
+    }
+    #[cfg(windows)]
+    fn pollable(&self) -> Option<io_extras::os::windows::RawHandleOrSocket> {
+        self.inner.pollable()
+    }
+    fn isatty(&self) -> bool {
+        self.inner.isatty()
+    }
+    async fn sock_accept(&self, fdflags: FdFlags) -> Result<Box<dyn WasiFile>, Error> {
+        let file = self.inner.sock_accept(fdflags).await?;
+        Ok(Box::new(ReplayFile::new(file, self.replay.clone())))
+    }
+    async fn sock_recv<'a>(
+        &self,
+        ri_data: &mut [IoSliceMut<'a>],
+        ri_flags: RiFlags,
+    ) -> Result<(u64, RoFlags), Error> {
+        if let Some(result) = self.replayed_recv(ri_data)? {
+            return Ok(result);
+        }
+        let result = self.inner.sock_recv(ri_data, ri_flags).await;
+        self.record_recv(ri_data, result)
+    }
+    async fn sock_send<'a>(
+        &self,
+        si_data: &[IoSlice<'a>],
+        si_flags: SiFlags,
+    ) -> Result<u64, Error> {
+        self.inner.sock_send(si_data, si_flags).await
+    }
+    async fn sock_shutdown(&self, how: SdFlags) -> Result<(), Error> {
+        self.inner.sock_shutdown(how).await
+    }
+    async fn datasync(&self) -> Result<(), Error> {
+        self.inner.datasync().await
+    }
+    async fn sync(&self) -> Result<(), Error> {
+        self.inner.sync().await
+    }
+    async fn get_fdflags(&self) -> Result<FdFlags, Error> {
+        self.inner.get_fdflags().await
+    }
+    async fn set_fdflags(&mut self, flags: FdFlags) -> Result<(), Error> {
+        self.inner.set_fdflags(flags).await
+    }
+    async fn get_filestat(&self) -> Result<Filestat, Error> {
+        self.inner.get_filestat().await
+    }
+    async fn set_filestat_size(&self, size: u64) -> Result<(), Error> {
+        self.inner.set_filestat_size(size).await
+    }
+    async fn advise(&self, offset: u64, len: u64, advice: Advice) -> Result<(), Error> {
+        self.inner.advise(offset, len, advice).await
+    }
+    async fn set_times(
+        &self,
+        atime: Option<SystemTimeSpec>,
+        mtime: Option<SystemTimeSpec>,
+    ) -> Result<(), Error> {
+        self.inner.set_times(atime, mtime).await
+    }
+    async fn read_vectored<'a>(&self, bufs: &mut [IoSliceMut<'a>]) -> Result<u64, Error> {
+        if let Some(n) = self.replayed_read(bufs)? {
+            // Keep the position of the underlying file in step with the
+            // guest's view of it. Streams such as stdin can't seek, and
+            // don't have a position to keep in step.
+            let _ = self.inner.seek(SeekFrom::Current(n as i64)).await;
+            return Ok(n);
+        }
+        let result = self.inner.read_vectored(bufs).await;
+        self.record_read(bufs, result)
+    }
+    async fn read_vectored_at<'a>(
+        &self,
+        bufs: &mut [IoSliceMut<'a>],
+        offset: u64,
+    ) -> Result<u64, Error> {
+        if let Some(n) = self.replayed_read(bufs)? {
+            return Ok(n);
+        }
+        let result = self.inner.read_vectored_at(bufs, offset).await;
+        self.record_read(bufs, result)
+    }
+    async fn write_vectored<'a>(&self, bufs: &[IoSlice<'a>]) -> Result<u64, Error> {
+        self.inner.write_vectored(bufs).await
+    }
+    async fn write_vectored_at<'a>(&self, bufs: &[IoSlice<'a>], offset: u64) -> Result<u64, Error> {
+        self.inner.write_vectored_at(bufs, offset).await
+    }
+    async fn seek(&self, pos: SeekFrom) -> Result<u64, Error> {
+        self.inner.seek(pos).await
+    }
+    async fn peek(&self, buf: &mut [u8]) -> Result<u64, Error> {
+        self.inner.peek(buf).await
+    }
+    fn num_ready_bytes(&self) -> Result<u64, Error> {
+        self.inner.num_ready_bytes()
+    }
+    async fn readable(&self) -> Result<(), Error> {
+        self.inner.readable().await
+    }
+    async fn writable(&self) -> Result<(), Error> {
+        self.inner.writable().await
+    }
+}
+
+/// A [`WasiDir`] whose files have their reads recorded or replayed.
+pub(crate) struct ReplayDir {
+    inner: Box<dyn WasiDir>,
+    replay: Replay,
+}
+
+impl ReplayDir {
+    pub(crate) fn new(inner: Box<dyn WasiDir>, replay: Replay) -> Self {
+        ReplayDir { inner, replay }
+    }
+}
+
+#[wiggle::async_trait]
+impl WasiDir for ReplayDir {
+    fn as_any(&self) -> &dyn Any {
+        // Directories downcast their peers in `rename` and `hard_link`.
+        self.inner.as_any()
+    }
+    async fn open_file(
+        &self,
+        symlink_follow: bool,
+        path: &str,
+        oflags: OFlags,
+        read: bool,
+        write: bool,
+        fdflags: FdFlags,
+    ) -> Result<OpenResult, Error> {
+        let opened = self
+            .inner
+            .open_file(symlink_follow, path, oflags, read, write, fdflags)
+            .await?;
+        Ok(match opened {
+            OpenResult::File(file) => {
+                OpenResult::File(Box::new(ReplayFile::new(file, self.replay.clone())))
+            }
+            OpenResult::Dir(dir) => {
+                OpenResult::Dir(Box::new(ReplayDir::new(dir, self.replay.clone())))
+            }
+        })
+    }
+    async fn create_dir(&self, path: &str) -> Result<(), Error> {
+        self.inner.create_dir(path).await
+    }
+    async fn readdir(
+        &self,
+        cursor: ReaddirCursor,
+    ) -> Result<Box<dyn Iterator<Item = Result<ReaddirEntity, Error>> + Send>, Error> {
+        self.inner.readdir(cursor).await
+    }
+    async fn symlink(&self, old_path: &str, new_path: &str) -> Result<(), Error> {
+        self.inner.symlink(old_path, new_path).await
+    }
+    async fn remove_dir(&self, path: &str) -> Result<(), Error> {
+        self.inner.remove_dir(path).await
+    }
+    async fn unlink_file(&self, path: &str) -> Result<(), Error> {
+        self.inner.unlink_file(path).await
+    }
+    async fn read_link(&self, path: &str) -> Result<PathBuf, Error> {
+        self.inner.read_link(path).await
+    }
+    async fn get_filestat(&self) -> Result<Filestat, Error> {
+        self.inner.get_filestat().await
+    }
+    async fn get_path_filestat(
+        &self,
+        path: &str,
+        follow_symlinks: bool,
+    ) -> Result<Filestat, Error> {
+        self.inner.get_path_filestat(path, follow_symlinks).await
+    }
+    async fn rename(
+        &self,
+        path: &str,
+        dest_dir: &dyn WasiDir,
+        dest_path: &str,
+    ) -> Result<(), Error> {
+        self.inner.rename(path, dest_dir, dest_path).await
+    }
+    async fn hard_link(
+        &self,
+        path: &str,
+        target_dir: &dyn WasiDir,
+        target_path: &str,
+    ) -> Result<(), Error> {
+        self.inner.hard_link(path, target_dir, target_path).await
+    }
+    async fn set_times(
+        &self,
+        path: &str,
+        atime: Option<SystemTimeSpec>,
+        mtime: Option<SystemTimeSpec>,
+        follow_symlinks: bool,
+    ) -> Result<(), Error> {
+        self.inner
+            .set_times(path, atime, mtime, follow_symlinks)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io;
+
+    #[test]
+    fn events_round_trip() {
+        let events = [
+            Event::Args(vec!["main.wasm".to_string(), String::new()]),
+            Event::Env(vec![]),
+            Event::SystemTime(Duration::new(1_700_000_000, 123_456_789)),
+            Event::MonotonicTime(Duration::new(0, 1)),
+            Event::Random(vec![0x8f, 0x3a, 0x0c]),
+            Event::Read(Ok(b"hello\n".to_vec())),
+            Event::Read(Ok(vec![])),
+            Event::Read(Err(8)),
+            Event::Recv(Ok((b"ok".to_vec(), 1))),
+            Event::Recv(Ok((vec![], 0))),
+            Event::Recv(Err(6)),
+            Event::StreamRead(Ok((b"hi".to_vec(), true))),
+            Event::StreamRead(Ok((vec![], false))),
+            Event::StreamRead(Err(())),
+        ];
+        for event in events {
+            assert_eq!(Event::decode(&event.encode()), Some(event));
+        }
+    }
+
+    #[test]
+    fn replay_reproduces_recording() {
+        #[derive(Clone, Default)]
+        struct Shared(Arc<Mutex<Vec<u8>>>);
+        impl Write for Shared {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.0.lock().unwrap().write(buf)
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let log = Shared::default();
+        let recording = Replay::record(log.clone());
+        let mut rng = ReplayRng::new(
+            Box::new(crate::random::Deterministic::new(vec![1, 2, 3])),
+            recording.clone(),
+        );
+        let recorded = (rng.next_u64(), rng.next_u32());
+        assert_eq!(recording.args(vec!["a".to_string()]).unwrap(), ["a"]);
+
+        let log = log.0.lock().unwrap().clone();
+        let replaying = Replay::replay(io::Cursor::new(log));
+        assert!(replaying.is_replaying());
+        let mut rng = ReplayRng::new(
+            Box::new(crate::random::Deterministic::new(vec![0])),
+            replaying.clone(),
+        );
+        assert_eq!((rng.next_u64(), rng.next_u32()), recorded);
+        assert_eq!(replaying.args(vec!["b".to_string()]).unwrap(), ["a"]);
+        assert!(replaying.args(vec![]).is_err());
+    }
+
+    #[test]
+    fn divergence_is_held_until_checked() {
+        let replaying = Replay::replay(io::Cursor::new(b"random 0102\n".to_vec()));
+        let mut rng = ReplayRng::new(
+            Box::new(crate::random::Deterministic::new(vec![7])),
+            replaying.clone(),
+        );
+
+        // The log holds two random bytes rather than four, so the host's
+        // bytes are used and the divergence is reported by the next check.
+        assert_eq!(rng.next_u32(), 0x07070707);
+        assert!(replaying.check().is_err());
+        assert!(replaying.check().is_ok());
+
+        // The log can't be used after it has diverged.
+        let mut buf = [0; 2];
+        rng.fill_bytes(&mut buf);
+        assert_eq!(buf, [7, 7]);
+        assert!(replaying.check().is_err());
+        assert!(replaying.args(vec![]).is_err());
+    }
+}
@@ -1009,6 +1009,7 @@ impl wasi_unstable::WasiUnstable for WasiCtx {
         }
 
         self.sched.poll_oneoff(&mut poll).await?;
+        self.check_replay()?;
 
         let results = poll.results();
         let num_results = results.len();
@@ -86,6 +86,7 @@ impl wasi_snapshot_preview1::WasiSnapshotPreview1 for WasiCtx {
         match id {
             types::Clockid::Realtime => {
                 let now = self.clocks.system()?.now(precision).into_std();
+                self.check_replay()?;
                 let d = now
                     .duration_since(std::time::SystemTime::UNIX_EPOCH)
                     .map_err(|_| {
@@ -96,6 +97,7 @@ impl wasi_snapshot_preview1::WasiSnapshotPreview1 for WasiCtx {
             types::Clockid::Monotonic => {
                 let clock = self.clocks.monotonic()?;
                 let now = clock.abs_clock.now(precision);
+                self.check_replay()?;
                 let d = now.duration_since(clock.creation_time);
                 Ok(d.as_nanos().try_into()?)
             }
@@ -982,6 +984,7 @@ impl wasi_snapshot_preview1::WasiSnapshotPreview1 for WasiCtx {
         }
 
         self.sched.poll_oneoff(&mut poll).await?;
+        self.check_replay()?;
 
         let results = poll.results();
         let num_results = results.len();
@@ -1102,6 +1105,7 @@ impl wasi_snapshot_preview1::WasiSnapshotPreview1 for WasiCtx {
             let mem = &mut buf.as_slice_mut()?.unwrap();
             self.random.lock().unwrap().try_fill_bytes(mem)?;
         }
+        self.check_replay()?;
         Ok(())
     }
 
@@ -34,6 +34,10 @@ impl StringArray {
         Ok(())
     }
 
+    pub(crate) fn into_vec(self) -> Vec<String> {
+        self.elems
+    }
+
     pub fn number_elements(&self) -> u32 {
         self.elems.len() as u32
     }
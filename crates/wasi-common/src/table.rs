@@ -55,6 +55,11 @@ impl Table {
         self.0.read().unwrap().map.contains_key(&key)
     }
 
+    /// The indices of all resources in the table.
+    pub(crate) fn keys(&self) -> Vec<u32> {
+        self.0.read().unwrap().map.keys().copied().collect()
+    }
+
     /// Check if the resource at a given index can be downcast to a given type.
     /// Note: this will always fail if the resource is already borrowed.
     pub fn is<T: Any + Sized>(&self, key: u32) -> bool {
@@ -2,8 +2,10 @@
 //! this crate. The `Box<dyn ...>` types returned by these interfaces allow
 //! implementations to maintain backend-specific state between calls.
 
+mod onnx;
 mod openvino;
 
+use self::onnx::OnnxBackend;
 use self::openvino::OpenvinoBackend;
 use crate::wit::types::{ExecutionTarget, Tensor};
 use crate::{ExecutionContext, Graph};
@@ -12,7 +14,10 @@ use wiggle::GuestError;
 
 /// Return a list of all available backend frameworks.
 pub fn list() -> Vec<(BackendKind, Box<dyn Backend>)> {
-    vec![(BackendKind::OpenVINO, Box::new(OpenvinoBackend::default()))]
+    vec![
+        (BackendKind::OpenVINO, Box::new(OpenvinoBackend::default())),
+        (BackendKind::Onnx, Box::new(OnnxBackend::default())),
+    ]
 }
 
 /// A [Backend] contains the necessary state to load [BackendGraph]s.
@@ -24,7 +29,7 @@ pub trait Backend: Send + Sync {
 /// A [BackendGraph] can create [BackendExecutionContext]s; this is the backing
 /// implementation for a [crate::witx::types::Graph].
 pub trait BackendGraph: Send + Sync {
-    fn init_execution_context(&mut self) -> Result<ExecutionContext, BackendError>;
+    fn init_execution_context(&self) -> Result<ExecutionContext, BackendError>;
 }
 
 /// A [BackendExecutionContext] performs the actual inference; this is the
@@ -52,4 +57,5 @@ pub enum BackendError {
 #[derive(Hash, PartialEq, Debug, Eq, Clone, Copy)]
 pub enum BackendKind {
     OpenVINO,
+    Onnx,
 }
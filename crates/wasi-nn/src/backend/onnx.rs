@@ -0,0 +1,231 @@
+//! Implements a `wasi-nn` [`Backend`] for ONNX models using `tract`, a
+//! pure-Rust inference engine which runs on the CPU and needs no system
+//! libraries.
+
+use super::{Backend, BackendError, BackendExecutionContext, BackendGraph};
+use crate::wit::types::{ExecutionTarget, Tensor, TensorType};
+use crate::{ExecutionContext, Graph};
+use std::sync::Arc;
+use tract_onnx::prelude::{
+    f16, Datum, DatumType, Framework, InferenceFact, InferenceModel, InferenceModelExt, IntoTensor,
+    TValue, TypedModel, TypedRunnableModel,
+};
+
+#[derive(Default)]
+pub(crate) struct OnnxBackend;
+
+impl Backend for OnnxBackend {
+    fn name(&self) -> &str {
+        "onnx"
+    }
+
+    fn load(&mut self, builders: &[&[u8]], target: ExecutionTarget) -> Result<Graph, BackendError> {
+        if builders.len() != 1 {
+            return Err(BackendError::InvalidNumberOfBuilders(1, builders.len()));
+        }
+        if target != ExecutionTarget::Cpu {
+            return Err(BackendError::BackendAccess(anyhow::anyhow!(
+                "the ONNX backend only supports CPU execution, not {target:?}"
+            )));
+        }
+
+        let model = tract_onnx::onnx().model_for_read(&mut &builders[0][..])?;
+        let box_: Box<dyn BackendGraph> = Box::new(OnnxGraph(Arc::new(model)));
+        Ok(box_.into())
+    }
+}
+
+/// An ONNX model whose input shapes may not be known until inference.
+struct OnnxGraph(Arc<InferenceModel>);
+
+impl BackendGraph for OnnxGraph {
+    fn init_execution_context(&self) -> Result<ExecutionContext, BackendError> {
+        let inputs = vec![None; self.0.inputs.len()];
+        let box_: Box<dyn BackendExecutionContext> = Box::new(OnnxExecutionContext {
+            model: self.0.clone(),
+            inputs,
+            plan: None,
+            outputs: vec![],
+        });
+        Ok(box_.into())
+    }
+}
+
+type Plan = TypedRunnableModel<TypedModel>;
+
+struct OnnxExecutionContext {
+    model: Arc<InferenceModel>,
+    inputs: Vec<Option<tract_onnx::prelude::Tensor>>,
+    /// The model optimized for the input shapes it was last run with; the
+    /// plan is only rebuilt when those shapes change.
+    plan: Option<(Vec<InferenceFact>, Arc<Plan>)>,
+    outputs: Vec<tract_onnx::prelude::Tensor>,
+}
+
+impl OnnxExecutionContext {
+    fn plan(&mut self, facts: Vec<InferenceFact>) -> Result<Arc<Plan>, BackendError> {
+        if let Some((planned, plan)) = &self.plan {
+            if *planned == facts {
+                return Ok(plan.clone());
+            }
+        }
+        let mut model = (*self.model).clone();
+        for (i, fact) in facts.iter().enumerate() {
+            model.set_input_fact(i, fact.clone())?;
+        }
+        let plan = Arc::new(model.into_optimized()?.into_runnable()?);
+        self.plan = Some((facts, plan.clone()));
+        Ok(plan)
+    }
+}
+
+impl BackendExecutionContext for OnnxExecutionContext {
+    fn set_input(&mut self, index: u32, tensor: &Tensor) -> Result<(), BackendError> {
+        let slot = self
+            .inputs
+            .get_mut(index as usize)
+            .ok_or_else(|| anyhow::anyhow!("the model has no input at index {index}"))?;
+        let shape = tensor
+            .dimensions
+            .iter()
+            .map(|&d| d as usize)
+            .collect::<Vec<_>>();
+        let data = &tensor.data;
+        let value = match tensor.tensor_type {
+            TensorType::Fp16 => {
+                from_le_bytes(&shape, data, |b| f16::from_bits(u16::from_le_bytes(b)))
+            }
+            TensorType::Fp32 => from_le_bytes(&shape, data, f32::from_le_bytes),
+            TensorType::U8 => from_le_bytes(&shape, data, u8::from_le_bytes),
+            TensorType::I32 => from_le_bytes(&shape, data, i32::from_le_bytes),
+            TensorType::Bf16 => {
+                return Err(BackendError::BackendAccess(anyhow::anyhow!(
+                    "the ONNX backend does not support bf16 tensors"
+                )))
+            }
+        }?;
+        *slot = Some(value);
+        Ok(())
+    }
+
+    fn compute(&mut self) -> Result<(), BackendError> {
+        let inputs = self
+            .inputs
+            .iter()
+            .enumerate()
+            .map(|(i, input)| {
+                input
+                    .clone()
+                    .ok_or_else(|| anyhow::anyhow!("no tensor was set for input {i}"))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let facts = inputs
+            .iter()
+            .map(|t| InferenceFact::dt_shape(t.datum_type(), t.shape()))
+            .collect();
+        let plan = self.plan(facts)?;
+        let outputs = plan.run(inputs.into_iter().map(TValue::from).collect())?;
+        self.outputs = outputs.into_iter().map(|v| v.into_tensor()).collect();
+        Ok(())
+    }
+
+    fn get_output(&mut self, index: u32, destination: &mut [u8]) -> Result<u32, BackendError> {
+        let output = self.outputs.get(index as usize).ok_or_else(|| {
+            anyhow::anyhow!("no output at index {index}; has `compute` been called?")
+        })?;
+        let bytes = match output.datum_type() {
+            DatumType::F16 => {
+                let values = output.as_slice::<f16>()?;
+                to_le_bytes(values, |v| v.to_bits().to_le_bytes())
+            }
+            DatumType::F32 => to_le_bytes(output.as_slice::<f32>()?, f32::to_le_bytes),
+            DatumType::U8 => to_le_bytes(output.as_slice::<u8>()?, u8::to_le_bytes),
+            DatumType::I32 => to_le_bytes(output.as_slice::<i32>()?, i32::to_le_bytes),
+            other => {
+                return Err(BackendError::BackendAccess(anyhow::anyhow!(
+                    "output {index} has type {other:?}, which wasi-nn cannot represent"
+                )))
+            }
+        };
+        if bytes.len() > destination.len() {
+            return Err(BackendError::NotEnoughMemory(bytes.len()));
+        }
+
+        // Copy the tensor data into the destination buffer.
+        destination[..bytes.len()].copy_from_slice(&bytes);
+        Ok(bytes.len() as u32)
+    }
+}
+
+/// Decode a tensor of `shape` from the little-endian `data` passed by the
+/// guest.
+fn from_le_bytes<T: Datum + Copy, const N: usize>(
+    shape: &[usize],
+    data: &[u8],
+    decode: impl Fn([u8; N]) -> T,
+) -> anyhow::Result<tract_onnx::prelude::Tensor> {
+    if data.len() % N != 0 {
+        anyhow::bail!("tensor data is not a whole number of {N}-byte elements");
+    }
+    let values = data
+        .chunks_exact(N)
+        .map(|chunk| decode(chunk.try_into().unwrap()))
+        .collect::<Vec<_>>();
+    tract_onnx::prelude::Tensor::from_shape(shape, &values)
+}
+
+/// Encode tensor `values` as the little-endian bytes expected by the guest.
+fn to_le_bytes<T: Copy, const N: usize>(values: &[T], encode: impl Fn(T) -> [u8; N]) -> Vec<u8> {
+    values.iter().flat_map(|&v| encode(v)).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// An ONNX model, for opset 13, whose graph applies `Relu` to a `1x4`
+    /// `f32` input `x` to produce the output `y`.
+    const RELU: &[u8] = include_bytes!("../../tests/fixtures/relu.onnx");
+
+    fn f32_tensor(dimensions: Vec<u32>, values: &[f32]) -> Tensor {
+        Tensor {
+            dimensions,
+            tensor_type: TensorType::Fp32,
+            data: to_le_bytes(values, f32::to_le_bytes),
+        }
+    }
+
+    #[test]
+    fn load_and_compute() {
+        let mut backend = OnnxBackend::default();
+        let graph = backend.load(&[RELU], ExecutionTarget::Cpu).unwrap();
+        let mut context = graph.init_execution_context().unwrap();
+        let input = f32_tensor(vec![1, 4], &[-1.5, 0.0, 2.0, -0.25]);
+        context.set_input(0, &input).unwrap();
+        context.compute().unwrap();
+
+        let mut output = [0; 16];
+        assert_eq!(context.get_output(0, &mut output).unwrap(), 16);
+        let output = output
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(output, [0.0, 0.0, 2.0, 0.0]);
+        assert!(matches!(
+            context.get_output(0, &mut [0; 8]),
+            Err(BackendError::NotEnoughMemory(16))
+        ));
+        assert!(context.get_output(1, &mut [0; 16]).is_err());
+    }
+
+    #[test]
+    fn rejects_unsupported_loads() {
+        let mut backend = OnnxBackend::default();
+        assert!(backend.load(&[RELU], ExecutionTarget::Gpu).is_err());
+        assert!(matches!(
+            backend.load(&[RELU, RELU], ExecutionTarget::Cpu),
+            Err(BackendError::InvalidNumberOfBuilders(1, 2))
+        ));
+        assert!(backend.load(&[&RELU[1..]], ExecutionTarget::Cpu).is_err());
+    }
+}
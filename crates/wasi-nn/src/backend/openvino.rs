@@ -4,7 +4,7 @@ use super::{Backend, BackendError, BackendExecutionContext, BackendGraph};
 use crate::wit::types::{ExecutionTarget, Tensor, TensorType};
 use crate::{ExecutionContext, Graph};
 use openvino::{InferenceError, Layout, Precision, SetupError, TensorDesc};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 #[derive(Default)]
 pub(crate) struct OpenvinoBackend(Option<openvino::Core>);
@@ -51,20 +51,25 @@ impl Backend for OpenvinoBackend {
 
         let exec_network =
             core.load_network(&cnn_network, map_execution_target_to_string(target))?;
-        let box_: Box<dyn BackendGraph> =
-            Box::new(OpenvinoGraph(Arc::new(cnn_network), exec_network));
+        let box_: Box<dyn BackendGraph> = Box::new(OpenvinoGraph(
+            Arc::new(cnn_network),
+            Mutex::new(exec_network),
+        ));
         Ok(box_.into())
     }
 }
 
-struct OpenvinoGraph(Arc<openvino::CNNNetwork>, openvino::ExecutableNetwork);
+struct OpenvinoGraph(
+    Arc<openvino::CNNNetwork>,
+    Mutex<openvino::ExecutableNetwork>,
+);
 
 unsafe impl Send for OpenvinoGraph {}
 unsafe impl Sync for OpenvinoGraph {}
 
 impl BackendGraph for OpenvinoGraph {
-    fn init_execution_context(&mut self) -> Result<ExecutionContext, BackendError> {
-        let infer_request = self.1.create_infer_request()?;
+    fn init_execution_context(&self) -> Result<ExecutionContext, BackendError> {
+        let infer_request = self.1.lock().unwrap().create_infer_request()?;
         let box_: Box<dyn BackendExecutionContext> =
             Box::new(OpenvinoExecutionContext(self.0.clone(), infer_request));
         Ok(box_.into())
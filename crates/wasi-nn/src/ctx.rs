@@ -2,7 +2,7 @@
 
 use crate::backend::{self, Backend, BackendError, BackendKind};
 use crate::wit::types::GraphEncoding;
-use crate::{ExecutionContext, Graph};
+use crate::{ExecutionContext, Graph, GraphRegistry};
 use std::{collections::HashMap, hash::Hash};
 use thiserror::Error;
 use wiggle::GuestError;
@@ -14,6 +14,7 @@ type GraphExecutionContextId = u32;
 /// Capture the state necessary for calling into the backend ML libraries.
 pub struct WasiNnCtx {
     pub(crate) backends: Backends,
+    pub(crate) registry: GraphRegistry,
     pub(crate) graphs: Table<GraphId, Graph>,
     pub(crate) executions: Table<GraphExecutionContextId, ExecutionContext>,
 }
@@ -23,10 +24,27 @@ impl WasiNnCtx {
     pub fn new(backends: Backends) -> Self {
         Self {
             backends,
+            registry: GraphRegistry::new(),
             graphs: Table::default(),
             executions: Table::default(),
         }
     }
+
+    /// Make the graphs preloaded in `registry` available to `load_by_name`.
+    pub fn with_registry(mut self, registry: GraphRegistry) -> Self {
+        self.registry = registry;
+        self
+    }
+
+    /// Add the graph registered as `name` to this context's graphs.
+    pub(crate) fn load_registered(&mut self, name: &str) -> WasiNnResult<GraphId> {
+        let graph = self
+            .registry
+            .get(name)
+            .ok_or_else(|| UsageError::NotFound(name.to_string()))?
+            .clone();
+        Ok(self.graphs.insert(graph))
+    }
 }
 impl Default for WasiNnCtx {
     fn default() -> Self {
@@ -49,7 +67,7 @@ pub enum WasiNnError {
 pub enum UsageError {
     #[error("Invalid context; has the load function been called?")]
     InvalidContext,
-    #[error("Only OpenVINO's IR is currently supported, passed encoding: {0:?}")]
+    #[error("No backend supports the passed encoding: {0:?}")]
     InvalidEncoding(GraphEncoding),
     #[error("OpenVINO expects only two buffers (i.e. [ir, weights]), passed: {0}")]
     InvalidNumberOfBuilders(u32),
@@ -1,15 +1,23 @@
 mod backend;
 mod ctx;
+mod registry;
 
 pub use ctx::WasiNnCtx;
+pub use registry::GraphRegistry;
 pub mod wit;
 pub mod witx;
 
+use std::sync::Arc;
+
 /// A backend-defined graph (i.e., ML model).
-pub struct Graph(Box<dyn backend::BackendGraph>);
+///
+/// Graphs are shared, rather than copied, between the contexts they are
+/// loaded into; see [`GraphRegistry`].
+#[derive(Clone)]
+pub struct Graph(Arc<dyn backend::BackendGraph>);
 impl From<Box<dyn backend::BackendGraph>> for Graph {
     fn from(value: Box<dyn backend::BackendGraph>) -> Self {
-        Self(value)
+        Self(value.into())
     }
 }
 impl std::ops::Deref for Graph {
@@ -18,11 +26,6 @@ impl std::ops::Deref for Graph {
         self.0.as_ref()
     }
 }
-impl std::ops::DerefMut for Graph {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        self.0.as_mut()
-    }
-}
 
 /// A backend-defined execution context.
 pub struct ExecutionContext(Box<dyn backend::BackendExecutionContext>);
@@ -0,0 +1,138 @@
+//! Implements graphs preloaded by the host: [GraphRegistry].
+
+use crate::backend::{self, BackendKind};
+use crate::wit::types::ExecutionTarget;
+use crate::Graph;
+use anyhow::{bail, Context};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// A set of named graphs, loaded ahead of time by the host, which guests can
+/// retrieve with `load_by_name` instead of passing the model bytes to `load`.
+///
+/// Loading a large model can be expensive, so a registry loads each model
+/// once. Cloning the registry into many [`WasiNnCtx`](crate::WasiNnCtx)s
+/// shares the loaded graphs between them.
+#[derive(Clone, Default)]
+pub struct GraphRegistry(HashMap<String, Graph>);
+
+impl GraphRegistry {
+    /// Make an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the model at `path` for execution on the CPU and register it as
+    /// `name`, replacing any graph previously registered under that name.
+    ///
+    /// The backend used is determined by the files found at `path`:
+    /// - an ONNX model is either a `*.onnx` file or a directory containing a
+    ///   `model.onnx` file;
+    /// - an OpenVINO model is a directory containing `model.xml` and
+    ///   `model.bin` files.
+    pub fn load(&mut self, name: impl Into<String>, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let (kind, files) = find_model_files(path)?;
+        let builders = files
+            .iter()
+            .map(|f| std::fs::read(f).with_context(|| format!("failed to read {}", f.display())))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let builders = builders.iter().map(|b| b.as_slice()).collect::<Vec<_>>();
+
+        let mut backends = backend::list();
+        let (_, backend) = backends
+            .iter_mut()
+            .find(|(k, _)| *k == kind)
+            .expect("all backend kinds are listed");
+        let graph = backend
+            .load(&builders, ExecutionTarget::Cpu)
+            .with_context(|| format!("failed to load model {}", path.display()))?;
+        self.0.insert(name.into(), graph);
+        Ok(())
+    }
+
+    /// Retrieve the graph registered as `name`.
+    pub(crate) fn get(&self, name: &str) -> Option<&Graph> {
+        self.0.get(name)
+    }
+}
+
+/// Determine which backend can run the model at `path`, and the files to
+/// pass to it in the order it expects.
+fn find_model_files(path: &Path) -> anyhow::Result<(BackendKind, Vec<PathBuf>)> {
+    if path.is_file() {
+        if path.extension().map_or(false, |e| e == "onnx") {
+            return Ok((BackendKind::Onnx, vec![path.to_path_buf()]));
+        }
+        bail!("unrecognized model file {}", path.display());
+    }
+
+    let onnx = path.join("model.onnx");
+    if onnx.is_file() {
+        return Ok((BackendKind::Onnx, vec![onnx]));
+    }
+    let xml = path.join("model.xml");
+    let weights = path.join("model.bin");
+    if xml.is_file() && weights.is_file() {
+        return Ok((BackendKind::OpenVINO, vec![xml, weights]));
+    }
+    bail!(
+        "no model found at {}; expected a `model.onnx` file, or `model.xml` and `model.bin` files",
+        path.display()
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::wit::types::{Tensor, TensorType};
+
+    const RELU: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/relu.onnx");
+
+    #[test]
+    fn load_and_get() {
+        let mut registry = GraphRegistry::new();
+        registry.load("relu", RELU).unwrap();
+        assert!(registry.get("other").is_none());
+
+        // Clones share the graphs loaded by the original registry.
+        let clone = registry.clone();
+        let graph = clone.get("relu").unwrap();
+        let mut context = graph.init_execution_context().unwrap();
+        let input = Tensor {
+            dimensions: vec![1, 4],
+            tensor_type: TensorType::Fp32,
+            data: [-1.0f32, 1.0, -2.0, 2.0]
+                .iter()
+                .flat_map(|v| v.to_le_bytes())
+                .collect(),
+        };
+        context.set_input(0, &input).unwrap();
+        context.compute().unwrap();
+        let mut output = [0; 16];
+        assert_eq!(context.get_output(0, &mut output).unwrap(), 16);
+        assert_eq!(output[4..8], 1.0f32.to_le_bytes());
+        assert_eq!(output[12..], 2.0f32.to_le_bytes());
+    }
+
+    #[test]
+    fn find_models() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let dir = tmp.path();
+
+        assert!(find_model_files(dir).is_err());
+        let mut registry = GraphRegistry::new();
+        assert!(registry.load("empty", dir).is_err());
+
+        let unrecognized = dir.join("model.txt");
+        std::fs::write(&unrecognized, b"").unwrap();
+        assert!(find_model_files(&unrecognized).is_err());
+
+        std::fs::copy(RELU, dir.join("model.onnx")).unwrap();
+        let (kind, files) = find_model_files(dir).unwrap();
+        assert_eq!(kind, BackendKind::Onnx);
+        assert_eq!(files, [dir.join("model.onnx")]);
+        registry.load("relu", dir).unwrap();
+        assert!(registry.get("relu").is_some());
+    }
+}
@@ -51,11 +51,13 @@ impl gen::graph::Host for WasiNnCtx {
         Ok(Ok(graph_id))
     }
 
+    /// Load a graph preloaded by the host under `name`.
     fn load_by_name(
         &mut self,
-        _name: String,
+        name: String,
     ) -> wasmtime::Result<Result<gen::graph::Graph, gen::errors::Error>> {
-        todo!()
+        let graph_id = self.load_registered(&name)?;
+        Ok(Ok(graph_id))
     }
 }
 
@@ -133,6 +135,7 @@ impl TryFrom<gen::graph::GraphEncoding> for crate::backend::BackendKind {
     fn try_from(value: gen::graph::GraphEncoding) -> Result<Self, Self::Error> {
         match value {
             gen::graph::GraphEncoding::Openvino => Ok(crate::backend::BackendKind::OpenVINO),
+            gen::graph::GraphEncoding::Onnx => Ok(crate::backend::BackendKind::Onnx),
             _ => Err(UsageError::InvalidEncoding(value.into())),
         }
     }
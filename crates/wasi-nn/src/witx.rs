@@ -78,8 +78,10 @@ impl<'a> gen::wasi_ephemeral_nn::WasiEphemeralNn for WasiNnCtx {
         Ok(graph_id.into())
     }
 
-    fn load_by_name<'b>(&mut self, _name: &wiggle::GuestPtr<'b, str>) -> Result<gen::types::Graph> {
-        todo!()
+    fn load_by_name<'b>(&mut self, name: &wiggle::GuestPtr<'b, str>) -> Result<gen::types::Graph> {
+        let name = name.as_cow()?;
+        let graph_id = self.load_registered(&name)?;
+        Ok(graph_id.into())
     }
 
     fn init_execution_context(
@@ -148,6 +150,7 @@ impl TryFrom<gen::types::GraphEncoding> for crate::backend::BackendKind {
     fn try_from(value: gen::types::GraphEncoding) -> std::result::Result<Self, Self::Error> {
         match value {
             gen::types::GraphEncoding::Openvino => Ok(crate::backend::BackendKind::OpenVINO),
+            gen::types::GraphEncoding::Onnx => Ok(crate::backend::BackendKind::Onnx),
             _ => Err(UsageError::InvalidEncoding(value.into())),
         }
     }
@@ -1,4 +1,5 @@
 pub mod host;
+use crate::preview2::bindings::clocks::timezone::TimezoneDisplay;
 use cap_std::time::Duration;
 
 pub trait HostWallClock: Send + Sync {
@@ -10,3 +11,14 @@ pub trait HostMonotonicClock: Send + Sync {
     fn resolution(&self) -> u64;
     fn now(&self) -> u64;
 }
+
+pub trait HostTimezone: Send + Sync {
+    /// Information needed to display the time `when`, given as a duration
+    /// since the Unix epoch, in this timezone.
+    fn display(&self, when: Duration) -> TimezoneDisplay;
+
+    /// The number of seconds to add to UTC to get the local time at `when`.
+    fn utc_offset(&self, when: Duration) -> i32 {
+        self.display(when).utc_offset
+    }
+}
@@ -1,7 +1,10 @@
-use super::{HostMonotonicClock, HostWallClock};
+use super::{HostMonotonicClock, HostTimezone, HostWallClock};
+use crate::preview2::bindings::clocks::timezone::TimezoneDisplay;
+use anyhow::{bail, Context};
 use cap_std::time::{Duration, Instant, SystemClock};
 use cap_std::{ambient_authority, AmbientAuthority};
 use cap_time_ext::{MonotonicClockExt, SystemClockExt};
+use std::path::{Component, Path};
 
 pub struct WallClock {
     /// The underlying system clock.
@@ -64,6 +67,110 @@ impl HostMonotonicClock for MonotonicClock {
     }
 }
 
+/// The directories in which Unix systems install the timezone database.
+const TZ_DATABASE_DIRECTORIES: &[&str] = &[
+    "/usr/share/zoneinfo",
+    "/usr/lib/zoneinfo",
+    "/usr/share/lib/zoneinfo",
+    "/etc/zoneinfo",
+];
+
+/// A timezone backed by the system timezone database.
+pub struct SystemTimezone {
+    tz: tz::TimeZone,
+}
+
+impl SystemTimezone {
+    /// The host's local timezone, as given by the `TZ` environment variable or
+    /// else `/etc/localtime`.
+    ///
+    /// This falls back to UTC if the local timezone can't be determined, which
+    /// is always the case on non-Unix platforms.
+    pub fn local() -> Self {
+        let tz = match std::env::var("TZ") {
+            Ok(tz) => tz::TimeZone::from_posix_tz(&tz),
+            Err(_) => tz::TimeZone::local(),
+        };
+        Self {
+            tz: tz.unwrap_or_else(|_| tz::TimeZone::utc()),
+        }
+    }
+
+    /// Looks up a timezone by its IANA name, such as `Europe/Paris`, in the
+    /// system timezone database.
+    ///
+    /// The database is read from the usual locations on Unix systems, so this
+    /// fails on platforms which don't ship one, such as Windows.
+    pub fn named(name: &str) -> anyhow::Result<Self> {
+        // Names are paths relative to the database, which mustn't escape it.
+        let is_valid_name = !name.is_empty()
+            && Path::new(name)
+                .components()
+                .all(|c| matches!(c, Component::Normal(_)));
+        if !is_valid_name {
+            bail!("invalid timezone name {name:?}");
+        }
+        let data = TZ_DATABASE_DIRECTORIES
+            .iter()
+            .find_map(|dir| std::fs::read(Path::new(dir).join(name)).ok())
+            .with_context(|| format!("failed to find timezone {name:?}"))?;
+        let tz = tz::TimeZone::from_tz_data(&data)
+            .with_context(|| format!("failed to parse timezone {name:?}"))?;
+        Ok(Self { tz })
+    }
+
+    /// A timezone which is always `utc_offset` seconds ahead of UTC, and never
+    /// observes daylight saving time.
+    pub fn fixed(utc_offset: i32) -> anyhow::Result<Self> {
+        let tz = tz::TimeZone::fixed(utc_offset)
+            .with_context(|| format!("invalid UTC offset {utc_offset}"))?;
+        Ok(Self { tz })
+    }
+
+    /// Coordinated Universal Time.
+    pub fn utc() -> Self {
+        Self {
+            tz: tz::TimeZone::utc(),
+        }
+    }
+}
+
+impl HostTimezone for SystemTimezone {
+    fn display(&self, when: Duration) -> TimezoneDisplay {
+        let local_time_type = i64::try_from(when.as_secs())
+            .ok()
+            .and_then(|secs| self.tz.find_local_time_type(secs).ok());
+        match local_time_type {
+            Some(ty) => {
+                let utc_offset = ty.ut_offset();
+                let name = match ty.time_zone_designation() {
+                    "" => format_utc_offset(utc_offset),
+                    name => name.to_string(),
+                };
+                TimezoneDisplay {
+                    utc_offset,
+                    name,
+                    in_daylight_saving_time: ty.is_dst(),
+                }
+            }
+            // WASI defines a timezone which can't be determined for the given
+            // time to display as UTC.
+            None => TimezoneDisplay {
+                utc_offset: 0,
+                name: "UTC".to_string(),
+                in_daylight_saving_time: false,
+            },
+        }
+    }
+}
+
+/// Formats a UTC offset in seconds like `-04:00`, for zones without a name.
+fn format_utc_offset(utc_offset: i32) -> String {
+    let sign = if utc_offset < 0 { '-' } else { '+' };
+    let minutes = utc_offset.unsigned_abs() / 60;
+    format!("{sign}{:02}:{:02}", minutes / 60, minutes % 60)
+}
+
 pub fn monotonic_clock() -> Box<dyn HostMonotonicClock + Send + Sync> {
     Box::new(MonotonicClock::new(ambient_authority()))
 }
@@ -71,3 +178,33 @@ pub fn monotonic_clock() -> Box<dyn HostMonotonicClock + Send + Sync> {
 pub fn wall_clock() -> Box<dyn HostWallClock + Send + Sync> {
     Box::new(WallClock::new(ambient_authority()))
 }
+
+pub fn timezone() -> Box<dyn HostTimezone + Send + Sync> {
+    Box::new(SystemTimezone::local())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn named_timezones() {
+        for name in ["", "/etc/localtime", "../zoneinfo/UTC", "Europe/../UTC"] {
+            assert!(SystemTimezone::named(name).is_err(), "{name:?}");
+        }
+        assert!(SystemTimezone::named("Not/A_Zone").is_err());
+
+        // Only check real zones where the host ships a timezone database.
+        if !Path::new("/usr/share/zoneinfo/Europe/Berlin").exists() {
+            return;
+        }
+        let berlin = SystemTimezone::named("Europe/Berlin").unwrap();
+        // 2023-01-01T00:00:00Z and 2023-07-01T00:00:00Z.
+        let winter = berlin.display(Duration::from_secs(1_672_531_200));
+        assert_eq!((winter.utc_offset, winter.name.as_str()), (3600, "CET"));
+        assert!(!winter.in_daylight_saving_time);
+        let summer = berlin.display(Duration::from_secs(1_688_169_600));
+        assert_eq!((summer.utc_offset, summer.name.as_str()), (7200, "CEST"));
+        assert!(summer.in_daylight_saving_time);
+    }
+}
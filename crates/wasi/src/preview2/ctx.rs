@@ -1,8 +1,10 @@
-use super::clocks::host::{monotonic_clock, wall_clock};
+use super::clocks::host::{monotonic_clock, timezone, wall_clock};
 use crate::preview2::{
-    clocks::{self, HostMonotonicClock, HostWallClock},
+    clocks::{self, HostMonotonicClock, HostTimezone, HostWallClock},
     filesystem::{Dir, TableFsExt},
-    pipe, random, stdio,
+    pipe, random,
+    replay::{Replay, ReplayInputStream, ReplayMonotonicClock, ReplayRng, ReplayWallClock},
+    stdio,
     stdio::{StdioInput, StdioOutput},
     stream::{HostInputStream, HostOutputStream, TableStreamExt},
     DirPerms, FilePerms, IsATTY, Table,
@@ -23,6 +25,8 @@ pub struct WasiCtxBuilder {
     insecure_random_seed: u128,
     wall_clock: Box<dyn HostWallClock + Send + Sync>,
     monotonic_clock: Box<dyn HostMonotonicClock + Send + Sync>,
+    timezone: Box<dyn HostTimezone + Send + Sync>,
+    replay: Option<Replay>,
     built: bool,
 }
 
@@ -37,6 +41,7 @@ impl WasiCtxBuilder {
     /// * no arguments
     /// * no preopens
     /// * clocks use the host implementation of wall/monotonic clocks
+    /// * the timezone is the host's local timezone
     /// * RNGs are all initialized with random state and suitable generator
     ///   quality to satisfy the requirements of WASI APIs.
     ///
@@ -68,6 +73,8 @@ impl WasiCtxBuilder {
             insecure_random_seed,
             wall_clock: wall_clock(),
             monotonic_clock: monotonic_clock(),
+            timezone: timezone(),
+            replay: None,
             built: false,
         }
     }
@@ -200,6 +207,31 @@ impl WasiCtxBuilder {
         self
     }
 
+    /// Set the timezone reported to the guest by `wasi:clocks/timezone`.
+    ///
+    /// By default this is the host's local timezone. Pinning it to a fixed
+    /// zone, such as [`SystemTimezone::named`] or [`SystemTimezone::utc`],
+    /// makes the local times computed by the guest independent of the host.
+    ///
+    /// [`SystemTimezone::named`]: crate::preview2::SystemTimezone::named
+    /// [`SystemTimezone::utc`]: crate::preview2::SystemTimezone::utc
+    pub fn timezone(&mut self, timezone: impl clocks::HostTimezone + 'static) -> &mut Self {
+        self.timezone = Box::new(timezone);
+        self
+    }
+
+    /// Record the nondeterministic results observed by the guest into
+    /// `replay`, or replay them from it.
+    ///
+    /// This applies to the arguments, environment, clocks, random number
+    /// generators and stdin of the built context, whichever order they are
+    /// configured in. See the [`replay`](crate::preview2::replay) module for
+    /// what is covered.
+    pub fn replay(&mut self, replay: Replay) -> &mut Self {
+        self.replay = Some(replay);
+        self
+    }
+
     /// Uses the configured context so far to construct the final `WasiCtx`.
     ///
     /// This will insert resources into the provided `table`.
@@ -215,21 +247,63 @@ impl WasiCtxBuilder {
 
         use anyhow::Context;
         let Self {
-            stdin,
+            mut stdin,
             stdout,
             stderr,
-            env,
-            args,
+            mut env,
+            mut args,
             preopens,
-            random,
-            insecure_random,
-            insecure_random_seed,
-            wall_clock,
-            monotonic_clock,
+            mut random,
+            mut insecure_random,
+            mut insecure_random_seed,
+            mut wall_clock,
+            mut monotonic_clock,
+            timezone,
+            replay,
             built: _,
         } = mem::replace(self, Self::new());
         self.built = true;
 
+        if let Some(replay) = &replay {
+            use crate::preview2::replay::replay_error;
+            args = replay.args(args).map_err(replay_error)?;
+            let vars = env.into_iter().map(|(k, v)| format!("{k}={v}")).collect();
+            env = replay
+                .env(vars)
+                .map_err(replay_error)?
+                .into_iter()
+                .map(|var| match var.split_once('=') {
+                    Some((k, v)) => (k.to_owned(), v.to_owned()),
+                    None => (var, String::new()),
+                })
+                .collect();
+            // When recording, the host's seed is already in `seed`.
+            let mut seed = insecure_random_seed.to_le_bytes();
+            replay.fill_bytes(&mut seed, |_| {});
+            replay.check().map_err(replay_error)?;
+            insecure_random_seed = u128::from_le_bytes(seed);
+            random = Box::new(ReplayRng {
+                inner: random,
+                replay: replay.clone(),
+            });
+            insecure_random = Box::new(ReplayRng {
+                inner: insecure_random,
+                replay: replay.clone(),
+            });
+            wall_clock = Box::new(ReplayWallClock {
+                inner: wall_clock,
+                replay: replay.clone(),
+            });
+            monotonic_clock = Box::new(ReplayMonotonicClock {
+                inner: monotonic_clock,
+                replay: replay.clone(),
+            });
+            stdin.0 = Box::new(ReplayInputStream {
+                inner: stdin.0,
+                replay: replay.clone(),
+            });
+        }
+
         let stdin_ix = table.push_input_stream(stdin.0).context("stdin")?;
         let stdout_ix = table.push_output_stream(stdout.0).context("stdout")?;
         let stderr_ix = table.push_output_stream(stderr.0).context("stderr")?;
@@ -265,6 +339,8 @@ impl WasiCtxBuilder {
             insecure_random_seed,
             wall_clock,
             monotonic_clock,
+            timezone,
+            replay,
         })
     }
 }
@@ -282,10 +358,26 @@ pub struct WasiCtx {
     pub(crate) insecure_random_seed: u128,
     pub(crate) wall_clock: Box<dyn HostWallClock + Send + Sync>,
     pub(crate) monotonic_clock: Box<dyn HostMonotonicClock + Send + Sync>,
+    pub(crate) timezone: Box<dyn HostTimezone + Send + Sync>,
     pub(crate) env: Vec<(String, String)>,
     pub(crate) args: Vec<String>,
     pub(crate) preopens: Vec<(u32, String)>,
     pub(crate) stdin: StdioInput,
     pub(crate) stdout: StdioOutput,
     pub(crate) stderr: StdioOutput,
+    pub(crate) replay: Option<Replay>,
+}
+
+impl WasiCtx {
+    /// Fails with the error which a recorded or replayed clock or random
+    /// number generator ran into, if any, so that the call which used them
+    /// traps.
+    pub(crate) fn check_replay(&self) -> anyhow::Result<()> {
+        match &self.replay {
+            Some(replay) => replay
+                .check()
+                .map_err(crate::preview2::replay::replay_error),
+            None => Ok(()),
+        }
+    }
 }
@@ -1,6 +1,7 @@
+use crate::preview2::replay::{self, Replay};
 use crate::preview2::{StreamRuntimeError, StreamState, Table, TableError};
 use bytes::{Bytes, BytesMut};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 bitflags::bitflags! {
     #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -15,6 +16,18 @@ pub(crate) struct File {
     /// implementing the stream types. Also needed for [`block`].
     pub file: Arc<cap_std::fs::File>,
     pub perms: FilePerms,
+    /// The advisory lock held through this descriptor, so that converting it
+    /// can restore it when the new lock can't be acquired.
+    pub lock: Arc<Mutex<FileLock>>,
+}
+
+/// The advisory lock operations of `wasi:filesystem/types`, and the lock held
+/// through a descriptor, `Unlock` meaning that none is held.
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum FileLock {
+    Shared,
+    Exclusive,
+    Unlock,
 }
 
 impl File {
@@ -22,6 +35,7 @@ impl File {
         Self {
             file: Arc::new(file),
             perms,
+            lock: Arc::new(Mutex::new(FileLock::Unlock)),
         }
     }
 
@@ -114,13 +128,31 @@ impl Dir {
 pub(crate) struct FileInputStream {
     file: Arc<cap_std::fs::File>,
     position: u64,
+    replay: Option<Replay>,
 }
 impl FileInputStream {
-    pub fn new(file: Arc<cap_std::fs::File>, position: u64) -> Self {
-        Self { file, position }
+    pub fn new(file: Arc<cap_std::fs::File>, position: u64, replay: Option<Replay>) -> Self {
+        Self {
+            file,
+            position,
+            replay,
+        }
     }
 
     pub async fn read(&mut self, size: usize) -> anyhow::Result<(Bytes, StreamState)> {
+        let Some(log) = self.replay.clone() else {
+            return self.read_file(size).await;
+        };
+        if let Some(result) = replay::replayed_read(&log, size)? {
+            let (bytes, state) = result?;
+            self.position += bytes.len() as u64;
+            return Ok((bytes, state));
+        }
+        let result = self.read_file(size).await;
+        replay::record_read(&log, result)
+    }
+
+    async fn read_file(&mut self, size: usize) -> anyhow::Result<(Bytes, StreamState)> {
         use system_interface::fs::FileIoExt;
         let f = Arc::clone(&self.file);
         let p = self.position;
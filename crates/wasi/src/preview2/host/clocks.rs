@@ -26,6 +26,7 @@ impl TryFrom<SystemTime> for Datetime {
 impl<T: WasiView> wall_clock::Host for T {
     fn now(&mut self) -> anyhow::Result<Datetime> {
         let now = self.ctx().wall_clock.now();
+        self.ctx().check_replay()?;
         Ok(Datetime {
             seconds: now.as_secs(),
             nanoseconds: now.subsec_nanos(),
@@ -43,7 +44,9 @@ impl<T: WasiView> wall_clock::Host for T {
 
 impl<T: WasiView> monotonic_clock::Host for T {
     fn now(&mut self) -> anyhow::Result<Instant> {
-        Ok(self.ctx().monotonic_clock.now())
+        let now = self.ctx().monotonic_clock.now();
+        self.ctx().check_replay()?;
+        Ok(now)
     }
 
     fn resolution(&mut self) -> anyhow::Result<Instant> {
@@ -55,6 +58,7 @@ impl<T: WasiView> monotonic_clock::Host for T {
         // Calculate time relative to clock object, which may not have the same zero
         // point as tokio Inst::now()
         let clock_now = self.ctx().monotonic_clock.now();
+        self.ctx().check_replay()?;
         if absolute && when < clock_now {
             // Deadline is in the past, so pollable is always ready:
             Ok(self
@@ -92,16 +96,24 @@ impl<T: WasiView> monotonic_clock::Host for T {
     }
 }
 
+// The `timezone` interface has no way to create timezones, so every handle a
+// guest can name refers to the timezone configured in the `WasiCtx`.
 impl<T: WasiView> timezone::Host for T {
     fn display(&mut self, timezone: Timezone, when: Datetime) -> anyhow::Result<TimezoneDisplay> {
-        todo!("timezone display is not implemented")
+        Ok(self.ctx().timezone.display(duration_from(when)))
     }
 
     fn utc_offset(&mut self, timezone: Timezone, when: Datetime) -> anyhow::Result<i32> {
-        todo!("timezone utc_offset is not implemented")
+        Ok(self.ctx().timezone.utc_offset(duration_from(when)))
     }
 
     fn drop_timezone(&mut self, timezone: Timezone) -> anyhow::Result<()> {
-        todo!("timezone drop is not implemented")
+        Ok(())
     }
 }
+
+fn duration_from(when: Datetime) -> std::time::Duration {
+    // Sub-second precision doesn't affect which offset is in effect, and
+    // ignoring it avoids overflowing when `nanoseconds` is out of range.
+    std::time::Duration::from_secs(when.seconds)
+}
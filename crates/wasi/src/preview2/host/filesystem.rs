@@ -1,7 +1,8 @@
 use crate::preview2::bindings::clocks::wall_clock;
 use crate::preview2::bindings::filesystem::{preopens, types};
 use crate::preview2::bindings::io::streams;
-use crate::preview2::filesystem::{Dir, File, TableFsExt};
+use crate::preview2::filesystem::{Dir, File, FileLock, TableFsExt};
+use crate::preview2::replay::replay_error;
 use crate::preview2::{DirPerms, FilePerms, Table, TableError, WasiView};
 
 use types::ErrorCode;
@@ -191,9 +192,6 @@ impl<T: WasiView> types::Host for T {
         len: types::Filesize,
         offset: types::Filesize,
     ) -> Result<(Vec<u8>, bool), types::Error> {
-        use std::io::IoSliceMut;
-        use system_interface::fs::FileIoExt;
-
         let table = self.table();
 
         let f = table.get_file(fd)?;
@@ -201,26 +199,32 @@ impl<T: WasiView> types::Host for T {
             return Err(ErrorCode::NotPermitted.into());
         }
 
-        let (mut buffer, r) = f
-            .spawn_blocking(move |f| {
-                let mut buffer = vec![0; len.try_into().unwrap_or(usize::MAX)];
-                let r = f.read_vectored_at(&mut [IoSliceMut::new(&mut buffer)], offset);
-                (buffer, r)
-            })
-            .await;
-
-        let (bytes_read, state) = match r? {
-            0 => (0, true),
-            n => (n, false),
-        };
-
-        buffer.truncate(
-            bytes_read
-                .try_into()
-                .expect("bytes read into memory as u64 fits in usize"),
-        );
-
-        Ok((buffer, state))
+        // Failed reads are replayed as `io`, as the log doesn't hold the
+        // error code.
+        let replay = self.ctx().replay.clone();
+        if let Some(replay) = &replay {
+            let size = len.try_into().unwrap_or(usize::MAX);
+            match replay
+                .replayed_stream_read(size)
+                .map_err(|e| types::Error::trap(replay_error(e)))?
+            {
+                Some(Ok(read)) => return Ok(read),
+                Some(Err(())) => return Err(ErrorCode::Io.into()),
+                None => {}
+            }
+        }
+        let result = read_at(f, len, offset).await;
+        if let Some(replay) = &replay {
+            let logged = match &result {
+                Ok((bytes, end)) => Ok((&bytes[..], *end)),
+                Err(e) if e.downcast_ref().is_some() => Err(()),
+                Err(_) => return result,
+            };
+            replay
+                .record_stream_read(logged)
+                .map_err(|e| types::Error::trap(replay_error(e)))?;
+        }
+        result
     }
 
     async fn write(
@@ -694,52 +698,123 @@ impl<T: WasiView> types::Host for T {
 
     async fn access_at(
         &mut self,
-        _fd: types::Descriptor,
-        _path_flags: types::PathFlags,
-        _path: String,
-        _access: types::AccessType,
+        fd: types::Descriptor,
+        path_flags: types::PathFlags,
+        path: String,
+        access: types::AccessType,
     ) -> Result<(), types::Error> {
-        todo!("filesystem access_at is not implemented")
+        let table = self.table();
+        let d = table.get_dir(fd)?;
+        if !d.perms.contains(DirPerms::READ) {
+            return Err(ErrorCode::NotPermitted.into());
+        }
+
+        let meta = if symlink_follow(path_flags) {
+            d.spawn_blocking(move |d| d.metadata(&path)).await?
+        } else {
+            d.spawn_blocking(move |d| d.symlink_metadata(&path)).await?
+        };
+        match access {
+            types::AccessType::Exists => Ok(()),
+            types::AccessType::Access(modes) => {
+                if metadata_grants_access(&meta, modes) {
+                    Ok(())
+                } else {
+                    Err(ErrorCode::Access.into())
+                }
+            }
+        }
     }
 
     async fn change_file_permissions_at(
         &mut self,
-        _fd: types::Descriptor,
-        _path_flags: types::PathFlags,
-        _path: String,
-        _mode: types::Modes,
+        fd: types::Descriptor,
+        path_flags: types::PathFlags,
+        path: String,
+        modes: types::Modes,
     ) -> Result<(), types::Error> {
-        todo!("filesystem change_file_permissions_at is not implemented")
+        let table = self.table();
+        let d = table.get_dir(fd)?;
+        if !d.perms.contains(DirPerms::MUTATE) {
+            return Err(ErrorCode::NotPermitted.into());
+        }
+        let follow = symlink_follow(path_flags);
+        d.spawn_blocking::<_, Result<(), types::Error>>(move |d| {
+            let meta = if follow {
+                d.metadata(&path)?
+            } else {
+                d.symlink_metadata(&path)?
+            };
+            if meta.is_dir() {
+                return Err(ErrorCode::IsDirectory.into());
+            }
+            let perms = permissions_from(meta.permissions(), modes, false)?;
+            set_permissions(d, &path, perms, follow)
+        })
+        .await
     }
 
     async fn change_directory_permissions_at(
         &mut self,
-        _fd: types::Descriptor,
-        _path_flags: types::PathFlags,
-        _path: String,
-        _mode: types::Modes,
+        fd: types::Descriptor,
+        path_flags: types::PathFlags,
+        path: String,
+        modes: types::Modes,
     ) -> Result<(), types::Error> {
-        todo!("filesystem change_directory_permissions_at is not implemented")
+        let table = self.table();
+        let d = table.get_dir(fd)?;
+        if !d.perms.contains(DirPerms::MUTATE) {
+            return Err(ErrorCode::NotPermitted.into());
+        }
+        // `executable` is not valid for directories: `readable` implies
+        // searchability instead.
+        if modes.contains(types::Modes::EXECUTABLE) {
+            return Err(ErrorCode::Invalid.into());
+        }
+        let follow = symlink_follow(path_flags);
+        d.spawn_blocking::<_, Result<(), types::Error>>(move |d| {
+            let meta = if follow {
+                d.metadata(&path)?
+            } else {
+                d.symlink_metadata(&path)?
+            };
+            if !meta.is_dir() {
+                return Err(ErrorCode::NotDirectory.into());
+            }
+            let perms = permissions_from(meta.permissions(), modes, true)?;
+            set_permissions(d, &path, perms, follow)
+        })
+        .await
     }
 
-    async fn lock_shared(&mut self, _fd: types::Descriptor) -> Result<(), types::Error> {
-        todo!("filesystem lock_shared is not implemented")
+    async fn lock_shared(&mut self, fd: types::Descriptor) -> Result<(), types::Error> {
+        let f = self.table().get_file(fd)?;
+        lock_descriptor(f, FileLock::Shared, true).await?;
+        Ok(())
     }
 
-    async fn lock_exclusive(&mut self, _fd: types::Descriptor) -> Result<(), types::Error> {
-        todo!("filesystem lock_exclusive is not implemented")
+    async fn lock_exclusive(&mut self, fd: types::Descriptor) -> Result<(), types::Error> {
+        let f = self.table().get_file(fd)?;
+        lock_descriptor(f, FileLock::Exclusive, true).await?;
+        Ok(())
     }
 
-    async fn try_lock_shared(&mut self, _fd: types::Descriptor) -> Result<(), types::Error> {
-        todo!("filesystem try_lock_shared is not implemented")
+    async fn try_lock_shared(&mut self, fd: types::Descriptor) -> Result<(), types::Error> {
+        let f = self.table().get_file(fd)?;
+        lock_descriptor(f, FileLock::Shared, false).await?;
+        Ok(())
     }
 
-    async fn try_lock_exclusive(&mut self, _fd: types::Descriptor) -> Result<(), types::Error> {
-        todo!("filesystem try_lock_exclusive is not implemented")
+    async fn try_lock_exclusive(&mut self, fd: types::Descriptor) -> Result<(), types::Error> {
+        let f = self.table().get_file(fd)?;
+        lock_descriptor(f, FileLock::Exclusive, false).await?;
+        Ok(())
     }
 
-    async fn unlock(&mut self, _fd: types::Descriptor) -> Result<(), types::Error> {
-        todo!("filesystem unlock is not implemented")
+    async fn unlock(&mut self, fd: types::Descriptor) -> Result<(), types::Error> {
+        let f = self.table().get_file(fd)?;
+        lock_descriptor(f, FileLock::Unlock, true).await?;
+        Ok(())
     }
 
     async fn read_via_stream(
@@ -762,7 +837,7 @@ impl<T: WasiView> types::Host for T {
         let clone = std::sync::Arc::clone(&f.file);
 
         // Create a stream view for it.
-        let reader = FileInputStream::new(clone, offset);
+        let reader = FileInputStream::new(clone, offset, self.ctx().replay.clone());
 
         // Insert the stream view into the table. Trap if the table is full.
         let index = self
@@ -961,6 +1036,9 @@ fn from_raw_os_error(err: Option<i32>) -> Option<types::Error> {
         RustixErrno::ALREADY => ErrorCode::Already.into(),
         RustixErrno::INPROGRESS => ErrorCode::InProgress.into(),
         RustixErrno::INTR => ErrorCode::Interrupted.into(),
+        RustixErrno::WOULDBLOCK => ErrorCode::WouldBlock.into(),
+        RustixErrno::DEADLK => ErrorCode::Deadlock.into(),
+        RustixErrno::NOLCK => ErrorCode::Unsupported.into(),
 
         // On some platforms.into(), these have the same value as other errno values.
         #[allow(unreachable_patterns)]
@@ -995,10 +1073,43 @@ fn from_raw_os_error(raw_os_error: Option<i32>) -> Option<types::Error> {
         Some(Foundation::ERROR_ALREADY_EXISTS) => ErrorCode::Exist.into(),
         Some(Foundation::ERROR_STOPPED_ON_SYMLINK) => ErrorCode::Loop.into(),
         Some(Foundation::ERROR_DIRECTORY_NOT_SUPPORTED) => ErrorCode::IsDirectory.into(),
+        Some(Foundation::ERROR_LOCK_VIOLATION) => ErrorCode::WouldBlock.into(),
         _ => return None,
     })
 }
 
+/// Reads at most `len` bytes from `f` at `offset`, returning them and whether
+/// the end of the file was reached.
+async fn read_at(
+    f: &File,
+    len: types::Filesize,
+    offset: types::Filesize,
+) -> Result<(Vec<u8>, bool), types::Error> {
+    use std::io::IoSliceMut;
+    use system_interface::fs::FileIoExt;
+
+    let (mut buffer, r) = f
+        .spawn_blocking(move |f| {
+            let mut buffer = vec![0; len.try_into().unwrap_or(usize::MAX)];
+            let r = f.read_vectored_at(&mut [IoSliceMut::new(&mut buffer)], offset);
+            (buffer, r)
+        })
+        .await;
+
+    let (bytes_read, state) = match r? {
+        0 => (0, true),
+        n => (n, false),
+    };
+
+    buffer.truncate(
+        bytes_read
+            .try_into()
+            .expect("bytes read into memory as u64 fits in usize"),
+    );
+
+    Ok((buffer, state))
+}
+
 impl From<std::io::Error> for types::Error {
     fn from(err: std::io::Error) -> types::Error {
         match from_raw_os_error(err.raw_os_error()) {
@@ -1104,6 +1215,237 @@ fn symlink_follow(path_flags: types::PathFlags) -> bool {
     path_flags.contains(types::PathFlags::SYMLINK_FOLLOW)
 }
 
+/// Checks `modes` against the permission bits in `meta`, the way `faccessat`
+/// with `AT_EACCESS` does for the effective user and groups of this process.
+#[cfg(unix)]
+fn metadata_grants_access(meta: &cap_std::fs::Metadata, modes: types::Modes) -> bool {
+    use cap_std::fs::MetadataExt;
+    use rustix::process::{getegid, geteuid, getgroups};
+    use types::Modes;
+
+    let mode = meta.mode();
+    let euid = geteuid().as_raw();
+    if euid == 0 {
+        // The superuser may read and write anything, and may execute anything
+        // that has at least one execute bit set.
+        return !modes.contains(Modes::EXECUTABLE) || meta.is_dir() || mode & 0o111 != 0;
+    }
+
+    let in_group = meta.gid() == getegid().as_raw()
+        || getgroups()
+            .map(|groups| groups.iter().any(|g| g.as_raw() == meta.gid()))
+            .unwrap_or(false);
+    let shift = if meta.uid() == euid {
+        6
+    } else if in_group {
+        3
+    } else {
+        0
+    };
+    let granted = (mode >> shift) & 0o7;
+
+    let mut wanted = 0;
+    if modes.contains(Modes::READABLE) {
+        wanted |= 0o4;
+    }
+    if modes.contains(Modes::WRITABLE) {
+        wanted |= 0o2;
+    }
+    if modes.contains(Modes::EXECUTABLE) {
+        wanted |= 0o1;
+    }
+    granted & wanted == wanted
+}
+
+/// Checks `modes` against the metadata in `meta`. Windows only tracks a
+/// read-only attribute, so everything is readable and executable, and
+/// anything not marked read-only is writable.
+#[cfg(windows)]
+fn metadata_grants_access(meta: &cap_std::fs::Metadata, modes: types::Modes) -> bool {
+    !(modes.contains(types::Modes::WRITABLE) && meta.permissions().readonly())
+}
+
+/// Computes the new permissions for a filesystem object from its current
+/// permissions and the requested `modes`.
+///
+/// Granting a mode only grants it to the owner, while revoking a mode revokes
+/// it from everyone, so that a guest can never widen access to an object
+/// beyond its owner. For directories, `readable` also controls searchability.
+#[cfg(unix)]
+fn permissions_from(
+    perms: cap_std::fs::Permissions,
+    modes: types::Modes,
+    is_dir: bool,
+) -> Result<cap_std::fs::Permissions, types::Error> {
+    use cap_std::fs::PermissionsExt;
+    use types::Modes;
+
+    let mut mode = perms.mode();
+    let mut update = |enable: bool, owner_bit: u32| {
+        if enable {
+            mode |= owner_bit;
+        } else {
+            mode &= !(owner_bit | owner_bit >> 3 | owner_bit >> 6);
+        }
+    };
+    update(modes.contains(Modes::READABLE), 0o400);
+    update(modes.contains(Modes::WRITABLE), 0o200);
+    if is_dir {
+        update(modes.contains(Modes::READABLE), 0o100);
+    } else {
+        update(modes.contains(Modes::EXECUTABLE), 0o100);
+    }
+    Ok(cap_std::fs::Permissions::from_mode(mode))
+}
+
+/// Computes the new permissions for a filesystem object from its current
+/// permissions and the requested `modes`.
+///
+/// Windows can only represent whether an object is writable, so revoking
+/// `readable` is reported as unsupported and `executable` is ignored.
+#[cfg(windows)]
+fn permissions_from(
+    mut perms: cap_std::fs::Permissions,
+    modes: types::Modes,
+    _is_dir: bool,
+) -> Result<cap_std::fs::Permissions, types::Error> {
+    if !modes.contains(types::Modes::READABLE) {
+        return Err(ErrorCode::Unsupported.into());
+    }
+    perms.set_readonly(!modes.contains(types::Modes::WRITABLE));
+    Ok(perms)
+}
+
+fn set_permissions(
+    d: &cap_std::fs::Dir,
+    path: &str,
+    perms: cap_std::fs::Permissions,
+    follow: bool,
+) -> Result<(), types::Error> {
+    use cap_fs_ext::DirExt;
+    if follow {
+        d.set_permissions(path, perms)?;
+    } else {
+        d.set_symlink_permissions(path, perms)?;
+    }
+    Ok(())
+}
+
+/// Acquires, converts or releases the advisory lock held through the
+/// descriptor `f`, on a blocking thread.
+async fn lock_descriptor(f: &File, lock: FileLock, blocking: bool) -> std::io::Result<()> {
+    let held = f.lock.clone();
+    f.spawn_blocking(move |f| lock_file(f, &held, lock, blocking))
+        .await
+}
+
+/// Acquires, converts or releases an advisory lock on `file`, where `held` is
+/// the lock currently held through it. When `blocking` is false, a lock which
+/// cannot be acquired immediately fails with `EWOULDBLOCK` rather than
+/// waiting.
+#[cfg(unix)]
+fn lock_file(
+    file: &cap_std::fs::File,
+    held: &std::sync::Mutex<FileLock>,
+    lock: FileLock,
+    blocking: bool,
+) -> std::io::Result<()> {
+    use rustix::fs::{flock, FlockOperation};
+    let operation = |lock, blocking| match (lock, blocking) {
+        (FileLock::Shared, true) => FlockOperation::LockShared,
+        (FileLock::Shared, false) => FlockOperation::NonBlockingLockShared,
+        (FileLock::Exclusive, true) => FlockOperation::LockExclusive,
+        (FileLock::Exclusive, false) => FlockOperation::NonBlockingLockExclusive,
+        (FileLock::Unlock, _) => FlockOperation::Unlock,
+    };
+
+    let mut held = held.lock().unwrap();
+    match flock(file, operation(lock, blocking)) {
+        Ok(()) => {
+            *held = lock;
+            Ok(())
+        }
+        Err(err) => {
+            // `flock` releases the lock it converts before acquiring the new
+            // one, so take the previous lock again rather than losing it.
+            if *held != FileLock::Unlock && flock(file, operation(*held, false)).is_err() {
+                *held = FileLock::Unlock;
+            }
+            Err(err.into())
+        }
+    }
+}
+
+/// Acquires, converts or releases an advisory lock on `file`, where `held` is
+/// the lock currently held through it. When `blocking` is false, a lock which
+/// cannot be acquired immediately fails with `ERROR_LOCK_VIOLATION` rather
+/// than waiting.
+#[cfg(windows)]
+fn lock_file(
+    file: &cap_std::fs::File,
+    held: &std::sync::Mutex<FileLock>,
+    lock: FileLock,
+    blocking: bool,
+) -> std::io::Result<()> {
+    use std::os::windows::io::AsRawHandle;
+    use windows_sys::Win32::Foundation::HANDLE;
+    use windows_sys::Win32::Storage::FileSystem::{
+        LockFileEx, UnlockFile, LOCKFILE_EXCLUSIVE_LOCK, LOCKFILE_FAIL_IMMEDIATELY,
+    };
+    use windows_sys::Win32::System::IO::OVERLAPPED;
+
+    let handle = file.as_raw_handle() as HANDLE;
+    let acquire = |lock, blocking| {
+        let mut flags = 0;
+        if lock == FileLock::Exclusive {
+            flags |= LOCKFILE_EXCLUSIVE_LOCK;
+        }
+        if !blocking {
+            flags |= LOCKFILE_FAIL_IMMEDIATELY;
+        }
+        let ok = unsafe {
+            let mut overlapped: OVERLAPPED = std::mem::zeroed();
+            LockFileEx(handle, flags, 0, u32::MAX, u32::MAX, &mut overlapped)
+        };
+        if ok == 0 {
+            Err(std::io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    };
+    let release = || unsafe {
+        UnlockFile(handle, 0, 0, u32::MAX, u32::MAX);
+    };
+
+    // Windows locks stack rather than convert between shared and exclusive
+    // like `flock` does, so conversions are done by hand.
+    let mut held = held.lock().unwrap();
+    match (*held, lock) {
+        (current, lock) if current == lock => {}
+        (_, FileLock::Unlock) => release(),
+        (FileLock::Unlock, lock) => acquire(lock, blocking)?,
+        // A handle can take a shared lock over its own exclusive lock, and
+        // the first unlock then releases the exclusive one.
+        (FileLock::Exclusive, FileLock::Shared) => {
+            acquire(FileLock::Shared, blocking)?;
+            release();
+        }
+        // But it can't take an exclusive lock over its own shared lock, so the
+        // shared lock has to go first, and is taken again on failure.
+        (current, lock) => {
+            release();
+            if let Err(err) = acquire(lock, blocking) {
+                if acquire(current, false).is_err() {
+                    *held = FileLock::Unlock;
+                }
+                return Err(err);
+            }
+        }
+    }
+    *held = lock;
+    Ok(())
+}
+
 pub(crate) struct ReaddirIterator(
     std::sync::Mutex<
         Box<dyn Iterator<Item = Result<types::DirectoryEntry, types::Error>> + Send + 'static>,
@@ -26,11 +26,12 @@ mod poll;
 #[cfg(feature = "preview1-on-preview2")]
 pub mod preview1;
 mod random;
+pub mod replay;
 mod stdio;
 mod stream;
 mod table;
 
-pub use self::clocks::{HostMonotonicClock, HostWallClock};
+pub use self::clocks::{host::SystemTimezone, HostMonotonicClock, HostTimezone, HostWallClock};
 pub use self::ctx::{WasiCtx, WasiCtxBuilder, WasiView};
 pub use self::error::I32Exit;
 pub use self::filesystem::{DirPerms, FilePerms};
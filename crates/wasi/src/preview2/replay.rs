@@ -0,0 +1,230 @@
+//! Recording and replaying the nondeterministic results observed by a
+//! component, in the log format of [`wasi_common::replay`].
+//!
+//! A [`Replay`] log is installed with
+//! [`WasiCtxBuilder::replay`](crate::preview2::WasiCtxBuilder::replay). It
+//! covers the arguments and environment, the wall and monotonic clocks, the
+//! secure and insecure random number generators and the insecure seed, stdin,
+//! and the data read from files with `read` and `read-via-stream`.
+
+use crate::preview2::clocks::{HostMonotonicClock, HostWallClock};
+use crate::preview2::stream::{HostInputStream, StreamRuntimeError, StreamState};
+use anyhow::Error;
+use bytes::Bytes;
+use cap_rand::RngCore;
+use cap_std::time::Duration;
+
+pub use wasi_common::replay::Replay;
+
+/// Converts an error from the replay log, which always traps, into the error
+/// type of the preview 2 host functions.
+pub(crate) fn replay_error(e: wasi_common::Error) -> Error {
+    match e.downcast() {
+        Ok(errno) => anyhow::anyhow!("unexpected replayed error {errno:?}"),
+        Err(trap) => trap,
+    }
+}
+
+/// When replaying, returns the replayed result of a read of at most `size`
+/// bytes from a stream.
+pub(crate) fn replayed_read(
+    replay: &Replay,
+    size: usize,
+) -> Result<Option<Result<(Bytes, StreamState), Error>>, Error> {
+    Ok(
+        match replay.replayed_stream_read(size).map_err(replay_error)? {
+            None => None,
+            Some(Ok((bytes, false))) => Some(Ok((bytes.into(), StreamState::Open))),
+            Some(Ok((bytes, true))) => Some(Ok((bytes.into(), StreamState::Closed))),
+            Some(Err(())) => Some(Err(StreamRuntimeError::from(anyhow::anyhow!(
+                "replayed stream read error"
+            ))
+            .into())),
+        },
+    )
+}
+
+/// When recording, logs the result of a read from a stream.
+pub(crate) fn record_read(
+    replay: &Replay,
+    result: Result<(Bytes, StreamState), Error>,
+) -> Result<(Bytes, StreamState), Error> {
+    let logged = match &result {
+        Ok((bytes, state)) => Ok((&bytes[..], state.is_closed())),
+        Err(e) if e.is::<StreamRuntimeError>() => Err(()),
+        // Traps end the execution, so there's nothing to replay.
+        Err(_) => return result,
+    };
+    replay.record_stream_read(logged).map_err(replay_error)?;
+    result
+}
+
+/// A [`HostWallClock`] whose readings are recorded or replayed.
+pub(crate) struct ReplayWallClock {
+    pub(crate) inner: Box<dyn HostWallClock + Send + Sync>,
+    pub(crate) replay: Replay,
+}
+
+impl HostWallClock for ReplayWallClock {
+    fn resolution(&self) -> Duration {
+        self.inner.resolution()
+    }
+
+    fn now(&self) -> Duration {
+        self.replay.system_time(|| self.inner.now())
+    }
+}
+
+/// A [`HostMonotonicClock`] whose readings are recorded or replayed.
+pub(crate) struct ReplayMonotonicClock {
+    pub(crate) inner: Box<dyn HostMonotonicClock + Send + Sync>,
+    pub(crate) replay: Replay,
+}
+
+impl HostMonotonicClock for ReplayMonotonicClock {
+    fn resolution(&self) -> u64 {
+        self.inner.resolution()
+    }
+
+    fn now(&self) -> u64 {
+        let d = self
+            .replay
+            .monotonic_time(|| Duration::from_nanos(self.inner.now()));
+        d.as_nanos().try_into().unwrap_or(u64::MAX)
+    }
+}
+
+/// A random number generator whose output is recorded or replayed.
+pub(crate) struct ReplayRng {
+    pub(crate) inner: Box<dyn RngCore + Send + Sync>,
+    pub(crate) replay: Replay,
+}
+
+impl RngCore for ReplayRng {
+    fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0; 4];
+        self.fill_bytes(&mut bytes);
+        u32::from_le_bytes(bytes)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0; 8];
+        self.fill_bytes(&mut bytes);
+        u64::from_le_bytes(bytes)
+    }
+
+    fn fill_bytes(&mut self, buf: &mut [u8]) {
+        let inner = &mut self.inner;
+        self.replay.fill_bytes(buf, |buf| inner.fill_bytes(buf));
+    }
+
+    fn try_fill_bytes(&mut self, buf: &mut [u8]) -> Result<(), cap_rand::Error> {
+        self.fill_bytes(buf);
+        Ok(())
+    }
+}
+
+/// A [`HostInputStream`] whose reads are recorded or replayed.
+pub(crate) struct ReplayInputStream {
+    pub(crate) inner: Box<dyn HostInputStream>,
+    pub(crate) replay: Replay,
+}
+
+#[async_trait::async_trait]
+impl HostInputStream for ReplayInputStream {
+    fn read(&mut self, size: usize) -> Result<(Bytes, StreamState), Error> {
+        if let Some(result) = replayed_read(&self.replay, size)? {
+            return result;
+        }
+        let result = self.inner.read(size);
+        record_read(&self.replay, result)
+    }
+
+    async fn ready(&mut self) -> Result<(), Error> {
+        // Replayed reads don't come from the host, so they are always ready.
+        if self.replay.is_replaying() {
+            return Ok(());
+        }
+        self.inner.ready().await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::preview2::pipe::MemoryInputPipe;
+    use crate::preview2::random::Deterministic;
+    use std::io::{self, Write};
+    use std::sync::{Arc, Mutex};
+
+    struct FixedClock(Duration);
+
+    impl HostWallClock for FixedClock {
+        fn resolution(&self) -> Duration {
+            Duration::from_nanos(1)
+        }
+
+        fn now(&self) -> Duration {
+            self.0
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn observe(
+        replay: &Replay,
+        secs: u64,
+        random: u8,
+        stdin: &'static [u8],
+    ) -> (Duration, u64, (Bytes, StreamState)) {
+        let clock = ReplayWallClock {
+            inner: Box::new(FixedClock(Duration::from_secs(secs))),
+            replay: replay.clone(),
+        };
+        let mut rng = ReplayRng {
+            inner: Box::new(Deterministic::new(vec![random])),
+            replay: replay.clone(),
+        };
+        let mut stdin = ReplayInputStream {
+            inner: Box::new(MemoryInputPipe::new(Bytes::from_static(stdin))),
+            replay: replay.clone(),
+        };
+        (clock.now(), rng.next_u64(), stdin.read(16).unwrap())
+    }
+
+    #[test]
+    fn replay_reproduces_recording() {
+        let log = Shared::default();
+        let recording = Replay::record(log.clone());
+        let recorded = observe(&recording, 1, 2, b"recorded");
+        assert_eq!(
+            recorded.2,
+            (Bytes::from_static(b"recorded"), StreamState::Closed)
+        );
+        assert!(recording.check().is_ok());
+
+        let log = log.0.lock().unwrap().clone();
+        let replaying = Replay::replay(io::Cursor::new(log));
+        assert_eq!(observe(&replaying, 3, 4, b"other"), recorded);
+        assert!(replaying.check().is_ok());
+
+        // Reading the clock past the end of the log falls back to the host,
+        // and the divergence is reported by the next check.
+        let clock = ReplayWallClock {
+            inner: Box::new(FixedClock(Duration::from_secs(5))),
+            replay: replaying.clone(),
+        };
+        assert_eq!(clock.now(), Duration::from_secs(5));
+        assert!(replaying.check().is_err());
+    }
+}
@@ -0,0 +1,11 @@
+//! Backends for the cache of compiled modules.
+//!
+//! By default the cache keeps compiled modules in a directory, as configured
+//! with [`Config::cache_config_load`](crate::Config::cache_config_load).
+//! Embedders can store them elsewhere by implementing [`CacheBackend`], and
+//! passing it to [`Config::module_cache_backend`](crate::Config::module_cache_backend).
+//!
+//! Not to be confused with [`crate::CacheStore`], which stores the compiled
+//! functions of Cranelift's incremental compilation cache.
+
+pub use wasmtime_cache::{CacheBackend, InMemoryCacheBackend};
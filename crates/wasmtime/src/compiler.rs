@@ -160,10 +160,44 @@ struct CompileOutput {
     info: Option<WasmFunctionInfo>,
 }
 
+impl CompileOutput {
+    fn wasm_function(
+        module: StaticModuleIndex,
+        translation: &ModuleTranslation<'_>,
+        def_func_index: DefinedFuncIndex,
+        info: WasmFunctionInfo,
+        function: Box<dyn Any + Send>,
+    ) -> Self {
+        let func_index = translation.module.func_index(def_func_index);
+        CompileOutput {
+            key: CompileKey::wasm_function(module, def_func_index),
+            symbol: format!(
+                "wasm[{}]::function[{}]",
+                module.as_u32(),
+                func_index.as_u32()
+            ),
+            function: CompiledFunction::Function(function),
+            info: Some(info),
+        }
+    }
+}
+
+/// The functions defined in a core Wasm module.
+///
+/// These are compiled separately from the other inputs so that compilers
+/// supporting it can inline calls between them.
+struct ModuleFunctions<'a> {
+    module: StaticModuleIndex,
+    translation: &'a ModuleTranslation<'a>,
+    types: &'a ModuleTypes,
+    bodies: PrimaryMap<DefinedFuncIndex, FunctionBodyData<'a>>,
+}
+
 /// The collection of things we need to compile for a Wasm module or component.
 #[derive(Default)]
 pub struct CompileInputs<'a> {
     inputs: Vec<CompileInput<'a>>,
+    functions: Vec<ModuleFunctions<'a>>,
 }
 
 impl<'a> CompileInputs<'a> {
@@ -253,23 +287,7 @@ impl<'a> CompileInputs<'a> {
         let mut sigs = BTreeSet::new();
 
         for (module, translation, functions) in translations {
-            for (def_func_index, func_body) in functions {
-                self.push_input(move |compiler| {
-                    let func_index = translation.module.func_index(def_func_index);
-                    let (info, function) =
-                        compiler.compile_function(translation, def_func_index, func_body, types)?;
-                    Ok(CompileOutput {
-                        key: CompileKey::wasm_function(module, def_func_index),
-                        symbol: format!(
-                            "wasm[{}]::function[{}]",
-                            module.as_u32(),
-                            func_index.as_u32()
-                        ),
-                        function: CompiledFunction::Function(function),
-                        info: Some(info),
-                    })
-                });
-
+            for def_func_index in functions.keys() {
                 let func_index = translation.module.func_index(def_func_index);
                 if translation.module.functions[func_index].is_escaping() {
                     self.push_input(move |compiler| {
@@ -312,6 +330,13 @@ impl<'a> CompileInputs<'a> {
                 }
             }
 
+            self.functions.push(ModuleFunctions {
+                module,
+                translation,
+                types,
+                bodies: functions,
+            });
+
             sigs.extend(translation.module.types.iter().map(|(_, ty)| match ty {
                 ModuleType::Function(ty) | ModuleType::Continuation(ty) => *ty,
             }));
@@ -340,7 +365,8 @@ impl<'a> CompileInputs<'a> {
         let compiler = engine.compiler();
 
         // Compile each individual input in parallel.
-        let raw_outputs = engine.run_maybe_parallel(self.inputs, |f| f(compiler))?;
+        let mut raw_outputs = engine.run_maybe_parallel(self.inputs, |f| f(compiler))?;
+        raw_outputs.extend(compile_functions(engine, self.functions)?);
 
         // Bucket the outputs by kind.
         let mut outputs: BTreeMap<u32, Vec<CompileOutput>> = BTreeMap::new();
@@ -366,6 +392,85 @@ impl<'a> CompileInputs<'a> {
     }
 }
 
+/// Compile the functions defined in each module, in parallel.
+///
+/// If the compiler supports inlining, every function is translated before any
+/// is compiled so that calls within a module can be inlined.
+fn compile_functions(
+    engine: &Engine,
+    functions: Vec<ModuleFunctions<'_>>,
+) -> Result<Vec<CompileOutput>> {
+    let compiler = engine.compiler();
+    let mut modules = Vec::new();
+    let mut bodies = Vec::new();
+    for ModuleFunctions {
+        module,
+        translation,
+        types,
+        bodies: module_bodies,
+    } in functions
+    {
+        modules.push((module, translation, types, module_bodies.len()));
+        bodies.extend(
+            module_bodies
+                .into_iter()
+                .map(move |(index, body)| (module, translation, types, index, body)),
+        );
+    }
+
+    let inlining_compiler = match compiler.inlining_compiler() {
+        Some(inlining_compiler) => inlining_compiler,
+        None => {
+            return engine.run_maybe_parallel(
+                bodies,
+                |(module, translation, types, index, body)| {
+                    let (info, function) =
+                        compiler.compile_function(translation, index, body, types)?;
+                    Ok(CompileOutput::wasm_function(
+                        module,
+                        translation,
+                        index,
+                        info,
+                        function,
+                    ))
+                },
+            );
+        }
+    };
+
+    let translated =
+        engine.run_maybe_parallel(bodies, |(_module, translation, types, index, body)| {
+            inlining_compiler.translate_function(translation, index, body, types)
+        })?;
+
+    // Regroup the translations by module so that each function can look up
+    // the other functions of its module.
+    let mut translated = translated.into_iter();
+    let modules: Vec<_> = modules
+        .into_iter()
+        .map(|(module, translation, types, len)| {
+            let funcs: PrimaryMap<DefinedFuncIndex, _> = translated.by_ref().take(len).collect();
+            (module, translation, types, funcs)
+        })
+        .collect();
+
+    let inputs = modules
+        .iter()
+        .flat_map(|m| m.3.keys().map(move |index| (m, index)))
+        .collect();
+    engine.run_maybe_parallel(inputs, |((module, translation, types, funcs), index)| {
+        let (info, function) =
+            inlining_compiler.compile_translated_function(translation, index, funcs, types)?;
+        Ok(CompileOutput::wasm_function(
+            *module,
+            translation,
+            index,
+            info,
+            function,
+        ))
+    })
+}
+
 #[derive(Default)]
 pub struct UnlinkedCompileOutputs {
     // A map from kind to `CompileOutput`.
@@ -16,7 +16,7 @@ use wasmtime_environ::Tunables;
 use wasmtime_jit::profiling::{self, ProfilingAgent};
 use wasmtime_runtime::{InstanceAllocator, OnDemandInstanceAllocator, RuntimeMemoryCreator};
 
-pub use wasmtime_environ::CacheStore;
+pub use wasmtime_environ::{CacheStore, FuelCosts, Instrumentation, InstrumentationEvent};
 
 /// Represents the module instance allocation strategy to use.
 #[derive(Clone)]
@@ -111,6 +111,7 @@ pub struct Config {
     pub(crate) memory_guaranteed_dense_image_size: u64,
     pub(crate) force_memory_init_memfd: bool,
     pub(crate) wmemcheck: bool,
+    pub(crate) instrumentation: Instrumentation,
     pub(crate) coredump_on_trap: bool,
     pub(crate) macos_use_mach_ports: bool,
 }
@@ -126,7 +127,6 @@ struct CompilerConfig {
     #[cfg(any(feature = "cranelift", feature = "winch"))]
     cache_store: Option<Arc<dyn CacheStore>>,
     clif_dir: Option<std::path::PathBuf>,
-    wmemcheck: bool,
 }
 
 #[cfg(any(feature = "cranelift", feature = "winch"))]
@@ -139,7 +139,6 @@ impl CompilerConfig {
             flags: HashSet::new(),
             cache_store: None,
             clif_dir: None,
-            wmemcheck: false,
         }
     }
 
@@ -205,6 +204,7 @@ impl Config {
             memory_guaranteed_dense_image_size: 16 << 20,
             force_memory_init_memfd: false,
             wmemcheck: false,
+            instrumentation: Instrumentation::new(),
             coredump_on_trap: false,
             macos_use_mach_ports: true,
         };
@@ -250,6 +250,12 @@ impl Config {
 
     /// Enables the incremental compilation cache in Cranelift, using the provided `CacheStore`
     /// backend for storage.
+    ///
+    /// Alternatively, when the `cache` feature is enabled, the
+    /// `incremental-compilation` setting of the cache configuration loaded with
+    /// [`Config::cache_config_load`] enables a store which keeps the compiled
+    /// functions in the cache directory. A store given to this method takes
+    /// precedence over that setting.
     #[cfg(all(feature = "incremental-cache", feature = "cranelift"))]
     pub fn enable_incremental_compilation(
         &mut self,
@@ -366,6 +372,59 @@ impl Config {
         self
     }
 
+    /// Configures whether compiled code is instrumented for the interactive
+    /// debugging API in [`debug`](crate::debug).
+    ///
+    /// When enabled, every WebAssembly function records its locals and
+    /// operand stack in a frame that the host can inspect, and checks before
+    /// each instruction whether the store has requested a pause. This makes
+    /// it possible to set breakpoints with [`Store::add_breakpoint`], to
+    /// single-step with [`DebugAction::Step`], and to read the state of all
+    /// active frames with [`debug::frames`].
+    ///
+    /// This instrumentation has a significant runtime cost and is intended
+    /// for debugging only.
+    ///
+    /// By default this option is `false`.
+    ///
+    /// [`Store::add_breakpoint`]: crate::Store::add_breakpoint
+    /// [`DebugAction::Step`]: crate::debug::DebugAction::Step
+    /// [`debug::frames`]: crate::debug::frames
+    pub fn debug_instrumentation(&mut self, enable: bool) -> &mut Self {
+        self.tunables.debug_instrumentation = enable;
+        self
+    }
+
+    /// Configures which events compiled code reports to the handler
+    /// configured with
+    /// [`Store::instrumentation_handler`](crate::Store::instrumentation_handler).
+    ///
+    /// Function entry and exit, `memory.grow`, and loads and stores within
+    /// selected address ranges can each be reported, see [`Instrumentation`].
+    /// Every reported event is a call into the host, so instrumentation is
+    /// mostly intended for tracing and analysis tools. Like
+    /// [`Config::fuel_costs`], the instrumentation is part of the compiled
+    /// code, so a module can only be deserialized into an engine configured
+    /// with the same instrumentation.
+    ///
+    /// By default nothing is instrumented.
+    pub fn instrumentation(&mut self, instrumentation: Instrumentation) -> &mut Self {
+        self.instrumentation = instrumentation;
+        self.update_instrumentation();
+        self
+    }
+
+    /// Computes the instrumentation compiled into code, which is what the
+    /// user asked for plus what [`Config::wmemcheck`] needs. The store only
+    /// reports the events the user asked for to its handler.
+    fn update_instrumentation(&mut self) {
+        self.tunables.instrumentation = if self.wmemcheck {
+            self.instrumentation.union(&Instrumentation::wmemcheck())
+        } else {
+            self.instrumentation.clone()
+        };
+    }
+
     /// Configures whether [`WasmBacktrace`] will be present in the context of
     /// errors returned from Wasmtime.
     ///
@@ -462,6 +521,22 @@ impl Config {
         self
     }
 
+    /// Configures how much fuel each WebAssembly operator consumes when
+    /// [`Config::consume_fuel`] is enabled.
+    ///
+    /// This allows charging expensive operators, such as memory accesses,
+    /// indirect calls, `memory.grow` or continuation switches, more than
+    /// cheap ones. The cost model is part of the compiled code, so modules
+    /// compiled with different costs are cached separately, and can't be
+    /// deserialized into an engine with different costs.
+    ///
+    /// By default every operator costs one unit of fuel, except for `nop`,
+    /// `drop` and structured control flow which are free.
+    pub fn fuel_costs(&mut self, costs: FuelCosts) -> &mut Self {
+        self.tunables.fuel_costs = costs;
+        self
+    }
+
     /// Enables epoch-based interruption.
     ///
     /// When executing code in async mode, we sometimes want to
@@ -867,13 +942,28 @@ impl Config {
     /// Configures whether the WebAssembly exception handling
     /// [proposal] will be enabled for compilation.
     ///
-    /// Note that this feature is a work-in-progress and is incomplete.
+    /// The proposal introduces the `try`, `catch`, `catch_all`, `throw`,
+    /// `rethrow` and `delegate` instructions. Exceptions which escape to the
+    /// host are reported as an [`Exception`](crate::Exception) error, and
+    /// host functions may throw one by returning it as their error.
+    ///
+    /// Exceptions are propagated by unwinding the frames between the thrower
+    /// and the handler, so calls outside of `try` blocks cost nothing, while
+    /// calls within them are followed by a check for a pending exception.
+    /// Compiled code uses the tail calling convention, like it does with the
+    /// tail call proposal enabled. This feature also requires the reference
+    /// types proposal to be enabled, since caught exceptions are tracked like
+    /// `externref` values.
+    ///
+    /// Modules which import or export tags are not supported yet, and fail to
+    /// compile.
     ///
     /// This is `false` by default.
     ///
     /// [proposal]: https://github.com/WebAssembly/exception-handling
     pub fn wasm_exceptions(&mut self, enable: bool) -> &mut Self {
         self.features.exceptions = enable;
+        self.tunables.exceptions = enable;
         self
     }
 
@@ -1101,6 +1191,30 @@ impl Config {
         Ok(self)
     }
 
+    /// Enables caching of compiled modules in a custom backend.
+    ///
+    /// This is an alternative to [`Config::cache_config_load`] for embedders
+    /// who want to store compiled artifacts somewhere other than a local
+    /// directory, for example in a store shared between several machines or
+    /// in memory. Every call to [`Module::new`](crate::Module::new) then looks
+    /// up `backend` first, and inserts the compiled artifacts into it on a miss.
+    /// The [`cache`](crate::cache) module provides an in-memory backend.
+    ///
+    /// Artifacts are handed to `backend` uncompressed, and no worker thread is
+    /// spawned to clean it up, so `backend` is responsible for evicting data.
+    ///
+    /// This method is only available when the `cache` feature of this crate is
+    /// enabled.
+    #[cfg(feature = "cache")]
+    #[cfg_attr(nightlydoc, doc(cfg(feature = "cache")))]
+    pub fn module_cache_backend(
+        &mut self,
+        backend: Arc<dyn crate::cache::CacheBackend>,
+    ) -> &mut Self {
+        self.cache_config = CacheConfig::new_with_backend(backend);
+        self
+    }
+
     /// Sets a custom memory creator.
     ///
     /// Custom memory creators are used when creating host `Memory` objects or when
@@ -1512,11 +1626,15 @@ impl Config {
 
     /// Enables memory error checking for wasm programs.
     ///
+    /// The checker is built on [`Config::instrumentation`]: every load and
+    /// store, `memory.grow`, and call to `malloc` and `free` is reported to
+    /// it, and an invalid access traps.
+    ///
     /// This option is disabled by default.
     #[cfg(any(feature = "cranelift", feature = "winch"))]
     pub fn wmemcheck(&mut self, enable: bool) -> &mut Self {
         self.wmemcheck = enable;
-        self.compiler_config.wmemcheck = enable;
+        self.update_instrumentation();
         self
     }
 
@@ -1567,6 +1685,9 @@ impl Config {
         if self.features.threads && !self.features.bulk_memory {
             bail!("feature 'threads' requires 'bulk_memory' to be enabled");
         }
+        if self.features.exceptions && !self.features.reference_types {
+            bail!("feature 'exceptions' requires 'reference_types' to be enabled");
+        }
         #[cfg(feature = "async")]
         if self.async_support && self.max_wasm_stack > self.async_stack_size {
             bail!("max_wasm_stack size cannot exceed the async_stack_size");
@@ -1666,14 +1787,6 @@ impl Config {
                 .insert("enable_probestack".into());
         }
 
-        if self.features.tail_call {
-            ensure!(
-                target.architecture != Architecture::S390x,
-                "Tail calls are not supported on s390x yet: \
-                 https://github.com/bytecodealliance/wasmtime/issues/6530"
-            );
-        }
-
         if self.native_unwind_info ||
              // Windows always needs unwind info, since it is part of the ABI.
              target.operating_system == target_lexicon::OperatingSystem::Windows
@@ -1715,12 +1828,28 @@ impl Config {
             compiler.enable(flag)?;
         }
 
+        // Keep Cranelift's incremental compilation cache in the cache
+        // directory if requested by the cache configuration, unless a store
+        // has been given explicitly.
+        #[cfg(all(
+            feature = "cache",
+            feature = "incremental-cache",
+            feature = "cranelift"
+        ))]
+        if self.compiler_config.cache_store.is_none()
+            && !matches!(self.compiler_config.strategy, Strategy::Winch)
+            && self.cache_config.incremental_compilation()
+        {
+            self.compiler_config.cache_store = Some(Arc::new(
+                wasmtime_cache::IncrementalCacheStore::new(&self.cache_config),
+            ));
+        }
+
         if let Some(cache_store) = &self.compiler_config.cache_store {
             compiler.enable_incremental_compilation(cache_store.clone())?;
         }
 
         compiler.set_tunables(self.tunables.clone())?;
-        compiler.wmemcheck(self.compiler_config.wmemcheck);
 
         Ok((self, compiler.build()?))
     }
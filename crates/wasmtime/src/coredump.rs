@@ -1,6 +1,10 @@
 use std::fmt;
 
-use crate::{store::StoreOpaque, FrameInfo, Global, Instance, Memory, Module, WasmBacktrace};
+use crate::{
+    store::StoreOpaque, AsContextMut, Extern, FrameInfo, Global, Instance, Memory, MemoryType,
+    Module, Mutability, Val, WasmBacktrace,
+};
+use wasm_encoder::{CoreDumpValue, Encode, SectionId};
 
 /// Representation of a core dump of a WebAssembly module
 ///
@@ -12,9 +16,12 @@ use crate::{store::StoreOpaque, FrameInfo, Global, Instance, Memory, Module, Was
 /// error returned this will get printed along with the rest of the error when
 /// the error is logged.
 ///
-/// Note that some state, such as Wasm locals or values on the operand stack,
-/// may be optimized away by the compiler or otherwise not recovered in the
-/// coredump.
+/// Note that Wasm locals and values on the operand stack are only recovered
+/// for code compiled with
+/// [`Config::debug_instrumentation`][crate::Config::debug_instrumentation].
+/// They are the values at the time the frame last called another function,
+/// or, for the innermost frame, at the time it trapped in a call to the host
+/// or in an `unreachable` instruction.
 ///
 /// Capturing of wasm coredumps can be configured through the
 /// [`Config::coredump_on_trap`][crate::Config::coredump_on_trap] method.
@@ -31,15 +38,38 @@ pub struct WasmCoreDump {
     store_memories: Vec<Memory>,
     store_globals: Vec<Global>,
     backtrace: WasmBacktrace,
+    /// The locals and operand stack of each frame of `backtrace`.
+    frame_values: Vec<(Vec<CoreDumpValue>, Vec<CoreDumpValue>)>,
 }
 
 impl WasmCoreDump {
-    pub(crate) fn new(store: &StoreOpaque, backtrace: WasmBacktrace) -> WasmCoreDump {
+    /// Creates a core dump of `store`, where `values` are the function
+    /// index, locals and operand stack of each frame of instrumented code,
+    /// innermost first.
+    pub(crate) fn new(
+        store: &StoreOpaque,
+        backtrace: WasmBacktrace,
+        values: impl IntoIterator<Item = (u32, (Vec<CoreDumpValue>, Vec<CoreDumpValue>))>,
+    ) -> WasmCoreDump {
         let modules: Vec<_> = store.modules().all_modules().cloned().collect();
         let instances: Vec<Instance> = store.all_instances().collect();
         let store_memories: Vec<Memory> = store.all_memories().collect();
         let store_globals: Vec<Global> = store.all_globals().collect();
 
+        // Frames of code compiled without debug instrumentation have no
+        // values, so values are matched with frames by function index.
+        let mut values = values.into_iter().peekable();
+        let frame_values = backtrace
+            .frames()
+            .iter()
+            .map(|frame| {
+                values
+                    .next_if(|(func_index, _)| *func_index == frame.func_index())
+                    .map(|(_, values)| values)
+                    .unwrap_or_default()
+            })
+            .collect();
+
         WasmCoreDump {
             name: String::from("store_name"),
             modules,
@@ -47,6 +77,7 @@ impl WasmCoreDump {
             store_memories,
             store_globals,
             backtrace,
+            frame_values,
         }
     }
 
@@ -76,6 +107,209 @@ impl WasmCoreDump {
     pub fn store_memories(&self) -> &[Memory] {
         self.store_memories.as_ref()
     }
+
+    /// Serializes this core dump into the [standard core dump binary
+    /// format], with `name` as the name of the program that dumped core.
+    ///
+    /// The dump records the modules and instances involved, the stack
+    /// frames, and the current contents of every instance's memories and
+    /// globals, read from `store`, which must be the store this core dump was
+    /// taken from. Memory pages which are all zeros are left out, and
+    /// references are recorded as null references.
+    ///
+    /// Frames are attributed to the first instance of their module. Their
+    /// locals and operand stacks are recorded when they were recovered, see
+    /// [`WasmCoreDump`], with values of types the format can't represent,
+    /// such as `v128`, recorded as missing.
+    ///
+    /// [standard core dump binary format]:
+    ///     https://github.com/WebAssembly/tool-conventions/blob/main/Coredump.md
+    pub fn serialize(&self, mut store: impl AsContextMut, name: &str) -> Vec<u8> {
+        let mut store = store.as_context_mut();
+
+        let mut memories = wasm_encoder::MemorySection::new();
+        let mut data = wasm_encoder::DataSection::new();
+        let mut globals = wasm_encoder::GlobalSection::new();
+
+        let mut modules = Vec::new();
+        (self.modules.len() as u32).encode(&mut modules);
+        for module in self.modules.iter() {
+            modules.push(0x00);
+            module.name().unwrap_or("<module>").encode(&mut modules);
+        }
+
+        let mut instances = Vec::new();
+        let mut instance_modules = Vec::new();
+        (self.instances.len() as u32).encode(&mut instances);
+        for instance in self.instances.iter() {
+            let handle = instance.handle(store.0);
+            let module_id = handle.module_id();
+            let num_memories = handle.module().memory_plans.len() as u32;
+            let num_globals = handle.module().globals.len() as u32;
+            let module_index = self
+                .modules
+                .iter()
+                .position(|m| Some(m.compiled_module().unique_id()) == module_id)
+                .unwrap_or(0);
+            instance_modules.push(module_id);
+
+            let mut memory_indices = Vec::new();
+            for index in 0..num_memories {
+                memory_indices.push(memories.len());
+                match instance.memory_by_index(store.0, index) {
+                    Some(Extern::Memory(memory)) => {
+                        let ty = memory.ty(&store);
+                        let pages = memory.size(&store);
+                        let contents = memory.data(&store);
+                        add_memory(&mut memories, &mut data, ty, pages, contents);
+                    }
+                    Some(Extern::SharedMemory(memory)) => {
+                        let contents = memory
+                            .data()
+                            .iter()
+                            .map(|byte| unsafe { *byte.get() })
+                            .collect::<Vec<_>>();
+                        add_memory(
+                            &mut memories,
+                            &mut data,
+                            memory.ty(),
+                            memory.size(),
+                            &contents,
+                        );
+                    }
+                    _ => unreachable!(),
+                }
+            }
+
+            let mut global_indices = Vec::new();
+            for index in 0..num_globals {
+                global_indices.push(globals.len());
+                let global = instance.global_by_index(store.0, index).unwrap();
+                let mutable = global.ty(&store).mutability() == Mutability::Var;
+                let value = global.get(&mut store);
+                add_global(&mut globals, mutable, value);
+            }
+
+            instances.push(0x00);
+            (module_index as u32).encode(&mut instances);
+            memory_indices.encode(&mut instances);
+            global_indices.encode(&mut instances);
+        }
+
+        let mut stack = wasm_encoder::CoreDumpStackSection::new("main");
+        for (frame, (locals, operand_stack)) in self.frames().iter().zip(&self.frame_values) {
+            let instance_index = instance_modules
+                .iter()
+                .position(|id| *id == Some(frame.module_id()))
+                .unwrap_or(0);
+            stack.frame(
+                instance_index as u32,
+                frame.func_index(),
+                u32::try_from(frame.func_offset().unwrap_or(0)).unwrap(),
+                locals.iter().cloned(),
+                operand_stack.iter().cloned(),
+            );
+        }
+
+        let mut module = wasm_encoder::Module::new();
+        module.section(&wasm_encoder::CoreDumpSection::new(name));
+        module.section(&custom_section("coremodules", &modules));
+        module.section(&custom_section("coreinstances", &instances));
+        module.section(&stack);
+        module.section(&memories);
+        module.section(&globals);
+        module.section(&data);
+        module.finish()
+    }
+}
+
+/// Encodes the payload of a custom section named `name`, which can be added
+/// to a module with `wasm_encoder::RawSection`.
+fn custom_section(name: &str, data: &[u8]) -> CustomSectionBytes {
+    let mut bytes = Vec::new();
+    name.encode(&mut bytes);
+    bytes.extend_from_slice(data);
+    CustomSectionBytes(bytes)
+}
+
+struct CustomSectionBytes(Vec<u8>);
+
+impl wasm_encoder::Section for CustomSectionBytes {
+    fn id(&self) -> u8 {
+        SectionId::Custom as u8
+    }
+}
+
+impl Encode for CustomSectionBytes {
+    fn encode(&self, sink: &mut Vec<u8>) {
+        self.0.encode(sink);
+    }
+}
+
+/// The size of the chunks of memory that are dumped as separate data
+/// segments, allowing chunks of zeros to be left out.
+const CHUNK_SIZE: usize = wasmtime_environ::WASM_PAGE_SIZE as usize;
+
+fn add_memory(
+    memories: &mut wasm_encoder::MemorySection,
+    data: &mut wasm_encoder::DataSection,
+    ty: MemoryType,
+    pages: u64,
+    contents: &[u8],
+) {
+    let index = memories.len();
+    memories.memory(wasm_encoder::MemoryType {
+        minimum: pages,
+        maximum: ty.maximum(),
+        memory64: ty.is_64(),
+        shared: ty.is_shared(),
+    });
+    for (i, chunk) in contents.chunks(CHUNK_SIZE).enumerate() {
+        if chunk.iter().all(|byte| *byte == 0) {
+            continue;
+        }
+        let offset = (i * CHUNK_SIZE) as u64;
+        let offset = if ty.is_64() {
+            wasm_encoder::ConstExpr::i64_const(offset as i64)
+        } else {
+            wasm_encoder::ConstExpr::i32_const(offset as i32)
+        };
+        data.active(index, &offset, chunk.iter().copied());
+    }
+}
+
+fn add_global(globals: &mut wasm_encoder::GlobalSection, mutable: bool, value: Val) {
+    let (val_type, init) = match value {
+        Val::I32(x) => (
+            wasm_encoder::ValType::I32,
+            wasm_encoder::ConstExpr::i32_const(x),
+        ),
+        Val::I64(x) => (
+            wasm_encoder::ValType::I64,
+            wasm_encoder::ConstExpr::i64_const(x),
+        ),
+        Val::F32(x) => (
+            wasm_encoder::ValType::F32,
+            wasm_encoder::ConstExpr::f32_const(f32::from_bits(x)),
+        ),
+        Val::F64(x) => (
+            wasm_encoder::ValType::F64,
+            wasm_encoder::ConstExpr::f64_const(f64::from_bits(x)),
+        ),
+        Val::V128(x) => (
+            wasm_encoder::ValType::V128,
+            wasm_encoder::ConstExpr::v128_const(x as i128),
+        ),
+        Val::FuncRef(_) => (
+            wasm_encoder::ValType::Ref(wasm_encoder::RefType::FUNCREF),
+            wasm_encoder::ConstExpr::ref_null(wasm_encoder::HeapType::Func),
+        ),
+        Val::ExternRef(_) => (
+            wasm_encoder::ValType::Ref(wasm_encoder::RefType::EXTERNREF),
+            wasm_encoder::ConstExpr::ref_null(wasm_encoder::HeapType::Extern),
+        ),
+    };
+    globals.global(wasm_encoder::GlobalType { val_type, mutable }, &init);
 }
 
 impl fmt::Display for WasmCoreDump {
@@ -0,0 +1,276 @@
+//! Interactive debugging of WebAssembly code.
+//!
+//! This module allows pausing WebAssembly execution at breakpoints or after
+//! every instruction, and inspecting the state of all active WebAssembly
+//! frames while paused. It requires code to be compiled with
+//! [`Config::debug_instrumentation`](crate::Config::debug_instrumentation)
+//! enabled.
+//!
+//! Breakpoints are set with [`Store::add_breakpoint`], by function index and
+//! module offset of an instruction, and single-stepping is started with
+//! [`Store::single_step`]. Whenever execution pauses, the handler configured
+//! with [`Store::debug_handler`] is invoked with a [`DebugEvent`] and can use
+//! [`frames`] to read the locals, operand stack and globals of every frame.
+//! The handler then returns a [`DebugAction`] saying whether to pause again
+//! after the next instruction.
+//!
+//! ```
+//! use wasmtime::*;
+//! use wasmtime::debug::{DebugAction, DebugEvent};
+//!
+//! # fn main() -> Result<()> {
+//! let mut config = Config::new();
+//! config.debug_instrumentation(true);
+//! let engine = Engine::new(&config)?;
+//! let module = Module::new(
+//!     &engine,
+//!     r#"(module (func (export "add") (param i32 i32) (result i32)
+//!         local.get 0
+//!         local.get 1
+//!         i32.add))"#,
+//! )?;
+//! let mut store = Store::new(&engine, Vec::new());
+//! store.debug_handler(|mut caller, _event: DebugEvent| {
+//!     let frame = &wasmtime::debug::frames(&caller)[0];
+//!     let offset = frame.offset();
+//!     caller.data_mut().push(offset);
+//!     Ok(DebugAction::Step)
+//! });
+//! store.single_step(true);
+//! let instance = Instance::new(&mut store, &module, &[])?;
+//! let add = instance.get_typed_func::<(i32, i32), i32>(&mut store, "add")?;
+//! assert_eq!(add.call(&mut store, (1, 2))?, 3);
+//! // Execution paused before each of the four instructions of `add`.
+//! assert_eq!(store.data().len(), 4);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`Store::add_breakpoint`]: crate::Store::add_breakpoint
+//! [`Store::single_step`]: crate::Store::single_step
+//! [`Store::debug_handler`]: crate::Store::debug_handler
+
+use crate::{AsContext, AsContextMut, Global, Instance};
+use std::collections::HashSet;
+use std::fmt;
+use wasmtime_environ::{VMDebugValueType, VMDEBUG_FRAME_VALUES_UNAVAILABLE};
+use wasmtime_runtime::{CompiledModuleId, VMDebugFrame, VMRuntimeLimits};
+
+/// The reason WebAssembly execution paused and invoked the
+/// [`Store::debug_handler`](crate::Store::debug_handler).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DebugEvent {
+    /// Execution reached an instruction which has a breakpoint.
+    Breakpoint,
+    /// Execution reached the next instruction while single-stepping.
+    Step,
+}
+
+/// What to do when WebAssembly execution resumes after being paused.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DebugAction {
+    /// Resume execution until the next breakpoint.
+    Continue,
+    /// Resume execution and pause again before the next instruction, which
+    /// may be in a callee or in the caller after a return.
+    Step,
+}
+
+/// The value of a local or operand stack slot of a [`DebugFrame`].
+///
+/// References other than `externref`s are indistinguishable from integers in
+/// compiled code, and show up as an `I32` or `I64` of the pointer width of
+/// the host.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DebugValue {
+    /// An `i32` value.
+    I32(i32),
+    /// An `i64` value.
+    I64(i64),
+    /// An `f32` value, as its bits.
+    F32(u32),
+    /// An `f64` value, as its bits.
+    F64(u64),
+    /// A `v128` value.
+    V128(u128),
+    /// An `externref` value, whose contents can't be inspected since it may
+    /// have been garbage collected while execution is paused.
+    ExternRef,
+}
+
+/// The state of an active WebAssembly function.
+///
+/// The values of locals and operand stack slots are those at the time
+/// execution paused or, for frames which are not the innermost one, at the
+/// time the frame called the next one. At a call, the operand stack still
+/// includes the arguments of the call. Both are empty if they weren't
+/// recorded at the instruction the frame is executing, which only happens
+/// when it can't trap or call the host.
+pub struct DebugFrame {
+    instance: Instance,
+    func_index: u32,
+    offset: u32,
+    locals: Vec<DebugValue>,
+    stack: Vec<DebugValue>,
+}
+
+impl DebugFrame {
+    /// Returns the instance the function of this frame belongs to.
+    pub fn instance(&self) -> Instance {
+        self.instance
+    }
+
+    /// Returns the index of the function of this frame, in the function index
+    /// space of its module.
+    pub fn func_index(&self) -> u32 {
+        self.func_index
+    }
+
+    /// Returns the offset, within the original module, of the instruction
+    /// this frame is executing.
+    pub fn offset(&self) -> u32 {
+        self.offset
+    }
+
+    /// Returns the values of the locals of this frame, starting with its
+    /// parameters.
+    pub fn locals(&self) -> &[DebugValue] {
+        &self.locals
+    }
+
+    /// Returns the values on the operand stack of this frame, from the bottom
+    /// to the top of the stack.
+    pub fn stack(&self) -> &[DebugValue] {
+        &self.stack
+    }
+
+    /// Returns the global with the given index in the global index space of
+    /// this frame's instance, whether it's exported or not, or `None` if
+    /// there's no such global.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `store` does not own this frame's instance.
+    pub fn global(&self, mut store: impl AsContextMut, index: u32) -> Option<Global> {
+        self.instance
+            .global_by_index(store.as_context_mut().0, index)
+    }
+}
+
+impl fmt::Debug for DebugFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DebugFrame")
+            .field("func_index", &self.func_index)
+            .field("offset", &self.offset)
+            .field("locals", &self.locals)
+            .field("stack", &self.stack)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Returns the active WebAssembly frames of `store`, innermost first.
+///
+/// This is meant to be called from the
+/// [`Store::debug_handler`](crate::Store::debug_handler), but can also be
+/// called from host functions invoked by WebAssembly. It returns nothing when
+/// no WebAssembly is executing, or when code isn't compiled with
+/// [`Config::debug_instrumentation`](crate::Config::debug_instrumentation).
+///
+/// Frames of functions running in a continuation only include those of
+/// that continuation.
+pub fn frames(store: impl AsContext) -> Vec<DebugFrame> {
+    let store = store.as_context().0;
+    let mut frames = Vec::new();
+    unsafe {
+        let mut ptr = *store.runtime_limits().debug_frame.get() as *const VMDebugFrame;
+        while let Some(frame) = ptr.as_ref() {
+            frames.extend(read_frame(frame));
+            ptr = frame.prev;
+        }
+    }
+    frames
+}
+
+/// Reads a frame maintained by instrumented code, which must be live.
+unsafe fn read_frame(frame: &VMDebugFrame) -> Option<DebugFrame> {
+    let instance = wasmtime_runtime::Instance::from_vmctx(frame.vmctx, |instance| {
+        instance.host_state().downcast_ref::<Instance>().copied()
+    })?;
+    let num_values = match frame.num_values {
+        VMDEBUG_FRAME_VALUES_UNAVAILABLE => 0,
+        n => n as usize,
+    };
+    let mut values = (0..num_values).map(|i| {
+        let value = frame.values.add(i).cast::<u8>();
+        match VMDebugValueType::from_u8(*frame.types.add(i)) {
+            Some(VMDebugValueType::I32) => DebugValue::I32(value.cast::<i32>().read_unaligned()),
+            Some(VMDebugValueType::I64) => DebugValue::I64(value.cast::<i64>().read_unaligned()),
+            Some(VMDebugValueType::F32) => DebugValue::F32(value.cast::<u32>().read_unaligned()),
+            Some(VMDebugValueType::F64) => DebugValue::F64(value.cast::<u64>().read_unaligned()),
+            Some(VMDebugValueType::V128) => DebugValue::V128(value.cast::<u128>().read_unaligned()),
+            Some(VMDebugValueType::ExternRef) => DebugValue::ExternRef,
+            None => unreachable!("invalid debug value type"),
+        }
+    });
+    let locals = values.by_ref().take(frame.num_locals as usize).collect();
+    let stack = values.collect();
+    Some(DebugFrame {
+        instance,
+        func_index: frame.func_index,
+        offset: frame.offset,
+        locals,
+        stack,
+    })
+}
+
+/// The breakpoints and single-stepping state of a store.
+#[derive(Default)]
+pub(crate) struct DebugState {
+    breakpoints: HashSet<(CompiledModuleId, u32, u32)>,
+    single_step: bool,
+}
+
+impl DebugState {
+    /// Returns why execution should pause at the innermost frame of
+    /// `limits`, if it should.
+    pub(crate) unsafe fn event(&self, limits: &VMRuntimeLimits) -> Option<DebugEvent> {
+        let frame = &*(*limits.debug_frame.get() as *const VMDebugFrame);
+        let module =
+            wasmtime_runtime::Instance::from_vmctx(frame.vmctx, |instance| instance.module_id());
+        if let Some(module) = module {
+            if self
+                .breakpoints
+                .contains(&(module, frame.func_index, frame.offset))
+            {
+                return Some(DebugEvent::Breakpoint);
+            }
+        }
+        if self.single_step {
+            return Some(DebugEvent::Step);
+        }
+        None
+    }
+
+    pub(crate) fn add_breakpoint(&mut self, module: CompiledModuleId, func: u32, offset: u32) {
+        self.breakpoints.insert((module, func, offset));
+    }
+
+    pub(crate) fn remove_breakpoint(
+        &mut self,
+        module: CompiledModuleId,
+        func: u32,
+        offset: u32,
+    ) -> bool {
+        self.breakpoints.remove(&(module, func, offset))
+    }
+
+    pub(crate) fn set_single_step(&mut self, enable: bool) {
+        self.single_step = enable;
+    }
+
+    /// Whether instrumented code must call into the store before every
+    /// instruction.
+    pub(crate) fn armed(&self) -> bool {
+        self.single_step || !self.breakpoints.is_empty()
+    }
+}
@@ -424,6 +424,11 @@ impl Engine {
             | "tls_model" // wasmtime doesn't use tls right now
             | "opt_level" // opt level doesn't change semantics
             | "enable_alias_analysis" // alias analysis-based opts don't change semantics
+            | "enable_inlining" // inlining doesn't change semantics
+            | "inlining_callee_size_limit"
+            | "inlining_caller_size_log2"
+            | "enable_loop_opts" // loop optimizations don't change semantics
+            | "loop_unroll_factor"
             | "probestack_func_adjusts_sp" // probestack above asserted disabled
             | "probestack_size_log2" // probestack above asserted disabled
             | "regalloc" // shouldn't change semantics
@@ -520,7 +525,21 @@ impl Engine {
             }
         }
 
-        #[cfg(target_arch = "riscv64")]
+        #[cfg(all(target_arch = "riscv64", target_os = "linux"))]
+        {
+            enabled = match flag {
+                // Vector instructions are required for Wasm SIMD, so make sure
+                // the host actually supports them.
+                "has_v" => Some(riscv64_host_has_v()),
+                // make sure `test_isa_flags_mismatch` test pass.
+                "not_a_flag" => None,
+                // due to `is_riscv64_feature_detected` is not stable.
+                // we cannot use it.
+                _ => Some(true),
+            }
+        }
+
+        #[cfg(all(target_arch = "riscv64", not(target_os = "linux")))]
         {
             enabled = match flag {
                 // make sure `test_isa_flags_mismatch` test pass.
@@ -666,14 +685,48 @@ pub enum Precompiled {
     Component,
 }
 
+/// Detects whether the host supports the RISC-V V extension.
+///
+/// There is no `is_riscv64_feature_detected` macro yet, so this uses the HWCAP
+/// bits from `getauxval`. Some emulators (QEMU < 8.1) don't report `v` there,
+/// so fall back to the `isa` line of `/proc/cpuinfo` in the same way that
+/// `cranelift-native` does.
+#[cfg(all(target_arch = "riscv64", target_os = "linux"))]
+fn riscv64_host_has_v() -> bool {
+    const HWCAP_RISCV_EXT_V: libc::c_ulong = 1 << (b'v' - b'a');
+
+    let hwcap = unsafe { libc::getauxval(libc::AT_HWCAP) };
+    if (hwcap & HWCAP_RISCV_EXT_V) != 0 {
+        return true;
+    }
+
+    let cpuinfo = match std::fs::read_to_string("/proc/cpuinfo") {
+        Ok(cpuinfo) => cpuinfo,
+        Err(_) => return false,
+    };
+    cpuinfo
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(key, _)| key.trim_end() == "isa")
+        .and_then(|(_, isa)| {
+            // Single letter extensions directly follow the `rv64` prefix, up
+            // to the first multi-letter extension, e.g. `rv64imafdcv_zba`.
+            let letters = isa.trim().split('_').next()?.strip_prefix("rv64")?;
+            Some(letters.contains('v'))
+        })
+        .unwrap_or(false)
+}
+
 #[cfg(test)]
 mod tests {
     use std::{
         collections::hash_map::DefaultHasher,
         hash::{Hash, Hasher},
+        sync::Arc,
     };
 
-    use crate::{Config, Engine, Module, ModuleVersionStrategy, OptLevel};
+    use crate::cache::InMemoryCacheBackend;
+    use crate::{Config, Engine, FuelCosts, Module, ModuleVersionStrategy, OptLevel};
 
     use anyhow::Result;
     use tempfile::TempDir;
@@ -737,6 +790,61 @@ mod tests {
         assert_eq!(engine.config().cache_config.cache_hits(), 1);
         assert_eq!(engine.config().cache_config.cache_misses(), 1);
 
+        let mut costs = FuelCosts::new();
+        costs.call(10);
+        let mut cfg = Config::new();
+        cfg.consume_fuel(true)
+            .fuel_costs(costs)
+            .cache_config_load(&config_path)?;
+        let engine = Engine::new(&cfg)?;
+        Module::new(&engine, "(module (func))")?;
+        assert_eq!(engine.config().cache_config.cache_hits(), 0);
+        assert_eq!(engine.config().cache_config.cache_misses(), 1);
+        Module::new(&engine, "(module (func))")?;
+        assert_eq!(engine.config().cache_config.cache_hits(), 1);
+        assert_eq!(engine.config().cache_config.cache_misses(), 1);
+
+        let mut cfg = Config::new();
+        cfg.consume_fuel(true).cache_config_load(&config_path)?;
+        let engine = Engine::new(&cfg)?;
+        Module::new(&engine, "(module (func))")?;
+        assert_eq!(engine.config().cache_config.cache_hits(), 0);
+        assert_eq!(engine.config().cache_config.cache_misses(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn custom_module_cache_backend() -> Result<()> {
+        let backend = Arc::new(InMemoryCacheBackend::new());
+        let mut cfg = Config::new();
+        cfg.module_cache_backend(backend.clone());
+        let engine = Engine::new(&cfg)?;
+        Module::new(&engine, "(module (func))")?;
+        assert_eq!(engine.config().cache_config.cache_hits(), 0);
+        assert_eq!(engine.config().cache_config.cache_misses(), 1);
+        Module::new(&engine, "(module (func))")?;
+        assert_eq!(engine.config().cache_config.cache_hits(), 1);
+        assert_eq!(engine.config().cache_config.cache_misses(), 1);
+        assert_eq!(backend.len(), 1);
+
+        // Another engine with the same settings shares the artifacts.
+        let mut cfg = Config::new();
+        cfg.module_cache_backend(backend.clone());
+        let engine = Engine::new(&cfg)?;
+        Module::new(&engine, "(module (func))")?;
+        assert_eq!(engine.config().cache_config.cache_hits(), 1);
+        assert_eq!(engine.config().cache_config.cache_misses(), 0);
+
+        let mut cfg = Config::new();
+        cfg.cranelift_opt_level(OptLevel::None)
+            .module_cache_backend(backend.clone());
+        let engine = Engine::new(&cfg)?;
+        Module::new(&engine, "(module (func))")?;
+        assert_eq!(engine.config().cache_config.cache_misses(), 1);
+        assert_eq!(backend.len(), 2);
+
         Ok(())
     }
 
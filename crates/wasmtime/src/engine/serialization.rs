@@ -332,11 +332,15 @@ impl Metadata {
             generate_native_debuginfo,
             parse_wasm_debuginfo,
             consume_fuel,
+            fuel_costs,
             epoch_interruption,
             static_memory_bound_is_maximum,
             guard_before_linear_memory,
             relaxed_simd_deterministic,
             tail_callable,
+            exceptions,
+            debug_instrumentation,
+            ref instrumentation,
 
             // This doesn't affect compilation, it's just a runtime setting.
             dynamic_memory_growth_reserve: _,
@@ -377,6 +381,9 @@ impl Metadata {
             "WebAssembly backtrace support",
         )?;
         Self::check_bool(consume_fuel, other.consume_fuel, "fuel support")?;
+        if fuel_costs != other.fuel_costs {
+            bail!("Module was compiled with different fuel costs than the host");
+        }
         Self::check_bool(
             epoch_interruption,
             other.epoch_interruption,
@@ -398,6 +405,15 @@ impl Metadata {
             "relaxed simd deterministic semantics",
         )?;
         Self::check_bool(tail_callable, other.tail_callable, "WebAssembly tail calls")?;
+        Self::check_bool(exceptions, other.exceptions, "WebAssembly exceptions")?;
+        Self::check_bool(
+            debug_instrumentation,
+            other.debug_instrumentation,
+            "debug instrumentation",
+        )?;
+        if *instrumentation != other.instrumentation {
+            bail!("Module was compiled with different instrumentation than the host");
+        }
 
         Ok(())
     }
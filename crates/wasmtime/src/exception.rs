@@ -0,0 +1,183 @@
+use crate::store::{StoreId, StoreOpaque};
+use crate::{AsContextMut, Instance, Val, ValType};
+use anyhow::{bail, Error, Result};
+use std::fmt;
+use wasmtime_environ::TagIndex;
+use wasmtime_runtime::exception::{self, VMException};
+use wasmtime_runtime::VMExternRef;
+
+/// A WebAssembly exception, thrown by the `throw` instruction of the
+/// [exception handling proposal][proposal].
+///
+/// An exception is an instance of a tag defined by a module, which carries a
+/// payload of values whose types are the parameters of the tag. Exceptions
+/// which are not caught by WebAssembly are returned to the host as an
+/// [`anyhow::Error`] whose root cause is an `Exception`, which can be
+/// retrieved with [`downcast_ref`](anyhow::Error::downcast_ref).
+///
+/// Host functions can also throw an exception to their WebAssembly caller by
+/// returning an `Exception` as their error, which WebAssembly can then catch
+/// with `catch` and `catch_all` clauses. Any other error returned by a host
+/// function is a trap, which cannot be caught.
+///
+/// Exceptions are only enabled with
+/// [`Config::wasm_exceptions`](crate::Config::wasm_exceptions).
+///
+/// [proposal]: https://github.com/WebAssembly/exception-handling
+#[derive(Clone)]
+pub struct Exception {
+    inner: VMExternRef,
+    store: StoreId,
+}
+
+impl Exception {
+    /// Creates a new exception of the tag with index `tag` defined by
+    /// `instance`, carrying the values of `payload`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `instance` does not define the tag `tag`, or if the
+    /// types of the values of `payload` are not the parameters of the tag.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `instance` is not owned by `store`.
+    pub fn new(
+        mut store: impl AsContextMut,
+        instance: &Instance,
+        tag: u32,
+        payload: &[Val],
+    ) -> Result<Exception> {
+        let mut store = store.as_context_mut();
+        let handle = instance.handle(store.0);
+        let tag = TagIndex::from_u32(tag);
+        if !handle.module().tags.is_valid(tag) {
+            bail!("instance does not define tag {}", tag.as_u32());
+        }
+        let params = handle
+            .tag_params(tag)
+            .iter()
+            .map(ValType::from_wasm_type)
+            .collect::<Vec<_>>();
+        if params.len() != payload.len() {
+            bail!(
+                "expected {} payload values, got {}",
+                params.len(),
+                payload.len()
+            );
+        }
+        for (ty, val) in params.iter().zip(payload) {
+            if val.ty() != *ty {
+                bail!(
+                    "payload value type mismatch: expected {ty}, found {}",
+                    val.ty()
+                );
+            }
+            if !val.comes_from_same_store(store.0) {
+                bail!("cross-`Store` values are not currently supported");
+            }
+        }
+
+        let vmctx = handle.vmctx();
+        let mut raws = Vec::with_capacity(payload.len());
+        for val in payload {
+            raws.push(unsafe { val.to_raw(&mut store) });
+        }
+        let inner = unsafe {
+            wasmtime_runtime::Instance::from_vmctx(vmctx, |i| VMException::new(i, tag, raws))
+        };
+        Ok(Exception {
+            inner,
+            store: store.0.id(),
+        })
+    }
+
+    /// Returns the index of the exception's tag, within the module of the
+    /// instance which defines it.
+    pub fn tag(&self) -> u32 {
+        self.vm().tag().as_u32()
+    }
+
+    /// Is the exception an instance of the tag with index `tag` defined by
+    /// `instance`?
+    ///
+    /// # Panics
+    ///
+    /// Panics if `instance` is not owned by `store`.
+    pub fn has_tag(&self, mut store: impl AsContextMut, instance: &Instance, tag: u32) -> bool {
+        let store = store.as_context_mut().0;
+        let handle = instance.handle(store);
+        let tag = TagIndex::from_u32(tag);
+        handle.module().tags.is_valid(tag) && self.vm().has_tag(handle.tag_id(tag))
+    }
+
+    /// Returns the values of the exception's payload.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the exception was not thrown within `store`.
+    pub fn payload(&self, mut store: impl AsContextMut) -> Vec<Val> {
+        let mut store = store.as_context_mut();
+        assert!(
+            self.store == store.0.id(),
+            "exception used with the wrong store"
+        );
+        let vm = self.vm();
+        vm.param_types()
+            .iter()
+            .zip(vm.payload())
+            .map(|(ty, raw)| unsafe {
+                Val::from_raw(&mut store, *raw, ValType::from_wasm_type(ty))
+            })
+            .collect()
+    }
+
+    fn vm(&self) -> &VMException {
+        exception::downcast(&self.inner)
+    }
+}
+
+impl fmt::Debug for Exception {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Exception")
+            .field("tag", &self.tag())
+            .finish_non_exhaustive()
+    }
+}
+
+impl fmt::Display for Exception {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "uncaught exception of tag {}", self.tag())
+    }
+}
+
+impl std::error::Error for Exception {}
+
+/// Makes the exception `error`, returned by a host function, pending in
+/// `store`, so that the WebAssembly caller of the host function handles it.
+///
+/// Returns `error` back if it is not an exception, if it was thrown in another
+/// store, or if exceptions are disabled, in which case it is a trap.
+pub(crate) fn throw_from_host(store: &mut StoreOpaque, error: Error) -> Result<(), Error> {
+    if !store.engine().config().tunables.exceptions {
+        return Err(error);
+    }
+    match error.downcast_ref::<Exception>() {
+        Some(exception) if exception.store == store.id() => {
+            let inner = exception.inner.clone();
+            unsafe { exception::set_pending(store.runtime_limits(), inner) };
+            Ok(())
+        }
+        _ => Err(error),
+    }
+}
+
+/// Takes the exception which WebAssembly left pending in `store` when
+/// returning to the host, if there is one.
+pub(crate) fn take_pending(store: &mut StoreOpaque) -> Option<Exception> {
+    let inner = unsafe { exception::take_pending(store.runtime_limits())? };
+    Some(Exception {
+        inner,
+        store: store.id(),
+    })
+}
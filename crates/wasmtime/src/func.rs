@@ -1,7 +1,7 @@
 use crate::store::{StoreData, StoreOpaque, Stored};
 use crate::{
     AsContext, AsContextMut, CallHook, Engine, Extern, FuncType, Instance, Module, StoreContext,
-    StoreContextMut, Val, ValRaw, ValType,
+    StoreContextMut, Trap, Val, ValRaw, ValType,
 };
 use anyhow::{bail, Context as _, Error, Result};
 use std::ffi::c_void;
@@ -910,7 +910,7 @@ impl Func {
         params_and_returns: *mut ValRaw,
         params_and_returns_capacity: usize,
     ) -> Result<()> {
-        invoke_wasm_and_catch_traps(store, |caller| {
+        invoke_wasm_and_catch_traps(store, Some(func_ref), |caller| {
             let func_ref = func_ref.as_ref();
             (func_ref.array_call)(
                 func_ref.vmctx,
@@ -1349,18 +1349,25 @@ impl Func {
 /// things like catch traps and set up GC properly.
 ///
 /// The `closure` provided receives a default "caller" `VMContext` parameter it
-/// can pass to the called wasm function, if desired.
+/// can pass to the called wasm function, if desired. The function it calls,
+/// if known, is `func_ref`, which is used for the store's statistics.
 pub(crate) fn invoke_wasm_and_catch_traps<T>(
     store: &mut StoreContextMut<'_, T>,
+    func_ref: Option<NonNull<VMFuncRef>>,
     closure: impl FnMut(*mut VMContext),
 ) -> Result<()> {
     unsafe {
+        let export_call = func_ref.and_then(|f| store.0.stats_export_call_start(f));
         let exit = enter_wasm(store);
 
         if let Err(trap) = store.0.call_hook(CallHook::CallingWasm) {
             exit_wasm(store, exit);
             return Err(trap);
         }
+        // Frames for the debug API are only valid while their function runs,
+        // so reset the innermost one which may have been left behind by a
+        // trap or by wasm returning to the host.
+        let debug_frame = *store.0.runtime_limits().debug_frame.get();
         let result = wasmtime_runtime::catch_traps(
             store.0.signal_handler(),
             store.0.engine().config().wasm_backtrace,
@@ -1368,9 +1375,31 @@ pub(crate) fn invoke_wasm_and_catch_traps<T>(
             store.0.default_caller(),
             closure,
         );
+        *store.0.runtime_limits().debug_frame.get() = debug_frame;
         exit_wasm(store, exit);
+        // An exception which wasm didn't catch is left pending when unwinding
+        // to the host, and is dropped if wasm trapped after throwing it.
+        let exception = crate::exception::take_pending(store.0);
+        if let Some(call) = export_call {
+            store.0.stats_export_call_end(call);
+        }
         store.0.call_hook(CallHook::ReturningFromWasm)?;
-        result.map_err(|t| crate::trap::from_runtime_box(store.0, t))
+        result.map_err(|t| {
+            // Errors raised by the host, including those propagating from
+            // nested calls into wasm, were counted where they were raised.
+            let user = matches!(t.reason, wasmtime_runtime::TrapReason::User { .. });
+            let error = crate::trap::from_runtime_box(store.0, t);
+            if !user {
+                if let Some(trap) = error.downcast_ref::<Trap>() {
+                    store.0.stats_trap(*trap);
+                }
+            }
+            error
+        })?;
+        match exception {
+            Some(exception) => Err(exception.into()),
+            None => Ok(()),
+        }
     }
 }
 
@@ -1954,6 +1983,7 @@ macro_rules! impl_into_func {
                 {
                     enum CallResult<U> {
                         Ok(U),
+                        Exception,
                         Trap(anyhow::Error),
                         Panic(Box<dyn std::any::Any + Send>),
                     }
@@ -2010,7 +2040,13 @@ macro_rules! impl_into_func {
                                 } else {
                                     match ret.into_abi_for_ret(caller.store.0, retptr) {
                                         Ok(val) => CallResult::Ok(val),
-                                        Err(trap) => CallResult::Trap(trap.into()),
+                                        // An exception is left pending for the
+                                        // caller to handle, without any
+                                        // results.
+                                        Err(trap) => match crate::exception::throw_from_host(caller.store.0, trap) {
+                                            Ok(()) => CallResult::Exception,
+                                            Err(trap) => CallResult::Trap(trap),
+                                        },
                                     }
                                 }
 
@@ -2020,6 +2056,7 @@ macro_rules! impl_into_func {
 
                     match result {
                         CallResult::Ok(val) => val,
+                        CallResult::Exception => wasmtime_runtime::exception::unwind(caller_vmctx),
                         CallResult::Trap(err) => crate::trap::raise(err),
                         CallResult::Panic(panic) => wasmtime_runtime::resume_panic(panic),
                     }
@@ -2148,9 +2185,17 @@ impl HostFunc {
         let func = move |caller_vmctx, values: &mut [ValRaw]| {
             Caller::<T>::with(caller_vmctx, |mut caller| {
                 caller.store.0.call_hook(CallHook::CallingHost)?;
-                let result = func(caller.sub_caller(), values)?;
+                let outcome = match func(caller.sub_caller(), values) {
+                    Ok(()) => crate::trampoline::HostCallOutcome::Returned,
+                    // An exception is left pending for the caller to handle,
+                    // without any results.
+                    Err(error) => {
+                        crate::exception::throw_from_host(caller.store.0, error)?;
+                        crate::trampoline::HostCallOutcome::Threw
+                    }
+                };
                 caller.store.0.call_hook(CallHook::ReturningFromHost)?;
-                Ok(result)
+                Ok(outcome)
             })
         };
         let ctx = crate::trampoline::create_array_call_function(&ty, func, engine)
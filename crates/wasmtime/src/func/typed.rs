@@ -174,7 +174,7 @@ where
         // the memory go away, so the size matters here for performance.
         let mut captures = (func, MaybeUninit::uninit(), params, false);
 
-        let result = invoke_wasm_and_catch_traps(store, |caller| {
+        let result = invoke_wasm_and_catch_traps(store, Some(func), |caller| {
             let (func_ref, ret, params, returned) = &mut captures;
             let func_ref = func_ref.as_ref();
             let result =
@@ -11,8 +11,9 @@ use std::ptr::NonNull;
 use std::sync::Arc;
 use wasmtime_environ::{EntityType, FuncIndex, GlobalIndex, MemoryIndex, PrimaryMap, TableIndex};
 use wasmtime_runtime::{
-    Imports, InstanceAllocationRequest, StorePtr, VMContext, VMFuncRef, VMFunctionImport,
-    VMGlobalImport, VMMemoryImport, VMNativeCallFunction, VMOpaqueContext, VMTableImport,
+    Imports, InstanceAllocationRequest, InstanceHandle, StorePtr, VMContext, VMFuncRef,
+    VMFunctionImport, VMGlobalImport, VMMemoryImport, VMNativeCallFunction, VMOpaqueContext,
+    VMTableImport,
 };
 
 /// An instantiated WebAssembly module.
@@ -290,6 +291,7 @@ impl Instance {
         // matter in practice since initialization isn't even running any
         // code here anyway.
         let id = store.add_instance(instance_handle.clone(), false);
+        store.stats_instance_created();
 
         // Additionally, before we start doing fallible instantiation, we
         // do one more step which is to insert an `InstanceData`
@@ -334,6 +336,15 @@ impl Instance {
         Instance(stored)
     }
 
+    /// Get the runtime handle of this instance.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `store` does not own this instance.
+    pub(crate) fn handle<'a>(&self, store: &'a StoreOpaque) -> &'a InstanceHandle {
+        store.instance(store[self.0].id)
+    }
+
     pub(crate) fn from_wasmtime(handle: InstanceData, store: &mut StoreOpaque) -> Instance {
         Instance(store.store_data_mut().insert(handle))
     }
@@ -346,7 +357,7 @@ impl Instance {
         let f = instance.get_exported_func(start);
         let caller_vmctx = instance.vmctx();
         unsafe {
-            super::func::invoke_wasm_and_catch_traps(store, |_default_caller| {
+            super::func::invoke_wasm_and_catch_traps(store, None, |_default_caller| {
                 let func = mem::transmute::<
                     NonNull<VMNativeCallFunction>,
                     extern "C" fn(*mut VMOpaqueContext, *mut VMContext),
@@ -526,6 +537,36 @@ impl Instance {
         self.get_export(store, name)?.into_global()
     }
 
+    /// Returns the global with the given index in this instance's index
+    /// space, whether it's exported or not, or `None` if there's no such
+    /// global.
+    pub(crate) fn global_by_index(&self, store: &mut StoreOpaque, index: u32) -> Option<Global> {
+        let index = GlobalIndex::from_u32(index);
+        let id = store[self.0].id;
+        let instance = store.instance_mut(id);
+        if !instance.module().globals.is_valid(index) {
+            return None;
+        }
+        let export = instance.get_exported_global(index);
+        Some(unsafe { Global::from_wasmtime_global(export, store) })
+    }
+
+    /// Returns the memory with the given index in this instance's index
+    /// space, whether it's exported or not, as either an [`Extern::Memory`]
+    /// or an [`Extern::SharedMemory`], or `None` if there's no such memory.
+    pub(crate) fn memory_by_index(&self, store: &mut StoreOpaque, index: u32) -> Option<Extern> {
+        let index = MemoryIndex::from_u32(index);
+        let id = store[self.0].id;
+        let instance = store.instance_mut(id);
+        if !instance.module().memory_plans.is_valid(index) {
+            return None;
+        }
+        let export = instance.get_exported_memory(index);
+        Some(unsafe {
+            Extern::from_wasmtime_export(wasmtime_runtime::Export::Memory(export), store)
+        })
+    }
+
     #[cfg(feature = "component-model")]
     pub(crate) fn id(&self, store: &StoreOpaque) -> InstanceId {
         store[self.0].id
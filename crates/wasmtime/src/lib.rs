@@ -397,6 +397,7 @@ mod code;
 mod config;
 mod coredump;
 mod engine;
+mod exception;
 mod externals;
 mod instance;
 mod limits;
@@ -416,20 +417,22 @@ mod values;
 pub use crate::config::*;
 pub use crate::coredump::*;
 pub use crate::engine::*;
+pub use crate::exception::Exception;
 pub use crate::externals::*;
 pub use crate::func::*;
 pub use crate::instance::{Instance, InstancePre};
 pub use crate::limits::*;
 pub use crate::linker::*;
 pub use crate::memory::*;
-pub use crate::module::Module;
-pub use crate::profiling::GuestProfiler;
+pub use crate::module::{FunctionReport, Module};
+pub use crate::profiling::{GuestProfileFormat, GuestProfiler};
 pub use crate::r#ref::ExternRef;
 pub use crate::resources::*;
 #[cfg(feature = "async")]
 pub use crate::store::CallHookHandler;
 pub use crate::store::{
-    AsContext, AsContextMut, CallHook, Store, StoreContext, StoreContextMut, UpdateDeadline,
+    AsContext, AsContextMut, CallHook, Store, StoreContext, StoreContextMut, StoreSnapshot,
+    StoreStats, UpdateDeadline,
 };
 pub use crate::trap::*;
 pub use crate::types::*;
@@ -442,8 +445,12 @@ pub use crate::values::*;
 /// This type alias is identical to `anyhow::Result`.
 pub use anyhow::{Error, Result};
 
+#[cfg(feature = "cache")]
+#[cfg_attr(nightlydoc, doc(cfg(feature = "cache")))]
+pub mod cache;
 #[cfg(feature = "component-model")]
 pub mod component;
+pub mod debug;
 
 cfg_if::cfg_if! {
     if #[cfg(all(target_os = "macos", not(feature = "posix-signals-on-macos")))] {
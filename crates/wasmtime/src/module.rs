@@ -3,7 +3,7 @@ use crate::{
     resources::ResourcesRequired,
     signatures::SignatureCollection,
     types::{ExportType, ExternType, ImportType},
-    Engine,
+    Engine, FrameSymbol,
 };
 use anyhow::{bail, Context, Result};
 use once_cell::sync::OnceCell;
@@ -16,7 +16,7 @@ use std::sync::Arc;
 use wasmparser::{Parser, ValidPayload, Validator};
 use wasmtime_environ::{
     DefinedFuncIndex, DefinedMemoryIndex, HostPtr, ModuleEnvironment, ModuleTypes, ObjectKind,
-    VMOffsets,
+    TagIndex, VMOffsets, WasmType,
 };
 use wasmtime_jit::{CodeMemory, CompiledModule, CompiledModuleInfo};
 use wasmtime_runtime::{
@@ -25,8 +25,10 @@ use wasmtime_runtime::{
 };
 
 mod registry;
+mod report;
 
 pub use registry::{is_wasm_trap_pc, register_code, unregister_code, ModuleRegistry};
+pub use report::FunctionReport;
 
 /// A compiled WebAssembly module, ready to be instantiated.
 ///
@@ -1092,6 +1094,33 @@ impl Module {
             (loc.start as usize, loc.length as usize)
         })
     }
+
+    /// Returns a report about the compilation of each function defined by
+    /// this module, in order of function index.
+    ///
+    /// Each report includes the function's name, the location and size of its
+    /// body in the original binary, the size of its compiled code, and
+    /// statistics such as its compile time and the number of Cranelift
+    /// instructions before and after optimizations. See [`FunctionReport`]
+    /// for more information.
+    pub fn function_reports<'a>(&'a self) -> impl ExactSizeIterator<Item = FunctionReport> + 'a {
+        self.compiled_module()
+            .finished_functions()
+            .map(|(f, _)| FunctionReport::new(self.compiled_module(), f))
+    }
+
+    /// Looks up the source locations of the instruction at `offset` in the
+    /// original WebAssembly binary using its DWARF debug information.
+    ///
+    /// Multiple symbols are returned when the instruction was inlined into
+    /// its function by the producer, innermost first. An empty list is
+    /// returned if there's no debug information for `offset`, or if the module
+    /// wasn't compiled with
+    /// [`Config::wasm_backtrace_details`](crate::Config::wasm_backtrace_details)
+    /// enabled.
+    pub fn symbolize(&self, offset: u32) -> Vec<FrameSymbol> {
+        FrameSymbol::lookup(self.compiled_module(), offset)
+    }
 }
 
 impl ModuleInner {
@@ -1217,6 +1246,11 @@ impl wasmtime_runtime::ModuleRuntimeInfo for ModuleInner {
     fn offsets(&self) -> &VMOffsets<HostPtr> {
         &self.offsets
     }
+
+    fn tag_params(&self, index: TagIndex) -> &[WasmType] {
+        let signature = self.module.module().tags[index].signature;
+        self.code.module_types()[signature].params()
+    }
 }
 
 impl wasmtime_runtime::ModuleInfo for ModuleInner {
@@ -1243,6 +1277,17 @@ impl wasmtime_runtime::ModuleInfo for ModuleInner {
 
         Some(&info.stack_maps[index].stack_map)
     }
+
+    fn lookup_exception_landing(&self, pc: usize) -> Option<&wasmtime_environ::ExceptionLanding> {
+        let text_offset = pc - self.module.text().as_ptr() as usize;
+        let (index, func_offset) = self.module.func_by_text_offset(text_offset)?;
+        let info = self.module.wasm_func_info(index);
+        let index = info
+            .exception_landings
+            .binary_search_by_key(&func_offset, |l| l.code_offset)
+            .ok()?;
+        Some(&info.exception_landings[index])
+    }
 }
 
 /// A barebones implementation of ModuleRuntimeInfo that is useful for
@@ -1325,6 +1370,10 @@ impl wasmtime_runtime::ModuleRuntimeInfo for BareModuleInfo {
     fn offsets(&self) -> &VMOffsets<HostPtr> {
         &self.offsets
     }
+
+    fn tag_params(&self, _index: TagIndex) -> &[WasmType] {
+        unreachable!()
+    }
 }
 
 /// Helper method to construct a `ModuleMemoryImages` for an associated
@@ -0,0 +1,98 @@
+use std::time::Duration;
+use wasmtime_environ::{DefinedFuncIndex, FunctionStats};
+use wasmtime_jit::CompiledModule;
+
+/// A report about the compilation of one function of a
+/// [`Module`](crate::Module), returned by
+/// [`Module::function_reports`](crate::Module::function_reports).
+///
+/// This is intended to find which functions of a large module dominate its
+/// code size and compile time.
+#[derive(Debug, Clone)]
+pub struct FunctionReport {
+    index: u32,
+    name: Option<String>,
+    body_offset: u32,
+    code_size: u32,
+    stack_map_entries: u32,
+    stats: FunctionStats,
+}
+
+impl FunctionReport {
+    pub(crate) fn new(module: &CompiledModule, index: DefinedFuncIndex) -> FunctionReport {
+        let func_index = module.module().func_index(index);
+        let info = module.wasm_func_info(index);
+        FunctionReport {
+            index: func_index.as_u32(),
+            name: module.func_name(func_index).map(|name| name.to_string()),
+            body_offset: info.start_srcloc.file_offset().unwrap_or(0),
+            code_size: module.func_loc(index).length,
+            stack_map_entries: info.stack_maps.len() as u32,
+            stats: info.stats,
+        }
+    }
+
+    /// Returns the index of the function within the module.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// Returns the name of the function from the module's name section, if
+    /// any.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns the offset of the function's body in the original WebAssembly
+    /// binary.
+    pub fn body_offset(&self) -> u32 {
+        self.body_offset
+    }
+
+    /// Returns the size in bytes of the function's body in the original
+    /// WebAssembly binary.
+    pub fn body_size(&self) -> u32 {
+        self.stats.body_size
+    }
+
+    /// Returns the size in bytes of the function's compiled code.
+    pub fn code_size(&self) -> u32 {
+        self.code_size
+    }
+
+    /// Returns the number of instructions in the compiled code which may
+    /// trap.
+    pub fn trap_sites(&self) -> u32 {
+        self.stats.trap_sites
+    }
+
+    /// Returns the number of stack maps recorded for the compiled code, one
+    /// per GC safepoint.
+    pub fn stack_map_entries(&self) -> u32 {
+        self.stack_map_entries
+    }
+
+    /// Returns how long it took to compile the function.
+    ///
+    /// Compile times aren't serialized with modules, so this is `None` for
+    /// modules that were deserialized or loaded from the cache.
+    pub fn compile_time(&self) -> Option<Duration> {
+        self.stats.compile_time
+    }
+
+    /// Returns the number of Cranelift IR instructions the function was
+    /// translated to, before optimizations.
+    ///
+    /// This is zero for functions compiled with Winch.
+    pub fn insts_before_opt(&self) -> u32 {
+        self.stats.insts_before_opt
+    }
+
+    /// Returns the number of Cranelift IR instructions of the function after
+    /// optimizations.
+    ///
+    /// This is zero for functions compiled with Winch.
+    pub fn insts_after_opt(&self) -> u32 {
+        self.stats.insts_after_opt
+    }
+}
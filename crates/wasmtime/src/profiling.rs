@@ -5,9 +5,12 @@ use fxprof_processed_profile::{
     CategoryHandle, CpuDelta, Frame, FrameFlags, FrameInfo, LibraryInfo, Profile,
     ReferenceTimestamp, Symbol, SymbolTable, Timestamp,
 };
+use serde_json::json;
+use std::collections::HashMap;
 use std::ops::Range;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
+use wasmtime_environ::DefinedFuncIndex;
 use wasmtime_jit::CompiledModule;
 use wasmtime_runtime::Backtrace;
 
@@ -72,11 +75,47 @@ use wasmtime_runtime::Backtrace;
 /// should only include user-provided modules in profiles.
 #[derive(Debug)]
 pub struct GuestProfiler {
+    name: String,
     profile: Profile,
     modules: Vec<(Range<usize>, fxprof_processed_profile::LibraryHandle)>,
     process: fxprof_processed_profile::ProcessHandle,
     thread: fxprof_processed_profile::ThreadHandle,
     start: Instant,
+    interval: Duration,
+    /// The functions of all profiled modules, sorted by the start address of
+    /// their code, which are referred to by index from `samples`.
+    functions: Vec<GuestFunction>,
+    /// The time at which each sample was taken, and its stack with the oldest
+    /// frame first, as indices into `functions`.
+    samples: Vec<(Duration, Vec<usize>)>,
+}
+
+#[derive(Debug)]
+struct GuestFunction {
+    address: Range<usize>,
+    module: String,
+    name: String,
+}
+
+/// The file formats in which a [`GuestProfiler`] can write a profile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum GuestProfileFormat {
+    /// The JSON-formatted [Firefox "processed profile format"][fmt], which may
+    /// be visualized at <https://profiler.firefox.com/>.
+    ///
+    /// [fmt]: https://github.com/firefox-devtools/profiler/blob/main/docs-developer/processed-profile-format.md
+    Firefox,
+    /// An uncompressed [pprof] protobuf, as read by `go tool pprof` and other
+    /// pprof-compatible tools.
+    ///
+    /// [pprof]: https://github.com/google/pprof/blob/main/proto/profile.proto
+    Pprof,
+    /// The JSON-formatted [speedscope file format][fmt], which may be
+    /// visualized at <https://www.speedscope.app/>.
+    ///
+    /// [fmt]: https://github.com/jlfwong/speedscope/wiki/Importing-from-custom-sources
+    Speedscope,
 }
 
 impl GuestProfiler {
@@ -99,28 +138,44 @@ impl GuestProfiler {
         let zero = ReferenceTimestamp::from_millis_since_unix_epoch(0.0);
         let mut profile = Profile::new(module_name, zero, interval.into());
 
+        let mut functions = Vec::new();
         let mut modules: Vec<_> = modules
             .into_iter()
             .filter_map(|(name, module)| {
                 let compiled = module.compiled_module();
                 let text = compiled.text().as_ptr_range();
                 let address_range = text.start as usize..text.end as usize;
-                module_symbols(name, compiled).map(|lib| (address_range, profile.add_lib(lib)))
+                let lib = module_symbols(name.clone(), compiled)?;
+                for (defined_idx, _) in compiled.finished_functions() {
+                    let loc = compiled.func_loc(defined_idx);
+                    let start = address_range.start + loc.start as usize;
+                    functions.push(GuestFunction {
+                        address: start..start + loc.length as usize,
+                        module: name.clone(),
+                        name: function_name(compiled, defined_idx),
+                    });
+                }
+                Some((address_range, profile.add_lib(lib)))
             })
             .collect();
 
         modules.sort_unstable_by_key(|(range, _)| range.start);
+        functions.sort_unstable_by_key(|f| f.address.start);
 
         profile.set_reference_timestamp(std::time::SystemTime::now().into());
         let process = profile.add_process(module_name, 0, Timestamp::from_nanos_since_reference(0));
         let thread = profile.add_thread(process, 0, Timestamp::from_nanos_since_reference(0), true);
         let start = Instant::now();
         Self {
+            name: module_name.to_string(),
             profile,
             modules,
             process,
             thread,
             start,
+            interval,
+            functions,
+            samples: Vec::new(),
         }
     }
 
@@ -129,11 +184,28 @@ impl GuestProfiler {
     /// typically be called from a callback registered using
     /// [`Store::epoch_deadline_callback()`](crate::Store::epoch_deadline_callback).
     pub fn sample(&mut self, store: impl AsContext) {
-        let now = Timestamp::from_nanos_since_reference(
-            self.start.elapsed().as_nanos().try_into().unwrap(),
-        );
+        let elapsed = self.start.elapsed();
+        let now = Timestamp::from_nanos_since_reference(elapsed.as_nanos().try_into().unwrap());
 
         let backtrace = Backtrace::new(store.as_context().0.vmruntime_limits());
+
+        let stack = backtrace
+            .frames()
+            .rev()
+            .filter_map(|frame| {
+                // Find the last function starting at or before this PC.
+                let idx = self
+                    .functions
+                    .partition_point(|f| f.address.start <= frame.pc());
+                let idx = idx.checked_sub(1)?;
+                self.functions[idx]
+                    .address
+                    .contains(&frame.pc())
+                    .then(|| idx)
+            })
+            .collect();
+        self.samples.push((elapsed, stack));
+
         let frames = backtrace
             .frames()
             // Samply needs to see the oldest frame first, but we list the newest
@@ -168,31 +240,204 @@ impl GuestProfiler {
     /// the [Firefox "processed profile format"][fmt]. Files in this format may
     /// be visualized at <https://profiler.firefox.com/>.
     ///
+    /// Use [`GuestProfiler::finish_with_format`] to write the profile in
+    /// another format.
+    ///
     /// [fmt]: https://github.com/firefox-devtools/profiler/blob/main/docs-developer/processed-profile-format.md
-    pub fn finish(mut self, output: impl std::io::Write) -> Result<()> {
-        let now = Timestamp::from_nanos_since_reference(
-            self.start.elapsed().as_nanos().try_into().unwrap(),
-        );
-        self.profile.set_thread_end_time(self.thread, now);
-        self.profile.set_process_end_time(self.process, now);
-
-        serde_json::to_writer(output, &self.profile)?;
+    pub fn finish(self, output: impl std::io::Write) -> Result<()> {
+        self.finish_with_format(GuestProfileFormat::Firefox, output)
+    }
+
+    /// When the guest finishes running, call this function to write the
+    /// profile to the given `output` in the given `format`.
+    ///
+    /// Each sample is weighted by the time elapsed since the previous sample,
+    /// or since the profiler was created for the first sample, in the formats
+    /// which support weights.
+    pub fn finish_with_format(
+        mut self,
+        format: GuestProfileFormat,
+        mut output: impl std::io::Write,
+    ) -> Result<()> {
+        let elapsed = self.start.elapsed();
+        match format {
+            GuestProfileFormat::Firefox => {
+                let now =
+                    Timestamp::from_nanos_since_reference(elapsed.as_nanos().try_into().unwrap());
+                self.profile.set_thread_end_time(self.thread, now);
+                self.profile.set_process_end_time(self.process, now);
+
+                serde_json::to_writer(output, &self.profile)?;
+            }
+            GuestProfileFormat::Pprof => output.write_all(&self.pprof(elapsed))?,
+            GuestProfileFormat::Speedscope => {
+                serde_json::to_writer(output, &self.speedscope(elapsed))?
+            }
+        }
         Ok(())
     }
+
+    /// Returns the weight of each sample, in nanoseconds.
+    fn weights(&self) -> impl Iterator<Item = u64> + '_ {
+        let starts = std::iter::once(Duration::ZERO).chain(self.samples.iter().map(|s| s.0));
+        self.samples
+            .iter()
+            .zip(starts)
+            .map(|((time, _), prev)| (*time - prev).as_nanos() as u64)
+    }
+
+    fn speedscope(&self, elapsed: Duration) -> serde_json::Value {
+        let frames = self
+            .functions
+            .iter()
+            .map(|f| json!({ "name": f.name, "file": f.module }))
+            .collect::<Vec<_>>();
+        json!({
+            "$schema": "https://www.speedscope.app/file-format-schema.json",
+            "exporter": concat!("wasmtime ", env!("CARGO_PKG_VERSION")),
+            "name": self.name,
+            "activeProfileIndex": 0,
+            "shared": { "frames": frames },
+            "profiles": [{
+                "type": "sampled",
+                "name": self.name,
+                "unit": "nanoseconds",
+                "startValue": 0,
+                "endValue": elapsed.as_nanos() as u64,
+                "samples": self.samples.iter().map(|s| &s.1).collect::<Vec<_>>(),
+                "weights": self.weights().collect::<Vec<_>>(),
+            }],
+        })
+    }
+
+    /// Encodes the profile as a `perftools.profiles.Profile` protobuf message.
+    ///
+    /// Every function has a single location, and the ID of both is its index
+    /// in `functions` plus one, since zero IDs are reserved.
+    fn pprof(&self, elapsed: Duration) -> Vec<u8> {
+        let mut strings = StringTable::default();
+        let mut profile = Vec::new();
+
+        let value_type = |strings: &mut StringTable, ty: &str, unit: &str| {
+            let mut message = Vec::new();
+            protobuf::int(&mut message, 1, strings.get(ty));
+            protobuf::int(&mut message, 2, strings.get(unit));
+            message
+        };
+        let samples = value_type(&mut strings, "samples", "count");
+        protobuf::bytes(&mut profile, 1, &samples);
+        let wall = value_type(&mut strings, "wall", "nanoseconds");
+        protobuf::bytes(&mut profile, 1, &wall);
+
+        for ((_, stack), weight) in self.samples.iter().zip(self.weights()) {
+            let mut sample = Vec::new();
+            // pprof lists the newest frame first.
+            let locations = stack.iter().rev().map(|i| *i as u64 + 1);
+            protobuf::packed(&mut sample, 1, locations);
+            protobuf::packed(&mut sample, 2, [1, weight]);
+            protobuf::bytes(&mut profile, 2, &sample);
+        }
+
+        for (i, function) in self.functions.iter().enumerate() {
+            let id = i as u64 + 1;
+            let mut line = Vec::new();
+            protobuf::int(&mut line, 1, id);
+            let mut location = Vec::new();
+            protobuf::int(&mut location, 1, id);
+            protobuf::bytes(&mut location, 4, &line);
+            protobuf::bytes(&mut profile, 4, &location);
+
+            let mut message = Vec::new();
+            protobuf::int(&mut message, 1, id);
+            protobuf::int(&mut message, 2, strings.get(&function.name));
+            protobuf::int(&mut message, 4, strings.get(&function.module));
+            protobuf::bytes(&mut profile, 5, &message);
+        }
+
+        let period_type = value_type(&mut strings, "wall", "nanoseconds");
+        for string in strings.strings.iter() {
+            protobuf::bytes(&mut profile, 6, string.as_bytes());
+        }
+        let start = std::time::SystemTime::now() - elapsed;
+        let start = start
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default();
+        protobuf::int(&mut profile, 9, start.as_nanos() as u64);
+        protobuf::int(&mut profile, 10, elapsed.as_nanos() as u64);
+        protobuf::bytes(&mut profile, 11, &period_type);
+        protobuf::int(&mut profile, 12, self.interval.as_nanos() as u64);
+        profile
+    }
+}
+
+/// The string table of a pprof profile, in which the first string must be
+/// empty.
+struct StringTable {
+    strings: Vec<String>,
+    indices: HashMap<String, u64>,
+}
+
+impl Default for StringTable {
+    fn default() -> Self {
+        StringTable {
+            strings: vec![String::new()],
+            indices: HashMap::from([(String::new(), 0)]),
+        }
+    }
+}
+
+impl StringTable {
+    fn get(&mut self, s: &str) -> u64 {
+        if let Some(index) = self.indices.get(s) {
+            return *index;
+        }
+        let index = self.strings.len() as u64;
+        self.strings.push(s.to_string());
+        self.indices.insert(s.to_string(), index);
+        index
+    }
+}
+
+/// Just enough of the protobuf wire format to encode pprof profiles.
+mod protobuf {
+    fn varint(out: &mut Vec<u8>, mut n: u64) {
+        while n >= 0x80 {
+            out.push((n as u8) | 0x80);
+            n >>= 7;
+        }
+        out.push(n as u8);
+    }
+
+    /// Encodes a `uint64` or non-negative `int64` field.
+    pub fn int(out: &mut Vec<u8>, field: u32, n: u64) {
+        varint(out, u64::from(field) << 3);
+        varint(out, n);
+    }
+
+    /// Encodes a length-delimited field: a string, bytes or a message.
+    pub fn bytes(out: &mut Vec<u8>, field: u32, bytes: &[u8]) {
+        varint(out, u64::from(field) << 3 | 2);
+        varint(out, bytes.len() as u64);
+        out.extend_from_slice(bytes);
+    }
+
+    /// Encodes a packed repeated `uint64` or non-negative `int64` field.
+    pub fn packed(out: &mut Vec<u8>, field: u32, ns: impl IntoIterator<Item = u64>) {
+        let mut packed = Vec::new();
+        for n in ns {
+            varint(&mut packed, n);
+        }
+        bytes(out, field, &packed);
+    }
 }
 
 fn module_symbols(name: String, compiled: &CompiledModule) -> Option<LibraryInfo> {
     let symbols = Vec::from_iter(compiled.finished_functions().map(|(defined_idx, _)| {
         let loc = compiled.func_loc(defined_idx);
-        let func_idx = compiled.module().func_index(defined_idx);
-        let name = match compiled.func_name(func_idx) {
-            None => format!("wasm_function_{}", defined_idx.as_u32()),
-            Some(name) => name.to_string(),
-        };
         Symbol {
             address: loc.start,
             size: Some(loc.length),
-            name,
+            name: function_name(compiled, defined_idx),
         }
     }));
     if symbols.is_empty() {
@@ -210,3 +455,11 @@ fn module_symbols(name: String, compiled: &CompiledModule) -> Option<LibraryInfo
         symbol_table: Some(Arc::new(SymbolTable::new(symbols))),
     })
 }
+
+fn function_name(compiled: &CompiledModule, defined_idx: DefinedFuncIndex) -> String {
+    let func_idx = compiled.module().func_index(defined_idx);
+    match compiled.func_name(func_idx) {
+        None => format!("wasm_function_{}", defined_idx.as_u32()),
+        Some(name) => name.to_string(),
+    }
+}
@@ -76,11 +76,12 @@
 //! contents of `StoreOpaque`. This is an invariant that we, as the authors of
 //! `wasmtime`, must uphold for the public interface to be safe.
 
+use crate::debug::{DebugAction, DebugEvent, DebugState};
 use crate::instance::InstanceData;
 use crate::linker::Definition;
 use crate::module::BareModuleInfo;
 use crate::trampoline::VMHostGlobalContext;
-use crate::{module::ModuleRegistry, Engine, Module, Trap, Val, ValRaw};
+use crate::{module::ModuleRegistry, Engine, InstrumentationEvent, Module, Trap, Val, ValRaw};
 use crate::{Global, Instance, Memory};
 use anyhow::{anyhow, bail, Result};
 use std::cell::UnsafeCell;
@@ -95,6 +96,7 @@ use std::ptr;
 use std::sync::atomic::AtomicU64;
 use std::sync::Arc;
 use std::task::{Context, Poll};
+use wasmtime_environ::FuncIndex;
 use wasmtime_runtime::{
     ExportGlobal, ExportMemory, InstanceAllocationRequest, InstanceAllocator, InstanceHandle,
     ModuleInfo, OnDemandInstanceAllocator, SignalHandler, StoreBox, StorePtr, VMContext,
@@ -107,6 +109,11 @@ mod data;
 pub use self::data::*;
 mod func_refs;
 use func_refs::FuncRefs;
+mod snapshot;
+pub use self::snapshot::StoreSnapshot;
+mod stats;
+use stats::StatsState;
+pub use stats::StoreStats;
 
 /// A [`Store`] is a collection of WebAssembly instances and host-defined state.
 ///
@@ -207,6 +214,15 @@ pub struct StoreInner<T> {
     call_hook: Option<CallHookInner<T>>,
     epoch_deadline_behavior:
         Option<Box<dyn FnMut(StoreContextMut<T>) -> Result<UpdateDeadline> + Send + Sync>>,
+    debug_handler:
+        Option<Box<dyn FnMut(StoreContextMut<T>, DebugEvent) -> Result<DebugAction> + Send + Sync>>,
+    instrumentation_handler: Option<
+        Box<
+            dyn FnMut(StoreContextMut<T>, Instance, InstrumentationEvent) -> Result<()>
+                + Send
+                + Sync,
+        >,
+    >,
     // for comments about `ManuallyDrop`, see `Store::into_data`
     data: ManuallyDrop<T>,
 }
@@ -348,6 +364,10 @@ pub struct StoreOpaque {
     component_host_table: wasmtime_runtime::component::ResourceTable,
     #[cfg(feature = "component-model")]
     component_calls: wasmtime_runtime::component::CallContexts,
+
+    /// Breakpoints and single-stepping state for the `debug` API.
+    debug_state: DebugState,
+    stats: Option<Box<StatsState>>,
 }
 
 #[cfg(feature = "async")]
@@ -488,10 +508,14 @@ impl<T> Store<T> {
                 component_host_table: Default::default(),
                 #[cfg(feature = "component-model")]
                 component_calls: Default::default(),
+                debug_state: DebugState::default(),
+                stats: None,
             },
             limiter: None,
             call_hook: None,
             epoch_deadline_behavior: None,
+            debug_handler: None,
+            instrumentation_handler: None,
             data: ManuallyDrop::new(data),
         });
 
@@ -753,6 +777,138 @@ impl<T> Store<T> {
         self.inner.gc()
     }
 
+    /// Captures the mutable state of every instance in this store, along with
+    /// the store's fuel and epoch deadline, so that it can later be rolled
+    /// back with [`Store::restore`].
+    ///
+    /// The snapshot includes the contents and sizes of all linear memories
+    /// and tables and the values of all globals defined by instances in this
+    /// store, including those created by the host with [`Memory::new`] and
+    /// [`Table::new`](crate::Table::new). Globals created by the host with
+    /// [`Global::new`](crate::Global::new) and the host state `T` are not
+    /// captured.
+    ///
+    /// # Cost
+    ///
+    /// Taking a snapshot copies the entire current contents of every linear
+    /// memory, so its time and space costs are proportional to the total size
+    /// of the memories, not to how much of them has been modified, and a
+    /// snapshot holds onto that much memory for as long as it is alive. Call
+    /// this sparingly on stores with large memories.
+    ///
+    /// On Linux the copy is made into an anonymous file which restoring maps
+    /// copy-on-write into the memory, so restoring a snapshot is cheap and
+    /// restoring it many times shares its pages. Elsewhere the copy is a plain
+    /// buffer, which restoring copies back into the memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the store contains memories which can't be
+    /// snapshotted, such as shared memories or memories created by a custom
+    /// [`MemoryCreator`](crate::MemoryCreator).
+    pub fn snapshot(&mut self) -> Result<StoreSnapshot> {
+        self.inner.snapshot()
+    }
+
+    /// Rolls back the mutable state of every instance in this store, along
+    /// with the store's fuel and epoch deadline, to the state captured by
+    /// `snapshot`.
+    ///
+    /// A snapshot can be restored any number of times.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `snapshot` was taken from another store, or if
+    /// instances have been added to this store since it was taken. Restoring
+    /// may also fail if memories can't be resized to their size in the
+    /// snapshot, in which case the store is left partially restored.
+    pub fn restore(&mut self, snapshot: &StoreSnapshot) -> Result<()> {
+        self.inner.restore(snapshot)
+    }
+
+    /// Configures whether execution statistics are collected for this store.
+    ///
+    /// When enabled, the store counts the events reported by
+    /// [`Store::stats`] from this point on. Statistics are disabled by
+    /// default, in which case collecting them costs nothing. Disabling them
+    /// discards the statistics collected so far.
+    pub fn collect_stats(&mut self, enable: bool) {
+        self.inner.collect_stats(enable)
+    }
+
+    /// Returns the execution statistics collected for this store, or `None`
+    /// if they're not enabled with [`Store::collect_stats`].
+    pub fn stats(&self) -> Option<&StoreStats> {
+        self.inner.stats()
+    }
+
+    /// Configures the handler invoked whenever WebAssembly execution pauses
+    /// at a breakpoint or while single-stepping.
+    ///
+    /// The handler can inspect the state of the paused WebAssembly with
+    /// [`debug::frames`](crate::debug::frames), and returns whether to keep
+    /// single-stepping. If it returns an error, that error is raised as a
+    /// trap. Without a handler, execution doesn't pause at all.
+    ///
+    /// See the [`debug`](crate::debug) module for more information.
+    pub fn debug_handler(
+        &mut self,
+        handler: impl FnMut(StoreContextMut<T>, DebugEvent) -> Result<DebugAction>
+            + Send
+            + Sync
+            + 'static,
+    ) {
+        self.inner.debug_handler = Some(Box::new(handler));
+    }
+
+    /// Configures the handler invoked for every event reported by code
+    /// compiled with [`Config::instrumentation`](crate::Config::instrumentation).
+    ///
+    /// The handler receives the instance whose code reported the event. If
+    /// it returns an error, that error is raised as a trap. Without a
+    /// handler, events are ignored.
+    pub fn instrumentation_handler(
+        &mut self,
+        handler: impl FnMut(StoreContextMut<T>, Instance, InstrumentationEvent) -> Result<()>
+            + Send
+            + Sync
+            + 'static,
+    ) {
+        self.inner.instrumentation_handler = Some(Box::new(handler));
+    }
+
+    /// Sets a breakpoint on the instruction at `offset` in the original
+    /// binary of `module`, within the function with index `func_index`.
+    ///
+    /// Execution pauses before that instruction whenever it's executed by
+    /// an instance of `module` in this store. Offsets which aren't those of
+    /// an instruction in that function are accepted but never reached.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `module` wasn't compiled with
+    /// [`Config::debug_instrumentation`](crate::Config::debug_instrumentation),
+    /// belongs to a different engine, or doesn't define a function with index
+    /// `func_index`.
+    pub fn add_breakpoint(&mut self, module: &Module, func_index: u32, offset: u32) -> Result<()> {
+        self.inner.add_breakpoint(module, func_index, offset)
+    }
+
+    /// Removes a breakpoint set with [`Store::add_breakpoint`], returning
+    /// whether it was set.
+    pub fn remove_breakpoint(&mut self, module: &Module, func_index: u32, offset: u32) -> bool {
+        self.inner.remove_breakpoint(module, func_index, offset)
+    }
+
+    /// Configures whether WebAssembly execution pauses before every
+    /// instruction.
+    ///
+    /// This is also set by the value returned by the
+    /// [`Store::debug_handler`] every time execution pauses.
+    pub fn single_step(&mut self, enable: bool) {
+        self.inner.single_step(enable)
+    }
+
     /// Returns the amount of fuel consumed by this store's execution so far.
     ///
     /// If fuel consumption is not enabled via
@@ -1027,6 +1183,13 @@ impl<'a, T> StoreContext<'a, T> {
     pub fn fuel_remaining(&mut self) -> Option<u64> {
         self.0.fuel_remaining()
     }
+
+    /// Returns the execution statistics collected for this store.
+    ///
+    /// For more information see [`Store::stats`].
+    pub fn stats(&self) -> Option<&'a StoreStats> {
+        self.0.stats()
+    }
 }
 
 impl<'a, T> StoreContextMut<'a, T> {
@@ -1056,6 +1219,55 @@ impl<'a, T> StoreContextMut<'a, T> {
         self.0.gc()
     }
 
+    /// Captures the mutable state of this store.
+    ///
+    /// For more information see [`Store::snapshot`].
+    pub fn snapshot(&mut self) -> Result<StoreSnapshot> {
+        self.0.snapshot()
+    }
+
+    /// Rolls back the mutable state of this store.
+    ///
+    /// For more information see [`Store::restore`].
+    pub fn restore(&mut self, snapshot: &StoreSnapshot) -> Result<()> {
+        self.0.restore(snapshot)
+    }
+
+    /// Configures whether execution statistics are collected for this store.
+    ///
+    /// For more information see [`Store::collect_stats`].
+    pub fn collect_stats(&mut self, enable: bool) {
+        self.0.collect_stats(enable)
+    }
+
+    /// Returns the execution statistics collected for this store.
+    ///
+    /// For more information see [`Store::stats`].
+    pub fn stats(&self) -> Option<&StoreStats> {
+        self.0.stats()
+    }
+
+    /// Sets a breakpoint.
+    ///
+    /// For more information see [`Store::add_breakpoint`].
+    pub fn add_breakpoint(&mut self, module: &Module, func_index: u32, offset: u32) -> Result<()> {
+        self.0.add_breakpoint(module, func_index, offset)
+    }
+
+    /// Removes a breakpoint.
+    ///
+    /// For more information see [`Store::remove_breakpoint`].
+    pub fn remove_breakpoint(&mut self, module: &Module, func_index: u32, offset: u32) -> bool {
+        self.0.remove_breakpoint(module, func_index, offset)
+    }
+
+    /// Configures whether execution pauses before every instruction.
+    ///
+    /// For more information see [`Store::single_step`].
+    pub fn single_step(&mut self, enable: bool) {
+        self.0.single_step(enable)
+    }
+
     /// Returns the fuel consumed by this store.
     ///
     /// For more information see [`Store::fuel_consumed`].
@@ -1138,6 +1350,12 @@ impl<T> StoreInner<T> {
     }
 
     pub fn call_hook(&mut self, s: CallHook) -> Result<()> {
+        match s {
+            CallHook::CallingWasm => self.stats_calling_wasm(),
+            CallHook::ReturningFromWasm => self.stats_returning_from_wasm(),
+            CallHook::CallingHost => self.stats_calling_host(),
+            CallHook::ReturningFromHost => self.stats_returning_from_host(),
+        }
         match &mut self.call_hook {
             Some(CallHookInner::Sync(hook)) => hook(&mut self.data, s),
 
@@ -1298,6 +1516,45 @@ impl StoreOpaque {
         }
     }
 
+    pub fn add_breakpoint(&mut self, module: &Module, func_index: u32, offset: u32) -> Result<()> {
+        if !Engine::same(self.engine(), module.engine()) {
+            bail!("cannot set a breakpoint in a module from a different engine");
+        }
+        if !self.engine().config().tunables.debug_instrumentation {
+            bail!("cannot set breakpoints without debug instrumentation enabled");
+        }
+        let env_module = module.env_module();
+        let func = FuncIndex::from_u32(func_index);
+        if !env_module.functions.is_valid(func) || env_module.is_imported_function(func) {
+            bail!("module doesn't define a function with index {func_index}");
+        }
+        let id = module.compiled_module().unique_id();
+        self.debug_state.add_breakpoint(id, func_index, offset);
+        self.update_debug_break();
+        Ok(())
+    }
+
+    pub fn remove_breakpoint(&mut self, module: &Module, func_index: u32, offset: u32) -> bool {
+        let id = module.compiled_module().unique_id();
+        let removed = self.debug_state.remove_breakpoint(id, func_index, offset);
+        self.update_debug_break();
+        removed
+    }
+
+    pub fn single_step(&mut self, enable: bool) {
+        self.debug_state.set_single_step(enable);
+        self.update_debug_break();
+    }
+
+    /// Updates the flag telling instrumented code whether to call into the
+    /// store before every instruction.
+    fn update_debug_break(&mut self) {
+        let armed = u32::from(self.debug_state.armed());
+        unsafe {
+            *self.runtime_limits.debug_break.get() = armed;
+        }
+    }
+
     /// Yields the async context, assuming that we are executing on a fiber and
     /// that fiber is not in the process of dying. This function will return
     /// None in the latter case (the fiber is dying), and panic if
@@ -1985,6 +2242,10 @@ unsafe impl<T> wasmtime_runtime::Store for StoreInner<T> {
         }
     }
 
+    fn memory_grown(&mut self, old_size: usize, new_size: usize) {
+        self.stats_memory_grown(old_size, new_size);
+    }
+
     fn table_growing(
         &mut self,
         current: u32,
@@ -2032,15 +2293,23 @@ unsafe impl<T> wasmtime_runtime::Store for StoreInner<T> {
         }
     }
 
+    fn table_grown(&mut self, old_size: u32, new_size: u32) {
+        self.stats_table_grown(old_size, new_size);
+    }
+
     fn out_of_gas(&mut self) -> Result<(), anyhow::Error> {
         return match &mut self.out_of_gas_behavior {
-            OutOfGas::Trap => Err(Trap::OutOfFuel.into()),
+            OutOfGas::Trap => {
+                self.stats_trap(Trap::OutOfFuel);
+                Err(Trap::OutOfFuel.into())
+            }
             #[cfg(feature = "async")]
             OutOfGas::InjectFuel {
                 injection_count,
                 fuel_to_inject,
             } => {
                 if *injection_count == 0 {
+                    self.stats_trap(Trap::OutOfFuel);
                     return Err(Trap::OutOfFuel.into());
                 }
                 *injection_count -= 1;
@@ -2061,7 +2330,10 @@ unsafe impl<T> wasmtime_runtime::Store for StoreInner<T> {
         // multiple times.
         let mut behavior = self.epoch_deadline_behavior.take();
         let delta_result = match &mut behavior {
-            None => Err(Trap::Interrupt.into()),
+            None => {
+                self.stats_trap(Trap::Interrupt);
+                Err(Trap::Interrupt.into())
+            }
             Some(callback) => callback((&mut *self).as_context_mut()).and_then(|update| {
                 let delta = match update {
                     UpdateDeadline::Continue(delta) => delta,
@@ -2091,6 +2363,62 @@ unsafe impl<T> wasmtime_runtime::Store for StoreInner<T> {
         delta_result
     }
 
+    fn debug_break(&mut self) -> Result<(), anyhow::Error> {
+        let event = match unsafe { self.debug_state.event(&self.runtime_limits) } {
+            Some(event) => event,
+            None => return Ok(()),
+        };
+        // Temporarily take the handler to avoid mutably borrowing multiple
+        // times.
+        let mut handler = self.debug_handler.take();
+        let result = match &mut handler {
+            None => Ok(DebugAction::Continue),
+            Some(handler) => handler((&mut *self).as_context_mut(), event),
+        };
+        // Put back the handler unless the handler replaced itself.
+        if self.debug_handler.is_none() {
+            self.debug_handler = handler;
+        }
+        let action = result?;
+        self.single_step(action == DebugAction::Step);
+        Ok(())
+    }
+
+    fn instrumentation_event(
+        &mut self,
+        instance: &dyn std::any::Any,
+        event: InstrumentationEvent,
+    ) -> Result<(), anyhow::Error> {
+        let instance = match instance.downcast_ref::<Instance>() {
+            Some(instance) => *instance,
+            None => return Ok(()),
+        };
+        // Events only needed by wmemcheck aren't reported.
+        if !self.engine().config().instrumentation.reports(&event) {
+            return Ok(());
+        }
+        // Temporarily take the handler to avoid mutably borrowing multiple
+        // times.
+        let mut handler = self.instrumentation_handler.take();
+        let result = match &mut handler {
+            None => Ok(()),
+            Some(handler) => handler((&mut *self).as_context_mut(), instance, event),
+        };
+        // Put back the handler unless the handler replaced itself.
+        if self.instrumentation_handler.is_none() {
+            self.instrumentation_handler = handler;
+        }
+        result
+    }
+
+    fn continuation_created(&mut self) {
+        self.stats_continuation_created();
+    }
+
+    fn continuation_switched(&mut self) {
+        self.stats_continuation_switch();
+    }
+
     #[cfg(feature = "component-model")]
     fn component_calls(&mut self) -> &mut wasmtime_runtime::component::CallContexts {
         &mut self.component_calls
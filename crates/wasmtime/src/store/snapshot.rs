@@ -0,0 +1,68 @@
+use crate::store::{StoreId, StoreOpaque};
+use anyhow::{bail, Result};
+use std::fmt;
+use wasmtime_runtime::InstanceSnapshot;
+
+/// A checkpoint of the mutable state of a [`Store`](crate::Store), created
+/// with [`Store::snapshot`](crate::Store::snapshot) and rolled back to with
+/// [`Store::restore`](crate::Store::restore).
+///
+/// A snapshot captures, for every instance in the store, the contents and
+/// sizes of its linear memories and tables and the values of its globals, as
+/// well as the store's fuel and epoch deadline.
+///
+/// Memory contents are copied in full when the snapshot is taken, see
+/// [`Store::snapshot`](crate::Store::snapshot) for the costs involved.
+pub struct StoreSnapshot {
+    store: StoreId,
+    instances: Vec<InstanceSnapshot>,
+    fuel_consumed: i64,
+    fuel_adj: i64,
+    epoch_deadline: u64,
+}
+
+impl fmt::Debug for StoreSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StoreSnapshot")
+            .field("instances", &self.instances.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl StoreOpaque {
+    pub(crate) fn snapshot(&mut self) -> Result<StoreSnapshot> {
+        let mut instances = Vec::with_capacity(self.instances.len());
+        for instance in self.instances.iter_mut() {
+            instances.push(instance.handle.snapshot()?);
+        }
+        Ok(StoreSnapshot {
+            store: self.id(),
+            instances,
+            fuel_consumed: unsafe { *self.runtime_limits.fuel_consumed.get() },
+            fuel_adj: self.fuel_adj,
+            epoch_deadline: unsafe { *self.runtime_limits.epoch_deadline.get() },
+        })
+    }
+
+    pub(crate) fn restore(&mut self, snapshot: &StoreSnapshot) -> Result<()> {
+        if snapshot.store != self.id() {
+            bail!("snapshot was taken from a different store");
+        }
+        if snapshot.instances.len() != self.instances.len() {
+            bail!(
+                "snapshot was taken with {} instances in the store, but it now has {}",
+                snapshot.instances.len(),
+                self.instances.len()
+            );
+        }
+        for (instance, instance_snapshot) in self.instances.iter_mut().zip(&snapshot.instances) {
+            instance.handle.restore(instance_snapshot)?;
+        }
+        unsafe {
+            *self.runtime_limits.fuel_consumed.get() = snapshot.fuel_consumed;
+            *self.runtime_limits.epoch_deadline.get() = snapshot.epoch_deadline;
+        }
+        self.fuel_adj = snapshot.fuel_adj;
+        Ok(())
+    }
+}
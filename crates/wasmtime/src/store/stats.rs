@@ -0,0 +1,246 @@
+use crate::store::StoreOpaque;
+use crate::Trap;
+use std::collections::HashMap;
+use std::ptr::NonNull;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use wasmtime_environ::EntityIndex;
+use wasmtime_runtime::VMFuncRef;
+
+/// Execution statistics of a [`Store`](crate::Store), returned by
+/// [`Store::stats`](crate::Store::stats).
+///
+/// Statistics are only collected once enabled with
+/// [`Store::collect_stats`](crate::Store::collect_stats), and count events
+/// since then.
+#[derive(Debug, Default, Clone)]
+pub struct StoreStats {
+    instances_created: u64,
+    memory_pages_grown: u64,
+    table_elements_grown: u64,
+    host_calls: u64,
+    host_call_time: Duration,
+    traps: HashMap<Trap, u64>,
+    fuel_per_export: HashMap<Arc<str>, u64>,
+    continuations_created: u64,
+    continuation_switches: u64,
+}
+
+impl StoreStats {
+    /// Returns the number of module instances created in the store.
+    pub fn instances_created(&self) -> u64 {
+        self.instances_created
+    }
+
+    /// Returns the total number of WebAssembly pages linear memories grew by,
+    /// whether from WebAssembly or from the host.
+    pub fn memory_pages_grown(&self) -> u64 {
+        self.memory_pages_grown
+    }
+
+    /// Returns the total number of elements tables grew by, whether from
+    /// WebAssembly or from the host.
+    pub fn table_elements_grown(&self) -> u64 {
+        self.table_elements_grown
+    }
+
+    /// Returns the number of calls from WebAssembly to host functions.
+    pub fn host_calls(&self) -> u64 {
+        self.host_calls
+    }
+
+    /// Returns the time spent in host functions called from WebAssembly.
+    ///
+    /// Time spent in WebAssembly called back from a host function isn't
+    /// included.
+    pub fn host_call_time(&self) -> Duration {
+        self.host_call_time
+    }
+
+    /// Returns the number of traps raised by WebAssembly, for each kind of
+    /// trap.
+    ///
+    /// Errors returned by host functions aren't counted.
+    pub fn traps(&self) -> &HashMap<Trap, u64> {
+        &self.traps
+    }
+
+    /// Returns the fuel consumed by calls from the host into each exported
+    /// function, keyed by export name.
+    ///
+    /// This includes the fuel consumed by everything the export called, and
+    /// is only recorded when
+    /// [`Config::consume_fuel`](crate::Config::consume_fuel) is enabled. When
+    /// several instances export functions with the same name, their fuel is
+    /// added up.
+    pub fn fuel_per_export(&self) -> &HashMap<Arc<str>, u64> {
+        &self.fuel_per_export
+    }
+
+    /// Returns the number of continuations created with `cont.new`.
+    pub fn continuations_created(&self) -> u64 {
+        self.continuations_created
+    }
+
+    /// Returns the number of switches between continuations, counting both
+    /// `resume` and `suspend`.
+    pub fn continuation_switches(&self) -> u64 {
+        self.continuation_switches
+    }
+}
+
+/// The statistics of a store along with the state needed to collect them.
+#[derive(Default)]
+pub(crate) struct StatsState {
+    stats: StoreStats,
+    /// For each host call in progress, the time from which it's been running
+    /// without calling back into WebAssembly.
+    host_calls: Vec<Instant>,
+    /// The export names of the functions called from the host, by address of
+    /// their `VMFuncRef`.
+    export_names: HashMap<usize, Option<Arc<str>>>,
+}
+
+/// A call from the host into WebAssembly whose fuel is being measured.
+pub(crate) struct ExportCall {
+    name: Arc<str>,
+    fuel_consumed: u64,
+}
+
+impl StoreOpaque {
+    pub(crate) fn collect_stats(&mut self, enable: bool) {
+        self.stats = match self.stats.take() {
+            Some(stats) if enable => Some(stats),
+            _ if enable => Some(Box::default()),
+            _ => None,
+        };
+    }
+
+    pub(crate) fn stats(&self) -> Option<&StoreStats> {
+        self.stats.as_ref().map(|state| &state.stats)
+    }
+
+    pub(crate) fn stats_instance_created(&mut self) {
+        if let Some(state) = &mut self.stats {
+            state.stats.instances_created += 1;
+        }
+    }
+
+    pub(crate) fn stats_memory_grown(&mut self, old_size: usize, new_size: usize) {
+        if let Some(state) = &mut self.stats {
+            let pages = (new_size - old_size) / wasmtime_environ::WASM_PAGE_SIZE as usize;
+            state.stats.memory_pages_grown += pages as u64;
+        }
+    }
+
+    pub(crate) fn stats_table_grown(&mut self, old_size: u32, new_size: u32) {
+        if let Some(state) = &mut self.stats {
+            state.stats.table_elements_grown += u64::from(new_size - old_size);
+        }
+    }
+
+    pub(crate) fn stats_trap(&mut self, trap: Trap) {
+        if let Some(state) = &mut self.stats {
+            *state.stats.traps.entry(trap).or_default() += 1;
+        }
+    }
+
+    pub(crate) fn stats_continuation_created(&mut self) {
+        if let Some(state) = &mut self.stats {
+            state.stats.continuations_created += 1;
+        }
+    }
+
+    pub(crate) fn stats_continuation_switch(&mut self) {
+        if let Some(state) = &mut self.stats {
+            state.stats.continuation_switches += 1;
+        }
+    }
+
+    pub(crate) fn stats_calling_host(&mut self) {
+        if let Some(state) = &mut self.stats {
+            state.stats.host_calls += 1;
+            state.host_calls.push(Instant::now());
+        }
+    }
+
+    pub(crate) fn stats_returning_from_host(&mut self) {
+        if let Some(state) = &mut self.stats {
+            if let Some(start) = state.host_calls.pop() {
+                state.stats.host_call_time += start.elapsed();
+            }
+        }
+    }
+
+    pub(crate) fn stats_calling_wasm(&mut self) {
+        if let Some(state) = &mut self.stats {
+            if let Some(start) = state.host_calls.last() {
+                state.stats.host_call_time += start.elapsed();
+            }
+        }
+    }
+
+    pub(crate) fn stats_returning_from_wasm(&mut self) {
+        if let Some(state) = &mut self.stats {
+            if let Some(start) = state.host_calls.last_mut() {
+                *start = Instant::now();
+            }
+        }
+    }
+
+    /// Starts measuring the fuel consumed by a call from the host to
+    /// `func_ref`, if it's a top-level call to an exported function.
+    pub(crate) fn stats_export_call_start(
+        &mut self,
+        func_ref: NonNull<VMFuncRef>,
+    ) -> Option<ExportCall> {
+        let state = self.stats.as_ref()?;
+        if !state.host_calls.is_empty() {
+            return None;
+        }
+        let fuel_consumed = self.fuel_consumed()?;
+        let key = func_ref.as_ptr() as usize;
+        let name = match state.export_names.get(&key) {
+            Some(name) => name.clone(),
+            None => {
+                let name = self.export_name(func_ref);
+                let state = self.stats.as_mut().unwrap();
+                state.export_names.insert(key, name.clone());
+                name
+            }
+        }?;
+        Some(ExportCall {
+            name,
+            fuel_consumed,
+        })
+    }
+
+    pub(crate) fn stats_export_call_end(&mut self, call: ExportCall) {
+        let fuel_consumed = self.fuel_consumed().unwrap_or(call.fuel_consumed);
+        if let Some(state) = &mut self.stats {
+            *state.stats.fuel_per_export.entry(call.name).or_default() +=
+                fuel_consumed - call.fuel_consumed;
+        }
+    }
+
+    /// Finds the name under which an instance of this store exports
+    /// `func_ref`, if any.
+    fn export_name(&mut self, func_ref: NonNull<VMFuncRef>) -> Option<Arc<str>> {
+        for instance in self.instances.iter_mut().filter(|i| !i.ondemand) {
+            let exports = instance
+                .handle
+                .exports()
+                .filter_map(|(name, index)| match index {
+                    EntityIndex::Function(index) => Some((name.clone(), *index)),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            for (name, index) in exports {
+                if instance.handle.get_exported_func(index).func_ref == func_ref {
+                    return Some(name.into());
+                }
+            }
+        }
+        None
+    }
+}
@@ -9,6 +9,16 @@ use wasmtime_runtime::{
     StoreBox, VMArrayCallHostFuncContext, VMContext, VMFuncRef, VMOpaqueContext,
 };
 
+/// How a host function called through `array_call_shim` finished, when it
+/// didn't trap.
+pub enum HostCallOutcome {
+    /// The function returned, and wrote its results to the values.
+    Returned,
+    /// The function threw an exception, which is pending in the store. The
+    /// values hold no results.
+    Threw,
+}
+
 struct TrampolineState<F> {
     func: F,
     #[allow(dead_code)]
@@ -28,7 +38,7 @@ unsafe extern "C" fn array_call_shim<F>(
     values_vec: *mut ValRaw,
     values_vec_len: usize,
 ) where
-    F: Fn(*mut VMContext, &mut [ValRaw]) -> Result<()> + 'static,
+    F: Fn(*mut VMContext, &mut [ValRaw]) -> Result<HostCallOutcome> + 'static,
 {
     // Here we are careful to use `catch_unwind` to ensure Rust panics don't
     // unwind past us. The primary reason for this is that Rust considers it UB
@@ -56,7 +66,13 @@ unsafe extern "C" fn array_call_shim<F>(
     }));
 
     match result {
-        Ok(Ok(())) => {}
+        Ok(Ok(HostCallOutcome::Returned)) => {}
+
+        // If an exception was thrown, then the frames of the caller are
+        // unwound up to its handler.
+        Ok(Ok(HostCallOutcome::Threw)) => {
+            wasmtime_runtime::exception::unwind(VMContext::from_opaque(caller_vmctx))
+        }
 
         // If a trap was raised (an error returned from the imported function)
         // then we smuggle the trap through `Box<dyn Error>` through to the
@@ -79,7 +95,7 @@ pub fn create_array_call_function<F>(
     engine: &Engine,
 ) -> Result<StoreBox<VMArrayCallHostFuncContext>>
 where
-    F: Fn(*mut VMContext, &mut [ValRaw]) -> Result<()> + Send + Sync + 'static,
+    F: Fn(*mut VMContext, &mut [ValRaw]) -> Result<HostCallOutcome> + Send + Sync + 'static,
 {
     use std::ptr;
 
@@ -4,7 +4,8 @@ use crate::{AsContext, Module};
 use anyhow::Error;
 use std::fmt;
 use wasmtime_environ::{EntityRef, FilePos};
-use wasmtime_jit::{demangle_function_name, demangle_function_name_or_index};
+use wasmtime_jit::{demangle_function_name, demangle_function_name_or_index, CompiledModule};
+use wasmtime_runtime::CompiledModuleId;
 
 /// Representation of a WebAssembly trap and what caused it to occur.
 ///
@@ -136,7 +137,11 @@ pub(crate) fn from_runtime_box(
 
     if let Some(coredump) = coredumpstack {
         let bt = WasmBacktrace::from_captured(store, coredump.bt, pc);
-        let cd = WasmCoreDump::new(store, bt);
+        let values = coredump
+            .func_indices
+            .into_iter()
+            .zip(coredump.locals.into_iter().zip(coredump.operand_stack));
+        let cd = WasmCoreDump::new(store, bt, values);
         error = error.context(cd);
     }
     error
@@ -425,6 +430,7 @@ impl fmt::Display for WasmBacktrace {
 /// to acquire this `FrameInfo`. For more information see [`WasmBacktrace`].
 #[derive(Debug)]
 pub struct FrameInfo {
+    module_id: CompiledModuleId,
     module_name: Option<String>,
     func_index: u32,
     func_name: Option<String>,
@@ -458,43 +464,16 @@ impl FrameInfo {
             text_offset
         );
 
-        // Use our wasm-relative pc to symbolize this frame. If there's a
-        // symbolication context (dwarf debug info) available then we can try to
-        // look this up there.
-        //
-        // Note that dwarf pcs are code-section-relative, hence the subtraction
-        // from the location of `instr`. Also note that all errors are ignored
-        // here for now since technically wasm modules can always have any
-        // custom section contents.
-        let mut symbols = Vec::new();
-
-        if let Some(s) = &module.symbolize_context().ok().and_then(|c| c) {
-            if let Some(offset) = instr.and_then(|i| i.file_offset()) {
-                let to_lookup = u64::from(offset) - s.code_section_offset();
-                if let Ok(mut frames) = s.addr2line().find_frames(to_lookup).skip_all_loads() {
-                    while let Ok(Some(frame)) = frames.next() {
-                        symbols.push(FrameSymbol {
-                            name: frame
-                                .function
-                                .as_ref()
-                                .and_then(|l| l.raw_name().ok())
-                                .map(|s| s.to_string()),
-                            file: frame
-                                .location
-                                .as_ref()
-                                .and_then(|l| l.file)
-                                .map(|s| s.to_string()),
-                            line: frame.location.as_ref().and_then(|l| l.line),
-                            column: frame.location.as_ref().and_then(|l| l.column),
-                        });
-                    }
-                }
-            }
-        }
+        // Use our wasm-relative pc to symbolize this frame.
+        let symbols = instr
+            .and_then(|i| i.file_offset())
+            .map(|offset| FrameSymbol::lookup(module, offset))
+            .unwrap_or_default();
 
         let index = module.module().func_index(index);
 
         Some(FrameInfo {
+            module_id: module.unique_id(),
             module_name: module.module().name.clone(),
             func_index: index.index() as u32,
             func_name: module.func_name(index).map(|s| s.to_string()),
@@ -504,6 +483,11 @@ impl FrameInfo {
         })
     }
 
+    /// Returns the id of the module this frame is for.
+    pub(crate) fn module_id(&self) -> CompiledModuleId {
+        self.module_id
+    }
+
     /// Returns the WebAssembly function index for this frame.
     ///
     /// This function index is the index in the function index space of the
@@ -601,6 +585,47 @@ pub struct FrameSymbol {
 }
 
 impl FrameSymbol {
+    /// Looks up the source locations of the instruction at `offset` within the
+    /// original wasm module using the module's DWARF debug information, if
+    /// any, returning the inlined frames first.
+    pub(crate) fn lookup(module: &CompiledModule, offset: u32) -> Vec<FrameSymbol> {
+        // If there's a symbolication context (dwarf debug info) available
+        // then we can try to look this up there.
+        //
+        // Note that dwarf pcs are code-section-relative, hence the subtraction
+        // from `offset`. Also note that all errors are ignored here for now
+        // since technically wasm modules can always have any custom section
+        // contents.
+        let mut symbols = Vec::new();
+        let s = match module.symbolize_context().ok().and_then(|c| c) {
+            Some(s) => s,
+            None => return symbols,
+        };
+        let to_lookup = match u64::from(offset).checked_sub(s.code_section_offset()) {
+            Some(to_lookup) => to_lookup,
+            None => return symbols,
+        };
+        if let Ok(mut frames) = s.addr2line().find_frames(to_lookup).skip_all_loads() {
+            while let Ok(Some(frame)) = frames.next() {
+                symbols.push(FrameSymbol {
+                    name: frame
+                        .function
+                        .as_ref()
+                        .and_then(|l| l.raw_name().ok())
+                        .map(|s| s.to_string()),
+                    file: frame
+                        .location
+                        .as_ref()
+                        .and_then(|l| l.file)
+                        .map(|s| s.to_string()),
+                    line: frame.location.as_ref().and_then(|l| l.line),
+                    column: frame.location.as_ref().and_then(|l| l.column),
+                });
+            }
+        }
+        symbols
+    }
+
     /// Returns the function name associated with this symbol.
     ///
     /// Note that this may not be present with malformed debug information, or
@@ -327,6 +327,14 @@ impl<T> WastContext<T> {
         Ok(())
     }
 
+    fn assert_exception(&self, result: Outcome) -> Result<()> {
+        match result {
+            Outcome::Ok(values) => bail!("expected exception, got {:?}", values),
+            Outcome::Trap(t) if t.downcast_ref::<Exception>().is_some() => Ok(()),
+            Outcome::Trap(t) => bail!("expected exception, got trap {:?}", t),
+        }
+    }
+
     fn assert_trap(&self, result: Outcome, expected: &str) -> Result<()> {
         let trap = match result {
             Outcome::Ok(values) => bail!("expected trap, got {:?}", values),
@@ -464,7 +472,10 @@ impl<T> WastContext<T> {
                     )
                 }
             }
-            AssertException { .. } => bail!("unimplemented assert_exception"),
+            AssertException { span: _, exec } => {
+                let result = self.perform_execute(exec)?;
+                self.assert_exception(result)?;
+            }
             AssertSuspension { .. } => bail!("unimplemented assert_suspension"),
         }
 
@@ -2,11 +2,13 @@ use anyhow::Result;
 use object::write::{Object, SymbolId};
 use std::any::Any;
 use std::sync::Mutex;
+use std::time::Instant;
 use wasmparser::FuncValidatorAllocations;
 use wasmtime_cranelift_shared::{CompiledFunction, ModuleTextBuilder};
 use wasmtime_environ::{
     CompileError, DefinedFuncIndex, FilePos, FuncIndex, FunctionBodyData, FunctionLoc,
-    ModuleTranslation, ModuleTypes, PrimaryMap, TrapEncodingBuilder, WasmFunctionInfo,
+    FunctionStats, ModuleTranslation, ModuleTypes, PrimaryMap, TrapEncodingBuilder,
+    WasmFunctionInfo,
 };
 use winch_codegen::{TargetIsa, TrampolineKind};
 
@@ -55,16 +57,14 @@ impl wasmtime_environ::Compiler for Compiler {
         data: FunctionBodyData<'_>,
         types: &ModuleTypes,
     ) -> Result<(WasmFunctionInfo, Box<dyn Any + Send>), CompileError> {
+        let start = Instant::now();
         let index = translation.module.func_index(index);
         let sig = translation.module.functions[index].signature;
         let ty = &types[sig];
         let FunctionBodyData { body, validator } = data;
-        let start_srcloc = FilePos::new(
-            body.get_binary_reader()
-                .original_position()
-                .try_into()
-                .unwrap(),
-        );
+        let reader = body.get_binary_reader();
+        let start_srcloc = FilePos::new(reader.original_position().try_into().unwrap());
+        let body_size = reader.bytes_remaining() as u32;
         let mut validator = validator.into_validator(self.take_allocations());
         let buffer = self
             .isa
@@ -72,6 +72,13 @@ impl wasmtime_environ::Compiler for Compiler {
             .map_err(|e| CompileError::Codegen(format!("{e:?}")));
         self.save_allocations(validator.into_allocations());
         let buffer = buffer?;
+        // Winch doesn't optimize, so there are no IR instruction counts.
+        let stats = FunctionStats {
+            body_size,
+            trap_sites: buffer.traps().len() as u32,
+            compile_time: Some(start.elapsed()),
+            ..FunctionStats::default()
+        };
         let compiled_function =
             CompiledFunction::new(buffer, CompiledFuncEnv {}, self.isa.function_alignment());
 
@@ -79,6 +86,8 @@ impl wasmtime_environ::Compiler for Compiler {
             WasmFunctionInfo {
                 start_srcloc,
                 stack_maps: Box::new([]),
+                exception_landings: Box::new([]),
+                stats,
             },
             Box::new(compiled_function),
         ))
@@ -0,0 +1,277 @@
+#![no_main]
+
+use cranelift_codegen::ir::{
+    self, BlockCall, ConstantData, ExternalName, Function, GlobalValueData, InstructionData,
+    InstructionMapper, UserExternalName, Value, ValueList, ValueListPool,
+};
+use cranelift_codegen::settings;
+use cranelift_reader::{parse_test, IsaSpec, ParseOptions};
+use libfuzzer_sys::fuzz_target;
+
+use cranelift_fuzzgen::*;
+
+fuzz_target!(|testcase: FunctionWithIsa| {
+    let text = format!("{:?}", testcase);
+    let test_file = parse_test(&text, ParseOptions::default())
+        .unwrap_or_else(|e| panic!("failed to parse the printed test case: {}\n{}", e, text));
+
+    let isa = match &test_file.isa_spec {
+        IsaSpec::Some(isas) if isas.len() == 1 => &isas[0],
+        _ => panic!("expected a single target in the printed test case"),
+    };
+    assert_eq!(isa.triple(), testcase.isa.triple());
+    assert_eq!(
+        setting_values(isa.flags().iter()),
+        setting_values(testcase.isa.flags().iter()),
+        "flags don't match"
+    );
+    assert_eq!(
+        setting_values(isa.isa_flags()),
+        setting_values(testcase.isa.isa_flags()),
+        "ISA flags don't match"
+    );
+
+    assert_eq!(test_file.functions.len(), 1);
+    assert_same_function(&testcase.func, &test_file.functions[0].0);
+});
+
+fn setting_values(values: impl IntoIterator<Item = settings::Value>) -> Vec<(String, String)> {
+    values
+        .into_iter()
+        .map(|value| (value.name.to_string(), value.value_string()))
+        .collect()
+}
+
+/// An external name, with user-defined names looked up in the function's name table, whose
+/// numbering isn't preserved by the text format.
+#[derive(Debug, PartialEq)]
+enum ResolvedName<'a> {
+    User(&'a UserExternalName),
+    Other(&'a ExternalName),
+}
+
+fn resolve_name<'a>(func: &'a Function, name: &'a ExternalName) -> ResolvedName<'a> {
+    match name {
+        ExternalName::User(name_ref) => {
+            ResolvedName::User(&func.params.user_named_funcs()[*name_ref])
+        }
+        name => ResolvedName::Other(name),
+    }
+}
+
+/// Check that `parsed` is the same function as `func`.
+///
+/// The text format doesn't name instructions, jump tables or immediates, so the parser numbers
+/// them in the order it sees them, and it lays out value lists in a pool of its own; all of
+/// these are compared by what they contain instead.
+fn assert_same_function(func: &Function, parsed: &Function) {
+    assert_eq!(func.name, parsed.name);
+    assert_eq!(func.signature, parsed.signature);
+    assert!(func.sized_stack_slots == parsed.sized_stack_slots);
+    assert!(func.dynamic_stack_slots == parsed.dynamic_stack_slots);
+    assert!(func.tables == parsed.tables);
+    assert_eq!(func.stack_limit, parsed.stack_limit);
+    assert!(func.dfg.signatures == parsed.dfg.signatures);
+    assert!(func.dfg.dynamic_types == parsed.dfg.dynamic_types);
+    assert!(func.dfg.constants == parsed.dfg.constants);
+
+    assert_eq!(func.global_values.len(), parsed.global_values.len());
+    for (gv, data) in func.global_values.iter() {
+        let parsed_data = &parsed.global_values[gv];
+        match (data, parsed_data) {
+            (
+                GlobalValueData::Symbol {
+                    name,
+                    offset,
+                    colocated,
+                    tls,
+                },
+                GlobalValueData::Symbol {
+                    name: parsed_name,
+                    offset: parsed_offset,
+                    colocated: parsed_colocated,
+                    tls: parsed_tls,
+                },
+            ) => {
+                assert_eq!(resolve_name(func, name), resolve_name(parsed, parsed_name));
+                assert_eq!(
+                    (offset, colocated, tls),
+                    (parsed_offset, parsed_colocated, parsed_tls)
+                );
+            }
+            _ => assert!(data == parsed_data, "{} doesn't match", gv),
+        }
+    }
+
+    assert_eq!(func.dfg.ext_funcs.len(), parsed.dfg.ext_funcs.len());
+    for (fn_ref, data) in func.dfg.ext_funcs.iter() {
+        let parsed_data = &parsed.dfg.ext_funcs[fn_ref];
+        assert_eq!(
+            resolve_name(func, &data.name),
+            resolve_name(parsed, &parsed_data.name)
+        );
+        assert_eq!(data.signature, parsed_data.signature);
+        assert_eq!(data.colocated, parsed_data.colocated);
+    }
+
+    let blocks: Vec<_> = func.layout.blocks().collect();
+    assert_eq!(blocks, parsed.layout.blocks().collect::<Vec<_>>());
+
+    for block in blocks {
+        assert_eq!(func.layout.is_cold(block), parsed.layout.is_cold(block));
+        assert_eq!(func.dfg.block_params(block), parsed.dfg.block_params(block));
+        assert_same_values(func, parsed, func.dfg.block_params(block));
+
+        let insts: Vec<_> = func.layout.block_insts(block).collect();
+        let parsed_insts: Vec<_> = parsed.layout.block_insts(block).collect();
+        assert_eq!(insts.len(), parsed_insts.len());
+        for (inst, parsed_inst) in insts.into_iter().zip(parsed_insts) {
+            let (data, pool, immediates) = Canonicalize::inst(func, inst);
+            let (parsed_data, parsed_pool, parsed_immediates) =
+                Canonicalize::inst(parsed, parsed_inst);
+
+            let display = func.dfg.display_inst(inst);
+            assert!(
+                pool == parsed_pool && data.eq(&parsed_data, &pool, |v| v),
+                "{} doesn't match {}",
+                display,
+                parsed.dfg.display_inst(parsed_inst)
+            );
+            assert_eq!(immediates, parsed_immediates, "{}", display);
+            assert_eq!(
+                func.dfg.inst_values(inst).collect::<Vec<_>>(),
+                parsed.dfg.inst_values(parsed_inst).collect::<Vec<_>>(),
+                "{}",
+                display
+            );
+            assert_eq!(
+                branch_blocks(func, &func.dfg.insts[inst]),
+                branch_blocks(parsed, &parsed.dfg.insts[parsed_inst]),
+                "{}",
+                display
+            );
+            assert_eq!(
+                func.dfg.ctrl_typevar(inst),
+                parsed.dfg.ctrl_typevar(parsed_inst),
+                "{}",
+                display
+            );
+            assert_eq!(func.srcloc(inst), parsed.srcloc(parsed_inst), "{}", display);
+            assert_eq!(
+                func.dfg.inst_results(inst),
+                parsed.dfg.inst_results(parsed_inst),
+                "{}",
+                display
+            );
+            assert_same_values(func, parsed, func.dfg.inst_results(inst));
+        }
+    }
+}
+
+fn assert_same_values(func: &Function, parsed: &Function, values: &[Value]) {
+    for &value in values {
+        assert_eq!(
+            func.dfg.value_type(value),
+            parsed.dfg.value_type(value),
+            "type of {}",
+            value
+        );
+    }
+}
+
+fn branch_blocks(func: &Function, data: &InstructionData) -> Vec<ir::Block> {
+    data.branch_destination(&func.dfg.jump_tables)
+        .iter()
+        .map(|block_call| block_call.block(&func.dfg.value_lists))
+        .collect()
+}
+
+/// Copies an instruction's value lists and block calls into a pool of its own, and replaces its
+/// jump tables and immediates by placeholders, recording the contents of the immediates.
+///
+/// Two instructions that are the same up to numbering end up with equal pools, and with the same
+/// value list and block call handles into them. Jump table contents are compared separately,
+/// through `InstructionData::branch_destination` and `DataFlowGraph::inst_values`.
+struct Canonicalize<'a> {
+    func: &'a Function,
+    pool: ValueListPool,
+    immediates: Vec<ConstantData>,
+}
+
+impl<'a> Canonicalize<'a> {
+    fn inst(
+        func: &'a Function,
+        inst: ir::Inst,
+    ) -> (InstructionData, ValueListPool, Vec<ConstantData>) {
+        let mut mapper = Self {
+            func,
+            pool: ValueListPool::new(),
+            immediates: Vec::new(),
+        };
+        let data = func.dfg.insts[inst].map(&mut mapper);
+        (data, mapper.pool, mapper.immediates)
+    }
+}
+
+impl InstructionMapper for Canonicalize<'_> {
+    fn map_value(&mut self, value: Value) -> Value {
+        value
+    }
+
+    fn map_value_list(&mut self, value_list: ValueList) -> ValueList {
+        ValueList::from_slice(
+            value_list.as_slice(&self.func.dfg.value_lists),
+            &mut self.pool,
+        )
+    }
+
+    fn map_global_value(&mut self, global_value: ir::GlobalValue) -> ir::GlobalValue {
+        global_value
+    }
+
+    fn map_jump_table(&mut self, _jump_table: ir::JumpTable) -> ir::JumpTable {
+        ir::JumpTable::from_u32(0)
+    }
+
+    fn map_block_call(&mut self, block_call: BlockCall) -> BlockCall {
+        let pool = &self.func.dfg.value_lists;
+        BlockCall::new(
+            block_call.block(pool),
+            block_call.args_slice(pool),
+            &mut self.pool,
+        )
+    }
+
+    fn map_func_ref(&mut self, func_ref: ir::FuncRef) -> ir::FuncRef {
+        func_ref
+    }
+
+    fn map_sig_ref(&mut self, sig_ref: ir::SigRef) -> ir::SigRef {
+        sig_ref
+    }
+
+    fn map_stack_slot(&mut self, stack_slot: ir::StackSlot) -> ir::StackSlot {
+        stack_slot
+    }
+
+    fn map_dynamic_stack_slot(
+        &mut self,
+        dynamic_stack_slot: ir::DynamicStackSlot,
+    ) -> ir::DynamicStackSlot {
+        dynamic_stack_slot
+    }
+
+    fn map_table(&mut self, table: ir::Table) -> ir::Table {
+        table
+    }
+
+    fn map_constant(&mut self, constant: ir::Constant) -> ir::Constant {
+        constant
+    }
+
+    fn map_immediate(&mut self, immediate: ir::Immediate) -> ir::Immediate {
+        self.immediates
+            .push(self.func.dfg.immediates[immediate].clone());
+        ir::Immediate::from_u32(self.immediates.len() as u32 - 1)
+    }
+}
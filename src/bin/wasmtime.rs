@@ -6,7 +6,8 @@
 use anyhow::Result;
 use clap::Parser;
 use wasmtime_cli::commands::{
-    CompileCommand, ConfigCommand, ExploreCommand, RunCommand, SettingsCommand, WastCommand,
+    CompileCommand, ConfigCommand, CoredumpCommand, ExploreCommand, RunCommand, SettingsCommand,
+    WastCommand,
 };
 
 /// Wasmtime WebAssembly Runtime
@@ -49,6 +50,8 @@ enum Subcommand {
     Config(ConfigCommand),
     /// Compiles a WebAssembly module.
     Compile(CompileCommand),
+    /// Inspect a core dump written by `wasmtime run --coredump-on-trap`.
+    Coredump(CoredumpCommand),
     /// Explore the compilation of a WebAssembly module to native code.
     Explore(ExploreCommand),
     /// Runs a WebAssembly module
@@ -66,6 +69,7 @@ impl Wasmtime {
         match subcommand {
             Subcommand::Config(c) => c.execute(),
             Subcommand::Compile(c) => c.execute(),
+            Subcommand::Coredump(c) => c.execute(),
             Subcommand::Explore(c) => c.execute(),
             Subcommand::Run(c) => c.execute(),
             Subcommand::Settings(c) => c.execute(),
@@ -2,9 +2,10 @@
 
 mod compile;
 mod config;
+mod coredump;
 mod explore;
 mod run;
 mod settings;
 mod wast;
 
-pub use self::{compile::*, config::*, explore::*, run::*, settings::*, wast::*};
+pub use self::{compile::*, config::*, coredump::*, explore::*, run::*, settings::*, wast::*};
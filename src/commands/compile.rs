@@ -5,7 +5,7 @@ use clap::Parser;
 use once_cell::sync::Lazy;
 use std::fs;
 use std::path::PathBuf;
-use wasmtime::Engine;
+use wasmtime::{Engine, Module};
 use wasmtime_cli_flags::CommonOptions;
 
 static AFTER_HELP: Lazy<String> = Lazy::new(|| {
@@ -26,7 +26,11 @@ static AFTER_HELP: Lazy<String> = Lazy::new(|| {
         \n\
         Compiling for a specific platform (Linux) and CPU preset (Skylake):\n\
         \n  \
-        wasmtime compile --target x86_64-unknown-linux --cranelift-enable skylake foo.wasm\n",
+        wasmtime compile --target x86_64-unknown-linux --cranelift-enable skylake foo.wasm\n\
+        \n\
+        Writing a JSON report about the compilation of each function:\n\
+        \n  \
+        wasmtime compile --report report.json foo.wasm\n",
         crate::FLAG_EXPLANATIONS.as_str()
     )
 });
@@ -54,6 +58,11 @@ pub struct CompileCommand {
     #[clap(long = "emit-clif", value_name = "PATH")]
     emit_clif: Option<PathBuf>,
 
+    /// The path to write a JSON report about the compilation of each function
+    /// to; only supported for core modules compiled for the host.
+    #[clap(long = "report", value_name = "PATH")]
+    report: Option<PathBuf>,
+
     /// The path of the WebAssembly to compile
     #[clap(index = 1, value_name = "MODULE")]
     module: PathBuf,
@@ -112,22 +121,58 @@ impl CompileCommand {
                 ..
             }) = wasmparser::Parser::new(0).parse(&input, true)
             {
+                if self.report.is_some() {
+                    bail!("'--report' is not supported for components");
+                }
                 fs::write(output, engine.precompile_component(&input)?)?;
                 return Ok(());
             }
         }
+
+        if let Some(report) = &self.report {
+            // The report is read from a loaded module, so compile it that way
+            // rather than through `precompile_module`.
+            let module = Module::new(&engine, &input)
+                .context("'--report' requires compiling for the host")?;
+            fs::write(output, module.serialize()?)?;
+            let report_json = serde_json::to_string_pretty(&function_reports(&module))?;
+            fs::write(report, report_json)
+                .with_context(|| format!("failed to write report to {}", report.display()))?;
+            return Ok(());
+        }
         fs::write(output, engine.precompile_module(&input)?)?;
 
         Ok(())
     }
 }
 
+/// Returns the JSON report of `wasmtime compile --report`.
+fn function_reports(module: &Module) -> serde_json::Value {
+    module
+        .function_reports()
+        .map(|report| {
+            serde_json::json!({
+                "index": report.index(),
+                "name": report.name(),
+                "body_offset": report.body_offset(),
+                "body_size": report.body_size(),
+                "code_size": report.code_size(),
+                "trap_sites": report.trap_sites(),
+                "stack_map_entries": report.stack_map_entries(),
+                "compile_time_us": report.compile_time().map(|t| t.as_micros() as u64),
+                "insts_before_opt": report.insts_before_opt(),
+                "insts_after_opt": report.insts_after_opt(),
+            })
+        })
+        .collect()
+}
+
 #[cfg(all(test, not(miri)))]
 mod test {
     use super::*;
     use std::io::Write;
     use tempfile::NamedTempFile;
-    use wasmtime::{Instance, Module, Store};
+    use wasmtime::{Instance, Store};
 
     #[test]
     fn test_successful_compile() -> Result<()> {
@@ -160,6 +205,48 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_compile_report() -> Result<()> {
+        let (mut input, input_path) = NamedTempFile::new()?.into_parts();
+        input.write_all(
+            "(module (func $add (param i32 i32) (result i32) local.get 0 local.get 1 i32.add) (func))"
+                .as_bytes(),
+        )?;
+        drop(input);
+
+        let output_path = NamedTempFile::new()?.into_temp_path();
+        let report_path = NamedTempFile::new()?.into_temp_path();
+
+        let command = CompileCommand::try_parse_from(vec![
+            "compile",
+            "--disable-logging",
+            "--disable-cache",
+            "-o",
+            output_path.to_str().unwrap(),
+            "--report",
+            report_path.to_str().unwrap(),
+            input_path.to_str().unwrap(),
+        ])?;
+
+        command.execute()?;
+
+        let report: serde_json::Value = serde_json::from_slice(&std::fs::read(report_path)?)?;
+        let funcs = report.as_array().unwrap();
+        assert_eq!(funcs.len(), 2);
+        assert_eq!(funcs[0]["index"], 0);
+        assert_eq!(funcs[0]["name"], "add");
+        assert!(funcs[0]["code_size"].as_u64().unwrap() > 0);
+        assert!(funcs[0]["insts_before_opt"].as_u64().unwrap() > 0);
+        assert!(funcs[0]["compile_time_us"].is_u64());
+        assert_eq!(funcs[1]["name"], serde_json::Value::Null);
+
+        let engine = Engine::default();
+        let contents = std::fs::read(output_path)?;
+        unsafe { Module::deserialize(&engine, contents)? };
+
+        Ok(())
+    }
+
     #[cfg(target_arch = "x86_64")]
     #[test]
     fn test_x64_flags_compile() -> Result<()> {
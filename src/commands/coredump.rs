@@ -0,0 +1,661 @@
+//! The module that implements the `wasmtime coredump` command.
+
+use anyhow::{anyhow, bail, Context, Result};
+use clap::Parser;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+use wasmparser::{BinaryReader, DataKind, Operator, Parser as WasmParser, Payload};
+use wasmtime::{Engine, FunctionReport, Module, WasmBacktraceDetails};
+use wasmtime_cli_flags::CommonOptions;
+
+/// Inspect a core dump written by `wasmtime run --coredump-on-trap`.
+#[derive(Parser)]
+#[clap(name = "coredump")]
+pub struct CoredumpCommand {
+    #[clap(flatten)]
+    common: CommonOptions,
+
+    /// The path of the core dump to inspect
+    #[clap(required = true, value_name = "COREDUMP")]
+    coredump: PathBuf,
+
+    /// The path of the WebAssembly module which dumped core, used to
+    /// symbolize stack frames
+    #[clap(required = true, value_name = "MODULE")]
+    module: PathBuf,
+
+    /// Print a region of memory, where MEMORY is an index of a memory in the
+    /// core dump (may be repeated)
+    #[clap(
+        long = "memory",
+        number_of_values = 1,
+        value_name = "MEMORY:ADDRESS:LENGTH",
+        value_parser = parse_memory_region
+    )]
+    memory: Vec<(u32, u64, u64)>,
+
+    /// Start an interactive prompt to explore the core dump
+    #[clap(short, long)]
+    interactive: bool,
+}
+
+fn parse_memory_region(s: &str) -> Result<(u32, u64, u64)> {
+    let parts: Vec<&str> = s.split(':').collect();
+    if parts.len() != 3 {
+        bail!("must be of the form `MEMORY:ADDRESS:LENGTH`");
+    }
+    Ok((
+        parse_number(parts[0])?,
+        parse_number(parts[1])?,
+        parse_number(parts[2])?,
+    ))
+}
+
+/// Parses a decimal number, or a hexadecimal number prefixed with `0x`.
+fn parse_number<T: TryFrom<u64>>(s: &str) -> Result<T> {
+    let n = match s.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => s.parse(),
+    }
+    .with_context(|| format!("invalid number `{s}`"))?;
+    T::try_from(n).map_err(|_| anyhow!("number `{s}` is out of range"))
+}
+
+impl CoredumpCommand {
+    /// Executes the command.
+    pub fn execute(&self) -> Result<()> {
+        self.common.init_logging();
+
+        let bytes = std::fs::read(&self.coredump)
+            .with_context(|| format!("failed to read core dump: {}", self.coredump.display()))?;
+        let coredump = CoreDump::parse(&bytes)
+            .with_context(|| format!("failed to parse core dump: {}", self.coredump.display()))?;
+
+        let mut config = self.common.config(None)?;
+        config.wasm_backtrace_details(WasmBacktraceDetails::Enable);
+        let engine = Engine::new(&config)?;
+        let module = Module::from_file(&engine, &self.module)?;
+        let inspector = Inspector::new(&coredump, &module);
+
+        let stdout = std::io::stdout();
+        let mut out = stdout.lock();
+        inspector.print_summary(&mut out)?;
+        for (memory, address, len) in self.memory.iter() {
+            writeln!(out)?;
+            inspector.print_memory(&mut out, *memory, *address, *len)?;
+        }
+        if self.interactive {
+            inspector.interact(&mut std::io::stdin().lock(), &mut out)?;
+        }
+        Ok(())
+    }
+}
+
+/// A value of a global, local, or operand stack slot in a core dump.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Value {
+    /// The value wasn't recorded, for example because it was optimized away.
+    Missing,
+    I32(i32),
+    I64(i64),
+    F32(u32),
+    F64(u64),
+    V128(u128),
+    /// A null reference; references can't be recorded in core dumps.
+    Null,
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Missing => write!(f, "<missing>"),
+            Value::I32(x) => write!(f, "i32 {x}"),
+            Value::I64(x) => write!(f, "i64 {x}"),
+            Value::F32(x) => write!(f, "f32 {}", f32::from_bits(*x)),
+            Value::F64(x) => write!(f, "f64 {}", f64::from_bits(*x)),
+            Value::V128(x) => write!(f, "v128 {x:#034x}"),
+            Value::Null => write!(f, "ref.null"),
+        }
+    }
+}
+
+/// An instance recorded in the `coreinstances` section.
+#[derive(Debug)]
+struct CoreInstance {
+    module: u32,
+    memories: Vec<u32>,
+    globals: Vec<u32>,
+}
+
+/// A stack frame recorded in the `corestack` section.
+#[derive(Debug)]
+struct CoreFrame {
+    instance: u32,
+    func: u32,
+    code_offset: u32,
+    locals: Vec<Value>,
+    stack: Vec<Value>,
+}
+
+/// A memory of the core dump, stored as the data segments which initialize
+/// it rather than as a contiguous buffer, since most of a memory is usually
+/// zeros.
+#[derive(Debug, Default)]
+struct CoreMemory<'a> {
+    size: u64,
+    segments: Vec<(u64, &'a [u8])>,
+}
+
+impl CoreMemory<'_> {
+    /// Reads `len` bytes at `address`, or returns an error if the region is
+    /// out of bounds.
+    fn read(&self, address: u64, len: u64) -> Result<Vec<u8>> {
+        let end = match address.checked_add(len) {
+            Some(end) if end <= self.size => end,
+            _ => bail!("region is out of bounds"),
+        };
+        let mut bytes = vec![0; usize::try_from(len)?];
+        for (offset, data) in self.segments.iter() {
+            let data_end = offset
+                .checked_add(data.len() as u64)
+                .ok_or_else(|| anyhow!("data segment at {offset:#x} overflows"))?;
+            let start = address.max(*offset);
+            let stop = end.min(data_end);
+            if start < stop {
+                bytes[(start - address) as usize..(stop - address) as usize]
+                    .copy_from_slice(&data[(start - offset) as usize..(stop - offset) as usize]);
+            }
+        }
+        Ok(bytes)
+    }
+}
+
+/// A core dump in the [standard core dump format].
+///
+/// [standard core dump format]:
+///     https://github.com/WebAssembly/tool-conventions/blob/main/Coredump.md
+#[derive(Debug, Default)]
+struct CoreDump<'a> {
+    name: String,
+    modules: Vec<String>,
+    instances: Vec<CoreInstance>,
+    thread: String,
+    frames: Vec<CoreFrame>,
+    memories: Vec<CoreMemory<'a>>,
+    globals: Vec<(bool, Value)>,
+}
+
+impl<'a> CoreDump<'a> {
+    fn parse(bytes: &'a [u8]) -> Result<CoreDump<'a>> {
+        let mut coredump = CoreDump::default();
+        let mut found_core = false;
+        for payload in WasmParser::new(0).parse_all(bytes) {
+            match payload? {
+                Payload::CustomSection(section) => {
+                    let mut reader = BinaryReader::new(section.data());
+                    match section.name() {
+                        "core" => {
+                            found_core = true;
+                            expect_zero(&mut reader)?;
+                            coredump.name = reader.read_string()?.to_string();
+                        }
+                        "coremodules" => {
+                            for _ in 0..reader.read_var_u32()? {
+                                expect_zero(&mut reader)?;
+                                coredump.modules.push(reader.read_string()?.to_string());
+                            }
+                        }
+                        "coreinstances" => {
+                            for _ in 0..reader.read_var_u32()? {
+                                expect_zero(&mut reader)?;
+                                let module = reader.read_var_u32()?;
+                                let memories = read_indices(&mut reader)?;
+                                let globals = read_indices(&mut reader)?;
+                                coredump.instances.push(CoreInstance {
+                                    module,
+                                    memories,
+                                    globals,
+                                });
+                            }
+                        }
+                        "corestack" => {
+                            expect_zero(&mut reader)?;
+                            coredump.thread = reader.read_string()?.to_string();
+                            for _ in 0..reader.read_var_u32()? {
+                                expect_zero(&mut reader)?;
+                                coredump.frames.push(CoreFrame {
+                                    instance: reader.read_var_u32()?,
+                                    func: reader.read_var_u32()?,
+                                    code_offset: reader.read_var_u32()?,
+                                    locals: read_values(&mut reader)?,
+                                    stack: read_values(&mut reader)?,
+                                });
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                Payload::MemorySection(section) => {
+                    for ty in section {
+                        let pages = ty?.initial;
+                        let size = pages
+                            .checked_mul(u64::from(wasmtime_environ::WASM_PAGE_SIZE))
+                            .ok_or_else(|| anyhow!("memory of {pages} pages is too large"))?;
+                        coredump.memories.push(CoreMemory {
+                            size,
+                            segments: Vec::new(),
+                        });
+                    }
+                }
+                Payload::GlobalSection(section) => {
+                    for global in section {
+                        let global = global?;
+                        let value = match global.init_expr.get_operators_reader().read()? {
+                            Operator::I32Const { value } => Value::I32(value),
+                            Operator::I64Const { value } => Value::I64(value),
+                            Operator::F32Const { value } => Value::F32(value.bits()),
+                            Operator::F64Const { value } => Value::F64(value.bits()),
+                            Operator::V128Const { value } => Value::V128(value.i128() as u128),
+                            Operator::RefNull { .. } => Value::Null,
+                            op => bail!("unsupported global initializer {op:?}"),
+                        };
+                        coredump.globals.push((global.ty.mutable, value));
+                    }
+                }
+                Payload::DataSection(section) => {
+                    for data in section {
+                        let data = data?;
+                        let (memory, offset) = match data.kind {
+                            DataKind::Active {
+                                memory_index,
+                                offset_expr,
+                            } => {
+                                let offset = match offset_expr.get_operators_reader().read()? {
+                                    Operator::I32Const { value } => u64::from(value as u32),
+                                    Operator::I64Const { value } => value as u64,
+                                    op => bail!("unsupported data segment offset {op:?}"),
+                                };
+                                (memory_index, offset)
+                            }
+                            DataKind::Passive => continue,
+                        };
+                        coredump
+                            .memories
+                            .get_mut(memory as usize)
+                            .ok_or_else(|| anyhow!("data segment for unknown memory {memory}"))?
+                            .segments
+                            .push((offset, data.data));
+                    }
+                }
+                _ => {}
+            }
+        }
+        if !found_core {
+            bail!("not a core dump: missing the `core` custom section");
+        }
+        Ok(coredump)
+    }
+}
+
+fn expect_zero(reader: &mut BinaryReader<'_>) -> Result<()> {
+    match reader.read_u8()? {
+        0 => Ok(()),
+        byte => bail!("unsupported core dump entry kind {byte:#x}"),
+    }
+}
+
+fn read_indices(reader: &mut BinaryReader<'_>) -> Result<Vec<u32>> {
+    (0..reader.read_var_u32()?)
+        .map(|_| Ok(reader.read_var_u32()?))
+        .collect()
+}
+
+fn read_values(reader: &mut BinaryReader<'_>) -> Result<Vec<Value>> {
+    (0..reader.read_var_u32()?)
+        .map(|_| {
+            Ok(match reader.read_u8()? {
+                0x01 => Value::Missing,
+                0x7F => Value::I32(reader.read_var_i32()?),
+                0x7E => Value::I64(reader.read_var_i64()?),
+                0x7D => Value::F32(reader.read_f32()?.bits()),
+                0x7C => Value::F64(reader.read_f64()?.bits()),
+                byte => bail!("unsupported value type {byte:#x}"),
+            })
+        })
+        .collect()
+}
+
+/// Prints the contents of a core dump, symbolizing frames with the module
+/// which dumped core.
+struct Inspector<'a> {
+    coredump: &'a CoreDump<'a>,
+    module: &'a Module,
+    /// The index of the module within the core dump which `module` is, if it
+    /// could be identified.
+    module_index: Option<u32>,
+    functions: HashMap<u32, FunctionReport>,
+}
+
+impl<'a> Inspector<'a> {
+    fn new(coredump: &'a CoreDump<'a>, module: &'a Module) -> Inspector<'a> {
+        // Core dumps identify modules by name only, so if there's more than
+        // one module the name has to match.
+        let module_index = if coredump.modules.len() == 1 {
+            Some(0)
+        } else {
+            module.name().and_then(|name| {
+                coredump
+                    .modules
+                    .iter()
+                    .position(|m| m == name)
+                    .map(|i| i as u32)
+            })
+        };
+        Inspector {
+            coredump,
+            module,
+            module_index,
+            functions: module
+                .function_reports()
+                .map(|report| (report.index(), report))
+                .collect(),
+        }
+    }
+
+    fn print_summary(&self, out: &mut impl Write) -> Result<()> {
+        writeln!(out, "core dump of `{}`", self.coredump.name)?;
+        writeln!(out)?;
+        self.print_backtrace(out)?;
+        writeln!(out)?;
+        self.print_globals(out)
+    }
+
+    fn print_backtrace(&self, out: &mut impl Write) -> Result<()> {
+        writeln!(out, "stack of thread `{}`:", self.coredump.thread)?;
+        for i in 0..self.coredump.frames.len() {
+            self.print_frame(out, i)?;
+        }
+        Ok(())
+    }
+
+    fn print_frame(&self, out: &mut impl Write, index: usize) -> Result<()> {
+        let frame = match self.coredump.frames.get(index) {
+            Some(frame) => frame,
+            None => bail!("no frame {index}"),
+        };
+        let module = self
+            .coredump
+            .instances
+            .get(frame.instance as usize)
+            .map(|instance| instance.module);
+        let module_name = module
+            .and_then(|m| self.coredump.modules.get(m as usize))
+            .map(|name| name.as_str())
+            .unwrap_or("<unknown>");
+        let report = if module.is_some() && module == self.module_index {
+            self.functions.get(&frame.func)
+        } else {
+            None
+        };
+
+        write!(out, "  {index:>3}: ")?;
+        match report {
+            Some(report) => {
+                let offset = report.body_offset() + frame.code_offset;
+                write!(out, "{offset:#8x} - {module_name}!")?;
+                match report.name() {
+                    Some(name) => writeln!(out, "{name}")?,
+                    None => writeln!(out, "<wasm function {}>", frame.func)?,
+                }
+                for symbol in self.module.symbolize(offset) {
+                    write!(out, "                    at ")?;
+                    if let Some(name) = symbol.name() {
+                        write!(out, "{name} ")?;
+                    }
+                    write!(out, "{}", symbol.file().unwrap_or("<unknown>"))?;
+                    if let Some(line) = symbol.line() {
+                        write!(out, ":{line}")?;
+                        if let Some(column) = symbol.column() {
+                            write!(out, ":{column}")?;
+                        }
+                    }
+                    writeln!(out)?;
+                }
+            }
+            None => writeln!(
+                out,
+                "{module_name}!<wasm function {}> + {:#x}",
+                frame.func, frame.code_offset
+            )?,
+        }
+        for (i, value) in frame.locals.iter().enumerate() {
+            writeln!(out, "         local {i}: {value}")?;
+        }
+        // Print the top of the operand stack first.
+        for (i, value) in frame.stack.iter().rev().enumerate() {
+            writeln!(out, "         stack {i}: {value}")?;
+        }
+        Ok(())
+    }
+
+    fn print_globals(&self, out: &mut impl Write) -> Result<()> {
+        for (i, instance) in self.coredump.instances.iter().enumerate() {
+            let module_name = self
+                .coredump
+                .modules
+                .get(instance.module as usize)
+                .map(|name| name.as_str())
+                .unwrap_or("<unknown>");
+            writeln!(out, "instance {i} of `{module_name}`:")?;
+            for (j, memory) in instance.memories.iter().enumerate() {
+                let size = self
+                    .coredump
+                    .memories
+                    .get(*memory as usize)
+                    .map(|m| m.size)
+                    .unwrap_or(0);
+                writeln!(
+                    out,
+                    "  memory {j} (core dump memory {memory}): {size:#x} bytes"
+                )?;
+            }
+            for (j, global) in instance.globals.iter().enumerate() {
+                match self.coredump.globals.get(*global as usize) {
+                    Some((mutable, value)) => {
+                        let mutability = if *mutable { "mut " } else { "" };
+                        writeln!(out, "  global {j}: {mutability}{value}")?;
+                    }
+                    None => writeln!(out, "  global {j}: <missing>")?,
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn print_memory(
+        &self,
+        out: &mut impl Write,
+        memory: u32,
+        address: u64,
+        len: u64,
+    ) -> Result<()> {
+        let bytes = self
+            .coredump
+            .memories
+            .get(memory as usize)
+            .ok_or_else(|| anyhow!("no memory {memory} in the core dump"))?
+            .read(address, len)
+            .with_context(|| format!("cannot read memory {memory}"))?;
+        writeln!(out, "memory {memory} at {address:#x}:")?;
+        for (i, line) in bytes.chunks(16).enumerate() {
+            write!(out, "  {:#010x}:", address + i as u64 * 16)?;
+            for byte in line {
+                write!(out, " {byte:02x}")?;
+            }
+            write!(out, "{:1$}  ", "", (16 - line.len()) * 3)?;
+            for byte in line {
+                let c = if byte.is_ascii_graphic() || *byte == b' ' {
+                    *byte as char
+                } else {
+                    '.'
+                };
+                write!(out, "{c}")?;
+            }
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+
+    /// Runs a prompt which reads commands from `input` until it's exhausted
+    /// or `quit` is entered.
+    fn interact(&self, input: &mut impl BufRead, out: &mut impl Write) -> Result<()> {
+        loop {
+            write!(out, "(coredump) ")?;
+            out.flush()?;
+            let mut line = String::new();
+            if input.read_line(&mut line)? == 0 {
+                writeln!(out)?;
+                return Ok(());
+            }
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let result = match &words[..] {
+                [] => Ok(()),
+                ["bt" | "backtrace"] => self.print_backtrace(out),
+                ["frame" | "f", index] => {
+                    parse_number(index).and_then(|index| self.print_frame(out, index))
+                }
+                ["globals" | "g"] => self.print_globals(out),
+                ["memory" | "m", memory, address, len] => parse_number(memory)
+                    .and_then(|memory| Ok((memory, parse_number(address)?, parse_number(len)?)))
+                    .and_then(|(memory, address, len)| {
+                        self.print_memory(out, memory, address, len)
+                    }),
+                ["help" | "h"] => {
+                    writeln!(out, "commands:")?;
+                    writeln!(out, "  bt                       print the stack")?;
+                    writeln!(out, "  frame INDEX              print one stack frame")?;
+                    writeln!(
+                        out,
+                        "  globals                  print instances and globals"
+                    )?;
+                    writeln!(out, "  memory MEMORY ADDR LEN   print a region of memory")?;
+                    writeln!(out, "  quit                     exit the prompt")?;
+                    Ok(())
+                }
+                ["quit" | "q" | "exit"] => return Ok(()),
+                _ => Err(anyhow!("unknown command, try `help`")),
+            };
+            if let Err(e) = result {
+                writeln!(out, "error: {e:#}")?;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use wasmtime::{Config, Instance, Store, WasmCoreDump};
+
+    fn coredump(engine: &Engine, module: &Module) -> Result<Vec<u8>> {
+        let mut store = Store::new(engine, ());
+        let instance = Instance::new(&mut store, module, &[])?;
+        let run = instance.get_typed_func::<(), ()>(&mut store, "run")?;
+        let err = run.call(&mut store, ()).unwrap_err();
+        let coredump = err.downcast_ref::<WasmCoreDump>().unwrap();
+        Ok(coredump.serialize(&mut store, "test.wasm"))
+    }
+
+    #[test]
+    fn test_inspect_coredump() -> Result<()> {
+        let mut config = Config::new();
+        config.coredump_on_trap(true);
+        let engine = Engine::new(&config)?;
+        let module = Module::new(
+            &engine,
+            r#"
+                (module $m
+                  (memory 1)
+                  (global $g (mut i32) (i32.const 7))
+                  (global f64 (f64.const 1.5))
+                  (data (i32.const 0x10) "hello")
+                  (func $run (export "run")
+                    (global.set $g (i32.const 42))
+                    (call $crash))
+                  (func $crash unreachable))
+            "#,
+        )?;
+        let bytes = coredump(&engine, &module)?;
+        let coredump = CoreDump::parse(&bytes)?;
+        assert_eq!(coredump.name, "test.wasm");
+        assert_eq!(coredump.modules, ["m"]);
+        assert_eq!(coredump.instances.len(), 1);
+        assert_eq!(coredump.frames.len(), 2);
+        assert_eq!(coredump.frames[0].func, 1);
+        assert_eq!(coredump.frames[1].func, 0);
+        assert_eq!(
+            coredump.globals,
+            [
+                (true, Value::I32(42)),
+                (false, Value::F64(1.5f64.to_bits()))
+            ]
+        );
+        assert_eq!(coredump.memories[0].size, 0x10000);
+        assert_eq!(coredump.memories[0].read(0xe, 9).unwrap(), b"\0\0hello\0\0");
+        assert!(coredump.memories[0].read(0xffff, 2).is_err());
+        assert!(coredump.memories[0].read(u64::MAX, 2).is_err());
+
+        let inspector = Inspector::new(&coredump, &module);
+        let mut out = Vec::new();
+        inspector.print_summary(&mut out)?;
+        inspector.print_memory(&mut out, 0, 0x10, 5)?;
+        let out = String::from_utf8(out)?;
+        assert!(out.contains("m!crash"), "{out}");
+        assert!(out.contains("m!run"), "{out}");
+        assert!(out.contains("global 0: mut i32 42"), "{out}");
+        assert!(out.contains("global 1: f64 1.5"), "{out}");
+        assert!(out.contains("68 65 6c 6c 6f"), "{out}");
+        assert!(out.contains("hello"), "{out}");
+
+        let mut out = Vec::new();
+        let mut input = "frame 0\nmemory 0 0x10 5\nmemory 3 0 1\nbogus\nquit\n".as_bytes();
+        inspector.interact(&mut input, &mut out)?;
+        let out = String::from_utf8(out)?;
+        assert!(out.contains("m!crash"), "{out}");
+        assert!(!out.contains("m!run"), "{out}");
+        assert!(out.contains("hello"), "{out}");
+        assert!(out.contains("error: no memory 3 in the core dump"), "{out}");
+        assert!(out.contains("error: unknown command"), "{out}");
+        Ok(())
+    }
+
+    #[test]
+    fn test_coredump_frame_values() -> Result<()> {
+        let mut config = Config::new();
+        config.coredump_on_trap(true).debug_instrumentation(true);
+        let engine = Engine::new(&config)?;
+        let module = Module::new(
+            &engine,
+            r#"
+                (module
+                  (func (export "run")
+                    (call $crash (i32.const 3)))
+                  (func $crash (param i32) (local i64)
+                    (local.set 1 (i64.const 5))
+                    (local.get 0)
+                    unreachable))
+            "#,
+        )?;
+        let bytes = coredump(&engine, &module)?;
+        let coredump = CoreDump::parse(&bytes)?;
+        assert_eq!(coredump.frames.len(), 2);
+        assert_eq!(coredump.frames[0].locals, [Value::I32(3), Value::I64(5)]);
+        assert_eq!(coredump.frames[0].stack, [Value::I32(3)]);
+        assert!(coredump.frames[1].locals.is_empty());
+        // The arguments of a call are still on the caller's operand stack.
+        assert_eq!(coredump.frames[1].stack, [Value::I32(3)]);
+        Ok(())
+    }
+}
@@ -10,15 +10,15 @@ use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
 use wasmtime::{
-    AsContextMut, Engine, Func, GuestProfiler, Linker, Module, Store, StoreLimits,
-    StoreLimitsBuilder, UpdateDeadline, Val, ValType,
+    AsContextMut, Engine, Func, GuestProfileFormat, GuestProfiler, Linker, Module, Store,
+    StoreLimits, StoreLimitsBuilder, UpdateDeadline, Val, ValType,
 };
 use wasmtime_cli_flags::{CommonOptions, WasiModules};
 use wasmtime_wasi::maybe_exit_on_error;
-use wasmtime_wasi::sync::{ambient_authority, Dir, TcpListener, WasiCtxBuilder};
+use wasmtime_wasi::sync::{ambient_authority, Dir, Replay, TcpListener, WasiCtxBuilder};
 
 #[cfg(feature = "wasi-nn")]
-use wasmtime_wasi_nn::WasiNnCtx;
+use wasmtime_wasi_nn::{GraphRegistry, WasiNnCtx};
 
 #[cfg(feature = "wasi-threads")]
 use wasmtime_wasi_threads::WasiThreadsCtx;
@@ -66,18 +66,39 @@ fn parse_profile(s: &str) -> Result<Profile> {
         ["perfmap"] => Ok(Profile::Native(wasmtime::ProfilingStrategy::PerfMap)),
         ["jitdump"] => Ok(Profile::Native(wasmtime::ProfilingStrategy::JitDump)),
         ["vtune"] => Ok(Profile::Native(wasmtime::ProfilingStrategy::VTune)),
-        ["guest"] => Ok(Profile::Guest {
-            path: "wasmtime-guest-profile.json".to_string(),
-            interval: Duration::from_millis(10),
-        }),
-        ["guest", path] => Ok(Profile::Guest {
-            path: path.to_string(),
-            interval: Duration::from_millis(10),
-        }),
-        ["guest", path, dur] => Ok(Profile::Guest {
-            path: path.to_string(),
-            interval: parse_dur(dur)?,
-        }),
+        ["guest", options @ ..] => {
+            let mut format = GuestProfileFormat::Firefox;
+            let mut positional = Vec::new();
+            for option in options {
+                match option.strip_prefix("format=") {
+                    Some("firefox") => format = GuestProfileFormat::Firefox,
+                    Some("pprof") => format = GuestProfileFormat::Pprof,
+                    Some("speedscope") => format = GuestProfileFormat::Speedscope,
+                    Some(other) => bail!("unknown guest profile format: {other}"),
+                    None => positional.push(*option),
+                }
+            }
+            let (path, interval) = match &positional[..] {
+                [] => (None, None),
+                [path] => (Some(path), None),
+                [path, dur] => (Some(path), Some(parse_dur(dur)?)),
+                _ => bail!("unknown profiling strategy: {s}"),
+            };
+            let path = match path {
+                Some(path) => path.to_string(),
+                None => match format {
+                    GuestProfileFormat::Pprof => "wasmtime-guest-profile.pb",
+                    GuestProfileFormat::Speedscope => "wasmtime-guest-profile.speedscope.json",
+                    _ => "wasmtime-guest-profile.json",
+                }
+                .to_string(),
+            };
+            Ok(Profile::Guest {
+                path,
+                interval: interval.unwrap_or(Duration::from_millis(10)),
+                format,
+            })
+        }
         _ => bail!("unknown profiling strategy: {s}"),
     }
 }
@@ -149,6 +170,15 @@ pub struct RunCommand {
     #[clap(long = "mapdir", number_of_values = 1, value_name = "GUEST_DIR::HOST_DIR", value_parser = parse_map_dirs)]
     map_dirs: Vec<(String, String)>,
 
+    /// Preload a wasi-nn graph from the given host path, which guests can then
+    /// load with `load_by_name` using the given name.
+    ///
+    /// The path is either an ONNX model (a `*.onnx` file, or a directory
+    /// containing `model.onnx`) or an OpenVINO model (a directory containing
+    /// `model.xml` and `model.bin`).
+    #[clap(long = "wasi-nn-graph", number_of_values = 1, value_name = "NAME::PATH", value_parser = parse_map_dirs)]
+    wasi_nn_graphs: Vec<(String, String)>,
+
     /// Load the given WebAssembly module before the main module
     #[clap(
         long = "preload",
@@ -176,11 +206,12 @@ pub struct RunCommand {
     ///
     /// The `guest` option can be additionally configured as:
     ///
-    ///     --profile=guest[,path[,interval]]
+    ///     --profile=guest[,path[,interval]][,format=FORMAT]
     ///
     /// where `path` is where to write the profile and `interval` is the
     /// duration between samples. When used with `--wasm-timeout` the timeout
-    /// will be rounded up to the nearest multiple of this interval.
+    /// will be rounded up to the nearest multiple of this interval. `FORMAT`
+    /// is one of `firefox` (the default), `pprof` or `speedscope`.
     #[clap(
         long,
         value_name = "STRATEGY",
@@ -188,6 +219,18 @@ pub struct RunCommand {
     )]
     profile: Option<Profile>,
 
+    /// Record the nondeterministic results observed by the guest through WASI,
+    /// such as clock readings, random bytes and file reads, into the given
+    /// file.
+    #[clap(long, value_name = "PATH", conflicts_with = "replay")]
+    record: Option<PathBuf>,
+
+    /// Replay the nondeterministic results previously recorded with
+    /// `--record` from the given file, rather than observing them from the
+    /// host.
+    #[clap(long, value_name = "PATH")]
+    replay: Option<PathBuf>,
+
     /// Enable coredump generation after a WebAssembly trap.
     #[clap(long = "coredump-on-trap", value_name = "PATH")]
     coredump_on_trap: Option<String>,
@@ -242,7 +285,11 @@ pub struct RunCommand {
 #[derive(Clone)]
 enum Profile {
     Native(wasmtime::ProfilingStrategy),
-    Guest { path: String, interval: Duration },
+    Guest {
+        path: String,
+        interval: Duration,
+        format: GuestProfileFormat,
+    },
 }
 
 impl RunCommand {
@@ -267,6 +314,7 @@ impl RunCommand {
         }
 
         config.wmemcheck(self.wmemcheck);
+        config.coredump_on_trap(self.coredump_on_trap.is_some());
 
         let engine = Engine::new(&config)?;
 
@@ -282,6 +330,7 @@ impl RunCommand {
         // Make wasi available by default.
         let preopen_dirs = self.compute_preopen_dirs()?;
         let argv = self.compute_argv()?;
+        let replay = self.compute_replay()?;
 
         let mut linker = Linker::new(&engine);
         linker.allow_unknown_exports(self.allow_unknown_exports);
@@ -302,6 +351,8 @@ impl RunCommand {
             &self.common.wasi_modules.unwrap_or(WasiModules::default()),
             self.listenfd,
             preopen_sockets,
+            replay,
+            &self.wasi_nn_graphs,
         )?;
 
         let mut limits = StoreLimitsBuilder::new();
@@ -423,14 +474,34 @@ impl RunCommand {
         Ok(result)
     }
 
+    fn compute_replay(&self) -> Result<Option<Replay>> {
+        if let Some(path) = &self.record {
+            let file = std::fs::File::create(path)
+                .with_context(|| format!("failed to create replay log {}", path.display()))?;
+            return Ok(Some(Replay::record(file)));
+        }
+        if let Some(path) = &self.replay {
+            let file = std::fs::File::open(path)
+                .with_context(|| format!("failed to open replay log {}", path.display()))?;
+            return Ok(Some(Replay::replay(file)));
+        }
+        Ok(None)
+    }
+
     fn setup_epoch_handler(
         &self,
         store: &mut Store<Host>,
         module_name: &str,
         modules: Vec<(String, Module)>,
     ) -> Box<dyn FnOnce(&mut Store<Host>)> {
-        if let Some(Profile::Guest { path, interval }) = &self.profile {
+        if let Some(Profile::Guest {
+            path,
+            interval,
+            format,
+        }) = &self.profile
+        {
             let interval = *interval;
+            let format = *format;
             store.data_mut().guest_profiler =
                 Some(Arc::new(GuestProfiler::new(module_name, interval, modules)));
 
@@ -478,13 +549,23 @@ impl RunCommand {
                     .expect("profiling doesn't support threads yet");
                 if let Err(e) = std::fs::File::create(&path)
                     .map_err(anyhow::Error::new)
-                    .and_then(|output| profiler.finish(std::io::BufWriter::new(output)))
+                    .and_then(|output| {
+                        profiler.finish_with_format(format, std::io::BufWriter::new(output))
+                    })
                 {
                     eprintln!("failed writing profile at {path}: {e:#}");
                 } else {
                     eprintln!();
                     eprintln!("Profile written to: {path}");
-                    eprintln!("View this profile at https://profiler.firefox.com/.");
+                    match format {
+                        GuestProfileFormat::Pprof => {
+                            eprintln!("View this profile with `go tool pprof {path}`.")
+                        }
+                        GuestProfileFormat::Speedscope => {
+                            eprintln!("View this profile at https://www.speedscope.app/.")
+                        }
+                        _ => eprintln!("View this profile at https://profiler.firefox.com/."),
+                    }
                 }
             });
         }
@@ -611,7 +692,8 @@ impl RunCommand {
                         .to_str()
                         .unwrap_or_else(|| "unknown");
 
-                    if let Err(coredump_err) = generate_coredump(&err, &source_name, coredump_path)
+                    if let Err(coredump_err) =
+                        generate_coredump(&err, store, &source_name, coredump_path)
                     {
                         eprintln!("warning: coredump failed to generate: {}", coredump_err);
                         err
@@ -689,6 +771,8 @@ fn populate_with_wasi(
     wasi_modules: &WasiModules,
     listenfd: bool,
     mut tcplisten: Vec<TcpListener>,
+    replay: Option<Replay>,
+    wasi_nn_graphs: &[(String, String)],
 ) -> Result<()> {
     if wasi_modules.wasi_common {
         wasmtime_wasi::add_to_linker(linker, |host| host.wasi.as_mut().unwrap())?;
@@ -720,6 +804,10 @@ fn populate_with_wasi(
             builder.preopened_dir(dir, name)?;
         }
 
+        if let Some(replay) = replay {
+            builder.replay(replay)?;
+        }
+
         store.data_mut().wasi = Some(builder.build());
     }
 
@@ -741,7 +829,11 @@ fn populate_with_wasi(
                 Arc::get_mut(host.wasi_nn.as_mut().unwrap())
                     .expect("wasi-nn is not implemented with multi-threading support")
             })?;
-            store.data_mut().wasi_nn = Some(Arc::new(WasiNnCtx::default()));
+            let mut registry = GraphRegistry::new();
+            for (name, path) in wasi_nn_graphs {
+                registry.load(name, path)?;
+            }
+            store.data_mut().wasi_nn = Some(Arc::new(WasiNnCtx::default().with_registry(registry)));
         }
     }
 
@@ -809,35 +901,20 @@ fn ctx_set_listenfd(mut num_fd: usize, builder: &mut WasiCtxBuilder) -> Result<u
     Ok(num_fd)
 }
 
-fn generate_coredump(err: &anyhow::Error, source_name: &str, coredump_path: &str) -> Result<()> {
-    let bt = err
-        .downcast_ref::<wasmtime::WasmBacktrace>()
-        .ok_or_else(|| anyhow!("no wasm backtrace found to generate coredump with"))?;
-
-    let coredump = wasm_encoder::CoreDumpSection::new(source_name);
-    let mut stacksection = wasm_encoder::CoreDumpStackSection::new("main");
-    for f in bt.frames() {
-        // We don't have the information at this point to map frames to
-        // individual instances of a module, so we won't be able to create the
-        // "frame ∈ instance ∈ module" hierarchy described in the core dump spec
-        // until we move core dump generation into the runtime. So for now
-        // instanceidx will be 0 for all frames
-        let instanceidx = 0;
-        stacksection.frame(
-            instanceidx,
-            f.func_index(),
-            u32::try_from(f.func_offset().unwrap_or(0)).unwrap(),
-            [],
-            [],
-        );
-    }
-    let mut module = wasm_encoder::Module::new();
-    module.section(&coredump);
-    module.section(&stacksection);
+fn generate_coredump(
+    err: &anyhow::Error,
+    store: &mut Store<Host>,
+    source_name: &str,
+    coredump_path: &str,
+) -> Result<()> {
+    let coredump = err
+        .downcast_ref::<wasmtime::WasmCoreDump>()
+        .ok_or_else(|| anyhow!("no wasm coredump found in the error"))?;
+    let bytes = coredump.serialize(store, source_name);
 
     let mut f = File::create(coredump_path)
         .context(format!("failed to create file at `{}`", coredump_path))?;
-    f.write_all(module.as_slice())
+    f.write_all(&bytes)
         .with_context(|| format!("failed to write coredump file at `{}`", coredump_path))?;
     Ok(())
 }
@@ -122,6 +122,36 @@ fn run_coredump_smoketest() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn coredump_inspect() -> Result<()> {
+    let wasm = build_wasm("tests/all/cli_tests/coredump_inspect.wat")?;
+    let coredump_file = NamedTempFile::new()?;
+    let coredump_arg = format!("--coredump-on-trap={}", coredump_file.path().display());
+    run_wasmtime(&[
+        "run",
+        "--invoke",
+        "a",
+        "--disable-cache",
+        &coredump_arg,
+        wasm.path().to_str().unwrap(),
+    ])
+    .unwrap_err();
+
+    let stdout = run_wasmtime(&[
+        "coredump",
+        "--disable-cache",
+        "--memory=0:0:16",
+        coredump_file.path().to_str().unwrap(),
+        wasm.path().to_str().unwrap(),
+    ])?;
+    let c = stdout.find("!c\n").unwrap();
+    let b = stdout.find("!b\n").unwrap();
+    assert!(c < b, "{stdout}");
+    assert!(stdout.contains("memory 0 at 0x0:"), "{stdout}");
+    assert!(stdout.contains("core"), "{stdout}");
+    Ok(())
+}
+
 // Running simple wat
 #[test]
 fn run_wasmtime_simple_wat() -> Result<()> {
@@ -731,3 +761,173 @@ fn wasi_misaligned_pointer() -> Result<()> {
     );
     Ok(())
 }
+
+#[test]
+fn record_and_replay() -> Result<()> {
+    let td = TempDir::new()?;
+    let log = td.path().join("replay.log");
+    let recorded_stdin = td.path().join("recorded-stdin");
+    std::fs::write(&recorded_stdin, "recorded stdin")?;
+    let other_stdin = td.path().join("other-stdin");
+    std::fs::write(&other_stdin, "something else")?;
+    let wasm = "tests/all/cli_tests/print_nondeterminism.wat";
+    let log_path = log.to_str().unwrap();
+
+    let recorded = run_wasmtime_for_output(
+        &["run", "--disable-cache", "--record", log_path, wasm],
+        Some(&recorded_stdin),
+    )?;
+    assert!(recorded.status.success());
+    assert_eq!(&recorded.stdout[24..38], b"recorded stdin");
+
+    // Replaying reproduces the random bytes, the time and stdin, even though
+    // the host now provides different ones.
+    let replayed = run_wasmtime_for_output(
+        &["run", "--disable-cache", "--replay", log_path, wasm],
+        Some(&other_stdin),
+    )?;
+    assert!(replayed.status.success());
+    assert_eq!(replayed.stdout, recorded.stdout);
+
+    let unrecorded =
+        run_wasmtime_for_output(&["run", "--disable-cache", wasm], Some(&other_stdin))?;
+    assert_ne!(unrecorded.stdout[..16], recorded.stdout[..16]);
+
+    // A truncated log traps rather than panicking.
+    let truncated = td.path().join("truncated.log");
+    let contents = std::fs::read_to_string(&log)?;
+    let first_lines = contents.lines().take(2).collect::<Vec<_>>().join("\n");
+    std::fs::write(&truncated, first_lines + "\n")?;
+    let diverged = run_wasmtime_for_output(
+        &[
+            "run",
+            "--disable-cache",
+            "--replay",
+            truncated.to_str().unwrap(),
+            wasm,
+        ],
+        Some(&other_stdin),
+    )?;
+    assert!(!diverged.status.success());
+    let stderr = String::from_utf8_lossy(&diverged.stderr);
+    assert!(stderr.contains("replay diverged"), "{stderr}");
+    assert!(!stderr.contains("panicked"), "{stderr}");
+    Ok(())
+}
+
+#[cfg(feature = "wasi-nn")]
+#[test]
+fn wasi_nn_named_graph() -> Result<()> {
+    let output = run_wasmtime_for_output(
+        &[
+            "run",
+            "--disable-cache",
+            "--wasi-modules",
+            "experimental-wasi-nn",
+            "--wasi-nn-graph",
+            "relu::crates/wasi-nn/tests/fixtures/relu.onnx",
+            "tests/all/cli_tests/wasi_nn_named_graph.wat",
+        ],
+        None,
+    )?;
+    assert!(output.status.success(), "{output:?}");
+    let expected = [0.0f32, 1.0, 0.0, 2.0]
+        .iter()
+        .flat_map(|v| v.to_le_bytes())
+        .collect::<Vec<_>>();
+    assert_eq!(output.stdout, expected);
+
+    // Guests can't load graphs the host didn't register.
+    let output = run_wasmtime_for_output(
+        &[
+            "run",
+            "--disable-cache",
+            "--wasi-modules",
+            "experimental-wasi-nn",
+            "tests/all/cli_tests/wasi_nn_named_graph.wat",
+        ],
+        None,
+    )?;
+    assert!(!output.status.success());
+    Ok(())
+}
+
+#[test]
+fn compile_with_incremental_cache() -> Result<()> {
+    let td = TempDir::new()?;
+    let cache_dir = td.path().join("cache");
+    let config = td.path().join("config.toml");
+    std::fs::write(
+        &config,
+        format!(
+            "
+                [cache]
+                enabled = true
+                directory = '{}'
+                incremental-compilation = true
+            ",
+            cache_dir.display()
+        ),
+    )?;
+
+    // Counts the functions stored in the incremental compilation cache,
+    // ignoring the statistics files of the cache worker.
+    let cached_functions = || -> Result<usize> {
+        let mut count = 0;
+        for dir in std::fs::read_dir(cache_dir.join("incremental"))? {
+            for file in std::fs::read_dir(dir?.path())? {
+                if file?.path().extension().is_none() {
+                    count += 1;
+                }
+            }
+        }
+        Ok(count)
+    };
+
+    let module = |multiplier: i32| -> Result<std::path::PathBuf> {
+        let path = td.path().join(format!("module-{multiplier}.wat"));
+        std::fs::write(
+            &path,
+            format!(
+                r#"
+                    (module
+                        (func (export "add") (param i32) (result i32)
+                            local.get 0
+                            i32.const 1
+                            i32.add)
+                        (func (export "mul") (param i32) (result i32)
+                            local.get 0
+                            i32.const {multiplier}
+                            i32.mul))
+                "#
+            ),
+        )?;
+        Ok(path)
+    };
+    let compile = |wat: &Path| -> Result<()> {
+        let cwasm = wat.with_extension("cwasm");
+        run_wasmtime(&[
+            "compile",
+            "--config",
+            config.to_str().unwrap(),
+            wat.to_str().unwrap(),
+            "-o",
+            cwasm.to_str().unwrap(),
+        ])?;
+        Ok(())
+    };
+
+    compile(&module(2)?)?;
+    let functions = cached_functions()?;
+    assert!(functions >= 2);
+
+    // Only the edited function is compiled and cached again.
+    compile(&module(3)?)?;
+    assert_eq!(cached_functions()?, functions + 1);
+
+    // Everything is already cached for the original module.
+    compile(&module(2)?)?;
+    assert_eq!(cached_functions()?, functions + 1);
+
+    Ok(())
+}
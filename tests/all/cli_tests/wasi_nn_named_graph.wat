@@ -0,0 +1,35 @@
+(module
+  (import "wasi_ephemeral_nn" "load_by_name"
+    (func $load_by_name (param i32 i32 i32) (result i32)))
+  (import "wasi_ephemeral_nn" "init_execution_context"
+    (func $init_execution_context (param i32 i32) (result i32)))
+  (import "wasi_ephemeral_nn" "set_input"
+    (func $set_input (param i32 i32 i32) (result i32)))
+  (import "wasi_ephemeral_nn" "compute"
+    (func $compute (param i32) (result i32)))
+  (import "wasi_ephemeral_nn" "get_output"
+    (func $get_output (param i32 i32 i32 i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "fd_write"
+    (func $fd_write (param i32 i32 i32 i32) (result i32)))
+  (func $check (param i32)
+    (if (local.get 0) (then unreachable)))
+  (func (export "_start")
+    ;; The graph preloaded as "relu" into 96, and a context for it into 100.
+    (call $check (call $load_by_name (i32.const 0) (i32.const 4) (i32.const 96)))
+    (call $check (call $init_execution_context (i32.load (i32.const 96)) (i32.const 100)))
+    (call $check (call $set_input (i32.load (i32.const 100)) (i32.const 0) (i32.const 64)))
+    (call $check (call $compute (i32.load (i32.const 100))))
+    ;; The 16 bytes of output at 128, written to stdout.
+    (call $check (call $get_output
+      (i32.load (i32.const 100)) (i32.const 0) (i32.const 128) (i32.const 16) (i32.const 104)))
+    (i32.store (i32.const 160) (i32.const 128))
+    (i32.store (i32.const 164) (i32.load (i32.const 104)))
+    (call $check (call $fd_write (i32.const 1) (i32.const 160) (i32.const 1) (i32.const 168))))
+  (memory (export "memory") 1)
+  (data (i32.const 0) "relu")
+  ;; The dimensions, [1, 4].
+  (data (i32.const 16) "\01\00\00\00\04\00\00\00")
+  ;; The f32 input, [-1, 1, -2, 2].
+  (data (i32.const 32) "\00\00\80\bf\00\00\80\3f\00\00\00\c0\00\00\00\40")
+  ;; The input tensor: its dimensions, its type (f32) and its data.
+  (data (i32.const 64) "\10\00\00\00\02\00\00\00\01\00\00\00\20\00\00\00\10\00\00\00"))
@@ -145,3 +145,34 @@ fn test_coredump_has_import_globals_and_memory() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_coredump_serialize() -> Result<()> {
+    let mut config = Config::default();
+    config.coredump_on_trap(true);
+    let engine = Engine::new(&config).unwrap();
+    let mut store = Store::<()>::new(&engine, ());
+
+    let wat = r#"
+      (module
+        (memory 1)
+        (global (mut i64) (i64.const 1))
+        (data (i32.const 0) "core")
+        (func (export "a")
+          unreachable
+        )
+      )
+    "#;
+
+    let module = Module::new(store.engine(), wat)?;
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let a_func = instance.get_typed_func::<(), ()>(&mut store, "a")?;
+    let e = a_func.call(&mut store, ()).unwrap_err();
+    let cd = e.downcast_ref::<WasmCoreDump>().unwrap();
+    let bytes = cd.serialize(&mut store, "test");
+    assert!(bytes.starts_with(b"\0asm"));
+    assert!(bytes.windows(9).any(|w| w == b"corestack"));
+
+    Ok(())
+}
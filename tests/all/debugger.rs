@@ -0,0 +1,165 @@
+use anyhow::Result;
+use wasmtime::debug::{self, DebugAction, DebugEvent, DebugValue};
+use wasmtime::*;
+
+const WAT: &str = r#"
+    (module
+      (import "" "inspect" (func $inspect))
+      (global $g (mut i32) (i32.const 7))
+
+      (func $inner (param i32) (result i32)
+        (local i64)
+        local.get 0
+        i32.const 5
+        i32.add
+        call $inspect)
+
+      (func (export "outer") (param i32) (result i32)
+        i32.const 1
+        local.get 0
+        call $inner
+        i32.add)
+    )
+"#;
+
+fn engine() -> Result<Engine> {
+    let mut config = Config::new();
+    config.debug_instrumentation(true);
+    Engine::new(&config)
+}
+
+fn instantiate<T>(store: &mut Store<T>, module: &Module) -> Result<Instance> {
+    let inspect = Func::wrap(&mut *store, || {});
+    Instance::new(store, module, &[inspect.into()])
+}
+
+/// Returns the function index and offset of every instruction executed by
+/// `outer(arg)`, in order.
+fn trace(engine: &Engine, module: &Module, arg: i32) -> Result<Vec<(u32, u32)>> {
+    let mut store = Store::new(engine, Vec::new());
+    store.debug_handler(|mut caller, event| {
+        assert_eq!(event, DebugEvent::Step);
+        let frames = debug::frames(&caller);
+        let location = (frames[0].func_index(), frames[0].offset());
+        caller.data_mut().push(location);
+        Ok(DebugAction::Step)
+    });
+    store.single_step(true);
+    let instance = instantiate(&mut store, module)?;
+    let outer = instance.get_typed_func::<i32, i32>(&mut store, "outer")?;
+    assert_eq!(outer.call(&mut store, arg)?, arg + 6);
+    Ok(store.into_data())
+}
+
+#[test]
+fn single_step_visits_every_instruction() -> Result<()> {
+    let engine = engine()?;
+    let module = Module::new(&engine, WAT)?;
+    let trace = trace(&engine, &module, 10)?;
+
+    // `outer` and `inner` are functions 2 and 1, with their `end`s.
+    let funcs = trace.iter().map(|(func, _)| *func).collect::<Vec<_>>();
+    assert_eq!(funcs, [2, 2, 2, 1, 1, 1, 1, 1, 2, 2]);
+    // Offsets increase within a function.
+    assert!(trace[..3].windows(2).all(|w| w[0].1 < w[1].1));
+    assert!(trace[3..8].windows(2).all(|w| w[0].1 < w[1].1));
+    assert!(trace[8].1 > trace[2].1);
+    Ok(())
+}
+
+#[test]
+fn breakpoint_exposes_locals_and_stack() -> Result<()> {
+    let engine = engine()?;
+    let module = Module::new(&engine, WAT)?;
+    // The `i32.add` of `inner`.
+    let (func, offset) = trace(&engine, &module, 10)?[5];
+
+    let mut store = Store::new(&engine, Vec::new());
+    store.debug_handler(|mut caller, event| {
+        assert_eq!(event, DebugEvent::Breakpoint);
+        let frames = debug::frames(&caller);
+        assert_eq!(frames.len(), 2);
+        assert_eq!(
+            frames[0].locals(),
+            [DebugValue::I32(10), DebugValue::I64(0)]
+        );
+        assert_eq!(frames[0].stack(), [DebugValue::I32(10), DebugValue::I32(5)]);
+        // The caller's operand stack includes the arguments of the call.
+        assert_eq!(frames[1].func_index(), 2);
+        assert_eq!(frames[1].locals(), [DebugValue::I32(10)]);
+        assert_eq!(frames[1].stack(), [DebugValue::I32(1), DebugValue::I32(10)]);
+        let g = frames[0].global(&mut caller, 0).unwrap();
+        assert_eq!(g.get(&mut caller).unwrap_i32(), 7);
+        assert!(frames[0].global(&mut caller, 1).is_none());
+        caller.data_mut().push(frames[0].offset());
+        Ok(DebugAction::Continue)
+    });
+    store.add_breakpoint(&module, func, offset)?;
+    let instance = instantiate(&mut store, &module)?;
+    let outer = instance.get_typed_func::<i32, i32>(&mut store, "outer")?;
+    assert_eq!(outer.call(&mut store, 10)?, 16);
+    assert_eq!(outer.call(&mut store, 10)?, 16);
+    assert_eq!(store.data().as_slice(), [offset, offset]);
+
+    assert!(store.remove_breakpoint(&module, func, offset));
+    assert!(!store.remove_breakpoint(&module, func, offset));
+    assert_eq!(outer.call(&mut store, 10)?, 16);
+    assert_eq!(store.data().len(), 2);
+    Ok(())
+}
+
+#[test]
+fn frames_from_host_function() -> Result<()> {
+    let engine = engine()?;
+    let module = Module::new(&engine, WAT)?;
+    let mut store = Store::new(&engine, Vec::new());
+    let inspect = Func::wrap(&mut store, |mut caller: Caller<'_, Vec<Vec<u32>>>| {
+        let frames = debug::frames(&caller)
+            .iter()
+            .map(|f| f.func_index())
+            .collect();
+        caller.data_mut().push(frames);
+    });
+    let instance = Instance::new(&mut store, &module, &[inspect.into()])?;
+    let outer = instance.get_typed_func::<i32, i32>(&mut store, "outer")?;
+    assert!(debug::frames(&store).is_empty());
+    outer.call(&mut store, 1)?;
+    assert!(debug::frames(&store).is_empty());
+    assert_eq!(store.data().as_slice(), [vec![1, 2]]);
+    Ok(())
+}
+
+#[test]
+fn handler_error_traps() -> Result<()> {
+    let engine = engine()?;
+    let module = Module::new(&engine, WAT)?;
+    let mut store = Store::new(&engine, ());
+    store.debug_handler(|_, _| anyhow::bail!("stop"));
+    store.single_step(true);
+    let instance = instantiate(&mut store, &module)?;
+    let outer = instance.get_typed_func::<i32, i32>(&mut store, "outer")?;
+    let err = outer.call(&mut store, 1).unwrap_err();
+    assert!(format!("{err:?}").contains("stop"));
+
+    // Execution continues normally once single-stepping is turned off.
+    store.single_step(false);
+    assert_eq!(outer.call(&mut store, 1)?, 7);
+    assert!(debug::frames(&store).is_empty());
+    Ok(())
+}
+
+#[test]
+fn add_breakpoint_errors() -> Result<()> {
+    let engine = engine()?;
+    let module = Module::new(&engine, WAT)?;
+    let mut store = Store::new(&engine, ());
+    // Function 0 is imported.
+    assert!(store.add_breakpoint(&module, 0, 0).is_err());
+    assert!(store.add_breakpoint(&module, 3, 0).is_err());
+
+    let engine = Engine::default();
+    let module = Module::new(&engine, WAT)?;
+    let mut store = Store::new(&engine, ());
+    assert!(store.add_breakpoint(&module, 1, 0).is_err());
+    Ok(())
+}
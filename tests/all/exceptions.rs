@@ -0,0 +1,204 @@
+use anyhow::{bail, Result};
+use wasmtime::*;
+
+const WAT: &str = r#"
+    (module
+      (import "" "host" (func $host (param i32)))
+      (tag $e0)
+      (tag $e1 (param i32))
+
+      (func (export "throw") (param i32) (throw $e1 (local.get 0)))
+
+      (func (export "call-host") (param i32) (result i32)
+        (try (result i32)
+          (do (call $host (local.get 0)) (i32.const 0))
+          (catch $e0 (i32.const -1))
+          (catch $e1)))
+
+      (func (export "call-host-catch-all") (param i32) (result i32)
+        (try (result i32)
+          (do (call $host (local.get 0)) (i32.const 0))
+          (catch_all (i32.const -1))))
+    )
+"#;
+
+fn engine() -> Result<Engine> {
+    Engine::new(Config::new().wasm_exceptions(true))
+}
+
+fn instantiate(store: &mut Store<Option<Instance>>, host: Func) -> Result<Instance> {
+    let module = Module::new(store.engine(), WAT)?;
+    let instance = Instance::new(&mut *store, &module, &[host.into()])?;
+    *store.data_mut() = Some(instance);
+    Ok(instance)
+}
+
+#[test]
+fn uncaught_exception_reaches_host() -> Result<()> {
+    let mut store = Store::new(&engine()?, None);
+    let host = Func::wrap(&mut store, |_: i32| {});
+    let instance = instantiate(&mut store, host)?;
+    let throw = instance.get_typed_func::<i32, ()>(&mut store, "throw")?;
+
+    let err = throw.call(&mut store, 42).unwrap_err();
+    let exception = err.downcast_ref::<Exception>().unwrap();
+    assert_eq!(exception.tag(), 1);
+    assert!(exception.has_tag(&mut store, &instance, 1));
+    assert!(!exception.has_tag(&mut store, &instance, 0));
+    assert_eq!(exception.payload(&mut store)[0].unwrap_i32(), 42);
+    Ok(())
+}
+
+#[test]
+fn host_throws_to_wasm() -> Result<()> {
+    let mut store = Store::new(&engine()?, None);
+    let host = Func::wrap(
+        &mut store,
+        |mut caller: Caller<'_, Option<Instance>>, x: i32| -> Result<()> {
+            let instance = caller.data().unwrap();
+            if x == 0 {
+                return Ok(());
+            }
+            Err(Exception::new(&mut caller, &instance, 1, &[Val::I32(x)])?.into())
+        },
+    );
+    let instance = instantiate(&mut store, host)?;
+    let call_host = instance.get_typed_func::<i32, i32>(&mut store, "call-host")?;
+
+    assert_eq!(call_host.call(&mut store, 0)?, 0);
+    assert_eq!(call_host.call(&mut store, 5)?, 5);
+    Ok(())
+}
+
+#[test]
+fn untyped_host_throws_to_wasm() -> Result<()> {
+    let mut store = Store::new(&engine()?, None);
+    let ty = FuncType::new([ValType::I32], []);
+    let host = Func::new(&mut store, ty, |mut caller, _, _| {
+        let instance = caller.data().unwrap();
+        Err(Exception::new(&mut caller, &instance, 0, &[])?.into())
+    });
+    let instance = instantiate(&mut store, host)?;
+    let call_host = instance.get_typed_func::<i32, i32>(&mut store, "call-host")?;
+
+    assert_eq!(call_host.call(&mut store, 1)?, -1);
+    Ok(())
+}
+
+#[test]
+fn host_forwards_wasm_exception() -> Result<()> {
+    let mut store = Store::new(&engine()?, None);
+    let host = Func::wrap(
+        &mut store,
+        |mut caller: Caller<'_, Option<Instance>>, x: i32| -> Result<()> {
+            let instance = caller.data().unwrap();
+            let throw = instance.get_typed_func::<i32, ()>(&mut caller, "throw")?;
+            throw.call(&mut caller, x + 1)
+        },
+    );
+    let instance = instantiate(&mut store, host)?;
+    let call_host = instance.get_typed_func::<i32, i32>(&mut store, "call-host")?;
+
+    assert_eq!(call_host.call(&mut store, 9)?, 10);
+    Ok(())
+}
+
+#[test]
+fn exceptions_unwind_frames_outside_of_try() -> Result<()> {
+    let mut store = Store::<()>::new(&engine()?, ());
+    let module = Module::new(
+        store.engine(),
+        r#"
+            (module
+              (tag $e (param i32))
+              (func $recurse (param i32) (result i32)
+                (if (i32.eqz (local.get 0)) (then (throw $e (i32.const 7))))
+                (i32.add (call $recurse (i32.sub (local.get 0) (i32.const 1))) (i32.const 1)))
+              (func (export "run") (param i32) (result i32)
+                (local i32)
+                (local.set 1 (i32.const 100))
+                (try (result i32)
+                  (do (call $recurse (local.get 0)))
+                  (catch $e (i32.add (local.get 1)))))
+            )
+        "#,
+    )?;
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let run = instance.get_typed_func::<i32, i32>(&mut store, "run")?;
+
+    assert_eq!(run.call(&mut store, 10)?, 107);
+    assert_eq!(run.call(&mut store, 0)?, 107);
+    Ok(())
+}
+
+#[test]
+fn host_errors_are_not_caught() -> Result<()> {
+    let mut store = Store::new(&engine()?, None);
+    let host = Func::wrap(&mut store, |_: i32| -> Result<()> {
+        bail!("not an exception")
+    });
+    let instance = instantiate(&mut store, host)?;
+    let call_host = instance.get_typed_func::<i32, i32>(&mut store, "call-host-catch-all")?;
+
+    let err = call_host.call(&mut store, 1).unwrap_err();
+    assert!(err.downcast_ref::<Exception>().is_none());
+    assert!(format!("{err:?}").contains("not an exception"));
+    Ok(())
+}
+
+#[test]
+fn exception_payload_type_is_checked() -> Result<()> {
+    let mut store = Store::new(&engine()?, None);
+    let host = Func::wrap(&mut store, |_: i32| {});
+    let instance = instantiate(&mut store, host)?;
+
+    assert!(Exception::new(&mut store, &instance, 1, &[Val::I64(1)]).is_err());
+    assert!(Exception::new(&mut store, &instance, 1, &[]).is_err());
+    assert!(Exception::new(&mut store, &instance, 2, &[]).is_err());
+    assert!(Exception::new(&mut store, &instance, 0, &[]).is_ok());
+    Ok(())
+}
+
+#[test]
+fn tags_of_other_instances_do_not_match() -> Result<()> {
+    let mut store = Store::new(&engine()?, None);
+    let host = Func::wrap(&mut store, |_: i32| {});
+    let thrower = instantiate(&mut store, host)?;
+
+    // The second instance's host import throws an exception of the first
+    // instance's tag, which its `catch` clauses for its own tags don't catch.
+    let forward = Func::wrap(
+        &mut store,
+        |mut caller: Caller<'_, Option<Instance>>, x: i32| -> Result<()> {
+            let thrower = caller.data().unwrap();
+            let throw = thrower.get_typed_func::<i32, ()>(&mut caller, "throw")?;
+            throw.call(&mut caller, x)
+        },
+    );
+    let module = Module::new(store.engine(), WAT)?;
+    let catcher = Instance::new(&mut store, &module, &[forward.into()])?;
+    let call_host = catcher.get_typed_func::<i32, i32>(&mut store, "call-host")?;
+
+    let err = call_host.call(&mut store, 3).unwrap_err();
+    let exception = err.downcast_ref::<Exception>().unwrap();
+    assert!(exception.has_tag(&mut store, &thrower, 1));
+    assert!(!exception.has_tag(&mut store, &catcher, 1));
+    assert!(!exception.has_tag(&mut store, &thrower, 2));
+    Ok(())
+}
+
+#[test]
+fn tag_imports_and_exports_are_rejected() -> Result<()> {
+    let engine = engine()?;
+    let err = Module::new(&engine, r#"(module (import "" "tag" (tag (param i32))))"#).unwrap_err();
+    assert!(
+        format!("{err:?}").contains("tag imports are not supported"),
+        "{err:?}"
+    );
+    let err = Module::new(&engine, r#"(module (tag (export "tag") (param i32)))"#).unwrap_err();
+    assert!(
+        format!("{err:?}").contains("tag exports are not supported"),
+        "{err:?}"
+    );
+    Ok(())
+}
@@ -234,3 +234,168 @@ fn unconditionally_trapping_memory_accesses_save_fuel_before_trapping() {
     assert!(consumed_fuel > 0);
     assert_eq!(init_fuel, consumed_fuel + store.fuel_remaining().unwrap());
 }
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn custom_fuel_costs() -> Result<()> {
+    const WAT: &str = r#"
+        (module
+          (memory 1)
+          (func $f)
+          (func (export "run")
+            nop
+            i32.const 0
+            i32.const 1
+            i32.store
+            i32.const 0
+            i32.load
+            drop
+            call $f
+            i32.const 1
+            memory.grow
+            drop))
+    "#;
+
+    fn run(costs: Option<FuelCosts>) -> Result<u64> {
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        if let Some(costs) = costs {
+            config.fuel_costs(costs);
+        }
+        let engine = Engine::new(&config)?;
+        let module = Module::new(&engine, WAT)?;
+        let mut store = Store::new(&engine, ());
+        store.add_fuel(u64::MAX)?;
+        let instance = Instance::new(&mut store, &module, &[])?;
+        let run = instance.get_typed_func::<(), ()>(&mut store, "run")?;
+        let before = store.fuel_consumed().unwrap();
+        run.call(&mut store, ())?;
+        Ok(store.fuel_consumed().unwrap() - before)
+    }
+
+    // Four constants, a load, a store, a call, and `memory.grow`, plus one
+    // for each function entry.
+    assert_eq!(run(None)?, 10);
+
+    let mut costs = FuelCosts::new();
+    costs
+        .nop(1)
+        .memory_load(10)
+        .memory_store(100)
+        .call(1_000)
+        .memory_grow(10_000);
+    assert_eq!(run(Some(costs))?, 11_117);
+
+    let mut costs = FuelCosts::new();
+    costs.default_cost(0).memory_grow(0);
+    assert_eq!(run(Some(costs))?, 5);
+    Ok(())
+}
+
+/// Returns how much more fuel the export `name` consumes when called with 200
+/// than with 100, which is proportional to the number of iterations of the
+/// loops run by the exports below.
+fn fuel_per_100_iterations(config: &mut Config, wat: &str, name: &str) -> Result<u64> {
+    config.consume_fuel(true);
+    let engine = Engine::new(config)?;
+    let module = Module::new(&engine, wat)?;
+    let mut store = Store::new(&engine, ());
+    store.add_fuel(u64::MAX)?;
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let func = instance.get_typed_func::<i32, ()>(&mut store, name)?;
+    let mut consumed = |n| -> Result<u64> {
+        let before = store.fuel_consumed().unwrap();
+        func.call(&mut store, n)?;
+        Ok(store.fuel_consumed().unwrap() - before)
+    };
+    Ok(consumed(200)? - consumed(100)?)
+}
+
+// Each export below runs a loop counting `n` down to zero, either in a callee
+// reached through the instruction under test or around that instruction, so
+// that fuel which isn't saved or reloaded around it is lost in proportion to
+// `n`.
+const COUNT: &str = r#"
+    (func $count (param $n i32)
+      (loop $l
+        (br_if $l (local.tee $n (i32.sub (local.get $n) (i32.const 1))))))
+    (func (export "call") (param $n i32)
+      (call $count (local.get $n)))
+"#;
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn fuel_is_saved_around_call_ref() -> Result<()> {
+    let wat = format!(
+        r#"
+            (module
+              (type $ft (func (param i32)))
+              {COUNT}
+              (func $nothing (param i32))
+              (elem declare func $count $nothing)
+
+              (func (export "call_ref") (param $n i32)
+                (call_ref $ft (local.get $n) (ref.func $count)))
+
+              (func (export "return_call_ref") (param $n i32)
+                (loop $l
+                  (br_if $l (local.tee $n (i32.sub (local.get $n) (i32.const 1)))))
+                (return_call_ref $ft (local.get $n) (ref.func $nothing)))
+            )
+        "#
+    );
+    let mut config = Config::new();
+    config.wasm_function_references(true).wasm_tail_call(true);
+
+    let expected = fuel_per_100_iterations(&mut config, &wat, "call")?;
+    assert!(expected >= 100);
+    for name in ["call_ref", "return_call_ref"] {
+        let consumed = fuel_per_100_iterations(&mut config, &wat, name)?;
+        assert_eq!(consumed, expected, "{name}");
+    }
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn fuel_is_saved_around_continuation_switches() -> Result<()> {
+    let wat = format!(
+        r#"
+            (module
+              (type $ft (func (param i32)))
+              (type $ct (cont $ft))
+              (type $unit (func))
+              (type $kt (cont $unit))
+              (tag $yield)
+              {COUNT}
+              (func $yield (param i32) (suspend $yield))
+              (elem declare func $count $yield)
+
+              (func (export "resume") (param $n i32)
+                (resume $ct (local.get $n) (cont.new $ct (ref.func $count))))
+
+              (func (export "suspend") (param $n i32)
+                (block $on_yield (result (ref $kt))
+                  (resume $ct (tag $yield $on_yield)
+                    (local.get $n) (cont.new $ct (ref.func $yield)))
+                  (return))
+                (loop $l
+                  (br_if $l (local.tee $n (i32.sub (local.get $n) (i32.const 1)))))
+                (resume $kt))
+            )
+        "#
+    );
+    let mut config = Config::new();
+    config
+        .wasm_function_references(true)
+        .wasm_exceptions(true)
+        .wasm_typed_continuations(true);
+
+    let expected = fuel_per_100_iterations(&mut config, &wat, "call")?;
+    assert!(expected >= 100);
+    for name in ["resume", "suspend"] {
+        let consumed = fuel_per_100_iterations(&mut config, &wat, name)?;
+        assert_eq!(consumed, expected, "{name}");
+    }
+    Ok(())
+}
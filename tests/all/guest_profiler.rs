@@ -0,0 +1,74 @@
+use anyhow::Result;
+use std::time::Duration;
+use wasmtime::*;
+
+fn profile(format: GuestProfileFormat) -> Result<Vec<u8>> {
+    let engine = Engine::default();
+    let module = Module::new(
+        &engine,
+        r#"
+            (module
+              (import "" "sample" (func $sample))
+              (func $inner (call $sample))
+              (func (export "run") (call $inner) (call $inner)))
+        "#,
+    )?;
+    let profiler = GuestProfiler::new(
+        "test",
+        Duration::from_millis(10),
+        vec![("test.wasm".to_string(), module.clone())],
+    );
+    let mut store = Store::new(&engine, Some(profiler));
+    let sample = Func::wrap(
+        &mut store,
+        |mut caller: Caller<'_, Option<GuestProfiler>>| {
+            let mut profiler = caller.data_mut().take().unwrap();
+            profiler.sample(&caller);
+            *caller.data_mut() = Some(profiler);
+        },
+    );
+    let instance = Instance::new(&mut store, &module, &[sample.into()])?;
+    let run = instance.get_typed_func::<(), ()>(&mut store, "run")?;
+    run.call(&mut store, ())?;
+
+    let mut output = Vec::new();
+    store
+        .into_data()
+        .unwrap()
+        .finish_with_format(format, &mut output)?;
+    Ok(output)
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn guest_profiler_formats() -> Result<()> {
+    let firefox: serde_json::Value =
+        serde_json::from_slice(&profile(GuestProfileFormat::Firefox)?)?;
+    assert!(firefox["threads"].is_array());
+
+    let speedscope: serde_json::Value =
+        serde_json::from_slice(&profile(GuestProfileFormat::Speedscope)?)?;
+    let frames = speedscope["shared"]["frames"].as_array().unwrap();
+    let names: Vec<_> = frames.iter().map(|f| f["name"].as_str().unwrap()).collect();
+    assert!(names.contains(&"inner"));
+    let samples = speedscope["profiles"][0]["samples"].as_array().unwrap();
+    assert_eq!(samples.len(), 2);
+    // The oldest frame comes first.
+    let stack = samples[0].as_array().unwrap();
+    assert_eq!(stack.len(), 2);
+    assert_eq!(frames[stack[1].as_u64().unwrap() as usize]["name"], "inner");
+    assert_eq!(
+        speedscope["profiles"][0]["weights"]
+            .as_array()
+            .unwrap()
+            .len(),
+        2
+    );
+
+    let pprof = profile(GuestProfileFormat::Pprof)?;
+    // The first field is the `sample_type` message.
+    assert_eq!(pprof[0], 1 << 3 | 2);
+    assert!(pprof.windows(5).any(|w| w == b"inner"));
+    assert!(pprof.windows(9).any(|w| w == b"test.wasm"));
+    Ok(())
+}
@@ -0,0 +1,225 @@
+use anyhow::Result;
+use wasmtime::*;
+
+fn record(store: &mut Store<Vec<InstrumentationEvent>>) {
+    store.instrumentation_handler(|mut caller, _instance, event| {
+        caller.data_mut().push(event);
+        Ok(())
+    });
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn instrument_calls() -> Result<()> {
+    let mut config = Config::new();
+    config
+        .wasm_tail_call(true)
+        .instrumentation(Instrumentation::new().calls(true));
+    let engine = Engine::new(&config)?;
+    let module = Module::new(
+        &engine,
+        r#"
+            (module
+              (func $leaf (result i32) (i32.const 1))
+              (func $early (result i32) (return (i32.const 2)))
+              (func $tail (result i32) (return_call $leaf))
+              (func (export "run") (result i32)
+                (i32.add
+                  (i32.add (call $leaf) (call $early))
+                  (call $tail))))
+        "#,
+    )?;
+    let mut store = Store::new(&engine, Vec::new());
+    record(&mut store);
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let run = instance.get_typed_func::<(), i32>(&mut store, "run")?;
+    assert_eq!(run.call(&mut store, ())?, 4);
+
+    use InstrumentationEvent::{Call, Return};
+    assert_eq!(
+        store.data(),
+        &[
+            Call { func_index: 3 },
+            Call { func_index: 0 },
+            Return { func_index: 0 },
+            Call { func_index: 1 },
+            Return { func_index: 1 },
+            Call { func_index: 2 },
+            Return { func_index: 2 },
+            Call { func_index: 0 },
+            Return { func_index: 0 },
+            Return { func_index: 3 },
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn instrument_memory() -> Result<()> {
+    let mut config = Config::new();
+    config.instrumentation(
+        Instrumentation::new()
+            .memory_grow(true)
+            .memory_range(16..20)
+            .memory_range(100..101),
+    );
+    let engine = Engine::new(&config)?;
+    let module = Module::new(
+        &engine,
+        r#"
+            (module
+              (memory 1)
+              (func (export "run")
+                (drop (memory.grow (i32.const 2)))
+                (i32.store (i32.const 0) (i32.const 1))
+                (i32.store offset=14 (i32.const 0) (i32.const 1))
+                (drop (i64.load (i32.const 20)))
+                (drop (i64.load offset=4 (i32.const 8)))
+                (i32.store8 (i32.const 100) (i32.const 1))
+                (drop (i32.load16_u (i32.const 101)))))
+        "#,
+    )?;
+    let mut store = Store::new(&engine, Vec::new());
+    record(&mut store);
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let run = instance.get_typed_func::<(), ()>(&mut store, "run")?;
+    run.call(&mut store, ())?;
+
+    use InstrumentationEvent::{Load, MemoryGrow, Store};
+    assert_eq!(
+        store.data(),
+        &[
+            MemoryGrow {
+                memory: 0,
+                delta: 2
+            },
+            Store {
+                memory: 0,
+                address: 14,
+                size: 4
+            },
+            Load {
+                memory: 0,
+                address: 12,
+                size: 8
+            },
+            Store {
+                memory: 0,
+                address: 100,
+                size: 1
+            },
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn instrument_allocations() -> Result<()> {
+    let mut config = Config::new();
+    config.instrumentation(Instrumentation::new().allocations(true).stack_pointer(true));
+    let engine = Engine::new(&config)?;
+    let module = Module::new(
+        &engine,
+        r#"
+            (module
+              (global $sp (mut i32) (i32.const 1024))
+              (func $malloc (param i32) (result i32) (i32.const 64))
+              (func $free (param i32))
+              (func (export "run")
+                (global.set $sp (i32.const 512))
+                (call $free (call $malloc (i32.const 16)))))
+        "#,
+    )?;
+    let mut store = Store::new(&engine, Vec::new());
+    record(&mut store);
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let run = instance.get_typed_func::<(), ()>(&mut store, "run")?;
+    run.call(&mut store, ())?;
+
+    use InstrumentationEvent::{AllocatorEntry, Free, Malloc, StackPointer};
+    assert_eq!(
+        store.data(),
+        &[
+            StackPointer { value: 512 },
+            AllocatorEntry,
+            Malloc {
+                address: 64,
+                size: 16
+            },
+            AllocatorEntry,
+            Free { address: 64 },
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+#[cfg(feature = "wmemcheck")]
+fn wmemcheck_only_reports_requested_events() -> Result<()> {
+    let mut config = Config::new();
+    config
+        .wmemcheck(true)
+        .instrumentation(Instrumentation::new().calls(true));
+    let engine = Engine::new(&config)?;
+    let module = Module::new(
+        &engine,
+        r#"
+            (module
+              (memory 1)
+              (func $malloc (param i32) (result i32) (i32.const 64))
+              (func (export "init")
+                (i32.store (call $malloc (i32.const 16)) (i32.const 1)))
+              (func (export "load") (param i32) (result i32)
+                (i32.load (local.get 0))))
+        "#,
+    )?;
+    let mut store = Store::new(&engine, Vec::new());
+    record(&mut store);
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let init = instance.get_typed_func::<(), ()>(&mut store, "init")?;
+    let load = instance.get_typed_func::<i32, i32>(&mut store, "load")?;
+    init.call(&mut store, ())?;
+    assert_eq!(load.call(&mut store, 64)?, 1);
+    assert!(store.data().iter().all(|event| matches!(
+        event,
+        InstrumentationEvent::Call { .. } | InstrumentationEvent::Return { .. }
+    )));
+
+    let err = load.call(&mut store, 128).unwrap_err();
+    assert!(format!("{err:?}").contains("Invalid load"), "{err:?}");
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn instrumentation_handler_error_traps() -> Result<()> {
+    let mut config = Config::new();
+    config.instrumentation(Instrumentation::new().calls(true));
+    let engine = Engine::new(&config)?;
+    let module = Module::new(&engine, r#"(module (func (export "run")))"#)?;
+    let mut store = Store::new(&engine, ());
+    store.instrumentation_handler(|_, _, _| anyhow::bail!("stop"));
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let run = instance.get_typed_func::<(), ()>(&mut store, "run")?;
+    let err = run.call(&mut store, ()).unwrap_err();
+    assert!(err.to_string().contains("stop"), "{err:?}");
+    Ok(())
+}
+
+#[test]
+fn instrumentation_must_match_to_deserialize() -> Result<()> {
+    let mut config = Config::new();
+    config.instrumentation(Instrumentation::new().calls(true));
+    let engine = Engine::new(&config)?;
+    let serialized = Module::new(&engine, "(module)")?.serialize()?;
+    let engine = Engine::default();
+    let err = unsafe { Module::deserialize(&engine, &serialized) }.unwrap_err();
+    assert!(
+        err.to_string().contains("different instrumentation"),
+        "{err:?}"
+    );
+    Ok(())
+}
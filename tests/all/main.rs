@@ -7,18 +7,22 @@ mod component_model;
 mod coredump;
 mod custom_signal_handler;
 mod debug;
+mod debugger;
 mod epoch_interruption;
+mod exceptions;
 mod externals;
 mod fuel;
 mod func;
 mod funcref;
 mod gc;
 mod globals;
+mod guest_profiler;
 mod host_funcs;
 mod iloop;
 mod import_calling_export;
 mod import_indexes;
 mod instance;
+mod instrumentation;
 mod invoke_func_via_table;
 mod limits;
 mod linker;
@@ -29,7 +33,9 @@ mod module_serialize;
 mod name;
 mod pooling_allocator;
 mod relocs;
+mod snapshot;
 mod stack_overflow;
+mod stats;
 mod store;
 mod table;
 mod threads;
@@ -53,10 +59,7 @@ pub(crate) fn ref_types_module(
     let mut config = Config::new();
     config.wasm_reference_types(true);
 
-    if !cfg!(target_arch = "s390x") {
-        // TODO(6530): s390x doesn't support tail calls yet.
-        config.wasm_tail_call(true);
-    }
+    config.wasm_tail_call(true);
 
     if use_epochs {
         config.epoch_interruption(true);
@@ -216,3 +216,123 @@ fn missing_sse_and_floats_still_works() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn inlines_calls_within_module() -> Result<()> {
+    let mut config = Config::new();
+    unsafe {
+        config.cranelift_flag_set("enable_inlining", "true");
+    }
+    let engine = Engine::new(&config)?;
+    let module = Module::new(
+        &engine,
+        r#"
+            (module
+                (func $add1 (param i32) (result i32)
+                    local.get 0
+                    i32.const 1
+                    i32.add)
+                (func $swap (param i32 i32) (result i32 i32)
+                    local.get 1
+                    local.get 0)
+                (func $div (param i32 i32) (result i32)
+                    local.get 0
+                    local.get 1
+                    i32.div_u)
+                (func $fib (param i32) (result i32)
+                    local.get 0
+                    i32.const 2
+                    i32.lt_u
+                    if (result i32)
+                        local.get 0
+                    else
+                        local.get 0
+                        i32.const 1
+                        i32.sub
+                        call $fib
+                        local.get 0
+                        i32.const 2
+                        i32.sub
+                        call $fib
+                        i32.add
+                    end)
+                (func (export "add1") (param i32) (result i32)
+                    local.get 0
+                    call $add1)
+                (func (export "sub") (param i32 i32) (result i32)
+                    local.get 0
+                    local.get 1
+                    call $swap
+                    i32.sub)
+                (func (export "div") (param i32 i32) (result i32)
+                    local.get 0
+                    local.get 1
+                    call $div)
+                (func (export "fib") (param i32) (result i32)
+                    local.get 0
+                    call $fib)
+            )
+        "#,
+    )?;
+    let mut store = Store::new(&engine, ());
+    let instance = Instance::new(&mut store, &module, &[])?;
+
+    let add1 = instance.get_typed_func::<i32, i32>(&mut store, "add1")?;
+    assert_eq!(add1.call(&mut store, 41)?, 42);
+
+    let sub = instance.get_typed_func::<(i32, i32), i32>(&mut store, "sub")?;
+    assert_eq!(sub.call(&mut store, (1, 3))?, 2);
+
+    let div = instance.get_typed_func::<(i32, i32), i32>(&mut store, "div")?;
+    assert_eq!(div.call(&mut store, (7, 2))?, 3);
+    let trap = div.call(&mut store, (7, 0)).unwrap_err();
+    assert_eq!(trap.downcast::<Trap>()?, Trap::IntegerDivisionByZero);
+
+    let fib = instance.get_typed_func::<i32, i32>(&mut store, "fib")?;
+    assert_eq!(fib.call(&mut store, 20)?, 6765);
+
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn function_reports() -> Result<()> {
+    let engine = Engine::default();
+    let wasm = wat::parse_str(
+        r#"
+            (module
+                (import "" "" (func))
+                (memory 1)
+                (func $load (param i32) (result i32)
+                    local.get 0
+                    i32.load)
+                (func $nop))
+        "#,
+    )?;
+    let module = Module::new(&engine, &wasm)?;
+    let reports = module.function_reports().collect::<Vec<_>>();
+    assert_eq!(reports.len(), 2);
+
+    let load = &reports[0];
+    assert_eq!(load.index(), 1);
+    assert_eq!(load.name(), Some("load"));
+    let body = load.body_offset() as usize..(load.body_offset() + load.body_size()) as usize;
+    assert_eq!(wasm[body.end - 1], 0x0b, "body should end with `end`");
+    assert!(load.code_size() > 0);
+    assert!(load.trap_sites() > 0);
+    assert_eq!(load.stack_map_entries(), 0);
+    assert!(load.compile_time().is_some());
+    assert!(load.insts_before_opt() > 0);
+    assert!(load.insts_after_opt() > 0);
+    assert_eq!(reports[1].name(), Some("nop"));
+
+    // Compile times aren't serialized, but everything else is.
+    let module = unsafe { Module::deserialize(&engine, &module.serialize()?)? };
+    let load = module.function_reports().next().unwrap();
+    assert_eq!(load.name(), Some("load"));
+    assert_eq!(load.body_size(), reports[0].body_size());
+    assert_eq!(load.insts_after_opt(), reports[0].insts_after_opt());
+    assert!(load.compile_time().is_none());
+    Ok(())
+}
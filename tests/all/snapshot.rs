@@ -0,0 +1,119 @@
+use anyhow::Result;
+use wasmtime::*;
+
+const WAT: &str = r#"
+    (module
+      (memory (export "memory") 1)
+      (table (export "table") 1 funcref)
+      (global $g (export "g") (mut i32) (i32.const 0))
+      (data (i32.const 0) "hello")
+
+      (func $f (export "f") (result i32) (i32.const 42))
+
+      (func (export "mutate")
+        (i32.store8 (i32.const 0) (i32.const 0x4a))
+        (drop (memory.grow (i32.const 2)))
+        (i32.store (i32.const 0x20000) (i32.const 7))
+        (drop (table.grow (ref.func $f) (i32.const 3)))
+        (global.set $g (i32.add (global.get $g) (i32.const 1))))
+    )
+"#;
+
+#[test]
+fn restore_rolls_back_instance_state() -> Result<()> {
+    let mut store = Store::<()>::default();
+    let module = Module::new(store.engine(), WAT)?;
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let memory = instance.get_memory(&mut store, "memory").unwrap();
+    let table = instance.get_table(&mut store, "table").unwrap();
+    let g = instance.get_global(&mut store, "g").unwrap();
+    let mutate = instance.get_typed_func::<(), ()>(&mut store, "mutate")?;
+
+    let snapshot = store.snapshot()?;
+
+    // Restoring the same snapshot can be done repeatedly.
+    for _ in 0..2 {
+        mutate.call(&mut store, ())?;
+        assert_eq!(memory.size(&store), 3);
+        assert_eq!(&memory.data(&store)[..5], b"Jello");
+        assert_eq!(table.size(&store), 4);
+        assert_eq!(g.get(&mut store).unwrap_i32(), 1);
+
+        store.restore(&snapshot)?;
+        assert_eq!(memory.size(&store), 1);
+        assert_eq!(&memory.data(&store)[..5], b"hello");
+        assert_eq!(table.size(&store), 1);
+        assert!(table.get(&mut store, 0).unwrap().unwrap_funcref().is_none());
+        assert_eq!(g.get(&mut store).unwrap_i32(), 0);
+    }
+
+    // Memory which is grown again after a restore starts out zeroed.
+    memory.grow(&mut store, 2)?;
+    assert_eq!(memory.data(&store)[0x20000], 0);
+    Ok(())
+}
+
+#[test]
+fn restore_rolls_back_host_memory_and_externref_globals() -> Result<()> {
+    let mut config = Config::new();
+    config.wasm_reference_types(true);
+    let engine = Engine::new(&config)?;
+    let mut store = Store::new(&engine, ());
+    let module = Module::new(
+        &engine,
+        r#"
+            (module
+              (import "" "memory" (memory 1))
+              (global (export "g") (mut externref) (ref.null extern)))
+        "#,
+    )?;
+    let memory = Memory::new(&mut store, MemoryType::new(1, None))?;
+    let instance = Instance::new(&mut store, &module, &[memory.into()])?;
+    let g = instance.get_global(&mut store, "g").unwrap();
+    g.set(&mut store, Val::ExternRef(Some(ExternRef::new(1_u32))))?;
+    memory.data_mut(&mut store)[100] = 1;
+
+    let snapshot = store.snapshot()?;
+
+    memory.data_mut(&mut store)[100] = 2;
+    g.set(&mut store, Val::ExternRef(Some(ExternRef::new(2_u32))))?;
+
+    store.restore(&snapshot)?;
+    assert_eq!(memory.data(&store)[100], 1);
+    let value = g.get(&mut store).unwrap_externref().unwrap();
+    assert_eq!(value.data().downcast_ref::<u32>(), Some(&1));
+    Ok(())
+}
+
+#[test]
+fn restore_rolls_back_fuel() -> Result<()> {
+    let mut config = Config::new();
+    config.consume_fuel(true);
+    let engine = Engine::new(&config)?;
+    let mut store = Store::new(&engine, ());
+    store.add_fuel(100)?;
+
+    let snapshot = store.snapshot()?;
+    store.consume_fuel(30)?;
+    assert_eq!(store.fuel_remaining(), Some(70));
+
+    store.restore(&snapshot)?;
+    assert_eq!(store.fuel_remaining(), Some(100));
+    assert_eq!(store.fuel_consumed(), Some(0));
+    Ok(())
+}
+
+#[test]
+fn restore_rejects_foreign_and_stale_snapshots() -> Result<()> {
+    let engine = Engine::default();
+    let module = Module::new(&engine, "(module (memory 1))")?;
+
+    let mut store1 = Store::new(&engine, ());
+    let mut store2 = Store::new(&engine, ());
+    let snapshot = store1.snapshot()?;
+    assert!(store2.restore(&snapshot).is_err());
+
+    Instance::new(&mut store1, &module, &[])?;
+    assert!(store1.restore(&snapshot).is_err());
+    Ok(())
+}
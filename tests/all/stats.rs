@@ -0,0 +1,139 @@
+use anyhow::Result;
+use std::time::Duration;
+use wasmtime::*;
+
+#[test]
+fn stats_disabled_by_default() -> Result<()> {
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    assert!(store.stats().is_none());
+    store.collect_stats(true);
+    assert_eq!(store.stats().unwrap().instances_created(), 0);
+    let module = Module::new(&engine, "(module)")?;
+    Instance::new(&mut store, &module, &[])?;
+    assert_eq!(store.stats().unwrap().instances_created(), 1);
+    store.collect_stats(false);
+    assert!(store.stats().is_none());
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn stats_count_execution() -> Result<()> {
+    let mut config = Config::new();
+    config.consume_fuel(true);
+    let engine = Engine::new(&config)?;
+    let module = Module::new(
+        &engine,
+        r#"
+            (module
+              (import "" "sleep" (func $sleep))
+              (memory (export "memory") 1)
+              (table (export "table") 1 funcref)
+              (func (export "grow")
+                (drop (memory.grow (i32.const 2)))
+                (drop (table.grow (ref.null func) (i32.const 3))))
+              (func (export "sleep") (call $sleep) (call $sleep))
+              (func (export "trap") unreachable)
+              (func (export "div") (param i32) (result i32)
+                (i32.div_u (i32.const 1) (local.get 0))))
+        "#,
+    )?;
+
+    let mut store = Store::new(&engine, ());
+    store.add_fuel(u64::MAX)?;
+    store.collect_stats(true);
+    let sleep = Func::wrap(&mut store, || std::thread::sleep(Duration::from_millis(10)));
+    let instance = Instance::new(&mut store, &module, &[sleep.into()])?;
+
+    let grow = instance.get_typed_func::<(), ()>(&mut store, "grow")?;
+    grow.call(&mut store, ())?;
+    let memory = instance.get_memory(&mut store, "memory").unwrap();
+    memory.grow(&mut store, 1)?;
+    let table = instance.get_table(&mut store, "table").unwrap();
+    table.grow(&mut store, 1, Val::FuncRef(None))?;
+
+    instance
+        .get_typed_func::<(), ()>(&mut store, "sleep")?
+        .call(&mut store, ())?;
+    let trap = instance.get_typed_func::<(), ()>(&mut store, "trap")?;
+    assert!(trap.call(&mut store, ()).is_err());
+    assert!(trap.call(&mut store, ()).is_err());
+    let div = instance.get_func(&mut store, "div").unwrap();
+    let mut results = [Val::I32(0)];
+    assert!(div.call(&mut store, &[Val::I32(0)], &mut results).is_err());
+    div.call(&mut store, &[Val::I32(1)], &mut results)?;
+
+    let stats = store.stats().unwrap();
+    assert_eq!(stats.instances_created(), 1);
+    assert_eq!(stats.memory_pages_grown(), 3);
+    assert_eq!(stats.table_elements_grown(), 4);
+    assert_eq!(stats.host_calls(), 2);
+    assert!(stats.host_call_time() >= Duration::from_millis(20));
+    assert_eq!(stats.traps().len(), 2);
+    assert_eq!(stats.traps()[&Trap::UnreachableCodeReached], 2);
+    assert_eq!(stats.traps()[&Trap::IntegerDivisionByZero], 1);
+    let fuel = stats.fuel_per_export();
+    assert_eq!(fuel.len(), 4);
+    assert!(fuel["grow"] > 0);
+    assert!(fuel["div"] > 0);
+    assert!(!fuel.contains_key("memory"));
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn stats_count_out_of_fuel() -> Result<()> {
+    let mut config = Config::new();
+    config.consume_fuel(true);
+    let engine = Engine::new(&config)?;
+    let module = Module::new(&engine, r#"(module (func (export "f") (loop br 0)))"#)?;
+    let mut store = Store::new(&engine, ());
+    store.add_fuel(1_000)?;
+    store.collect_stats(true);
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let f = instance.get_typed_func::<(), ()>(&mut store, "f")?;
+    assert!(f.call(&mut store, ()).is_err());
+    let stats = store.stats().unwrap();
+    assert_eq!(stats.traps()[&Trap::OutOfFuel], 1);
+    assert!(stats.fuel_per_export()["f"] >= 1_000);
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn stats_count_continuations() -> Result<()> {
+    let mut config = Config::new();
+    config
+        .wasm_function_references(true)
+        .wasm_exceptions(true)
+        .wasm_typed_continuations(true);
+    let engine = Engine::new(&config)?;
+    let module = Module::new(
+        &engine,
+        r#"
+            (module
+              (type $ft (func))
+              (type $ct (cont $ft))
+              (tag $yield)
+              (func $g (suspend $yield))
+              (elem declare func $g)
+              (func (export "run")
+                (block $on_yield (result (ref $ct))
+                  (resume $ct (tag $yield $on_yield) (cont.new $ct (ref.func $g)))
+                  (return))
+                (resume $ct)))
+        "#,
+    )?;
+    let mut store = Store::new(&engine, ());
+    store.collect_stats(true);
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let run = instance.get_typed_func::<(), ()>(&mut store, "run")?;
+    run.call(&mut store, ())?;
+    let stats = store.stats().unwrap();
+    assert_eq!(stats.continuations_created(), 1);
+    assert_eq!(stats.continuation_switches(), 3);
+    // Fuel isn't enabled.
+    assert!(stats.fuel_per_export().is_empty());
+    Ok(())
+}
@@ -0,0 +1,66 @@
+(module
+  (tag $e0)
+  (tag $e1 (param i32))
+
+  (func $throw-e1 (param i32) (throw $e1 (local.get 0)))
+
+  (func (export "rethrow") (param i32) (result i32)
+    (try (result i32)
+      (do
+        (try (result i32)
+          (do (call $throw-e1 (local.get 0)) (i32.const 0))
+          (catch $e1 (drop) (rethrow 0))))
+      (catch $e1 (i32.const 1) (i32.add))))
+
+  (func (export "rethrow-outer") (result i32)
+    (try (result i32)
+      (do
+        (try (result i32)
+          (do (throw $e1 (i32.const 7)))
+          (catch_all
+            (try (result i32)
+              (do (throw $e0))
+              (catch $e0 (rethrow 1))))))
+      (catch $e1)))
+
+  (func (export "rethrow-uncaught")
+    (try
+      (do (throw $e0))
+      (catch_all (rethrow 0))))
+
+  (func (export "delegate") (param i32) (result i32)
+    (try $outer (result i32)
+      (do
+        (try (result i32)
+          (do
+            (try (result i32)
+              (do (call $throw-e1 (local.get 0)) (i32.const 0))
+              (delegate $outer)))
+          (catch $e1 (i32.const 100))))
+      (catch $e1 (i32.const 200) (i32.add))))
+
+  (func (export "delegate-to-caller") (result i32)
+    (try (result i32)
+      (do
+        (try (result i32)
+          (do (throw $e0))
+          (delegate 1)))
+      (catch $e0 (i32.const 1))))
+
+  (func (export "unwind-through-frames") (param i32) (result i32)
+    (try (result i32)
+      (do (call $deep (local.get 0)))
+      (catch $e1)))
+
+  (func $deep (param i32) (result i32)
+    (if (result i32) (i32.eqz (local.get 0))
+      (then (throw $e1 (i32.const 99)))
+      (else (i32.add (call $deep (i32.sub (local.get 0) (i32.const 1))) (i32.const 1)))))
+)
+
+(assert_return (invoke "rethrow" (i32.const 41)) (i32.const 42))
+(assert_return (invoke "rethrow-outer") (i32.const 7))
+(assert_exception (invoke "rethrow-uncaught"))
+(assert_return (invoke "delegate" (i32.const 5)) (i32.const 205))
+(assert_exception (invoke "delegate-to-caller"))
+(assert_return (invoke "unwind-through-frames" (i32.const 10)) (i32.const 99))
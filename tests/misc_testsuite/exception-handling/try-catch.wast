@@ -0,0 +1,99 @@
+(module
+  (tag $e0)
+  (tag $e1 (param i32))
+  (tag $e2 (param i64 f32 f64))
+
+  (func $throw-e0 (throw $e0))
+  (func $throw-e1 (param i32) (throw $e1 (local.get 0)))
+  (func (export "throw-e0") (call $throw-e0))
+
+  (func (export "catch-e0") (result i32)
+    (try (result i32)
+      (do (call $throw-e0) (i32.const 0))
+      (catch $e0 (i32.const 1))))
+
+  (func (export "catch-payload") (param i32) (result i32)
+    (try (result i32)
+      (do (throw $e1 (local.get 0)))
+      (catch $e1)))
+
+  (func (export "catch-multi-payload") (result f64)
+    (local $a i64) (local $b f32) (local $c f64)
+    (try (result f64)
+      (do
+        (throw $e2 (i64.const 3) (f32.const 4.5) (f64.const 0.25)))
+      (catch $e2
+        (local.set $c)
+        (local.set $b)
+        (local.set $a)
+        (f64.add
+          (f64.add (f64.convert_i64_s (local.get $a)) (f64.promote_f32 (local.get $b)))
+          (local.get $c)))))
+
+  (func (export "catch-nth") (param i32) (result i32)
+    (try (result i32)
+      (do
+        (if (i32.eqz (local.get 0)) (then (throw $e0)))
+        (call $throw-e1 (local.get 0))
+        (i32.const 0))
+      (catch $e0 (i32.const 100))
+      (catch $e1 (i32.const 200) (i32.add))))
+
+  (func (export "catch-all") (param i32) (result i32)
+    (try (result i32)
+      (do
+        (if (i32.eqz (local.get 0)) (then (throw $e0)))
+        (call $throw-e1 (local.get 0))
+        (i32.const 0))
+      (catch_all (i32.const 300))))
+
+  (func (export "uncaught") (result i32)
+    (try (result i32)
+      (do (call $throw-e0) (i32.const 0))
+      (catch $e1)))
+
+  (func (export "no-throw") (result i32)
+    (try (result i32)
+      (do (i32.const 7))
+      (catch_all (i32.const 8))))
+
+  (func (export "nested") (param i32) (result i32)
+    (try (result i32)
+      (do
+        (try (result i32)
+          (do (call $throw-e1 (local.get 0)) (i32.const 0))
+          (catch $e0 (i32.const 1))))
+      (catch $e1 (i32.const 10) (i32.mul))))
+
+  (func (export "throw-in-catch") (result i32)
+    (try (result i32)
+      (do
+        (try (result i32)
+          (do (throw $e0))
+          (catch $e0 (throw $e1 (i32.const 42)))))
+      (catch $e1)))
+
+  (func (export "loop-throw") (param i32) (result i32)
+    (local i32)
+    (loop $l
+      (try
+        (do (call $throw-e1 (local.get 0)))
+        (catch $e1 (local.get 1) (i32.add) (local.set 1)))
+      (local.tee 0 (i32.sub (local.get 0) (i32.const 1)))
+      (br_if $l))
+    (local.get 1))
+)
+
+(assert_exception (invoke "throw-e0"))
+(assert_return (invoke "catch-e0") (i32.const 1))
+(assert_return (invoke "catch-payload" (i32.const 5)) (i32.const 5))
+(assert_return (invoke "catch-multi-payload") (f64.const 7.75))
+(assert_return (invoke "catch-nth" (i32.const 0)) (i32.const 100))
+(assert_return (invoke "catch-nth" (i32.const 3)) (i32.const 203))
+(assert_return (invoke "catch-all" (i32.const 0)) (i32.const 300))
+(assert_return (invoke "catch-all" (i32.const 1)) (i32.const 300))
+(assert_exception (invoke "uncaught"))
+(assert_return (invoke "no-throw") (i32.const 7))
+(assert_return (invoke "nested" (i32.const 4)) (i32.const 40))
+(assert_return (invoke "throw-in-catch") (i32.const 42))
+(assert_return (invoke "loop-throw" (i32.const 4)) (i32.const 10))